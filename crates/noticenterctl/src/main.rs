@@ -1,35 +1,134 @@
 //! Command-line control surface for the UnixNotis D-Bus interface.
 
+mod send;
+mod watch;
+
 use anyhow::{anyhow, Context, Result};
-use clap::{Parser, Subcommand, ValueEnum};
-use std::process::Command as ProcCommand;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use unixnotis_core::popup_positions::PopupPositions;
 use unixnotis_core::util;
-use unixnotis_core::{ControlProxy, NotificationView, PanelDebugLevel};
+use unixnotis_core::{
+    Anchor, AuditEntry, BusNames, ClearScope, ControlProxy, MemoryReport, NotificationView,
+    OsdKind, PanelDebugLevel, PanelGeometry, PanelTarget, RuleConfig,
+};
 use zbus::Connection;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
+    /// Talk to the instance started with this `--bus-suffix` (falls back to UNIXNOTIS_BUS_SUFFIX)
+    #[arg(long, global = true)]
+    bus_suffix: Option<String>,
+    /// Print JSON from list-active, list-history, rules list, audit, and watch
+    #[arg(long, global = true)]
+    json: bool,
     #[command(subcommand)]
     command: Command,
 }
 
 #[derive(Subcommand, Debug)]
 enum Command {
+    /// Print DND state, history size, and eviction counters.
+    Status,
     TogglePanel,
     OpenPanel {
         #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "info")]
         debug: Option<DebugLevelArg>,
+        /// Expand and scroll to this app's notification group.
+        #[arg(long, conflicts_with_all = ["debug", "media"])]
+        app: Option<String>,
+        /// Focus the media player controls.
+        #[arg(long, conflicts_with = "debug")]
+        media: bool,
     },
     ClosePanel,
+    /// Place the panel elsewhere until it next closes.
+    PanelGeometry {
+        /// Anchor such as "top-right" or "center".
+        #[arg(long, value_parser = parse_anchor)]
+        anchor: Option<String>,
+        /// Margins as "top,right,bottom,left", or one value for every edge.
+        #[arg(long, value_parser = parse_margin)]
+        margin: Option<MarginArg>,
+        /// Output connector (e.g. "DP-1") or model name, or "focused".
+        #[arg(long)]
+        output: Option<String>,
+        /// Drop the override and use the configured placement again.
+        #[arg(long, conflicts_with_all = ["anchor", "margin", "output"])]
+        reset: bool,
+    },
+    /// Show placeholder popups and the panel outline where they will appear.
+    PreviewLayout {
+        /// How long the placeholders stay on screen (1 to 60).
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..=60))]
+        seconds: u32,
+    },
     Dnd {
         #[arg(value_enum)]
-        state: DndState,
+        state: SwitchState,
+        /// Turn DND off again after this long, e.g. "30m", "1h", or "1h30m" (with `on`).
+        #[arg(long = "for", value_parser = parse_duration_secs)]
+        duration: Option<u32>,
+    },
+    /// Switch all popups off or on; notifications still collect in the panel and sounds play.
+    Popups {
+        #[arg(value_enum)]
+        state: SwitchState,
+    },
+    /// Flash the volume or brightness overlay, e.g. `osd volume 40` from a key binding.
+    Osd {
+        #[arg(value_enum)]
+        kind: OsdKindArg,
+        #[arg(value_parser = clap::value_parser!(u32).range(0..=100))]
+        percent: u32,
+    },
+    /// Show an app's popups at their own anchor, remembered across sessions.
+    ///
+    /// Without an app, lists the remembered positions.
+    PopupPosition {
+        /// App name as its popups show it (case-insensitive).
+        app: Option<String>,
+        #[arg(value_parser = parse_anchor, requires = "app")]
+        anchor: Option<String>,
+        /// Forget the app's position so its popups join the regular stack again.
+        #[arg(long, requires = "app", conflicts_with = "anchor")]
+        reset: bool,
+    },
+    /// Mute notification sounds; popups keep showing, unlike DND.
+    Mute {
+        #[arg(value_enum)]
+        state: SwitchState,
     },
-    Clear,
+    /// Act on a hardware key forwarded by a compositor binding, e.g. "XF86NotificationCenter".
+    KeyHandler {
+        #[arg(value_parser = parse_hardware_key)]
+        key: HardwareKey,
+    },
+    /// Clear notifications; without --scope, `history.clear_scope` decides what goes.
+    Clear {
+        #[arg(long, value_enum)]
+        scope: Option<ClearScopeArg>,
+    },
+    /// Restore what the last clear removed, within 10 seconds of it.
+    UndoClear,
     Dismiss {
         id: u32,
     },
+    /// Post a notification as a regular client, like notify-send.
+    Send(send::SendArgs),
+    /// Invoke a notification's action as its button would; `default` is a click on the body.
+    Invoke {
+        id: u32,
+        action_key: String,
+    },
+    /// Answer a notification that offers an inline reply, such as a chat message.
+    Reply {
+        id: u32,
+        text: String,
+    },
     ListActive {
         #[arg(long)]
         full: bool,
@@ -38,17 +137,122 @@ enum Command {
         #[arg(long)]
         full: bool,
     },
+    /// Print notification and state events as they happen, one per line.
+    Watch {
+        #[arg(long)]
+        full: bool,
+    },
+    /// List or change notification rules; changes are saved to config.toml.
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommand,
+    },
+    /// Print recent state-changing calls and who made them (needs `[security.control] audit`).
+    Audit,
+    /// Print a shell completion script to stdout.
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page (roff) to stdout.
+    Man,
+    Debug {
+        #[command(subcommand)]
+        command: DebugCommand,
+    },
+    /// Change the open panel's debug level; it resets when the panel opens or closes.
+    DebugLevel {
+        #[arg(value_enum)]
+        level: DebugLevelArg,
+        /// Keep running and print the panel's debug lines.
+        #[arg(long)]
+        follow: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RulesCommand {
+    /// Print rules in the order they apply.
+    List,
+    /// Hide popups and silence sounds for an app (case-insensitive substring).
+    Mute { app: String },
+    /// Show every notification from an app at this urgency.
+    Urgency {
+        app: String,
+        #[arg(value_enum)]
+        level: UrgencyArg,
+    },
+    /// Remove the rule with this name.
+    Remove { name: String },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum ClearScopeArg {
+    All,
+    History,
+    Active,
+}
+
+impl From<ClearScopeArg> for ClearScope {
+    fn from(value: ClearScopeArg) -> Self {
+        match value {
+            ClearScopeArg::All => ClearScope::All,
+            ClearScopeArg::History => ClearScope::History,
+            ClearScopeArg::Active => ClearScope::Active,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum UrgencyArg {
+    Low,
+    Normal,
+    Critical,
+}
+
+#[derive(Subcommand, Debug)]
+enum DebugCommand {
+    /// Report per-process memory and cache sizes.
+    Memory,
+    /// Drop icon caches and shrink buffers in every process.
+    Trim,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum OsdKindArg {
+    Volume,
+    Brightness,
+}
+
+impl From<OsdKindArg> for OsdKind {
+    fn from(value: OsdKindArg) -> Self {
+        match value {
+            OsdKindArg::Volume => OsdKind::Volume,
+            OsdKindArg::Brightness => OsdKind::Brightness,
+        }
+    }
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
-enum DndState {
+enum SwitchState {
     On,
     Off,
     Toggle,
 }
 
+/// Dedicated keys some keyboards and laptops ship for notifications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HardwareKey {
+    /// Toggles the panel.
+    NotificationCenter,
+    /// Toggles Do Not Disturb.
+    DoNotDisturb,
+}
+
 #[derive(ValueEnum, Debug, Clone, Copy)]
 enum DebugLevelArg {
+    /// Only meaningful for `debug-level`; `open-panel --debug off` opens without debugging.
+    Off,
     Critical,
     Warn,
     Info,
@@ -58,6 +262,7 @@ enum DebugLevelArg {
 impl From<DebugLevelArg> for PanelDebugLevel {
     fn from(value: DebugLevelArg) -> Self {
         match value {
+            DebugLevelArg::Off => PanelDebugLevel::Off,
             DebugLevelArg::Critical => PanelDebugLevel::Critical,
             DebugLevelArg::Warn => PanelDebugLevel::Warn,
             DebugLevelArg::Info => PanelDebugLevel::Info,
@@ -69,33 +274,110 @@ impl From<DebugLevelArg> for PanelDebugLevel {
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    // Completions and the man page need no daemon, so they work before one is running.
+    match args.command {
+        Command::Completions { shell } => return write_completions(shell, &mut std::io::stdout()),
+        Command::Man => return write_man(&mut std::io::stdout()),
+        _ => {}
+    }
+    let names = BusNames::resolve(args.bus_suffix.as_deref())?;
     let connection = Connection::session()
         .await
         .context("connect to session bus")?;
-    let proxy = ControlProxy::new(&connection)
+    // Sending talks to the notification server itself, which need not be UnixNotis.
+    if let Command::Send(send) = &args.command {
+        return send::send(&connection, &names, send).await;
+    }
+    let proxy = names
+        .control_proxy(&connection)
         .await
         .context("connect to unixnotis control interface")?;
 
     match args.command {
+        Command::Status => {
+            let state = proxy.get_state().await?;
+            if state.dnd_enabled && state.dnd_until > 0 {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_secs() as i64);
+                let minutes = ((state.dnd_until - now).max(0) as u64).div_ceil(60);
+                println!("dnd: on (ends in {minutes}m)");
+            } else {
+                println!("dnd: {}", if state.dnd_enabled { "on" } else { "off" });
+            }
+            if !state.inhibited_by.is_empty() {
+                println!("suppressed: {}", state.inhibited_by);
+            }
+            println!(
+                "popups: {}",
+                if state.popups_enabled { "on" } else { "off" }
+            );
+            println!("mute: {}", if state.muted { "on" } else { "off" });
+            println!("history: {}", state.history_count);
+            println!(
+                "evictions: active {} history {}",
+                state.active_evictions, state.history_evictions
+            );
+        }
         Command::TogglePanel => proxy.toggle_panel().await?,
-        Command::OpenPanel { debug } => {
-            if let Some(level) = debug {
-                proxy.open_panel_debug(level.into()).await?;
-                follow_debug_logs().context("follow unixnotis debug logs")?;
+        Command::OpenPanel { debug, app, media } => {
+            if let Some(level) = debug.filter(|level| !matches!(level, DebugLevelArg::Off)) {
+                watch::follow_debug(&proxy, level.into()).await?;
+            } else if let Some(app) = app {
+                proxy.open_panel_at(PanelTarget::App, &app).await?;
+            } else if media {
+                proxy.open_panel_at(PanelTarget::Media, "").await?;
             } else {
                 proxy.open_panel().await?;
             }
         }
         Command::ClosePanel => proxy.close_panel().await?,
-        Command::Clear => proxy.clear_all().await?,
+        Command::PanelGeometry {
+            anchor,
+            margin,
+            output,
+            reset,
+        } => {
+            let geometry = PanelGeometry {
+                anchor: anchor.unwrap_or_default(),
+                margin: margin.map(|margin| margin.0.to_vec()).unwrap_or_default(),
+                output: output.unwrap_or_default(),
+            };
+            if geometry.is_empty() && !reset {
+                return Err(anyhow!(
+                    "pass --anchor, --margin, or --output (or --reset to clear the override)"
+                ));
+            }
+            proxy.set_panel_geometry(geometry).await?;
+        }
+        Command::PreviewLayout { seconds } => proxy.preview_layout(seconds).await?,
+        Command::KeyHandler { key } => match key {
+            HardwareKey::NotificationCenter => proxy.toggle_panel().await?,
+            HardwareKey::DoNotDisturb => toggle_dnd(&proxy).await?,
+        },
+        Command::Clear { scope } => match scope {
+            Some(scope) => {
+                proxy.clear(scope.into()).await?;
+            }
+            None => proxy.clear_all().await?,
+        },
+        Command::UndoClear => {
+            if !proxy.undo_clear().await? {
+                return Err(anyhow!(
+                    "nothing to undo; clears can only be undone for 10 seconds"
+                ));
+            }
+        }
         Command::Dismiss { id } => proxy.dismiss(id).await?,
+        Command::Invoke { id, action_key } => proxy.invoke_action(id, &action_key).await?,
+        Command::Reply { id, text } => proxy.reply(id, &text).await?,
         Command::ListActive { full } => {
             let allow_full = full && util::diagnostic_mode();
             if full && !util::diagnostic_mode() {
                 eprintln!("--full requires UNIXNOTIS_DIAGNOSTIC=1; using redacted output");
             }
             let notifications = proxy.list_active().await?;
-            print_notifications("active", &notifications, allow_full);
+            print_notifications("active", &notifications, allow_full, args.json)?;
         }
         Command::ListHistory { full } => {
             let allow_full = full && util::diagnostic_mode();
@@ -103,54 +385,553 @@ async fn main() -> Result<()> {
                 eprintln!("--full requires UNIXNOTIS_DIAGNOSTIC=1; using redacted output");
             }
             let notifications = proxy.list_history().await?;
-            print_notifications("history", &notifications, allow_full);
+            print_notifications("history", &notifications, allow_full, args.json)?;
+        }
+        Command::Watch { full } => {
+            let allow_full = full && util::diagnostic_mode();
+            if full && !util::diagnostic_mode() {
+                eprintln!("--full requires UNIXNOTIS_DIAGNOSTIC=1; using redacted output");
+            }
+            watch::watch(&proxy, args.json, allow_full).await?;
         }
-        Command::Dnd { state } => match state {
-            DndState::On => proxy.set_dnd(true).await?,
-            DndState::Off => proxy.set_dnd(false).await?,
-            DndState::Toggle => {
-                let current = proxy.get_state().await?;
-                proxy.set_dnd(!current.dnd_enabled).await?;
+        Command::Rules { command } => match command {
+            RulesCommand::List => {
+                let rules = proxy
+                    .list_rules()
+                    .await?
+                    .iter()
+                    .map(RuleConfig::from_dict)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| anyhow!("daemon sent an invalid rule: {err}"))?;
+                if args.json {
+                    println!("{}", serde_json::to_string(&rules)?);
+                    return Ok(());
+                }
+                println!("rules: {}", rules.len());
+                for rule in &rules {
+                    println!("- {}", describe_rule(rule));
+                }
+            }
+            RulesCommand::Mute { app } => {
+                edit_app_rule(&proxy, &app, |rule| {
+                    rule.no_popup = Some(true);
+                    rule.silent = Some(true);
+                })
+                .await?;
+            }
+            RulesCommand::Urgency { app, level } => {
+                edit_app_rule(&proxy, &app, |rule| rule.force_urgency = Some(level as u8)).await?;
+            }
+            RulesCommand::Remove { name } => {
+                if !proxy.remove_rule(&name).await? {
+                    return Err(anyhow!("no rule named {name:?}"));
+                }
             }
         },
+        Command::Completions { .. } | Command::Man | Command::Send(_) => {}
+        Command::Audit => {
+            let entries = proxy.list_audit().await?;
+            if args.json {
+                println!("{}", serde_json::to_string(&entries)?);
+                return Ok(());
+            }
+            if entries.is_empty() {
+                println!("no audited calls (is [security.control] audit on?)");
+            }
+            let now_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as i64);
+            for entry in &entries {
+                println!("{}", describe_audit(entry, now_ms));
+            }
+        }
+        Command::Debug { command } => match command {
+            DebugCommand::Memory => {
+                let reports = proxy.get_memory_report().await?;
+                print_memory_reports(&reports);
+            }
+            DebugCommand::Trim => proxy.trim_caches().await?,
+        },
+        Command::DebugLevel { level, follow } => {
+            if follow {
+                watch::follow_debug_level(&proxy, level.into()).await?;
+            } else {
+                proxy.set_panel_debug_level(level.into()).await?;
+            }
+        }
+        Command::Dnd { state, duration } => match (state, duration) {
+            (SwitchState::On, Some(seconds)) => proxy.set_dnd_for(seconds).await?,
+            (_, Some(_)) => return Err(anyhow!("--for only applies to `dnd on`")),
+            (SwitchState::On, None) => proxy.set_dnd(true).await?,
+            (SwitchState::Off, None) => proxy.set_dnd(false).await?,
+            (SwitchState::Toggle, None) => toggle_dnd(&proxy).await?,
+        },
+        Command::Popups { state } => {
+            let enabled = match state {
+                SwitchState::On => true,
+                SwitchState::Off => false,
+                SwitchState::Toggle => !proxy.get_state().await?.popups_enabled,
+            };
+            proxy.set_popups_enabled(enabled).await?;
+        }
+        Command::Osd { kind, percent } => proxy.show_osd(kind.into(), percent).await?,
+        Command::PopupPosition { app, anchor, reset } => match (app, anchor) {
+            (Some(app), Some(anchor)) => proxy.set_app_popup_anchor(&app, &anchor).await?,
+            (Some(app), None) if reset => proxy.set_app_popup_anchor(&app, "").await?,
+            (app, _) => {
+                let anchors = proxy.list_app_popup_anchors().await?;
+                let positions = PopupPositions::from_names(
+                    anchors
+                        .iter()
+                        .map(|(app, anchor)| (app.as_str(), anchor.as_str())),
+                );
+                let key = app.map(|app| app.trim().to_lowercase());
+                let positions: Vec<(&str, Anchor)> = positions
+                    .iter()
+                    .filter(|(name, _)| key.as_deref().is_none_or(|key| key == *name))
+                    .collect();
+                if args.json {
+                    let map: serde_json::Map<String, serde_json::Value> = positions
+                        .iter()
+                        .map(|(name, anchor)| (name.to_string(), anchor.name().into()))
+                        .collect();
+                    println!("{}", serde_json::Value::Object(map));
+                } else if positions.is_empty() {
+                    println!("popup positions: none");
+                } else {
+                    for (name, anchor) in positions {
+                        println!("{name}: {}", anchor.name());
+                    }
+                }
+            }
+        },
+        Command::Mute { state } => {
+            let muted = match state {
+                SwitchState::On => true,
+                SwitchState::Off => false,
+                SwitchState::Toggle => !proxy.get_state().await?.muted,
+            };
+            proxy.set_muted(muted).await?;
+        }
     }
 
     Ok(())
 }
 
-fn print_notifications(label: &str, notifications: &[NotificationView], full: bool) {
+async fn toggle_dnd(proxy: &ControlProxy<'_>) -> Result<()> {
+    let current = proxy.get_state().await?;
+    proxy.set_dnd(!current.dnd_enabled).await?;
+    Ok(())
+}
+
+/// Update the rule named after `app`, starting a new one when there is none yet.
+async fn edit_app_rule(
+    proxy: &ControlProxy<'_>,
+    app: &str,
+    edit: impl FnOnce(&mut RuleConfig),
+) -> Result<()> {
+    let app = app.trim();
+    if app.is_empty() {
+        return Err(anyhow!("app name must not be empty"));
+    }
+    let existing = proxy
+        .list_rules()
+        .await?
+        .iter()
+        .filter_map(|dict| RuleConfig::from_dict(dict).ok())
+        .find(|rule| rule.name.as_deref() == Some(app));
+    let mut rule = existing.unwrap_or_else(|| RuleConfig::for_app(app));
+    edit(&mut rule);
+    proxy.set_rule(rule.to_dict()).await?;
+    Ok(())
+}
+
+/// One-line summary such as `quiet chat: app=discord -> no_popup silent`.
+fn describe_rule(rule: &RuleConfig) -> String {
+    let mut matches = Vec::new();
+    for (key, value) in [
+        ("app", &rule.app),
+        ("summary", &rule.summary),
+        ("body", &rule.body),
+        ("category", &rule.category),
+    ] {
+        if let Some(value) = value {
+            matches.push(format!("{key}={value:?}"));
+        }
+    }
+    if let Some(urgency) = rule.urgency {
+        matches.push(format!("urgency={urgency}"));
+    }
+    let mut effects = Vec::new();
+    for (key, value) in [
+        ("no_popup", rule.no_popup),
+        ("silent", rule.silent),
+        ("resident", rule.resident),
+        ("transient", rule.transient),
+        ("clamp_timeout", rule.clamp_timeout),
+    ] {
+        if let Some(value) = value {
+            effects.push(if value {
+                key.to_string()
+            } else {
+                format!("{key}=false")
+            });
+        }
+    }
+    if let Some(urgency) = rule.force_urgency {
+        effects.push(format!("force_urgency={urgency}"));
+    }
+    if let Some(timeout) = rule.expire_timeout_ms {
+        effects.push(format!("expire_timeout_ms={timeout}"));
+    }
+    if let Some(minutes) = rule.history_ttl_minutes {
+        effects.push(format!("history_ttl_minutes={minutes}"));
+    }
+    let name = rule.name.as_deref().unwrap_or("(unnamed)");
+    let matches = if matches.is_empty() {
+        "everything".to_string()
+    } else {
+        matches.join(" ")
+    };
+    format!("{name}: {matches} -> {}", effects.join(" "))
+}
+
+fn print_notifications(
+    label: &str,
+    notifications: &[NotificationView],
+    full: bool,
+    json: bool,
+) -> Result<()> {
     let limit = if full {
         util::diagnostic_log_limit()
     } else {
         util::default_log_limit()
     };
+    if json {
+        let views: Vec<NotificationView> = notifications
+            .iter()
+            .map(|notification| redact_view(notification, limit))
+            .collect();
+        println!("{}", serde_json::to_string(&views)?);
+        return Ok(());
+    }
     println!("{} notifications: {}", label, notifications.len());
     for notification in notifications {
         let summary = util::sanitize_log_value(&notification.summary, limit);
+        let ttl = if notification.history_ttl_minutes > 0 {
+            format!(" (ttl {}m)", notification.history_ttl_minutes)
+        } else {
+            String::new()
+        };
+        // Action keys are what `invoke` takes.
+        let actions = if notification.actions.is_empty() {
+            String::new()
+        } else {
+            let keys: Vec<&str> = notification
+                .actions
+                .iter()
+                .map(|action| action.key.as_str())
+                .collect();
+            format!(" actions={}", keys.join(","))
+        };
         println!(
-            "- #{id} [{app}] {summary}",
+            "- #{id} [{app}] {summary}{ttl}{actions}",
             id = notification.id,
             app = notification.app_name,
             summary = summary
         );
     }
+    Ok(())
+}
+
+/// Copy of `view` with summary and body shortened to `limit` and raw image bytes dropped.
+///
+/// Image dimensions, path, and icon name are kept; the pixel data is of no use to scripts.
+fn redact_view(view: &NotificationView, limit: usize) -> NotificationView {
+    let mut view = view.clone();
+    view.summary = util::sanitize_log_value(&view.summary, limit);
+    view.body = util::sanitize_log_value(&view.body, limit);
+    view.image.image_data.data = Vec::new();
+    view
+}
+
+/// One line such as `5m ago ClearAll by waybar (pid 812, :1.40)`.
+fn describe_audit(entry: &AuditEntry, now_ms: i64) -> String {
+    let secs = ((now_ms - entry.at_unix_ms).max(0) / 1000) as u64;
+    let age = match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    };
+    let comm = if entry.comm.is_empty() {
+        "unknown"
+    } else {
+        &entry.comm
+    };
+    format!(
+        "{age} ago {method} by {comm} (pid {pid}, {sender}){refused}",
+        method = entry.method,
+        pid = entry.pid,
+        sender = entry.sender,
+        refused = if entry.allowed { "" } else { " refused" }
+    )
+}
+
+fn print_memory_reports(reports: &[MemoryReport]) {
+    for report in reports {
+        println!(
+            "{process} (pid {pid}): rss {rss}",
+            process = report.process,
+            pid = report.pid,
+            rss = format_bytes(report.rss_bytes)
+        );
+        for (label, bytes) in &report.caches {
+            println!("  {label}: {}", format_bytes(*bytes));
+        }
+    }
 }
 
-fn follow_debug_logs() -> Result<()> {
-    let status = ProcCommand::new("journalctl")
-        .args([
-            "--user",
-            "-f",
-            "-u",
-            "unixnotis-daemon.service",
-            "-o",
-            "cat",
-        ])
-        .status()
-        .context("start journalctl follow")?;
-    if status.success() {
-        Ok(())
+fn format_bytes(bytes: u64) -> String {
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    if bytes >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB as f64)
+    } else if bytes >= KIB {
+        format!("{:.1} KiB", bytes as f64 / KIB as f64)
     } else {
-        Err(anyhow!("journalctl exited with status {}", status))
+        format!("{bytes} B")
+    }
+}
+
+/// Panel margins as top, right, bottom, left.
+#[derive(Debug, Clone, Copy)]
+struct MarginArg([i32; 4]);
+
+fn parse_anchor(value: &str) -> Result<String, String> {
+    Anchor::from_name(value)
+        .map(|_| value.trim().to_string())
+        .ok_or_else(|| {
+            format!(
+                "unknown anchor: {value} (expected top-right, top-left, bottom-right, \
+                 bottom-left, top, bottom, left, right, or center)"
+            )
+        })
+}
+
+fn parse_margin(value: &str) -> Result<MarginArg, String> {
+    let values = value
+        .split(',')
+        .map(|part| part.trim().parse::<u16>().map(i32::from))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid margin: {value}"))?;
+    match values[..] {
+        [all] => Ok(MarginArg([all; 4])),
+        [top, right, bottom, left] => Ok(MarginArg([top, right, bottom, left])),
+        _ => Err(format!("margin needs one or four values: {value}")),
+    }
+}
+
+/// Accept xkb keysym names (`XF86NotificationCenter`, `XF86DoNotDisturb`) as well as evdev
+/// names (`KEY_NOTIFICATION_CENTER`), ignoring case, dashes, and underscores.
+fn parse_hardware_key(value: &str) -> Result<HardwareKey, String> {
+    let normalized: String = value
+        .trim()
+        .chars()
+        .filter(|ch| *ch != '_' && *ch != '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let name = normalized
+        .strip_prefix("xf86")
+        .or_else(|| normalized.strip_prefix("key"))
+        .unwrap_or(&normalized);
+    match name {
+        "notificationcenter" => Ok(HardwareKey::NotificationCenter),
+        "donotdisturb" | "dnd" => Ok(HardwareKey::DoNotDisturb),
+        _ => Err(format!(
+            "unknown key: {value} (expected XF86NotificationCenter or XF86DoNotDisturb)"
+        )),
+    }
+}
+
+/// Parse durations such as "90s", "30m", "1h30m"; a bare number means minutes.
+fn parse_duration_secs(value: &str) -> Result<u32, String> {
+    let value = value.trim();
+    if let Ok(minutes) = value.parse::<u32>() {
+        return minutes
+            .checked_mul(60)
+            .filter(|seconds| *seconds > 0)
+            .ok_or_else(|| format!("invalid duration: {value}"));
+    }
+    let mut total: u32 = 0;
+    let mut digits = String::new();
+    for ch in value.chars() {
+        if ch.is_ascii_digit() {
+            digits.push(ch);
+            continue;
+        }
+        let unit = match ch {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86_400,
+            _ => return Err(format!("invalid duration unit '{ch}' in {value}")),
+        };
+        let amount: u32 = digits
+            .parse()
+            .map_err(|_| format!("invalid duration: {value}"))?;
+        digits.clear();
+        total = amount
+            .checked_mul(unit)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or_else(|| format!("duration too long: {value}"))?;
+    }
+    if !digits.is_empty() || total == 0 {
+        return Err(format!("invalid duration: {value}"));
+    }
+    Ok(total)
+}
+
+fn write_completions(shell: Shell, out: &mut impl Write) -> Result<()> {
+    clap_complete::generate(shell, &mut Args::command(), "noticenterctl", out);
+    Ok(())
+}
+
+fn write_man(out: &mut impl Write) -> Result<()> {
+    clap_mangen::Man::new(Args::command())
+        .render(out)
+        .context("render man page")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        describe_audit, describe_rule, parse_duration_secs, parse_hardware_key, parse_margin,
+        redact_view, write_completions, write_man, AuditEntry, HardwareKey, NotificationView,
+        RuleConfig, Shell,
+    };
+
+    #[test]
+    fn generated_docs_cover_the_subcommands() {
+        let mut script = Vec::new();
+        write_completions(Shell::Bash, &mut script).unwrap();
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("toggle-panel"));
+        assert!(script.contains("completions"));
+
+        let mut page = Vec::new();
+        write_man(&mut page).unwrap();
+        let page = String::from_utf8(page).unwrap();
+        assert!(page.contains("noticenterctl"));
+        assert!(page.contains(".SH SUBCOMMANDS"));
+    }
+
+    #[test]
+    fn audit_lines_name_the_caller() {
+        let mut entry = AuditEntry {
+            at_unix_ms: 1_000_000,
+            method: "ClearAll".to_string(),
+            sender: ":1.40".to_string(),
+            pid: 812,
+            comm: "waybar".to_string(),
+            allowed: true,
+        };
+        assert_eq!(
+            describe_audit(&entry, 1_300_000),
+            "5m ago ClearAll by waybar (pid 812, :1.40)"
+        );
+        entry.comm.clear();
+        entry.allowed = false;
+        assert_eq!(
+            describe_audit(&entry, 1_004_000),
+            "4s ago ClearAll by unknown (pid 812, :1.40) refused"
+        );
+    }
+
+    #[test]
+    fn parse_duration_accepts_units_and_bare_minutes() {
+        assert_eq!(parse_duration_secs("30m"), Ok(1800));
+        assert_eq!(parse_duration_secs("1h30m"), Ok(5400));
+        assert_eq!(parse_duration_secs("45"), Ok(2700));
+        assert_eq!(parse_duration_secs("90s"), Ok(90));
+        assert!(parse_duration_secs("10").is_ok());
+        assert!(parse_duration_secs("0").is_err());
+        assert!(parse_duration_secs("5x").is_err());
+        assert!(parse_duration_secs("1h5").is_err());
+    }
+
+    #[test]
+    fn parse_margin_accepts_one_or_four_values() {
+        assert_eq!(parse_margin("12").map(|margin| margin.0), Ok([12; 4]));
+        assert_eq!(
+            parse_margin("8, 16,0,4").map(|margin| margin.0),
+            Ok([8, 16, 0, 4])
+        );
+        assert!(parse_margin("1,2").is_err());
+        assert!(parse_margin("-4").is_err());
+    }
+
+    #[test]
+    fn parse_hardware_key_accepts_keysym_and_evdev_names() {
+        assert_eq!(
+            parse_hardware_key("XF86NotificationCenter"),
+            Ok(HardwareKey::NotificationCenter)
+        );
+        assert_eq!(
+            parse_hardware_key("KEY_NOTIFICATION_CENTER"),
+            Ok(HardwareKey::NotificationCenter)
+        );
+        assert_eq!(
+            parse_hardware_key("XF86DoNotDisturb"),
+            Ok(HardwareKey::DoNotDisturb)
+        );
+        assert_eq!(parse_hardware_key("xf86dnd"), Ok(HardwareKey::DoNotDisturb));
+        assert!(parse_hardware_key("XF86AudioMute").is_err());
+    }
+
+    #[test]
+    fn describe_rule_lists_matches_and_effects() {
+        let mut rule = RuleConfig::for_app("discord");
+        rule.no_popup = Some(true);
+        rule.silent = Some(false);
+        rule.force_urgency = Some(0);
+        assert_eq!(
+            describe_rule(&rule),
+            "discord: app=\"discord\" -> no_popup silent=false force_urgency=0"
+        );
+        let rule = RuleConfig {
+            history_ttl_minutes: Some(10),
+            ..RuleConfig::default()
+        };
+        assert_eq!(
+            describe_rule(&rule),
+            "(unnamed): everything -> history_ttl_minutes=10"
+        );
+    }
+
+    #[test]
+    fn redacted_views_drop_image_bytes_and_long_text() {
+        let mut view = NotificationView {
+            id: 3,
+            app_name: "chat".to_string(),
+            summary: "line one\nline two".to_string(),
+            body: "x".repeat(100),
+            actions: Vec::new(),
+            urgency: 1,
+            is_transient: false,
+            is_resident: false,
+            received_at_unix_ms: 0,
+            image: Default::default(),
+            history_ttl_minutes: 0,
+            pinned: false,
+            progress: -1,
+        };
+        view.image.image_data.width = 2;
+        view.image.image_data.data = vec![0; 16];
+        let redacted = redact_view(&view, 10);
+        assert!(!redacted.summary.contains('\n'));
+        assert!(redacted.body.chars().count() < view.body.len());
+        assert!(redacted.image.image_data.data.is_empty());
+        assert_eq!(redacted.image.image_data.width, 2);
+        assert_eq!(redacted.app_name, "chat");
     }
 }
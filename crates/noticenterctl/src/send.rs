@@ -0,0 +1,217 @@
+//! `noticenterctl send`: post a notification the way any client would.
+//!
+//! The call goes to `org.freedesktop.Notifications` rather than the control interface, so rules,
+//! popups, sounds, and themes see exactly what an app's notification would produce. It works
+//! against any notification server, and needs no `notify-send` to try things out. Flags
+//! follow notify-send, plus dunstify's `--replace-id` and `--print-id`.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use futures_util::StreamExt;
+use unixnotis_core::BusNames;
+use zbus::zvariant::Value;
+use zbus::Connection;
+
+#[derive(clap::Args, Debug)]
+pub struct SendArgs {
+    summary: String,
+    body: Option<String>,
+    #[arg(long, short = 'a', default_value = "noticenterctl")]
+    app_name: String,
+    #[arg(long, short = 'u', value_enum, default_value = "normal")]
+    urgency: UrgencyArg,
+    /// Icon name or path.
+    #[arg(long, short = 'i', default_value = "")]
+    icon: String,
+    /// Action as `key=label`; repeat for more. `default` is a click on the notification.
+    #[arg(long = "action", short = 'A', value_parser = parse_action)]
+    actions: Vec<(String, String)>,
+    /// Hint as `type:name:value`, type being boolean, int, byte, string, or double; repeatable.
+    #[arg(long = "hint", value_parser = parse_hint)]
+    hints: Vec<Hint>,
+    /// Timeout in milliseconds; -1 leaves it to the server, 0 never expires.
+    #[arg(long, short = 't', default_value_t = -1, allow_negative_numbers = true)]
+    timeout: i32,
+    /// Replace the notification with this ID.
+    #[arg(long, short = 'r', default_value_t = 0)]
+    replace_id: u32,
+    /// Print the ID of the notification.
+    #[arg(long, short = 'p')]
+    print_id: bool,
+    /// Wait until the notification closes or an action is invoked, printing the action key.
+    #[arg(long, short = 'w')]
+    wait: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum UrgencyArg {
+    Low,
+    Normal,
+    Critical,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum HintValue {
+    Boolean(bool),
+    Int(i32),
+    Byte(u8),
+    String(String),
+    Double(f64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Hint {
+    name: String,
+    value: HintValue,
+}
+
+impl HintValue {
+    fn to_value(&self) -> Value<'_> {
+        match self {
+            Self::Boolean(value) => Value::from(*value),
+            Self::Int(value) => Value::from(*value),
+            Self::Byte(value) => Value::from(*value),
+            Self::String(value) => Value::from(value.as_str()),
+            Self::Double(value) => Value::from(*value),
+        }
+    }
+}
+
+pub async fn send(connection: &Connection, names: &BusNames, args: &SendArgs) -> Result<()> {
+    let proxy = names
+        .notifications_proxy(connection)
+        .await
+        .context("connect to the notification server")?;
+    // Subscribe first so a quick click or timeout cannot slip past before the ID is known.
+    let streams = if args.wait {
+        Some((
+            proxy.receive_action_invoked().await?,
+            proxy.receive_notification_closed().await?,
+        ))
+    } else {
+        None
+    };
+
+    let urgency: u8 = match args.urgency {
+        UrgencyArg::Low => 0,
+        UrgencyArg::Normal => 1,
+        UrgencyArg::Critical => 2,
+    };
+    let mut hints: HashMap<&str, Value<'_>> = HashMap::from([("urgency", Value::from(urgency))]);
+    for hint in &args.hints {
+        hints.insert(hint.name.as_str(), hint.value.to_value());
+    }
+    let actions: Vec<&str> = args
+        .actions
+        .iter()
+        .flat_map(|(key, label)| [key.as_str(), label.as_str()])
+        .collect();
+    let id = proxy
+        .notify(
+            &args.app_name,
+            args.replace_id,
+            &args.icon,
+            &args.summary,
+            args.body.as_deref().unwrap_or_default(),
+            &actions,
+            hints,
+            args.timeout,
+        )
+        .await
+        .context("send notification")?;
+    if args.print_id {
+        println!("{id}");
+    }
+
+    let Some((mut actions, mut closed)) = streams else {
+        return Ok(());
+    };
+    loop {
+        tokio::select! {
+            signal = actions.next() => {
+                let Some(signal) = signal else {
+                    return Ok(());
+                };
+                if let Ok(args) = signal.args() {
+                    if *args.id() == id {
+                        println!("{}", args.action_key());
+                        return Ok(());
+                    }
+                }
+            }
+            signal = closed.next() => {
+                let Some(signal) = signal else {
+                    return Ok(());
+                };
+                if signal.args().is_ok_and(|args| *args.id() == id) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn parse_action(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, label)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), label.to_string()))
+        }
+        _ => Err(format!("invalid action: {value} (expected key=label)")),
+    }
+}
+
+fn parse_hint(value: &str) -> Result<Hint, String> {
+    let mut parts = value.splitn(3, ':');
+    let (Some(kind), Some(name), Some(raw)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("invalid hint: {value} (expected type:name:value)"));
+    };
+    let invalid = || format!("invalid {kind} value for hint {name}: {raw}");
+    let value = match kind.to_ascii_lowercase().as_str() {
+        "boolean" | "bool" => HintValue::Boolean(raw.parse().map_err(|_| invalid())?),
+        "int" => HintValue::Int(raw.parse().map_err(|_| invalid())?),
+        "byte" => HintValue::Byte(raw.parse().map_err(|_| invalid())?),
+        "string" => HintValue::String(raw.to_string()),
+        "double" => HintValue::Double(raw.parse().map_err(|_| invalid())?),
+        _ => {
+            return Err(format!(
+                "unknown hint type: {kind} (expected boolean, int, byte, string, or double)"
+            ))
+        }
+    };
+    Ok(Hint {
+        name: name.to_string(),
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_and_actions_parse_like_notify_send() {
+        assert_eq!(
+            parse_hint("int:value:40"),
+            Ok(Hint {
+                name: "value".to_string(),
+                value: HintValue::Int(40),
+            })
+        );
+        assert_eq!(
+            parse_hint("string:x-dunst-stack-tag:volume:main").map(|hint| hint.value),
+            Ok(HintValue::String("volume:main".to_string()))
+        );
+        assert!(parse_hint("byte:urgency:300").is_err());
+        assert!(parse_hint("int:value").is_err());
+        assert!(parse_hint("uint:value:1").is_err());
+
+        assert_eq!(
+            parse_action("default=Open"),
+            Ok(("default".to_string(), "Open".to_string()))
+        );
+        assert!(parse_action("Open").is_err());
+        assert!(parse_action("=Open").is_err());
+    }
+}
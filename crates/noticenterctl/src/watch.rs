@@ -0,0 +1,249 @@
+//! `noticenterctl watch`: follow control signals and print one line per event.
+//!
+//! Notifications are redacted the same way as `list-active --json` unless diagnostic mode allows
+//! `--full`. `open-panel --debug` follows the panel's `debug_log` signal the same way.
+
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use serde::Serialize;
+use unixnotis_core::util;
+use unixnotis_core::{
+    CloseReason, ControlProxy, ControlState, DebugLogStream, NotificationView, PanelDebugLevel,
+};
+
+use crate::redact_view;
+
+/// One control signal as printed by `watch`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum WatchEvent {
+    Added {
+        notification: NotificationView,
+        show_popup: bool,
+    },
+    Updated {
+        notification: NotificationView,
+        show_popup: bool,
+    },
+    Closed {
+        id: u32,
+        reason: &'static str,
+    },
+    State {
+        dnd_enabled: bool,
+        dnd_until: i64,
+        inhibited_by: String,
+        popups_enabled: bool,
+        muted: bool,
+        history_count: u32,
+    },
+}
+
+impl WatchEvent {
+    fn state(state: &ControlState) -> Self {
+        WatchEvent::State {
+            dnd_enabled: state.dnd_enabled,
+            dnd_until: state.dnd_until,
+            inhibited_by: state.inhibited_by.clone(),
+            popups_enabled: state.popups_enabled,
+            muted: state.muted,
+            history_count: state.history_count,
+        }
+    }
+
+    /// Human-readable line such as `added #12 [mail] New message`.
+    fn human(&self) -> String {
+        match self {
+            WatchEvent::Added { notification, .. } => {
+                format!("added {}", describe_notification(notification))
+            }
+            WatchEvent::Updated { notification, .. } => {
+                format!("updated {}", describe_notification(notification))
+            }
+            WatchEvent::Closed { id, reason } => format!("closed #{id} ({reason})"),
+            WatchEvent::State {
+                dnd_enabled,
+                popups_enabled,
+                muted,
+                history_count,
+                ..
+            } => format!(
+                "state dnd={} popups={} mute={} history={history_count}",
+                if *dnd_enabled { "on" } else { "off" },
+                if *popups_enabled { "on" } else { "off" },
+                if *muted { "on" } else { "off" }
+            ),
+        }
+    }
+}
+
+fn describe_notification(notification: &NotificationView) -> String {
+    format!(
+        "#{id} [{app}] {summary}",
+        id = notification.id,
+        app = notification.app_name,
+        summary = notification.summary
+    )
+}
+
+fn reason_name(reason: CloseReason) -> &'static str {
+    match reason {
+        CloseReason::Expired => "expired",
+        CloseReason::DismissedByUser => "dismissed",
+        CloseReason::ClosedByCall => "closed",
+        CloseReason::Undefined => "undefined",
+        CloseReason::Evicted => "evicted",
+        CloseReason::Pruned => "pruned",
+        CloseReason::SenderGone => "sender-gone",
+    }
+}
+
+/// Print events until a signal stream ends.
+pub async fn watch(proxy: &ControlProxy<'_>, json: bool, full: bool) -> Result<()> {
+    let limit = if full {
+        util::diagnostic_log_limit()
+    } else {
+        util::default_log_limit()
+    };
+    let mut added_stream = proxy.receive_notification_added().await?;
+    let mut batch_stream = proxy.receive_notifications_added_batch().await?;
+    let mut updated_stream = proxy.receive_notification_updated().await?;
+    let mut closed_stream = proxy.receive_notification_closed().await?;
+    let mut state_stream = proxy.receive_state_changed().await?;
+
+    loop {
+        let events = tokio::select! {
+            signal = added_stream.next() => {
+                let Some(signal) = signal else { break };
+                let Ok(args) = signal.args() else { continue };
+                vec![WatchEvent::Added {
+                    notification: redact_view(args.notification(), limit),
+                    show_popup: *args.show_popup(),
+                }]
+            }
+            // Bursts arrive as one signal but print as one `added` line each.
+            signal = batch_stream.next() => {
+                let Some(signal) = signal else { break };
+                let Ok(args) = signal.args() else { continue };
+                args.notifications()
+                    .iter()
+                    .map(|(notification, show_popup)| WatchEvent::Added {
+                        notification: redact_view(notification, limit),
+                        show_popup: *show_popup,
+                    })
+                    .collect()
+            }
+            signal = updated_stream.next() => {
+                let Some(signal) = signal else { break };
+                let Ok(args) = signal.args() else { continue };
+                vec![WatchEvent::Updated {
+                    notification: redact_view(args.notification(), limit),
+                    show_popup: *args.show_popup(),
+                }]
+            }
+            signal = closed_stream.next() => {
+                let Some(signal) = signal else { break };
+                let Ok(args) = signal.args() else { continue };
+                vec![WatchEvent::Closed {
+                    id: *args.id(),
+                    reason: reason_name(*args.reason()),
+                }]
+            }
+            signal = state_stream.next() => {
+                let Some(signal) = signal else { break };
+                let Ok(args) = signal.args() else { continue };
+                vec![WatchEvent::state(args.state())]
+            }
+        };
+        for event in events {
+            if json {
+                println!("{}", serde_json::to_string(&event)?);
+            } else {
+                println!("{}", event.human());
+            }
+        }
+    }
+    Err(anyhow!("daemon signal stream ended"))
+}
+
+/// Open the panel with debug logging and print its debug lines until the stream ends.
+pub async fn follow_debug(proxy: &ControlProxy<'_>, level: PanelDebugLevel) -> Result<()> {
+    // Subscribe first so lines logged while the panel opens are not missed.
+    let stream = proxy.receive_debug_log().await?;
+    proxy.open_panel_debug(level).await?;
+    print_debug_lines(stream).await
+}
+
+/// Like `follow_debug`, but changes the level of the panel as it stands instead of opening it.
+pub async fn follow_debug_level(proxy: &ControlProxy<'_>, level: PanelDebugLevel) -> Result<()> {
+    let stream = proxy.receive_debug_log().await?;
+    proxy.set_panel_debug_level(level).await?;
+    print_debug_lines(stream).await
+}
+
+async fn print_debug_lines(mut stream: DebugLogStream<'_>) -> Result<()> {
+    while let Some(signal) = stream.next().await {
+        let Ok(args) = signal.args() else { continue };
+        println!("{}", debug_line(*args.level(), args.message()));
+    }
+    Err(anyhow!("daemon signal stream ended"))
+}
+
+fn debug_line(level: PanelDebugLevel, message: &str) -> String {
+    let name = match level {
+        PanelDebugLevel::Critical => "critical",
+        PanelDebugLevel::Warn => "warn",
+        PanelDebugLevel::Info => "info",
+        PanelDebugLevel::Verbose => "verbose",
+        PanelDebugLevel::Off => "off",
+    };
+    format!("[{name}] {message}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_print_as_tagged_json_and_plain_lines() {
+        let added = WatchEvent::Added {
+            notification: NotificationView {
+                id: 12,
+                app_name: "mail".to_string(),
+                summary: "New message".to_string(),
+                body: String::new(),
+                actions: Vec::new(),
+                urgency: 1,
+                is_transient: false,
+                is_resident: false,
+                received_at_unix_ms: 1_700_000_000_000,
+                image: Default::default(),
+                history_ttl_minutes: 0,
+                pinned: false,
+                progress: -1,
+            },
+            show_popup: true,
+        };
+        assert_eq!(added.human(), "added #12 [mail] New message");
+        let json: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&added).expect("serializes"))
+                .expect("valid json");
+        assert_eq!(json["event"], "added");
+        assert_eq!(json["notification"]["summary"], "New message");
+        assert_eq!(json["show_popup"], true);
+
+        let closed = WatchEvent::Closed {
+            id: 12,
+            reason: reason_name(CloseReason::DismissedByUser),
+        };
+        assert_eq!(closed.human(), "closed #12 (dismissed)");
+        assert_eq!(
+            serde_json::to_string(&closed).expect("serializes"),
+            r#"{"event":"closed","id":12,"reason":"dismissed"}"#
+        );
+        assert_eq!(
+            debug_line(PanelDebugLevel::Warn, "css reload failed"),
+            "[warn] css reload failed"
+        );
+    }
+}
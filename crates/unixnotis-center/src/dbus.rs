@@ -1,19 +1,23 @@
 //! D-Bus runtime for center UI events and control commands.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::time::Duration;
 
 use futures_util::StreamExt;
 use tokio::sync::mpsc::{self, UnboundedSender};
 use tracing::{info, warn};
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::ui_ipc::{ControlCommand, ControlEvent};
 use unixnotis_core::{
-    CloseReason, ControlProxy, ControlState, Margins, NotificationView, PanelDebugLevel,
-    PanelRequest,
+    BusNames, ClearScope, ControlProxy, ControlState, Margins, NotificationImage, NotificationView,
+    PanelDebugLevel, PanelGeometry, PanelRequest, RuleConfig, HISTORY_PAGE_SIZE,
 };
+use zbus::zvariant::Value;
 use zbus::{Connection, Result as ZbusResult};
 
 use crate::debug;
 use crate::media::MediaInfo;
+use crate::ui::keys::PanelKeyAction;
 
 /// Events delivered to the GTK main loop.
 #[derive(Debug, Clone)]
@@ -21,14 +25,35 @@ pub enum UiEvent {
     Seed {
         state: ControlState,
         active: Vec<NotificationView>,
+        /// The newest history page; the rest arrives as `HistoryPage`.
         history: Vec<NotificationView>,
     },
+    /// Older history requested with `UiCommand::LoadHistory`.
+    HistoryPage(Vec<NotificationView>),
+    /// The list scrolled near its end while more history may be waiting.
+    HistoryWanted,
+    /// Answer to `UiCommand::FetchImage`; `None` when the notification is gone.
+    ImageFetched(u32, Option<NotificationImage>),
     NotificationAdded(NotificationView, bool),
-    NotificationUpdated(NotificationView, bool),
-    NotificationClosed(u32, CloseReason),
-    StateChanged(ControlState),
+    /// Notifications that arrived in one burst, to be added with a single rebuild.
+    NotificationsAdded(Vec<NotificationView>),
+    /// A control signal shared with the popups.
+    Control(ControlEvent),
+    NotificationPinned(u32, bool),
     PanelRequested(PanelRequest),
+    /// Temporary panel placement; an empty geometry restores the config.
+    PanelGeometryRequested(PanelGeometry),
+    /// New debug level for the panel as it stands, without opening or closing it.
+    DebugLevelRequested(PanelDebugLevel),
     GroupToggled(String),
+    /// Advance the panel list to the next sort order.
+    SortCycled,
+    /// A key bound in `[panel.keys]` was pressed; Close is sent to the daemon directly.
+    PanelKey(PanelKeyAction),
+    /// The header's Clear button (`None`, follow the config) or a clear menu entry was pressed.
+    ClearRequested(Option<ClearScope>),
+    /// A clear removed something and can be undone for a while.
+    Cleared,
     /// Updated set of active media players for the widget.
     MediaUpdated(Vec<MediaInfo>),
     MediaCleared,
@@ -36,33 +61,57 @@ pub enum UiEvent {
     ClickOutside,
     /// Hyprland reserved work area update for panel sizing.
     WorkAreaUpdated(Option<Margins>),
+    /// A monitor was connected or disconnected.
+    MonitorsChanged,
+    /// The panel window's scale factor changed, e.g. on a fractional scaling toggle.
+    ScaleChanged(i32),
+    /// The panel window was closed by the compositor or, without layer-shell, the user.
+    SurfaceClosed,
     RefreshWidgets,
+    /// Current notification rules, in the order they apply.
+    RulesListed(Vec<RuleConfig>),
     CssReload,
     ConfigReload,
+    /// The config file changed but does not parse; carries the error.
+    ConfigInvalid(String),
 }
 
 /// Commands sent from GTK handlers to the D-Bus runtime.
 #[derive(Debug, Clone)]
 pub enum UiCommand {
-    Dismiss(u32),
-    InvokeAction { id: u32, action_key: String },
-    ClearAll,
-    SetDnd(bool),
-    ClosePanel,
+    /// A plain control call, shared with the popups.
+    Control(ControlCommand),
+    Clear(ClearScope),
+    /// Restore what the last clear removed, if the daemon still has it.
+    UndoClear,
+    /// Fetch the history page starting at this offset.
+    LoadHistory(u32),
+    /// Fetch the image pixels a listing left out, for a preview.
+    FetchImage(u32),
+    /// Tell the user an edited config was not applied, with the parse error.
+    NotifyConfigInvalid(String),
+}
+
+impl From<ControlCommand> for UiCommand {
+    fn from(command: ControlCommand) -> Self {
+        Self::Control(command)
+    }
 }
 
 pub fn start_dbus_task(
     runtime: &tokio::runtime::Handle,
     connection: Connection,
+    names: BusNames,
     sender: async_channel::Sender<UiEvent>,
 ) -> UnboundedSender<UiCommand> {
     let (command_tx, command_rx) = mpsc::unbounded_channel();
-    runtime.spawn(run_dbus_loop(connection, sender, command_rx));
+    runtime.spawn(run_dbus_loop(connection, names, sender, command_rx));
     command_tx
 }
 
 async fn run_dbus_loop(
     connection: Connection,
+    names: BusNames,
     sender: async_channel::Sender<UiEvent>,
     mut command_rx: mpsc::UnboundedReceiver<UiCommand>,
 ) {
@@ -70,7 +119,7 @@ async fn run_dbus_loop(
     let mut offline_commands: VecDeque<UiCommand> = VecDeque::new();
 
     loop {
-        let proxy = match ControlProxy::new(&connection).await {
+        let proxy = match names.control_proxy(&connection).await {
             Ok(proxy) => proxy,
             Err(err) => {
                 warn!(?err, "control interface unavailable, retrying");
@@ -81,7 +130,7 @@ async fn run_dbus_loop(
         };
         info!("connected to unixnotis control interface");
         seed_state(&proxy, &sender).await;
-        flush_offline_commands(&proxy, &sender, &mut offline_commands).await;
+        flush_offline_commands(&proxy, &names, &sender, &mut offline_commands).await;
 
         let mut added_stream = match proxy.receive_notification_added().await {
             Ok(stream) => stream,
@@ -91,6 +140,14 @@ async fn run_dbus_loop(
                 continue;
             }
         };
+        let mut batch_stream = match proxy.receive_notifications_added_batch().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(?err, "failed to subscribe to notifications_added_batch");
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                continue;
+            }
+        };
         let mut updated_stream = match proxy.receive_notification_updated().await {
             Ok(stream) => stream,
             Err(err) => {
@@ -107,6 +164,14 @@ async fn run_dbus_loop(
                 continue;
             }
         };
+        let mut pinned_stream = match proxy.receive_notification_pinned().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(?err, "failed to subscribe to notification_pinned");
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                continue;
+            }
+        };
         let mut state_stream = match proxy.receive_state_changed().await {
             Ok(stream) => stream,
             Err(err) => {
@@ -123,6 +188,62 @@ async fn run_dbus_loop(
                 continue;
             }
         };
+        let mut geometry_stream = match proxy.receive_panel_geometry_requested().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(?err, "failed to subscribe to panel_geometry_requested");
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                continue;
+            }
+        };
+        let mut debug_level_stream = match proxy.receive_panel_debug_level_requested().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(?err, "failed to subscribe to panel_debug_level_requested");
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                continue;
+            }
+        };
+        let mut preview_stream = match proxy.receive_layout_preview_requested().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(?err, "failed to subscribe to layout_preview_requested");
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                continue;
+            }
+        };
+        let mut memory_stream = match proxy.receive_memory_report_requested().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(?err, "failed to subscribe to memory_report_requested");
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                continue;
+            }
+        };
+        let mut trim_stream = match proxy.receive_trim_requested().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(?err, "failed to subscribe to trim_requested");
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                continue;
+            }
+        };
+        let mut rules_stream = match proxy.receive_rules_changed().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(?err, "failed to subscribe to rules_changed");
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                continue;
+            }
+        };
+        let mut history_stream = match proxy.receive_history_changed().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(?err, "failed to subscribe to history_changed");
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                continue;
+            }
+        };
 
         loop {
             tokio::select! {
@@ -130,7 +251,7 @@ async fn run_dbus_loop(
                     let Some(command) = command else {
                         break;
                     };
-                    if let Err(err) = handle_command(&proxy, &sender, command).await {
+                    if let Err(err) = handle_command(&proxy, &names, &sender, command).await {
                         warn!(?err, "control command failed");
                     }
                 }
@@ -148,6 +269,20 @@ async fn run_dbus_loop(
                             .await;
                     }
                 }
+                signal = batch_stream.next() => {
+                    let Some(signal) = signal else {
+                        warn!("notifications_added_batch stream ended");
+                        break;
+                    };
+                    if let Ok(args) = signal.args() {
+                        let notifications = args
+                            .notifications()
+                            .iter()
+                            .map(|(notification, _show_popup)| notification.clone())
+                            .collect();
+                        let _ = sender.send(UiEvent::NotificationsAdded(notifications)).await;
+                    }
+                }
                 signal = updated_stream.next() => {
                     let Some(signal) = signal else {
                         warn!("notification_updated stream ended");
@@ -155,10 +290,10 @@ async fn run_dbus_loop(
                     };
                     if let Ok(args) = signal.args() {
                         let _ = sender
-                            .send(UiEvent::NotificationUpdated(
+                            .send(UiEvent::Control(ControlEvent::NotificationUpdated(
                                 args.notification().clone(),
                                 *args.show_popup(),
-                            ))
+                            )))
                             .await;
                     }
                 }
@@ -169,10 +304,21 @@ async fn run_dbus_loop(
                     };
                     if let Ok(args) = signal.args() {
                         let _ = sender
-                            .send(UiEvent::NotificationClosed(
+                            .send(UiEvent::Control(ControlEvent::NotificationClosed(
                                 *args.id(),
                                 *args.reason(),
-                            ))
+                            )))
+                            .await;
+                    }
+                }
+                signal = pinned_stream.next() => {
+                    let Some(signal) = signal else {
+                        warn!("notification_pinned stream ended");
+                        break;
+                    };
+                    if let Ok(args) = signal.args() {
+                        let _ = sender
+                            .send(UiEvent::NotificationPinned(*args.id(), *args.pinned()))
                             .await;
                     }
                 }
@@ -182,7 +328,8 @@ async fn run_dbus_loop(
                         break;
                     };
                     if let Ok(args) = signal.args() {
-                        let _ = sender.send(UiEvent::StateChanged(args.state().clone())).await;
+                        let event = ControlEvent::StateChanged(args.state().clone());
+                        let _ = sender.send(UiEvent::Control(event)).await;
                     }
                 }
                 signal = panel_stream.next() => {
@@ -191,8 +338,68 @@ async fn run_dbus_loop(
                         break;
                     };
                     if let Ok(args) = signal.args() {
-                        let _ = sender.send(UiEvent::PanelRequested(*args.request())).await;
+                        let _ = sender.send(UiEvent::PanelRequested(args.request().clone())).await;
+                    }
+                }
+                signal = geometry_stream.next() => {
+                    let Some(signal) = signal else {
+                        warn!("panel_geometry_requested stream ended");
+                        break;
+                    };
+                    if let Ok(args) = signal.args() {
+                        let _ = sender
+                            .send(UiEvent::PanelGeometryRequested(args.geometry().clone()))
+                            .await;
+                    }
+                }
+                signal = debug_level_stream.next() => {
+                    let Some(signal) = signal else {
+                        warn!("panel_debug_level_requested stream ended");
+                        break;
+                    };
+                    if let Ok(args) = signal.args() {
+                        let _ = sender.send(UiEvent::DebugLevelRequested(*args.level())).await;
+                    }
+                }
+                signal = preview_stream.next() => {
+                    let Some(signal) = signal else {
+                        warn!("layout_preview_requested stream ended");
+                        break;
+                    };
+                    if let Ok(args) = signal.args() {
+                        let event = ControlEvent::LayoutPreview(*args.seconds());
+                        let _ = sender.send(UiEvent::Control(event)).await;
+                    }
+                }
+                signal = memory_stream.next() => {
+                    if signal.is_none() {
+                        warn!("memory_report_requested stream ended");
+                        break;
+                    }
+                    let _ = sender
+                        .send(UiEvent::Control(ControlEvent::MemoryReportRequested))
+                        .await;
+                }
+                signal = trim_stream.next() => {
+                    if signal.is_none() {
+                        warn!("trim_requested stream ended");
+                        break;
                     }
+                    let _ = sender.send(UiEvent::Control(ControlEvent::TrimCaches)).await;
+                }
+                signal = rules_stream.next() => {
+                    if signal.is_none() {
+                        warn!("rules_changed stream ended");
+                        break;
+                    }
+                    send_rules(&proxy, &sender).await;
+                }
+                signal = history_stream.next() => {
+                    if signal.is_none() {
+                        warn!("history_changed stream ended");
+                        break;
+                    }
+                    seed_state(&proxy, &sender).await;
                 }
             }
         }
@@ -204,7 +411,7 @@ async fn run_dbus_loop(
 async fn seed_state(proxy: &ControlProxy<'_>, sender: &async_channel::Sender<UiEvent>) {
     let state = proxy.get_state().await;
     let active = proxy.list_active().await;
-    let history = proxy.list_history().await;
+    let history = proxy.list_history_range(0, HISTORY_PAGE_SIZE).await;
 
     if let (Ok(state), Ok(active), Ok(history)) = (state, active, history) {
         let _ = sender
@@ -215,26 +422,96 @@ async fn seed_state(proxy: &ControlProxy<'_>, sender: &async_channel::Sender<UiE
             })
             .await;
     }
+    send_rules(proxy, sender).await;
+}
+
+async fn send_rules(proxy: &ControlProxy<'_>, sender: &async_channel::Sender<UiEvent>) {
+    let dicts = match proxy.list_rules().await {
+        Ok(dicts) => dicts,
+        Err(err) => {
+            warn!(?err, "failed to list rules");
+            return;
+        }
+    };
+    let rules = dicts
+        .iter()
+        .filter_map(|dict| match RuleConfig::from_dict(dict) {
+            Ok(rule) => Some(rule),
+            Err(err) => {
+                warn!(err, "skipping invalid rule from daemon");
+                None
+            }
+        })
+        .collect();
+    let _ = sender.send(UiEvent::RulesListed(rules)).await;
 }
 
 async fn handle_command(
     proxy: &ControlProxy<'_>,
+    names: &BusNames,
     sender: &async_channel::Sender<UiEvent>,
     command: UiCommand,
 ) -> ZbusResult<()> {
     match command {
-        UiCommand::Dismiss(id) => proxy.dismiss(id).await,
-        UiCommand::InvokeAction { id, action_key } => proxy.invoke_action(id, &action_key).await,
-        UiCommand::ClearAll => {
-            proxy.clear_all().await?;
+        UiCommand::Control(command) => command.send(proxy).await,
+        UiCommand::Clear(scope) => {
+            if !proxy.clear(scope).await?.is_empty() {
+                let _ = sender.send(UiEvent::Cleared).await;
+            }
             seed_state(proxy, sender).await;
             Ok(())
         }
-        UiCommand::SetDnd(enabled) => proxy.set_dnd(enabled).await,
-        UiCommand::ClosePanel => proxy.close_panel().await,
+        UiCommand::UndoClear => {
+            // The daemon signals what came back, so every panel updates the same way.
+            proxy.undo_clear().await?;
+            Ok(())
+        }
+        UiCommand::LoadHistory(offset) => {
+            let page = proxy.list_history_range(offset, HISTORY_PAGE_SIZE).await?;
+            let _ = sender.send(UiEvent::HistoryPage(page)).await;
+            Ok(())
+        }
+        UiCommand::FetchImage(id) => {
+            let (image, result) = match proxy.get_image(id).await {
+                Ok(image) => (Some(image), Ok(())),
+                Err(err) => (None, Err(err)),
+            };
+            let _ = sender.send(UiEvent::ImageFetched(id, image)).await;
+            result
+        }
+        UiCommand::NotifyConfigInvalid(error) => {
+            notify_config_invalid(proxy.inner().connection(), names, &error).await
+        }
     }
 }
 
+/// Post the parse error through the daemon's Notify, like any other notification.
+async fn notify_config_invalid(
+    connection: &Connection,
+    names: &BusNames,
+    error: &str,
+) -> ZbusResult<()> {
+    let summary = tr("config-invalid-summary");
+    let body = tr_args("config-invalid-body", &[("error", error.into())]);
+    let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+    hints.insert("urgency", Value::U8(1));
+    names
+        .notifications_proxy(connection)
+        .await?
+        .notify(
+            "UnixNotis",
+            0,
+            "dialog-warning",
+            &summary,
+            &body,
+            &[],
+            hints,
+            -1,
+        )
+        .await
+        .map(|_| ())
+}
+
 const MAX_OFFLINE_COMMANDS: usize = 128;
 
 fn stash_offline_commands(
@@ -243,6 +520,10 @@ fn stash_offline_commands(
 ) {
     let mut drained = 0usize;
     while let Ok(command) = command_rx.try_recv() {
+        // Nobody can be following debug lines while the daemon is away.
+        if matches!(command, UiCommand::Control(ControlCommand::DebugLog { .. })) {
+            continue;
+        }
         if offline.len() >= MAX_OFFLINE_COMMANDS {
             offline.pop_front();
             warn!("dropping control command while interface is unavailable");
@@ -262,6 +543,7 @@ fn stash_offline_commands(
 
 async fn flush_offline_commands(
     proxy: &ControlProxy<'_>,
+    names: &BusNames,
     sender: &async_channel::Sender<UiEvent>,
     offline: &mut VecDeque<UiCommand>,
 ) {
@@ -272,7 +554,7 @@ async fn flush_offline_commands(
         format!("replaying {} buffered control command(s)", offline.len())
     });
     while let Some(command) = offline.pop_front() {
-        if let Err(err) = handle_command(proxy, sender, command).await {
+        if let Err(err) = handle_command(proxy, names, sender, command).await {
             warn!(?err, "buffered control command failed");
         }
     }
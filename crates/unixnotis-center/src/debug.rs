@@ -1,12 +1,22 @@
 //! Debug logging helpers gated by panel debug level.
 
 use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
 
 use tracing::{error, info, warn};
 use unixnotis_core::PanelDebugLevel;
 
 static DEBUG_LEVEL: AtomicU8 = AtomicU8::new(PanelDebugLevel::Off as u8);
 
+type Forward = Box<dyn Fn(PanelDebugLevel, String) + Send + Sync>;
+
+static FORWARD: OnceLock<Forward> = OnceLock::new();
+
+/// Also hand every logged line to `forward`, which relays it to `noticenterctl` over D-Bus.
+pub fn set_forward(forward: impl Fn(PanelDebugLevel, String) + Send + Sync + 'static) {
+    let _ = FORWARD.set(Box::new(forward));
+}
+
 pub fn set_level(level: PanelDebugLevel) {
     DEBUG_LEVEL.store(level as u8, Ordering::Relaxed);
 }
@@ -42,4 +52,7 @@ pub fn log(level: PanelDebugLevel, message: impl FnOnce() -> String) {
             info!(debug_level = ?level, message = %message, "unixnotis debug");
         }
     }
+    if let Some(forward) = FORWARD.get() {
+        forward(level, message);
+    }
 }
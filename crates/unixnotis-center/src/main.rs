@@ -5,15 +5,14 @@ use std::env;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
-use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 use glib::MainContext;
 use gtk::prelude::*;
 use tracing::{info, warn};
-use tracing_subscriber::EnvFilter;
-use unixnotis_core::Config;
+use unixnotis_core::ui_ipc::ControlCommand;
+use unixnotis_core::{BusNames, Config};
 use unixnotis_ui::css::{self, CssKind};
 use zbus::Connection;
 
@@ -28,12 +27,21 @@ struct Args {
     /// Path to config.toml
     #[arg(long)]
     config: Option<PathBuf>,
+
+    /// Suffix for alternate bus names (falls back to UNIXNOTIS_BUS_SUFFIX)
+    #[arg(long)]
+    bus_suffix: Option<String>,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
     let (config, config_path) = load_config(&args).context("load config")?;
-    init_tracing(&config);
+    let names = BusNames::resolve(args.bus_suffix.as_deref())?;
+    let trace_guard = unixnotis_core::logging::init_tracing(
+        config.general.log_level.as_deref(),
+        "unixnotis-center",
+    );
+    unixnotis_core::i18n::init(config.general.language.as_deref());
     let config_source = if args.config.is_some() {
         "custom"
     } else if config_path.exists() {
@@ -54,6 +62,8 @@ fn main() -> Result<()> {
             "Wayland session not detected; panel UI requires Wayland"
         ));
     }
+    gtk::init().context("initialize GTK")?;
+    unixnotis_ui::surface::init(config.general.layer_shell).context("layer-shell required")?;
 
     let theme_base = config_path
         .parent()
@@ -66,7 +76,13 @@ fn main() -> Result<()> {
         .ensure_theme_files(&theme_paths)
         .context("ensure theme files")?;
 
-    let app = gtk::Application::new(Some("com.unixnotis.Center"), Default::default());
+    let app = gtk::Application::new(
+        Some(names.app_id("com.unixnotis.Center").as_str()),
+        Default::default(),
+    );
+    if trace_guard.is_profiling() {
+        unixnotis_ui::profiling::quit_on_terminate(&app);
+    }
 
     app.connect_activate(move |app| {
         let (event_tx, event_rx) = async_channel::unbounded();
@@ -87,10 +103,19 @@ fn main() -> Result<()> {
                 return;
             }
         };
-        let command_tx =
-            dbus::start_dbus_task(runtime.handle(), connection.clone(), event_tx.clone());
+        let command_tx = dbus::start_dbus_task(
+            runtime.handle(),
+            connection.clone(),
+            names.clone(),
+            event_tx.clone(),
+        );
+        let debug_tx = command_tx.clone();
+        debug::set_forward(move |level, message| {
+            let _ = debug_tx.send(ControlCommand::DebugLog { level, message }.into());
+        });
 
-        let css_manager = css::CssManager::new_panel(theme_paths.clone(), config.theme.clone());
+        let mut css_manager = css::CssManager::new_panel(theme_paths.clone(), config.theme.clone());
+        css_manager.set_density(config.panel.density);
         css_manager.apply_to_display();
         css_manager.reload(css::DEFAULT_CSS);
 
@@ -112,28 +137,17 @@ fn main() -> Result<()> {
         })));
 
         let ui_clone = ui.clone();
-        let rebuild_source: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
         MainContext::default().spawn_local(async move {
             while let Ok(event) = event_rx.recv().await {
-                let mut ui = ui_clone.borrow_mut();
-                ui.handle_event(event);
-                while let Ok(next_event) = event_rx.try_recv() {
-                    ui.handle_event(next_event);
+                {
+                    let mut ui = ui_clone.borrow_mut();
+                    ui.handle_event(event);
+                    while let Ok(next_event) = event_rx.try_recv() {
+                        ui.handle_event(next_event);
+                    }
                 }
                 // Coalesce rebuilds to once per frame to avoid bursty list churn.
-                if ui.list_needs_rebuild() && rebuild_source.borrow().is_none() {
-                    let ui_weak = Rc::downgrade(&ui_clone);
-                    let rebuild_source_handle = rebuild_source.clone();
-                    // Debounce rebuilds to batch burst updates into a single list refresh.
-                    let source_id =
-                        glib::timeout_add_local_once(Duration::from_millis(16), move || {
-                            if let Some(ui) = ui_weak.upgrade() {
-                                ui.borrow_mut().flush_list_rebuild();
-                            }
-                            *rebuild_source_handle.borrow_mut() = None;
-                        });
-                    *rebuild_source.borrow_mut() = Some(source_id);
-                }
+                ui::UiState::schedule_list_rebuild(&ui_clone);
             }
         });
 
@@ -143,9 +157,18 @@ fn main() -> Result<()> {
                 let _ = event_tx.try_send(dbus::UiEvent::CssReload);
             }
         });
-        css::start_config_watcher(config_path.clone(), move || {
-            let _ = event_tx.try_send(dbus::UiEvent::ConfigReload);
-        });
+        css::start_config_watcher(
+            config_path.clone(),
+            {
+                let event_tx = event_tx.clone();
+                move || {
+                    let _ = event_tx.try_send(dbus::UiEvent::ConfigReload);
+                }
+            },
+            move |error| {
+                let _ = event_tx.try_send(dbus::UiEvent::ConfigInvalid(error));
+            },
+        );
         info!("unixnotis-center running");
     });
 
@@ -165,19 +188,6 @@ fn load_config(args: &Args) -> Result<(Config, PathBuf)> {
     Ok((config, path))
 }
 
-fn init_tracing(config: &Config) {
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        EnvFilter::new(
-            config
-                .general
-                .log_level
-                .clone()
-                .unwrap_or_else(|| "info".to_string()),
-        )
-    });
-    tracing_subscriber::fmt().with_env_filter(filter).init();
-}
-
 fn is_wayland_session() -> bool {
     if let Ok(session_type) = env::var("XDG_SESSION_TYPE") {
         if session_type.eq_ignore_ascii_case("wayland") {
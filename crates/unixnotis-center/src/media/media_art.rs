@@ -0,0 +1,292 @@
+//! Album art for players that publish http(s) art URLs, such as Spotify and browsers.
+//!
+//! GTK cannot load remote URLs on its own, and fetching on the GTK thread would stall the
+//! panel. Downloads go through GIO (gvfs handles http and https) on a blocking worker of the
+//! media runtime, so the panel needs no HTTP or TLS stack, and land in
+//! `$XDG_CACHE_HOME/unixnotis/media-art`. The card shows its placeholder until the file is
+//! there; the player is then refreshed and picks it up.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use gio::prelude::*;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{debug, warn};
+use unixnotis_core::Config;
+
+use super::MediaSignal;
+
+/// Larger images are not album art; the download is aborted past this size.
+const MAX_ART_BYTES: u64 = 5 * 1024 * 1024;
+/// The oldest downloads are removed once the cache grows past this.
+const MAX_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+const READ_CHUNK: usize = 64 * 1024;
+/// A URL that failed is not tried again for this long.
+const RETRY_AFTER: Duration = Duration::from_secs(10 * 60);
+/// Failed URLs remembered at most; a player cycling through broken art cannot grow the map.
+const MAX_FAILED: usize = 64;
+
+#[derive(Clone)]
+pub(super) struct ArtFetcher {
+    dir: Option<PathBuf>,
+    state: Arc<Mutex<FetchState>>,
+    signal_tx: UnboundedSender<MediaSignal>,
+}
+
+#[derive(Default)]
+struct FetchState {
+    inflight: HashSet<String>,
+    failed: HashMap<String, Instant>,
+}
+
+impl FetchState {
+    /// Remember that `uri` failed at `now`, forgetting expired and then the oldest failures.
+    fn note_failure(&mut self, uri: String, now: Instant) {
+        self.failed
+            .retain(|_, failed_at| now.duration_since(*failed_at) < RETRY_AFTER);
+        while self.failed.len() >= MAX_FAILED {
+            let Some(oldest) = self
+                .failed
+                .iter()
+                .min_by_key(|(_, failed_at)| **failed_at)
+                .map(|(uri, _)| uri.clone())
+            else {
+                break;
+            };
+            self.failed.remove(&oldest);
+        }
+        self.failed.insert(uri, now);
+    }
+}
+
+impl ArtFetcher {
+    pub(super) fn new(signal_tx: UnboundedSender<MediaSignal>) -> Self {
+        let dir = Config::default_cache_dir()
+            .ok()
+            .map(|dir| dir.join("media-art"));
+        Self {
+            dir,
+            state: Arc::new(Mutex::new(FetchState::default())),
+            signal_tx,
+        }
+    }
+
+    /// Art the UI can load directly: local art as is, remote art once it is cached.
+    ///
+    /// A remote URL that is not cached yet starts a download and yields `None`; `bus_name` is
+    /// refreshed when the download completes.
+    pub(super) fn resolve(&self, uri: String, bus_name: &str) -> Option<String> {
+        if !is_remote(&uri) {
+            return Some(uri);
+        }
+        let path = self.dir.as_ref()?.join(cache_name(&uri));
+        if path.is_file() {
+            return Some(path.to_string_lossy().into_owned());
+        }
+        self.start(uri, path, bus_name.to_string());
+        None
+    }
+
+    fn start(&self, uri: String, path: PathBuf, bus_name: String) {
+        {
+            let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+            if state
+                .failed
+                .get(&uri)
+                .is_some_and(|failed_at| failed_at.elapsed() < RETRY_AFTER)
+            {
+                return;
+            }
+            if !state.inflight.insert(uri.clone()) {
+                return;
+            }
+        }
+        let fetcher = self.clone();
+        tokio::spawn(async move {
+            let fetched = download(&uri, &path).await;
+            {
+                let mut state = fetcher.state.lock().unwrap_or_else(|err| err.into_inner());
+                state.inflight.remove(&uri);
+                if fetched {
+                    state.failed.remove(&uri);
+                } else {
+                    state.note_failure(uri, Instant::now());
+                }
+            }
+            if !fetched {
+                return;
+            }
+            if let Some(dir) = fetcher.dir.clone() {
+                let _ = tokio::task::spawn_blocking(move || prune_cache(&dir)).await;
+            }
+            let _ = fetcher
+                .signal_tx
+                .send(MediaSignal::PropertiesChanged(bus_name));
+        });
+    }
+}
+
+fn is_remote(uri: &str) -> bool {
+    uri.starts_with("https://") || uri.starts_with("http://")
+}
+
+/// File name for `uri`; FNV-1a keeps it stable across builds, unlike `DefaultHasher`.
+fn cache_name(uri: &str) -> String {
+    let hash = uri.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+async fn download(uri: &str, path: &Path) -> bool {
+    let Some(dir) = path.parent() else {
+        return false;
+    };
+    if let Err(err) = tokio::fs::create_dir_all(dir).await {
+        warn!(?err, dir = %dir.display(), "failed to create album art cache");
+        return false;
+    }
+    // Written beside the final name and renamed, so a half-written file is never shown.
+    let partial = path.with_extension("part");
+    let cancellable = gio::Cancellable::new();
+    let worker = tokio::task::spawn_blocking({
+        let uri = uri.to_string();
+        let partial = partial.clone();
+        let cancellable = cancellable.clone();
+        move || fetch_to(&uri, &partial, &cancellable)
+    });
+    let size = match tokio::time::timeout(FETCH_TIMEOUT, worker).await {
+        Ok(Ok(Ok(size))) => size,
+        Ok(Ok(Err(err))) => {
+            debug!(uri, %err, "album art download failed");
+            0
+        }
+        Ok(Err(err)) => {
+            warn!(?err, "album art download task failed");
+            0
+        }
+        Err(_) => {
+            // The worker stops at its next read and its partial file is removed below.
+            cancellable.cancel();
+            debug!(uri, "album art download timed out");
+            0
+        }
+    };
+    if size == 0 {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return false;
+    }
+    match tokio::fs::rename(&partial, path).await {
+        Ok(()) => {
+            debug!(uri, size, "album art cached");
+            true
+        }
+        Err(err) => {
+            warn!(?err, "failed to store album art");
+            false
+        }
+    }
+}
+
+/// Copy `uri` into `partial`, giving up past `MAX_ART_BYTES`; returns the size written.
+fn fetch_to(uri: &str, partial: &Path, cancellable: &gio::Cancellable) -> Result<u64, String> {
+    let stream = gio::File::for_uri(uri)
+        .read(Some(cancellable))
+        .map_err(|err| err.to_string())?;
+    let mut file = std::fs::File::create(partial).map_err(|err| err.to_string())?;
+    let mut size = 0;
+    loop {
+        let chunk = stream
+            .read_bytes(READ_CHUNK, Some(cancellable))
+            .map_err(|err| err.to_string())?;
+        if chunk.is_empty() {
+            return Ok(size);
+        }
+        // Servers may send no length up front, so the limit is checked as the bytes arrive.
+        size += chunk.len() as u64;
+        if size > MAX_ART_BYTES {
+            return Err("larger than the album art limit".to_string());
+        }
+        file.write_all(&chunk).map_err(|err| err.to_string())?;
+    }
+}
+
+fn prune_cache(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let files = entries
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let modified = meta.modified().ok()?;
+            meta.is_file().then(|| (entry.path(), meta.len(), modified))
+        })
+        .collect();
+    for path in files_to_evict(files, MAX_CACHE_BYTES) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Oldest files to remove so the rest fit in `limit` bytes.
+fn files_to_evict(mut files: Vec<(PathBuf, u64, SystemTime)>, limit: u64) -> Vec<PathBuf> {
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+    let mut evicted = Vec::new();
+    for (path, size, _) in files {
+        if total <= limit {
+            break;
+        }
+        total = total.saturating_sub(size);
+        evicted.push(path);
+    }
+    evicted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_evicts_oldest_files_first() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let files = vec![
+            (PathBuf::from("new"), 40, at(30)),
+            (PathBuf::from("old"), 40, at(10)),
+            (PathBuf::from("mid"), 40, at(20)),
+        ];
+        assert_eq!(
+            files_to_evict(files.clone(), 80),
+            vec![PathBuf::from("old")]
+        );
+        assert_eq!(
+            files_to_evict(files.clone(), 40),
+            vec![PathBuf::from("old"), PathBuf::from("mid")]
+        );
+        assert!(files_to_evict(files, 120).is_empty());
+        assert_eq!(cache_name("https://a"), cache_name("https://a"));
+        assert_ne!(cache_name("https://a"), cache_name("https://b"));
+    }
+
+    #[test]
+    fn failed_urls_are_capped_and_expire() {
+        let start = Instant::now();
+        let mut state = FetchState::default();
+        for index in 0..MAX_FAILED + 10 {
+            let at = start + Duration::from_secs(index as u64);
+            state.note_failure(format!("https://art/{index}"), at);
+        }
+        assert_eq!(state.failed.len(), MAX_FAILED);
+        assert!(!state.failed.contains_key("https://art/0"));
+        assert!(state
+            .failed
+            .contains_key(&format!("https://art/{}", MAX_FAILED + 9)));
+
+        state.note_failure("https://late".to_string(), start + RETRY_AFTER * 2);
+        assert_eq!(state.failed.len(), 1);
+    }
+}
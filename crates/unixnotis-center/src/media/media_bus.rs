@@ -9,17 +9,27 @@ use tokio::sync::mpsc::UnboundedSender;
 use tracing::warn;
 use unixnotis_core::{MediaConfig, PanelDebugLevel};
 use zbus::fdo::{DBusProxy, PropertiesProxy};
+use zbus::zvariant::ObjectPath;
 use zbus::{Connection, Proxy, ProxyBuilder};
 
-use super::{MediaCommand, MediaSignal, MPRIS_APP, MPRIS_PATH, MPRIS_PLAYER, MPRIS_PREFIX};
+use super::{
+    MediaCommand, MediaSignal, MPRIS_APP, MPRIS_PATH, MPRIS_PLAYER, MPRIS_PREFIX, MPRIS_TRACKLIST,
+};
 use crate::debug;
+use crate::ui::hyprland;
 
 #[derive(Clone)]
 pub(super) struct PlayerState {
     pub(super) bus_name: String,
     pub(super) identity: String,
     pub(super) player: Proxy<'static>,
+    // Root MPRIS interface used for Raise and desktop entry lookups.
+    pub(super) app: Proxy<'static>,
+    // Present only for players advertising HasTrackList.
+    pub(super) tracklist: Option<Proxy<'static>>,
     pub(super) properties: PropertiesProxy<'static>,
+    // MPRIS CanControl; players without it reject writes such as Volume.
+    pub(super) can_control: bool,
 }
 
 pub(super) async fn refresh_players(
@@ -93,7 +103,7 @@ pub(super) fn spawn_properties_listener(
             let Ok(args) = update.args() else {
                 continue;
             };
-            if args.interface_name != MPRIS_PLAYER {
+            if args.interface_name != MPRIS_PLAYER && args.interface_name != MPRIS_TRACKLIST {
                 continue;
             }
             if !is_relevant_media_change(&args.changed_properties, &args.invalidated_properties) {
@@ -111,8 +121,9 @@ fn is_relevant_media_change(
     changed: &HashMap<&str, zbus::zvariant::Value<'_>>,
     invalidated: &[&str],
 ) -> bool {
-    const KEYS: [&str; 8] = [
+    const KEYS: [&str; 10] = [
         "Metadata",
+        "Tracks",
         "PlaybackStatus",
         "LoopStatus",
         "Shuffle",
@@ -120,6 +131,7 @@ fn is_relevant_media_change(
         "CanPause",
         "CanGoNext",
         "CanGoPrevious",
+        "Volume",
     ];
 
     if changed.keys().any(|key| KEYS.contains(key)) {
@@ -133,7 +145,8 @@ pub(super) async fn handle_command(
     command: MediaCommand,
 ) -> zbus::Result<Option<String>> {
     match command {
-        MediaCommand::Refresh => Ok(None),
+        // Raise is handled by the runtime loop because it also closes the panel.
+        MediaCommand::Refresh | MediaCommand::Raise { .. } => Ok(None),
         MediaCommand::PlayPause { bus_name } => {
             if let Some(state) = players.get(&bus_name) {
                 debug::log(PanelDebugLevel::Info, || {
@@ -156,6 +169,35 @@ pub(super) async fn handle_command(
             }
             Ok(None)
         }
+        MediaCommand::GoTo { bus_name, track_id } => {
+            let Some(tracklist) = players
+                .get(&bus_name)
+                .and_then(|state| state.tracklist.as_ref())
+            else {
+                return Ok(None);
+            };
+            debug::log(PanelDebugLevel::Info, || {
+                format!("media command: go to {track_id} on {bus_name}")
+            });
+            let track = ObjectPath::try_from(track_id.as_str())?;
+            let _value: () = tracklist.call("GoTo", &(track,)).await?;
+            Ok(Some(bus_name))
+        }
+        MediaCommand::SetVolume { bus_name, volume } => {
+            let Some(state) = players.get(&bus_name).filter(|state| state.can_control) else {
+                return Ok(None);
+            };
+            debug::log(PanelDebugLevel::Verbose, || {
+                format!("media command: volume {volume:.2} {bus_name}")
+            });
+            // Slider drags send a stream of these; the Volume change signal refreshes the card,
+            // so no extra refresh is requested here.
+            state
+                .player
+                .set_property("Volume", volume.clamp(0.0, 1.0))
+                .await?;
+            Ok(None)
+        }
         MediaCommand::Previous { bus_name } => {
             if let Some(state) = players.get(&bus_name) {
                 debug::log(PanelDebugLevel::Info, || {
@@ -170,6 +212,34 @@ pub(super) async fn handle_command(
     }
 }
 
+/// Bring the player window forward via MPRIS `Raise`, falling back to the compositor.
+pub(super) async fn raise_player(state: &PlayerState) {
+    let bus_name = state.bus_name.clone();
+    debug::log(PanelDebugLevel::Info, || {
+        format!("media command: raise {bus_name}")
+    });
+    let can_raise: bool = state.app.get_property("CanRaise").await.unwrap_or(false);
+    if can_raise {
+        let result: zbus::Result<()> = state.app.call("Raise", &()).await;
+        match result {
+            Ok(()) => return,
+            Err(err) => warn!(?err, player = %state.bus_name, "mpris raise failed"),
+        }
+    }
+
+    // Desktop entries usually match the window class; the identity is a last resort.
+    let desktop_entry: Option<String> = state.app.get_property("DesktopEntry").await.ok();
+    let class = desktop_entry
+        .filter(|entry| !entry.is_empty())
+        .unwrap_or_else(|| state.identity.clone());
+    let result = tokio::task::spawn_blocking(move || hyprland::focus_window_class(&class)).await;
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => warn!(?err, player = %state.bus_name, "compositor focus failed"),
+        Err(err) => warn!(?err, "compositor focus task failed"),
+    }
+}
+
 pub(super) async fn build_player_state(
     connection: &Connection,
     name: &str,
@@ -183,6 +253,26 @@ pub(super) async fn build_player_state(
         .interface(MPRIS_PLAYER)?
         .build()
         .await?;
+    let app = ProxyBuilder::new(connection)
+        .destination(name.to_string())?
+        .path(MPRIS_PATH)?
+        .interface(MPRIS_APP)?
+        .build()
+        .await?;
+    let has_tracklist: bool = app.get_property("HasTrackList").await.unwrap_or(false);
+    // The spec does not expect CanControl to change, so it is read once per player.
+    let can_control: bool = player.get_property("CanControl").await.unwrap_or(false);
+    let tracklist = if has_tracklist {
+        let proxy = ProxyBuilder::new(connection)
+            .destination(name.to_string())?
+            .path(MPRIS_PATH)?
+            .interface(MPRIS_TRACKLIST)?
+            .build()
+            .await?;
+        Some(proxy)
+    } else {
+        None
+    };
     let properties = PropertiesProxy::builder(connection)
         .destination(name.to_string())?
         .path(MPRIS_PATH)?
@@ -193,7 +283,10 @@ pub(super) async fn build_player_state(
         bus_name: name.to_string(),
         identity,
         player,
+        app,
+        tracklist,
         properties,
+        can_control,
     }))
 }
 
@@ -9,6 +9,7 @@ use tracing::debug;
 
 use crate::dbus::UiEvent;
 
+use super::media_art::ArtFetcher;
 use super::media_bus::PlayerState;
 use super::media_metadata::fetch_media_info;
 use super::MediaInfo;
@@ -16,11 +17,12 @@ use super::MediaInfo;
 pub(super) async fn refresh_cache(
     players: &HashMap<String, PlayerState>,
     cache: &mut HashMap<String, MediaInfo>,
+    art: &ArtFetcher,
 ) {
     cache.clear();
     let states: Vec<PlayerState> = players.values().cloned().collect();
     for state in states {
-        if let Some(info) = fetch_media_info(&state).await {
+        if let Some(info) = fetch_media_info(&state, art).await {
             cache.insert(state.bus_name.clone(), info);
         }
     }
@@ -29,13 +31,14 @@ pub(super) async fn refresh_cache(
 pub(super) async fn refresh_player_cache(
     players: &HashMap<String, PlayerState>,
     cache: &mut HashMap<String, MediaInfo>,
+    art: &ArtFetcher,
     bus_name: &str,
 ) {
     let Some(state) = players.get(bus_name).cloned() else {
         cache.remove(bus_name);
         return;
     };
-    if let Some(info) = fetch_media_info(&state).await {
+    if let Some(info) = fetch_media_info(&state, art).await {
         cache.insert(bus_name.to_string(), info);
     } else {
         cache.remove(bus_name);
@@ -5,12 +5,16 @@
 use std::collections::HashMap;
 use std::path::Path;
 
-use zbus::zvariant::OwnedValue;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
 
+use super::media_art::ArtFetcher;
 use super::media_bus::PlayerState;
-use super::MediaInfo;
+use super::{MediaInfo, QueuedTrack};
 
-pub(super) async fn fetch_media_info(state: &PlayerState) -> Option<MediaInfo> {
+// Upcoming tracks shown in the media widget queue preview.
+const MAX_QUEUE_PREVIEW: usize = 5;
+
+pub(super) async fn fetch_media_info(state: &PlayerState, art: &ArtFetcher) -> Option<MediaInfo> {
     // Missing metadata should not drop the card; fall back to identity-only.
     let metadata: HashMap<String, OwnedValue> = state
         .player
@@ -19,7 +23,13 @@ pub(super) async fn fetch_media_info(state: &PlayerState) -> Option<MediaInfo> {
         .unwrap_or_default();
     let title = metadata_string(&metadata, "xesam:title").unwrap_or_default();
     let artist = metadata_artist(&metadata).unwrap_or_default();
-    let art_uri = metadata_string(&metadata, "mpris:artUrl").and_then(normalize_art_uri);
+    let art_uri = metadata_string(&metadata, "mpris:artUrl")
+        .and_then(normalize_art_uri)
+        .and_then(|uri| art.resolve(uri, &state.bus_name));
+    let queue = match state.tracklist.as_ref() {
+        Some(tracklist) => fetch_queue(tracklist, metadata_track_id(&metadata)).await,
+        None => Vec::new(),
+    };
 
     let playback_status: String = state
         .player
@@ -38,6 +48,11 @@ pub(super) async fn fetch_media_info(state: &PlayerState) -> Option<MediaInfo> {
         .get_property("CanGoPrevious")
         .await
         .unwrap_or(false);
+    let volume = if state.can_control {
+        state.player.get_property::<f64>("Volume").await.ok()
+    } else {
+        None
+    };
 
     Some(MediaInfo {
         bus_name: state.bus_name.clone(),
@@ -50,9 +65,54 @@ pub(super) async fn fetch_media_info(state: &PlayerState) -> Option<MediaInfo> {
         can_pause,
         can_next,
         can_prev,
+        volume,
+        queue,
     })
 }
 
+async fn fetch_queue(
+    tracklist: &zbus::Proxy<'static>,
+    current: Option<String>,
+) -> Vec<QueuedTrack> {
+    let tracks: Vec<OwnedObjectPath> = tracklist.get_property("Tracks").await.unwrap_or_default();
+    // Without a current track id the whole list counts as upcoming.
+    let start = current
+        .and_then(|current| tracks.iter().position(|track| track.as_str() == current))
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    let upcoming: Vec<OwnedObjectPath> = tracks
+        .into_iter()
+        .skip(start)
+        .take(MAX_QUEUE_PREVIEW)
+        .collect();
+    if upcoming.is_empty() {
+        return Vec::new();
+    }
+    let metadata: Vec<HashMap<String, OwnedValue>> = tracklist
+        .call("GetTracksMetadata", &(upcoming,))
+        .await
+        .unwrap_or_default();
+    metadata
+        .iter()
+        .filter_map(|entry| {
+            Some(QueuedTrack {
+                id: metadata_track_id(entry)?,
+                title: metadata_string(entry, "xesam:title").unwrap_or_default(),
+                artist: metadata_artist(entry).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn metadata_track_id(map: &HashMap<String, OwnedValue>) -> Option<String> {
+    // Track ids are object paths per spec, though some players send plain strings.
+    let value = map.get("mpris:trackid")?;
+    if let Ok(path) = OwnedObjectPath::try_from(value.try_clone().ok()?) {
+        return Some(path.as_str().to_string());
+    }
+    metadata_string(map, "mpris:trackid")
+}
+
 fn metadata_string(map: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
     let value = map.get(key)?;
     let owned = value.try_clone().ok()?;
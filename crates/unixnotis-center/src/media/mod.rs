@@ -2,6 +2,7 @@
 //!
 //! Keeps the runtime loop here while delegating focused helpers to media_* modules.
 
+mod media_art;
 mod media_bus;
 mod media_cache;
 mod media_metadata;
@@ -13,14 +14,15 @@ use std::time::Duration;
 use futures_util::StreamExt;
 use tokio::sync::mpsc::{self, UnboundedSender};
 use tracing::warn;
-use unixnotis_core::MediaConfig;
+use unixnotis_core::{MediaConfig, PanelRequest};
 use zbus::fdo::DBusProxy;
 use zbus::Connection;
 
 use crate::dbus::UiEvent;
 
+use media_art::ArtFetcher;
 use media_bus::{
-    build_player_state, handle_command, is_allowed_player, refresh_players,
+    build_player_state, handle_command, is_allowed_player, raise_player, refresh_players,
     spawn_properties_listener, PlayerState,
 };
 use media_cache::{refresh_cache, refresh_player_cache, send_snapshot};
@@ -33,6 +35,7 @@ const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
 const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
 const MPRIS_PLAYER: &str = "org.mpris.MediaPlayer2.Player";
 const MPRIS_APP: &str = "org.mpris.MediaPlayer2";
+const MPRIS_TRACKLIST: &str = "org.mpris.MediaPlayer2.TrackList";
 
 #[derive(Debug, Clone)]
 pub struct MediaInfo {
@@ -46,6 +49,18 @@ pub struct MediaInfo {
     pub can_pause: bool,
     pub can_next: bool,
     pub can_prev: bool,
+    /// Player volume from 0.0 to 1.0, or None when the player does not let us change it.
+    pub volume: Option<f64>,
+    /// Upcoming tracks from the TrackList interface, empty when unsupported.
+    pub queue: Vec<QueuedTrack>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedTrack {
+    /// TrackList object path used to jump to the track.
+    pub id: String,
+    pub title: String,
+    pub artist: String,
 }
 
 #[derive(Debug, Clone)]
@@ -54,6 +69,9 @@ pub enum MediaCommand {
     PlayPause { bus_name: String },
     Next { bus_name: String },
     Previous { bus_name: String },
+    Raise { bus_name: String },
+    GoTo { bus_name: String, track_id: String },
+    SetVolume { bus_name: String, volume: f64 },
 }
 
 #[derive(Debug)]
@@ -96,6 +114,32 @@ impl MediaHandle {
             });
         }
     }
+
+    pub fn go_to(&self, bus_name: &str, track_id: &str) {
+        if let Some(tx) = &self.command_tx {
+            let _ = tx.send(MediaCommand::GoTo {
+                bus_name: bus_name.to_string(),
+                track_id: track_id.to_string(),
+            });
+        }
+    }
+
+    pub fn set_volume(&self, bus_name: &str, volume: f64) {
+        if let Some(tx) = &self.command_tx {
+            let _ = tx.send(MediaCommand::SetVolume {
+                bus_name: bus_name.to_string(),
+                volume,
+            });
+        }
+    }
+
+    pub fn raise(&self, bus_name: &str) {
+        if let Some(tx) = &self.command_tx {
+            let _ = tx.send(MediaCommand::Raise {
+                bus_name: bus_name.to_string(),
+            });
+        }
+    }
 }
 
 pub fn start_media_task(
@@ -141,6 +185,7 @@ pub fn start_media_task(
 
         // Dedicated signal channel keeps property updates out of the UI thread.
         let (signal_tx, mut signal_rx) = mpsc::unbounded_channel::<MediaSignal>();
+        let art = ArtFetcher::new(signal_tx.clone());
         let mut players: HashMap<String, PlayerState> = HashMap::new();
         let mut cache: HashMap<String, MediaInfo> = HashMap::new();
         let mut refresh = true;
@@ -153,7 +198,7 @@ pub fn start_media_task(
                 {
                     warn!(?err, "failed to refresh media players");
                 }
-                refresh_cache(&players, &mut cache).await;
+                refresh_cache(&players, &mut cache, &art).await;
                 send_snapshot(&sender, &cache).await;
                 schedule_metadata_fallbacks(&cache, signal_tx.clone());
                 refresh = false;
@@ -168,10 +213,19 @@ pub fn start_media_task(
                         MediaCommand::Refresh => {
                             refresh = true;
                         }
+                        MediaCommand::Raise { bus_name } => {
+                            if let Some(state) = players.get(&bus_name) {
+                                raise_player(state).await;
+                                // Hide the panel so the raised window is not covered by it.
+                                let _ = sender
+                                    .send(UiEvent::PanelRequested(PanelRequest::close()))
+                                    .await;
+                            }
+                        }
                         command => {
                             if let Ok(Some(name)) = handle_command(&players, command).await {
                                 // Post-command refresh keeps controls responsive without polling.
-                                refresh_player_cache(&players, &mut cache, &name).await;
+                                refresh_player_cache(&players, &mut cache, &art, &name).await;
                                 send_snapshot(&sender, &cache).await;
                                 schedule_metadata_fallback(&cache, signal_tx.clone(), &name);
                                 for delay_ms in [150_u64, 650_u64] {
@@ -191,7 +245,7 @@ pub fn start_media_task(
                     };
                     let MediaSignal::PropertiesChanged(name) = signal;
                     // Property changes are per-player; refresh only the updated entry.
-                    refresh_player_cache(&players, &mut cache, &name).await;
+                    refresh_player_cache(&players, &mut cache, &art, &name).await;
                     send_snapshot(&sender, &cache).await;
                     schedule_metadata_fallback(&cache, signal_tx.clone(), &name);
                 }
@@ -213,6 +267,7 @@ pub fn start_media_task(
                             &signal_tx,
                             &mut players,
                             &mut cache,
+                            &art,
                             &sender,
                         )
                         .await
@@ -239,6 +294,7 @@ async fn apply_owner_change(
     signal_tx: &UnboundedSender<MediaSignal>,
     players: &mut HashMap<String, PlayerState>,
     cache: &mut HashMap<String, MediaInfo>,
+    art: &ArtFetcher,
     sender: &async_channel::Sender<UiEvent>,
 ) -> zbus::Result<()> {
     if !name.starts_with(MPRIS_PREFIX) {
@@ -273,7 +329,7 @@ async fn apply_owner_change(
             signal_tx.clone(),
         );
         players.insert(name.to_string(), state);
-        refresh_player_cache(players, cache, name).await;
+        refresh_player_cache(players, cache, art, name).await;
         send_snapshot(sender, cache).await;
         schedule_metadata_fallback(cache, signal_tx.clone(), name);
     }
@@ -0,0 +1,81 @@
+//! Optional clock and date header at the top of the panel.
+
+use gtk::glib;
+use gtk::prelude::*;
+use tracing::warn;
+use unixnotis_core::PanelClockConfig;
+
+pub struct ClockHeader {
+    container: gtk::Box,
+    time: gtk::Label,
+    date: gtk::Label,
+    config: PanelClockConfig,
+}
+
+impl ClockHeader {
+    pub fn new(container: &gtk::Box, config: &PanelClockConfig) -> Self {
+        let root = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        root.add_css_class("unixnotis-clock");
+
+        let time = gtk::Label::new(None);
+        time.add_css_class("unixnotis-clock-time");
+        time.set_xalign(0.0);
+        let date = gtk::Label::new(None);
+        date.add_css_class("unixnotis-clock-date");
+        date.set_xalign(0.0);
+
+        root.append(&time);
+        root.append(&date);
+        container.append(&root);
+
+        let mut clock = Self {
+            container: container.clone(),
+            time,
+            date,
+            config: PanelClockConfig::default(),
+        };
+        clock.apply_config(config);
+        clock
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn apply_config(&mut self, config: &PanelClockConfig) {
+        self.config = config.clone();
+        // Hide the whole section so the panel spacing does not leave a gap.
+        self.container.set_visible(config.enabled);
+        self.date.set_visible(!config.date_format.trim().is_empty());
+        self.refresh();
+    }
+
+    /// Re-render both lines; labels are only touched when the text changes.
+    pub fn refresh(&self) {
+        if !self.config.enabled {
+            return;
+        }
+        let now = match glib::DateTime::now_local() {
+            Ok(now) => now,
+            Err(err) => {
+                warn!(?err, "clock refresh failed");
+                return;
+            }
+        };
+        set_formatted(&self.time, &now, &self.config.time_format);
+        if self.date.is_visible() {
+            set_formatted(&self.date, &now, &self.config.date_format);
+        }
+    }
+}
+
+fn set_formatted(label: &gtk::Label, now: &glib::DateTime, format: &str) {
+    // GLib rejects unknown conversions; show the raw format so the mistake is visible.
+    let text = now
+        .format(format)
+        .map(|text| text.to_string())
+        .unwrap_or_else(|_| format.to_string());
+    if label.text().as_str() != text {
+        label.set_text(&text);
+    }
+}
@@ -0,0 +1,66 @@
+//! Placeholder shown over the list while there are no notifications.
+
+use gtk::prelude::*;
+use unixnotis_core::i18n::tr;
+use unixnotis_core::PanelEmptyStateConfig;
+
+pub struct EmptyState {
+    container: gtk::Box,
+    icon: gtk::Image,
+    label: gtk::Label,
+    enabled: bool,
+    empty: bool,
+}
+
+impl EmptyState {
+    pub fn new(container: &gtk::Box, config: &PanelEmptyStateConfig) -> Self {
+        let root = gtk::Box::new(gtk::Orientation::Vertical, 8);
+        root.add_css_class("unixnotis-empty-state");
+
+        let icon = gtk::Image::new();
+        icon.add_css_class("unixnotis-empty-state-icon");
+        icon.set_pixel_size(48);
+        let label = gtk::Label::new(None);
+        label.add_css_class("unixnotis-empty-state-label");
+        label.set_wrap(true);
+        label.set_justify(gtk::Justification::Center);
+
+        root.append(&icon);
+        root.append(&label);
+        container.append(&root);
+
+        let mut state = Self {
+            container: container.clone(),
+            icon,
+            label,
+            enabled: false,
+            empty: true,
+        };
+        state.apply_config(config);
+        state
+    }
+
+    pub fn apply_config(&mut self, config: &PanelEmptyStateConfig) {
+        self.enabled = config.enabled;
+        let icon_name = config.icon.trim();
+        self.icon
+            .set_icon_name((!icon_name.is_empty()).then_some(icon_name));
+        self.icon.set_visible(!icon_name.is_empty());
+        let text = config.text.clone().unwrap_or_else(|| tr("list-empty"));
+        self.label.set_text(&text);
+        self.label.set_visible(!text.trim().is_empty());
+        self.sync_visibility();
+    }
+
+    /// Track whether the list currently has any notifications.
+    pub fn set_empty(&mut self, empty: bool) {
+        if self.empty != empty {
+            self.empty = empty;
+            self.sync_visibility();
+        }
+    }
+
+    fn sync_visibility(&self) {
+        self.container.set_visible(self.enabled && self.empty);
+    }
+}
@@ -1,7 +1,7 @@
 //! Hyprland IPC helpers for panel visibility and work area hints.
 
 use std::env;
-use std::io::{BufRead, BufReader, Read, Write};
+use std::io::{BufRead, BufReader};
 use std::os::unix::net::UnixStream;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -10,6 +10,8 @@ use std::thread;
 use serde_json::Value;
 use tracing::{debug, warn};
 use unixnotis_core::{util, Margins};
+use unixnotis_ui::hyprland::send_command;
+pub use unixnotis_ui::hyprland::{focused_monitor, is_available};
 
 use crate::dbus::UiEvent;
 
@@ -216,36 +218,63 @@ fn parse_reserved(value: &Value) -> Option<Margins> {
     None
 }
 
-fn send_command(command: &str) -> std::io::Result<String> {
-    // Hyprland exposes its IPC socket via XDG_RUNTIME_DIR + HYPRLAND_INSTANCE_SIGNATURE.
-    // If these env vars aren't present, we're not in a Hyprland session (or IPC isn't available).
-    let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE").unwrap_or_default();
-    let runtime_dir = env::var("XDG_RUNTIME_DIR").unwrap_or_default();
-    if signature.is_empty() || runtime_dir.is_empty() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Hyprland environment not available",
-        ));
+/// Focus the first window whose class matches `class` (case-insensitive, whole name).
+pub fn focus_window_class(class: &str) -> std::io::Result<()> {
+    let command = format!("dispatch focuswindow class:(?i)^{}$", escape_regex(class));
+    let response = send_command(&command)?;
+    if response.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(util::log_snippet(&response)))
     }
+}
+
+/// Active keymap name of the main keyboard, such as "English (US)".
+pub fn active_keymap() -> std::io::Result<Option<String>> {
+    let response = send_command("j/devices")?;
+    let value: Value = serde_json::from_str(&response)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    Ok(parse_active_keymap(&value))
+}
 
-    // ".socket.sock" is Hyprland's request/response command socket (not the event stream).
-    let socket_path = format!("{runtime_dir}/hypr/{signature}/.socket.sock");
-    let mut stream = UnixStream::connect(&socket_path)?;
+fn parse_active_keymap(devices: &Value) -> Option<String> {
+    let keyboards = devices.get("keyboards")?.as_array()?;
+    // Virtual keyboards and macro pads also report keymaps; the main keyboard is the one
+    // whose layout follows switchxkblayout.
+    let keyboard = keyboards
+        .iter()
+        .find(|keyboard| keyboard.get("main").and_then(Value::as_bool) == Some(true))
+        .or_else(|| keyboards.first())?;
+    keyboard
+        .get("active_keymap")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
 
-    // Hyprland expects newline-terminated commands on this socket.
-    let request = format!("{command}\n");
-    stream.write_all(request.as_bytes())?;
-    stream.flush()?; // Make sure the command is sent immediately.
+/// Switch the main keyboard to its next configured layout.
+pub fn switch_keyboard_layout() -> std::io::Result<()> {
+    let response = send_command("dispatch switchxkblayout current next")?;
+    if response.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(util::log_snippet(&response)))
+    }
+}
 
-    // Hyprland replies with a plain-text response; read it fully until EOF.
-    let mut response = String::new();
-    stream.read_to_string(&mut response)?;
-    Ok(response)
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if !ch.is_alphanumeric() && ch != '-' && ch != '_' {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_reserved;
+    use super::{parse_active_keymap, parse_reserved};
 
     #[test]
     fn parse_reserved_array_order() {
@@ -257,4 +286,16 @@ mod tests {
         assert_eq!(margins.bottom, 30);
         assert_eq!(margins.left, 40);
     }
+
+    #[test]
+    fn parse_active_keymap_prefers_main_keyboard() {
+        let value = serde_json::json!({
+            "keyboards": [
+                {"name": "ydotoold-virtual-device", "active_keymap": "English (US)", "main": false},
+                {"name": "at-translated-set-2-keyboard", "active_keymap": "German", "main": true}
+            ]
+        });
+        assert_eq!(parse_active_keymap(&value), Some("German".to_string()));
+        assert_eq!(parse_active_keymap(&serde_json::json!({"mice": []})), None);
+    }
 }
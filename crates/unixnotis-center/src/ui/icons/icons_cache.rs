@@ -35,6 +35,19 @@ pub(super) enum IconKey {
         size: i32,
         scale: i32,
     },
+    /// Large preview of an image-data hint (`path` empty) or an image file.
+    Preview {
+        path: String,
+        hash: u64,
+        height: i32,
+        scale: i32,
+    },
+    /// Preview of image-data a listing left out, fetched from the daemon by notification id.
+    HeldPreview {
+        id: u32,
+        height: i32,
+        scale: i32,
+    },
 }
 
 impl IconKey {
@@ -43,6 +56,9 @@ impl IconKey {
             IconKey::ImageData { size, scale, .. }
             | IconKey::Path { size, scale, .. }
             | IconKey::Name { size, scale, .. } => (*size, *scale),
+            IconKey::Preview { height, scale, .. } | IconKey::HeldPreview { height, scale, .. } => {
+                (*height, *scale)
+            }
         }
     }
 }
@@ -101,7 +117,7 @@ pub(super) fn icon_key_for_name(name: &str, size: i32, scale: i32) -> Option<Ico
     })
 }
 
-fn hash_image_data(data: &[u8]) -> u64 {
+pub(super) fn hash_image_data(data: &[u8]) -> u64 {
     // Hash helper for raw image blobs used as cache keys/dedup identifiers.
     // We avoid hashing the entire buffer (which could be large) by hashing:
     // - total length
@@ -128,14 +144,29 @@ fn hash_image_data(data: &[u8]) -> u64 {
     hasher.finish()
 }
 
-pub(super) fn set_image_key(image: &gtk::Image, key: IconKey) {
+pub(super) fn set_image_key(image: &impl IsA<gtk::Widget>, key: IconKey) {
     unsafe {
-        // SAFETY: gtk::Image is main-thread only; the quark/type pairing is stable.
+        // SAFETY: widgets are main-thread only; the quark/type pairing is stable.
         image.set_qdata(icon_key_quark(), key);
     }
 }
 
-pub(super) fn image_key_matches(image: &gtk::Image, key: &IconKey) -> bool {
+pub(super) fn image_key(image: &impl IsA<gtk::Widget>) -> Option<IconKey> {
+    unsafe {
+        image
+            .qdata::<IconKey>(icon_key_quark())
+            .map(|ptr| ptr.as_ref().clone())
+    }
+}
+
+pub(super) fn clear_image_key(image: &impl IsA<gtk::Widget>) {
+    unsafe {
+        // SAFETY: same quark/type pairing as set_image_key.
+        let _ = image.steal_qdata::<IconKey>(icon_key_quark());
+    }
+}
+
+pub(super) fn image_key_matches(image: &impl IsA<gtk::Widget>, key: &IconKey) -> bool {
     unsafe {
         image
             .qdata::<IconKey>(icon_key_quark())
@@ -247,6 +278,17 @@ impl IconCache {
         paintable
     }
 
+    pub(super) fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub(super) fn clear(&mut self) {
+        // Widgets keep their own Rc references, so visible icons survive the drop.
+        self.entries = HashMap::new();
+        self.order = VecDeque::new();
+        self.total_bytes = 0;
+    }
+
     fn promote(&mut self, key: &IconKey) {
         // Promote the key in the recency deque:
         // - find its current position
@@ -1,18 +1,19 @@
-//! Background decoding for raster icons.
+//! Background decoding for raster icons and image previews.
 //!
-//! Offloads image decoding and resizing to worker threads.
+//! Offloads image decoding and resizing to the shared decode pool.
 
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::thread;
 
-use crossbeam_channel as channel;
 use fast_image_resize as fir;
 use gtk::gdk;
 use gtk::gdk::Texture;
 use gtk::glib;
 use gtk::prelude::*;
+use tracing::instrument;
+use unixnotis_ui::decode_pool::{DecodePool, DecodePriority, DecodeToken};
+use unixnotis_ui::preview::{pack_rows, PreviewSource, MIN_PREVIEW_SOURCE};
 
 use super::icons_cache::IconKey;
 
@@ -21,7 +22,8 @@ const MAX_ICON_BYTES: u64 = 16 * 1024 * 1024;
 const MAX_ICON_DIMENSION: u32 = 2048;
 
 pub(super) struct IconWorker {
-    sender: channel::Sender<IconJob>,
+    pool: DecodePool,
+    update_tx: async_channel::Sender<IconUpdate>,
 }
 
 pub(super) struct IconUpdate {
@@ -41,88 +43,125 @@ pub(super) struct RasterImage {
     pub(super) stride: i32,
 }
 
-enum IconJob {
-    Decode {
+impl IconWorker {
+    pub(super) fn new(update_tx: async_channel::Sender<IconUpdate>, workers: usize) -> Self {
+        // Keep the pool small because decode is CPU-heavy and we don't want to starve GTK.
+        Self {
+            pool: DecodePool::new(workers),
+            update_tx,
+        }
+    }
+
+    pub(super) fn submit_decode(
+        &self,
         key: IconKey,
         path: PathBuf,
         size: i32,
         scale: i32,
-    },
-}
-
-impl IconWorker {
-    pub(super) fn new(update_tx: async_channel::Sender<IconUpdate>) -> Self {
-        // Unbounded job queue; UI thread submits decode work, workers consume.
-        let (sender, receiver) = channel::unbounded::<IconJob>();
-
-        // Keep worker count small (<=2) because decode is CPU-heavy and we don't want to starve GTK.
-        // available_parallelism() may fail in constrained environments, so default to 1.
-        let worker_count = thread::available_parallelism()
-            .map(|count| count.get().min(2))
-            .unwrap_or(1);
-
-        for _ in 0..worker_count {
-            let receiver = receiver.clone();
-            let update_tx = update_tx.clone();
-
-            thread::spawn(move || {
-                // Blocking worker loop: wait for decode jobs, run decode, report back to UI via update_tx.
-                for job in receiver.iter() {
-                    let IconJob::Decode {
-                        key,
-                        path,
-                        size,
-                        scale,
-                    } = job;
-
-                    // Decode off-thread; GTK objects should be created/applied on the main loop later.
-                    let result = decode_raster(&path, size, scale);
-
-                    // send_blocking is fine here (worker thread), avoids busy looping if UI is momentarily slow.
-                    let _ = update_tx.send_blocking(IconUpdate { key, result });
-                }
-            });
-        }
-
-        Self { sender }
+        token: DecodeToken,
+        priority: DecodePriority,
+    ) {
+        let update_tx = self.update_tx.clone();
+        self.pool.submit(priority, move || {
+            if !token.claim() {
+                // Cancelled after every row scrolled away, or already run from another lane.
+                return;
+            }
+            // Decode off-thread; GTK objects should be created/applied on the main loop later.
+            let result = decode_raster(&path, size, scale);
+            // send_blocking is fine here (worker thread), avoids busy looping if UI is momentarily slow.
+            let _ = update_tx.send_blocking(IconUpdate { key, result });
+        });
     }
 
-    pub(super) fn submit_decode(&self, key: IconKey, path: PathBuf, size: i32, scale: i32) {
-        // Best-effort enqueue; if the worker is shut down, dropping the job is acceptable.
-        let _ = self.sender.send(IconJob::Decode {
-            key,
-            path,
-            size,
-            scale,
+    /// Decode a preview scaled down to fit `max_width` x `max_height` device pixels.
+    pub(super) fn submit_preview(
+        &self,
+        key: IconKey,
+        source: PreviewSource,
+        max_width: u32,
+        max_height: u32,
+        token: DecodeToken,
+    ) {
+        let update_tx = self.update_tx.clone();
+        self.pool.submit(DecodePriority::Visible, move || {
+            if !token.claim() {
+                return;
+            }
+            let result = match decode_preview(source, max_width, max_height) {
+                Ok(image) => IconResult::Raster(image),
+                Err(err) => IconResult::Failed(err),
+            };
+            let _ = update_tx.send_blocking(IconUpdate { key, result });
         });
     }
 }
 
-fn decode_raster(path: &Path, size: i32, scale: i32) -> IconResult {
-    let metadata = match std::fs::metadata(path) {
-        Ok(metadata) => metadata,
-        Err(err) => return IconResult::Failed(err.to_string()),
+#[instrument(level = "debug", skip_all, fields(max_height = max_height))]
+fn decode_preview(
+    source: PreviewSource,
+    max_width: u32,
+    max_height: u32,
+) -> Result<RasterImage, String> {
+    let (bytes, width, height) = match source {
+        PreviewSource::Path(path) => {
+            let image =
+                image::load_from_memory(&read_icon_file(&path)?).map_err(|err| err.to_string())?;
+            let rgba = image.to_rgba8();
+            let (width, height) = rgba.dimensions();
+            (rgba.into_raw(), width, height)
+        }
+        PreviewSource::Pixels {
+            bytes,
+            width,
+            height,
+            rowstride,
+        } => (pack_rows(bytes, width, height, rowstride)?, width, height),
     };
+    if width.max(height) < MIN_PREVIEW_SOURCE as u32 {
+        return Err("image too small for a preview".to_string());
+    }
+    let (target_width, target_height) = fit_within(width, height, max_width, max_height);
+    resize_rgba(bytes, (width, height), (target_width, target_height))
+}
+
+/// Largest size with the source aspect ratio inside the box; never upscales.
+fn fit_within(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    let ratio = (f64::from(max_width) / f64::from(width))
+        .min(f64::from(max_height) / f64::from(height))
+        .min(1.0);
+    let scaled = |side: u32| ((f64::from(side) * ratio).round() as u32).max(1);
+    (scaled(width), scaled(height))
+}
+
+fn read_icon_file(path: &Path) -> Result<Vec<u8>, String> {
+    let metadata = std::fs::metadata(path).map_err(|err| err.to_string())?;
     if !metadata.is_file() {
-        return IconResult::Failed("icon path is not a regular file".to_string());
+        return Err("icon path is not a regular file".to_string());
     }
     if metadata.len() > MAX_ICON_BYTES {
-        return IconResult::Failed(format!("icon file too large ({} bytes)", metadata.len()));
+        return Err(format!("icon file too large ({} bytes)", metadata.len()));
     }
 
     // Read the file into memory with a hard cap to avoid unbounded allocations.
-    let file = match File::open(path) {
-        Ok(file) => file,
-        Err(err) => return IconResult::Failed(err.to_string()),
-    };
+    let file = File::open(path).map_err(|err| err.to_string())?;
     let mut bytes = Vec::with_capacity(metadata.len() as usize);
     let mut limited = file.take(MAX_ICON_BYTES + 1);
-    if let Err(err) = limited.read_to_end(&mut bytes) {
-        return IconResult::Failed(err.to_string());
-    }
+    limited
+        .read_to_end(&mut bytes)
+        .map_err(|err| err.to_string())?;
     if bytes.len() as u64 > MAX_ICON_BYTES {
-        return IconResult::Failed("icon file too large".to_string());
+        return Err("icon file too large".to_string());
     }
+    Ok(bytes)
+}
+
+#[instrument(level = "debug", skip_all, fields(size = size, scale = scale))]
+fn decode_raster(path: &Path, size: i32, scale: i32) -> IconResult {
+    let bytes = match read_icon_file(path) {
+        Ok(bytes) => bytes,
+        Err(err) => return IconResult::Failed(err),
+    };
 
     // Decode the image from the raw bytes. load_from_memory auto-detects the format.
     let image = match image::load_from_memory(&bytes) {
@@ -142,31 +181,38 @@ fn decode_raster(path: &Path, size: i32, scale: i32) -> IconResult {
     let rgba = image.to_rgba8();
     let width = rgba.width();
     let height = rgba.height();
+    match resize_rgba(rgba.into_raw(), (width, height), (target, target)) {
+        Ok(image) => IconResult::Raster(image),
+        Err(err) => IconResult::Failed(err),
+    }
+}
+
+fn resize_rgba(
+    bytes: Vec<u8>,
+    (width, height): (u32, u32),
+    (target_width, target_height): (u32, u32),
+) -> Result<RasterImage, String> {
     if width > i32::MAX as u32 || height > i32::MAX as u32 {
-        return IconResult::Failed("decoded icon exceeds supported dimensions".to_string());
+        return Err("decoded icon exceeds supported dimensions".to_string());
     }
-    let src =
-        match fir::images::Image::from_vec_u8(width, height, rgba.into_raw(), fir::PixelType::U8x4)
-        {
-            Ok(src) => src,
-            Err(err) => return IconResult::Failed(err.to_string()),
-        };
-    let mut dst = fir::images::Image::new(target, target, fir::PixelType::U8x4);
+    let src = fir::images::Image::from_vec_u8(width, height, bytes, fir::PixelType::U8x4)
+        .map_err(|err| err.to_string())?;
+    let mut dst = fir::images::Image::new(target_width, target_height, fir::PixelType::U8x4);
     let options = fir::ResizeOptions::new()
         .resize_alg(fir::ResizeAlg::Convolution(fir::FilterType::CatmullRom));
     let mut resizer = fir::Resizer::new();
-    if let Err(err) = resizer.resize(&src, &mut dst, Some(&options)) {
-        return IconResult::Failed(err.to_string());
-    }
+    resizer
+        .resize(&src, &mut dst, Some(&options))
+        .map_err(|err| err.to_string())?;
 
-    let width = target as i32;
-    let height = target as i32;
+    let width = target_width as i32;
+    let height = target_height as i32;
 
     // Bytes per row for RGBA8. saturating_mul avoids overflow if width is unexpectedly large.
     let stride = width.saturating_mul(4);
 
     // into_vec consumes the resize buffer and returns the owned RGBA bytes (no extra copy).
-    IconResult::Raster(RasterImage {
+    Ok(RasterImage {
         bytes: dst.into_vec(),
         width,
         height,
@@ -188,3 +234,15 @@ pub(super) fn texture_from_raster(image: &RasterImage) -> Texture {
     )
     .upcast::<Texture>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::fit_within;
+
+    #[test]
+    fn previews_keep_aspect_and_never_upscale() {
+        assert_eq!(fit_within(1920, 1080, 320, 160), (284, 160));
+        assert_eq!(fit_within(600, 2000, 320, 160), (48, 160));
+        assert_eq!(fit_within(200, 100, 320, 160), (200, 100));
+    }
+}
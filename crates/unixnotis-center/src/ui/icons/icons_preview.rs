@@ -0,0 +1,273 @@
+//! Large previews for notifications that carry screenshots or photos.
+//!
+//! Previews share the icon cache and decode pool. They decode no larger than the configured
+//! box at the output scale, so a full-size screenshot never reaches GTK.
+
+use std::path::PathBuf;
+
+use gtk::glib;
+use gtk::prelude::*;
+use tracing::debug;
+use unixnotis_core::{NotificationImage, NotificationView};
+use unixnotis_ui::decode_pool::DecodeToken;
+use unixnotis_ui::preview::{PreviewSource, MIN_PREVIEW_SOURCE};
+use unixnotis_ui::scaled_texture::ScaledTexture;
+
+use super::icons_cache::{
+    clear_image_key, hash_image_data, image_key, image_key_matches, set_image_key, CachedPaintable,
+    IconKey,
+};
+use super::icons_decode::{texture_from_raster, IconResult, IconUpdate};
+use super::icons_sources::{file_path_from_hint, is_svg_path};
+use super::{IconResolver, IconResolverInner};
+
+// Pictures waiting on one preview decode.
+pub(super) struct InflightPreview {
+    waiters: Vec<glib::WeakRef<gtk::Picture>>,
+    token: DecodeToken,
+}
+
+impl IconResolver {
+    /// Show a preview of the notification image in `picture`, or hide it when there is none.
+    pub fn apply_preview(
+        &self,
+        picture: &gtk::Picture,
+        notification: &NotificationView,
+        max_height: i32,
+        scale: i32,
+    ) {
+        self.inner.apply_preview(
+            picture,
+            notification.id,
+            &notification.image,
+            max_height,
+            scale,
+        );
+    }
+
+    /// Hide a preview and drop its pending decode, e.g. when its row stacks or unbinds.
+    pub fn release_preview(&self, picture: &gtk::Picture) {
+        self.inner.hide_preview(picture);
+    }
+
+    /// Set how to request the pixels of a listed notification; the answer goes to
+    /// `image_fetched`.
+    pub fn set_image_fetcher(&self, fetch: impl Fn(u32) + 'static) {
+        self.inner.fetch_image.replace(Some(Box::new(fetch)));
+    }
+
+    /// Full image for notification `id`, or `None` when the daemon no longer has it.
+    pub fn image_fetched(&self, id: u32, image: Option<NotificationImage>) {
+        self.inner.image_fetched(id, image);
+    }
+}
+
+impl IconResolverInner {
+    fn apply_preview(
+        &self,
+        picture: &gtk::Picture,
+        id: u32,
+        image: &NotificationImage,
+        max_height: i32,
+        scale: i32,
+    ) {
+        let Some(key) = preview_key(id, image, max_height, scale) else {
+            self.hide_preview(picture);
+            return;
+        };
+        if image_key(picture).as_ref() == Some(&key) {
+            // Already shown, pending, or known to have no preview.
+            return;
+        }
+        self.detach_preview(picture, Some(&key));
+        set_image_key(picture, key.clone());
+        picture.set_paintable(None::<&gtk::gdk::Paintable>);
+        picture.set_visible(false);
+        if let Some(paintable) = self.cache.borrow_mut().get(&key) {
+            show_preview(picture, &paintable, scale);
+            return;
+        }
+        if self.missing_names.borrow_mut().contains(&key) {
+            return;
+        }
+
+        let mut previews = self.previews.borrow_mut();
+        if let Some(pending) = previews.get_mut(&key) {
+            pending.waiters.push(picture.downgrade());
+            return;
+        }
+        let source = match &key {
+            // The decode is queued once the pixels arrive.
+            IconKey::HeldPreview { .. } => None,
+            _ => match preview_source(image, &key) {
+                Some(source) => Some(source),
+                None => return,
+            },
+        };
+        let token = DecodeToken::default();
+        previews.insert(
+            key.clone(),
+            InflightPreview {
+                waiters: vec![picture.downgrade()],
+                token: token.clone(),
+            },
+        );
+        let Some(source) = source else {
+            drop(previews);
+            if let Some(fetch) = self.fetch_image.borrow().as_ref() {
+                fetch(id);
+            }
+            return;
+        };
+        let scale = scale.max(1) as u32;
+        let height = max_height.max(1) as u32 * scale;
+        self.worker
+            .submit_preview(key, source, height * 2, height, token);
+    }
+
+    fn hide_preview(&self, picture: &gtk::Picture) {
+        self.detach_preview(picture, None);
+        clear_image_key(picture);
+        picture.set_paintable(None::<&gtk::gdk::Paintable>);
+        picture.set_visible(false);
+    }
+
+    fn detach_preview(&self, picture: &gtk::Picture, keep: Option<&IconKey>) {
+        let Some(key) = image_key(picture) else {
+            return;
+        };
+        if keep == Some(&key) {
+            return;
+        }
+        let mut previews = self.previews.borrow_mut();
+        let Some(pending) = previews.get_mut(&key) else {
+            return;
+        };
+        pending
+            .waiters
+            .retain(|waiter| waiter.upgrade().is_some_and(|waiting| &waiting != picture));
+        if pending.waiters.is_empty() {
+            pending.token.cancel();
+            previews.remove(&key);
+        }
+    }
+
+    fn image_fetched(&self, id: u32, image: Option<NotificationImage>) {
+        let source = image
+            .filter(|image| image.has_image_data)
+            .and_then(|image| PreviewSource::from_image_data(&image.image_data));
+        let mut previews = self.previews.borrow_mut();
+        let keys: Vec<IconKey> = previews
+            .keys()
+            .filter(|key| matches!(key, IconKey::HeldPreview { id: held, .. } if *held == id))
+            .cloned()
+            .collect();
+        for key in keys {
+            let IconKey::HeldPreview { height, scale, .. } = key else {
+                continue;
+            };
+            let Some(source) = &source else {
+                // Gone from the daemon or too small after all; stop asking.
+                previews.remove(&key);
+                self.missing_names.borrow_mut().insert(key);
+                continue;
+            };
+            let Some(token) = previews.get(&key).map(|pending| pending.token.clone()) else {
+                continue;
+            };
+            let height = height.max(1) as u32 * scale.max(1) as u32;
+            self.worker
+                .submit_preview(key, source.clone(), height * 2, height, token);
+        }
+    }
+
+    pub(super) fn finish_preview(&self, update: IconUpdate) {
+        let waiters = self
+            .previews
+            .borrow_mut()
+            .remove(&update.key)
+            .map(|pending| pending.waiters)
+            .unwrap_or_default();
+        let paintable = match update.result {
+            IconResult::Raster(image) => self.cache.borrow_mut().insert(
+                update.key.clone(),
+                CachedPaintable::from_texture(texture_from_raster(&image)),
+            ),
+            IconResult::Failed(err) => {
+                debug!(?err, "preview decode failed");
+                // Remember the miss so rebinding the row does not decode it again.
+                self.missing_names.borrow_mut().insert(update.key);
+                return;
+            }
+        };
+        let (IconKey::Preview { scale, .. } | IconKey::HeldPreview { scale, .. }) = update.key
+        else {
+            return;
+        };
+        for waiter in waiters {
+            let Some(picture) = waiter.upgrade() else {
+                continue;
+            };
+            if image_key_matches(&picture, &update.key) {
+                show_preview(&picture, &paintable, scale);
+            }
+        }
+    }
+}
+
+fn show_preview(picture: &gtk::Picture, paintable: &CachedPaintable, scale: i32) {
+    let Ok(texture) = paintable
+        .paintable()
+        .clone()
+        .downcast::<gtk::gdk::Texture>()
+    else {
+        return;
+    };
+    picture.set_paintable(Some(&ScaledTexture::new(texture, scale)));
+    picture.set_visible(true);
+}
+
+// Image-data wins over a file path, matching the icon order.
+fn preview_key(id: u32, image: &NotificationImage, max_height: i32, scale: i32) -> Option<IconKey> {
+    if image.has_image_data {
+        let data = &image.image_data;
+        if data.width.max(data.height) < MIN_PREVIEW_SOURCE {
+            return None;
+        }
+        if data.data.is_empty() {
+            // Listings leave the pixels with the daemon.
+            return Some(IconKey::HeldPreview {
+                id,
+                height: max_height,
+                scale,
+            });
+        }
+        return Some(IconKey::Preview {
+            path: String::new(),
+            hash: hash_image_data(&data.data),
+            height: max_height,
+            scale,
+        });
+    }
+    let path = file_path_from_hint(&image.image_path)?;
+    // Vector images are icons, not pictures worth a preview.
+    if is_svg_path(&path) {
+        return None;
+    }
+    Some(IconKey::Preview {
+        path: path.to_string_lossy().into_owned(),
+        hash: 0,
+        height: max_height,
+        scale,
+    })
+}
+
+fn preview_source(image: &NotificationImage, key: &IconKey) -> Option<PreviewSource> {
+    let IconKey::Preview { path, .. } = key else {
+        return None;
+    };
+    if !path.is_empty() {
+        return Some(PreviewSource::Path(PathBuf::from(path)));
+    }
+    PreviewSource::from_image_data(&image.image_data)
+}
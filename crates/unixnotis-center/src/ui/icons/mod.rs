@@ -5,6 +5,7 @@
 
 mod icons_cache;
 mod icons_decode;
+mod icons_preview;
 mod icons_sources;
 
 use std::cell::RefCell;
@@ -17,15 +18,18 @@ use gtk::glib;
 use gtk::prelude::*;
 use tracing::debug;
 use unixnotis_core::NotificationView;
+use unixnotis_ui::decode_pool::{DecodePriority, DecodeToken};
+use unixnotis_ui::desktop_icons::DesktopIconIndex;
 
 use icons_cache::{
-    icon_key_for_image, icon_key_for_name, icon_key_for_path, image_key_matches, set_image_key,
-    CachedPaintable, IconCache, IconKey,
+    icon_key_for_image, icon_key_for_name, icon_key_for_path, image_key, image_key_matches,
+    set_image_key, CachedPaintable, IconCache, IconKey,
 };
 use icons_decode::{texture_from_raster, IconResult, IconUpdate, IconWorker};
+use icons_preview::InflightPreview;
 use icons_sources::{
     collect_icon_candidates, file_path_from_hint, image_data_texture, is_svg_path,
-    resolve_icon_source, resolve_path_texture, DesktopIconIndex, IconSource,
+    resolve_icon_source, resolve_path_texture, IconSource,
 };
 
 /// Resolves notification icons using image hints, themed icons, and desktop metadata.
@@ -34,13 +38,15 @@ pub struct IconResolver {
 }
 
 impl IconResolver {
-    pub fn new() -> Self {
+    pub fn new(decode_workers: usize) -> Self {
         let (update_tx, update_rx) = async_channel::unbounded::<IconUpdate>();
-        let worker = IconWorker::new(update_tx);
+        let worker = IconWorker::new(update_tx, decode_workers);
         let inner = Rc::new(IconResolverInner {
             desktop_index: DesktopIconIndex::new(),
             cache: RefCell::new(IconCache::new(256)),
             inflight: RefCell::new(HashMap::new()),
+            previews: RefCell::new(HashMap::new()),
+            fetch_image: RefCell::new(None),
             missing_names: RefCell::new(MissingIconCache::new(512)),
             worker,
         });
@@ -63,12 +69,44 @@ impl IconResolver {
     ) {
         self.inner.apply_icon(image, notification, size, scale);
     }
+
+    /// Queue a low-priority decode so the icon is cached before its row is first shown.
+    pub fn prefetch(&self, notification: &NotificationView, size: i32, scale: i32) {
+        self.inner.prefetch(notification, size, scale);
+    }
+
+    /// Detach an image from pending decodes when its row leaves the viewport.
+    pub fn release_image(&self, image: &gtk::Image) {
+        self.inner.detach_waiter(image, None);
+    }
+
+    /// Cache sizes for memory reports.
+    pub fn memory_estimates(&self) -> Vec<(String, u64)> {
+        let bytes = self.inner.cache.borrow().total_bytes();
+        vec![("icon_cache".to_string(), bytes as u64)]
+    }
+
+    /// Drop decoded icons and cached misses; they are rebuilt on the next bind.
+    pub fn trim(&self) {
+        self.inner.cache.borrow_mut().clear();
+        self.inner.missing_names.borrow_mut().clear();
+    }
+}
+
+// Rows waiting on one decode; the token cancels it once every waiter is gone.
+struct InflightDecode {
+    waiters: Vec<glib::WeakRef<gtk::Image>>,
+    token: DecodeToken,
+    priority: DecodePriority,
 }
 
 struct IconResolverInner {
     desktop_index: DesktopIconIndex,
     cache: RefCell<IconCache>,
-    inflight: RefCell<HashMap<IconKey, Vec<glib::WeakRef<gtk::Image>>>>,
+    inflight: RefCell<HashMap<IconKey, InflightDecode>>,
+    previews: RefCell<HashMap<IconKey, InflightPreview>>,
+    // Asks the daemon for pixels a listing left out; answered through `image_fetched`.
+    fetch_image: RefCell<Option<Box<dyn Fn(u32)>>>,
     missing_names: RefCell<MissingIconCache>,
     worker: IconWorker,
 }
@@ -81,7 +119,13 @@ impl IconResolverInner {
         size: i32,
         scale: i32,
     ) {
-        if let Some(resolved) = self.resolve_icon(notification, size, scale) {
+        let resolved = self.resolve_icon(notification, size, scale);
+        let next_key = resolved.as_ref().map(|resolved| match resolved {
+            IconResolution::Ready { key, .. } | IconResolution::Async { key, .. } => key,
+        });
+        // A rebound row no longer needs the decode it was waiting on.
+        self.detach_waiter(image, next_key);
+        if let Some(resolved) = resolved {
             match resolved {
                 IconResolution::Ready { key, paintable } => {
                     set_image_key(image, key);
@@ -227,24 +271,112 @@ impl IconResolverInner {
 
     fn enqueue(&self, request: IconDecodeRequest, image: &gtk::Image) {
         let mut inflight = self.inflight.borrow_mut();
-        if let Some(waiters) = inflight.get_mut(&request.key) {
-            waiters.push(image.downgrade());
+        if let Some(pending) = inflight.get_mut(&request.key) {
+            if !pending
+                .waiters
+                .iter()
+                .any(|waiter| waiter.upgrade().as_ref() == Some(image))
+            {
+                pending.waiters.push(image.downgrade());
+            }
+            if pending.priority == DecodePriority::Prefetch {
+                // A prefetched icon is now on screen; queue it again in the visible lane and
+                // let whichever copy runs first claim the shared token.
+                pending.priority = DecodePriority::Visible;
+                self.worker.submit_decode(
+                    request.key,
+                    request.path,
+                    request.size,
+                    request.scale,
+                    pending.token.clone(),
+                    DecodePriority::Visible,
+                );
+            }
             return;
         }
-        inflight.insert(request.key.clone(), vec![image.downgrade()]);
+        let token = DecodeToken::default();
+        inflight.insert(
+            request.key.clone(),
+            InflightDecode {
+                waiters: vec![image.downgrade()],
+                token: token.clone(),
+                priority: DecodePriority::Visible,
+            },
+        );
         self.worker.submit_decode(
+            request.key,
+            request.path,
+            request.size,
+            request.scale,
+            token,
+            DecodePriority::Visible,
+        );
+    }
+
+    fn prefetch(&self, notification: &NotificationView, size: i32, scale: i32) {
+        let Some(IconResolution::Async { request, .. }) =
+            self.resolve_icon(notification, size, scale)
+        else {
+            // Ready icons are cached already; unresolved ones have nothing to decode.
+            return;
+        };
+        let mut inflight = self.inflight.borrow_mut();
+        if inflight.contains_key(&request.key) {
+            return;
+        }
+        let token = DecodeToken::default();
+        inflight.insert(
             request.key.clone(),
-            request.path.clone(),
+            InflightDecode {
+                waiters: Vec::new(),
+                token: token.clone(),
+                priority: DecodePriority::Prefetch,
+            },
+        );
+        self.worker.submit_decode(
+            request.key,
+            request.path,
             request.size,
             request.scale,
+            token,
+            DecodePriority::Prefetch,
         );
     }
 
+    fn detach_waiter(&self, image: &gtk::Image, keep: Option<&IconKey>) {
+        let Some(key) = image_key(image) else {
+            return;
+        };
+        if keep == Some(&key) {
+            return;
+        }
+        let mut inflight = self.inflight.borrow_mut();
+        let Some(pending) = inflight.get_mut(&key) else {
+            return;
+        };
+        pending
+            .waiters
+            .retain(|waiter| waiter.upgrade().is_some_and(|waiting| &waiting != image));
+        if pending.waiters.is_empty() {
+            // Nobody on screen wants this icon anymore; let the worker skip it.
+            pending.token.cancel();
+            inflight.remove(&key);
+        }
+    }
+
     fn handle_update(&self, update: IconUpdate) {
+        if matches!(
+            update.key,
+            IconKey::Preview { .. } | IconKey::HeldPreview { .. }
+        ) {
+            self.finish_preview(update);
+            return;
+        }
         let waiters = self
             .inflight
             .borrow_mut()
             .remove(&update.key)
+            .map(|pending| pending.waiters)
             .unwrap_or_default();
 
         let paintable = match update.result {
@@ -327,6 +459,11 @@ impl MissingIconCache {
         }
     }
 
+    fn clear(&mut self) {
+        self.order = VecDeque::new();
+        self.set = HashSet::new();
+    }
+
     fn purge_expired(&mut self) {
         let ttl = Duration::from_secs(30);
         let now = Instant::now();
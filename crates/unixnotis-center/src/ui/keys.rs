@@ -0,0 +1,156 @@
+//! Panel key bindings from `[panel.keys]`.
+
+use std::cell::{Cell, RefCell};
+
+use gtk::gdk;
+use gtk::prelude::*;
+use tracing::warn;
+use unixnotis_core::PanelKeysConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelKeyAction {
+    Next,
+    Previous,
+    First,
+    Last,
+    Activate,
+    Dismiss,
+    Close,
+}
+
+/// Bound keys, shared between the panel's key controller and the UI state.
+pub struct PanelKeymap {
+    bindings: RefCell<Vec<(gdk::Key, PanelKeyAction)>>,
+    // Enter and Delete only act on the list while a row has the cursor; otherwise they go
+    // to the focused button as usual.
+    cursor_shown: Cell<bool>,
+}
+
+impl PanelKeymap {
+    pub fn new(config: &PanelKeysConfig) -> Self {
+        let keymap = Self {
+            bindings: RefCell::new(Vec::new()),
+            cursor_shown: Cell::new(false),
+        };
+        keymap.apply_config(config);
+        keymap
+    }
+
+    pub fn apply_config(&self, config: &PanelKeysConfig) {
+        let mut bindings = Vec::new();
+        for (names, action) in [
+            (&config.next, PanelKeyAction::Next),
+            (&config.previous, PanelKeyAction::Previous),
+            (&config.first, PanelKeyAction::First),
+            (&config.last, PanelKeyAction::Last),
+            (&config.activate, PanelKeyAction::Activate),
+            (&config.dismiss, PanelKeyAction::Dismiss),
+            (&config.close, PanelKeyAction::Close),
+        ] {
+            for name in names {
+                match gdk::Key::from_name(name.trim()) {
+                    Some(key) => bindings.push((key, action)),
+                    None => warn!(key = %name, ?action, "ignoring unknown key name in panel.keys"),
+                }
+            }
+        }
+        self.bindings.replace(bindings);
+    }
+
+    pub fn set_cursor_shown(&self, shown: bool) {
+        self.cursor_shown.set(shown);
+    }
+
+    /// Action bound to `key`, or `None` when the key should reach the focused widget.
+    pub fn action_for(
+        &self,
+        key: gdk::Key,
+        modifiers: gdk::ModifierType,
+        focus: Option<gtk::Widget>,
+    ) -> Option<PanelKeyAction> {
+        // Chords belong to the compositor and to widgets' own shortcuts.
+        let chords = gdk::ModifierType::CONTROL_MASK
+            | gdk::ModifierType::ALT_MASK
+            | gdk::ModifierType::SUPER_MASK;
+        if modifiers.intersects(chords) {
+            return None;
+        }
+        let action = self
+            .bindings
+            .borrow()
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)?;
+        // Text fields and sliders keep their keys while focused; Escape still closes.
+        let typing =
+            focus.is_some_and(|widget| widget.is::<gtk::Editable>() || widget.is::<gtk::Range>());
+        let printable = key.to_unicode().is_some_and(|ch| !ch.is_control());
+        filter_action(action, typing, printable, self.cursor_shown.get())
+    }
+}
+
+/// `action` unless the focused widget or a missing cursor should have the key instead.
+fn filter_action(
+    action: PanelKeyAction,
+    typing: bool,
+    printable: bool,
+    cursor_shown: bool,
+) -> Option<PanelKeyAction> {
+    match action {
+        _ if typing && (action != PanelKeyAction::Close || printable) => None,
+        PanelKeyAction::Activate | PanelKeyAction::Dismiss if !cursor_shown => None,
+        _ => Some(action),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typing_fields_keep_their_keys_except_escape() {
+        use PanelKeyAction::*;
+        assert_eq!(filter_action(Next, true, true, true), None);
+        assert_eq!(filter_action(Next, true, false, true), None);
+        assert_eq!(filter_action(Dismiss, true, false, true), None);
+        // Escape still closes from a text field; a printable close key such as `q` is typed.
+        assert_eq!(filter_action(Close, true, false, false), Some(Close));
+        assert_eq!(filter_action(Close, true, true, false), None);
+        assert_eq!(filter_action(Next, false, true, false), Some(Next));
+    }
+
+    #[test]
+    fn enter_and_delete_need_a_cursor() {
+        use PanelKeyAction::*;
+        for action in [Activate, Dismiss] {
+            assert_eq!(filter_action(action, false, false, false), None);
+            assert_eq!(filter_action(action, false, false, true), Some(action));
+        }
+        assert_eq!(filter_action(Previous, false, false, false), Some(Previous));
+    }
+
+    #[test]
+    fn bound_keys_map_to_actions_and_chords_pass_through() {
+        let keymap = PanelKeymap::new(&PanelKeysConfig::default());
+        let none = gdk::ModifierType::empty();
+        assert_eq!(
+            keymap.action_for(gdk::Key::j, none, None),
+            Some(PanelKeyAction::Next)
+        );
+        assert_eq!(
+            keymap.action_for(gdk::Key::G, gdk::ModifierType::SHIFT_MASK, None),
+            Some(PanelKeyAction::Last)
+        );
+        assert_eq!(
+            keymap.action_for(gdk::Key::j, gdk::ModifierType::CONTROL_MASK, None),
+            None
+        );
+        assert_eq!(keymap.action_for(gdk::Key::x, none, None), None);
+        assert_eq!(keymap.action_for(gdk::Key::Delete, none, None), None);
+        keymap.set_cursor_shown(true);
+        assert_eq!(
+            keymap.action_for(gdk::Key::Delete, none, None),
+            Some(PanelKeyAction::Dismiss)
+        );
+    }
+}
@@ -14,7 +14,7 @@ impl NotificationList {
         key: &Rc<str>,
         ids: &[u32],
     ) -> (Vec<RowItem>, Vec<RowKey>) {
-        let expanded = self.group_expanded.get(key).copied().unwrap_or(false);
+        let expanded = self.is_group_expanded(key);
         let Some(first_entry) = ids.first().and_then(|id| self.entries.get(id)) else {
             return (Vec::new(), Vec::new());
         };
@@ -47,8 +47,9 @@ impl NotificationList {
             let Some(entry) = self.entries.get(id) else {
                 continue;
             };
+            // Rows carry the block key so pinned critical rows can be styled by section.
             entry.item.update(RowData::notification(
-                entry.app_key.clone(),
+                key.clone(),
                 entry.view.clone(),
                 stacked,
                 entry.is_active,
@@ -78,7 +79,7 @@ impl NotificationList {
     }
 
     pub(super) fn group_block_len(&self, key: &Rc<str>, ids: &[u32]) -> usize {
-        let expanded = self.group_expanded.get(key).copied().unwrap_or(false);
+        let expanded = self.is_group_expanded(key);
         let mut len = 1; // header
         if expanded {
             len += ids.len();
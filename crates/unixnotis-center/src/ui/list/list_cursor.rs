@@ -0,0 +1,171 @@
+//! Keyboard cursor over the list rows.
+//!
+//! The list uses `NoSelection`, so the cursor is kept here rather than by GTK. It remembers the
+//! row key and position, marks the bound row with the `cursor` class, and scrolls it into view.
+//! Stack ghosts are skipped. When the row under the cursor goes away, for example after a
+//! dismiss, the cursor moves to the row that took its place.
+
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use tracing::debug;
+use unixnotis_core::NotificationView;
+
+use super::list_scroll::row_key_for_widget;
+use super::{NotificationList, RowKey};
+
+const CURSOR_CLASS: &str = "cursor";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMove {
+    Next,
+    Previous,
+    First,
+    Last,
+}
+
+/// The row under the cursor, for the panel to act on.
+pub enum CursorTarget {
+    Group(String),
+    Notification(Rc<NotificationView>),
+}
+
+#[derive(Clone)]
+pub(super) struct Cursor {
+    pub(super) key: RowKey,
+    position: usize,
+}
+
+impl NotificationList {
+    pub fn move_cursor(&mut self, step: CursorMove) {
+        // Keys must match what is on screen before positions mean anything.
+        self.flush_rebuild();
+        let current = self
+            .cursor
+            .borrow()
+            .as_ref()
+            .and_then(|cursor| self.current_keys.iter().position(|key| *key == cursor.key));
+        let target = step_target(&self.current_keys, current, step);
+        self.set_cursor(target);
+    }
+
+    pub fn clear_cursor(&mut self) {
+        self.set_cursor(None);
+    }
+
+    pub fn has_cursor(&self) -> bool {
+        self.cursor.borrow().is_some()
+    }
+
+    pub fn cursor_target(&self) -> Option<CursorTarget> {
+        match &self.cursor.borrow().as_ref()?.key {
+            RowKey::GroupHeader { group } => Some(CursorTarget::Group(group.to_string())),
+            RowKey::Notification { id } => self
+                .entries
+                .get(id)
+                .map(|entry| CursorTarget::Notification(entry.view.clone())),
+            RowKey::Ghost { .. } => None,
+        }
+    }
+
+    /// Keep the cursor on a row after a rebuild moved or removed the one it was on.
+    pub(super) fn sync_cursor(&mut self) {
+        let Some(cursor) = self.cursor.borrow().clone() else {
+            return;
+        };
+        if let Some(position) = self.current_keys.iter().position(|key| *key == cursor.key) {
+            self.cursor.replace(Some(Cursor {
+                key: cursor.key,
+                position,
+            }));
+            return;
+        }
+        let len = self.current_keys.len();
+        let start = cursor.position.min(len);
+        let navigable = |index: &usize| !matches!(self.current_keys[*index], RowKey::Ghost { .. });
+        let target = (start..len)
+            .find(navigable)
+            .or_else(|| (0..start).rev().find(navigable));
+        self.set_cursor(target);
+    }
+
+    fn set_cursor(&mut self, position: Option<usize>) {
+        let cursor = position.and_then(|position| {
+            self.current_keys.get(position).map(|key| Cursor {
+                key: key.clone(),
+                position,
+            })
+        });
+        let key = cursor.as_ref().map(|cursor| cursor.key.clone());
+        self.cursor.replace(cursor);
+        // Rows bound later check the cursor themselves; these are the ones on screen now.
+        let mut child = self.list_view.first_child();
+        while let Some(widget) = child {
+            child = widget.next_sibling();
+            let Some(root) = widget.first_child() else {
+                continue;
+            };
+            let on_cursor = key.is_some() && row_key_for_widget(&widget) == key;
+            set_cursor_class(&root, on_cursor);
+        }
+        let Some(position) = position else {
+            return;
+        };
+        debug!(position, "list cursor moved");
+        let position = position as u32;
+        if let Err(err) = self
+            .list_view
+            .activate_action("list.scroll-to-item", Some(&position.to_variant()))
+        {
+            debug!(?err, "failed to scroll to cursor");
+        }
+    }
+}
+
+/// Row the cursor lands on after `step` from `current`; stack ghosts are skipped and the ends
+/// of the list hold the cursor in place.
+fn step_target(keys: &[RowKey], current: Option<usize>, step: CursorMove) -> Option<usize> {
+    let len = keys.len();
+    let navigable = |index: &usize| !matches!(keys[*index], RowKey::Ghost { .. });
+    match (step, current) {
+        (CursorMove::First, _) | (CursorMove::Next, None) => (0..len).find(navigable),
+        (CursorMove::Last, _) | (CursorMove::Previous, None) => (0..len).rev().find(navigable),
+        (CursorMove::Next, Some(index)) => (index + 1..len).find(navigable).or(Some(index)),
+        (CursorMove::Previous, Some(index)) => (0..index).rev().find(navigable).or(Some(index)),
+    }
+}
+
+pub(super) fn set_cursor_class(root: &impl IsA<gtk::Widget>, on_cursor: bool) {
+    if on_cursor {
+        root.add_css_class(CURSOR_CLASS);
+    } else {
+        root.remove_css_class(CURSOR_CLASS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_skip_ghosts_and_stop_at_the_ends() {
+        let group: Rc<str> = Rc::from("chat");
+        let keys = [
+            RowKey::GroupHeader {
+                group: group.clone(),
+            },
+            RowKey::Notification { id: 1 },
+            RowKey::Ghost { group, depth: 1 },
+            RowKey::Notification { id: 2 },
+        ];
+        assert_eq!(step_target(&keys, None, CursorMove::Next), Some(0));
+        assert_eq!(step_target(&keys, None, CursorMove::Previous), Some(3));
+        assert_eq!(step_target(&keys, Some(1), CursorMove::Next), Some(3));
+        assert_eq!(step_target(&keys, Some(3), CursorMove::Previous), Some(1));
+        assert_eq!(step_target(&keys, Some(3), CursorMove::Next), Some(3));
+        assert_eq!(step_target(&keys, Some(0), CursorMove::Previous), Some(0));
+        assert_eq!(step_target(&keys, Some(1), CursorMove::Last), Some(3));
+        assert_eq!(step_target(&keys, Some(3), CursorMove::First), Some(0));
+        assert_eq!(step_target(&[], None, CursorMove::Next), None);
+    }
+}
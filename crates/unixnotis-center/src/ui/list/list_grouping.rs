@@ -1,10 +1,35 @@
 //! Grouping key normalization and list consistency helpers.
 
 use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::rc::Rc;
 
+use unixnotis_core::{PanelSortOrder, Urgency};
+
 use super::NotificationList;
 
+/// Reserved key for the pinned critical section.
+///
+/// The leading control character is stripped by key normalization, so no app group can collide.
+pub(super) const CRITICAL_GROUP_KEY: &str = "\u{1}critical";
+
+/// Reserved key for notifications pinned from the panel.
+pub(super) const PINNED_GROUP_KEY: &str = "\u{1}pinned";
+
+pub(super) fn is_critical_section(key: &str) -> bool {
+    key == CRITICAL_GROUP_KEY
+}
+
+pub(super) fn is_pinned_section(key: &str) -> bool {
+    key == PINNED_GROUP_KEY
+}
+
+/// Sections above the app groups, which cannot be collapsed.
+pub(super) fn is_fixed_section(key: &str) -> bool {
+    is_critical_section(key) || is_pinned_section(key)
+}
+
 impl NotificationList {
     pub(super) fn intern_key(&mut self, key: &str) -> Rc<str> {
         let normalized = self.normalize_group_key(key);
@@ -50,6 +75,91 @@ impl NotificationList {
         Cow::Borrowed(trimmed)
     }
 
+    pub(super) fn collect_groups(
+        &mut self,
+        grouped: &mut HashMap<Rc<str>, Vec<u32>>,
+        group_order: &mut Vec<Rc<str>>,
+    ) {
+        let mut ids = std::mem::take(&mut self.ids_scratch);
+        ids.clear();
+        // Active rows always lead history so in-flight notifications stay near the top.
+        if self.sort_order == PanelSortOrder::OldestFirst {
+            ids.extend(self.active_order.iter().rev());
+            ids.extend(self.history_order.iter().rev());
+        } else {
+            ids.extend(self.active_order.iter());
+            ids.extend(self.history_order.iter());
+        }
+        if self.sort_order == PanelSortOrder::Urgency {
+            // Stable sort keeps newest-first ordering within each urgency level.
+            ids.sort_by_key(|id| {
+                Reverse(
+                    self.entries
+                        .get(id)
+                        .map(|entry| entry.view.urgency)
+                        .unwrap_or(0),
+                )
+            });
+        }
+
+        let mut critical = Vec::new();
+        let mut pinned = Vec::new();
+        for id in &ids {
+            let Some(entry) = self.entries.get(id) else {
+                continue;
+            };
+            if self.sticky_critical
+                && entry.is_active
+                && entry.view.urgency == Urgency::Critical as u8
+            {
+                critical.push(*id);
+                continue;
+            }
+            if entry.view.pinned {
+                pinned.push(*id);
+                continue;
+            }
+            let key = entry.app_key.clone();
+            let bucket = grouped.entry(key.clone()).or_insert_with(|| {
+                group_order.push(key.clone());
+                Vec::new()
+            });
+            bucket.push(*id);
+        }
+        if self.sort_order == PanelSortOrder::AppName {
+            // Group keys are already case-normalized, so a plain sort is alphabetical.
+            group_order.sort();
+        }
+        // Both sections lead regardless of sort order, critical above pinned.
+        if !pinned.is_empty() {
+            group_order.insert(0, self.pinned_key.clone());
+            grouped.insert(self.pinned_key.clone(), pinned);
+        }
+        if !critical.is_empty() {
+            group_order.insert(0, self.critical_key.clone());
+            grouped.insert(self.critical_key.clone(), critical);
+        }
+
+        ids.clear();
+        self.ids_scratch = ids;
+    }
+
+    pub(super) fn is_group_expanded(&self, key: &Rc<str>) -> bool {
+        is_fixed_section(key) || self.group_expanded.get(key).copied().unwrap_or(false)
+    }
+
+    pub(super) fn restore_group_expansion(&mut self, group_order: &[Rc<str>]) {
+        // Newly visible groups pick up their persisted state before block sizes are computed.
+        for key in group_order {
+            if self.group_expanded.contains_key(key) {
+                continue;
+            }
+            if self.group_state.is_expanded(key) {
+                self.group_expanded.insert(key.clone(), true);
+            }
+        }
+    }
+
     pub(super) fn expected_list_len(&self) -> usize {
         // Sum group block sizes to mirror the visible list length (headers + rows + ghosts).
         self.group_order
@@ -0,0 +1,107 @@
+//! History loaded a page at a time as the list scrolls toward its end.
+//!
+//! The seed carries only the newest page, so opening the panel costs the same with 100 or
+//! 5000 entries in history. Scrolling within a viewport of the bottom asks for the next page;
+//! group headers count only the entries loaded so far.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use async_channel::Sender;
+use gtk::prelude::*;
+use tracing::debug;
+use unixnotis_core::{NotificationView, HISTORY_PAGE_SIZE};
+
+use crate::dbus::UiEvent;
+
+use super::NotificationList;
+
+/// Paging state shared with the scroll handlers.
+pub(super) struct HistoryPaging {
+    /// Open while the daemon may hold older entries and no page request is in flight.
+    wanted: Rc<Cell<bool>>,
+    more: bool,
+    /// History size last reported by the daemon, for the header count.
+    total: usize,
+}
+
+impl HistoryPaging {
+    pub(super) fn new() -> Self {
+        Self {
+            wanted: Rc::new(Cell::new(false)),
+            more: false,
+            total: 0,
+        }
+    }
+
+    /// Ask for the next page once the viewport comes within one page of the list end.
+    pub(super) fn watch(&self, scroller: &gtk::ScrolledWindow, event_tx: Sender<UiEvent>) {
+        let wanted = self.wanted.clone();
+        let check = move |adjustment: &gtk::Adjustment| {
+            let remaining = adjustment.upper() - adjustment.value() - adjustment.page_size();
+            if wanted.get() && remaining < adjustment.page_size() {
+                // The event channel is unbounded, so a failed send means shutdown.
+                let _ = event_tx.try_send(UiEvent::HistoryWanted);
+            }
+        };
+        let adjustment = scroller.vadjustment();
+        adjustment.connect_value_changed(check.clone());
+        // A short list never scrolls, so also check whenever its height changes.
+        adjustment.connect_changed(check);
+    }
+
+    fn page_received(&mut self, len: usize) {
+        self.more = len >= HISTORY_PAGE_SIZE as usize;
+        self.wanted.set(self.more);
+    }
+}
+
+impl NotificationList {
+    /// Add a page of older history below what is already loaded.
+    pub fn append_history(&mut self, page: Vec<NotificationView>) {
+        self.history_paging.page_received(page.len());
+        let mut added = 0usize;
+        for notification in page {
+            // Entries archived since the last request shift the daemon's offsets.
+            if self.entries.contains_key(&notification.id) {
+                continue;
+            }
+            let id = notification.id;
+            let key = self.store_entry(notification, false);
+            self.history_order.push_back(id);
+            self.dirty_groups.insert(key);
+            added += 1;
+        }
+        debug!(
+            added,
+            more = self.history_paging.more,
+            "history page appended"
+        );
+        if added > 0 {
+            self.trim_to_limits();
+            self.request_rebuild();
+        }
+    }
+
+    /// Offset for the next history page, once per page; `None` while one is pending.
+    pub fn take_history_request(&mut self) -> Option<u32> {
+        if !self.history_paging.wanted.replace(false) {
+            return None;
+        }
+        Some(u32::try_from(self.history_order.len()).unwrap_or(u32::MAX))
+    }
+
+    pub fn set_history_total(&mut self, total: usize) {
+        self.history_paging.total = total;
+    }
+
+    /// Notifications counted in the header, including history not loaded yet.
+    pub fn total_count(&self) -> usize {
+        let history = if self.history_paging.more {
+            self.history_paging.total.max(self.history_order.len())
+        } else {
+            self.history_order.len()
+        };
+        self.active_order.len() + history
+    }
+}
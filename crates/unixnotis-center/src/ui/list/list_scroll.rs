@@ -0,0 +1,97 @@
+//! Scroll anchoring that keeps the reading position stable across incremental updates.
+//!
+//! Block splices above the viewport remove and reinsert rows, which drops GTK's own anchor.
+//! The top visible row is captured before the splice and its offset restored once the list
+//! has been laid out again.
+
+use std::cell::Cell;
+
+use gtk::glib;
+use gtk::prelude::*;
+
+use super::list_widgets::bound_row_data;
+use super::{NotificationList, RowKey};
+
+// The first tick runs before the spliced rows are laid out; the second sees real positions.
+const RESTORE_AFTER_FRAMES: u8 = 2;
+// Sub-pixel drift is not worth a scroll adjustment.
+const MIN_SCROLL_DELTA: f64 = 1.0;
+
+/// Top visible row and its distance from the viewport top.
+pub(super) struct ScrollAnchor {
+    key: RowKey,
+    offset: f64,
+}
+
+impl NotificationList {
+    pub(super) fn capture_scroll_anchor(&self) -> Option<ScrollAnchor> {
+        let adjustment = self.list_view.vadjustment()?;
+        if adjustment.value() <= 0.0 {
+            // At the top, new arrivals should stay visible instead of pushing the view down.
+            return None;
+        }
+        let mut best: Option<ScrollAnchor> = None;
+        let mut child = self.list_view.first_child();
+        while let Some(widget) = child {
+            child = widget.next_sibling();
+            let Some(bounds) = widget.compute_bounds(&self.list_view) else {
+                continue;
+            };
+            let top = bounds.y() as f64;
+            if top + bounds.height() as f64 <= 0.0 {
+                continue;
+            }
+            if best.as_ref().is_some_and(|anchor| anchor.offset <= top) {
+                continue;
+            }
+            if let Some(key) = row_key_for_widget(&widget) {
+                best = Some(ScrollAnchor { key, offset: top });
+            }
+        }
+        best
+    }
+
+    pub(super) fn restore_scroll_anchor(&self, anchor: ScrollAnchor) {
+        if !self.current_keys.contains(&anchor.key) {
+            // The anchor row is gone; let GTK keep whatever position it settles on.
+            return;
+        }
+        let frames = Cell::new(0u8);
+        self.list_view.add_tick_callback(move |view, _| {
+            frames.set(frames.get() + 1);
+            if frames.get() < RESTORE_AFTER_FRAMES {
+                return glib::ControlFlow::Continue;
+            }
+            let Some(adjustment) = view.vadjustment() else {
+                return glib::ControlFlow::Break;
+            };
+            if let Some(top) = row_offset(view, &anchor.key) {
+                let delta = top - anchor.offset;
+                if delta.abs() >= MIN_SCROLL_DELTA {
+                    let max = (adjustment.upper() - adjustment.page_size()).max(adjustment.lower());
+                    adjustment
+                        .set_value((adjustment.value() + delta).clamp(adjustment.lower(), max));
+                }
+            }
+            glib::ControlFlow::Break
+        });
+    }
+}
+
+fn row_offset(view: &gtk::ListView, key: &RowKey) -> Option<f64> {
+    let mut child = view.first_child();
+    while let Some(widget) = child {
+        if row_key_for_widget(&widget).as_ref() == Some(key) {
+            return widget.compute_bounds(view).map(|bounds| bounds.y() as f64);
+        }
+        child = widget.next_sibling();
+    }
+    None
+}
+
+pub(super) fn row_key_for_widget(widget: &gtk::Widget) -> Option<RowKey> {
+    // List rows wrap the factory root, so check the row widget and its direct child.
+    bound_row_data(widget)
+        .or_else(|| widget.first_child().and_then(|root| bound_row_data(&root)))
+        .map(|data| RowKey::from_data(&data))
+}
@@ -0,0 +1,97 @@
+//! Persisted list state that should survive panel restarts.
+//!
+//! Stores expanded group keys in a small JSON file under the XDG state directory.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use unixnotis_core::Config;
+
+const STATE_FILE: &str = "panel-state.json";
+// Bound the file so long-lived sessions with many one-off apps stay small.
+const MAX_EXPANDED_GROUPS: usize = 512;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct PanelStateFile {
+    expanded_groups: BTreeSet<String>,
+}
+
+/// Expanded group keys restored on seed and written back on toggle.
+pub(super) struct GroupStateStore {
+    path: Option<PathBuf>,
+    expanded: BTreeSet<String>,
+}
+
+impl GroupStateStore {
+    pub(super) fn load() -> Self {
+        let path = Config::default_state_dir()
+            .ok()
+            .map(|dir| dir.join(STATE_FILE));
+        let expanded = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(
+                |contents| match serde_json::from_str::<PanelStateFile>(&contents) {
+                    Ok(state) => Some(state.expanded_groups),
+                    Err(err) => {
+                        warn!(?err, "ignoring unreadable panel state file");
+                        None
+                    }
+                },
+            )
+            .unwrap_or_default();
+        debug!(groups = expanded.len(), "loaded persisted group state");
+        Self { path, expanded }
+    }
+
+    pub(super) fn is_expanded(&self, key: &str) -> bool {
+        self.expanded.contains(key)
+    }
+
+    pub(super) fn set_expanded(&mut self, key: &str, expanded: bool) {
+        // Collapsed is the default, so only expanded keys need to be stored.
+        let changed = if expanded {
+            if self.expanded.len() >= MAX_EXPANDED_GROUPS && !self.expanded.contains(key) {
+                debug!("persisted group state is full; skipping");
+                return;
+            }
+            self.expanded.insert(key.to_string())
+        } else {
+            self.expanded.remove(key)
+        };
+        if changed {
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+        let state = PanelStateFile {
+            expanded_groups: self.expanded.clone(),
+        };
+        let contents = match serde_json::to_string_pretty(&state) {
+            Ok(contents) => contents,
+            Err(err) => {
+                warn!(?err, "failed to serialize panel state");
+                return;
+            }
+        };
+        if let Some(dir) = path.parent() {
+            if let Err(err) = fs::create_dir_all(dir) {
+                warn!(?err, "failed to create state directory");
+                return;
+            }
+        }
+        // Write through a temp file so a crash never leaves a truncated state file.
+        let tmp = path.with_extension("json.tmp");
+        if let Err(err) = fs::write(&tmp, contents).and_then(|_| fs::rename(&tmp, path)) {
+            warn!(?err, "failed to write panel state");
+        }
+    }
+}
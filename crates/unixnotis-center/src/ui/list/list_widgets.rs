@@ -11,13 +11,77 @@ use gtk::prelude::*;
 use gtk::{self, Align};
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::debug;
-use unixnotis_core::{util, NotificationView, Urgency};
+use unixnotis_core::i18n::tr;
+use unixnotis_core::markup;
+use unixnotis_core::ui_ipc::ControlCommand;
+use unixnotis_core::{
+    util, IconConfig, NotificationView, PanelConfig, PanelDensity, Urgency, INLINE_REPLY_ACTION,
+};
+use unixnotis_ui::links;
 
 use crate::dbus::{UiCommand, UiEvent};
 
 use super::super::icons::IconResolver;
+use super::list_grouping::{is_critical_section, is_fixed_section, is_pinned_section};
 use super::list_item::{RowData, RowItem, RowKind};
 
+const COMPACT_ICON_SIZE: i32 = 18;
+const COMPACT_BODY_LINES: i32 = 2;
+const MINIMAL_ICON_SIZE: i32 = 16;
+
+/// Row sizing derived from panel config and shared with the list factory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowLayout {
+    pub icon_size: i32,
+    pub fixed_icon_slots: bool,
+    pub body_max_lines: i32,
+    pub show_body: bool,
+    /// Image preview height cap for unstacked rows; 0 turns previews off.
+    pub preview_height: i32,
+    pub density: PanelDensity,
+}
+
+impl RowLayout {
+    pub fn from_config(panel: &PanelConfig, icons: &IconConfig) -> Self {
+        let mut icon_size = panel.row_icon_size;
+        let mut body_max_lines = panel.body_max_lines;
+        // Denser presets cap rows without overriding stricter user limits.
+        match panel.density {
+            PanelDensity::Comfortable => {}
+            PanelDensity::Compact => {
+                icon_size = icon_size.min(COMPACT_ICON_SIZE);
+                if body_max_lines == 0 || body_max_lines > COMPACT_BODY_LINES {
+                    body_max_lines = COMPACT_BODY_LINES;
+                }
+            }
+            PanelDensity::Minimal => {
+                icon_size = icon_size.min(MINIMAL_ICON_SIZE);
+            }
+        }
+        Self {
+            icon_size,
+            fixed_icon_slots: panel.fixed_icon_slots,
+            body_max_lines,
+            show_body: panel.density != PanelDensity::Minimal,
+            // Minimal rows stay one line tall.
+            preview_height: if icons.image_preview && panel.density != PanelDensity::Minimal {
+                icons.preview_max_height
+            } else {
+                0
+            },
+            density: panel.density,
+        }
+    }
+
+    fn css_class(self) -> Option<&'static str> {
+        match self.density {
+            PanelDensity::Comfortable => None,
+            PanelDensity::Compact => Some("compact"),
+            PanelDensity::Minimal => Some("minimal"),
+        }
+    }
+}
+
 /// GTK wrapper widgets for each row type.
 pub(super) struct RowWidgets {
     kind: RowKind,
@@ -27,6 +91,8 @@ pub(super) struct RowWidgets {
     ghost: Option<GhostRowWidgets>,
     handler: RefCell<Option<(RowItem, gtk::glib::SignalHandlerId)>>,
     command_tx: UnboundedSender<UiCommand>,
+    layout: Rc<Cell<RowLayout>>,
+    applied_layout: Cell<Option<RowLayout>>,
 }
 
 fn row_widgets_quark() -> gtk::glib::Quark {
@@ -34,6 +100,11 @@ fn row_widgets_quark() -> gtk::glib::Quark {
     *QUARK.get_or_init(|| gtk::glib::Quark::from_str("unixnotis-row-widgets"))
 }
 
+fn bound_item_quark() -> gtk::glib::Quark {
+    static QUARK: OnceLock<gtk::glib::Quark> = OnceLock::new();
+    *QUARK.get_or_init(|| gtk::glib::Quark::from_str("unixnotis-bound-item"))
+}
+
 struct GroupRowWidgets {
     icon: gtk::Image,
     title: gtk::Label,
@@ -43,12 +114,17 @@ struct GroupRowWidgets {
 }
 
 struct NotificationRowWidgets {
+    icon_slot: gtk::Box,
     icon: gtk::Image,
     app_label: gtk::Label,
     summary_label: gtk::Label,
     body_label: gtk::Label,
+    progress: gtk::ProgressBar,
+    preview: gtk::Picture,
     actions_box: gtk::Box,
+    pin_button: gtk::Button,
     notify_id: Rc<Cell<u32>>,
+    pinned: Rc<Cell<bool>>,
     action_cache: RefCell<Vec<(String, String)>>,
     icon_sig: RefCell<Option<IconSignature>>,
 }
@@ -66,10 +142,12 @@ struct IconSignature {
     image_len: usize,
     image_width: i32,
     image_height: i32,
+    size: i32,
+    scale: i32,
 }
 
 impl IconSignature {
-    fn from(notification: &NotificationView) -> Self {
+    fn from(notification: &NotificationView, size: i32, scale: i32) -> Self {
         Self {
             image_path: notification.image.image_path.clone(),
             icon_name: notification.image.icon_name.clone(),
@@ -78,6 +156,8 @@ impl IconSignature {
             image_len: notification.image.image_data.data.len(),
             image_width: notification.image.image_data.width,
             image_height: notification.image.image_data.height,
+            size,
+            scale,
         }
     }
 }
@@ -87,15 +167,20 @@ impl RowWidgets {
         kind: RowKind,
         command_tx: UnboundedSender<UiCommand>,
         event_tx: Sender<UiEvent>,
+        layout: Rc<Cell<RowLayout>>,
     ) -> Self {
         match kind {
-            RowKind::GroupHeader => Self::new_group(command_tx, event_tx),
-            RowKind::Notification => Self::new_notification(command_tx),
-            RowKind::Ghost => Self::new_ghost(command_tx),
+            RowKind::GroupHeader => Self::new_group(command_tx, event_tx, layout),
+            RowKind::Notification => Self::new_notification(command_tx, layout),
+            RowKind::Ghost => Self::new_ghost(command_tx, layout),
         }
     }
 
-    fn new_group(command_tx: UnboundedSender<UiCommand>, event_tx: Sender<UiEvent>) -> Self {
+    fn new_group(
+        command_tx: UnboundedSender<UiCommand>,
+        event_tx: Sender<UiEvent>,
+        layout: Rc<Cell<RowLayout>>,
+    ) -> Self {
         let root = gtk::Box::new(gtk::Orientation::Vertical, 6);
         root.add_css_class("unixnotis-group");
         root.add_css_class("unixnotis-group-row");
@@ -167,17 +252,29 @@ impl RowWidgets {
             ghost: None,
             handler: RefCell::new(None),
             command_tx,
+            layout,
+            applied_layout: Cell::new(None),
         }
     }
 
-    fn new_notification(command_tx: UnboundedSender<UiCommand>) -> Self {
+    fn new_notification(
+        command_tx: UnboundedSender<UiCommand>,
+        layout: Rc<Cell<RowLayout>>,
+    ) -> Self {
         let root = gtk::Box::new(gtk::Orientation::Vertical, 6);
         root.add_css_class("unixnotis-panel-card");
 
         let header = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        // The slot keeps its size while the icon is hidden so async decodes do not shift text.
+        let icon_slot = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+        icon_slot.add_css_class("unixnotis-panel-icon-slot");
+        icon_slot.set_valign(Align::Center);
         let icon = gtk::Image::new();
         icon.set_pixel_size(22);
+        icon.set_halign(Align::Center);
+        icon.set_valign(Align::Center);
         icon.add_css_class("unixnotis-panel-icon");
+        icon_slot.append(&icon);
 
         let app_label = gtk::Label::new(None);
         app_label.set_xalign(0.0);
@@ -186,13 +283,18 @@ impl RowWidgets {
         let spacer = gtk::Box::new(gtk::Orientation::Horizontal, 1);
         spacer.set_hexpand(true);
 
+        let pin_button = gtk::Button::from_icon_name("view-pin-symbolic");
+        pin_button.set_halign(Align::End);
+        pin_button.add_css_class("unixnotis-panel-pin");
+
         let close_button = gtk::Button::from_icon_name("window-close-symbolic");
         close_button.set_halign(Align::End);
         close_button.add_css_class("unixnotis-panel-close");
 
-        header.append(&icon);
+        header.append(&icon_slot);
         header.append(&app_label);
         header.append(&spacer);
+        header.append(&pin_button);
         header.append(&close_button);
 
         let summary_label = gtk::Label::new(None);
@@ -204,6 +306,20 @@ impl RowWidgets {
         body_label.set_xalign(0.0);
         body_label.set_wrap(true);
         body_label.add_css_class("unixnotis-panel-body");
+        links::open_with_default_app(&body_label);
+
+        // Shown for notifications carrying the `value` hint.
+        let progress = gtk::ProgressBar::new();
+        progress.add_css_class("unixnotis-panel-progress");
+        progress.set_visible(false);
+
+        // Hidden until a decoded preview arrives; see IconResolver::apply_preview.
+        let preview = gtk::Picture::new();
+        preview.set_can_shrink(true);
+        preview.set_halign(Align::Start);
+        preview.set_overflow(gtk::Overflow::Hidden);
+        preview.add_css_class("unixnotis-panel-preview");
+        preview.set_visible(false);
 
         let actions_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
         actions_box.add_css_class("unixnotis-notification-actions");
@@ -211,6 +327,8 @@ impl RowWidgets {
         root.append(&header);
         root.append(&summary_label);
         root.append(&body_label);
+        root.append(&progress);
+        root.append(&preview);
         root.append(&actions_box);
 
         let notify_id = Rc::new(Cell::new(0));
@@ -222,7 +340,22 @@ impl RowWidgets {
                 return;
             }
             debug!(id, "dismiss clicked");
-            let _ = close_tx.send(UiCommand::Dismiss(id));
+            let _ = close_tx.send(ControlCommand::Dismiss(id).into());
+        });
+
+        let pinned = Rc::new(Cell::new(false));
+        let pin_tx = command_tx.clone();
+        let notify_id_clone = notify_id.clone();
+        let pinned_clone = pinned.clone();
+        pin_button.connect_clicked(move |_| {
+            let id = notify_id_clone.get();
+            if id == 0 {
+                return;
+            }
+            let pinned = !pinned_clone.get();
+            debug!(id, pinned, "pin clicked");
+            // The row moves once the daemon confirms with notification_pinned.
+            let _ = pin_tx.send(ControlCommand::Pin { id, pinned }.into());
         });
 
         Self {
@@ -230,22 +363,29 @@ impl RowWidgets {
             root,
             group: None,
             notification: Some(NotificationRowWidgets {
+                icon_slot,
                 icon,
                 app_label,
                 summary_label,
                 body_label,
+                progress,
+                preview,
                 actions_box,
+                pin_button,
                 notify_id,
+                pinned,
                 action_cache: RefCell::new(Vec::new()),
                 icon_sig: RefCell::new(None),
             }),
             ghost: None,
             handler: RefCell::new(None),
             command_tx,
+            layout,
+            applied_layout: Cell::new(None),
         }
     }
 
-    fn new_ghost(command_tx: UnboundedSender<UiCommand>) -> Self {
+    fn new_ghost(command_tx: UnboundedSender<UiCommand>, layout: Rc<Cell<RowLayout>>) -> Self {
         let root = gtk::Box::new(gtk::Orientation::Vertical, 0);
         root.add_css_class("unixnotis-panel-card");
         root.add_css_class("unixnotis-stack-ghost");
@@ -261,10 +401,17 @@ impl RowWidgets {
             }),
             handler: RefCell::new(None),
             command_tx,
+            layout,
+            applied_layout: Cell::new(None),
         }
     }
 
     fn refresh(&self, data: &RowData, icon_resolver: &IconResolver) {
+        let layout = self.layout.get();
+        if self.applied_layout.get() != Some(layout) {
+            self.apply_layout(layout);
+            self.applied_layout.set(Some(layout));
+        }
         match self.kind {
             RowKind::GroupHeader => {
                 if let Some(group) = &self.group {
@@ -279,6 +426,7 @@ impl RowWidgets {
                         data,
                         icon_resolver,
                         &self.command_tx,
+                        layout,
                     );
                 }
             }
@@ -290,8 +438,52 @@ impl RowWidgets {
         }
     }
 
-    pub(super) fn unbind(&self) {
+    fn apply_layout(&self, layout: RowLayout) {
+        for class in ["compact", "minimal"] {
+            self.root.remove_css_class(class);
+        }
+        if let Some(class) = layout.css_class() {
+            self.root.add_css_class(class);
+        }
+        let Some(row) = &self.notification else {
+            return;
+        };
+        row.icon.set_pixel_size(layout.icon_size);
+        if layout.fixed_icon_slots {
+            row.icon_slot
+                .set_size_request(layout.icon_size, layout.icon_size);
+        } else {
+            row.icon_slot.set_size_request(-1, -1);
+        }
+        if layout.body_max_lines > 0 {
+            // Ellipsized bodies cap row height so wrapping text cannot stretch the list.
+            row.body_label.set_lines(layout.body_max_lines);
+            row.body_label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        } else {
+            row.body_label.set_lines(-1);
+            row.body_label
+                .set_ellipsize(gtk::pango::EllipsizeMode::None);
+        }
+        // Force the icon to be re-applied at the new size on the next refresh.
+        row.icon_sig.borrow_mut().take();
+    }
+
+    pub(super) fn unbind(&self, icon_resolver: &IconResolver) {
         self.disconnect();
+        // Unbound rows are off screen, so their pending icon decodes can be dropped.
+        if let Some(group) = &self.group {
+            icon_resolver.release_image(&group.icon);
+        }
+        if let Some(row) = &self.notification {
+            icon_resolver.release_image(&row.icon);
+            icon_resolver.release_preview(&row.preview);
+        }
+        unsafe {
+            // SAFETY: clearing uses the same quark/type pairing as bind_row.
+            let _ = self
+                .root
+                .steal_qdata::<gtk::glib::WeakRef<RowItem>>(bound_item_quark());
+        }
     }
 
     fn disconnect(&self) {
@@ -306,6 +498,7 @@ pub(super) fn ensure_row_widgets(
     kind: RowKind,
     command_tx: UnboundedSender<UiCommand>,
     event_tx: Sender<UiEvent>,
+    layout: Rc<Cell<RowLayout>>,
 ) -> Rc<RowWidgets> {
     if let Some(existing) = get_row_widgets(list_item) {
         if existing.kind == kind {
@@ -313,7 +506,7 @@ pub(super) fn ensure_row_widgets(
         }
     }
 
-    let widgets = Rc::new(RowWidgets::new(kind, command_tx, event_tx));
+    let widgets = Rc::new(RowWidgets::new(kind, command_tx, event_tx, layout));
     list_item.set_child(Some(&widgets.root));
     set_row_widgets(list_item, widgets.clone());
     debug!(?kind, "row widgets created");
@@ -337,6 +530,20 @@ pub(super) fn bind_row(
         None
     });
     *widgets.handler.borrow_mut() = Some((item.clone(), handler));
+    unsafe {
+        // SAFETY: row roots stay on the GTK main thread; a weak ref avoids keeping items alive
+        // after they leave the store.
+        widgets.root.set_qdata(bound_item_quark(), item.downgrade());
+    }
+}
+
+/// Row data bound to a factory root widget, used to locate rows on screen.
+pub(super) fn bound_row_data(root: &gtk::Widget) -> Option<RowData> {
+    unsafe {
+        root.qdata::<gtk::glib::WeakRef<RowItem>>(bound_item_quark())
+            .and_then(|ptr| ptr.as_ref().upgrade())
+            .map(|item| item.data())
+    }
 }
 
 pub(super) fn set_row_widgets(list_item: &gtk::ListItem, widgets: Rc<RowWidgets>) {
@@ -376,10 +583,30 @@ fn update_group_row(
         .map(|notification| notification.app_name.trim())
         .filter(|name| !name.is_empty())
         .unwrap_or_else(|| data.group_key.as_ref());
+    let critical_section = is_critical_section(&data.group_key);
     // Display the original app label while the normalized key drives grouping behavior.
     // Fall back to the group key if no sample notification is available.
-    group.title.set_text(display_name);
+    let pinned_section = is_pinned_section(&data.group_key);
+    if critical_section {
+        group.title.set_text(&tr("group-critical"));
+        root.add_css_class("unixnotis-critical-section");
+    } else if pinned_section {
+        group.title.set_text(&tr("group-pinned"));
+        root.remove_css_class("unixnotis-critical-section");
+    } else {
+        group.title.set_text(display_name);
+        root.remove_css_class("unixnotis-critical-section");
+    }
+    if pinned_section {
+        root.add_css_class("unixnotis-pinned-section");
+    } else {
+        root.remove_css_class("unixnotis-pinned-section");
+    }
     group.count.set_text(&format!("{}", data.count));
+    // Fixed sections cannot be collapsed, so the chevron would be misleading.
+    group
+        .chevron
+        .set_visible(!is_fixed_section(&data.group_key));
     let chevron_name = if data.expanded {
         "pan-up-symbolic"
     } else {
@@ -394,7 +621,13 @@ fn update_group_row(
 
     *group.group_key.borrow_mut() = data.group_key.clone();
 
-    if let Some(notification) = data.notification.as_ref() {
+    if critical_section {
+        group.icon.set_icon_name(Some("dialog-warning-symbolic"));
+        group.icon.set_visible(true);
+    } else if pinned_section {
+        group.icon.set_icon_name(Some("view-pin-symbolic"));
+        group.icon.set_visible(true);
+    } else if let Some(notification) = data.notification.as_ref() {
         let scale = root.scale_factor();
         icon_resolver.apply_icon(&group.icon, notification.as_ref(), 18, scale);
     } else {
@@ -408,6 +641,7 @@ fn update_notification_row(
     data: &RowData,
     icon_resolver: &IconResolver,
     command_tx: &UnboundedSender<UiCommand>,
+    layout: RowLayout,
 ) {
     let Some(notification) = data.notification.as_ref() else {
         return;
@@ -429,10 +663,40 @@ fn update_notification_row(
     } else {
         root.remove_css_class("stacked");
     }
+    if is_critical_section(&data.group_key) {
+        root.add_css_class("unixnotis-critical-row");
+    } else {
+        root.remove_css_class("unixnotis-critical-row");
+    }
+    if !data.is_active && notification.history_ttl_minutes > 0 {
+        // Rule-limited history rows explain why they disappear early.
+        root.add_css_class("short-lived");
+        root.set_tooltip_text(Some(&format!(
+            "Removed from history after {} min",
+            notification.history_ttl_minutes
+        )));
+    } else {
+        root.remove_css_class("short-lived");
+        root.set_tooltip_text(None);
+    }
+
+    row.pinned.set(notification.pinned);
+    if notification.pinned {
+        root.add_css_class("pinned");
+        row.pin_button.add_css_class("active");
+        row.pin_button
+            .set_tooltip_text(Some(&tr("list-unpin-tooltip")));
+    } else {
+        root.remove_css_class("pinned");
+        row.pin_button.remove_css_class("active");
+        row.pin_button
+            .set_tooltip_text(Some(&tr("list-pin-tooltip")));
+    }
 
     row.app_label.set_text(&notification.app_name);
     row.summary_label.set_text(&notification.summary);
-    update_body_label(&row.body_label, &notification.body);
+    update_body_label(&row.body_label, &notification.body, layout.show_body);
+    update_progress(&row.progress, notification);
     row.notify_id.set(notification.id);
 
     update_actions(
@@ -442,13 +706,19 @@ fn update_notification_row(
         notification,
     );
 
-    let next_sig = IconSignature::from(notification);
+    let scale = root.scale_factor();
+    let next_sig = IconSignature::from(notification, layout.icon_size, scale);
     let mut sig_guard = row.icon_sig.borrow_mut();
     if sig_guard.as_ref() != Some(&next_sig) {
-        let scale = root.scale_factor();
-        icon_resolver.apply_icon(&row.icon, notification, 22, scale);
+        icon_resolver.apply_icon(&row.icon, notification, layout.icon_size, scale);
         *sig_guard = Some(next_sig);
     }
+    // Stacked rows stay compact; the preview shows once the group is expanded.
+    if layout.preview_height > 0 && !data.stacked {
+        icon_resolver.apply_preview(&row.preview, notification, layout.preview_height, scale);
+    } else {
+        icon_resolver.release_preview(&row.preview);
+    }
 }
 
 fn update_ghost_row(ghost: &GhostRowWidgets, root: &gtk::Box, data: &RowData) {
@@ -465,14 +735,24 @@ fn update_ghost_row(ghost: &GhostRowWidgets, root: &gtk::Box, data: &RowData) {
     *depth = data.ghost_depth;
 }
 
-fn update_body_label(label: &gtk::Label, body: &str) {
-    if body.is_empty() {
+fn update_body_label(label: &gtk::Label, body: &str, show_body: bool) {
+    if body.is_empty() || !show_body {
         label.set_text("");
         label.set_visible(false);
         return;
     }
     label.set_visible(true);
-    label.set_markup(body);
+    label.set_markup(&markup::sanitize_body(body));
+}
+
+fn update_progress(bar: &gtk::ProgressBar, notification: &NotificationView) {
+    match notification.progress_fraction() {
+        Some(fraction) => {
+            bar.set_fraction(fraction);
+            bar.set_visible(true);
+        }
+        None => bar.set_visible(false),
+    }
 }
 
 fn update_actions(
@@ -511,6 +791,10 @@ fn update_actions(
     }
 
     for action in &notification.actions {
+        if action.key == INLINE_REPLY_ACTION {
+            actions_box.append(&reply_entry(command_tx, notification.id, &action.label));
+            continue;
+        }
         let button = gtk::Button::with_label(&action.label);
         button.add_css_class("unixnotis-panel-action");
         button.add_css_class("unixnotis-notification-action");
@@ -519,11 +803,33 @@ fn update_actions(
         let id = notification.id;
         button.connect_clicked(move |_| {
             debug!(id, action = %action_key, "action invoked");
-            let _ = tx.send(UiCommand::InvokeAction {
-                id,
-                action_key: action_key.clone(),
-            });
+            let _ = tx.send(
+                ControlCommand::InvokeAction {
+                    id,
+                    action_key: action_key.clone(),
+                }
+                .into(),
+            );
         });
         actions_box.append(&button);
     }
 }
+
+/// Text field standing in for an `inline-reply` button; Enter sends the reply.
+fn reply_entry(command_tx: &UnboundedSender<UiCommand>, id: u32, label: &str) -> gtk::Entry {
+    let entry = gtk::Entry::new();
+    entry.set_placeholder_text(Some(label));
+    entry.set_hexpand(true);
+    entry.add_css_class("unixnotis-notification-reply");
+    let tx = command_tx.clone();
+    entry.connect_activate(move |entry| {
+        let text = entry.text().trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        debug!(id, "reply sent");
+        let _ = tx.send(ControlCommand::Reply { id, text }.into());
+        entry.set_text("");
+    });
+    entry
+}
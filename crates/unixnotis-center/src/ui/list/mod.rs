@@ -4,10 +4,15 @@
 //! `list_widgets.rs` to avoid bloating unrelated logic.
 
 mod list_blocks;
+mod list_cursor;
 mod list_grouping;
+mod list_history;
 mod list_item;
+mod list_scroll;
+mod list_state;
 mod list_widgets;
 
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::rc::Rc;
 
@@ -16,26 +21,44 @@ use gio::prelude::*;
 use gtk::glib;
 use gtk::prelude::*;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing::debug;
-use unixnotis_core::{CloseReason, NotificationView};
+use tracing::{debug, instrument};
+use unixnotis_core::{CloseReason, KeepExpiredConfig, NotificationView, PanelSortOrder, Urgency};
 
 use crate::dbus::{UiCommand, UiEvent};
 
-use super::icons::IconResolver;
+use self::list_cursor::{set_cursor_class, Cursor};
+use self::list_history::HistoryPaging;
 use self::list_item::{RowData, RowItem, RowKind};
+use self::list_state::GroupStateStore;
 use self::list_widgets::{
     bind_row, clear_row_widgets, ensure_row_widgets, get_row_widgets, set_row_widgets, RowWidgets,
 };
+use super::icons::IconResolver;
+
+pub use self::list_cursor::{CursorMove, CursorTarget};
+pub use self::list_widgets::RowLayout;
 
 /// Maintains notification data and renders grouped widgets into the panel list.
 pub struct NotificationList {
     store: gio::ListStore,
+    list_view: gtk::ListView,
     entries: HashMap<u32, NotificationEntry>,
     // Active notifications render first to match the in-flight stack.
     active_order: VecDeque<u32>,
     // Historical notifications follow active ones in most-recent-first order.
     history_order: VecDeque<u32>,
+    history_paging: HistoryPaging,
+    // Display ordering applied on top of the active/history queues.
+    sort_order: PanelSortOrder,
+    ids_scratch: Vec<u32>,
+    // Lifts active critical notifications into a pinned section above the groups.
+    sticky_critical: bool,
+    critical_key: Rc<str>,
+    // Section for notifications pinned from the panel, below critical and above the groups.
+    pinned_key: Rc<str>,
     group_expanded: HashMap<Rc<str>, bool>,
+    // Expansion choices persisted across panel restarts.
+    group_state: GroupStateStore,
     group_headers: HashMap<Rc<str>, RowItem>,
     group_order: Vec<Rc<str>>,
     group_order_scratch: Vec<Rc<str>>,
@@ -49,10 +72,18 @@ pub struct NotificationList {
     items_scratch: Vec<RowItem>,
     objects_scratch: Vec<glib::Object>,
     needs_rebuild: bool,
+    // Rebuild requests folded into the pending flush, reported to the debug HUD.
+    rebuild_requests: u32,
     // Groups with pending content/visibility changes since the last flush.
     dirty_groups: HashSet<Rc<str>>,
     max_active: usize,
     max_entries: usize,
+    // Mirrors the daemon policy so dropped expirations are not archived locally.
+    keep_expired: KeepExpiredConfig,
+    // Shared with row widgets so layout changes apply on the next bind.
+    row_layout: Rc<Cell<RowLayout>>,
+    // Keyboard cursor, shared with the factory so rebound rows pick up its mark.
+    cursor: Rc<RefCell<Option<Cursor>>>,
 }
 
 struct NotificationEntry {
@@ -76,7 +107,9 @@ impl NotificationList {
         icon_resolver: Rc<IconResolver>,
         max_active: usize,
         max_entries: usize,
+        row_layout: RowLayout,
     ) -> Self {
+        let row_layout = Rc::new(Cell::new(row_layout));
         let store = gio::ListStore::new::<RowItem>();
         let selection = gtk::NoSelection::new(Some(store.clone()));
         let factory = gtk::SignalListItemFactory::new();
@@ -87,9 +120,12 @@ impl NotificationList {
         list_view.set_vexpand(true);
 
         scroller.set_child(Some(&list_view));
+        let history_paging = HistoryPaging::new();
+        history_paging.watch(&scroller, event_tx.clone());
 
         let command_tx_clone = command_tx.clone();
         let event_tx_clone = event_tx.clone();
+        let layout_clone = row_layout.clone();
         factory.connect_setup(move |_, list_item| {
             let root = gtk::Box::new(gtk::Orientation::Vertical, 0);
             list_item.set_child(Some(&root));
@@ -98,13 +134,17 @@ impl NotificationList {
                 RowKind::Ghost,
                 command_tx_clone.clone(),
                 event_tx_clone.clone(),
+                layout_clone.clone(),
             );
             set_row_widgets(list_item, Rc::new(widgets));
         });
 
+        let cursor: Rc<RefCell<Option<Cursor>>> = Rc::new(RefCell::new(None));
         let command_tx_clone = command_tx.clone();
         let event_tx_clone = event_tx.clone();
         let icon_resolver_clone = icon_resolver.clone();
+        let layout_clone = row_layout.clone();
+        let cursor_clone = cursor.clone();
         factory.connect_bind(move |_, list_item| {
             let Some(item) = list_item.item().and_downcast::<RowItem>() else {
                 return;
@@ -115,24 +155,42 @@ impl NotificationList {
                 data.kind,
                 command_tx_clone.clone(),
                 event_tx_clone.clone(),
+                layout_clone.clone(),
             );
 
             bind_row(widgets, &item, &data, icon_resolver_clone.clone());
+            if let Some(root) = list_item.child() {
+                let key = RowKey::from_data(&data);
+                let on_cursor = cursor_clone
+                    .borrow()
+                    .as_ref()
+                    .is_some_and(|cursor| cursor.key == key);
+                set_cursor_class(&root, on_cursor);
+            }
         });
 
+        let icon_resolver_clone = icon_resolver.clone();
         factory.connect_unbind(move |_, list_item| {
             if let Some(widgets) = get_row_widgets(list_item) {
-                widgets.unbind();
+                widgets.unbind(&icon_resolver_clone);
             }
             clear_row_widgets(list_item);
         });
 
         Self {
             store,
+            list_view,
             entries: HashMap::new(),
             active_order: VecDeque::new(),
             history_order: VecDeque::new(),
+            history_paging,
+            sort_order: PanelSortOrder::default(),
+            ids_scratch: Vec::new(),
+            sticky_critical: false,
+            critical_key: Rc::from(list_grouping::CRITICAL_GROUP_KEY),
+            pinned_key: Rc::from(list_grouping::PINNED_GROUP_KEY),
             group_expanded: HashMap::new(),
+            group_state: GroupStateStore::load(),
             group_headers: HashMap::new(),
             group_order: Vec::new(),
             group_order_scratch: Vec::new(),
@@ -145,10 +203,92 @@ impl NotificationList {
             items_scratch: Vec::new(),
             objects_scratch: Vec::new(),
             needs_rebuild: false,
+            rebuild_requests: 0,
             dirty_groups: HashSet::new(),
             max_active,
             max_entries,
+            keep_expired: KeepExpiredConfig::default(),
+            row_layout,
+            cursor,
+        }
+    }
+
+    /// Apply new row sizing and rebind every row so existing widgets pick it up.
+    pub fn set_row_layout(&mut self, layout: RowLayout) {
+        if self.row_layout.get() == layout {
+            return;
+        }
+        self.row_layout.set(layout);
+        self.store.remove_all();
+        self.current_keys.clear();
+        self.group_ranges.clear();
+        debug!(?layout, "row layout updated");
+        self.request_rebuild();
+    }
+
+    /// Rebind every row so icons are resolved again, e.g. for a new scale factor.
+    pub fn rebind_rows(&mut self) {
+        self.store.remove_all();
+        self.current_keys.clear();
+        self.group_ranges.clear();
+        self.request_rebuild();
+    }
+
+    /// Switch the list ordering and rebuild from scratch so every group lands in place.
+    pub fn set_sort_order(&mut self, order: PanelSortOrder) {
+        if self.sort_order == order {
+            return;
+        }
+        self.sort_order = order;
+        self.store.remove_all();
+        self.current_keys.clear();
+        self.group_ranges.clear();
+        debug!(?order, "sort order updated");
+        self.request_rebuild();
+    }
+
+    pub fn sort_order(&self) -> PanelSortOrder {
+        self.sort_order
+    }
+
+    /// Enable or disable the pinned critical section above the grouped list.
+    pub fn set_sticky_critical(&mut self, enabled: bool) {
+        if self.sticky_critical == enabled {
+            return;
         }
+        self.sticky_critical = enabled;
+        self.store.remove_all();
+        self.current_keys.clear();
+        self.group_ranges.clear();
+        debug!(enabled, "sticky critical section updated");
+        self.request_rebuild();
+    }
+
+    /// Estimated bytes held by the list for memory reports.
+    pub fn memory_estimates(&self) -> Vec<(String, u64)> {
+        let views: usize = self
+            .entries
+            .values()
+            .map(|entry| entry.view.estimated_size())
+            .sum();
+        vec![("list_notifications".to_string(), views as u64)]
+    }
+
+    /// Release spare capacity in scratch buffers left behind by large rebuilds.
+    pub fn shrink_buffers(&mut self) {
+        self.ids_scratch.shrink_to_fit();
+        self.group_order_scratch.shrink_to_fit();
+        self.keys_scratch.shrink_to_fit();
+        self.items_scratch.shrink_to_fit();
+        self.objects_scratch.shrink_to_fit();
+        self.grouped_cache.retain(|_, ids| !ids.is_empty());
+        self.grouped_cache.shrink_to_fit();
+        self.ghost_items.shrink_to_fit();
+        self.interned.shrink_to_fit();
+    }
+
+    pub fn set_keep_expired(&mut self, policy: KeepExpiredConfig) {
+        self.keep_expired = policy;
     }
 
     pub fn seed(&mut self, active: Vec<NotificationView>, history: Vec<NotificationView>) {
@@ -171,9 +311,8 @@ impl NotificationList {
         for notification in active {
             self.insert_entry(notification, true);
         }
-        for notification in history {
-            self.insert_entry(notification, false);
-        }
+        // The seed carries the newest history page; older pages load on scroll.
+        self.append_history(history);
         self.trim_to_limits();
 
         debug!(
@@ -206,9 +345,14 @@ impl NotificationList {
         let mut existing = false;
         let mut old_is_active = None;
         let mut group_changed = false;
+        let mut urgency_changed = false;
+        let mut critical_involved = false;
         if let Some(entry) = self.entries.get_mut(&id) {
             existing = true;
             old_is_active = Some(entry.is_active);
+            urgency_changed = entry.view.urgency != notification.urgency;
+            critical_involved = entry.view.urgency == Urgency::Critical as u8
+                || notification.urgency == Urgency::Critical as u8;
             if let Some(key) = new_key {
                 entry.app_key = key;
                 group_changed = true;
@@ -235,6 +379,10 @@ impl NotificationList {
             && !group_changed
             && old_is_active == Some(is_active)
             && !ordering_changed
+            // Urgency ordering moves rows when the urgency hint changes.
+            && !(urgency_changed && self.sort_order == PanelSortOrder::Urgency)
+            // Critical rows may live in the pinned section rather than their app group.
+            && !(critical_involved && self.sticky_critical)
             && !self.needs_rebuild
         {
             if let Some(entry) = self.entries.get(&id) {
@@ -297,7 +445,12 @@ impl NotificationList {
 
     pub fn mark_closed(&mut self, id: u32, reason: CloseReason) {
         let group_key = self.entries.get(&id).map(|entry| entry.app_key.clone());
-        if matches!(reason, CloseReason::DismissedByUser) {
+        let dropped_expired = matches!(reason, CloseReason::Expired)
+            && self
+                .entries
+                .get(&id)
+                .is_some_and(|entry| !self.keep_expired.keeps(entry.view.urgency));
+        if matches!(reason, CloseReason::DismissedByUser | CloseReason::Pruned) || dropped_expired {
             self.remove_entry(id);
             if let Some(key) = group_key {
                 self.dirty_groups.insert(key);
@@ -322,16 +475,43 @@ impl NotificationList {
     }
 
     pub fn toggle_group(&mut self, key: &str) {
+        if list_grouping::is_fixed_section(key) {
+            // The critical and pinned sections always stay expanded.
+            return;
+        }
         let key = self.intern_key(key);
         let expanded = self.group_expanded.entry(key.clone()).or_insert(false);
         *expanded = !*expanded;
+        self.group_state.set_expanded(&key, *expanded);
         self.dirty_groups.insert(key.clone());
         debug!(app = key.as_ref(), expanded = *expanded, "group toggled");
         self.request_rebuild();
     }
 
-    pub fn total_count(&self) -> usize {
-        self.active_order.len() + self.history_order.len()
+    /// Expand the group for `app` and scroll it into view; returns false when it has no rows.
+    pub fn reveal_group(&mut self, app: &str) -> bool {
+        let key = self.intern_key(app);
+        if !self.entries.values().any(|entry| entry.app_key == key) {
+            return false;
+        }
+        // Reveal without persisting, so a status bar click does not change the saved layout.
+        if !self.is_group_expanded(&key) {
+            self.group_expanded.insert(key.clone(), true);
+            self.dirty_groups.insert(key.clone());
+            self.request_rebuild();
+        }
+        self.flush_rebuild();
+        let Some(range) = self.group_ranges.get(&key) else {
+            return false;
+        };
+        let position = range.start as u32;
+        if let Err(err) = self
+            .list_view
+            .activate_action("list.scroll-to-item", Some(&position.to_variant()))
+        {
+            debug!(?err, "failed to scroll to group");
+        }
+        true
     }
 
     fn trim_to_limits(&mut self) {
@@ -351,33 +531,60 @@ impl NotificationList {
             }
         }
 
-        if self.max_entries == 0 {
-            for id in self.history_order.drain(..) {
+        // Pinned rows are exempt from the history cap, as they are in the daemon.
+        while self.history_order.len() > self.max_entries {
+            let Some(index) = self
+                .history_order
+                .iter()
+                .rposition(|id| !self.entries.get(id).is_some_and(|entry| entry.view.pinned))
+            else {
+                break;
+            };
+            if let Some(id) = self.history_order.remove(index) {
                 if let Some(entry) = self.entries.remove(&id) {
                     self.dirty_groups.insert(entry.app_key);
                 }
             }
-        } else {
-            while self.history_order.len() > self.max_entries {
-                if let Some(id) = self.history_order.pop_back() {
-                    if let Some(entry) = self.entries.remove(&id) {
-                        self.dirty_groups.insert(entry.app_key);
-                    }
-                }
-            }
         }
     }
 
-    pub fn flush_rebuild(&mut self) {
-        if !self.needs_rebuild {
+    /// Move a notification into or out of the pinned section.
+    pub fn set_pinned(&mut self, id: u32, pinned: bool) {
+        let Some(entry) = self.entries.get_mut(&id) else {
+            return;
+        };
+        if entry.view.pinned == pinned {
             return;
         }
+        let mut view = entry.view.as_ref().clone();
+        view.pinned = pinned;
+        entry.view = Rc::new(view);
+        self.dirty_groups.insert(entry.app_key.clone());
+        self.dirty_groups.insert(self.pinned_key.clone());
+        debug!(id, pinned, "notification pin changed");
+        self.request_rebuild();
+    }
+
+    /// Apply pending changes; returns how many rebuild requests the flush covered.
+    #[instrument(level = "debug", skip_all)]
+    pub fn flush_rebuild(&mut self) -> u32 {
+        if !self.needs_rebuild {
+            return 0;
+        }
         self.needs_rebuild = false;
+        let requests = std::mem::take(&mut self.rebuild_requests);
         if self.store.n_items() == 0 || self.group_ranges.is_empty() {
             self.rebuild_list();
-            return;
+            self.sync_cursor();
+            return requests;
         }
+        let anchor = self.capture_scroll_anchor();
         self.apply_updates();
+        if let Some(anchor) = anchor {
+            self.restore_scroll_anchor(anchor);
+        }
+        self.sync_cursor();
+        requests
     }
 
     pub fn needs_rebuild(&self) -> bool {
@@ -385,6 +592,18 @@ impl NotificationList {
     }
 
     fn insert_entry(&mut self, notification: NotificationView, is_active: bool) -> Rc<str> {
+        let id = notification.id;
+        let app_key = self.store_entry(notification, is_active);
+        if is_active {
+            self.active_order.push_front(id);
+        } else {
+            self.history_order.push_front(id);
+        }
+        app_key
+    }
+
+    /// Record an entry without placing it in either order queue.
+    fn store_entry(&mut self, notification: NotificationView, is_active: bool) -> Rc<str> {
         let id = notification.id;
         let app_key = self.intern_key(&notification.app_name);
         let view = Rc::new(notification);
@@ -401,11 +620,6 @@ impl NotificationList {
             item,
         };
         self.entries.insert(id, entry);
-        if is_active {
-            self.active_order.push_front(id);
-        } else {
-            self.history_order.push_front(id);
-        }
         app_key
     }
 
@@ -421,23 +635,14 @@ impl NotificationList {
         let mut grouped = std::mem::take(&mut self.grouped_cache);
         grouped.clear();
 
-        // Build app-based groups in active+history order for stable UI layout.
-        for id in self.active_order.iter().chain(self.history_order.iter()) {
-            let Some(entry) = self.entries.get(id) else {
-                continue;
-            };
-            let key = entry.app_key.clone();
-            let bucket = grouped.entry(key.clone()).or_insert_with(|| {
-                group_order.push(key.clone());
-                Vec::new()
-            });
-            bucket.push(*id);
-        }
+        // Build app-based groups in the configured sort order for stable UI layout.
+        self.collect_groups(&mut grouped, &mut group_order);
 
         self.group_headers
             .retain(|key, _| grouped.contains_key(key));
         self.group_expanded
             .retain(|key, _| grouped.contains_key(key));
+        self.restore_group_expansion(&group_order);
 
         let mut items = std::mem::take(&mut self.items_scratch);
         items.clear();
@@ -517,22 +722,21 @@ impl NotificationList {
         let mut grouped = std::mem::take(&mut self.grouped_cache);
         grouped.clear();
 
-        for id in self.active_order.iter().chain(self.history_order.iter()) {
-            let Some(entry) = self.entries.get(id) else {
-                continue;
-            };
-            let key = entry.app_key.clone();
-            let bucket = grouped.entry(key.clone()).or_insert_with(|| {
-                group_order.push(key.clone());
-                Vec::new()
-            });
-            bucket.push(*id);
-        }
+        self.collect_groups(&mut grouped, &mut group_order);
 
         self.group_headers
             .retain(|key, _| grouped.contains_key(key));
         self.group_expanded
             .retain(|key, _| grouped.contains_key(key));
+        self.restore_group_expansion(&group_order);
+        if self.sticky_critical && !self.dirty_groups.is_empty() {
+            // Any change can move rows in or out of the pinned section, and it stays small.
+            self.dirty_groups.insert(self.critical_key.clone());
+        }
+        if !self.dirty_groups.is_empty() {
+            // The same holds for pinned rows, which keep their place when their app group changes.
+            self.dirty_groups.insert(self.pinned_key.clone());
+        }
 
         let mut keep_groups: HashSet<Rc<str>> = HashSet::new();
         let mut removed_groups: HashSet<Rc<str>> = HashSet::new();
@@ -634,6 +838,7 @@ impl NotificationList {
 
     fn request_rebuild(&mut self) {
         self.needs_rebuild = true;
+        self.rebuild_requests = self.rebuild_requests.saturating_add(1);
     }
 }
 
@@ -643,3 +848,18 @@ enum RowKey {
     Notification { id: u32 },
     Ghost { group: Rc<str>, depth: u8 },
 }
+
+impl RowKey {
+    fn from_data(data: &RowData) -> Self {
+        match data.kind {
+            RowKind::GroupHeader => RowKey::GroupHeader {
+                group: data.group_key.clone(),
+            },
+            RowKind::Notification => RowKey::Notification { id: data.id },
+            RowKind::Ghost => RowKey::Ghost {
+                group: data.group_key.clone(),
+                depth: data.ghost_depth,
+            },
+        }
+    }
+}
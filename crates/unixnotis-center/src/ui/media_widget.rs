@@ -1,28 +1,76 @@
 //! Media carousel widget for the center panel.
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 use std::rc::Rc;
 
 use gtk::prelude::*;
 use gtk::{gio, Align};
+use unixnotis_core::i18n::tr;
 
-use crate::media::{MediaHandle, MediaInfo};
+use crate::media::{MediaHandle, MediaInfo, QueuedTrack};
 
 use super::marquee::MarqueeLabel;
 
+// Minimum horizontal fling speed (px/s) before a swipe switches players.
+const SWIPE_MIN_VELOCITY: f64 = 300.0;
+
 /// GTK widget that renders media players with an in-panel carousel.
 pub struct MediaWidget {
+    carousel: Carousel,
+}
+
+// Shared handles so navigation buttons and the swipe gesture drive the same selection.
+#[derive(Clone)]
+struct Carousel {
     root: gtk::Box,
     nav_prev: gtk::Button,
     nav_next: gtk::Button,
     card: MediaCardWidgets,
+    picker: PlayerPicker,
+    volume: VolumeRow,
+    queue: QueueSection,
     selection: Rc<RefCell<MediaSelection>>,
 }
 
+// Dropdown for jumping straight to a player when several are open.
+#[derive(Clone)]
+struct PlayerPicker {
+    dropdown: gtk::DropDown,
+    model: gtk::StringList,
+    // Set while the dropdown is synced from the selection, so the sync is not taken as a pick.
+    updating: Rc<Cell<bool>>,
+}
+
+// Volume slider for players that accept MPRIS Volume writes.
+#[derive(Clone)]
+struct VolumeRow {
+    root: gtk::Box,
+    scale: gtk::Scale,
+    handle: MediaHandle,
+    // Player the slider currently drives.
+    bus_name: Rc<RefCell<Option<String>>>,
+    updating: Rc<Cell<bool>>,
+}
+
+// Expandable "Up next" list for players exposing the MPRIS TrackList interface.
+#[derive(Clone)]
+struct QueueSection {
+    expander: gtk::Expander,
+    list: gtk::Box,
+    handle: MediaHandle,
+    // Bus name and tracks currently rendered, to skip rebuilding rows on unrelated updates.
+    shown: Rc<RefCell<Option<(String, Vec<QueuedTrack>)>>>,
+}
+
 #[derive(Default)]
 struct MediaSelection {
     players: Vec<MediaInfo>,
     current_index: usize,
+    // Lowercased identity fragments from `media.player_order`; empty keeps playback ordering.
+    player_order: Vec<String>,
+    // Bus names that were playing in the previous snapshot.
+    playing: HashSet<String>,
 }
 
 #[derive(Clone)]
@@ -65,104 +113,346 @@ impl MediaWidget {
 
         let selection = Rc::new(RefCell::new(MediaSelection::default()));
         let card = build_media_card(&handle, selection.clone(), marquee_width, title_char_limit);
+        let picker = PlayerPicker::new();
+        let volume = VolumeRow::new(handle.clone());
+        let queue = QueueSection::new(handle);
 
         row.append(&nav_prev);
         row.append(&card.root);
         row.append(&nav_next);
+        root.append(&picker.dropdown);
         root.append(&row);
+        root.append(&volume.root);
+        root.append(&queue.expander);
         container.append(&root);
 
-        let selection_prev = selection.clone();
-        let card_prev = card.clone();
-        let root_prev = root.clone();
-        let nav_prev_clone = nav_prev.clone();
-        let nav_next_clone = nav_next.clone();
-        nav_prev.connect_clicked(move |_| {
-            selection_prev.borrow_mut().prev();
-            apply_selection(
-                &selection_prev.borrow(),
-                &card_prev,
-                &root_prev,
-                &nav_prev_clone,
-                &nav_next_clone,
-            );
-        });
-
-        let selection_next = selection.clone();
-        let card_next = card.clone();
-        let root_next = root.clone();
-        let nav_prev_clone = nav_prev.clone();
-        let nav_next_clone = nav_next.clone();
-        nav_next.connect_clicked(move |_| {
-            selection_next.borrow_mut().next();
-            apply_selection(
-                &selection_next.borrow(),
-                &card_next,
-                &root_next,
-                &nav_prev_clone,
-                &nav_next_clone,
-            );
-        });
-
-        Self {
+        let carousel = Carousel {
             root,
             nav_prev,
             nav_next,
             card,
+            picker,
+            volume,
+            queue,
             selection,
-        }
+        };
+
+        let carousel_pick = carousel.clone();
+        carousel
+            .picker
+            .dropdown
+            .connect_selected_notify(move |dropdown| {
+                if carousel_pick.picker.updating.get() {
+                    return;
+                }
+                carousel_pick.select(dropdown.selected() as usize);
+            });
+
+        let carousel_prev = carousel.clone();
+        carousel
+            .nav_prev
+            .connect_clicked(move |_| carousel_prev.step(false));
+        let carousel_next = carousel.clone();
+        carousel
+            .nav_next
+            .connect_clicked(move |_| carousel_next.step(true));
+
+        // Swiping left advances, matching touch carousels elsewhere in the desktop.
+        let swipe = gtk::GestureSwipe::new();
+        let carousel_swipe = carousel.clone();
+        swipe.connect_swipe(move |_, velocity_x, velocity_y| {
+            if velocity_x.abs() < SWIPE_MIN_VELOCITY || velocity_x.abs() < velocity_y.abs() {
+                return;
+            }
+            carousel_swipe.step(velocity_x < 0.0);
+        });
+        row.add_controller(swipe);
+
+        Self { carousel }
     }
 
     pub fn update(&mut self, infos: &[MediaInfo]) {
-        self.selection.borrow_mut().set_players(infos.to_vec());
+        self.carousel
+            .selection
+            .borrow_mut()
+            .set_players(infos.to_vec());
+        self.carousel.refresh();
+    }
+
+    pub fn clear(&mut self) {
+        let mut selection = self.carousel.selection.borrow_mut();
+        selection.players.clear();
+        selection.playing.clear();
+        self.carousel.root.set_visible(false);
+    }
+
+    pub fn apply_layout(&mut self, panel_width: i32, title_char_limit: usize) {
+        let marquee_width = panel_width.saturating_sub(240).max(140);
+        let card = &self.carousel.card;
+        card.text_box.set_size_request(marquee_width, -1);
+        card.title_label
+            .update_limits(marquee_width, title_char_limit);
+    }
+
+    /// Pin players whose identity matches these fragments to the front, in order.
+    pub fn set_player_order(&mut self, order: &[String]) {
+        let order: Vec<String> = order.iter().map(|entry| entry.to_lowercase()).collect();
+        let mut selection = self.carousel.selection.borrow_mut();
+        if selection.player_order == order {
+            return;
+        }
+        selection.player_order = order;
+        let players = std::mem::take(&mut selection.players);
+        selection.set_players(players);
+        drop(selection);
+        self.carousel.refresh();
+    }
+}
+
+impl Carousel {
+    fn step(&self, forward: bool) {
+        {
+            let mut selection = self.selection.borrow_mut();
+            if forward {
+                selection.next();
+            } else {
+                selection.prev();
+            }
+        }
+        self.refresh();
+    }
+
+    fn select(&self, index: usize) {
+        self.selection.borrow_mut().select(index);
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        let selection = self.selection.borrow();
         apply_selection(
-            &self.selection.borrow(),
+            &selection,
             &self.card,
             &self.root,
             &self.nav_prev,
             &self.nav_next,
         );
+        self.picker.update(&selection);
+        self.volume.update(selection.current());
+        self.queue.update(selection.current());
+    }
+}
+
+impl PlayerPicker {
+    fn new() -> Self {
+        let model = gtk::StringList::new(&[]);
+        let dropdown = gtk::DropDown::new(Some(model.clone()), None::<gtk::Expression>);
+        dropdown.add_css_class("unixnotis-media-picker");
+        dropdown.set_halign(Align::Start);
+        dropdown.set_tooltip_text(Some(&tr("media-choose-player")));
+        dropdown.set_visible(false);
+        Self {
+            dropdown,
+            model,
+            updating: Rc::new(Cell::new(false)),
+        }
     }
 
-    pub fn clear(&mut self) {
-        self.selection.borrow_mut().players.clear();
-        self.root.set_visible(false);
+    fn update(&self, selection: &MediaSelection) {
+        self.dropdown.set_visible(selection.has_multiple());
+        let names: Vec<&str> = selection
+            .players
+            .iter()
+            .map(|info| info.identity.as_str())
+            .collect();
+        let shown = (0..self.model.n_items())
+            .map(|index| self.model.string(index))
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default();
+        self.updating.set(true);
+        if shown.len() != names.len()
+            || shown
+                .iter()
+                .zip(&names)
+                .any(|(shown, name)| shown.as_str() != *name)
+        {
+            self.model.splice(0, self.model.n_items(), &names);
+        }
+        self.dropdown.set_selected(selection.current_index as u32);
+        self.updating.set(false);
     }
+}
 
-    pub fn apply_layout(&mut self, panel_width: i32, title_char_limit: usize) {
-        let marquee_width = panel_width.saturating_sub(240).max(140);
-        self.card.text_box.set_size_request(marquee_width, -1);
-        self.card
-            .title_label
-            .update_limits(marquee_width, title_char_limit);
+impl VolumeRow {
+    fn new(handle: MediaHandle) -> Self {
+        let root = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        root.add_css_class("unixnotis-media-volume");
+        root.set_visible(false);
+
+        let icon = gtk::Image::from_icon_name("audio-volume-medium-symbolic");
+        icon.set_tooltip_text(Some(&tr("media-volume")));
+
+        let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 1.0, 0.01);
+        scale.set_draw_value(false);
+        scale.set_hexpand(true);
+        scale.add_css_class("unixnotis-quick-slider-scale");
+
+        root.append(&icon);
+        root.append(&scale);
+
+        let row = Self {
+            root,
+            scale,
+            handle,
+            bus_name: Rc::new(RefCell::new(None)),
+            updating: Rc::new(Cell::new(false)),
+        };
+        let row_changed = row.clone();
+        row.scale.connect_value_changed(move |scale| {
+            if row_changed.updating.get() {
+                return;
+            }
+            if let Some(bus_name) = row_changed.bus_name.borrow().as_deref() {
+                row_changed.handle.set_volume(bus_name, scale.value());
+            }
+        });
+        row
+    }
+
+    fn update(&self, info: Option<&MediaInfo>) {
+        let Some((info, volume)) = info.and_then(|info| Some((info, info.volume?))) else {
+            self.root.set_visible(false);
+            self.bus_name.replace(None);
+            return;
+        };
+        self.root.set_visible(true);
+        self.bus_name.replace(Some(info.bus_name.clone()));
+        // Players echo each step of a drag late; following them would pull the knob back.
+        if self.scale.state_flags().contains(gtk::StateFlags::ACTIVE) {
+            return;
+        }
+        self.updating.set(true);
+        self.scale.set_value(volume.clamp(0.0, 1.0));
+        self.updating.set(false);
+    }
+}
+
+impl QueueSection {
+    fn new(handle: MediaHandle) -> Self {
+        let list = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        list.add_css_class("unixnotis-media-queue-list");
+
+        let expander = gtk::Expander::new(Some(&tr("media-up-next")));
+        expander.add_css_class("unixnotis-media-queue");
+        expander.set_child(Some(&list));
+        expander.set_visible(false);
+
+        Self {
+            expander,
+            list,
+            handle,
+            shown: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    fn update(&self, info: Option<&MediaInfo>) {
+        let Some(info) = info.filter(|info| !info.queue.is_empty()) else {
+            self.expander.set_visible(false);
+            self.shown.replace(None);
+            return;
+        };
+        self.expander.set_visible(true);
+
+        let mut shown = self.shown.borrow_mut();
+        if shown
+            .as_ref()
+            .is_some_and(|(bus, tracks)| *bus == info.bus_name && *tracks == info.queue)
+        {
+            return;
+        }
+        *shown = Some((info.bus_name.clone(), info.queue.clone()));
+
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+        for track in &info.queue {
+            self.list.append(&self.build_row(&info.bus_name, track));
+        }
+    }
+
+    fn build_row(&self, bus_name: &str, track: &QueuedTrack) -> gtk::Button {
+        let text = match (track.title.is_empty(), track.artist.is_empty()) {
+            (true, _) => tr("media-unknown-track"),
+            (false, true) => track.title.clone(),
+            (false, false) => format!("{} \u{2014} {}", track.title, track.artist),
+        };
+        let label = gtk::Label::new(Some(&text));
+        label.set_xalign(0.0);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        label.set_single_line_mode(true);
+
+        let button = gtk::Button::new();
+        button.add_css_class("unixnotis-media-queue-item");
+        button.set_child(Some(&label));
+        button.set_tooltip_text(Some(&tr("media-play-track")));
+
+        let handle = self.handle.clone();
+        let bus_name = bus_name.to_string();
+        let track_id = track.id.clone();
+        button.connect_clicked(move |_| handle.go_to(&bus_name, &track_id));
+        button
     }
 }
 
 impl MediaSelection {
-    fn set_players(&mut self, players: Vec<MediaInfo>) {
+    fn set_players(&mut self, mut players: Vec<MediaInfo>) {
         let current_bus = self.current_bus();
+        if !self.player_order.is_empty() {
+            // Fixed ordering keeps the carousel stable instead of following playback state.
+            players
+                .sort_by_cached_key(|info| (self.order_rank(info), info.identity.to_lowercase()));
+        }
+        // A player that just started playing takes focus unless the current one is playing too.
+        let newly_playing = players
+            .iter()
+            .position(|info| is_playing(info) && !self.playing.contains(&info.bus_name));
+        self.playing = players
+            .iter()
+            .filter(|info| is_playing(info))
+            .map(|info| info.bus_name.clone())
+            .collect();
         self.players = players;
         if self.players.is_empty() {
             self.current_index = 0;
             return;
         }
-        if let Some(current_bus) = current_bus {
-            if let Some(index) = self
-                .players
-                .iter()
-                .position(|info| info.bus_name == current_bus)
-            {
-                self.current_index = index;
-                return;
-            }
-        }
-        self.current_index = 0;
+        let current_index =
+            current_bus.and_then(|bus| self.players.iter().position(|info| info.bus_name == bus));
+        self.current_index = match (current_index, newly_playing) {
+            (Some(index), Some(playing)) if !is_playing(&self.players[index]) => playing,
+            (Some(index), _) => index,
+            (None, _) => self.players.iter().position(is_playing).unwrap_or(0),
+        };
+    }
+
+    fn order_rank(&self, info: &MediaInfo) -> usize {
+        let identity = info.identity.to_lowercase();
+        let bus_name = info.bus_name.to_lowercase();
+        self.player_order
+            .iter()
+            .position(|entry| identity.contains(entry) || bus_name.contains(entry))
+            .unwrap_or(self.player_order.len())
     }
 
     fn current(&self) -> Option<&MediaInfo> {
         self.players.get(self.current_index)
     }
 
+    fn select(&mut self, index: usize) {
+        if index < self.players.len() {
+            self.current_index = index;
+        }
+    }
+
     fn current_bus(&self) -> Option<String> {
         self.current().map(|info| info.bus_name.clone())
     }
@@ -197,6 +487,10 @@ impl MediaSelection {
     }
 }
 
+fn is_playing(info: &MediaInfo) -> bool {
+    info.playback_status == "Playing"
+}
+
 fn apply_selection(
     selection: &MediaSelection,
     card: &MediaCardWidgets,
@@ -253,10 +547,8 @@ impl MediaCardWidgets {
         let next_uri = info.art_uri.clone();
         if *self.art_uri.borrow() != next_uri {
             if let Some(uri) = info.art_uri.as_ref() {
-                let file = if uri.starts_with("http://")
-                    || uri.starts_with("https://")
-                    || uri.starts_with("file://")
-                {
+                // Remote art arrives as a cached file path once the media task fetched it.
+                let file = if uri.starts_with("file://") {
                     gio::File::for_uri(uri)
                 } else {
                     gio::File::for_path(uri)
@@ -335,9 +627,15 @@ fn build_media_card(
     let spacer = gtk::Box::new(gtk::Orientation::Horizontal, 1);
     spacer.set_hexpand(true);
 
+    let raise_button = gtk::Button::from_icon_name("window-restore-symbolic");
+    raise_button.add_css_class("unixnotis-media-raise");
+    raise_button.set_tooltip_text(Some(&tr("media-show-player")));
+    raise_button.set_valign(Align::Center);
+
     source_row.append(&source_label);
     source_row.append(&spacer);
     source_row.append(&position_label);
+    source_row.append(&raise_button);
 
     let title_label = MarqueeLabel::new("unixnotis-media-title", marquee_width, title_char_limit);
     let marquee_widget = title_label.widget();
@@ -393,7 +691,7 @@ fn build_media_card(
         }
     });
 
-    let selection_prev = selection;
+    let selection_prev = selection.clone();
     let handle_prev = handle.clone();
     prev_button.connect_clicked(move |_| {
         if let Some(bus_name) = selection_prev.borrow().current_bus() {
@@ -401,6 +699,25 @@ fn build_media_card(
         }
     });
 
+    let selection_raise = selection.clone();
+    let handle_raise = handle.clone();
+    raise_button.connect_clicked(move |_| {
+        if let Some(bus_name) = selection_raise.borrow().current_bus() {
+            handle_raise.raise(&bus_name);
+        }
+    });
+
+    // Clicking the cover art raises the player as well, like most shell media cards.
+    let art_click = gtk::GestureClick::new();
+    let selection_art = selection;
+    let handle_art = handle.clone();
+    art_click.connect_released(move |_, _, _, _| {
+        if let Some(bus_name) = selection_art.borrow().current_bus() {
+            handle_art.raise(&bus_name);
+        }
+    });
+    art_frame.add_controller(art_click);
+
     let art_uri = Rc::new(RefCell::new(None));
 
     MediaCardWidgets {
@@ -417,3 +734,80 @@ fn build_media_card(
         art_uri,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::MediaSelection;
+    use crate::media::MediaInfo;
+
+    fn player(identity: &str, status: &str) -> MediaInfo {
+        MediaInfo {
+            bus_name: format!("org.mpris.MediaPlayer2.{}", identity.to_lowercase()),
+            identity: identity.to_string(),
+            title: String::new(),
+            artist: String::new(),
+            playback_status: status.to_string(),
+            art_uri: None,
+            can_play: true,
+            can_pause: true,
+            can_next: true,
+            can_prev: true,
+            volume: None,
+            queue: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn fixed_order_sorts_players_by_identity() {
+        let mut selection = MediaSelection {
+            player_order: vec!["mpv".to_string(), "spotify".to_string()],
+            ..MediaSelection::default()
+        };
+        selection.set_players(vec![
+            player("Spotify", "Playing"),
+            player("Firefox", "Paused"),
+            player("mpv", "Paused"),
+        ]);
+        let order: Vec<&str> = selection
+            .players
+            .iter()
+            .map(|info| info.identity.as_str())
+            .collect();
+        assert_eq!(order, ["mpv", "Spotify", "Firefox"]);
+        // The playing player is preferred for the initial selection.
+        assert_eq!(selection.current().unwrap().identity, "Spotify");
+    }
+
+    #[test]
+    fn newly_playing_player_takes_focus_from_paused_one() {
+        let mut selection = MediaSelection::default();
+        selection.set_players(vec![player("mpv", "Paused"), player("Spotify", "Paused")]);
+        assert_eq!(selection.current().unwrap().identity, "mpv");
+
+        selection.set_players(vec![player("mpv", "Paused"), player("Spotify", "Playing")]);
+        assert_eq!(selection.current().unwrap().identity, "Spotify");
+
+        // Browsing back to a paused player sticks while nothing new starts playing.
+        selection.prev();
+        selection.set_players(vec![player("mpv", "Paused"), player("Spotify", "Playing")]);
+        assert_eq!(selection.current().unwrap().identity, "mpv");
+    }
+
+    #[test]
+    fn picked_player_stays_selected_across_snapshots() {
+        let mut selection = MediaSelection::default();
+        let players = vec![
+            player("mpv", "Playing"),
+            player("Spotify", "Paused"),
+            player("Firefox", "Paused"),
+        ];
+        selection.set_players(players.clone());
+        selection.select(2);
+        assert_eq!(selection.current().unwrap().identity, "Firefox");
+
+        // Out-of-range picks, e.g. from a stale dropdown, keep the current player.
+        selection.select(7);
+        selection.set_players(players);
+        assert_eq!(selection.current().unwrap().identity, "Firefox");
+    }
+}
@@ -1,6 +1,6 @@
 //! Center UI state, widget wiring, and event handling.
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -9,19 +9,33 @@ use std::time::Instant;
 use gtk::gdk;
 use gtk::prelude::*;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing::{debug, info};
-use unixnotis_core::{Config, Margins, PanelDebugLevel, PanelRequest};
+use tracing::{debug, info, warn};
+use unixnotis_core::ui_ipc::{ControlCommand, ControlEvent};
+use unixnotis_core::{
+    Config, Margins, MemoryReport, NotificationView, PanelDebugLevel, PanelGeometry, PanelRequest,
+    PanelTarget,
+};
 
 use crate::dbus::{UiCommand, UiEvent};
 use crate::debug;
 use unixnotis_ui::css::{self, CssManager};
+use unixnotis_ui::debug_hud::HudRebuild;
+use unixnotis_ui::layout_preview::LayoutPreview;
+use unixnotis_ui::surface;
 
-mod hyprland;
+mod clock;
+mod empty_state;
+pub(crate) mod hyprland;
 mod icons;
+pub(crate) mod keys;
 mod list;
 mod marquee;
 mod media_widget;
+mod new_pill;
 mod panel;
+mod rules_editor;
+mod undo_bar;
+mod user_header;
 mod widgets;
 
 /// GTK state for the notification center panel.
@@ -30,11 +44,23 @@ pub struct UiState {
     config_path: std::path::PathBuf,
     css: CssManager,
     panel: panel::PanelWidgets,
+    user_header: user_header::UserHeader,
+    clock: clock::ClockHeader,
     list: list::NotificationList,
+    icon_resolver: Rc<icons::IconResolver>,
+    new_pill: new_pill::NewNotificationsPill,
+    empty_state: empty_state::EmptyState,
+    rules_editor: rules_editor::RulesEditor,
+    undo_bar: undo_bar::UndoBar,
+    keymap: Rc<keys::PanelKeymap>,
     dnd_guard: Rc<Cell<bool>>,
     panel_visible: bool,
     panel_visible_flag: Arc<AtomicBool>,
     work_area: Option<Margins>,
+    // Scripted placement that lasts until the panel next closes.
+    geometry_override: Option<PanelGeometry>,
+    // Outline window from `noticenterctl preview-layout`.
+    layout_preview: LayoutPreview,
     media: Option<media_widget::MediaWidget>,
     media_handle: Option<crate::media::MediaHandle>,
     volume: Option<widgets::volume::VolumeWidget>,
@@ -47,6 +73,8 @@ pub struct UiState {
     refresh_source: Option<gtk::glib::SourceId>,
     last_fast_refresh: Option<Instant>,
     last_slow_refresh: Option<Instant>,
+    // When the pending frame-clock rebuild was queued; `None` while nothing is queued.
+    rebuild_requested_at: Option<Instant>,
     // Keeps the shared async runtime alive for D-Bus and media tasks.
     _runtime: Arc<tokio::runtime::Runtime>,
 }
@@ -66,26 +94,49 @@ pub struct UiStateInit {
 impl UiState {
     pub fn new(init: UiStateInit) -> Self {
         let panel = panel::build_panel_widgets(&init.app, &init.config);
-        let icon_resolver = Rc::new(icons::IconResolver::new());
+        let user_header =
+            user_header::UserHeader::new(&panel.user_container, &init.config.panel.header);
+        let clock = clock::ClockHeader::new(&panel.clock_container, &init.config.panel.clock);
+        let icon_resolver = Rc::new(icons::IconResolver::new(init.config.icons.decode_workers));
+        let fetch_tx = init.command_tx.clone();
+        icon_resolver.set_image_fetcher(move |id| {
+            let _ = fetch_tx.send(UiCommand::FetchImage(id));
+        });
         debug::set_level(PanelDebugLevel::Off);
-        let list = list::NotificationList::new(
+        let mut list = list::NotificationList::new(
             panel.scroller.clone(),
             init.command_tx.clone(),
             init.event_tx.clone(),
-            icon_resolver,
+            icon_resolver.clone(),
             init.config.history.max_active,
             init.config.history.max_entries,
+            list::RowLayout::from_config(&init.config.panel, &init.config.icons),
+        );
+        list.set_sort_order(init.config.panel.sort_order);
+        list.set_sticky_critical(init.config.panel.sticky_critical);
+        list.set_keep_expired(init.config.history.keep_expired);
+        let new_pill = new_pill::NewNotificationsPill::new(panel.new_pill.clone(), &panel.scroller);
+        let empty_state =
+            empty_state::EmptyState::new(&panel.empty_container, &init.config.panel.empty_state);
+        let rules_editor =
+            rules_editor::RulesEditor::new(&panel.rules_menu, init.command_tx.clone());
+        let undo_bar = undo_bar::UndoBar::new(
+            panel.undo_bar.clone(),
+            &panel.undo_button,
+            init.command_tx.clone(),
         );
 
         let dnd_guard = Rc::new(Cell::new(false));
         let panel_visible_flag = Arc::new(AtomicBool::new(false));
         let media = init.media_handle.as_ref().map(|handle| {
-            media_widget::MediaWidget::new(
+            let mut media = media_widget::MediaWidget::new(
                 &panel.media_container,
                 handle.clone(),
-                init.config.panel.width,
+                panel.root.width_request(),
                 init.config.media.title_char_limit,
-            )
+            );
+            media.set_player_order(&init.config.media.player_order);
+            media
         });
         if media.is_none() {
             panel.media_container.set_visible(false);
@@ -99,19 +150,49 @@ impl UiState {
                 return;
             }
             debug!(enabled = button.is_active(), "dnd toggled");
-            let _ = dnd_tx.send(UiCommand::SetDnd(button.is_active()));
+            let _ = dnd_tx.send(ControlCommand::SetDnd(button.is_active()).into());
         });
+        for (button, preset) in &panel.dnd_presets {
+            let preset = *preset;
+            let dnd_tx = init.command_tx.clone();
+            let dnd_menu = panel.dnd_menu.clone();
+            button.connect_clicked(move |_| {
+                dnd_menu.popdown();
+                let Some(seconds) = preset.seconds() else {
+                    warn!(?preset, "failed to compute dnd preset duration");
+                    return;
+                };
+                debug!(seconds, "timed dnd requested");
+                let _ = dnd_tx.send(ControlCommand::SetDndFor(seconds).into());
+            });
+        }
 
-        let clear_tx = init.command_tx.clone();
+        let sort_tx = init.event_tx.clone();
+        panel.sort_button.connect_clicked(move |_| {
+            let _ = sort_tx.try_send(UiEvent::SortCycled);
+        });
+
+        // Routed through the event loop so the undo bar only appears when something was cleared.
+        let clear_tx = init.event_tx.clone();
         panel.clear_button.connect_clicked(move |_| {
             debug!("clear all clicked");
-            let _ = clear_tx.send(UiCommand::ClearAll);
+            let _ = clear_tx.try_send(UiEvent::ClearRequested(None));
         });
+        for (button, scope) in &panel.clear_scopes {
+            let scope = *scope;
+            let clear_tx = init.event_tx.clone();
+            let clear_menu = panel.clear_menu.clone();
+            button.connect_clicked(move |_| {
+                clear_menu.popdown();
+                debug!(?scope, "scoped clear clicked");
+                let _ = clear_tx.try_send(UiEvent::ClearRequested(Some(scope)));
+            });
+        }
 
         let close_tx = init.command_tx.clone();
         panel.close_button.connect_clicked(move |_| {
             debug!("close panel clicked");
-            let _ = close_tx.send(UiCommand::ClosePanel);
+            let _ = close_tx.send(ControlCommand::ClosePanel.into());
         });
 
         if init.config.panel.close_on_click_outside {
@@ -128,7 +209,7 @@ impl UiState {
                         return;
                     }
                     if !window.is_active() {
-                        let _ = close_tx.send(UiCommand::ClosePanel);
+                        let _ = close_tx.send(ControlCommand::ClosePanel.into());
                     }
                 });
             }
@@ -140,22 +221,50 @@ impl UiState {
                     return;
                 }
                 if !window.is_active() {
-                    let _ = close_tx.send(UiCommand::ClosePanel);
+                    let _ = close_tx.send(ControlCommand::ClosePanel.into());
                 }
             });
         }
 
-        let esc_tx = init.command_tx.clone();
+        let keymap = Rc::new(keys::PanelKeymap::new(&init.config.panel.keys));
+        let keymap_clone = keymap.clone();
+        let key_window = panel.window.downgrade();
+        let key_command_tx = init.command_tx.clone();
+        let key_event_tx = init.event_tx.clone();
         let key_controller = gtk::EventControllerKey::new();
-        key_controller.connect_key_pressed(move |_, key, _, _| {
-            if key == gdk::Key::Escape {
-                let _ = esc_tx.send(UiCommand::ClosePanel);
-                return gtk::glib::Propagation::Stop;
+        // Capture phase, so list keys work wherever focus sits in the panel.
+        key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+        key_controller.connect_key_pressed(move |_, key, _, modifiers| {
+            let focus = key_window
+                .upgrade()
+                .and_then(|window| GtkWindowExt::focus(&window));
+            match keymap_clone.action_for(key, modifiers, focus) {
+                // Close requests go through the daemon to keep control state consistent.
+                Some(keys::PanelKeyAction::Close) => {
+                    let _ = key_command_tx.send(ControlCommand::ClosePanel.into());
+                }
+                Some(action) => {
+                    let _ = key_event_tx.try_send(UiEvent::PanelKey(action));
+                }
+                None => return gtk::glib::Propagation::Proceed,
             }
-            gtk::glib::Propagation::Proceed
+            gtk::glib::Propagation::Stop
         });
         panel.root.add_controller(key_controller);
 
+        let closed_tx = init.event_tx.clone();
+        surface::keep_alive_on_close(&panel.window, move || {
+            let _ = closed_tx.try_send(UiEvent::SurfaceClosed);
+        });
+        let monitors_tx = init.event_tx.clone();
+        surface::connect_monitors_changed(move || {
+            let _ = monitors_tx.try_send(UiEvent::MonitorsChanged);
+        });
+        let scale_tx = init.event_tx.clone();
+        panel.window.connect_scale_factor_notify(move |window| {
+            let _ = scale_tx.try_send(UiEvent::ScaleChanged(window.scale_factor()));
+        });
+
         if init.config.panel.respect_work_area {
             hyprland::refresh_reserved_work_area(
                 init.config.panel.output.clone(),
@@ -168,11 +277,21 @@ impl UiState {
             config_path: init.config_path,
             css: init.css,
             panel,
+            user_header,
+            clock,
             list,
+            icon_resolver,
+            new_pill,
+            empty_state,
+            rules_editor,
+            undo_bar,
+            keymap,
             dnd_guard,
             panel_visible: false,
             panel_visible_flag,
             work_area: None,
+            geometry_override: None,
+            layout_preview: LayoutPreview::default(),
             media,
             media_handle: init.media_handle,
             volume,
@@ -185,6 +304,7 @@ impl UiState {
             refresh_source: None,
             last_fast_refresh: None,
             last_slow_refresh: None,
+            rebuild_requested_at: None,
             _runtime: init.runtime,
         }
     }
@@ -205,52 +325,37 @@ impl UiState {
                 self.update_state(state);
                 self.refresh_counts();
             }
-            UiEvent::NotificationAdded(notification, _show_popup) => {
-                debug!(
-                    id = notification.id,
-                    app = %notification.app_name,
-                    "notification added"
-                );
-                self.log_debug(PanelDebugLevel::Verbose, || {
-                    format!(
-                        "notification added: {} #{}",
-                        notification.app_name, notification.id
-                    )
-                });
-                self.list.add_or_update(notification, true);
+            UiEvent::HistoryPage(history) => {
+                debug!(history = history.len(), "received history page");
+                self.list.append_history(history);
                 self.refresh_counts();
             }
-            UiEvent::NotificationUpdated(notification, _show_popup) => {
-                debug!(
-                    id = notification.id,
-                    app = %notification.app_name,
-                    "notification updated"
-                );
-                self.log_debug(PanelDebugLevel::Verbose, || {
-                    format!(
-                        "notification updated: {} #{}",
-                        notification.app_name, notification.id
-                    )
-                });
-                self.list.add_or_update(notification, true);
-                self.refresh_counts();
+            UiEvent::HistoryWanted => {
+                if let Some(offset) = self.list.take_history_request() {
+                    let _ = self.command_tx.send(UiCommand::LoadHistory(offset));
+                }
             }
-            UiEvent::NotificationClosed(id, reason) => {
-                debug!(id, ?reason, "notification closed");
-                self.log_debug(PanelDebugLevel::Verbose, || {
-                    format!("notification closed: #{id} ({reason:?})")
-                });
-                self.list.mark_closed(id, reason);
+            UiEvent::ImageFetched(id, image) => {
+                debug!(id, found = image.is_some(), "notification image fetched");
+                self.icon_resolver.image_fetched(id, image);
+            }
+            UiEvent::NotificationAdded(notification, _show_popup) => {
+                self.add_notification(notification);
                 self.refresh_counts();
             }
-            UiEvent::StateChanged(state) => {
-                debug!(dnd = state.dnd_enabled, "state updated");
-                self.log_debug(PanelDebugLevel::Info, || {
-                    format!("state changed: dnd={}", state.dnd_enabled)
-                });
-                self.update_state(state);
+            UiEvent::NotificationsAdded(notifications) => {
+                debug!(count = notifications.len(), "notification batch added");
+                for notification in notifications {
+                    self.add_notification(notification);
+                }
                 self.refresh_counts();
             }
+            UiEvent::Control(event) => self.handle_control_event(event),
+            UiEvent::PanelKey(action) => self.handle_panel_key(action),
+            UiEvent::NotificationPinned(id, pinned) => {
+                debug!(id, pinned, "notification pin changed");
+                self.list.set_pinned(id, pinned);
+            }
             UiEvent::PanelRequested(request) => {
                 debug!(?request, "panel request");
                 self.log_debug(PanelDebugLevel::Info, || {
@@ -258,12 +363,46 @@ impl UiState {
                 });
                 self.apply_panel_request(request);
             }
+            UiEvent::PanelGeometryRequested(geometry) => {
+                debug!(?geometry, "panel geometry request");
+                self.log_debug(PanelDebugLevel::Info, || {
+                    format!("panel geometry request: {:?}", geometry)
+                });
+                self.set_geometry_override(geometry);
+            }
+            UiEvent::DebugLevelRequested(level) => {
+                debug!(?level, "debug level request");
+                // Log under whichever level allows it, so followers see the change either way.
+                if level == PanelDebugLevel::Off {
+                    self.log_debug(PanelDebugLevel::Info, || "debug mode disabled".to_string());
+                    self.set_debug_level(level);
+                } else {
+                    self.set_debug_level(level);
+                    self.log_debug(PanelDebugLevel::Info, || {
+                        format!("debug level changed: {:?}", level)
+                    });
+                }
+            }
             UiEvent::GroupToggled(key) => {
                 debug!(app = %key, "group toggled");
                 self.log_debug(PanelDebugLevel::Verbose, || format!("group toggled: {key}"));
                 self.list.toggle_group(&key);
                 self.refresh_counts();
             }
+            UiEvent::ClearRequested(scope) => {
+                // Resolved here so a config reload changes what the Clear button does.
+                let scope = scope.unwrap_or(self.config.history.clear_scope);
+                let _ = self.command_tx.send(UiCommand::Clear(scope));
+            }
+            UiEvent::Cleared => self.undo_bar.show(),
+            UiEvent::SortCycled => {
+                let order = self.list.sort_order().next();
+                debug!(?order, "sort order cycled");
+                self.log_debug(PanelDebugLevel::Verbose, || {
+                    format!("sort order cycled: {order:?}")
+                });
+                self.set_sort_order(order);
+            }
             UiEvent::MediaUpdated(infos) => {
                 debug!(players = infos.len(), "media updated");
                 self.log_debug(PanelDebugLevel::Verbose, || {
@@ -284,10 +423,42 @@ impl UiState {
                 debug!("click outside detected");
                 self.close_if_click_outside();
             }
+            UiEvent::MonitorsChanged => {
+                debug!("monitors changed");
+                self.log_debug(PanelDebugLevel::Info, || {
+                    "monitors changed; re-anchoring panel".to_string()
+                });
+                // The configured output may have come back, or the panel's output may be gone.
+                self.apply_panel_geometry();
+                self.refresh_work_area();
+                self.restore_panel_surface();
+            }
+            UiEvent::ScaleChanged(scale) => {
+                debug!(scale, "panel scale factor changed");
+                self.log_debug(PanelDebugLevel::Info, || {
+                    format!("scale factor changed to {scale}; reloading icons")
+                });
+                // Decoded icons are sized for the old scale; drop them and rebind every row so
+                // they are decoded again instead of being stretched.
+                self.icon_resolver.trim();
+                self.list.rebind_rows();
+                self.apply_panel_geometry();
+                self.refresh_work_area();
+            }
+            UiEvent::SurfaceClosed => {
+                if surface::layer_shell_enabled() {
+                    debug!("panel surface closed by compositor");
+                    self.apply_panel_geometry();
+                    self.restore_panel_surface();
+                } else if self.panel_visible {
+                    // A regular window closed from its title bar; treat it like the close button.
+                    let _ = self.command_tx.send(ControlCommand::ClosePanel.into());
+                }
+            }
             UiEvent::WorkAreaUpdated(reserved) => {
                 debug!(?reserved, "work area updated");
                 self.work_area = reserved;
-                panel::apply_panel_config(&self.panel, &self.config, self.work_area);
+                self.apply_panel_geometry();
                 let message = format!("work area update: {:?}", self.work_area);
                 self.log_debug(PanelDebugLevel::Info, move || message);
             }
@@ -296,6 +467,12 @@ impl UiState {
                     self.refresh_widgets(false);
                 }
             }
+            UiEvent::RulesListed(rules) => {
+                self.log_debug(PanelDebugLevel::Verbose, || {
+                    format!("rules listed: {}", rules.len())
+                });
+                self.rules_editor.set_rules(rules);
+            }
             UiEvent::CssReload => {
                 debug!("css reload requested");
                 self.css.reload(css::DEFAULT_CSS);
@@ -305,19 +482,178 @@ impl UiState {
                 debug!("config reload requested");
                 self.reload_config();
             }
+            UiEvent::ConfigInvalid(error) => {
+                self.log_debug(PanelDebugLevel::Info, || {
+                    format!("config not applied: {error}")
+                });
+                let _ = self.command_tx.send(UiCommand::NotifyConfigInvalid(error));
+            }
+        }
+    }
+
+    fn handle_control_event(&mut self, event: ControlEvent) {
+        match event {
+            ControlEvent::NotificationUpdated(notification, _show_popup) => {
+                debug!(
+                    id = notification.id,
+                    app = %notification.app_name,
+                    "notification updated"
+                );
+                self.log_debug(PanelDebugLevel::Verbose, || {
+                    format!(
+                        "notification updated: {} #{}",
+                        notification.app_name, notification.id
+                    )
+                });
+                self.list.add_or_update(notification, true);
+                self.refresh_counts();
+            }
+            ControlEvent::NotificationClosed(id, reason) => {
+                debug!(id, ?reason, "notification closed");
+                self.log_debug(PanelDebugLevel::Verbose, || {
+                    format!("notification closed: #{id} ({reason:?})")
+                });
+                self.list.mark_closed(id, reason);
+                self.refresh_counts();
+            }
+            ControlEvent::StateChanged(state) => {
+                debug!(dnd = state.dnd_enabled, "state updated");
+                self.log_debug(PanelDebugLevel::Info, || {
+                    format!("state changed: dnd={}", state.dnd_enabled)
+                });
+                self.update_state(state);
+                self.refresh_counts();
+            }
+            ControlEvent::LayoutPreview(seconds) => {
+                debug!(seconds, "layout preview request");
+                self.show_layout_preview(seconds);
+            }
+            ControlEvent::MemoryReportRequested => {
+                let mut caches = self.icon_resolver.memory_estimates();
+                caches.extend(self.list.memory_estimates());
+                let report = MemoryReport::for_current_process("center", caches);
+                let _ = self
+                    .command_tx
+                    .send(ControlCommand::ReportMemory(report).into());
+            }
+            ControlEvent::TrimCaches => {
+                debug!("cache trim requested");
+                self.icon_resolver.trim();
+                self.list.shrink_buffers();
+                self.log_debug(PanelDebugLevel::Info, || "caches trimmed".to_string());
+            }
         }
     }
 
-    pub fn flush_list_rebuild(&mut self) {
-        self.list.flush_rebuild();
+    /// Move the list cursor or act on the row under it for a bound key.
+    fn handle_panel_key(&mut self, action: keys::PanelKeyAction) {
+        let step = match action {
+            keys::PanelKeyAction::Next => Some(list::CursorMove::Next),
+            keys::PanelKeyAction::Previous => Some(list::CursorMove::Previous),
+            keys::PanelKeyAction::First => Some(list::CursorMove::First),
+            keys::PanelKeyAction::Last => Some(list::CursorMove::Last),
+            keys::PanelKeyAction::Activate
+            | keys::PanelKeyAction::Dismiss
+            | keys::PanelKeyAction::Close => None,
+        };
+        if let Some(step) = step {
+            self.list.move_cursor(step);
+        } else {
+            match (action, self.list.cursor_target()) {
+                (keys::PanelKeyAction::Activate, Some(list::CursorTarget::Group(key))) => {
+                    debug!(app = %key, "group toggled from keyboard");
+                    self.list.toggle_group(&key);
+                    self.refresh_counts();
+                }
+                (
+                    keys::PanelKeyAction::Activate,
+                    Some(list::CursorTarget::Notification(notification)),
+                ) => {
+                    // Rows without a default action have nothing for Enter to do.
+                    if notification
+                        .actions
+                        .iter()
+                        .any(|action| action.key == "default")
+                    {
+                        debug!(id = notification.id, "default action invoked from keyboard");
+                        let _ = self.command_tx.send(
+                            ControlCommand::InvokeAction {
+                                id: notification.id,
+                                action_key: "default".to_string(),
+                            }
+                            .into(),
+                        );
+                    }
+                }
+                (
+                    keys::PanelKeyAction::Dismiss,
+                    Some(list::CursorTarget::Notification(notification)),
+                ) => {
+                    debug!(id = notification.id, "dismissed from keyboard");
+                    let _ = self
+                        .command_tx
+                        .send(ControlCommand::Dismiss(notification.id).into());
+                }
+                _ => {}
+            }
+        }
+        self.keymap.set_cursor_shown(self.list.has_cursor());
     }
 
-    pub fn list_needs_rebuild(&self) -> bool {
-        self.list.needs_rebuild()
+    /// Apply pending list changes on the next frame, so an event storm costs one rebuild.
+    ///
+    /// The frame clock only ticks while the panel is mapped; a hidden panel flushes from idle
+    /// instead, and a flush queued just before the panel hides runs on its first frame back.
+    pub fn schedule_list_rebuild(ui: &Rc<RefCell<Self>>) {
+        let mut state = ui.borrow_mut();
+        if !state.list.needs_rebuild() || state.rebuild_requested_at.is_some() {
+            return;
+        }
+        state.rebuild_requested_at = Some(Instant::now());
+        let ui_weak = Rc::downgrade(ui);
+        if state.panel.window.is_mapped() {
+            state.panel.window.add_tick_callback(move |_, _| {
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.borrow_mut().flush_list_rebuild();
+                }
+                gtk::glib::ControlFlow::Break
+            });
+        } else {
+            gtk::glib::idle_add_local_once(move || {
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.borrow_mut().flush_list_rebuild();
+                }
+            });
+        }
+    }
+
+    fn flush_list_rebuild(&mut self) {
+        let requested_at = self.rebuild_requested_at.take();
+        let started = Instant::now();
+        let requests = self.list.flush_rebuild();
+        self.keymap.set_cursor_shown(self.list.has_cursor());
+        if requests == 0 {
+            return;
+        }
+        let rebuild = HudRebuild {
+            wait: requested_at
+                .map(|at| started.duration_since(at))
+                .unwrap_or_default(),
+            took: started.elapsed(),
+            requests,
+        };
+        self.panel.debug_hud.set_rebuild(rebuild);
+        self.log_debug(PanelDebugLevel::Verbose, || {
+            format!(
+                "list rebuilt: {requests} changes in {:?}, {:?} after the first",
+                rebuild.took, rebuild.wait
+            )
+        });
     }
 
     fn reload_config(&mut self) {
         let widgets_before = self.config.widgets.clone();
+        let sort_before = self.config.panel.sort_order;
         let config = match Config::load_from_path(&self.config_path) {
             Ok(config) => config,
             Err(err) => {
@@ -343,8 +679,20 @@ impl UiState {
         self.config = config.clone();
         debug!("config reloaded");
         self.css.update_theme(theme_paths, config.theme.clone());
+        self.css.set_density(config.panel.density);
         self.css.reload(css::DEFAULT_CSS);
-        panel::apply_panel_config(&self.panel, &config, self.work_area);
+        self.apply_panel_geometry();
+        self.user_header.apply_config(&config.panel.header);
+        self.clock.apply_config(&config.panel.clock);
+        self.empty_state.apply_config(&config.panel.empty_state);
+        self.list
+            .set_row_layout(list::RowLayout::from_config(&config.panel, &config.icons));
+        self.list.set_sticky_critical(config.panel.sticky_critical);
+        self.keymap.apply_config(&config.panel.keys);
+        if config.panel.sort_order != sort_before {
+            // Only a config change overrides the order picked from the panel toggle.
+            self.set_sort_order(config.panel.sort_order);
+        }
         self.log_debug(PanelDebugLevel::Info, || {
             "panel config applied after reload".to_string()
         });
@@ -355,15 +703,26 @@ impl UiState {
             debug!("widget config unchanged; skipping rebuild");
         }
         self.restart_refresh_timer();
-        if config.panel.respect_work_area {
+        self.refresh_work_area();
+    }
+
+    fn refresh_work_area(&mut self) {
+        if self.config.panel.respect_work_area {
             self.work_area = None;
             hyprland::refresh_reserved_work_area(
-                config.panel.output.clone(),
+                self.config.panel.output.clone(),
                 self.event_tx.clone(),
             );
         }
     }
 
+    /// Re-map the panel after its surface was closed while it should be open.
+    fn restore_panel_surface(&self) {
+        if self.panel_visible && !self.panel.window.is_visible() {
+            self.panel.window.set_visible(true);
+        }
+    }
+
     fn apply_media_config(&mut self, config: &Config) {
         if !config.media.enabled {
             self.panel.media_container.set_visible(false);
@@ -377,16 +736,21 @@ impl UiState {
         match (self.media.as_mut(), self.media_handle.as_ref()) {
             (Some(media), _) => {
                 debug!("media layout updated");
-                media.apply_layout(config.panel.width, config.media.title_char_limit);
+                media.apply_layout(
+                    self.panel.root.width_request(),
+                    config.media.title_char_limit,
+                );
+                media.set_player_order(&config.media.player_order);
             }
             (None, Some(handle)) => {
                 debug!("media widget created");
-                let media = media_widget::MediaWidget::new(
+                let mut media = media_widget::MediaWidget::new(
                     &self.panel.media_container,
                     handle.clone(),
-                    config.panel.width,
+                    self.panel.root.width_request(),
                     config.media.title_char_limit,
                 );
+                media.set_player_order(&config.media.player_order);
                 self.media = Some(media);
             }
             (None, None) => {
@@ -422,41 +786,152 @@ impl UiState {
         }
     }
 
+    fn set_sort_order(&mut self, order: unixnotis_core::PanelSortOrder) {
+        self.list.set_sort_order(order);
+        self.panel.sort_button.set_label(&order.label());
+    }
+
     fn update_state(&mut self, state: unixnotis_core::ControlState) {
+        self.list.set_history_total(state.history_count as usize);
         self.dnd_guard.set(true);
         self.panel.dnd_toggle.set_active(state.dnd_enabled);
         self.dnd_guard.set(false);
+        let until = (state.dnd_enabled && state.dnd_until > 0)
+            .then(|| gtk::glib::DateTime::from_unix_local(state.dnd_until).ok())
+            .flatten()
+            .and_then(|until| until.format("%H:%M").ok())
+            .map(|time| {
+                unixnotis_core::i18n::tr_args("panel-dnd-until", &[("time", time.as_str().into())])
+            });
+        self.panel.dnd_toggle.set_tooltip_text(until.as_deref());
     }
 
-    fn refresh_counts(&self) {
+    fn add_notification(&mut self, notification: NotificationView) {
+        debug!(
+            id = notification.id,
+            app = %notification.app_name,
+            "notification added"
+        );
+        self.log_debug(PanelDebugLevel::Verbose, || {
+            format!(
+                "notification added: {} #{}",
+                notification.app_name, notification.id
+            )
+        });
+        if !self.panel_visible {
+            // Decode while the panel is closed so the row has its icon on open.
+            let size =
+                list::RowLayout::from_config(&self.config.panel, &self.config.icons).icon_size;
+            let scale = self.panel.window.scale_factor();
+            self.icon_resolver.prefetch(&notification, size, scale);
+        }
+        self.list.add_or_update(notification, true);
+        self.new_pill.note_arrival();
+    }
+
+    fn refresh_counts(&mut self) {
         let total = self.list.total_count();
         self.panel.header_count.set_text(&format!("{total}"));
+        self.empty_state.set_empty(total == 0);
     }
 
     fn apply_panel_request(&mut self, request: PanelRequest) {
         match request.action {
             unixnotis_core::PanelAction::Open => {
-                debug::set_level(PanelDebugLevel::Off);
+                self.set_debug_level(PanelDebugLevel::Off);
                 self.set_visible(true);
             }
             unixnotis_core::PanelAction::Close => {
-                debug::set_level(PanelDebugLevel::Off);
+                self.set_debug_level(PanelDebugLevel::Off);
                 self.set_visible(false);
             }
             unixnotis_core::PanelAction::Toggle => {
                 if !self.panel_visible {
-                    debug::set_level(PanelDebugLevel::Off);
+                    self.set_debug_level(PanelDebugLevel::Off);
                 }
                 self.set_visible(!self.panel_visible);
             }
         }
 
         if request.debug != PanelDebugLevel::Off {
-            debug::set_level(request.debug);
+            self.set_debug_level(request.debug);
             self.log_debug(PanelDebugLevel::Info, || {
                 format!("debug mode enabled: {:?}", request.debug)
             });
         }
+        if self.panel_visible {
+            self.reveal_target(request.target, &request.app);
+        }
+    }
+
+    fn reveal_target(&mut self, target: PanelTarget, app: &str) {
+        match target {
+            PanelTarget::None => {}
+            PanelTarget::Media => {
+                if self.media.is_none() || !self.panel.media_container.is_visible() {
+                    debug!("media target requested without a media widget");
+                    return;
+                }
+                self.panel
+                    .media_container
+                    .child_focus(gtk::DirectionType::TabForward);
+            }
+            PanelTarget::App => {
+                if !self.list.reveal_group(app) {
+                    debug!(app, "no notifications for requested app");
+                }
+            }
+        }
+    }
+
+    fn set_geometry_override(&mut self, mut geometry: PanelGeometry) {
+        if geometry.is_empty() {
+            self.geometry_override = None;
+        } else {
+            if geometry.output.trim().eq_ignore_ascii_case("focused") {
+                // Resolve once so the panel stays put if focus moves while it is open.
+                geometry.output = match hyprland::focused_monitor() {
+                    Ok(output) => output.unwrap_or_default(),
+                    Err(err) => {
+                        warn!(
+                            ?err,
+                            "failed to query focused monitor; keeping configured output"
+                        );
+                        String::new()
+                    }
+                };
+            }
+            self.geometry_override = Some(geometry);
+        }
+        self.apply_panel_geometry();
+    }
+
+    /// Apply the configured panel placement with any scripted override on top.
+    fn apply_panel_geometry(&mut self) {
+        let width = panel::apply_panel_config(
+            &self.panel,
+            &self.config,
+            self.geometry_override.as_ref(),
+            self.work_area,
+        );
+        // Output blocks can narrow the panel, so keep the media marquee in step.
+        if let Some(media) = self.media.as_mut() {
+            media.apply_layout(width, self.config.media.title_char_limit);
+        }
+    }
+
+    fn show_layout_preview(&self, seconds: u32) {
+        let Some(app) = self.panel.window.application() else {
+            return;
+        };
+        // Outline the placement the panel would open with right now, override included.
+        let window = panel::build_outline_window(
+            &app,
+            &self.config,
+            self.geometry_override.as_ref(),
+            self.work_area,
+        );
+        self.layout_preview.show(vec![window], seconds);
     }
 
     fn set_visible(&mut self, visible: bool) {
@@ -500,7 +975,13 @@ impl UiState {
                 toggles.set_watch_active(false);
             }
             self.stop_refresh_timer();
-            debug::set_level(PanelDebugLevel::Off);
+            // Each opening starts without a cursor, so Enter reaches the focused button.
+            self.list.clear_cursor();
+            self.keymap.set_cursor_shown(false);
+            if self.geometry_override.take().is_some() {
+                self.apply_panel_geometry();
+            }
+            self.set_debug_level(PanelDebugLevel::Off);
         }
     }
 
@@ -518,7 +999,7 @@ impl UiState {
         self.log_debug(PanelDebugLevel::Info, || {
             "click outside detected; requesting close".to_string()
         });
-        let _ = self.command_tx.send(UiCommand::ClosePanel);
+        let _ = self.command_tx.send(ControlCommand::ClosePanel.into());
     }
 
     fn refresh_widgets(&mut self, force: bool) {
@@ -556,6 +1037,8 @@ impl UiState {
                     .map(|last| now.duration_since(last).as_millis() as u64 >= slow_ms)
                     .unwrap_or(true));
         if refresh_slow {
+            self.user_header.refresh();
+            self.clock.refresh();
             if let Some(toggles) = self.toggles.as_ref() {
                 if force || toggles.needs_polling() {
                     toggles.refresh();
@@ -592,7 +1075,14 @@ impl UiState {
             .unwrap_or(false);
         let stats_poll = self.stats.is_some();
         let cards_poll = self.cards.is_some();
-        if !(volume_poll || brightness_poll || toggles_poll || stats_poll || cards_poll) {
+        let clock_poll = self.clock.is_enabled() || self.user_header.is_enabled();
+        if !(volume_poll
+            || brightness_poll
+            || toggles_poll
+            || stats_poll
+            || cards_poll
+            || clock_poll)
+        {
             return;
         }
         let fast = self.config.widgets.refresh_interval_ms;
@@ -633,6 +1123,13 @@ impl UiState {
         debug::log(level, message);
     }
 
+    fn set_debug_level(&self, level: PanelDebugLevel) {
+        debug::set_level(level);
+        self.panel
+            .debug_hud
+            .set_enabled(level.allows(PanelDebugLevel::Info));
+    }
+
     fn is_click_outside_panel(&self) -> bool {
         // Hyprland focus changes can be hover-driven; only close when a mouse button is down.
         let Some(display) = gdk::Display::default() else {
@@ -715,7 +1212,8 @@ fn build_extra_widgets(
     Option<widgets::stats::StatGrid>,
     Option<widgets::cards::CardGrid>,
 ) {
-    let toggles = widgets::toggles::ToggleGrid::new(&config.widgets.toggles);
+    let toggles =
+        widgets::toggles::ToggleGrid::new(&config.widgets.toggles, &config.widgets.night_light);
     if let Some(grid) = toggles.as_ref() {
         panel.toggle_container.set_visible(true);
         panel.toggle_container.append(grid.root());
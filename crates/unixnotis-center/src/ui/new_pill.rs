@@ -0,0 +1,54 @@
+//! Floating "new notifications" pill shown while the list is scrolled into history.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use unixnotis_core::i18n::tr_args;
+
+/// Counts arrivals while scrolled down and offers a jump back to the top.
+pub struct NewNotificationsPill {
+    button: gtk::Button,
+    adjustment: gtk::Adjustment,
+    pending: Rc<Cell<u32>>,
+}
+
+impl NewNotificationsPill {
+    pub fn new(button: gtk::Button, scroller: &gtk::ScrolledWindow) -> Self {
+        let adjustment = scroller.vadjustment();
+        let pending = Rc::new(Cell::new(0));
+
+        let adjustment_clone = adjustment.clone();
+        button.connect_clicked(move |_| {
+            // Reaching the top hides the pill through the value-changed handler below.
+            adjustment_clone.set_value(adjustment_clone.lower());
+        });
+
+        let button_clone = button.clone();
+        let pending_clone = pending.clone();
+        adjustment.connect_value_changed(move |adjustment| {
+            if adjustment.value() <= adjustment.lower() {
+                pending_clone.set(0);
+                button_clone.set_visible(false);
+            }
+        });
+
+        Self {
+            button,
+            adjustment,
+            pending,
+        }
+    }
+
+    /// Record a new notification; only counts when the viewport is away from the top.
+    pub fn note_arrival(&self) {
+        if self.adjustment.value() <= self.adjustment.lower() {
+            return;
+        }
+        let pending = self.pending.get().saturating_add(1);
+        self.pending.set(pending);
+        self.button
+            .set_label(&tr_args("panel-new-pill", &[("count", pending.into())]));
+        self.button.set_visible(true);
+    }
+}
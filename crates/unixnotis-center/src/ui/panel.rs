@@ -2,25 +2,96 @@
 
 use gtk::gdk;
 use gtk::gdk::prelude::*;
+use gtk::glib;
 use gtk::prelude::*;
 use gtk::Align;
-use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
-use unixnotis_core::{Anchor, Config, Margins, PanelKeyboardInteractivity};
+use gtk4_layer_shell::{Edge, KeyboardMode, LayerShell};
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::{
+    Anchor, ClearScope, Config, ExclusiveZone, Margins, PanelConfig, PanelGeometry,
+    PanelKeyboardInteractivity,
+};
+use unixnotis_ui::debug_hud::{DebugHud, HudPlacement};
+use unixnotis_ui::surface;
 
 /// GTK widgets backing the notification center panel window.
 pub struct PanelWidgets {
     pub window: gtk::ApplicationWindow,
     pub root: gtk::Box,
+    pub user_container: gtk::Box,
+    pub clock_container: gtk::Box,
     pub quick_controls: gtk::Box,
     pub toggle_container: gtk::Box,
     pub stat_container: gtk::Box,
     pub card_container: gtk::Box,
     pub scroller: gtk::ScrolledWindow,
+    pub new_pill: gtk::Button,
+    pub undo_bar: gtk::Box,
+    pub undo_button: gtk::Button,
+    pub empty_container: gtk::Box,
     pub media_container: gtk::Box,
     pub header_count: gtk::Label,
+    pub sort_button: gtk::Button,
     pub dnd_toggle: gtk::ToggleButton,
+    pub dnd_menu: gtk::MenuButton,
+    pub dnd_presets: Vec<(gtk::Button, DndPreset)>,
+    pub rules_menu: gtk::MenuButton,
     pub clear_button: gtk::Button,
+    pub clear_menu: gtk::MenuButton,
+    pub clear_scopes: Vec<(gtk::Button, ClearScope)>,
     pub close_button: gtk::Button,
+    pub debug_hud: DebugHud,
+}
+
+/// Timed Do Not Disturb choices offered next to the DND toggle.
+#[derive(Debug, Clone, Copy)]
+pub enum DndPreset {
+    Minutes(u32),
+    UntilTomorrow,
+}
+
+impl DndPreset {
+    const ALL: [DndPreset; 3] = [
+        DndPreset::Minutes(30),
+        DndPreset::Minutes(60),
+        DndPreset::UntilTomorrow,
+    ];
+
+    fn label(self) -> String {
+        match self {
+            DndPreset::Minutes(60) => tr("panel-dnd-for-hour"),
+            DndPreset::Minutes(minutes) => {
+                tr_args("panel-dnd-for-minutes", &[("minutes", minutes.into())])
+            }
+            DndPreset::UntilTomorrow => tr("panel-dnd-until-tomorrow"),
+        }
+    }
+
+    /// Seconds from now until this preset ends.
+    pub fn seconds(self) -> Option<u32> {
+        match self {
+            DndPreset::Minutes(minutes) => Some(minutes * 60),
+            DndPreset::UntilTomorrow => {
+                let now = glib::DateTime::now_local().ok()?;
+                // Late-night choices still mean "this morning", not the morning after.
+                let day = if now.hour() < 4 {
+                    now.clone()
+                } else {
+                    now.add_days(1).ok()?
+                };
+                let end = glib::DateTime::from_local(
+                    day.year(),
+                    day.month(),
+                    day.day_of_month(),
+                    8,
+                    0,
+                    0.0,
+                )
+                .ok()?;
+                u32::try_from(end.difference(&now).as_seconds()).ok()
+            }
+        }
+    }
 }
 
 pub fn build_panel_widgets(app: &gtk::Application, config: &Config) -> PanelWidgets {
@@ -30,23 +101,18 @@ pub fn build_panel_widgets(app: &gtk::Application, config: &Config) -> PanelWidg
     window.set_title(Some("UnixNotis Center"));
     window.add_css_class("unixnotis-panel-window");
 
-    window.init_layer_shell();
-    window.set_namespace(Some("unixnotis-panel"));
-    window.set_layer(Layer::Overlay);
-    apply_anchor(&window, config.panel.anchor, config.panel.margin);
-    window.set_exclusive_zone(0);
-    window.set_keyboard_mode(map_keyboard_mode(config.panel.keyboard_interactivity));
-
-    let monitor = if let Some(output) = config.panel.output.as_ref() {
-        find_monitor(output).or_else(default_monitor)
-    } else {
-        default_monitor()
-    };
-    if let Some(monitor) = monitor.as_ref() {
-        window.set_monitor(Some(monitor));
+    let layered = surface::init_layer_surface(&window, "unixnotis-panel");
+    let (placement, monitor) = resolve_placement(config, None);
+    if layered {
+        apply_anchor(&window, placement.anchor, placement.margin);
+        apply_exclusive_zone(&window, &placement);
+        window.set_keyboard_mode(map_keyboard_mode(placement.keyboard_interactivity));
+        if let Some(monitor) = monitor.as_ref() {
+            window.set_monitor(Some(monitor));
+        }
     }
 
-    let (width, height) = resolve_panel_size(config, monitor.as_ref(), None);
+    let (width, height) = resolve_panel_size(&placement, monitor.as_ref(), None);
     window.set_default_size(width, height);
     if height > 0 {
         window.set_size_request(width, height);
@@ -62,11 +128,17 @@ pub fn build_panel_widgets(app: &gtk::Application, config: &Config) -> PanelWidg
     // Keep the panel width stable regardless of child content.
     root.set_size_request(width, -1);
 
+    let user_container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    user_container.add_css_class("unixnotis-user-section");
+
+    let clock_container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    clock_container.add_css_class("unixnotis-clock-section");
+
     let header = gtk::Box::new(gtk::Orientation::Horizontal, 8);
     header.add_css_class("unixnotis-panel-header");
 
     let title_box = gtk::Box::new(gtk::Orientation::Vertical, 2);
-    let title = gtk::Label::new(Some("Notifications"));
+    let title = gtk::Label::new(Some(&tr("panel-title")));
     title.set_xalign(0.0);
     title.add_css_class("unixnotis-panel-title");
     let count = gtk::Label::new(Some("0"));
@@ -81,15 +153,68 @@ pub fn build_panel_widgets(app: &gtk::Application, config: &Config) -> PanelWidg
     let actions = gtk::Box::new(gtk::Orientation::Horizontal, 6);
     actions.add_css_class("unixnotis-panel-actions");
 
-    let dnd_toggle = gtk::ToggleButton::with_label("Do Not Disturb");
+    let sort_button = gtk::Button::with_label(&config.panel.sort_order.label());
+    sort_button.add_css_class("unixnotis-panel-action");
+    sort_button.add_css_class("unixnotis-panel-sort");
+    sort_button.set_tooltip_text(Some(&tr("panel-sort-tooltip")));
+    let dnd_toggle = gtk::ToggleButton::with_label(&tr("panel-dnd"));
     dnd_toggle.add_css_class("unixnotis-panel-action");
-    let clear_button = gtk::Button::with_label("Clear");
+    let dnd_menu = gtk::MenuButton::new();
+    dnd_menu.set_icon_name("pan-down-symbolic");
+    dnd_menu.add_css_class("unixnotis-panel-action");
+    dnd_menu.add_css_class("unixnotis-dnd-menu");
+    dnd_menu.set_tooltip_text(Some(&tr("panel-dnd-menu-tooltip")));
+    let dnd_popover = gtk::Popover::new();
+    dnd_popover.add_css_class("unixnotis-dnd-presets");
+    let preset_box = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    let mut dnd_presets = Vec::new();
+    for preset in DndPreset::ALL {
+        let button = gtk::Button::with_label(&preset.label());
+        button.add_css_class("unixnotis-dnd-preset");
+        preset_box.append(&button);
+        dnd_presets.push((button, preset));
+    }
+    dnd_popover.set_child(Some(&preset_box));
+    dnd_menu.set_popover(Some(&dnd_popover));
+    // The popover itself is filled in by the rules editor.
+    let rules_menu = gtk::MenuButton::new();
+    rules_menu.set_icon_name("preferences-system-notifications-symbolic");
+    rules_menu.add_css_class("unixnotis-panel-action");
+    rules_menu.add_css_class("unixnotis-rules-menu");
+    rules_menu.set_tooltip_text(Some(&tr("panel-rules-tooltip")));
+    let clear_button = gtk::Button::with_label(&tr("panel-clear"));
     clear_button.add_css_class("unixnotis-panel-action");
-    let close_button = gtk::Button::with_label("Close");
+    // The button follows `history.clear_scope`; the menu clears a chosen list once.
+    let clear_menu = gtk::MenuButton::new();
+    clear_menu.set_icon_name("pan-down-symbolic");
+    clear_menu.add_css_class("unixnotis-panel-action");
+    clear_menu.add_css_class("unixnotis-clear-menu");
+    clear_menu.set_tooltip_text(Some(&tr("panel-clear-menu-tooltip")));
+    let clear_popover = gtk::Popover::new();
+    clear_popover.add_css_class("unixnotis-clear-scopes");
+    let scope_box = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    let mut clear_scopes = Vec::new();
+    for (scope, label) in [
+        (ClearScope::History, "panel-clear-history"),
+        (ClearScope::Active, "panel-clear-active"),
+        (ClearScope::All, "panel-clear-all"),
+    ] {
+        let button = gtk::Button::with_label(&tr(label));
+        button.add_css_class("unixnotis-clear-scope");
+        scope_box.append(&button);
+        clear_scopes.push((button, scope));
+    }
+    clear_popover.set_child(Some(&scope_box));
+    clear_menu.set_popover(Some(&clear_popover));
+    let close_button = gtk::Button::with_label(&tr("panel-close"));
     close_button.add_css_class("unixnotis-panel-action");
 
+    actions.append(&sort_button);
     actions.append(&dnd_toggle);
+    actions.append(&dnd_menu);
+    actions.append(&rules_menu);
     actions.append(&clear_button);
+    actions.append(&clear_menu);
     actions.append(&close_button);
 
     let spacer = gtk::Box::new(gtk::Orientation::Horizontal, 1);
@@ -127,13 +252,56 @@ pub fn build_panel_widgets(app: &gtk::Application, config: &Config) -> PanelWidg
     scroller.set_min_content_width(width);
     scroller.set_max_content_width(width);
 
+    // Floats over the list so arrivals can be announced without moving the viewport.
+    let new_pill = gtk::Button::with_label("");
+    new_pill.add_css_class("unixnotis-new-pill");
+    new_pill.set_halign(Align::Center);
+    new_pill.set_valign(Align::Start);
+    new_pill.set_visible(false);
+    let undo_bar = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    undo_bar.add_css_class("unixnotis-undo-bar");
+    undo_bar.set_halign(Align::Center);
+    undo_bar.set_valign(Align::End);
+    undo_bar.set_visible(false);
+    let undo_label = gtk::Label::new(Some(&tr("panel-undo-cleared")));
+    undo_label.add_css_class("unixnotis-undo-label");
+    let undo_button = gtk::Button::with_label(&tr("panel-undo"));
+    undo_button.add_css_class("unixnotis-undo-button");
+    undo_bar.append(&undo_label);
+    undo_bar.append(&undo_button);
+    let empty_container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    empty_container.add_css_class("unixnotis-empty-section");
+    empty_container.set_halign(Align::Center);
+    empty_container.set_valign(Align::Center);
+    empty_container.set_can_target(false);
+    empty_container.set_visible(false);
+    let list_overlay = gtk::Overlay::new();
+    list_overlay.set_vexpand(true);
+    list_overlay.set_hexpand(true);
+    list_overlay.set_child(Some(&scroller));
+    list_overlay.add_overlay(&empty_container);
+    list_overlay.add_overlay(&new_pill);
+    list_overlay.add_overlay(&undo_bar);
+    // Placement readout for `open-panel --debug info`; hidden at lower levels.
+    let debug_hud = DebugHud::new();
+    debug_hud.attach(&window);
+    debug_hud.set_placement(HudPlacement {
+        anchor: placement.anchor,
+        margin: placement.margin,
+        work_area: None,
+        requested: (width, height),
+    });
+    list_overlay.add_overlay(debug_hud.widget());
+
+    root.append(&user_container);
+    root.append(&clock_container);
     root.append(&header);
     root.append(&quick_controls);
     root.append(&media_container);
     root.append(&toggle_container);
     root.append(&stat_container);
     root.append(&card_container);
-    root.append(&scroller);
+    root.append(&list_overlay);
 
     window.set_child(Some(&root));
     window.set_visible(false);
@@ -141,30 +309,118 @@ pub fn build_panel_widgets(app: &gtk::Application, config: &Config) -> PanelWidg
     PanelWidgets {
         window,
         root,
+        user_container,
+        clock_container,
         quick_controls,
         toggle_container,
         stat_container,
         card_container,
         scroller,
+        new_pill,
+        undo_bar,
+        undo_button,
+        empty_container,
         media_container,
         header_count: count,
+        sort_button,
         dnd_toggle,
+        dnd_menu,
+        dnd_presets,
+        rules_menu,
         clear_button,
+        clear_menu,
+        clear_scopes,
         close_button,
+        debug_hud,
     }
 }
 
-fn resolve_panel_size(
+/// Click-through outline of the panel surface for `noticenterctl preview-layout`.
+pub fn build_outline_window(
+    app: &gtk::Application,
+    config: &Config,
+    geometry: Option<&PanelGeometry>,
+    reserved: Option<Margins>,
+) -> gtk::ApplicationWindow {
+    // Stand-in for content-sized panels, whose real height depends on what they show.
+    const NATURAL_HEIGHT: i32 = 480;
+
+    let window = gtk::ApplicationWindow::new(app);
+    window.set_decorated(false);
+    window.set_resizable(false);
+    window.set_title(Some("UnixNotis Layout Preview"));
+    window.add_css_class("unixnotis-panel-window");
+
+    let layered = surface::init_layer_surface(&window, "unixnotis-layout-preview");
+    let (placement, monitor) = resolve_placement(config, geometry);
+    if layered {
+        apply_anchor(&window, placement.anchor, placement.margin);
+        window.set_exclusive_zone(0);
+        window.set_keyboard_mode(KeyboardMode::None);
+        if let Some(monitor) = monitor.as_ref() {
+            window.set_monitor(Some(monitor));
+        }
+    }
+    let (width, height) = resolve_panel_size(&placement, monitor.as_ref(), reserved);
+    let height = if height > 0 { height } else { NATURAL_HEIGHT };
+    window.set_default_size(width, height);
+    window.set_size_request(width, height);
+
+    let outline = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    outline.add_css_class("unixnotis-layout-preview");
+    outline.add_css_class("unixnotis-layout-preview-panel");
+    let label = gtk::Label::new(Some(&tr("preview-panel")));
+    label.add_css_class("unixnotis-layout-preview-label");
+    label.set_vexpand(true);
+    outline.append(&label);
+    window.set_child(Some(&outline));
+
+    window.connect_realize(|window| {
+        if let Some(surface) = window.surface() {
+            surface.set_input_region(&gtk::cairo::Region::create());
+        }
+    });
+    window
+}
+
+/// Pick the panel's monitor, then layer the matching `[outputs]` block and any scripted
+/// geometry override over the configured panel settings.
+fn resolve_placement(
     config: &Config,
+    geometry: Option<&PanelGeometry>,
+) -> (PanelConfig, Option<gdk::Monitor>) {
+    let output = geometry
+        .map(|geometry| geometry.output.trim())
+        .filter(|output| !output.is_empty())
+        .or(config.panel.output.as_deref());
+    let monitor = match output {
+        Some(output) => find_monitor(output).or_else(default_monitor),
+        None => default_monitor(),
+    };
+    let names: Vec<String> = monitor
+        .iter()
+        .flat_map(|monitor| [monitor.connector(), monitor.model()])
+        .flatten()
+        .map(|name| name.to_string())
+        .collect();
+    let mut panel = config.panel_for_output(&names);
+    if let Some(geometry) = geometry {
+        geometry.apply_to(&mut panel);
+    }
+    (panel, monitor)
+}
+
+fn resolve_panel_size(
+    panel: &PanelConfig,
     monitor: Option<&gdk::Monitor>,
     reserved: Option<Margins>,
 ) -> (i32, i32) {
-    let width = config.panel.width.max(1);
-    if config.panel.height > 0 {
-        return (width, config.panel.height);
+    let width = panel.width.max(1);
+    if panel.height > 0 {
+        return (width, panel.height);
     }
-    if matches!(config.panel.anchor, Anchor::Left | Anchor::Right) {
-        if let Some(height) = compute_side_panel_height(config, monitor, reserved) {
+    if matches!(panel.anchor, Anchor::Left | Anchor::Right) {
+        if let Some(height) = compute_side_panel_height(panel, monitor, reserved) {
             return (width, height);
         }
     }
@@ -173,21 +429,21 @@ fn resolve_panel_size(
 }
 
 fn compute_side_panel_height(
-    config: &Config,
+    panel: &PanelConfig,
     monitor: Option<&gdk::Monitor>,
     reserved: Option<Margins>,
 ) -> Option<i32> {
     const MIN_HEIGHT: i32 = 520;
     const BOTTOM_PAD: i32 = 96;
 
-    if !matches!(config.panel.anchor, Anchor::Left | Anchor::Right) {
+    if !matches!(panel.anchor, Anchor::Left | Anchor::Right) {
         return None;
     }
 
     let monitor = monitor?;
     let geometry = monitor.geometry();
-    let mut work_area = geometry.height() - (config.panel.margin.top + config.panel.margin.bottom);
-    if config.panel.respect_work_area {
+    let mut work_area = geometry.height() - (panel.margin.top + panel.margin.bottom);
+    if panel.respect_work_area {
         if let Some(reserved) = reserved {
             work_area -= reserved.top + reserved.bottom;
         }
@@ -251,6 +507,8 @@ fn apply_anchor(window: &impl IsA<gtk::Window>, anchor: Anchor, margin: Margins)
             window.set_anchor(Edge::Top, true);
             // Avoid bottom anchoring so computed height and overrides are respected.
         }
+        // No anchored edges; the compositor centers the surface.
+        Anchor::Center => {}
     }
 
     window.set_margin(Edge::Top, margin.top);
@@ -259,22 +517,40 @@ fn apply_anchor(window: &impl IsA<gtk::Window>, anchor: Anchor, margin: Margins)
     window.set_margin(Edge::Left, margin.left);
 }
 
-pub fn apply_panel_config(panel: &PanelWidgets, config: &Config, reserved: Option<Margins>) {
-    let monitor = if let Some(output) = config.panel.output.as_ref() {
-        find_monitor(output).or_else(default_monitor)
-    } else {
-        default_monitor()
-    };
-    if let Some(monitor) = monitor.as_ref() {
-        panel.window.set_monitor(Some(monitor));
+/// Reserve space for the panel as `exclusive_zone` asks; call after `apply_anchor`.
+///
+/// Side panels are normally anchored to the top edge only so their computed height holds.
+/// Compositors reserve space only for surfaces that span an edge, so while a zone is set
+/// they are anchored to the bottom as well.
+fn apply_exclusive_zone(window: &impl IsA<gtk::Window>, panel: &PanelConfig) {
+    surface::apply_exclusive_zone(window, panel.exclusive_zone);
+    if panel.exclusive_zone != ExclusiveZone::None
+        && matches!(panel.anchor, Anchor::Left | Anchor::Right)
+    {
+        window.set_anchor(Edge::Bottom, true);
     }
+}
 
-    panel
-        .window
-        .set_keyboard_mode(map_keyboard_mode(config.panel.keyboard_interactivity));
-    apply_anchor(&panel.window, config.panel.anchor, config.panel.margin);
+/// Re-apply placement and size; returns the resolved panel width for width-dependent widgets.
+pub fn apply_panel_config(
+    panel: &PanelWidgets,
+    config: &Config,
+    geometry: Option<&PanelGeometry>,
+    reserved: Option<Margins>,
+) -> i32 {
+    let (placement, monitor) = resolve_placement(config, geometry);
+    if surface::layer_shell_enabled() {
+        if let Some(monitor) = monitor.as_ref() {
+            panel.window.set_monitor(Some(monitor));
+        }
+        panel
+            .window
+            .set_keyboard_mode(map_keyboard_mode(placement.keyboard_interactivity));
+        apply_anchor(&panel.window, placement.anchor, placement.margin);
+        apply_exclusive_zone(&panel.window, &placement);
+    }
 
-    let (width, height) = resolve_panel_size(config, monitor.as_ref(), reserved);
+    let (width, height) = resolve_panel_size(&placement, monitor.as_ref(), reserved);
     panel.window.set_default_size(width, height);
     if height > 0 {
         panel.window.set_size_request(width, height);
@@ -284,6 +560,13 @@ pub fn apply_panel_config(panel: &PanelWidgets, config: &Config, reserved: Optio
     panel.root.set_size_request(width, -1);
     panel.scroller.set_min_content_width(width);
     panel.scroller.set_max_content_width(width);
+    panel.debug_hud.set_placement(HudPlacement {
+        anchor: placement.anchor,
+        margin: placement.margin,
+        work_area: reserved,
+        requested: (width, height),
+    });
+    width
 }
 
 fn map_keyboard_mode(mode: PanelKeyboardInteractivity) -> KeyboardMode {
@@ -300,10 +583,13 @@ fn find_monitor(name: &str) -> Option<gdk::Monitor> {
     for index in 0..monitors.n_items() {
         let item = monitors.item(index)?;
         let monitor = item.downcast::<gdk::Monitor>().ok()?;
-        if let Some(model) = monitor.model() {
-            if model == name {
-                return Some(monitor);
-            }
+        // Accept compositor connector names (DP-1) as well as model names.
+        if monitor
+            .connector()
+            .is_some_and(|connector| connector == name)
+            || monitor.model().is_some_and(|model| model == name)
+        {
+            return Some(monitor);
         }
     }
     None
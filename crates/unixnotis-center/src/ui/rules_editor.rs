@@ -0,0 +1,292 @@
+//! Rules popover for muting an app or forcing its urgency without editing config.toml.
+//!
+//! Changes go to the daemon, which applies them right away and saves them to config.toml.
+//! Rules made here are named after their app, so editing the same app again replaces its rule.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use gtk::Align;
+use tokio::sync::mpsc::UnboundedSender;
+use unixnotis_core::i18n::tr;
+use unixnotis_core::ui_ipc::ControlCommand;
+use unixnotis_core::RuleConfig;
+
+use crate::dbus::UiCommand;
+
+/// Urgency dropdown entries; the first leaves urgency alone, the rest map to 0, 1, and 2.
+const URGENCY_LABELS: [&str; 4] = [
+    "rules-urgency-keep",
+    "rules-urgency-low",
+    "rules-urgency-normal",
+    "rules-urgency-critical",
+];
+
+/// Rule list and form shown from the panel header.
+pub struct RulesEditor {
+    list: gtk::ListBox,
+    empty: gtk::Label,
+    rules: Rc<RefCell<Vec<RuleConfig>>>,
+    command_tx: UnboundedSender<UiCommand>,
+}
+
+#[derive(Clone)]
+struct RuleForm {
+    app: gtk::Entry,
+    mute: gtk::CheckButton,
+    urgency: gtk::DropDown,
+    /// Name of the rule loaded for editing, so saving replaces it even if it was not made here.
+    editing: Rc<RefCell<Option<String>>>,
+}
+
+impl RuleForm {
+    fn load(&self, rule: &RuleConfig) {
+        self.editing.replace(rule.name.clone());
+        self.app.set_text(rule.app.as_deref().unwrap_or_default());
+        self.mute
+            .set_active(rule.no_popup == Some(true) && rule.silent == Some(true));
+        let selected = rule
+            .force_urgency
+            .map_or(0, |urgency| u32::from(urgency) + 1);
+        self.urgency.set_selected(selected);
+    }
+
+    fn clear(&self) {
+        self.editing.replace(None);
+        self.app.set_text("");
+        self.mute.set_active(false);
+        self.urgency.set_selected(0);
+    }
+
+    fn force_urgency(&self) -> Option<u8> {
+        match self.urgency.selected() {
+            1 => Some(0),
+            2 => Some(1),
+            3 => Some(2),
+            _ => None,
+        }
+    }
+}
+
+impl RulesEditor {
+    pub fn new(menu: &gtk::MenuButton, command_tx: UnboundedSender<UiCommand>) -> Self {
+        let popover = gtk::Popover::new();
+        popover.add_css_class("unixnotis-rules");
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 8);
+
+        let title = gtk::Label::new(Some(&tr("rules-title")));
+        title.add_css_class("unixnotis-rules-title");
+        title.set_xalign(0.0);
+
+        let list = gtk::ListBox::new();
+        list.add_css_class("unixnotis-rules-list");
+        list.set_selection_mode(gtk::SelectionMode::None);
+        let scroller = gtk::ScrolledWindow::new();
+        scroller.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+        scroller.set_propagate_natural_height(true);
+        scroller.set_max_content_height(240);
+        scroller.set_child(Some(&list));
+        let empty = gtk::Label::new(Some(&tr("rules-empty")));
+        empty.add_css_class("unixnotis-rules-empty");
+        empty.set_xalign(0.0);
+
+        let urgency_labels = URGENCY_LABELS.map(tr);
+        let urgency_labels: Vec<&str> = urgency_labels.iter().map(String::as_str).collect();
+        let form = RuleForm {
+            app: gtk::Entry::new(),
+            mute: gtk::CheckButton::with_label(&tr("rules-mute")),
+            urgency: gtk::DropDown::from_strings(&urgency_labels),
+            editing: Rc::new(RefCell::new(None)),
+        };
+        form.app
+            .set_placeholder_text(Some(&tr("rules-app-placeholder")));
+        let save = gtk::Button::with_label(&tr("rules-save"));
+        save.add_css_class("unixnotis-rules-save");
+        save.set_halign(Align::End);
+        let form_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        form_box.add_css_class("unixnotis-rules-form");
+        form_box.append(&form.app);
+        form_box.append(&form.mute);
+        form_box.append(&form.urgency);
+        form_box.append(&save);
+
+        content.append(&title);
+        content.append(&scroller);
+        content.append(&empty);
+        content.append(&form_box);
+        popover.set_child(Some(&content));
+        menu.set_popover(Some(&popover));
+
+        let rules: Rc<RefCell<Vec<RuleConfig>>> = Rc::new(RefCell::new(Vec::new()));
+
+        // Activating a rule loads it into the form for editing.
+        let rules_clone = rules.clone();
+        let form_clone = form.clone();
+        list.connect_row_activated(move |_, row| {
+            let rules = rules_clone.borrow();
+            if let Some(rule) = usize::try_from(row.index())
+                .ok()
+                .and_then(|index| rules.get(index))
+            {
+                form_clone.load(rule);
+            }
+        });
+
+        let rules_clone = rules.clone();
+        let form_clone = form.clone();
+        let save_tx = command_tx.clone();
+        let save_action = move || {
+            let app = form_clone.app.text().trim().to_string();
+            if app.is_empty() {
+                return;
+            }
+            let name = form_clone.editing.borrow().clone().unwrap_or(app.clone());
+            let existing = rules_clone
+                .borrow()
+                .iter()
+                .find(|rule| rule.name.as_deref() == Some(name.as_str()))
+                .cloned();
+            let had_rule = existing.is_some();
+            let mut rule = existing.unwrap_or_else(|| RuleConfig::for_app(&app));
+            rule.app = Some(app);
+            let rule = edit_rule(
+                rule,
+                form_clone.mute.is_active(),
+                form_clone.force_urgency(),
+            );
+            // A rule that no longer changes anything is dropped instead of kept as a no-op.
+            if has_effect(&rule) {
+                let _ = save_tx.send(ControlCommand::SetRule(rule).into());
+            } else if had_rule {
+                let _ = save_tx.send(ControlCommand::RemoveRule(name).into());
+            }
+            form_clone.clear();
+        };
+        let save_clone = save_action.clone();
+        save.connect_clicked(move |_| save_clone());
+        form.app.connect_activate(move |_| save_action());
+
+        Self {
+            list,
+            empty,
+            rules,
+            command_tx,
+        }
+    }
+
+    /// Show the daemon's current rules.
+    pub fn set_rules(&self, rules: Vec<RuleConfig>) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+        for rule in &rules {
+            self.list.append(&self.build_row(rule));
+        }
+        self.empty.set_visible(rules.is_empty());
+        self.list.set_visible(!rules.is_empty());
+        *self.rules.borrow_mut() = rules;
+    }
+
+    fn build_row(&self, rule: &RuleConfig) -> gtk::ListBoxRow {
+        let row = gtk::ListBoxRow::new();
+        row.add_css_class("unixnotis-rule-row");
+        let content = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+
+        let label = gtk::Label::new(Some(&describe_rule(rule)));
+        label.add_css_class("unixnotis-rule-label");
+        label.set_xalign(0.0);
+        label.set_hexpand(true);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        content.append(&label);
+
+        match rule.name.clone() {
+            Some(name) => {
+                let remove = gtk::Button::from_icon_name("window-close-symbolic");
+                remove.add_css_class("unixnotis-rule-remove");
+                remove.set_tooltip_text(Some(&tr("rules-remove-tooltip")));
+                remove.set_valign(Align::Center);
+                let remove_tx = self.command_tx.clone();
+                remove.connect_clicked(move |_| {
+                    let _ = remove_tx.send(ControlCommand::RemoveRule(name.clone()).into());
+                });
+                content.append(&remove);
+            }
+            None => {
+                // The D-Bus API addresses rules by name, so unnamed ones stay read-only here.
+                row.set_activatable(false);
+                row.add_css_class("unixnotis-rule-readonly");
+                row.set_tooltip_text(Some(&tr("rules-config-only")));
+            }
+        }
+        row.set_child(Some(&content));
+        row
+    }
+}
+
+/// Apply the form's choices to a rule, leaving fields the form does not show untouched.
+fn edit_rule(mut rule: RuleConfig, mute: bool, force_urgency: Option<u8>) -> RuleConfig {
+    let muted = mute.then_some(true);
+    rule.no_popup = muted;
+    rule.silent = muted;
+    rule.force_urgency = force_urgency;
+    rule
+}
+
+/// Whether a rule changes anything about the notifications it matches.
+fn has_effect(rule: &RuleConfig) -> bool {
+    rule.no_popup.is_some()
+        || rule.silent.is_some()
+        || rule.force_urgency.is_some()
+        || rule.expire_timeout_ms.is_some()
+        || rule.resident.is_some()
+        || rule.transient.is_some()
+        || rule.history_ttl_minutes.is_some()
+        || rule.clamp_timeout.is_some()
+}
+
+/// Short row label such as `discord · muted · Critical urgency`.
+fn describe_rule(rule: &RuleConfig) -> String {
+    let target = rule
+        .app
+        .as_deref()
+        .or(rule.summary.as_deref())
+        .or(rule.category.as_deref())
+        .or(rule.name.as_deref())
+        .unwrap_or("*");
+    let mut parts = vec![target.to_string()];
+    if rule.no_popup == Some(true) && rule.silent == Some(true) {
+        parts.push(tr("rules-muted"));
+    }
+    if let Some(urgency) = rule.force_urgency {
+        parts.push(tr(URGENCY_LABELS[usize::from(urgency.min(2)) + 1]));
+    }
+    parts.join(" · ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_edits_keep_other_rule_fields() {
+        let rule = RuleConfig {
+            history_ttl_minutes: Some(10),
+            ..RuleConfig::for_app("discord")
+        };
+        let muted = edit_rule(rule, true, Some(2));
+        assert_eq!(muted.no_popup, Some(true));
+        assert_eq!(muted.silent, Some(true));
+        assert_eq!(muted.force_urgency, Some(2));
+        assert_eq!(muted.history_ttl_minutes, Some(10));
+
+        let cleared = edit_rule(muted, false, None);
+        assert!(cleared.no_popup.is_none());
+        assert!(has_effect(&cleared));
+        assert!(!has_effect(&edit_rule(
+            RuleConfig::for_app("discord"),
+            false,
+            None
+        )));
+    }
+}
@@ -0,0 +1,60 @@
+//! "Cleared · Undo" bar shown over the list for a short while after a clear.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::glib;
+use gtk::prelude::*;
+use tokio::sync::mpsc::UnboundedSender;
+use unixnotis_core::CLEAR_UNDO_SECONDS;
+
+use crate::dbus::UiCommand;
+
+/// Offers to restore cleared notifications until the daemon's undo window closes.
+pub struct UndoBar {
+    bar: gtk::Box,
+    hide_source: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl UndoBar {
+    pub fn new(
+        bar: gtk::Box,
+        button: &gtk::Button,
+        command_tx: UnboundedSender<UiCommand>,
+    ) -> Self {
+        let hide_source: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+
+        let bar_clone = bar.clone();
+        let source_clone = hide_source.clone();
+        button.connect_clicked(move |_| {
+            let _ = command_tx.send(UiCommand::UndoClear);
+            hide(&bar_clone, &source_clone);
+        });
+
+        Self { bar, hide_source }
+    }
+
+    /// Show the bar, restarting its timer if an earlier clear left it up.
+    pub fn show(&self) {
+        if let Some(source) = self.hide_source.borrow_mut().take() {
+            source.remove();
+        }
+        self.bar.set_visible(true);
+        let bar = self.bar.clone();
+        let slot = self.hide_source.clone();
+        let seconds = u32::try_from(CLEAR_UNDO_SECONDS).unwrap_or(u32::MAX);
+        let source = glib::timeout_add_seconds_local_once(seconds, move || {
+            // The source is done once this runs, so drop it without removing it again.
+            slot.borrow_mut().take();
+            bar.set_visible(false);
+        });
+        self.hide_source.replace(Some(source));
+    }
+}
+
+fn hide(bar: &gtk::Box, hide_source: &RefCell<Option<glib::SourceId>>) {
+    if let Some(source) = hide_source.borrow_mut().take() {
+        source.remove();
+    }
+    bar.set_visible(false);
+}
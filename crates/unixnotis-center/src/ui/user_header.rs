@@ -0,0 +1,206 @@
+//! Optional user row at the top of the panel: avatar, name, hostname, and uptime.
+
+use std::ffi::CStr;
+use std::fs;
+use std::path::PathBuf;
+
+use gtk::glib;
+use gtk::prelude::*;
+use unixnotis_core::PanelHeaderConfig;
+
+const AVATAR_SIZE: i32 = 40;
+const ACCOUNTS_ICON_DIR: &str = "/var/lib/AccountsService/icons";
+
+pub struct UserHeader {
+    container: gtk::Box,
+    avatar: gtk::Image,
+    name: gtk::Label,
+    details: gtk::Label,
+    config: PanelHeaderConfig,
+    user: UserInfo,
+}
+
+struct UserInfo {
+    login: String,
+    /// Full name from the passwd GECOS field, when set.
+    display_name: Option<String>,
+    hostname: String,
+}
+
+impl UserHeader {
+    pub fn new(container: &gtk::Box, config: &PanelHeaderConfig) -> Self {
+        let root = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+        root.add_css_class("unixnotis-user-header");
+
+        let avatar = gtk::Image::new();
+        avatar.set_pixel_size(AVATAR_SIZE);
+        avatar.add_css_class("unixnotis-user-avatar");
+        // Clip the image to the rounded CSS outline.
+        avatar.set_overflow(gtk::Overflow::Hidden);
+
+        let text = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        text.set_valign(gtk::Align::Center);
+        let name = gtk::Label::new(None);
+        name.add_css_class("unixnotis-user-name");
+        name.set_xalign(0.0);
+        let details = gtk::Label::new(None);
+        details.add_css_class("unixnotis-user-details");
+        details.set_xalign(0.0);
+        text.append(&name);
+        text.append(&details);
+
+        root.append(&avatar);
+        root.append(&text);
+        container.append(&root);
+
+        let user = UserInfo::current();
+        name.set_text(user.display_name.as_deref().unwrap_or(&user.login));
+
+        let mut header = Self {
+            container: container.clone(),
+            avatar,
+            name,
+            details,
+            config: PanelHeaderConfig::default(),
+            user,
+        };
+        header.apply_config(config);
+        header
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn apply_config(&mut self, config: &PanelHeaderConfig) {
+        self.config = config.clone();
+        self.container.set_visible(config.enabled);
+        self.avatar.set_visible(config.show_avatar);
+        if config.show_avatar {
+            match find_avatar(config.avatar.as_deref(), &self.user.login) {
+                Some(path) => self.avatar.set_from_file(Some(&path)),
+                None => self.avatar.set_icon_name(Some("avatar-default-symbolic")),
+            }
+        }
+        // Show the login on hover when the row displays the full name instead.
+        self.name.set_tooltip_text(
+            self.user
+                .display_name
+                .as_ref()
+                .map(|_| self.user.login.as_str()),
+        );
+        self.refresh();
+    }
+
+    /// Update the uptime; the rest of the row only changes on reload.
+    pub fn refresh(&self) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut parts = Vec::new();
+        if self.config.show_hostname {
+            parts.push(self.user.hostname.clone());
+        }
+        if self.config.show_uptime {
+            if let Some(seconds) = read_uptime() {
+                parts.push(format!("up {}", format_uptime(seconds)));
+            }
+        }
+        let text = parts.join(" · ");
+        self.details.set_visible(!text.is_empty());
+        if self.details.text().as_str() != text {
+            self.details.set_text(&text);
+        }
+    }
+}
+
+impl UserInfo {
+    fn current() -> Self {
+        let (login, gecos) = passwd_entry().unwrap_or_else(|| {
+            let login = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+            (login, String::new())
+        });
+        // GECOS is "Full Name,room,phone,..."; only the first field is the name.
+        let display_name = gecos
+            .split(',')
+            .next()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string);
+        Self {
+            login,
+            display_name,
+            hostname: glib::host_name().to_string(),
+        }
+    }
+}
+
+fn passwd_entry() -> Option<(String, String)> {
+    // SAFETY: getpwuid returns a pointer into static storage or null; both strings are copied
+    // out before any other passwd lookup can run on this (GTK) thread.
+    unsafe {
+        let entry = libc::getpwuid(libc::getuid());
+        if entry.is_null() {
+            return None;
+        }
+        let login = CStr::from_ptr((*entry).pw_name)
+            .to_string_lossy()
+            .into_owned();
+        let gecos = if (*entry).pw_gecos.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr((*entry).pw_gecos)
+                .to_string_lossy()
+                .into_owned()
+        };
+        Some((login, gecos))
+    }
+}
+
+fn find_avatar(configured: Option<&str>, login: &str) -> Option<PathBuf> {
+    if let Some(path) = configured {
+        let path = match path.strip_prefix("~/") {
+            Some(rest) => glib::home_dir().join(rest),
+            None => PathBuf::from(path),
+        };
+        return path.is_file().then_some(path);
+    }
+    [
+        PathBuf::from(ACCOUNTS_ICON_DIR).join(login),
+        glib::home_dir().join(".face"),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+}
+
+fn read_uptime() -> Option<u64> {
+    let contents = fs::read_to_string("/proc/uptime").ok()?;
+    let seconds: f64 = contents.split_whitespace().next()?.parse().ok()?;
+    Some(seconds as u64)
+}
+
+/// Compact uptime such as "3d 4h", "5h 12m", or "8m".
+fn format_uptime(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    let hours = minutes / 60;
+    let days = hours / 24;
+    if days > 0 {
+        format!("{days}d {}h", hours % 24)
+    } else if hours > 0 {
+        format!("{hours}h {}m", minutes % 60)
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_uptime;
+
+    #[test]
+    fn format_uptime_uses_two_largest_units() {
+        assert_eq!(format_uptime(59), "0m");
+        assert_eq!(format_uptime(5 * 3600 + 12 * 60 + 30), "5h 12m");
+        assert_eq!(format_uptime(3 * 86_400 + 4 * 3600 + 59 * 60), "3d 4h");
+    }
+}
@@ -2,13 +2,18 @@
 
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use gtk::prelude::*;
 use gtk::{glib, Align};
 use tracing::warn;
-use unixnotis_core::{CardWidgetConfig, PanelDebugLevel};
+use unixnotis_core::{CardActionConfig, CardWidgetConfig, PanelDebugLevel};
 
-use super::util::run_command_capture_async;
+use super::keyboard::KeyboardLayout;
+use super::power::build_power_actions;
+use super::script::ScriptView;
+use super::updates::UpdatesCheck;
+use super::util::{run_command, run_command_capture_async};
 use crate::debug;
 
 pub struct CardGrid {
@@ -22,7 +27,11 @@ struct CardItem {
     body_label: gtk::Label,
     calendar: Option<gtk::Calendar>,
     is_calendar: bool,
+    updates: Option<UpdatesCheck>,
+    keyboard: Option<Rc<KeyboardLayout>>,
+    script: Option<Rc<ScriptView>>,
     inflight: Rc<Cell<bool>>,
+    last_run: Cell<Option<Instant>>,
     last_value: Rc<RefCell<Option<String>>>,
 }
 
@@ -79,17 +88,33 @@ impl CardItem {
             match kind {
                 "calendar" => root.add_css_class("unixnotis-info-card-calendar"),
                 "weather" => root.add_css_class("unixnotis-info-card-weather"),
+                "updates" => root.add_css_class("unixnotis-info-card-updates"),
+                "script" => root.add_css_class("unixnotis-info-card-script"),
+                "keyboard" => root.add_css_class("unixnotis-info-card-keyboard"),
+                "power" => root.add_css_class("unixnotis-info-card-power"),
                 _ => {}
             }
         }
         if config.min_height > 0 {
             root.set_size_request(-1, config.min_height);
         }
+        if let Some(click_cmd) = config.click_cmd.clone() {
+            root.add_css_class("clickable");
+            let click = gtk::GestureClick::new();
+            click.connect_released(move |_, _, _, _| run_command(&click_cmd));
+            root.add_controller(click);
+        }
 
+        let is_script = matches!(config.kind.as_deref(), Some("script"));
         let header = gtk::Box::new(gtk::Orientation::Horizontal, 6);
         header.add_css_class("unixnotis-info-header");
-        if let Some(icon_name) = config.icon.as_ref() {
-            let icon = gtk::Image::from_icon_name(icon_name);
+        let mut header_icon = None;
+        // Script cards always get an icon slot because their output can set one.
+        if config.icon.is_some() || is_script {
+            let icon = gtk::Image::new();
+            if let Some(icon_name) = config.icon.as_deref() {
+                icon.set_icon_name(Some(icon_name));
+            }
             if matches!(config.kind.as_deref(), Some("weather")) {
                 icon.set_pixel_size(24);
                 icon.add_css_class("unixnotis-info-icon-weather");
@@ -98,6 +123,7 @@ impl CardItem {
             }
             icon.add_css_class("unixnotis-info-icon");
             header.append(&icon);
+            header_icon = Some(icon);
         }
 
         let title = gtk::Label::new(Some(&config.title));
@@ -121,18 +147,47 @@ impl CardItem {
             calendar.set_valign(Align::Start);
             root.append(&calendar);
             Some(calendar)
+        } else if matches!(config.kind.as_deref(), Some("power")) {
+            let actions = if config.actions.is_empty() {
+                CardActionConfig::power_defaults()
+            } else {
+                config.actions.clone()
+            };
+            root.append(&build_power_actions(&actions));
+            None
         } else {
             root.append(&body_label);
             None
         };
 
+        let updates =
+            matches!(config.kind.as_deref(), Some("updates")).then(|| UpdatesCheck::new(&config));
+        let keyboard = matches!(config.kind.as_deref(), Some("keyboard"))
+            .then(|| Rc::new(KeyboardLayout::new()));
+        // Without a custom click command, clicking a keyboard card cycles layouts.
+        if let (Some(keyboard), None) = (keyboard.as_ref(), config.click_cmd.as_ref()) {
+            root.add_css_class("clickable");
+            let click = gtk::GestureClick::new();
+            let keyboard = keyboard.clone();
+            let label = body_label.clone();
+            click.connect_released(move |_, _, _, _| keyboard.cycle(&label));
+            root.add_controller(click);
+        }
+        let script = header_icon
+            .filter(|_| is_script)
+            .map(|icon| Rc::new(ScriptView::new(&root, icon, config.icon.clone())));
+
         Self {
             config,
             root,
             body_label,
             calendar,
             is_calendar,
+            updates,
+            keyboard,
+            script,
             inflight: Rc::new(Cell::new(false)),
+            last_run: Cell::new(None),
             last_value: Rc::new(RefCell::new(None)),
         }
     }
@@ -149,16 +204,32 @@ impl CardItem {
         debug::log(PanelDebugLevel::Verbose, || {
             format!("card refresh: {}", self.config.title)
         });
+        if let Some(updates) = self.updates.as_ref() {
+            updates.refresh(&self.root, &self.body_label);
+            return;
+        }
+        if let Some(keyboard) = self.keyboard.as_ref() {
+            keyboard.refresh(&self.body_label);
+            return;
+        }
         let Some(cmd) = self.config.cmd.as_ref() else {
             return;
         };
         if self.inflight.get() {
             return;
         }
+        if let (Some(minutes), Some(last_run)) = (self.config.interval_minutes, self.last_run.get())
+        {
+            if last_run.elapsed() < Duration::from_secs(u64::from(minutes) * 60) {
+                return;
+            }
+        }
         self.inflight.set(true);
+        self.last_run.set(Some(Instant::now()));
         let cmd = cmd.clone();
         let rx = run_command_capture_async(&cmd);
         let label = self.body_label.clone();
+        let script = self.script.clone();
         let inflight = self.inflight.clone();
         let last_value = self.last_value.clone();
         glib::MainContext::default().spawn_local(async move {
@@ -191,7 +262,10 @@ impl CardItem {
                 if last_value.borrow().as_deref() == Some(value) {
                     return;
                 }
-                label.set_text(value);
+                match script.as_ref() {
+                    Some(script) => script.apply(value, &label),
+                    None => label.set_text(value),
+                }
                 *last_value.borrow_mut() = Some(value.to_string());
             }
         });
@@ -135,6 +135,39 @@ pub(in crate::ui::widgets) fn run_command_capture_status_async(
     rx
 }
 
+/// Run a long check (package queries, network probes) on its own thread so it never
+/// holds one of the pooled workers for the whole timeout.
+pub(in crate::ui::widgets) fn run_command_capture_long_async(
+    cmd: &str,
+    timeout: Duration,
+) -> async_channel::Receiver<Result<Output, io::Error>> {
+    let (tx, rx) = async_channel::bounded(1);
+    let cmd = cmd.trim().to_string();
+    if cmd.is_empty() {
+        let _ = tx.send_blocking(Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "command was empty",
+        )));
+        return rx;
+    }
+    debug::log(PanelDebugLevel::Verbose, || {
+        let snippet = util::log_snippet(&cmd);
+        format!("spawn long command: {snippet}")
+    });
+    let thread_tx = tx.clone();
+    let spawned = std::thread::Builder::new()
+        .name("unixnotis-long-command".to_string())
+        .spawn(move || {
+            let runtime = build_command_runtime();
+            let result = run_command_with_timeout(&cmd, timeout, runtime.as_ref());
+            let _ = thread_tx.send_blocking(result);
+        });
+    if let Err(err) = spawned {
+        let _ = tx.send_blocking(Err(err));
+    }
+    rx
+}
+
 struct CommandJob {
     cmd: String,
     plan: CommandPlan,
@@ -0,0 +1,140 @@
+//! Keyboard layout indicator for the "keyboard" card kind.
+//!
+//! Hyprland is queried over its IPC socket; other sessions fall back to `xkb-switch`.
+//! Clicking the card cycles to the next configured layout.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+use gtk::glib;
+use gtk::prelude::*;
+use tracing::warn;
+use unixnotis_core::PanelDebugLevel;
+
+use super::util::{run_command, run_command_capture_status_async};
+use crate::debug;
+use crate::ui::hyprland;
+
+// Give the compositor a moment to apply a switch before reading it back.
+const SWITCH_SETTLE: Duration = Duration::from_millis(160);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum LayoutBackend {
+    Hyprland,
+    XkbSwitch,
+}
+
+impl LayoutBackend {
+    fn detect() -> Option<Self> {
+        if hyprland::is_available() {
+            return Some(Self::Hyprland);
+        }
+        glib::find_program_in_path("xkb-switch").map(|_| Self::XkbSwitch)
+    }
+}
+
+/// Layout state for one card, shared with its click handler.
+pub(super) struct KeyboardLayout {
+    backend: Option<LayoutBackend>,
+    inflight: Rc<Cell<bool>>,
+}
+
+impl KeyboardLayout {
+    pub(super) fn new() -> Self {
+        let backend = LayoutBackend::detect();
+        if backend.is_none() {
+            warn!("keyboard card needs Hyprland or xkb-switch");
+        }
+        Self {
+            backend,
+            inflight: Rc::new(Cell::new(false)),
+        }
+    }
+
+    pub(super) fn refresh(&self, label: &gtk::Label) {
+        let Some(backend) = self.backend else {
+            set_label(label, "n/a");
+            return;
+        };
+        if self.inflight.get() {
+            return;
+        }
+        self.inflight.set(true);
+        let label = label.clone();
+        let inflight = self.inflight.clone();
+        match backend {
+            LayoutBackend::Hyprland => {
+                let (tx, rx) = async_channel::bounded(1);
+                thread::spawn(move || {
+                    let _ = tx.send_blocking(hyprland::active_keymap());
+                });
+                glib::MainContext::default().spawn_local(async move {
+                    let result = rx.recv().await;
+                    inflight.set(false);
+                    match result {
+                        Ok(Ok(Some(layout))) => set_label(&label, &layout),
+                        Ok(Ok(None)) => set_label(&label, "n/a"),
+                        Ok(Err(err)) => {
+                            warn!(?err, "failed to query hyprland keyboard layout");
+                            set_label(&label, "n/a");
+                        }
+                        Err(_) => {}
+                    }
+                });
+            }
+            LayoutBackend::XkbSwitch => {
+                let rx = run_command_capture_status_async("xkb-switch -p");
+                glib::MainContext::default().spawn_local(async move {
+                    let result = rx.recv().await;
+                    inflight.set(false);
+                    match result {
+                        Ok(Ok(output)) if output.status.success() => {
+                            let stdout = String::from_utf8_lossy(&output.stdout);
+                            set_label(&label, stdout.trim());
+                        }
+                        Ok(Ok(_)) | Ok(Err(_)) => {
+                            warn!("xkb-switch failed to report the keyboard layout");
+                            set_label(&label, "n/a");
+                        }
+                        Err(_) => {}
+                    }
+                });
+            }
+        }
+    }
+
+    /// Switch to the next layout, then show the new one.
+    pub(super) fn cycle(self: &Rc<Self>, label: &gtk::Label) {
+        let Some(backend) = self.backend else {
+            return;
+        };
+        debug::log(PanelDebugLevel::Info, || {
+            "keyboard layout cycle".to_string()
+        });
+        match backend {
+            LayoutBackend::Hyprland => {
+                thread::spawn(|| {
+                    if let Err(err) = hyprland::switch_keyboard_layout() {
+                        warn!(?err, "failed to switch hyprland keyboard layout");
+                    }
+                });
+            }
+            LayoutBackend::XkbSwitch => run_command("xkb-switch -n"),
+        }
+        let layout = Rc::clone(self);
+        let label = label.clone();
+        glib::timeout_add_local(SWITCH_SETTLE, move || {
+            layout.refresh(&label);
+            glib::ControlFlow::Break
+        });
+    }
+}
+
+fn set_label(label: &gtk::Label, text: &str) {
+    let text = if text.is_empty() { "n/a" } else { text };
+    if label.text().as_str() != text {
+        label.set_text(text);
+    }
+}
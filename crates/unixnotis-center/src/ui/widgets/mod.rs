@@ -6,7 +6,12 @@ pub mod stats;
 pub mod toggles;
 pub mod volume;
 
+mod keyboard;
+mod night_light;
+mod power;
+mod script;
 mod stats_builtin;
+mod updates;
 mod util;
 
 pub use util::CommandSlider;
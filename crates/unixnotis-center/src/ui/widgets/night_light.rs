@@ -0,0 +1,222 @@
+//! Built-in night light backend for toggles with `state_cmd = "builtin:night-light"`.
+//!
+//! Gamma tools keep the color temperature only while they run, so "on" means a backend
+//! process is alive. State is read from `/proc` rather than a child handle so it survives
+//! widget rebuilds, panel restarts, and instances started outside the panel. The scan reads
+//! every process directory, so it runs on GLib's blocking pool rather than the GTK thread.
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use gtk::{gio, glib};
+use tracing::warn;
+use unixnotis_core::{NightLightConfig, PanelDebugLevel};
+
+use crate::debug;
+
+const AUTO_ORDER: [NightLightBackend; 3] = [
+    NightLightBackend::Hyprsunset,
+    NightLightBackend::Wlsunset,
+    NightLightBackend::Gammastep,
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum NightLightBackend {
+    Hyprsunset,
+    Wlsunset,
+    Gammastep,
+}
+
+impl NightLightBackend {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "hyprsunset" => Some(Self::Hyprsunset),
+            "wlsunset" => Some(Self::Wlsunset),
+            "gammastep" => Some(Self::Gammastep),
+            _ => None,
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Hyprsunset => "hyprsunset",
+            Self::Wlsunset => "wlsunset",
+            Self::Gammastep => "gammastep",
+        }
+    }
+
+    fn args(self, temperature: u32) -> Vec<String> {
+        match self {
+            Self::Hyprsunset => vec!["-t".to_string(), temperature.to_string()],
+            // wlsunset only runs a day/night schedule; near-equal temperatures make the
+            // schedule irrelevant, and fixed times avoid needing a location.
+            Self::Wlsunset => vec![
+                "-t".to_string(),
+                temperature.to_string(),
+                "-T".to_string(),
+                (temperature + 1).to_string(),
+                "-S".to_string(),
+                "06:00".to_string(),
+                "-s".to_string(),
+                "18:00".to_string(),
+            ],
+            Self::Gammastep => vec!["-P".to_string(), "-O".to_string(), temperature.to_string()],
+        }
+    }
+}
+
+pub(super) struct NightLight {
+    backend: Option<NightLightBackend>,
+    temperature: u32,
+}
+
+impl NightLight {
+    pub(super) fn new(config: &NightLightConfig) -> Self {
+        let backend = if config.backend.trim().eq_ignore_ascii_case("auto") {
+            AUTO_ORDER
+                .into_iter()
+                .find(|backend| glib::find_program_in_path(backend.program()).is_some())
+        } else {
+            NightLightBackend::from_name(&config.backend)
+        };
+        if backend.is_none() {
+            warn!(
+                backend = %config.backend,
+                "night light needs hyprsunset, wlsunset, or gammastep"
+            );
+        }
+        Self {
+            backend,
+            temperature: config.temperature,
+        }
+    }
+
+    /// Check for a running backend off the GTK thread, then call `apply` with the answer.
+    pub(super) fn query_active(&self, apply: impl FnOnce(bool) + 'static) {
+        let Some(backend) = self.backend else {
+            apply(false);
+            return;
+        };
+        glib::MainContext::default().spawn_local(async move {
+            let running =
+                gio::spawn_blocking(move || !find_processes(backend.program()).is_empty()).await;
+            apply(running.unwrap_or(false));
+        });
+    }
+
+    /// Start or stop the backend; the work runs off the GTK thread, and `done` is called on it
+    /// once that finished.
+    pub(super) fn set_active(&self, active: bool, done: impl FnOnce() + 'static) {
+        let Some(backend) = self.backend else {
+            done();
+            return;
+        };
+        let temperature = self.temperature;
+        glib::MainContext::default().spawn_local(async move {
+            let _ = gio::spawn_blocking(move || apply_active(backend, temperature, active)).await;
+            done();
+        });
+    }
+}
+
+fn apply_active(backend: NightLightBackend, temperature: u32, active: bool) {
+    let running = find_processes(backend.program());
+    debug::log(PanelDebugLevel::Info, || {
+        format!(
+            "night light {} via {} (running: {})",
+            if active { "on" } else { "off" },
+            backend.program(),
+            running.len()
+        )
+    });
+    if !active {
+        for pid in running {
+            // SIGTERM lets the tool restore the original gamma ramps before exiting.
+            // SAFETY: kill has no memory-safety requirements; a stale pid only fails.
+            unsafe {
+                libc::kill(pid, libc::SIGTERM);
+            }
+        }
+        return;
+    }
+    if !running.is_empty() {
+        return;
+    }
+    let mut command = Command::new(backend.program());
+    command
+        .args(backend.args(temperature))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Its own process group keeps the tool out of the panel's signal handling.
+        .process_group(0);
+    match command.spawn() {
+        Ok(mut child) => {
+            // Reap the child when it exits so it never lingers as a zombie.
+            thread::spawn(move || {
+                let _ = child.wait();
+            });
+        }
+        Err(err) => {
+            warn!(
+                ?err,
+                program = backend.program(),
+                "failed to start night light"
+            );
+        }
+    }
+}
+
+/// Pids of this user's processes whose command name is `program`.
+fn find_processes(program: &str) -> Vec<i32> {
+    find_processes_in(Path::new("/proc"), program)
+}
+
+fn find_processes_in(proc_root: &Path, program: &str) -> Vec<i32> {
+    let Ok(entries) = fs::read_dir(proc_root) else {
+        return Vec::new();
+    };
+    // SAFETY: getuid has no preconditions and cannot fail.
+    let uid = unsafe { libc::getuid() };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<i32>().ok()?;
+            if entry.metadata().ok()?.uid() != uid {
+                return None;
+            }
+            let comm = fs::read_to_string(entry.path().join("comm")).ok()?;
+            (comm.trim_end() == program).then_some(pid)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_scan_matches_comm_of_numeric_entries() {
+        let root = std::env::temp_dir().join(format!("unixnotis-proc-{}", std::process::id()));
+        for (entry, comm) in [
+            ("101", "gammastep\n"),
+            ("102", "bash\n"),
+            ("103", "gammastep-indicator\n"),
+            ("self", "gammastep\n"),
+        ] {
+            fs::create_dir_all(root.join(entry)).unwrap();
+            fs::write(root.join(entry).join("comm"), comm).unwrap();
+        }
+        // A process that exited between the listing and the read has no comm.
+        fs::create_dir_all(root.join("104")).unwrap();
+
+        assert_eq!(find_processes_in(&root, "gammastep"), vec![101]);
+        assert!(find_processes_in(&root, "wlsunset").is_empty());
+        assert!(find_processes_in(&root.join("missing"), "gammastep").is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
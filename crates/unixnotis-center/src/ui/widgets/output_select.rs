@@ -0,0 +1,112 @@
+//! Field extraction from structured command output for sliders and toggles.
+//!
+//! `parse = "json:/volume"` reads a JSON pointer, `parse = "key:Volume"` reads a
+//! `key=value` (or `key: value`) line; the extracted text then goes through the usual parsing.
+
+use tracing::warn;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(in crate::ui::widgets) enum OutputSelector {
+    /// RFC 6901 pointer into a JSON document.
+    Json(String),
+    /// Key of a `key=value` or `key: value` line (case-insensitive).
+    Key(String),
+}
+
+impl OutputSelector {
+    /// Resolve the configured `parse` spec; invalid specs are logged and ignored.
+    pub(in crate::ui::widgets) fn from_config(spec: Option<&str>) -> Option<Self> {
+        let spec = spec?.trim();
+        if spec.is_empty() {
+            return None;
+        }
+        let selector = Self::parse(spec);
+        if selector.is_none() {
+            warn!(
+                spec,
+                "unknown parse spec; expected json:<pointer> or key:<name>"
+            );
+        }
+        selector
+    }
+
+    fn parse(spec: &str) -> Option<Self> {
+        let (kind, target) = spec.split_once(':')?;
+        let target = target.trim();
+        match kind.trim() {
+            "json" => {
+                // Accept "json:volume" as shorthand for the pointer "/volume".
+                let pointer = if target.is_empty() || target.starts_with('/') {
+                    target.to_string()
+                } else {
+                    format!("/{target}")
+                };
+                Some(Self::Json(pointer))
+            }
+            "key" | "kv" if !target.is_empty() => Some(Self::Key(target.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Extracted field text, or `None` when the output does not contain it.
+    pub(in crate::ui::widgets) fn extract(&self, output: &str) -> Option<String> {
+        match self {
+            Self::Json(pointer) => {
+                let document = parse_json(output)?;
+                match document.pointer(pointer)? {
+                    serde_json::Value::String(value) => Some(value.clone()),
+                    serde_json::Value::Number(value) => Some(value.to_string()),
+                    serde_json::Value::Bool(value) => Some(value.to_string()),
+                    _ => None,
+                }
+            }
+            Self::Key(key) => output.lines().find_map(|line| {
+                let (name, value) = line.split_once('=').or_else(|| line.split_once(':'))?;
+                if !name.trim().eq_ignore_ascii_case(key) {
+                    return None;
+                }
+                Some(value.trim().trim_matches('"').to_string())
+            }),
+        }
+    }
+}
+
+fn parse_json(output: &str) -> Option<serde_json::Value> {
+    if let Ok(value) = serde_json::from_str(output) {
+        return Some(value);
+    }
+    // Line-oriented tools may print several documents; the newest state is the last one.
+    let last = output.lines().rev().find(|line| !line.trim().is_empty())?;
+    serde_json::from_str(last).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputSelector;
+
+    #[test]
+    fn selectors_extract_json_and_key_value_fields() {
+        let json = OutputSelector::parse("json:/sink/volume").unwrap();
+        assert_eq!(
+            json.extract(r#"{"sink": {"volume": 0.42, "muted": false}}"#),
+            Some("0.42".to_string())
+        );
+        let shorthand = OutputSelector::parse("json:enabled").unwrap();
+        assert_eq!(
+            shorthand.extract("{\"enabled\": true}"),
+            Some("true".to_string())
+        );
+
+        let key = OutputSelector::parse("key:Powered").unwrap();
+        assert_eq!(
+            key.extract("Name: hci0\nPowered: yes\n"),
+            Some("yes".to_string())
+        );
+        let kv = OutputSelector::parse("kv:level").unwrap();
+        assert_eq!(kv.extract("LEVEL=\"70\""), Some("70".to_string()));
+        assert_eq!(kv.extract("other=1"), None);
+
+        assert_eq!(OutputSelector::parse("xml:/a"), None);
+        assert_eq!(OutputSelector::parse("key:"), None);
+    }
+}
@@ -0,0 +1,91 @@
+//! Session action buttons for the "power" card kind.
+//!
+//! Actions marked `confirm` open a small popover first, so a stray click on reboot or
+//! shut down does not end the session.
+
+use gtk::prelude::*;
+use gtk::Align;
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::{CardActionConfig, PanelDebugLevel};
+
+use super::util::run_command;
+use crate::debug;
+
+/// Build the button row for a power card.
+pub(super) fn build_power_actions(actions: &[CardActionConfig]) -> gtk::Box {
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    row.add_css_class("unixnotis-power-actions");
+    row.set_homogeneous(true);
+    row.set_hexpand(true);
+    for action in actions {
+        if action.cmd.trim().is_empty() {
+            continue;
+        }
+        row.append(&build_action_button(action));
+    }
+    row
+}
+
+fn build_action_button(action: &CardActionConfig) -> gtk::Button {
+    let button = gtk::Button::from_icon_name(&action.icon);
+    button.add_css_class("unixnotis-power-button");
+    button.set_tooltip_text(Some(&action.label));
+    button.set_focusable(false);
+
+    if !action.confirm {
+        let action = action.clone();
+        button.connect_clicked(move |_| run_action(&action));
+        return button;
+    }
+
+    let popover = build_confirm_popover(action);
+    popover.set_parent(&button);
+    // Popovers are not regular children; unparent them or GTK warns when the card is rebuilt.
+    let parented = popover.clone();
+    button.connect_destroy(move |_| parented.unparent());
+    button.connect_clicked(move |_| popover.popup());
+    button
+}
+
+fn build_confirm_popover(action: &CardActionConfig) -> gtk::Popover {
+    let popover = gtk::Popover::new();
+    popover.add_css_class("unixnotis-power-confirm");
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    let prompt = gtk::Label::new(Some(&tr_args(
+        "power-confirm",
+        &[("action", action.label.as_str().into())],
+    )));
+    prompt.add_css_class("unixnotis-power-confirm-label");
+    prompt.set_xalign(0.0);
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    buttons.set_halign(Align::End);
+    let cancel = gtk::Button::with_label(&tr("power-cancel"));
+    cancel.add_css_class("unixnotis-power-cancel");
+    let confirm = gtk::Button::with_label(&action.label);
+    confirm.add_css_class("unixnotis-power-accept");
+    buttons.append(&cancel);
+    buttons.append(&confirm);
+
+    content.append(&prompt);
+    content.append(&buttons);
+    popover.set_child(Some(&content));
+
+    let cancel_popover = popover.clone();
+    cancel.connect_clicked(move |_| cancel_popover.popdown());
+    let confirm_popover = popover.clone();
+    let action = action.clone();
+    confirm.connect_clicked(move |_| {
+        confirm_popover.popdown();
+        run_action(&action);
+    });
+    popover
+}
+
+fn run_action(action: &CardActionConfig) {
+    debug::log(PanelDebugLevel::Info, || {
+        format!("power action: {}", action.label)
+    });
+    run_command(&action.cmd);
+}
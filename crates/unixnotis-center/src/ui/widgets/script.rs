@@ -0,0 +1,154 @@
+//! Generic rendering for "script" cards driven by a small JSON protocol.
+//!
+//! The card command prints one JSON object per run, for example
+//! `{"text": "3 tasks", "value": 42, "icon": "task-due-symbolic", "state": "warning"}`.
+//! Every field is optional, so scripts only send what they need.
+
+use std::cell::RefCell;
+
+use gtk::prelude::*;
+use serde::Deserialize;
+use tracing::warn;
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+struct ScriptOutput {
+    /// Body text; falls back to the value when absent.
+    text: Option<String>,
+    /// Percentage shown as a level bar (clamped to 0-100).
+    value: Option<f64>,
+    /// Icon name replacing the configured card icon.
+    icon: Option<String>,
+    /// State name added to the card as a CSS class (e.g. "warning", "critical").
+    state: Option<String>,
+    tooltip: Option<String>,
+}
+
+/// Widgets a script card updates in addition to its body label.
+pub(super) struct ScriptView {
+    root: gtk::Box,
+    icon: gtk::Image,
+    default_icon: Option<String>,
+    bar: gtk::LevelBar,
+    state_class: RefCell<Option<String>>,
+}
+
+impl ScriptView {
+    pub(super) fn new(root: &gtk::Box, icon: gtk::Image, default_icon: Option<String>) -> Self {
+        let bar = gtk::LevelBar::for_interval(0.0, 100.0);
+        bar.add_css_class("unixnotis-info-level");
+        bar.set_visible(false);
+        root.append(&bar);
+        icon.set_visible(default_icon.is_some());
+        Self {
+            root: root.clone(),
+            icon,
+            default_icon,
+            bar,
+            state_class: RefCell::new(None),
+        }
+    }
+
+    pub(super) fn apply(&self, raw: &str, label: &gtk::Label) {
+        let output = parse_script_output(raw).unwrap_or_else(|| {
+            warn!("script card output is not a JSON object; showing it as text");
+            ScriptOutput {
+                text: Some(raw.to_string()),
+                ..ScriptOutput::default()
+            }
+        });
+
+        let text = output
+            .text
+            .or_else(|| output.value.map(|value| format!("{value:.0}%")))
+            .unwrap_or_default();
+        if label.text().as_str() != text {
+            label.set_text(&text);
+        }
+
+        match output.value {
+            Some(value) => {
+                self.bar.set_value(value.clamp(0.0, 100.0));
+                self.bar.set_visible(true);
+            }
+            None => self.bar.set_visible(false),
+        }
+
+        match output.icon.or_else(|| self.default_icon.clone()) {
+            Some(icon) => {
+                self.icon.set_icon_name(Some(&icon));
+                self.icon.set_visible(true);
+            }
+            None => self.icon.set_visible(false),
+        }
+
+        let state = output.state.as_deref().and_then(state_class);
+        let mut current = self.state_class.borrow_mut();
+        if *current != state {
+            if let Some(previous) = current.as_deref() {
+                self.root.remove_css_class(previous);
+            }
+            if let Some(next) = state.as_deref() {
+                self.root.add_css_class(next);
+            }
+            *current = state;
+        }
+
+        self.root.set_tooltip_text(output.tooltip.as_deref());
+    }
+}
+
+fn parse_script_output(raw: &str) -> Option<ScriptOutput> {
+    if let Ok(output) = serde_json::from_str(raw) {
+        return Some(output);
+    }
+    // Scripts that log progress first still work when the JSON is the last line.
+    let last = raw.lines().rev().find(|line| !line.trim().is_empty())?;
+    serde_json::from_str(last).ok()
+}
+
+/// Card class for a script `state`; prefixed so a script cannot add or remove the card's own
+/// classes.
+fn state_class(state: &str) -> Option<String> {
+    is_css_ident(state).then(|| format!("unixnotis-script-state-{state}"))
+}
+
+// Only plain identifiers become classes so script output cannot inject selectors.
+fn is_css_ident(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 32
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_css_ident, parse_script_output, state_class, ScriptOutput};
+
+    #[test]
+    fn parse_script_output_accepts_partial_and_trailing_json() {
+        assert_eq!(
+            parse_script_output(r#"{"text": "3 tasks", "value": 42, "state": "warning"}"#),
+            Some(ScriptOutput {
+                text: Some("3 tasks".to_string()),
+                value: Some(42.0),
+                state: Some("warning".to_string()),
+                ..ScriptOutput::default()
+            })
+        );
+        let logged = "syncing...\n{\"icon\": \"mail-unread-symbolic\"}\n";
+        assert_eq!(
+            parse_script_output(logged).and_then(|output| output.icon),
+            Some("mail-unread-symbolic".to_string())
+        );
+        assert_eq!(parse_script_output("plain text"), None);
+        assert!(is_css_ident("warning"));
+        assert!(!is_css_ident("a b{}"));
+        assert_eq!(
+            state_class("unixnotis-info-card").as_deref(),
+            Some("unixnotis-script-state-unixnotis-info-card")
+        );
+        assert_eq!(state_class("a b{}"), None);
+    }
+}
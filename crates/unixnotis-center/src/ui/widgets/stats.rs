@@ -23,6 +23,7 @@ pub struct StatGrid {
 
 struct StatItem {
     config: StatWidgetConfig,
+    thresholds: StatThresholds,
     root: gtk::Box,
     value_label: gtk::Label,
     builtin: Rc<RefCell<Option<BuiltinStat>>>,
@@ -30,6 +31,13 @@ struct StatItem {
     last_value: Rc<RefCell<Option<String>>>,
 }
 
+// Value limits that drive the `warning` and `critical` classes on the card.
+#[derive(Clone, Copy)]
+struct StatThresholds {
+    warning: Option<f64>,
+    critical: Option<f64>,
+}
+
 struct BuiltinStatJob {
     stat: BuiltinStat,
     respond: async_channel::Sender<(BuiltinStat, String)>,
@@ -152,8 +160,14 @@ impl StatItem {
             .as_ref()
             .and_then(|cmd| BuiltinStat::from_command(cmd));
 
+        let thresholds = StatThresholds {
+            warning: config.warning_above,
+            critical: config.critical_above,
+        };
+
         Self {
             config,
+            thresholds,
             root: card,
             value_label,
             builtin: Rc::new(RefCell::new(builtin)),
@@ -190,6 +204,8 @@ impl StatItem {
             }
 
             let label = self.value_label.clone();
+            let card = self.root.clone();
+            let thresholds = self.thresholds;
             let inflight = self.inflight.clone();
             let builtin_cell = self.builtin.clone();
             let last_value = self.last_value.clone();
@@ -205,6 +221,7 @@ impl StatItem {
                     apply_cached_value(&label, &last_value);
                 } else if last_value.borrow().as_deref() != Some(&value) {
                     label.set_text(&value);
+                    thresholds.apply(&card, &value);
                     *last_value.borrow_mut() = Some(value);
                 }
             });
@@ -219,6 +236,8 @@ impl StatItem {
         let cmd = cmd.clone();
         let rx = run_command_capture_async(&cmd);
         let label = self.value_label.clone();
+        let card = self.root.clone();
+        let thresholds = self.thresholds;
         let inflight = self.inflight.clone();
         let last_value = self.last_value.clone();
         glib::MainContext::default().spawn_local(async move {
@@ -249,6 +268,7 @@ impl StatItem {
                 apply_cached_value(&label, &last_value);
             } else {
                 label.set_text(value);
+                thresholds.apply(&card, value);
                 *last_value.borrow_mut() = Some(value.to_string());
             }
         });
@@ -259,10 +279,46 @@ impl StatItem {
             return;
         }
         self.value_label.set_text(value);
+        self.thresholds.apply(&self.root, value);
         *self.last_value.borrow_mut() = Some(value.to_string());
     }
 }
 
+impl StatThresholds {
+    fn apply(self, card: &gtk::Box, value: &str) {
+        if self.warning.is_none() && self.critical.is_none() {
+            return;
+        }
+        let number = leading_number(value);
+        let above = |limit: Option<f64>| matches!((number, limit), (Some(n), Some(l)) if n > l);
+        let critical = above(self.critical);
+        let warning = !critical && above(self.warning);
+        set_class(card, "critical", critical);
+        set_class(card, "warning", warning);
+    }
+}
+
+fn set_class(widget: &gtk::Box, class: &str, enabled: bool) {
+    if enabled {
+        widget.add_css_class(class);
+    } else {
+        widget.remove_css_class(class);
+    }
+}
+
+/// First number in a stat value, e.g. 71 from "71°C" or 12.5 from "12.5/31.2 GB".
+fn leading_number(value: &str) -> Option<f64> {
+    let start = value.find(|ch: char| ch.is_ascii_digit() || ch == '-')?;
+    let rest = &value[start..];
+    let end = rest
+        .char_indices()
+        .skip(1)
+        .find(|(_, ch)| !(ch.is_ascii_digit() || *ch == '.'))
+        .map(|(index, _)| index)
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
 fn apply_cached_value(label: &gtk::Label, cache: &Rc<RefCell<Option<String>>>) {
     if let Some(value) = cache.borrow().as_ref() {
         if label.text().as_str() != value {
@@ -272,3 +328,16 @@ fn apply_cached_value(label: &gtk::Label, cache: &Rc<RefCell<Option<String>>>) {
         label.set_text("n/a");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::leading_number;
+
+    #[test]
+    fn leading_number_reads_stat_values() {
+        assert_eq!(leading_number("71°C"), Some(71.0));
+        assert_eq!(leading_number("12.5/31.2 GB"), Some(12.5));
+        assert_eq!(leading_number("RX 3.1 KB/s"), Some(3.1));
+        assert_eq!(leading_number("n/a"), None);
+    }
+}
@@ -3,9 +3,15 @@
 //! Reads system data from procfs/sysfs to avoid spawning shell commands.
 
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
+use unixnotis_core::util;
+
+const HWMON_ROOT: &str = "/sys/class/hwmon";
+// Chips whose first temperature input is the CPU package reading.
+const CPU_TEMP_CHIPS: [&str; 3] = ["coretemp", "k10temp", "zenpower"];
+
 #[derive(Clone, Debug)]
 pub(super) struct BuiltinStat {
     kind: BuiltinStatKind,
@@ -18,7 +24,19 @@ enum BuiltinStatKind {
     Memory,
     Load,
     Battery,
-    Network { iface: Option<String> },
+    /// Interface to sample; `None` follows the default route.
+    Network {
+        iface: Option<String>,
+    },
+    Disk {
+        mount: String,
+    },
+    Temp {
+        label: Option<String>,
+    },
+    Fan {
+        label: Option<String>,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -29,9 +47,14 @@ enum BuiltinState {
         last_idle: u64,
     },
     Network {
-        last_rx: u64,
-        last_tx: u64,
-        last_at: Instant,
+        // Interface the baseline belongs to; a change resets the baseline.
+        active: Option<String>,
+        // Previous rx/tx byte counters and when they were read.
+        last: Option<(u64, u64, Instant)>,
+    },
+    Hwmon {
+        // Resolved sensor input, rescanned when it stops being readable.
+        input: Option<PathBuf>,
     },
 }
 
@@ -66,7 +89,16 @@ impl BuiltinStat {
             BuiltinStatKind::Memory => read_memory(),
             BuiltinStatKind::Load => read_loadavg(),
             BuiltinStatKind::Battery => read_battery(),
-            BuiltinStatKind::Network { iface } => read_network(&mut self.state, iface),
+            BuiltinStatKind::Network { iface } => read_network(&mut self.state, iface.as_deref()),
+            BuiltinStatKind::Disk { mount } => read_disk(mount),
+            BuiltinStatKind::Temp { label } => {
+                let millidegrees = read_hwmon(&mut self.state, "temp", label.as_deref())?;
+                Some(format!("{:.0}°C", millidegrees as f64 / 1000.0))
+            }
+            BuiltinStatKind::Fan { label } => {
+                let rpm = read_hwmon(&mut self.state, "fan", label.as_deref())?;
+                Some(format!("{rpm} RPM"))
+            }
         }
     }
 
@@ -77,10 +109,12 @@ impl BuiltinStat {
                 last_idle: 0,
             },
             BuiltinStatKind::Network { .. } => BuiltinState::Network {
-                last_rx: 0,
-                last_tx: 0,
-                last_at: Instant::now(),
+                active: None,
+                last: None,
             },
+            BuiltinStatKind::Temp { .. } | BuiltinStatKind::Fan { .. } => {
+                BuiltinState::Hwmon { input: None }
+            }
             _ => BuiltinState::None,
         };
         Self { kind, state }
@@ -95,9 +129,31 @@ impl BuiltinStat {
             "load" => Some(Self::new(BuiltinStatKind::Load)),
             "battery" => Some(Self::new(BuiltinStatKind::Battery)),
             "net" => {
-                let iface = parts.next().map(|value| value.to_string());
+                let iface = parts
+                    .next()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty() && *value != "auto")
+                    .map(str::to_string);
                 Some(Self::new(BuiltinStatKind::Network { iface }))
             }
+            "disk" => {
+                // Mount points never contain ':' in practice, but keep the rest intact anyway.
+                let mount = parts.collect::<Vec<_>>().join(":");
+                let mount = match mount.trim() {
+                    "" => "/".to_string(),
+                    mount => mount.to_string(),
+                };
+                Some(Self::new(BuiltinStatKind::Disk { mount }))
+            }
+            "temp" | "fan" => {
+                let label = parts.collect::<Vec<_>>().join(":");
+                let label = (!label.trim().is_empty()).then(|| label.trim().to_string());
+                if kind == "temp" {
+                    Some(Self::new(BuiltinStatKind::Temp { label }))
+                } else {
+                    Some(Self::new(BuiltinStatKind::Fan { label }))
+                }
+            }
             _ => None,
         }
     }
@@ -128,41 +184,42 @@ impl BuiltinStat {
     }
 }
 
-fn read_network(state: &mut BuiltinState, iface: &mut Option<String>) -> Option<String> {
-    if iface.is_none() {
-        *iface = pick_default_iface();
+fn read_network(state: &mut BuiltinState, requested: Option<&str>) -> Option<String> {
+    let BuiltinState::Network { active, last } = state else {
+        return None;
+    };
+    let contents = fs::read_to_string("/proc/net/dev").ok()?;
+    // Auto mode follows the default route, so switching from Wi-Fi to a dock is picked up.
+    let iface = match requested {
+        Some(iface) => Some(iface.to_string()),
+        None => pick_default_iface(&contents),
+    };
+    if *active != iface {
+        *active = iface;
+        *last = None;
     }
-    let iface = iface.as_ref()?;
-    let (rx, tx) = read_network_bytes(iface)?;
-    match state {
-        BuiltinState::Network {
-            last_rx,
-            last_tx,
-            last_at,
-        } => {
-            let now = Instant::now();
-            let elapsed = now.duration_since(*last_at).as_secs_f64();
-            let rx_rate = if elapsed > 0.0 {
-                (rx.saturating_sub(*last_rx)) as f64 / elapsed
+    let (rx, tx) = parse_net_dev(&contents, active.as_deref()?)?;
+    let now = Instant::now();
+    let (rx_rate, tx_rate) = match last.replace((rx, tx, now)) {
+        Some((last_rx, last_tx, last_at)) => {
+            let elapsed = now.duration_since(last_at).as_secs_f64();
+            if elapsed > 0.0 {
+                (
+                    rx.saturating_sub(last_rx) as f64 / elapsed,
+                    tx.saturating_sub(last_tx) as f64 / elapsed,
+                )
             } else {
-                0.0
-            };
-            let tx_rate = if elapsed > 0.0 {
-                (tx.saturating_sub(*last_tx)) as f64 / elapsed
-            } else {
-                0.0
-            };
-            *last_rx = rx;
-            *last_tx = tx;
-            *last_at = now;
-            Some(format!(
-                "RX {} TX {}",
-                format_rate(rx_rate),
-                format_rate(tx_rate)
-            ))
+                (0.0, 0.0)
+            }
         }
-        _ => None,
-    }
+        // The first sample only sets the baseline; counters since boot are not a rate.
+        None => (0.0, 0.0),
+    };
+    Some(format!(
+        "RX {} TX {}",
+        format_rate(rx_rate),
+        format_rate(tx_rate)
+    ))
 }
 
 fn read_cpu_sample() -> Option<(u64, u64)> {
@@ -238,38 +295,133 @@ fn read_battery() -> Option<String> {
     None
 }
 
-fn read_network_bytes(iface: &str) -> Option<(u64, u64)> {
-    let base = Path::new("/sys/class/net").join(iface).join("statistics");
-    let rx = fs::read_to_string(base.join("rx_bytes")).ok()?;
-    let tx = fs::read_to_string(base.join("tx_bytes")).ok()?;
-    let rx = rx.trim().parse::<u64>().ok()?;
-    let tx = tx.trim().parse::<u64>().ok()?;
-    Some((rx, tx))
+fn read_disk(mount: &str) -> Option<String> {
+    let usage = util::fs_usage(Path::new(mount))?;
+    Some(format_capacity(usage.used, usage.total))
 }
 
-fn pick_default_iface() -> Option<String> {
-    let entries = fs::read_dir("/sys/class/net").ok()?;
+fn read_hwmon(state: &mut BuiltinState, prefix: &str, label: Option<&str>) -> Option<i64> {
+    let BuiltinState::Hwmon { input } = state else {
+        return None;
+    };
+    if let Some(value) = input.as_ref().and_then(|path| read_sysfs_i64(path)) {
+        return Some(value);
+    }
+    // Hwmon numbering can change across boots or module reloads, so resolve by name.
+    *input = find_hwmon_input(prefix, label);
+    input.as_ref().and_then(|path| read_sysfs_i64(path))
+}
+
+fn find_hwmon_input(prefix: &str, label: Option<&str>) -> Option<PathBuf> {
+    let mut chips: Vec<PathBuf> = fs::read_dir(HWMON_ROOT)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    chips.sort();
     let mut fallback = None;
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let iface = path
-            .file_name()
-            .and_then(|value| value.to_str())
-            .unwrap_or("lo");
-        if iface == "lo" {
+    for chip_dir in chips {
+        let chip = fs::read_to_string(chip_dir.join("name")).unwrap_or_default();
+        let chip = chip.trim();
+        let Ok(entries) = fs::read_dir(&chip_dir) else {
             continue;
-        }
-        if fallback.is_none() {
-            fallback = Some(iface.to_string());
-        }
-        let operstate = fs::read_to_string(path.join("operstate")).unwrap_or_default();
-        if operstate.trim() == "up" {
-            return Some(iface.to_string());
+        };
+        let mut inputs: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter(|name| name.starts_with(prefix) && name.ends_with("_input"))
+            .collect();
+        inputs.sort();
+        for input in inputs {
+            let path = chip_dir.join(&input);
+            let base = input.trim_end_matches("_input");
+            let sensor =
+                fs::read_to_string(chip_dir.join(format!("{base}_label"))).unwrap_or_default();
+            let sensor = sensor.trim();
+            match label {
+                // Match either the chip name ("k10temp") or the sensor label ("Package id 0").
+                Some(label) => {
+                    if chip.eq_ignore_ascii_case(label) || sensor.eq_ignore_ascii_case(label) {
+                        return Some(path);
+                    }
+                }
+                None => {
+                    if prefix == "temp" && CPU_TEMP_CHIPS.contains(&chip) {
+                        return Some(path);
+                    }
+                    // Idle fan headers report 0 RPM; prefer one that is spinning.
+                    if prefix == "fan" && read_sysfs_i64(&path).is_some_and(|rpm| rpm > 0) {
+                        return Some(path);
+                    }
+                    if fallback.is_none() {
+                        fallback = Some(path);
+                    }
+                }
+            }
         }
     }
     fallback
 }
 
+fn read_sysfs_i64(path: &Path) -> Option<i64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn format_capacity(used: u64, total: u64) -> String {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    let used = used as f64 / GIB;
+    let total = total as f64 / GIB;
+    if total >= 1024.0 {
+        format!("{:.1}/{:.1} TB", used / 1024.0, total / 1024.0)
+    } else {
+        format!("{:.0}/{:.0} GB", used, total)
+    }
+}
+
+/// Receive and transmit byte counters for `iface` from /proc/net/dev contents.
+fn parse_net_dev(contents: &str, iface: &str) -> Option<(u64, u64)> {
+    contents.lines().skip(2).find_map(|line| {
+        let (name, counters) = line.split_once(':')?;
+        if name.trim() != iface {
+            return None;
+        }
+        let fields: Vec<&str> = counters.split_whitespace().collect();
+        let rx = fields.first()?.parse().ok()?;
+        let tx = fields.get(8)?.parse().ok()?;
+        Some((rx, tx))
+    })
+}
+
+fn pick_default_iface(net_dev: &str) -> Option<String> {
+    if let Some(iface) = fs::read_to_string("/proc/net/route")
+        .ok()
+        .and_then(|routes| default_route_iface(&routes))
+    {
+        return Some(iface);
+    }
+    // Without a default route, fall back to the busiest non-loopback interface.
+    net_dev
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, _) = line.split_once(':')?;
+            let name = name.trim();
+            let (rx, tx) = parse_net_dev(net_dev, name)?;
+            (name != "lo").then(|| (rx.saturating_add(tx), name.to_string()))
+        })
+        .max()
+        .map(|(_, name)| name)
+}
+
+fn default_route_iface(routes: &str) -> Option<String> {
+    routes.lines().skip(1).find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let iface = fields.next()?;
+        let destination = fields.next()?;
+        (destination == "00000000").then(|| iface.to_string())
+    })
+}
+
 fn format_rate(rate: f64) -> String {
     let units = ["B/s", "KB/s", "MB/s", "GB/s"];
     let mut value = rate.max(0.0);
@@ -296,3 +448,23 @@ fn extract_iface(cmd: &str) -> Option<String> {
         Some(iface.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{default_route_iface, parse_net_dev};
+
+    #[test]
+    fn net_dev_and_route_parsing() {
+        let net_dev = "Inter-|   Receive                            |  Transmit\n \
+             face |bytes    packets errs drop fifo frame compressed multicast|bytes\n    \
+             lo:  1200      10    0    0    0     0          0         0     1200 10 0 0 0 0 0 0\n  \
+             wlan0: 987654 900    0    0    0     0          0         0    123456 800 0 0 0 0 0 0\n";
+        assert_eq!(parse_net_dev(net_dev, "wlan0"), Some((987654, 123456)));
+        assert_eq!(parse_net_dev(net_dev, "eth0"), None);
+
+        let routes = "Iface\tDestination\tGateway\tFlags\n\
+                      wlan0\t0000A8C0\t00000000\t0001\n\
+                      wlan0\t00000000\t0100A8C0\t0003\n";
+        assert_eq!(default_route_iface(routes), Some("wlan0".to_string()));
+    }
+}
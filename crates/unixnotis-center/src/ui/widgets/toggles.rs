@@ -8,10 +8,12 @@ use std::sync::Arc;
 use gtk::prelude::*;
 use gtk::{glib, Align};
 use tracing::warn;
-use unixnotis_core::{PanelDebugLevel, ToggleWidgetConfig};
+use unixnotis_core::{NightLightConfig, PanelDebugLevel, ToggleWidgetConfig};
 
+use super::night_light::NightLight;
 use super::util::{
     run_command, run_command_capture_status_async, start_command_watch, CommandWatch,
+    OutputSelector,
 };
 use crate::debug;
 
@@ -22,6 +24,8 @@ pub struct ToggleGrid {
 
 struct ToggleItem {
     config: ToggleWidgetConfig,
+    selector: Option<OutputSelector>,
+    night_light: Option<Rc<NightLight>>,
     button: gtk::ToggleButton,
     guard: Rc<Cell<bool>>,
     refresh_gen: Arc<AtomicU64>,
@@ -29,13 +33,13 @@ struct ToggleItem {
 }
 
 impl ToggleGrid {
-    pub fn new(configs: &[ToggleWidgetConfig]) -> Option<Self> {
+    pub fn new(configs: &[ToggleWidgetConfig], night_light: &NightLightConfig) -> Option<Self> {
         let mut items = Vec::new();
         for config in configs {
             if !config.enabled {
                 continue;
             }
-            items.push(ToggleItem::new(config.clone()));
+            items.push(ToggleItem::new(config.clone(), night_light));
         }
         if items.is_empty() {
             return None;
@@ -82,7 +86,7 @@ impl ToggleGrid {
 }
 
 impl ToggleItem {
-    fn new(config: ToggleWidgetConfig) -> Self {
+    fn new(config: ToggleWidgetConfig, night_light_config: &NightLightConfig) -> Self {
         let guard = Rc::new(Cell::new(false));
         let refresh_gen = Arc::new(AtomicU64::new(0));
         let button = gtk::ToggleButton::new();
@@ -105,8 +109,14 @@ impl ToggleItem {
         content.append(&label);
         button.set_child(Some(&content));
 
+        let selector = OutputSelector::from_config(config.parse.as_deref());
+        let night_light = (config.state_cmd.as_deref()
+            == Some(ToggleWidgetConfig::NIGHT_LIGHT_BUILTIN))
+        .then(|| Rc::new(NightLight::new(night_light_config)));
         let guard_clone = guard.clone();
+        let toggle_night_light = night_light.clone();
         let state_cmd = config.state_cmd.clone();
+        let toggle_selector = selector.clone();
         let on_cmd = config.on_cmd.clone();
         let off_cmd = config.off_cmd.clone();
         let refresh_gen_for_toggle = refresh_gen.clone();
@@ -118,6 +128,22 @@ impl ToggleItem {
             debug::log(PanelDebugLevel::Info, || {
                 format!("toggle '{}' set to {}", label, button.is_active())
             });
+            if let Some(night_light) = toggle_night_light.clone() {
+                let guard = guard_clone.clone();
+                let refresh_gen = refresh_gen_for_toggle.clone();
+                let button = button.clone();
+                let active = button.is_active();
+                night_light.clone().set_active(active, move || {
+                    // Give a stopped tool a moment to exit before reading the state back.
+                    glib::timeout_add_local_once(
+                        std::time::Duration::from_millis(160),
+                        move || {
+                            refresh_night_light(&night_light, &button, &guard, &refresh_gen);
+                        },
+                    );
+                });
+                return;
+            }
             let command = if button.is_active() {
                 on_cmd.as_ref()
             } else {
@@ -130,8 +156,15 @@ impl ToggleItem {
                 let guard = guard_clone.clone();
                 let refresh_gen = refresh_gen_for_toggle.clone();
                 let button = button.clone();
+                let selector = toggle_selector.clone();
                 glib::timeout_add_local(std::time::Duration::from_millis(160), move || {
-                    refresh_toggle_state(&state_cmd, &button, &guard, &refresh_gen);
+                    refresh_toggle_state(
+                        &state_cmd,
+                        selector.as_ref(),
+                        &button,
+                        &guard,
+                        &refresh_gen,
+                    );
                     glib::ControlFlow::Break
                 });
             }
@@ -139,6 +172,8 @@ impl ToggleItem {
 
         let item = Self {
             config,
+            selector,
+            night_light,
             button,
             guard,
             refresh_gen,
@@ -149,8 +184,18 @@ impl ToggleItem {
     }
 
     fn refresh(&self) {
+        if let Some(night_light) = self.night_light.as_ref() {
+            refresh_night_light(night_light, &self.button, &self.guard, &self.refresh_gen);
+            return;
+        }
         if let Some(state_cmd) = self.config.state_cmd.as_ref() {
-            refresh_toggle_state(state_cmd, &self.button, &self.guard, &self.refresh_gen);
+            refresh_toggle_state(
+                state_cmd,
+                self.selector.as_ref(),
+                &self.button,
+                &self.guard,
+                &self.refresh_gen,
+            );
         }
     }
 
@@ -159,7 +204,11 @@ impl ToggleItem {
     }
 
     fn set_watch_active(&self, active: bool) {
-        if self.config.watch_cmd.is_none() || self.config.state_cmd.is_none() {
+        // The built-in night light reads its state directly and has nothing to watch.
+        if self.config.watch_cmd.is_none()
+            || self.config.state_cmd.is_none()
+            || self.night_light.is_some()
+        {
             return;
         }
         let mut handle = self.watch_handle.borrow_mut();
@@ -186,19 +235,40 @@ impl ToggleItem {
         let button = self.button.clone();
         let guard = self.guard.clone();
         let refresh_gen = self.refresh_gen.clone();
+        let selector = self.selector.clone();
         start_command_watch(watch_cmd, move || {
-            refresh_toggle_state(&state_cmd, &button, &guard, &refresh_gen);
+            refresh_toggle_state(&state_cmd, selector.as_ref(), &button, &guard, &refresh_gen);
         })
     }
 }
 
+/// Sync the button with whether a night light backend runs; a newer refresh wins.
+fn refresh_night_light(
+    night_light: &NightLight,
+    button: &gtk::ToggleButton,
+    guard: &Rc<Cell<bool>>,
+    refresh_gen: &Arc<AtomicU64>,
+) {
+    let gen = refresh_gen.fetch_add(1, Ordering::Relaxed) + 1;
+    let button = button.clone();
+    let guard = guard.clone();
+    let refresh_gen = Arc::clone(refresh_gen);
+    night_light.query_active(move |active| {
+        if refresh_gen.load(Ordering::Relaxed) == gen {
+            apply_toggle_state(&button, &guard, active);
+        }
+    });
+}
+
 fn refresh_toggle_state(
     cmd: &str,
+    selector: Option<&OutputSelector>,
     button: &gtk::ToggleButton,
     guard: &Rc<Cell<bool>>,
     refresh_gen: &Arc<AtomicU64>,
 ) {
     let cmd = cmd.to_string();
+    let selector = selector.cloned();
     let gen = refresh_gen.fetch_add(1, Ordering::Relaxed) + 1;
     let rx = run_command_capture_status_async(&cmd);
     let button = button.clone();
@@ -221,19 +291,31 @@ fn refresh_toggle_state(
         };
         let success = output.status.success();
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let active = if stdout.trim().is_empty() {
+        let active = if let Some(selector) = selector.as_ref() {
+            match selector.extract(&stdout) {
+                Some(field) => parse_toggle_state(&field),
+                None => {
+                    warn!(?cmd, "toggle state output is missing the parse field");
+                    return;
+                }
+            }
+        } else if stdout.trim().is_empty() {
             success
         } else {
             parse_toggle_state(&stdout)
         };
-        if button.is_active() != active {
-            guard.set(true);
-            button.set_active(active);
-            guard.set(false);
-        }
+        apply_toggle_state(&button, &guard, active);
     });
 }
 
+fn apply_toggle_state(button: &gtk::ToggleButton, guard: &Rc<Cell<bool>>, active: bool) {
+    if button.is_active() != active {
+        guard.set(true);
+        button.set_active(active);
+        guard.set(false);
+    }
+}
+
 fn parse_toggle_state(output: &str) -> bool {
     for line in output.lines() {
         let lower = line.trim().to_ascii_lowercase();
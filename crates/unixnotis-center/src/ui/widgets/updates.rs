@@ -0,0 +1,258 @@
+//! Pending system update count for the "updates" card kind.
+//!
+//! Update checks hit package mirrors, so the last result is cached under the XDG state
+//! directory and the command only reruns once that result is older than the card interval.
+
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use gtk::glib;
+use gtk::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::{CardWidgetConfig, Config, PanelDebugLevel};
+
+use super::util::run_command_capture_long_async;
+use crate::debug;
+
+const CACHE_FILE: &str = "updates-cache.json";
+const DEFAULT_INTERVAL_MINUTES: u32 = 60;
+// Failed checks retry sooner than the interval, but never in a tight loop.
+const FAILURE_RETRY: Duration = Duration::from_secs(10 * 60);
+// Mirror syncs can be slow; the check runs on its own thread so a long wait is fine.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(120);
+// Exit codes that still carry a valid list: checkupdates uses 2 for "no updates",
+// dnf check-update uses 100 for "updates available".
+const OK_EXIT_CODES: [i32; 3] = [0, 2, 100];
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+struct UpdatesCache {
+    cmd: String,
+    count: Option<u32>,
+    /// Unix seconds of the last successful check.
+    checked_at: u64,
+}
+
+/// Update-count state for one card, shared with its in-flight check.
+pub(super) struct UpdatesCheck {
+    cmd: String,
+    interval: Duration,
+    path: Option<PathBuf>,
+    cache: Rc<RefCell<UpdatesCache>>,
+    last_attempt: Rc<Cell<Option<Instant>>>,
+    inflight: Rc<Cell<bool>>,
+}
+
+impl UpdatesCheck {
+    pub(super) fn new(config: &CardWidgetConfig) -> Self {
+        let cmd = config.cmd.clone().unwrap_or_else(default_command);
+        let minutes = config
+            .interval_minutes
+            .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+            .max(1);
+        let path = Config::default_state_dir()
+            .ok()
+            .map(|dir| dir.join(CACHE_FILE));
+        // A cache written for a different command says nothing about this one.
+        let cache = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str::<UpdatesCache>(&contents).ok())
+            .filter(|cache| cache.cmd == cmd)
+            .unwrap_or_else(|| UpdatesCache {
+                cmd: cmd.clone(),
+                ..UpdatesCache::default()
+            });
+        Self {
+            cmd,
+            interval: Duration::from_secs(u64::from(minutes) * 60),
+            path,
+            cache: Rc::new(RefCell::new(cache)),
+            last_attempt: Rc::new(Cell::new(None)),
+            inflight: Rc::new(Cell::new(false)),
+        }
+    }
+
+    pub(super) fn refresh(&self, root: &gtk::Box, label: &gtk::Label) {
+        if !self.inflight.get() && self.is_due() {
+            self.start_check(root, label);
+        }
+        render(
+            &self.cache.borrow(),
+            self.interval,
+            self.inflight.get(),
+            root,
+            label,
+        );
+    }
+
+    fn start_check(&self, root: &gtk::Box, label: &gtk::Label) {
+        debug::log(PanelDebugLevel::Info, || {
+            format!("updates check start: {}", self.cmd)
+        });
+        self.inflight.set(true);
+        self.last_attempt.set(Some(Instant::now()));
+        let rx = run_command_capture_long_async(&self.cmd, CHECK_TIMEOUT);
+        let cmd = self.cmd.clone();
+        let interval = self.interval;
+        let path = self.path.clone();
+        let cache = self.cache.clone();
+        let inflight = self.inflight.clone();
+        let root = root.clone();
+        let label = label.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let result = rx.recv().await;
+            inflight.set(false);
+            match result {
+                Ok(Ok(output))
+                    if output
+                        .status
+                        .code()
+                        .is_some_and(|code| OK_EXIT_CODES.contains(&code)) =>
+                {
+                    let count = parse_update_count(&String::from_utf8_lossy(&output.stdout));
+                    let mut cache = cache.borrow_mut();
+                    cache.count = Some(count);
+                    cache.checked_at = unix_now();
+                    save_cache(path.as_ref(), &cache);
+                }
+                Ok(Ok(output)) => {
+                    warn!(?cmd, status = ?output.status.code(), "updates check failed");
+                }
+                Ok(Err(err)) => {
+                    warn!(?cmd, ?err, "updates check failed");
+                }
+                Err(_) => {}
+            }
+            render(&cache.borrow(), interval, false, &root, &label);
+        });
+    }
+
+    fn is_due(&self) -> bool {
+        if let Some(last) = self.last_attempt.get() {
+            if last.elapsed() < FAILURE_RETRY.min(self.interval) {
+                return false;
+            }
+        }
+        let cache = self.cache.borrow();
+        cache.count.is_none() || cache_age(&cache) >= self.interval
+    }
+}
+
+fn render(
+    cache: &UpdatesCache,
+    interval: Duration,
+    checking: bool,
+    root: &gtk::Box,
+    label: &gtk::Label,
+) {
+    let text = match cache.count {
+        None if checking => tr("updates-checking"),
+        None => tr("value-unavailable"),
+        Some(0) => tr("updates-up-to-date"),
+        Some(count) => tr_args("updates-count", &[("count", count.into())]),
+    };
+    if label.text().as_str() != text {
+        label.set_text(&text);
+    }
+    if cache.count.is_some_and(|count| count > 0) {
+        root.add_css_class("has-updates");
+    } else {
+        root.remove_css_class("has-updates");
+    }
+    // Results older than two intervals mean recent checks have been failing.
+    if cache.count.is_some() && cache_age(cache) >= interval * 2 {
+        root.add_css_class("stale");
+    } else {
+        root.remove_css_class("stale");
+    }
+    let tooltip = glib::DateTime::from_unix_local(cache.checked_at as i64)
+        .ok()
+        .filter(|_| cache.count.is_some())
+        .and_then(|checked| checked.format("Last checked %H:%M").ok());
+    root.set_tooltip_text(tooltip.as_deref());
+}
+
+fn cache_age(cache: &UpdatesCache) -> Duration {
+    Duration::from_secs(unix_now().saturating_sub(cache.checked_at))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn save_cache(path: Option<&PathBuf>, cache: &UpdatesCache) {
+    let Some(path) = path else {
+        return;
+    };
+    let contents = match serde_json::to_string(cache) {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!(?err, "failed to serialize updates cache");
+            return;
+        }
+    };
+    if let Some(dir) = path.parent() {
+        if let Err(err) = fs::create_dir_all(dir) {
+            warn!(?err, "failed to create state directory");
+            return;
+        }
+    }
+    let tmp = path.with_extension("json.tmp");
+    if let Err(err) = fs::write(&tmp, contents).and_then(|_| fs::rename(&tmp, path)) {
+        warn!(?err, "failed to write updates cache");
+    }
+}
+
+fn default_command() -> String {
+    // checkupdates uses a private database copy, so it never locks the system one.
+    if glib::find_program_in_path("checkupdates").is_some() {
+        return "checkupdates".to_string();
+    }
+    "dnf check-update -q".to_string()
+}
+
+/// Count package lines in checkupdates or dnf check-update output.
+fn parse_update_count(output: &str) -> u32 {
+    let mut count = 0;
+    for line in output.lines() {
+        // dnf repeats replaced packages under this heading; they are already counted.
+        if line.starts_with("Obsoleting") {
+            break;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        // Skip blanks and status lines such as "Last metadata expiration check: ...".
+        if fields.len() < 2 || fields.iter().any(|field| field.ends_with(':')) {
+            continue;
+        }
+        count += 1;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_update_count;
+
+    #[test]
+    fn parse_update_count_handles_checkupdates_and_dnf() {
+        let arch = "linux 6.9.1.arch1-1 -> 6.9.2.arch1-1\nmesa 1:24.1.0-1 -> 1:24.1.1-1\n";
+        assert_eq!(parse_update_count(arch), 2);
+
+        let dnf = "Last metadata expiration check: 0:12:03 ago on Mon 01 Jan.\n\n\
+                   kernel.x86_64   6.9.2-200.fc40   updates\n\
+                   mesa-dri-drivers.x86_64   24.1.1-1.fc40   updates\n\
+                   Obsoleting Packages\n\
+                   grub2-tools.x86_64   1:2.06-120.fc40   updates\n";
+        assert_eq!(parse_update_count(dnf), 2);
+        assert_eq!(parse_update_count(""), 0);
+    }
+}
@@ -2,6 +2,8 @@
 
 #[path = "command_utils.rs"]
 mod command_utils;
+#[path = "output_select.rs"]
+mod output_select;
 #[path = "watch_utils.rs"]
 mod watch_utils;
 
@@ -18,8 +20,10 @@ use unixnotis_core::{util, NumericParseMode, PanelDebugLevel, SliderWidgetConfig
 
 use crate::debug;
 pub(super) use command_utils::{
-    run_command, run_command_capture_async, run_command_capture_status_async,
+    run_command, run_command_capture_async, run_command_capture_long_async,
+    run_command_capture_status_async,
 };
+pub(super) use output_select::OutputSelector;
 pub(super) use watch_utils::{start_command_watch, CommandWatch};
 
 pub struct CommandSlider {
@@ -30,11 +34,19 @@ pub struct CommandSlider {
     icon_name: String,
     icon_muted: Option<String>,
     config: SliderWidgetConfig,
+    parse: SliderParse,
     updating: Rc<Cell<bool>>,
     refresh_gen: Arc<AtomicU64>,
     watch_handle: RefCell<Option<CommandWatch>>,
 }
 
+// How slider command output turns into a value.
+#[derive(Clone)]
+struct SliderParse {
+    mode: NumericParseMode,
+    selector: Option<OutputSelector>,
+}
+
 impl CommandSlider {
     pub fn new(config: SliderWidgetConfig, extra_class: &str) -> Self {
         let root = gtk::Box::new(gtk::Orientation::Horizontal, 10);
@@ -80,7 +92,10 @@ impl CommandSlider {
         let icon_muted = config.icon_muted.clone();
         let min = config.min;
         let max = config.max;
-        let parse_mode = config.parse_mode;
+        let parse = SliderParse {
+            mode: config.parse_mode,
+            selector: OutputSelector::from_config(config.parse.as_deref()),
+        };
 
         if let Some(toggle_cmd) = config.toggle_cmd.as_ref() {
             let cmd = toggle_cmd.clone();
@@ -92,6 +107,7 @@ impl CommandSlider {
             let refresh_gen = refresh_gen.clone();
             let refresh_icon_name = icon_name.clone();
             let refresh_icon_muted = icon_muted.clone();
+            let refresh_parse = parse.clone();
             icon_button.connect_clicked(move |_| {
                 run_command(&cmd);
                 let refresh_cmd = refresh_cmd.clone();
@@ -102,6 +118,7 @@ impl CommandSlider {
                 let refresh_gen = refresh_gen.clone();
                 let refresh_icon_name = refresh_icon_name.clone();
                 let refresh_icon_muted = refresh_icon_muted.clone();
+                let refresh_parse = refresh_parse.clone();
                 glib::timeout_add_local(Duration::from_millis(160), move || {
                     refresh_inner(
                         refresh_cmd.clone(),
@@ -114,7 +131,7 @@ impl CommandSlider {
                         refresh_gen.clone(),
                         refresh_icon_name.clone(),
                         refresh_icon_muted.clone(),
-                        parse_mode,
+                        refresh_parse.clone(),
                     );
                     glib::ControlFlow::Break
                 });
@@ -150,6 +167,7 @@ impl CommandSlider {
             icon_name,
             icon_muted,
             config,
+            parse,
             updating,
             refresh_gen,
             watch_handle: RefCell::new(None),
@@ -168,7 +186,7 @@ impl CommandSlider {
             self.refresh_gen.clone(),
             self.icon_name.clone(),
             self.icon_muted.clone(),
-            self.config.parse_mode,
+            self.parse.clone(),
         );
     }
 
@@ -202,7 +220,7 @@ impl CommandSlider {
         let refresh_icon_muted = self.icon_muted.clone();
         let min = self.config.min;
         let max = self.config.max;
-        let parse_mode = self.config.parse_mode;
+        let parse = self.parse.clone();
         start_command_watch(cmd, move || {
             refresh_inner(
                 refresh_cmd.clone(),
@@ -215,7 +233,7 @@ impl CommandSlider {
                 refresh_gen.clone(),
                 refresh_icon_name.clone(),
                 refresh_icon_muted.clone(),
-                parse_mode,
+                parse.clone(),
             );
         })
     }
@@ -233,7 +251,7 @@ fn refresh_inner(
     refresh_gen: Arc<AtomicU64>,
     icon_name: String,
     icon_muted: Option<String>,
-    parse_mode: NumericParseMode,
+    parse: SliderParse,
 ) {
     let gen = refresh_gen.fetch_add(1, Ordering::Relaxed) + 1;
 
@@ -259,7 +277,12 @@ fn refresh_inner(
             return;
         }
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let value = match parse_numeric(&stdout, min, max, parse_mode) {
+        let field = match parse.selector.as_ref() {
+            Some(selector) => selector.extract(&stdout),
+            None => Some(stdout.to_string()),
+        };
+        let parsed = field.and_then(|field| parse_numeric(&field, min, max, parse.mode));
+        let value = match parsed {
             Some(value) => value,
             None => {
                 let snippet = util::log_snippet(stdout.trim());
@@ -0,0 +1,170 @@
+# German UI strings.
+
+## Panel header
+
+panel-title = Benachrichtigungen
+panel-sort-tooltip = Sortierung ändern
+panel-dnd = Nicht stören
+panel-dnd-menu-tooltip = Eine Weile nicht stören
+panel-dnd-for-hour = Für 1 Stunde
+panel-dnd-for-minutes = Für { $minutes } Minuten
+panel-dnd-until-tomorrow = Bis morgen
+panel-dnd-until = Bis { $time }
+panel-clear = Leeren
+panel-clear-menu-tooltip = Nur einen Teil leeren
+panel-clear-history = Verlauf leeren
+panel-clear-active = Aktive leeren
+panel-clear-all = Alles leeren
+panel-close = Schließen
+panel-new-pill = { $count } neu · nach oben
+panel-undo-cleared = Benachrichtigungen gelöscht
+panel-undo = Rückgängig
+panel-rules-tooltip = Benachrichtigungsregeln
+
+## Rules editor
+
+rules-title = Regeln
+rules-empty = Noch keine Regeln
+rules-app-placeholder = App-Name
+rules-mute = Popups und Töne stummschalten
+rules-urgency-keep = Dringlichkeit beibehalten
+rules-urgency-low = Niedrige Dringlichkeit
+rules-urgency-normal = Normale Dringlichkeit
+rules-urgency-critical = Kritische Dringlichkeit
+rules-save = Speichern
+rules-remove-tooltip = Regel entfernen
+rules-muted = stumm
+rules-config-only = Diese Regel in config.toml bearbeiten
+
+sort-newest = Neueste
+sort-oldest = Älteste
+sort-app = App
+sort-urgency = Dringlichkeit
+
+## Notification list
+
+group-critical = Kritisch
+group-pinned = Angeheftet
+list-pin-tooltip = Oben anheften
+list-unpin-tooltip = Lösen
+list-empty = Alles erledigt
+
+## Media controls
+
+media-up-next = Als Nächstes
+media-unknown-track = Unbekannter Titel
+media-play-track = Diesen Titel abspielen
+media-show-player = Player anzeigen
+media-choose-player = Player wählen
+media-volume = Player-Lautstärke
+
+## Widgets
+
+power-confirm = { $action }?
+power-cancel = Abbrechen
+value-unavailable = k. A.
+updates-checking = Wird geprüft…
+updates-up-to-date = Aktuell
+updates-count =
+    { $count ->
+        [one] 1 Aktualisierung
+       *[other] { $count } Aktualisierungen
+    }
+
+## Layout preview
+
+preview-popup = Popup { $slot }
+preview-urgent = Dringend { $slot }
+preview-app = { $apps } { $slot }
+preview-panel = Panel
+
+## Config reload
+
+config-invalid-summary = Konfiguration nicht übernommen
+config-invalid-body =
+    Die bisherigen Einstellungen bleiben aktiv.
+    { $error }
+
+## Popups
+
+popup-group-more = +{ $count }
+popup-group-collapse = Weniger anzeigen
+popup-overflow = +{ $count } weitere
+popup-overflow-tooltip = Benachrichtigungszentrale öffnen
+
+## Daemon
+
+welcome-summary = Willkommen bei UnixNotis
+welcome-body =
+    Panel ein- und ausblenden: noticenterctl toggle-panel
+    Nicht stören: noticenterctl dnd toggle
+    Einstellungen und Beispiele: { $config }
+battery-low-summary = Akku schwach
+battery-low-body = Noch { $percent } %.
+battery-critical-summary = Akku fast leer
+battery-critical-body = Noch { $percent } %. Ladegerät anschließen oder jetzt in Bereitschaft versetzen.
+battery-unplugged-summary = Ladegerät getrennt
+battery-unplugged-body = Akkubetrieb, noch { $percent } %.
+battery-suspend = Bereitschaft
+unit-failed-summary = { $unit } fehlgeschlagen
+unit-failed-user-body = Benutzer-Unit: { $description }
+unit-failed-system-body = System-Unit: { $description }
+unit-show-journal = Journal anzeigen
+disk-low-summary = Wenig Speicherplatz
+disk-low-line = { $mount }: { $free } frei ({ $percent } %)
+mail-new-count = { $count } neue Nachrichten
+mail-no-subject = (kein Betreff)
+mail-open = Öffnen
+action-app-gone-summary = { $app } läuft nicht mehr
+action-app-gone-body = Die Aktion konnte nicht zugestellt werden.
+
+## Installer
+
+installer-title = UnixNotis-Installationsprogramm
+installer-subtitle = Benachrichtigungszentrale für Arch und Wayland
+installer-system-status = Systemstatus
+installer-actions = Aktionen
+installer-compatibility = Kompatibilität
+installer-daemons = Benachrichtigungsdienste
+installer-owner = Besitzer
+installer-current-owner = Aktueller Besitzer
+installer-verification = Prüfung
+installer-service-layout = Dienst-Aufteilung
+installer-shell-extras = Vervollständigung und Manpage
+installer-enabled = aktiviert
+installer-disabled = deaktiviert
+installer-blocked = Blockiert
+installer-confirm = { $action } bestätigen
+installer-confirmation = Bestätigung
+installer-reinstall-warning = Die Neuinstallation überschreibt die Programme und die systemd-Unit.
+installer-reset-warning = Zurücksetzen überschreibt config.toml und die Theme-Dateien mit den Standardwerten.
+installer-progress = Fortschritt
+installer-steps = Schritte
+installer-logs = Protokoll
+installer-in-progress = Läuft
+installer-completed = Abgeschlossen
+installer-failed = Fehlgeschlagen
+installer-pending = Ausstehend
+installer-error = Fehler
+installer-see-logs = Details stehen im Protokoll.
+installer-running = Läuft...
+installer-done-footer = Enter = zurück zum Menü  Q = beenden
+installer-quit = Beenden
+installer-key-select = auswählen
+installer-key-move = bewegen
+installer-key-refresh = neu prüfen
+installer-key-verify = Prüfung umschalten
+installer-key-layout = Unit-Aufteilung
+installer-key-extras = Vervollständigung
+installer-key-quit = beenden
+installer-key-proceed = fortfahren
+installer-key-cancel = abbrechen
+
+action-test = Probelauf
+action-install = Installieren
+action-reinstall = Neu installieren
+action-uninstall = Deinstallieren
+action-reset = Konfiguration zurücksetzen
+
+layout-single = eine Unit
+layout-split = getrennte Units
@@ -0,0 +1,186 @@
+# English UI strings. This catalog is the fallback for every other language,
+# so every message id must exist here first.
+
+## Panel header
+
+panel-title = Notifications
+panel-sort-tooltip = Change sort order
+panel-dnd = Do Not Disturb
+panel-dnd-menu-tooltip = Do Not Disturb for a while
+panel-dnd-for-hour = For 1 hour
+panel-dnd-for-minutes = For { $minutes } minutes
+panel-dnd-until-tomorrow = Until tomorrow
+# $time is the local end time, e.g. 08:00.
+panel-dnd-until = Until { $time }
+panel-clear = Clear
+panel-clear-menu-tooltip = Clear only some notifications
+panel-clear-history = Clear history
+panel-clear-active = Clear active
+panel-clear-all = Clear everything
+panel-close = Close
+panel-new-pill = { $count } new · jump to top
+panel-undo-cleared = Notifications cleared
+panel-undo = Undo
+panel-rules-tooltip = Notification rules
+
+## Rules editor
+
+rules-title = Rules
+rules-empty = No rules yet
+rules-app-placeholder = App name
+rules-mute = Mute popups and sounds
+rules-urgency-keep = Keep urgency
+rules-urgency-low = Low urgency
+rules-urgency-normal = Normal urgency
+rules-urgency-critical = Critical urgency
+rules-save = Save
+rules-remove-tooltip = Remove rule
+rules-muted = muted
+# Shown for rules without a name, which can only be changed in config.toml.
+rules-config-only = Edit this rule in config.toml
+
+sort-newest = Newest
+sort-oldest = Oldest
+sort-app = App
+sort-urgency = Urgency
+
+## Notification list
+
+group-critical = Critical
+group-pinned = Pinned
+list-pin-tooltip = Pin to the top
+list-unpin-tooltip = Unpin
+list-empty = All caught up
+
+## Media controls
+
+media-up-next = Up next
+media-unknown-track = Unknown track
+media-play-track = Play this track
+media-show-player = Show player
+media-choose-player = Choose player
+media-volume = Player volume
+
+## Widgets
+
+# $action is the configured power button label.
+power-confirm = { $action }?
+power-cancel = Cancel
+value-unavailable = n/a
+updates-checking = Checking…
+updates-up-to-date = Up to date
+updates-count =
+    { $count ->
+        [one] 1 update
+       *[other] { $count } updates
+    }
+
+## Layout preview
+
+# $slot counts the popup slots from 1.
+preview-popup = Popup { $slot }
+preview-urgent = Urgent { $slot }
+# $apps lists the apps moved to that corner with `noticenterctl popup-position`.
+preview-app = { $apps } { $slot }
+preview-panel = Panel
+
+## Config reload
+
+config-invalid-summary = Config not applied
+# $error is the parse error, which names the line at fault.
+config-invalid-body =
+    The previous settings stay active.
+    { $error }
+
+## Popups
+
+# $count is how many more popups from the same app are collapsed behind this one.
+popup-group-more = +{ $count }
+popup-group-collapse = Show less
+# $count is how many popups are queued or piled up beyond the ones shown in full.
+popup-overflow = +{ $count } more
+popup-overflow-tooltip = Open the notification center
+
+## Daemon
+
+welcome-summary = Welcome to UnixNotis
+# $config is the path of the config file that was just written.
+welcome-body =
+    Toggle the panel: noticenterctl toggle-panel
+    Do Not Disturb: noticenterctl dnd toggle
+    Settings and examples: { $config }
+battery-low-summary = Battery low
+# $percent is the remaining charge, rounded to whole percent.
+battery-low-body = { $percent }% remaining.
+battery-critical-summary = Battery critically low
+battery-critical-body = { $percent }% remaining. Plug in the charger or suspend now.
+battery-unplugged-summary = Charger unplugged
+battery-unplugged-body = Running on battery, { $percent }% remaining.
+battery-suspend = Suspend now
+# $unit is a systemd unit name such as "backup.service".
+unit-failed-summary = { $unit } failed
+# $description is the unit's own description.
+unit-failed-user-body = User unit: { $description }
+unit-failed-system-body = System unit: { $description }
+unit-show-journal = Show journal
+disk-low-summary = Low disk space
+# One line per mount; $free is a size such as "3.2 GiB", $percent the free share.
+disk-low-line = { $mount }: { $free } free ({ $percent }%)
+# Summary when several messages arrive at once; the body lists the first few.
+mail-new-count = { $count } new messages
+mail-no-subject = (no subject)
+mail-open = Open
+# Shown when an action is clicked for an app that has since exited.
+action-app-gone-summary = { $app } is no longer running
+action-app-gone-body = The action could not be delivered.
+
+## Installer
+
+installer-title = UnixNotis Installer
+installer-subtitle = Arch Wayland Notification Center
+installer-system-status = System status
+installer-actions = Actions
+installer-compatibility = Compatibility
+installer-daemons = Notification daemons
+installer-owner = Owner
+installer-current-owner = Current owner
+installer-verification = Verification
+installer-service-layout = Service layout
+installer-shell-extras = Completions and man page
+installer-enabled = enabled
+installer-disabled = disabled
+installer-blocked = Blocked
+installer-confirm = Confirm { $action }
+installer-confirmation = Confirmation
+installer-reinstall-warning = Reinstall will overwrite binaries and the systemd unit.
+installer-reset-warning = Reset will overwrite config.toml and theme files with defaults.
+installer-progress = Progress
+installer-steps = Steps
+installer-logs = Logs
+installer-in-progress = In progress
+installer-completed = Completed
+installer-failed = Failed
+installer-pending = Pending
+installer-error = Error
+installer-see-logs = See logs for full output.
+installer-running = Running...
+installer-done-footer = Enter = back to menu  Q = quit
+installer-quit = Quit
+installer-key-select = select
+installer-key-move = move
+installer-key-refresh = refresh
+installer-key-verify = toggle verify
+installer-key-layout = unit layout
+installer-key-extras = completions
+installer-key-quit = quit
+installer-key-proceed = proceed
+installer-key-cancel = cancel
+
+action-test = Trial run
+action-install = Install
+action-reinstall = Reinstall
+action-uninstall = Uninstall
+action-reset = Reset config
+
+layout-single = single unit
+layout-split = separate units
@@ -4,6 +4,7 @@
 
 use std::env;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -13,10 +14,14 @@ use tracing::warn;
 use crate::{DEFAULT_BASE_CSS, DEFAULT_PANEL_CSS, DEFAULT_POPUP_CSS, DEFAULT_WIDGETS_CSS};
 
 use super::config_runtime::{apply_brightness_backend, apply_volume_backend, sanitize_config};
-use super::Config;
+use super::{Config, RuleConfig};
 
 static LEGACY_RENAME_WARNED: AtomicBool = AtomicBool::new(false);
 
+/// Commented config template written on first start; everything in it is a default.
+pub const DEFAULT_CONFIG_TOML: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/config.toml"));
+
 #[derive(Debug, Clone)]
 pub struct ThemePaths {
     pub base_css: PathBuf,
@@ -31,6 +36,8 @@ pub enum ConfigError {
     ReadFailed(String),
     #[error("failed to parse config: {0}")]
     ParseFailed(String),
+    #[error("failed to write config file: {0}")]
+    WriteFailed(String),
     #[error("missing $HOME, unable to resolve config directory")]
     MissingHome,
 }
@@ -40,8 +47,13 @@ impl Config {
     pub fn load_from_path(path: &Path) -> Result<Self, ConfigError> {
         let contents =
             fs::read_to_string(path).map_err(|err| ConfigError::ReadFailed(err.to_string()))?;
+        Self::parse(&contents)
+    }
+
+    /// Parse config file contents, e.g. to check an edit before applying it.
+    pub fn parse(contents: &str) -> Result<Self, ConfigError> {
         let mut config: Config =
-            toml::from_str(&contents).map_err(|err| ConfigError::ParseFailed(err.to_string()))?;
+            toml::from_str(contents).map_err(|err| ConfigError::ParseFailed(err.to_string()))?;
         config.apply_runtime_defaults();
         Ok(config)
     }
@@ -57,6 +69,40 @@ impl Config {
         Self::load_from_path(&path)
     }
 
+    /// Write the commented template to `path` unless a config already exists there.
+    ///
+    /// Returns true when the file was created.
+    pub fn write_default_config(path: &Path) -> Result<bool, ConfigError> {
+        if path.exists() {
+            return Ok(false);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| ConfigError::ReadFailed(err.to_string()))?;
+        }
+        write_if_missing(path, DEFAULT_CONFIG_TOML)?;
+        Ok(true)
+    }
+
+    /// Replace the `[[rules]]` tables in the config file at `path`, keeping the rest as written.
+    ///
+    /// Comments above the first existing rule are kept. A missing file is created.
+    pub fn write_rules(path: &Path, rules: &[RuleConfig]) -> Result<(), ConfigError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(ConfigError::ReadFailed(err.to_string())),
+        };
+        let contents = replace_rules(&contents, rules)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| ConfigError::WriteFailed(err.to_string()))?;
+        }
+        // Write beside the config and rename so a crash never leaves a truncated file.
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, contents)
+            .and_then(|()| fs::rename(&tmp, path))
+            .map_err(|err| ConfigError::WriteFailed(err.to_string()))
+    }
+
     /// Resolve configured CSS paths relative to the config directory.
     pub fn resolve_theme_paths(&self) -> Result<ThemePaths, ConfigError> {
         let base = Self::default_config_dir()?;
@@ -130,6 +176,28 @@ impl Config {
         Ok(PathBuf::from(home).join(".config").join("unixnotis"))
     }
 
+    /// Return the state directory for runtime data that should survive restarts.
+    pub fn default_state_dir() -> Result<PathBuf, ConfigError> {
+        if let Ok(xdg) = env::var("XDG_STATE_HOME") {
+            return Ok(PathBuf::from(xdg).join("unixnotis"));
+        }
+        let home = env::var("HOME").map_err(|_| ConfigError::MissingHome)?;
+        // Follow the XDG base directory default for state files.
+        Ok(PathBuf::from(home)
+            .join(".local")
+            .join("state")
+            .join("unixnotis"))
+    }
+
+    /// Return the cache directory for data that can be fetched again, such as album art.
+    pub fn default_cache_dir() -> Result<PathBuf, ConfigError> {
+        if let Ok(xdg) = env::var("XDG_CACHE_HOME") {
+            return Ok(PathBuf::from(xdg).join("unixnotis"));
+        }
+        let home = env::var("HOME").map_err(|_| ConfigError::MissingHome)?;
+        Ok(PathBuf::from(home).join(".cache").join("unixnotis"))
+    }
+
     /// Return the default config file path.
     pub fn default_config_path() -> Result<PathBuf, ConfigError> {
         Ok(Self::default_config_dir()?.join("config.toml"))
@@ -145,9 +213,221 @@ impl Config {
     }
 }
 
+fn replace_rules(contents: &str, rules: &[RuleConfig]) -> Result<String, ConfigError> {
+    let mut document: toml_edit::DocumentMut = contents
+        .parse()
+        .map_err(|err: toml_edit::TomlError| ConfigError::ParseFailed(err.to_string()))?;
+    let previous = document
+        .get("rules")
+        .and_then(toml_edit::Item::as_array_of_tables)
+        .and_then(|tables| tables.get(0))
+        .map(|first| (first.position(), first.decor().clone()));
+
+    let mut tables = toml_edit::ArrayOfTables::new();
+    for rule in rules {
+        let table = toml_edit::ser::to_document(rule)
+            .map_err(|err| ConfigError::WriteFailed(err.to_string()))?;
+        tables.push(table.as_table().clone());
+    }
+    if let (Some(first), Some((position, decor))) = (tables.get_mut(0), previous) {
+        // Later tables inherit the position, so the rules stay where they were in the file.
+        if let Some(position) = position {
+            first.set_position(position);
+        }
+        *first.decor_mut() = decor;
+    }
+    if tables.is_empty() {
+        document.remove("rules");
+    } else {
+        document.insert("rules", toml_edit::Item::ArrayOfTables(tables));
+    }
+    Ok(document.to_string())
+}
+
 fn write_if_missing(path: &Path, contents: &str) -> Result<(), ConfigError> {
     if path.exists() {
         return Ok(());
     }
     fs::write(path, contents).map_err(|err| ConfigError::ReadFailed(err.to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_only_restates_defaults() {
+        let template: Config = toml::from_str(DEFAULT_CONFIG_TOML).expect("template parses");
+        assert_eq!(
+            toml::to_string(&template).unwrap(),
+            toml::to_string(&Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn template_examples_parse_when_uncommented() {
+        let uncommented = DEFAULT_CONFIG_TOML
+            .lines()
+            .map(|line| {
+                let body = line.strip_prefix("# ").unwrap_or(line);
+                let is_setting = body.split_once(" = ").is_some_and(|(key, _)| {
+                    key.chars().all(|ch| ch.is_ascii_lowercase() || ch == '_')
+                });
+                if body.starts_with('[') || is_setting {
+                    body
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let config: Config = toml::from_str(&uncommented).expect("examples parse");
+        assert_eq!(config.rules.len(), 3);
+        assert_eq!(config.widgets.cards.len(), 2);
+        assert_eq!(config.outputs.len(), 2);
+        assert_eq!(config.mail.accounts.len(), 1);
+        assert_eq!(config.mqtt.topics.len(), 1);
+    }
+
+    #[test]
+    fn power_defaults_confirm_only_what_ends_the_session() {
+        let actions = crate::CardActionConfig::power_defaults();
+        let summary = actions
+            .iter()
+            .map(|action| (action.icon.as_str(), action.confirm))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("system-lock-screen-symbolic", false),
+                ("system-suspend-symbolic", false),
+                ("system-reboot-symbolic", true),
+                ("system-shutdown-symbolic", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn replacing_rules_keeps_the_rest_of_the_file() {
+        let original = r#"# My config
+[general]
+dnd_default = true # keep this
+
+# Muted apps
+[[rules]]
+name = "old"
+app = "spotify"
+
+[panel]
+width = 420
+"#;
+        let rules = vec![
+            RuleConfig {
+                name: Some("discord".to_string()),
+                app: Some("discord".to_string()),
+                no_popup: Some(true),
+                ..RuleConfig::default()
+            },
+            RuleConfig {
+                app: Some("backup".to_string()),
+                force_urgency: Some(2),
+                ..RuleConfig::default()
+            },
+        ];
+        let updated = replace_rules(original, &rules).expect("rules replaced");
+        assert_eq!(
+            updated,
+            r#"# My config
+[general]
+dnd_default = true # keep this
+
+# Muted apps
+[[rules]]
+name = "discord"
+app = "discord"
+no_popup = true
+
+[[rules]]
+app = "backup"
+force_urgency = 2
+
+[panel]
+width = 420
+"#
+        );
+        let config: Config = toml::from_str(&updated).expect("updated config parses");
+        assert_eq!(config.rules, rules);
+
+        let cleared = replace_rules(&updated, &[]).expect("rules removed");
+        assert!(!cleared.contains("[[rules]]"));
+        assert!(cleared.contains("width = 420"));
+    }
+
+    #[test]
+    fn exclusive_zone_accepts_names_and_pixels() {
+        let config: Config = toml::from_str(
+            r#"
+            [panel]
+            exclusive_zone = "auto"
+
+            [popups]
+            exclusive_zone = 48
+            "#,
+        )
+        .expect("config parses");
+        assert_eq!(config.panel.exclusive_zone, crate::ExclusiveZone::Auto);
+        assert_eq!(
+            config.popups.exclusive_zone,
+            crate::ExclusiveZone::Fixed(48)
+        );
+        assert!(toml::to_string(&config)
+            .unwrap()
+            .contains("exclusive_zone = 48"));
+
+        for bad in ["\"wide\"", "-1"] {
+            let text = format!("[panel]\nexclusive_zone = {bad}\n");
+            assert!(toml::from_str::<Config>(&text).is_err(), "{bad} accepted");
+        }
+    }
+
+    #[test]
+    fn output_blocks_override_placement() {
+        let config: Config = toml::from_str(
+            r#"
+            [panel]
+            width = 420
+
+            [outputs."DP-1".panel]
+            width = 300
+            anchor = "left"
+
+            [outputs."Dell U2720Q".panel]
+            width = 500
+            "#,
+        )
+        .expect("config parses");
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let panel = config.panel_for_output(&names(&["DP-1", "Dell U2720Q"]));
+        assert_eq!(panel.width, 300);
+        assert!(matches!(panel.anchor, crate::Anchor::Left));
+        assert_eq!(panel.margin.top, config.panel.margin.top);
+
+        assert_eq!(
+            config
+                .panel_for_output(&names(&["DP-2", "Dell U2720Q"]))
+                .width,
+            500
+        );
+        assert_eq!(config.panel_for_output(&names(&["HDMI-A-1"])).width, 420);
+        assert_eq!(
+            config.popups_for_output(&names(&["DP-1"])).width,
+            config.popups.width
+        );
+    }
+}
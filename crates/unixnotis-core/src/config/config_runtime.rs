@@ -15,6 +15,14 @@ const MAX_POPUP_WIDTH: i32 = 2048;
 const MAX_SPACING: i32 = 256;
 const MAX_MARGIN: i32 = 512;
 const MAX_CARD_HEIGHT: i32 = 2048;
+const MIN_ROW_ICON_SIZE: i32 = 12;
+const MAX_ROW_ICON_SIZE: i32 = 96;
+const MAX_BODY_LINES: i32 = 32;
+const MAX_DECODE_WORKERS: usize = 8;
+const MIN_PREVIEW_HEIGHT: i32 = 48;
+const MAX_PREVIEW_HEIGHT: i32 = 512;
+const MIN_NIGHT_TEMPERATURE: u32 = 1000;
+const MAX_NIGHT_TEMPERATURE: u32 = 6500;
 
 pub(super) fn apply_volume_backend(volume: &mut SliderWidgetConfig) {
     if !volume.enabled {
@@ -94,6 +102,20 @@ pub(super) fn sanitize_config(config: &mut Config) {
         config.panel.height = config.panel.height.clamp(1, MAX_PANEL_HEIGHT);
     }
 
+    // Keep row icon slots and body caps within sane bounds; 0 lines means unlimited.
+    config.panel.row_icon_size = config
+        .panel
+        .row_icon_size
+        .clamp(MIN_ROW_ICON_SIZE, MAX_ROW_ICON_SIZE);
+    config.panel.body_max_lines = config.panel.body_max_lines.clamp(0, MAX_BODY_LINES);
+
+    // Decoding is CPU-heavy; cap explicit worker counts so a typo cannot spawn hundreds.
+    config.icons.decode_workers = config.icons.decode_workers.min(MAX_DECODE_WORKERS);
+    config.icons.preview_max_height = config
+        .icons
+        .preview_max_height
+        .clamp(MIN_PREVIEW_HEIGHT, MAX_PREVIEW_HEIGHT);
+
     // Normalize popup sizing and spacing.
     if config.popups.width <= 0 {
         config.popups.width = super::PopupConfig::default().width;
@@ -113,6 +135,42 @@ pub(super) fn sanitize_config(config: &mut Config) {
     config.panel.margin.right = config.panel.margin.right.clamp(0, MAX_MARGIN);
     config.panel.margin.bottom = config.panel.margin.bottom.clamp(0, MAX_MARGIN);
     config.panel.margin.left = config.panel.margin.left.clamp(0, MAX_MARGIN);
+    for output in config.outputs.values_mut() {
+        for (placement, max_width) in [
+            (&mut output.panel, MAX_PANEL_WIDTH),
+            (&mut output.popups, MAX_POPUP_WIDTH),
+        ] {
+            if let Some(margin) = placement.margin.as_mut() {
+                margin.top = margin.top.clamp(0, MAX_MARGIN);
+                margin.right = margin.right.clamp(0, MAX_MARGIN);
+                margin.bottom = margin.bottom.clamp(0, MAX_MARGIN);
+                margin.left = margin.left.clamp(0, MAX_MARGIN);
+            }
+            // Non-positive widths fall back to the global value instead of the built-in default.
+            placement.width = placement
+                .width
+                .filter(|width| *width > 0)
+                .map(|width| width.clamp(1, max_width));
+        }
+    }
+
+    // A low threshold under the critical one would skip straight to the critical warning.
+    config.battery.critical_percent = config.battery.critical_percent.min(100);
+    config.battery.low_percent = config
+        .battery
+        .low_percent
+        .clamp(config.battery.critical_percent, 100);
+
+    config.disk.min_free_percent = config.disk.min_free_percent.min(100);
+    config.disk.mounts.retain(|mount| !mount.trim().is_empty());
+
+    config
+        .mqtt
+        .topics
+        .retain(|topic| !topic.topic.trim().is_empty());
+    for topic in &mut config.mqtt.topics {
+        topic.urgency = topic.urgency.min(2);
+    }
 
     for stat in &mut config.widgets.stats {
         if stat.min_height < 0 {
@@ -126,4 +184,10 @@ pub(super) fn sanitize_config(config: &mut Config) {
         }
         card.min_height = card.min_height.clamp(0, MAX_CARD_HEIGHT);
     }
+    // Gamma backends reject temperatures outside the usual daylight range.
+    config.widgets.night_light.temperature = config
+        .widgets
+        .night_light
+        .temperature
+        .clamp(MIN_NIGHT_TEMPERATURE, MAX_NIGHT_TEMPERATURE);
 }
@@ -2,7 +2,12 @@
 //!
 //! Keeps schema definitions in one place for easier auditing.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
+use zbus::zvariant::Type;
+
+use crate::model::Urgency;
 
 /// Top-level configuration loaded from config.toml.
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
@@ -13,17 +18,137 @@ pub struct Config {
     pub panel: PanelConfig,
     pub history: HistoryConfig,
     pub media: MediaConfig,
+    pub battery: BatteryConfig,
+    pub systemd: SystemdConfig,
+    pub disk: DiskConfig,
+    pub remote: RemoteConfig,
+    pub security: SecurityConfig,
+    pub mail: MailConfig,
+    pub mqtt: MqttConfig,
     pub widgets: WidgetsConfig,
     pub sound: SoundConfig,
     pub theme: ThemeConfig,
+    pub icons: IconConfig,
     pub rules: Vec<RuleConfig>,
+    /// Placement overrides keyed by output connector (`DP-1`) or monitor model.
+    pub outputs: BTreeMap<String, OutputConfig>,
 }
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct GeneralConfig {
     pub dnd_default: bool,
     pub log_level: Option<String>,
+    /// Start and stop the popup and panel processes with the daemon.
+    pub manage_ui: bool,
+    /// UI language tag (e.g. "de"); unset follows LC_MESSAGES.
+    pub language: Option<String>,
+    /// Whether the panel and popups place themselves with wlr-layer-shell.
+    pub layer_shell: LayerShellMode,
+    /// What happens to notifications with actions once the app that sent them leaves the bus.
+    pub sender_exit: SenderExit,
+    /// Hold back popups and sounds while the focused window is fullscreen (Hyprland), sending
+    /// non-critical notifications straight to history.
+    pub suppress_when_fullscreen: bool,
+    /// Like `suppress_when_fullscreen`, while the screen is recorded or shared (Hyprland).
+    pub suppress_when_screencast: bool,
+    /// Like `suppress_when_fullscreen`, while an app inhibits the screensaver, e.g. for video.
+    pub suppress_when_inhibited: bool,
+    /// Log popup latency percentiles split into daemon, bus, and GTK time.
+    pub measure_latency: bool,
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        Self {
+            dnd_default: false,
+            log_level: None,
+            manage_ui: true,
+            language: None,
+            layer_shell: LayerShellMode::Auto,
+            sender_exit: SenderExit::Keep,
+            suppress_when_fullscreen: false,
+            suppress_when_screencast: false,
+            suppress_when_inhibited: false,
+            measure_latency: false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, Default, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SenderExit {
+    /// Leave the notification as it is.
+    #[default]
+    Keep,
+    /// Remove its action buttons and take down its popup; the panel entry stays.
+    Stale,
+    /// Close it as if the app had withdrawn it.
+    Close,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, Default, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayerShellMode {
+    /// Use layer-shell when the compositor offers it, otherwise fall back to regular windows.
+    #[default]
+    Auto,
+    /// Refuse to start without layer-shell instead of falling back.
+    Require,
+    /// Always use regular windows, for compositors whose layer-shell support misbehaves.
+    Off,
+}
+
+/// Screen space a layer surface asks the compositor to keep other windows out of.
+///
+/// Written as `"none"`, `"auto"`, or a pixel count in config files.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, Default, Eq, PartialEq)]
+#[serde(try_from = "ExclusiveZoneValue", into = "ExclusiveZoneValue")]
+pub enum ExclusiveZone {
+    /// Reserve nothing; the surface still stays clear of space other surfaces reserve.
+    #[default]
+    None,
+    /// Reserve the surface's own size along the edge it is anchored to.
+    Auto,
+    /// Reserve this many pixels from the anchored edge.
+    Fixed(i32),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum ExclusiveZoneValue {
+    Name(String),
+    Pixels(i32),
+}
+
+impl TryFrom<ExclusiveZoneValue> for ExclusiveZone {
+    type Error = String;
+
+    fn try_from(value: ExclusiveZoneValue) -> Result<Self, Self::Error> {
+        match value {
+            ExclusiveZoneValue::Name(name) => match name.trim().to_ascii_lowercase().as_str() {
+                "none" => Ok(Self::None),
+                "auto" => Ok(Self::Auto),
+                other => Err(format!(
+                    "unknown exclusive_zone \"{other}\"; expected \"none\", \"auto\", or pixels"
+                )),
+            },
+            ExclusiveZoneValue::Pixels(pixels) if pixels >= 0 => Ok(Self::Fixed(pixels)),
+            ExclusiveZoneValue::Pixels(pixels) => {
+                Err(format!("exclusive_zone must not be negative, got {pixels}"))
+            }
+        }
+    }
+}
+
+impl From<ExclusiveZone> for ExclusiveZoneValue {
+    fn from(zone: ExclusiveZone) -> Self {
+        match zone {
+            ExclusiveZone::None => Self::Name("none".to_string()),
+            ExclusiveZone::Auto => Self::Name("auto".to_string()),
+            ExclusiveZone::Fixed(pixels) => Self::Pixels(pixels),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -34,10 +159,31 @@ pub struct PopupConfig {
     pub width: i32,
     pub spacing: i32,
     pub max_visible: usize,
+    /// Timeout for low urgency; unset uses `default_timeout_ms`.
+    pub low_timeout_ms: Option<u64>,
+    /// Timeout for normal urgency, and for low urgency without `low_timeout_ms`.
+    #[serde(alias = "normal_timeout_ms")]
     pub default_timeout_ms: u64,
+    /// Timeout for critical urgency; unset keeps critical notifications until dismissed.
     pub critical_timeout_ms: Option<u64>,
+    /// Shortest timeout a popup gets; shorter ones, including the app's own, are raised to it.
+    pub min_timeout_ms: Option<u64>,
+    /// Longest timeout a popup gets; wins over `min_timeout_ms` when the two cross.
+    pub max_timeout_ms: Option<u64>,
     pub allow_click_through: bool,
     pub output: Option<String>,
+    /// Pause a popup's timeout while the pointer is over it.
+    pub pause_on_hover: bool,
+    /// Freeze timeouts while the system is suspended instead of letting them run out.
+    pub pause_during_suspend: bool,
+    /// Collapse popups from the same app into one toast with a counter.
+    pub group_by_app: bool,
+    /// Space reserved for the popup stack while it is shown.
+    pub exclusive_zone: ExclusiveZone,
+    /// Treatment of critical notifications when urgent mode is on.
+    pub urgent: UrgentPopupConfig,
+    /// On-screen display for volume and brightness levels.
+    pub osd: OsdConfig,
 }
 
 impl Default for PopupConfig {
@@ -48,14 +194,115 @@ impl Default for PopupConfig {
             width: 360,
             spacing: 12,
             max_visible: 4,
+            low_timeout_ms: None,
             default_timeout_ms: 5000,
             critical_timeout_ms: None,
+            min_timeout_ms: None,
+            max_timeout_ms: None,
             allow_click_through: false,
             output: None,
+            pause_on_hover: true,
+            pause_during_suspend: true,
+            group_by_app: false,
+            exclusive_zone: ExclusiveZone::None,
+            urgent: UrgentPopupConfig::default(),
+            osd: OsdConfig::default(),
+        }
+    }
+}
+
+/// `[popups.osd]`: the volume and brightness overlay.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OsdConfig {
+    /// Show volume and brightness notifications (a `value` hint plus a synchronous tag such as
+    /// `volume`) as the overlay instead of a toast.
+    pub capture_notifications: bool,
+    pub anchor: Anchor,
+    pub margin: Margins,
+    pub width: i32,
+    /// How long the overlay stays after the last update.
+    pub timeout_ms: u64,
+}
+
+impl Default for OsdConfig {
+    fn default() -> Self {
+        Self {
+            capture_notifications: false,
+            anchor: Anchor::Bottom,
+            margin: Margins {
+                top: 0,
+                right: 0,
+                bottom: 96,
+                left: 0,
+            },
+            width: 260,
+            timeout_ms: 1500,
+        }
+    }
+}
+
+/// `[popups.urgent]`: how critical notifications are shown while urgent mode is enabled.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UrgentPopupConfig {
+    /// Keep critical notifications up until dismissed and style them as urgent.
+    pub enabled: bool,
+    /// Show urgent popups on their own surface at this anchor; unset keeps them in the stack.
+    pub anchor: Option<Anchor>,
+    /// Pulse urgent popups with the `.unixnotis-popup-pulse` class.
+    pub pulse: bool,
+    /// Sound theme name played instead of the notification's own sound.
+    pub sound_name: Option<String>,
+    /// Sound file played instead of the notification's own sound; wins over `sound_name`.
+    pub sound_file: Option<String>,
+}
+
+impl Default for UrgentPopupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            anchor: None,
+            pulse: true,
+            sound_name: None,
+            sound_file: None,
         }
     }
 }
 
+impl PopupConfig {
+    /// Whether a notification of `urgency` gets urgent mode.
+    pub fn is_urgent(&self, urgency: Urgency) -> bool {
+        self.urgent.enabled && urgency == Urgency::Critical
+    }
+
+    /// Default timeout for `urgency` when the app leaves it to the server; `None` never expires.
+    pub fn timeout_ms(&self, urgency: Urgency) -> Option<u64> {
+        let timeout_ms = match urgency {
+            Urgency::Low => self.low_timeout_ms.unwrap_or(self.default_timeout_ms),
+            Urgency::Normal => self.default_timeout_ms,
+            Urgency::Critical => self.critical_timeout_ms?,
+        };
+        (timeout_ms > 0).then_some(timeout_ms)
+    }
+
+    /// `timeout_ms` held within `min_timeout_ms` and `max_timeout_ms`.
+    pub fn clamp_timeout_ms(&self, timeout_ms: u64) -> u64 {
+        let timeout_ms = self
+            .min_timeout_ms
+            .map_or(timeout_ms, |min| timeout_ms.max(min));
+        self.max_timeout_ms
+            .map_or(timeout_ms, |max| timeout_ms.min(max))
+    }
+
+    /// Whether `output = "focused"` asks popups to open on the focused monitor.
+    pub fn follows_focus(&self) -> bool {
+        self.output
+            .as_deref()
+            .is_some_and(|output| output.trim().eq_ignore_ascii_case("focused"))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct PanelConfig {
@@ -65,12 +312,34 @@ pub struct PanelConfig {
     pub height: i32,
     pub keyboard_interactivity: PanelKeyboardInteractivity,
     pub output: Option<String>,
+    /// Space reserved for the panel while it is open.
+    pub exclusive_zone: ExclusiveZone,
     /// Hide the panel when focus leaves the window.
     pub close_on_blur: bool,
     /// Close the panel when a different window becomes active (Hyprland only).
     pub close_on_click_outside: bool,
     /// Respect compositor reserved work area when computing height (Hyprland only).
     pub respect_work_area: bool,
+    /// Row density for the notification list.
+    pub density: PanelDensity,
+    /// Pixel size of notification row icons.
+    pub row_icon_size: i32,
+    /// Reserve the icon slot even while icons load or when none resolves.
+    pub fixed_icon_slots: bool,
+    /// Maximum body lines before ellipsizing (0 = unlimited).
+    pub body_max_lines: i32,
+    /// Initial ordering of the notification list.
+    pub sort_order: PanelSortOrder,
+    /// Pin active critical notifications in a section above the grouped list.
+    pub sticky_critical: bool,
+    /// Optional user, hostname, and uptime row at the top of the panel.
+    pub header: PanelHeaderConfig,
+    /// Optional clock and date shown at the top of the panel.
+    pub clock: PanelClockConfig,
+    /// Placeholder shown in place of the list when there are no notifications.
+    pub empty_state: PanelEmptyStateConfig,
+    /// Keyboard bindings for moving through the list.
+    pub keys: PanelKeysConfig,
 }
 
 impl Default for PanelConfig {
@@ -87,9 +356,112 @@ impl Default for PanelConfig {
             height: 0,
             keyboard_interactivity: PanelKeyboardInteractivity::OnDemand,
             output: None,
+            exclusive_zone: ExclusiveZone::None,
             close_on_blur: false,
             close_on_click_outside: true,
             respect_work_area: true,
+            density: PanelDensity::Comfortable,
+            row_icon_size: 22,
+            fixed_icon_slots: true,
+            body_max_lines: 0,
+            sort_order: PanelSortOrder::NewestFirst,
+            sticky_critical: false,
+            header: PanelHeaderConfig::default(),
+            clock: PanelClockConfig::default(),
+            empty_state: PanelEmptyStateConfig::default(),
+            keys: PanelKeysConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PanelHeaderConfig {
+    pub enabled: bool,
+    pub show_avatar: bool,
+    /// Avatar image path; unset uses the AccountsService icon, then `~/.face`.
+    pub avatar: Option<String>,
+    pub show_hostname: bool,
+    pub show_uptime: bool,
+}
+
+impl Default for PanelHeaderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            show_avatar: true,
+            avatar: None,
+            show_hostname: true,
+            show_uptime: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PanelClockConfig {
+    pub enabled: bool,
+    /// strftime-style format for the time line.
+    pub time_format: String,
+    /// strftime-style format for the date line; empty hides it.
+    pub date_format: String,
+}
+
+impl Default for PanelClockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            time_format: "%H:%M".to_string(),
+            date_format: "%A, %B %-d".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PanelEmptyStateConfig {
+    pub enabled: bool,
+    /// Themed icon name; empty hides the icon.
+    pub icon: String,
+    /// Message text; unset uses the translated "All caught up".
+    pub text: Option<String>,
+}
+
+impl Default for PanelEmptyStateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            icon: "emblem-ok-symbolic".to_string(),
+            text: None,
+        }
+    }
+}
+
+/// GDK key names per panel action; an empty list leaves the action unbound.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PanelKeysConfig {
+    pub next: Vec<String>,
+    pub previous: Vec<String>,
+    pub first: Vec<String>,
+    pub last: Vec<String>,
+    /// Run the selected notification's default action, or expand or collapse a group.
+    pub activate: Vec<String>,
+    pub dismiss: Vec<String>,
+    pub close: Vec<String>,
+}
+
+impl Default for PanelKeysConfig {
+    fn default() -> Self {
+        let keys = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Self {
+            next: keys(&["Down", "j"]),
+            previous: keys(&["Up", "k"]),
+            first: keys(&["Home", "g"]),
+            last: keys(&["End", "G"]),
+            activate: keys(&["Return", "KP_Enter"]),
+            dismiss: keys(&["Delete", "d"]),
+            close: keys(&["Escape", "q"]),
         }
     }
 }
@@ -100,6 +472,14 @@ pub struct HistoryConfig {
     pub max_entries: usize,
     pub max_active: usize,
     pub transient_to_history: bool,
+    /// Per-urgency choice of archiving or dropping notifications that time out.
+    pub keep_expired: KeepExpiredConfig,
+    /// Save history under the state directory so it survives daemon restarts.
+    pub persist: bool,
+    /// Drop history entries received more than this many days ago; 0 keeps them.
+    pub max_age_days: u32,
+    /// What the panel's Clear button and `noticenterctl clear` remove.
+    pub clear_scope: ClearScope,
 }
 
 impl Default for HistoryConfig {
@@ -108,6 +488,75 @@ impl Default for HistoryConfig {
             max_entries: 200,
             max_active: 500,
             transient_to_history: false,
+            keep_expired: KeepExpiredConfig::default(),
+            persist: false,
+            max_age_days: 0,
+            clear_scope: ClearScope::All,
+        }
+    }
+}
+
+/// Which notifications a clear removes; sent over D-Bus by name.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, Type, Default, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[zvariant(signature = "s")]
+pub enum ClearScope {
+    /// Active notifications and history.
+    #[default]
+    All,
+    /// History only; active notifications and their popups stay.
+    History,
+    /// Active notifications only; history stays.
+    Active,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeepExpiredConfig {
+    pub low: bool,
+    pub normal: bool,
+    pub critical: bool,
+}
+
+impl Default for KeepExpiredConfig {
+    fn default() -> Self {
+        Self {
+            low: true,
+            normal: true,
+            critical: true,
+        }
+    }
+}
+
+impl KeepExpiredConfig {
+    /// Whether an expired notification with this urgency byte moves to history.
+    pub fn keeps(&self, urgency: u8) -> bool {
+        match urgency {
+            0 => self.low,
+            2 => self.critical,
+            _ => self.normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IconConfig {
+    /// Icon decode worker threads per UI process (0 = auto, at most 2).
+    pub decode_workers: usize,
+    /// Show large notification images, such as screenshots, as a preview in popups and
+    /// expanded panel rows.
+    pub image_preview: bool,
+    /// Preview height cap in logical pixels; the width is capped at twice this.
+    pub preview_max_height: i32,
+}
+
+impl Default for IconConfig {
+    fn default() -> Self {
+        Self {
+            decode_workers: 0,
+            image_preview: false,
+            preview_max_height: 160,
         }
     }
 }
@@ -125,6 +574,11 @@ pub struct MediaConfig {
     pub allowlist: Vec<String>,
     /// Denylist of player identifiers or bus names (case-insensitive substrings).
     pub denylist: Vec<String>,
+    /// Fixed carousel order by player identity or bus name (case-insensitive substrings).
+    /// Empty keeps playing players first.
+    pub player_order: Vec<String>,
+    /// Suppress popups and sounds from detected MPRIS players and keep them out of history.
+    pub suppress_player_notifications: bool,
 }
 
 impl Default for MediaConfig {
@@ -135,6 +589,235 @@ impl Default for MediaConfig {
             title_char_limit: 32,
             allowlist: Vec::new(),
             denylist: vec!["playerctld".to_string()],
+            player_order: Vec::new(),
+            suppress_player_notifications: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BatteryConfig {
+    /// Watch UPower and send low-battery and charger notifications from the daemon.
+    pub enabled: bool,
+    /// Charge percentage at or below which a low-battery warning is sent.
+    pub low_percent: u8,
+    /// Charge percentage at or below which a critical warning with a suspend action is sent.
+    pub critical_percent: u8,
+    /// Notify when the charger is unplugged.
+    pub notify_unplugged: bool,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            low_percent: 15,
+            critical_percent: 5,
+            notify_unplugged: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SystemdConfig {
+    /// Notify when a systemd unit enters the failed state.
+    pub enabled: bool,
+    /// Watch units of the user manager (`systemctl --user`).
+    pub user: bool,
+    /// Watch units of the system manager as well.
+    pub system: bool,
+    /// Unit names to stay quiet about (case-insensitive substrings).
+    pub ignore: Vec<String>,
+    /// Command run by the "Show journal" action; `{unit}` is replaced by the unit name and
+    /// `{scope}` by `--user` or `--system`.
+    pub journal_cmd: String,
+}
+
+impl Default for SystemdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            user: true,
+            system: false,
+            ignore: Vec::new(),
+            journal_cmd: "xdg-terminal-exec journalctl {scope} -e -u {unit}".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiskConfig {
+    /// Warn when free space on one of `mounts` drops below `min_free_percent`.
+    pub enabled: bool,
+    /// Mount points checked on `widgets.refresh_interval_slow_ms`.
+    pub mounts: Vec<String>,
+    /// Free space percentage below which a mount counts as low.
+    pub min_free_percent: u8,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mounts: vec!["/".to_string(), "/home".to_string()],
+            min_free_percent: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// Accept notifications as JSON over HTTP on `socket` (and `listen`, if set).
+    pub enabled: bool,
+    /// Unix socket path; unset uses `$XDG_RUNTIME_DIR/unixnotis/remote.sock`.
+    pub socket: Option<String>,
+    /// Optional TCP address such as `127.0.0.1:8790`, e.g. as the end of an SSH tunnel.
+    pub listen: Option<String>,
+    /// Shared secret every request must send as `Authorization: Bearer <token>`.
+    pub token: String,
+}
+
+/// `[security]`: limits on who may drive the daemon over D-Bus.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SecurityConfig {
+    pub control: ControlSecurityConfig,
+}
+
+/// `[security.control]`: which clients may call control methods that change state.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ControlSecurityConfig {
+    /// Executables allowed to change state; empty lets any session client do so.
+    pub allowed_executables: Vec<String>,
+    /// Ask polkit before clearing notifications or changing rules.
+    pub polkit: bool,
+    /// Polkit action checked when `polkit` is on.
+    pub polkit_action: String,
+    /// Log every state-changing control call with its caller and keep the latest ones for
+    /// `noticenterctl audit`.
+    pub audit: bool,
+}
+
+impl Default for ControlSecurityConfig {
+    fn default() -> Self {
+        Self {
+            allowed_executables: Vec::new(),
+            polkit: false,
+            polkit_action: "com.unixnotis.control.clear".to_string(),
+            audit: false,
+        }
+    }
+}
+
+/// New-mail notifications over IMAP IDLE; only acted on when the daemon is built with `imap`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MailConfig {
+    pub accounts: Vec<MailAccountConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MailAccountConfig {
+    /// Shown as the notification app name.
+    pub name: String,
+    pub enabled: bool,
+    pub host: String,
+    /// Implicit-TLS IMAP port.
+    pub port: u16,
+    pub username: String,
+    /// Command that prints the password, e.g. `pass show mail/work`.
+    pub password_cmd: String,
+    pub mailbox: String,
+    /// Command whose stdin and stdout carry the IMAP session, replacing the built-in
+    /// `openssl s_client` connection (e.g. `ssh mailhost /usr/lib/dovecot/imap`).
+    pub tunnel: Option<String>,
+    /// Command run when a new-mail notification is clicked.
+    pub open_cmd: Option<String>,
+}
+
+impl Default for MailAccountConfig {
+    fn default() -> Self {
+        Self {
+            name: "Mail".to_string(),
+            enabled: true,
+            host: String::new(),
+            port: 993,
+            username: String::new(),
+            password_cmd: String::new(),
+            mailbox: "INBOX".to_string(),
+            tunnel: None,
+            open_cmd: None,
+        }
+    }
+}
+
+/// Notifications from MQTT topics; only acted on when the daemon is built with `mqtt`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// Connect with TLS, verified against the system certificate store.
+    pub tls: bool,
+    /// Client id sent to the broker; empty uses `unixnotis-<hostname>`.
+    pub client_id: String,
+    pub username: String,
+    /// Command that prints the broker password, e.g. `pass show mqtt`.
+    pub password_cmd: String,
+    pub topics: Vec<MqttTopicConfig>,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            tls: false,
+            client_id: String::new(),
+            username: String::new(),
+            password_cmd: String::new(),
+            topics: Vec::new(),
+        }
+    }
+}
+
+/// One subscribed topic filter and how its messages become notifications.
+///
+/// `summary` and `body` are templates: `{topic}` is the message topic, `{payload}` the raw
+/// payload, and `{name}` or `{name.inner}` a field of a JSON object payload.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MqttTopicConfig {
+    /// Topic filter; `+` and `#` wildcards are allowed.
+    pub topic: String,
+    /// Shown as the notification app name, so rules can match on it.
+    pub app: String,
+    pub summary: String,
+    pub body: String,
+    pub icon: String,
+    /// 0 = low, 1 = normal, 2 = critical.
+    pub urgency: u8,
+    pub category: Option<String>,
+}
+
+impl Default for MqttTopicConfig {
+    fn default() -> Self {
+        Self {
+            topic: String::new(),
+            app: "MQTT".to_string(),
+            summary: "{topic}".to_string(),
+            body: "{payload}".to_string(),
+            icon: String::new(),
+            urgency: 1,
+            category: None,
         }
     }
 }
@@ -147,6 +830,7 @@ pub struct WidgetsConfig {
     pub toggles: Vec<ToggleWidgetConfig>,
     pub stats: Vec<StatWidgetConfig>,
     pub cards: Vec<CardWidgetConfig>,
+    pub night_light: NightLightConfig,
     pub refresh_interval_ms: u64,
     pub refresh_interval_slow_ms: u64,
 }
@@ -171,6 +855,7 @@ impl Default for WidgetsConfig {
                 CardWidgetConfig::default_calendar(),
                 CardWidgetConfig::default_weather(),
             ],
+            night_light: NightLightConfig::default(),
             refresh_interval_ms: 1000,
             refresh_interval_slow_ms: 3000,
         }
@@ -193,6 +878,8 @@ pub struct SliderWidgetConfig {
     pub step: f64,
     /// Controls how numeric command output is interpreted for slider values.
     pub parse_mode: NumericParseMode,
+    /// Extract the value from structured output first: "json:<pointer>" or "key:<name>".
+    pub parse: Option<String>,
 }
 
 impl SliderWidgetConfig {
@@ -236,6 +923,7 @@ impl SliderWidgetConfig {
             max: 100.0,
             step: 1.0,
             parse_mode: NumericParseMode::Auto,
+            parse: None,
         }
     }
 
@@ -262,6 +950,7 @@ impl SliderWidgetConfig {
             max: 100.0,
             step: 1.0,
             parse_mode: NumericParseMode::Auto,
+            parse: None,
         }
     }
 }
@@ -294,9 +983,14 @@ pub struct ToggleWidgetConfig {
     pub on_cmd: Option<String>,
     pub off_cmd: Option<String>,
     pub watch_cmd: Option<String>,
+    /// Extract the state from structured output first: "json:<pointer>" or "key:<name>".
+    pub parse: Option<String>,
 }
 
 impl ToggleWidgetConfig {
+    /// `state_cmd` that hands the toggle to the built-in night light backend.
+    pub const NIGHT_LIGHT_BUILTIN: &'static str = "builtin:night-light";
+
     fn default_wifi() -> Self {
         Self {
             enabled: true,
@@ -306,6 +1000,7 @@ impl ToggleWidgetConfig {
             on_cmd: Some("nmcli radio wifi on".to_string()),
             off_cmd: Some("nmcli radio wifi off".to_string()),
             watch_cmd: Some("nmcli -t monitor".to_string()),
+            parse: None,
         }
     }
 
@@ -318,6 +1013,7 @@ impl ToggleWidgetConfig {
             on_cmd: Some("bluetoothctl power on".to_string()),
             off_cmd: Some("bluetoothctl power off".to_string()),
             watch_cmd: Some("bluetoothctl --monitor".to_string()),
+            parse: None,
         }
     }
 
@@ -330,6 +1026,7 @@ impl ToggleWidgetConfig {
             on_cmd: Some("rfkill block all".to_string()),
             off_cmd: Some("rfkill unblock all".to_string()),
             watch_cmd: Some("udevadm monitor --udev --subsystem-match=rfkill".to_string()),
+            parse: None,
         }
     }
 
@@ -338,10 +1035,30 @@ impl ToggleWidgetConfig {
             enabled: true,
             label: "Night".to_string(),
             icon: "weather-clear-night-symbolic".to_string(),
-            state_cmd: None,
+            state_cmd: Some(Self::NIGHT_LIGHT_BUILTIN.to_string()),
             on_cmd: None,
             off_cmd: None,
             watch_cmd: None,
+            parse: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct NightLightConfig {
+    /// Program driving the built-in night light toggle: "auto", "hyprsunset", "wlsunset",
+    /// or "gammastep". "auto" picks the first one installed.
+    pub backend: String,
+    /// Color temperature in Kelvin while night light is on.
+    pub temperature: u32,
+}
+
+impl Default for NightLightConfig {
+    fn default() -> Self {
+        Self {
+            backend: "auto".to_string(),
+            temperature: 4000,
         }
     }
 }
@@ -356,6 +1073,7 @@ impl Default for ToggleWidgetConfig {
             on_cmd: None,
             off_cmd: None,
             watch_cmd: None,
+            parse: None,
         }
     }
 }
@@ -369,6 +1087,10 @@ pub struct StatWidgetConfig {
     pub kind: Option<String>,
     pub cmd: Option<String>,
     pub min_height: i32,
+    /// Add the `warning` class when the leading number of the value exceeds this.
+    pub warning_above: Option<f64>,
+    /// Add the `critical` class when the leading number of the value exceeds this.
+    pub critical_above: Option<f64>,
 }
 
 impl StatWidgetConfig {
@@ -380,6 +1102,8 @@ impl StatWidgetConfig {
             kind: None,
             cmd: Some("builtin:cpu".to_string()),
             min_height: 72,
+            warning_above: None,
+            critical_above: None,
         }
     }
 
@@ -391,6 +1115,8 @@ impl StatWidgetConfig {
             kind: None,
             cmd: Some("builtin:memory".to_string()),
             min_height: 72,
+            warning_above: None,
+            critical_above: None,
         }
     }
 
@@ -402,6 +1128,8 @@ impl StatWidgetConfig {
             kind: None,
             cmd: Some("builtin:battery".to_string()),
             min_height: 72,
+            warning_above: None,
+            critical_above: None,
         }
     }
 }
@@ -415,6 +1143,8 @@ impl Default for StatWidgetConfig {
             kind: None,
             cmd: None,
             min_height: 72,
+            warning_above: None,
+            critical_above: None,
         }
     }
 }
@@ -430,6 +1160,71 @@ pub struct CardWidgetConfig {
     pub cmd: Option<String>,
     pub min_height: i32,
     pub monospace: bool,
+    /// Minimum minutes between command runs; unset uses the kind default
+    /// (60 for "updates", every slow refresh otherwise).
+    pub interval_minutes: Option<u32>,
+    /// Command run when the card is clicked.
+    pub click_cmd: Option<String>,
+    /// Buttons shown by "power" cards; empty uses lock, suspend, reboot, and shut down.
+    pub actions: Vec<CardActionConfig>,
+}
+
+/// One button on a "power" card.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct CardActionConfig {
+    pub label: String,
+    pub icon: String,
+    pub cmd: String,
+    /// Ask before running, for actions that end the session.
+    pub confirm: bool,
+}
+
+impl CardActionConfig {
+    fn new(label: &str, icon: &str, cmd: &str, confirm: bool) -> Self {
+        Self {
+            label: label.to_string(),
+            icon: icon.to_string(),
+            cmd: cmd.to_string(),
+            confirm,
+        }
+    }
+
+    /// Actions used by "power" cards that do not list their own.
+    pub fn power_defaults() -> Vec<Self> {
+        vec![
+            Self::new(
+                "Lock",
+                "system-lock-screen-symbolic",
+                "loginctl lock-session",
+                false,
+            ),
+            Self::new(
+                "Suspend",
+                "system-suspend-symbolic",
+                "systemctl suspend",
+                false,
+            ),
+            Self::new("Reboot", "system-reboot-symbolic", "systemctl reboot", true),
+            Self::new(
+                "Shut down",
+                "system-shutdown-symbolic",
+                "systemctl poweroff",
+                true,
+            ),
+        ]
+    }
+}
+
+impl Default for CardActionConfig {
+    fn default() -> Self {
+        Self {
+            label: "Action".to_string(),
+            icon: "system-run-symbolic".to_string(),
+            cmd: String::new(),
+            confirm: false,
+        }
+    }
 }
 
 impl CardWidgetConfig {
@@ -443,6 +1238,9 @@ impl CardWidgetConfig {
             cmd: None,
             min_height: 180,
             monospace: false,
+            interval_minutes: None,
+            click_cmd: None,
+            actions: Vec::new(),
         }
     }
 
@@ -456,6 +1254,9 @@ impl CardWidgetConfig {
             cmd: None,
             min_height: 160,
             monospace: false,
+            interval_minutes: None,
+            click_cmd: None,
+            actions: Vec::new(),
         }
     }
 }
@@ -471,6 +1272,9 @@ impl Default for CardWidgetConfig {
             cmd: None,
             min_height: 120,
             monospace: false,
+            interval_minutes: None,
+            click_cmd: None,
+            actions: Vec::new(),
         }
     }
 }
@@ -542,7 +1346,7 @@ impl Default for ThemeConfig {
     }
 }
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
 #[serde(default)]
 pub struct RuleConfig {
     /// Optional rule name for logging or debugging.
@@ -569,9 +1373,13 @@ pub struct RuleConfig {
     pub resident: Option<bool>,
     /// Override transient flag when set.
     pub transient: Option<bool>,
+    /// Prune matched notifications from history after this many minutes (0 disables).
+    pub history_ttl_minutes: Option<u32>,
+    /// Apply `popups.min_timeout_ms` and `popups.max_timeout_ms`; false exempts matches.
+    pub clamp_timeout: Option<bool>,
 }
 
-#[derive(Debug, Copy, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, Default, PartialEq, Eq, Hash)]
 #[serde(rename_all = "kebab-case")]
 pub enum Anchor {
     /// Default anchor for panels when no explicit config value is set.
@@ -584,6 +1392,89 @@ pub enum Anchor {
     Bottom,
     Left,
     Right,
+    /// Anchored to no edge; the compositor centers the surface on its output.
+    Center,
+}
+
+impl Anchor {
+    /// Parse the kebab-case name used in config files, such as `top-right`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "top-right" => Some(Self::TopRight),
+            "top-left" => Some(Self::TopLeft),
+            "bottom-right" => Some(Self::BottomRight),
+            "bottom-left" => Some(Self::BottomLeft),
+            "top" => Some(Self::Top),
+            "bottom" => Some(Self::Bottom),
+            "left" => Some(Self::Left),
+            "right" => Some(Self::Right),
+            "center" => Some(Self::Center),
+            _ => None,
+        }
+    }
+
+    /// The kebab-case name `from_name` accepts.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TopRight => "top-right",
+            Self::TopLeft => "top-left",
+            Self::BottomRight => "bottom-right",
+            Self::BottomLeft => "bottom-left",
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Center => "center",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PanelDensity {
+    /// Default spacing with full-size icons and bodies.
+    #[default]
+    Comfortable,
+    /// Tighter rows with smaller icons and bodies capped to two lines.
+    Compact,
+    /// Single-line rows with small icons; bodies are hidden.
+    Minimal,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PanelSortOrder {
+    /// Active notifications first, then history, each most recent first.
+    #[default]
+    NewestFirst,
+    /// Active notifications first, then history, each oldest first.
+    OldestFirst,
+    /// Groups ordered alphabetically by app name.
+    AppName,
+    /// Critical notifications first, then by recency within each urgency level.
+    Urgency,
+}
+
+impl PanelSortOrder {
+    /// Next order in the cycle used by the panel sort toggle.
+    pub fn next(self) -> Self {
+        match self {
+            Self::NewestFirst => Self::OldestFirst,
+            Self::OldestFirst => Self::AppName,
+            Self::AppName => Self::Urgency,
+            Self::Urgency => Self::NewestFirst,
+        }
+    }
+
+    /// Short label shown on the panel sort toggle.
+    pub fn label(self) -> String {
+        crate::i18n::tr(match self {
+            Self::NewestFirst => "sort-newest",
+            Self::OldestFirst => "sort-oldest",
+            Self::AppName => "sort-app",
+            Self::Urgency => "sort-urgency",
+        })
+    }
 }
 
 #[derive(Debug, Copy, Clone, Deserialize, Serialize, Default)]
@@ -623,3 +1514,65 @@ impl Default for Margins {
         }
     }
 }
+
+/// Per-output overrides from an `[outputs."<name>"]` block.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    pub panel: OutputPlacement,
+    pub popups: OutputPlacement,
+}
+
+/// Placement settings an output block may override; unset fields keep the global value.
+#[derive(Debug, Copy, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OutputPlacement {
+    pub anchor: Option<Anchor>,
+    pub margin: Option<Margins>,
+    pub width: Option<i32>,
+}
+
+impl OutputPlacement {
+    fn apply(&self, anchor: &mut Anchor, margin: &mut Margins, width: &mut i32) {
+        if let Some(value) = self.anchor {
+            *anchor = value;
+        }
+        if let Some(value) = self.margin {
+            *margin = value;
+        }
+        if let Some(value) = self.width {
+            *width = value;
+        }
+    }
+}
+
+impl Config {
+    /// Output block for the first of `names` that has one.
+    ///
+    /// Pass the connector before the model so `DP-1` wins over a model-name block.
+    pub fn output_config(&self, names: &[String]) -> Option<&OutputConfig> {
+        names.iter().find_map(|name| self.outputs.get(name))
+    }
+
+    /// Panel settings with the matching output block applied.
+    pub fn panel_for_output(&self, names: &[String]) -> PanelConfig {
+        let mut panel = self.panel.clone();
+        if let Some(output) = self.output_config(names) {
+            output
+                .panel
+                .apply(&mut panel.anchor, &mut panel.margin, &mut panel.width);
+        }
+        panel
+    }
+
+    /// Popup settings with the matching output block applied.
+    pub fn popups_for_output(&self, names: &[String]) -> PopupConfig {
+        let mut popups = self.popups.clone();
+        if let Some(output) = self.output_config(names) {
+            output
+                .popups
+                .apply(&mut popups.anchor, &mut popups.margin, &mut popups.width);
+        }
+        popups
+    }
+}
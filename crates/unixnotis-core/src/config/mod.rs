@@ -6,5 +6,5 @@ mod config_io;
 mod config_runtime;
 mod config_types;
 
-pub use config_io::{ConfigError, ThemePaths};
+pub use config_io::{ConfigError, ThemePaths, DEFAULT_CONFIG_TOML};
 pub use config_types::*;
@@ -1,11 +1,16 @@
 //! D-Bus control interface types and proxy definitions.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use zbus::proxy;
-use zbus::zvariant::Type;
+use zbus::zvariant::{OwnedValue, Str, Type};
 
-use crate::NotificationView;
+use crate::notifications::NotificationsProxy;
+use crate::{
+    Anchor, ClearScope, Margins, NotificationImage, NotificationView, PanelConfig, RuleConfig,
+};
 
 /// Well-known bus name for the UnixNotis control interface.
 pub const CONTROL_BUS_NAME: &str = "com.unixnotis.Control";
@@ -14,11 +19,160 @@ pub const CONTROL_OBJECT_PATH: &str = "/com/unixnotis/Control";
 /// D-Bus interface name for control calls.
 pub const CONTROL_INTERFACE: &str = "com.unixnotis.Control";
 
+/// Well-known bus name of the freedesktop notification service.
+pub const NOTIFICATIONS_BUS_NAME: &str = "org.freedesktop.Notifications";
+/// Environment variable selecting suffixed bus names, read when `--bus-suffix` is not given.
+pub const BUS_SUFFIX_ENV: &str = "UNIXNOTIS_BUS_SUFFIX";
+/// How long `undo_clear` can bring back what the last clear removed.
+pub const CLEAR_UNDO_SECONDS: u64 = 10;
+/// Action key of KDE's inline-reply extension; `Reply` answers it with `NotificationReplied`.
+pub const INLINE_REPLY_ACTION: &str = "inline-reply";
+/// History entries the panel asks for at a time through `list_history_range`.
+pub const HISTORY_PAGE_SIZE: u32 = 100;
+
+/// Suffixes must form a valid bus name element so the derived names are accepted.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid bus suffix {0:?}: use letters, digits, and underscores, starting with a letter")]
+pub struct InvalidBusSuffix(pub String);
+
+/// Bus names for one UnixNotis instance.
+///
+/// A suffix such as `dev` gives a second instance its own names (`com.unixnotis.Control.dev`,
+/// `org.freedesktop.Notifications.dev`), so a nested session can run beside the main one.
+#[derive(Debug, Clone)]
+pub struct BusNames {
+    pub notifications: String,
+    pub control: String,
+    suffix: Option<String>,
+}
+
+impl BusNames {
+    pub fn new(suffix: Option<&str>) -> Result<Self, InvalidBusSuffix> {
+        let suffix = suffix.map(str::trim).filter(|suffix| !suffix.is_empty());
+        let Some(suffix) = suffix else {
+            return Ok(Self {
+                notifications: NOTIFICATIONS_BUS_NAME.to_string(),
+                control: CONTROL_BUS_NAME.to_string(),
+                suffix: None,
+            });
+        };
+        let valid = suffix.starts_with(|ch: char| ch.is_ascii_alphabetic())
+            && suffix
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+        if !valid {
+            return Err(InvalidBusSuffix(suffix.to_string()));
+        }
+        Ok(Self {
+            notifications: format!("{NOTIFICATIONS_BUS_NAME}.{suffix}"),
+            control: format!("{CONTROL_BUS_NAME}.{suffix}"),
+            suffix: Some(suffix.to_string()),
+        })
+    }
+
+    /// Names from an explicit `--bus-suffix`, falling back to `UNIXNOTIS_BUS_SUFFIX`.
+    pub fn resolve(flag: Option<&str>) -> Result<Self, InvalidBusSuffix> {
+        match flag {
+            Some(suffix) => Self::new(Some(suffix)),
+            None => Self::new(std::env::var(BUS_SUFFIX_ENV).ok().as_deref()),
+        }
+    }
+
+    pub fn suffix(&self) -> Option<&str> {
+        self.suffix.as_deref()
+    }
+
+    /// GTK application id for a UI process, suffixed so instances do not merge.
+    pub fn app_id(&self, base: &str) -> String {
+        match self.suffix.as_deref() {
+            Some(suffix) => format!("{base}.{suffix}"),
+            None => base.to_string(),
+        }
+    }
+
+    /// Control proxy addressed to this instance's daemon.
+    pub async fn control_proxy<'a>(
+        &self,
+        connection: &zbus::Connection,
+    ) -> zbus::Result<ControlProxy<'a>> {
+        ControlProxy::builder(connection)
+            .destination(self.control.clone())?
+            .build()
+            .await
+    }
+
+    /// Notifications proxy addressed to this instance's notification server.
+    pub async fn notifications_proxy<'a>(
+        &self,
+        connection: &zbus::Connection,
+    ) -> zbus::Result<NotificationsProxy<'a>> {
+        NotificationsProxy::builder(connection)
+            .destination(self.notifications.clone())?
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await
+    }
+}
+
 /// Control-plane state broadcast to the UI.
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct ControlState {
     pub dnd_enabled: bool,
+    /// Unix time (seconds) when timed Do Not Disturb ends; 0 when DND is not timed.
+    pub dnd_until: i64,
     pub history_count: u32,
+    /// Active notifications pushed out by `history.max_active` since the daemon started.
+    pub active_evictions: u32,
+    /// History entries dropped by `history.max_entries` since the daemon started.
+    pub history_evictions: u32,
+    /// Comma-separated reasons popups are held back without DND, such as `fullscreen`;
+    /// empty when nothing suppresses them.
+    pub inhibited_by: String,
+    /// False while popups are switched off; notifications still reach the panel and sounds
+    /// still play.
+    pub popups_enabled: bool,
+    /// True while notification sounds are muted; popups and the panel are unaffected.
+    pub muted: bool,
+}
+
+/// One state-changing control call recorded by `[security.control] audit`.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct AuditEntry {
+    pub at_unix_ms: i64,
+    /// D-Bus method name, such as `ClearAll`.
+    pub method: String,
+    /// Unique bus name of the caller.
+    pub sender: String,
+    /// Caller PID; zero when the bus could not tell.
+    pub pid: u32,
+    /// Command name from `/proc/PID/comm`; empty when unavailable.
+    pub comm: String,
+    /// False when the policy refused the call.
+    pub allowed: bool,
+}
+
+/// Memory usage snapshot reported by one UnixNotis process.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct MemoryReport {
+    /// Process role, such as `daemon`, `center`, or `popups`.
+    pub process: String,
+    pub pid: u32,
+    /// Resident set size read from procfs; zero when unavailable.
+    pub rss_bytes: u64,
+    /// Estimated cache and buffer sizes as `(label, bytes)` pairs.
+    pub caches: Vec<(String, u64)>,
+}
+
+impl MemoryReport {
+    /// Build a report for the current process with the given cache estimates.
+    pub fn for_current_process(process: &str, caches: Vec<(String, u64)>) -> Self {
+        Self {
+            process: process.to_string(),
+            pid: std::process::id(),
+            rss_bytes: crate::util::process_rss_bytes().unwrap_or(0),
+            caches,
+        }
+    }
 }
 
 /// Panel visibility actions sent to the UI.
@@ -60,11 +214,54 @@ impl PanelDebugLevel {
     }
 }
 
-/// Panel request payload combining action and requested debug verbosity.
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, Type)]
+/// Part of the panel to bring into view when a request opens it.
+#[derive(Debug, Copy, Clone, Serialize_repr, Deserialize_repr, Type, Eq, PartialEq, Default)]
+#[repr(u32)]
+pub enum PanelTarget {
+    #[default]
+    None = 0,
+    /// Focus the media player controls.
+    Media = 1,
+    /// Expand and scroll to the group named by `PanelRequest::app`.
+    App = 2,
+}
+
+/// Level shown by the on-screen display.
+#[derive(Debug, Copy, Clone, Serialize_repr, Deserialize_repr, Type, Eq, PartialEq)]
+#[repr(u32)]
+pub enum OsdKind {
+    Volume = 0,
+    Brightness = 1,
+}
+
+impl OsdKind {
+    /// Kind named by a notification's synchronous tag, such as `volume` or `brightness`.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.to_ascii_lowercase();
+        if ["volume", "audio", "sound"]
+            .iter()
+            .any(|word| tag.contains(word))
+        {
+            Some(Self::Volume)
+        } else if ["bright", "backlight"]
+            .iter()
+            .any(|word| tag.contains(word))
+        {
+            Some(Self::Brightness)
+        } else {
+            None
+        }
+    }
+}
+
+/// Panel request payload combining action, requested debug verbosity, and target.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct PanelRequest {
     pub action: PanelAction,
     pub debug: PanelDebugLevel,
+    pub target: PanelTarget,
+    /// App name for `PanelTarget::App`; empty otherwise.
+    pub app: String,
 }
 
 impl PanelRequest {
@@ -72,6 +269,17 @@ impl PanelRequest {
         Self {
             action: PanelAction::Open,
             debug: PanelDebugLevel::Off,
+            target: PanelTarget::None,
+            app: String::new(),
+        }
+    }
+
+    pub fn open_target(target: PanelTarget, app: &str) -> Self {
+        Self {
+            action: PanelAction::Open,
+            debug: PanelDebugLevel::Off,
+            target,
+            app: app.to_string(),
         }
     }
 
@@ -79,6 +287,8 @@ impl PanelRequest {
         Self {
             action: PanelAction::Open,
             debug: level,
+            target: PanelTarget::None,
+            app: String::new(),
         }
     }
 
@@ -86,6 +296,8 @@ impl PanelRequest {
         Self {
             action: PanelAction::Close,
             debug: PanelDebugLevel::Off,
+            target: PanelTarget::None,
+            app: String::new(),
         }
     }
 
@@ -93,6 +305,61 @@ impl PanelRequest {
         Self {
             action: PanelAction::Toggle,
             debug: PanelDebugLevel::Off,
+            target: PanelTarget::None,
+            app: String::new(),
+        }
+    }
+}
+
+/// Temporary panel placement requested by scripts; the panel reverts to config when it closes.
+///
+/// Empty fields keep the configured value, so an all-empty geometry clears the override.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct PanelGeometry {
+    /// Anchor name as written in config, such as `top-right` or `center`.
+    pub anchor: String,
+    /// Margins as `[top, right, bottom, left]`.
+    pub margin: Vec<i32>,
+    /// Output connector (`DP-1`) or model name, or `focused` for the focused monitor.
+    pub output: String,
+}
+
+impl PanelGeometry {
+    pub fn is_empty(&self) -> bool {
+        self.anchor.trim().is_empty() && self.margin.is_empty() && self.output.trim().is_empty()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.anchor.trim().is_empty() && Anchor::from_name(&self.anchor).is_none() {
+            return Err(format!("unknown anchor: {}", self.anchor));
+        }
+        if !self.margin.is_empty() && self.margin.len() != 4 {
+            return Err("margin needs four values: top, right, bottom, left".to_string());
+        }
+        if self.margin.iter().any(|value| *value < 0) {
+            return Err("margin values must not be negative".to_string());
+        }
+        Ok(())
+    }
+
+    /// Overlay this geometry on the configured panel placement.
+    ///
+    /// `output` is copied as given; resolving `focused` is left to the panel.
+    pub fn apply_to(&self, panel: &mut PanelConfig) {
+        if let Some(anchor) = Anchor::from_name(&self.anchor) {
+            panel.anchor = anchor;
+        }
+        if let [top, right, bottom, left] = self.margin[..] {
+            panel.margin = Margins {
+                top,
+                right,
+                bottom,
+                left,
+            };
+        }
+        let output = self.output.trim();
+        if !output.is_empty() {
+            panel.output = Some(output.to_string());
         }
     }
 }
@@ -105,6 +372,127 @@ pub enum CloseReason {
     DismissedByUser = 2,
     ClosedByCall = 3,
     Undefined = 4,
+    /// UnixNotis extension: moved to history to honor `history.max_active`, or because
+    /// automatic suppression sent it there.
+    Evicted = 5,
+    /// UnixNotis extension: pruned from history by a rule's `history_ttl_minutes`.
+    Pruned = 6,
+    /// UnixNotis extension: the sending app left the bus (`general.sender_exit = "close"`).
+    SenderGone = 7,
+}
+
+impl CloseReason {
+    /// Reason code for the freedesktop interface, which only defines codes 1-4.
+    pub fn spec_code(self) -> u32 {
+        match self {
+            CloseReason::Evicted | CloseReason::Pruned | CloseReason::SenderGone => {
+                CloseReason::Undefined as u32
+            }
+            reason => reason as u32,
+        }
+    }
+}
+
+/// A rule as sent over D-Bus, keyed by its config field names.
+///
+/// D-Bus has no optional values, so unset fields are left out of the dictionary.
+pub type RuleDict = HashMap<String, OwnedValue>;
+
+impl RuleConfig {
+    /// Empty rule matching `app`, named after it so later edits for the app replace it.
+    pub fn for_app(app: &str) -> Self {
+        let app = app.trim();
+        Self {
+            name: Some(app.to_string()),
+            app: Some(app.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn to_dict(&self) -> RuleDict {
+        let mut dict = RuleDict::new();
+        let strings = [
+            ("name", &self.name),
+            ("app", &self.app),
+            ("summary", &self.summary),
+            ("body", &self.body),
+            ("category", &self.category),
+        ];
+        for (key, value) in strings {
+            if let Some(value) = value {
+                dict.insert(key.to_string(), Str::from(value.clone()).into());
+            }
+        }
+        let flags = [
+            ("no_popup", self.no_popup),
+            ("silent", self.silent),
+            ("resident", self.resident),
+            ("transient", self.transient),
+            ("clamp_timeout", self.clamp_timeout),
+        ];
+        for (key, value) in flags {
+            if let Some(value) = value {
+                dict.insert(key.to_string(), value.into());
+            }
+        }
+        if let Some(urgency) = self.urgency {
+            dict.insert("urgency".to_string(), urgency.into());
+        }
+        if let Some(urgency) = self.force_urgency {
+            dict.insert("force_urgency".to_string(), urgency.into());
+        }
+        if let Some(timeout) = self.expire_timeout_ms {
+            dict.insert("expire_timeout_ms".to_string(), timeout.into());
+        }
+        if let Some(minutes) = self.history_ttl_minutes {
+            dict.insert("history_ttl_minutes".to_string(), minutes.into());
+        }
+        dict
+    }
+
+    /// Parse a rule sent by a client; unknown keys and mistyped values are rejected.
+    pub fn from_dict(dict: &RuleDict) -> Result<Self, String> {
+        let mut rule = RuleConfig::default();
+        for (key, value) in dict {
+            match key.as_str() {
+                "name" => rule.name = Some(string_field(key, value)?),
+                "app" => rule.app = Some(string_field(key, value)?),
+                "summary" => rule.summary = Some(string_field(key, value)?),
+                "body" => rule.body = Some(string_field(key, value)?),
+                "category" => rule.category = Some(string_field(key, value)?),
+                "urgency" => rule.urgency = Some(urgency_field(key, value)?),
+                "no_popup" => rule.no_popup = Some(field(key, value)?),
+                "silent" => rule.silent = Some(field(key, value)?),
+                "force_urgency" => rule.force_urgency = Some(urgency_field(key, value)?),
+                "expire_timeout_ms" => rule.expire_timeout_ms = Some(field(key, value)?),
+                "resident" => rule.resident = Some(field(key, value)?),
+                "transient" => rule.transient = Some(field(key, value)?),
+                "history_ttl_minutes" => rule.history_ttl_minutes = Some(field(key, value)?),
+                "clamp_timeout" => rule.clamp_timeout = Some(field(key, value)?),
+                _ => return Err(format!("unknown rule field: {key}")),
+            }
+        }
+        Ok(rule)
+    }
+}
+
+fn field<'a, T>(key: &str, value: &'a OwnedValue) -> Result<T, String>
+where
+    T: TryFrom<&'a OwnedValue>,
+{
+    T::try_from(value).map_err(|_| format!("rule field {key} has the wrong type"))
+}
+
+fn string_field(key: &str, value: &OwnedValue) -> Result<String, String> {
+    field::<&str>(key, value).map(str::to_string)
+}
+
+fn urgency_field(key: &str, value: &OwnedValue) -> Result<u8, String> {
+    let urgency: u8 = field(key, value)?;
+    if urgency > 2 {
+        return Err(format!("rule field {key} must be 0, 1, or 2"));
+    }
+    Ok(urgency)
 }
 
 #[proxy(
@@ -122,12 +510,30 @@ trait Control {
     /// History notifications for the panel.
     fn list_history(&self) -> zbus::Result<Vec<NotificationView>>;
 
+    /// Up to `limit` history notifications, newest first, skipping the `offset` newest.
+    fn list_history_range(&self, offset: u32, limit: u32) -> zbus::Result<Vec<NotificationView>>;
+
+    /// Full image of an active or history notification, with the pixels listings leave out.
+    fn get_image(&self, id: u32) -> zbus::Result<NotificationImage>;
+
     /// Open the control center panel.
     fn open_panel(&self) -> zbus::Result<()>;
 
     /// Open the control center panel with debug logging.
     fn open_panel_debug(&self, level: PanelDebugLevel) -> zbus::Result<()>;
 
+    /// Change the panel's debug level in place; it resets when the panel next opens or closes.
+    fn set_panel_debug_level(&self, level: PanelDebugLevel) -> zbus::Result<()>;
+
+    /// Open the control center panel and bring `target` into view.
+    fn open_panel_at(&self, target: PanelTarget, app: &str) -> zbus::Result<()>;
+
+    /// Override the panel placement until the panel next closes.
+    fn set_panel_geometry(&self, geometry: PanelGeometry) -> zbus::Result<()>;
+
+    /// Show placeholder popups and the panel outline for `seconds`.
+    fn preview_layout(&self, seconds: u32) -> zbus::Result<()>;
+
     /// Close the control center panel.
     fn close_panel(&self) -> zbus::Result<()>;
 
@@ -137,15 +543,82 @@ trait Control {
     /// Update the Do Not Disturb state.
     fn set_dnd(&self, enabled: bool) -> zbus::Result<()>;
 
+    /// Enable Do Not Disturb for `seconds`, then turn it off again.
+    fn set_dnd_for(&self, seconds: u32) -> zbus::Result<()>;
+
+    /// Switch popups off or back on, critical ones included; unlike DND, sounds keep playing.
+    fn set_popups_enabled(&self, enabled: bool) -> zbus::Result<()>;
+
+    /// Mute or unmute notification sounds, critical ones included; popups keep showing.
+    fn set_muted(&self, muted: bool) -> zbus::Result<()>;
+
+    /// Flash the on-screen display with `percent` (0 to 100) for `kind`.
+    fn show_osd(&self, kind: OsdKind, percent: u32) -> zbus::Result<()>;
+
+    /// Show `app`'s popups at `anchor` (such as `bottom-left`) from now on; an empty anchor
+    /// returns them to the regular stack.
+    fn set_app_popup_anchor(&self, app: &str, anchor: &str) -> zbus::Result<()>;
+
+    /// Apps given their own popup anchor, as lowercased app name and anchor name.
+    fn list_app_popup_anchors(&self) -> zbus::Result<Vec<(String, String)>>;
+
     /// Remove a notification by ID.
     fn dismiss(&self, id: u32) -> zbus::Result<()>;
 
+    /// Pin or unpin a notification; returns false when no notification has that ID.
+    fn pin(&self, id: u32, pinned: bool) -> zbus::Result<bool>;
+
+    /// Pause a notification's timeout while its popup is hovered (`hold`), or resume it with
+    /// the time it had left.
+    fn hold_expiration(&self, id: u32, hold: bool) -> zbus::Result<()>;
+
     /// Invoke an action key for a notification.
     fn invoke_action(&self, id: u32, action_key: &str) -> zbus::Result<()>;
 
-    /// Clear all notifications from history and popups.
+    /// Send `text` as the reply to a notification offering an `inline-reply` action.
+    fn reply(&self, id: u32, text: &str) -> zbus::Result<()>;
+
+    /// Clear notifications as set by `history.clear_scope`. Use `clear` to learn what was removed.
     fn clear_all(&self) -> zbus::Result<()>;
 
+    /// Clear active notifications, history, or both, regardless of `history.clear_scope`.
+    /// Returns the IDs removed; empty when there was nothing to clear.
+    fn clear(&self, scope: ClearScope) -> zbus::Result<Vec<u32>>;
+
+    /// Bring back what the last clear removed, within `CLEAR_UNDO_SECONDS` of it;
+    /// returns false when there is nothing left to restore.
+    fn undo_clear(&self) -> zbus::Result<bool>;
+
+    /// Memory reports from the daemon and every UI process that answered in time.
+    fn get_memory_report(&self) -> zbus::Result<Vec<MemoryReport>>;
+
+    /// Submit a UI-side memory report after a `memory_report_requested` signal.
+    fn report_memory(&self, report: MemoryReport) -> zbus::Result<()>;
+
+    /// Report that popup `id` was painted `render_us` after its `popup_shown` arrived; sent
+    /// only with `general.measure_latency` on.
+    #[zbus(no_reply)]
+    fn report_popup_rendered(&self, id: u32, render_us: u64) -> zbus::Result<()>;
+
+    /// Rebroadcast a panel debug line as `debug_log`; sent by the panel while debugging.
+    #[zbus(no_reply)]
+    fn publish_debug_log(&self, level: PanelDebugLevel, message: &str) -> zbus::Result<()>;
+
+    /// Drop icon caches and shrink buffers in the daemon and UI processes.
+    fn trim_caches(&self) -> zbus::Result<()>;
+
+    /// Configured notification rules, in the order they apply.
+    fn list_rules(&self) -> zbus::Result<Vec<RuleDict>>;
+
+    /// Replace the rule with the same name, or append it, and save the rules to config.toml.
+    fn set_rule(&self, rule: RuleDict) -> zbus::Result<()>;
+
+    /// Remove the rule named `name`; returns false when no rule has that name.
+    fn remove_rule(&self, name: &str) -> zbus::Result<bool>;
+
+    /// Recent state-changing calls, oldest first; empty unless `[security.control] audit` is on.
+    fn list_audit(&self) -> zbus::Result<Vec<AuditEntry>>;
+
     #[zbus(signal)]
     fn notification_added(
         &self,
@@ -163,9 +636,111 @@ trait Control {
     #[zbus(signal)]
     fn notification_closed(&self, id: u32, reason: CloseReason) -> zbus::Result<()>;
 
+    #[zbus(signal)]
+    fn notification_pinned(&self, id: u32, pinned: bool) -> zbus::Result<()>;
+
+    /// Several `notification_added` signals folded into one, sent when notifications arrive in a
+    /// burst (after resume, for example) so UIs can rebuild once.
+    #[zbus(signal)]
+    fn notifications_added_batch(
+        &self,
+        notifications: Vec<(NotificationView, bool)>,
+    ) -> zbus::Result<()>;
+
+    /// Show notification `id` as a popup, stacked by `slot`: newer popups get higher slots, and a
+    /// replacement keeps its slot so the popup updates where it is. The notification itself comes
+    /// in `notification_added`, `notifications_added_batch` or `notification_updated`; a batched
+    /// addition may arrive after this signal.
+    #[zbus(signal)]
+    fn popup_shown(&self, id: u32, slot: u32) -> zbus::Result<()>;
+
     #[zbus(signal)]
     fn state_changed(&self, state: ControlState) -> zbus::Result<()>;
 
     #[zbus(signal)]
     fn panel_requested(&self, request: PanelRequest) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn panel_geometry_requested(&self, geometry: PanelGeometry) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn panel_debug_level_requested(&self, level: PanelDebugLevel) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn layout_preview_requested(&self, seconds: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn osd_requested(&self, kind: OsdKind, percent: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn app_popup_anchor_changed(&self, app: &str, anchor: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn memory_report_requested(&self) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn trim_requested(&self) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn rules_changed(&self) -> zbus::Result<()>;
+
+    /// History changed in a way no close signal describes, such as an undone clear putting
+    /// entries back. Listeners keeping a copy of history should list it again.
+    #[zbus(signal)]
+    fn history_changed(&self) -> zbus::Result<()>;
+
+    /// One panel debug line at `level`, sent while the panel runs with debug logging.
+    #[zbus(signal)]
+    fn debug_log(&self, level: PanelDebugLevel, message: String) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BusNames, RuleDict};
+    use crate::RuleConfig;
+
+    #[test]
+    fn bus_suffix_derives_names_and_rejects_invalid_elements() {
+        let names = BusNames::new(Some("dev")).expect("valid suffix");
+        assert_eq!(names.control, "com.unixnotis.Control.dev");
+        assert_eq!(names.notifications, "org.freedesktop.Notifications.dev");
+        assert_eq!(
+            names.app_id("com.unixnotis.Center"),
+            "com.unixnotis.Center.dev"
+        );
+
+        let names = BusNames::new(Some("  ")).expect("blank suffix");
+        assert_eq!(names.control, "com.unixnotis.Control");
+        assert!(names.suffix().is_none());
+
+        assert!(BusNames::new(Some("2nd")).is_err());
+        assert!(BusNames::new(Some("dev.test")).is_err());
+    }
+
+    #[test]
+    fn rules_round_trip_through_dicts() {
+        let rule = RuleConfig {
+            name: Some("quiet chat".to_string()),
+            app: Some("discord".to_string()),
+            no_popup: Some(true),
+            force_urgency: Some(0),
+            expire_timeout_ms: Some(-1),
+            history_ttl_minutes: Some(10),
+            clamp_timeout: Some(false),
+            ..RuleConfig::default()
+        };
+        let dict = rule.to_dict();
+        assert_eq!(dict.len(), 7);
+        assert_eq!(RuleConfig::from_dict(&dict), Ok(rule));
+
+        let mut dict = RuleDict::new();
+        dict.insert("force_urgency".to_string(), 3u8.into());
+        assert!(RuleConfig::from_dict(&dict).is_err());
+        let mut dict = RuleDict::new();
+        dict.insert("app".to_string(), true.into());
+        assert!(RuleConfig::from_dict(&dict).is_err());
+        let mut dict = RuleDict::new();
+        dict.insert("mute".to_string(), true.into());
+        assert!(RuleConfig::from_dict(&dict).is_err());
+    }
 }
@@ -0,0 +1,194 @@
+//! Translated UI strings backed by embedded Fluent catalogs.
+//!
+//! Frontends call [`init`] once at startup with the `general.language` override, then look up
+//! strings with [`tr`] and [`tr_args`]. Messages missing from the active catalog fall back to
+//! English, and unknown ids render as the id itself so a typo stays visible instead of blank.
+
+use std::env;
+use std::sync::OnceLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+pub use fluent_bundle::FluentValue;
+
+/// Catalogs shipped with UnixNotis, keyed by language tag. English must stay first.
+const CATALOGS: &[(&str, &str)] = &[
+    (
+        "en",
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/i18n/en.ftl")),
+    ),
+    (
+        "de",
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/i18n/de.ftl")),
+    ),
+];
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Select the UI language for this process.
+///
+/// `language` is the config override; when unset the locale comes from `LC_ALL`,
+/// `LC_MESSAGES`, then `LANG`. Only the first call has an effect, so changing
+/// `general.language` needs a restart of the frontend.
+pub fn init(language: Option<&str>) {
+    let _ = CATALOG.set(Catalog::load(language));
+}
+
+/// Language tag of the active catalog.
+pub fn language() -> &'static str {
+    catalog().language
+}
+
+/// Look up a message without arguments.
+pub fn tr(id: &str) -> String {
+    catalog().format(id, None)
+}
+
+/// Look up a message with named arguments, e.g. `tr_args("new-pill", &[("count", 3.into())])`.
+pub fn tr_args(id: &str, args: &[(&str, FluentValue<'_>)]) -> String {
+    let mut fluent_args = FluentArgs::with_capacity(args.len());
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+    catalog().format(id, Some(&fluent_args))
+}
+
+fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog::load(None))
+}
+
+struct Catalog {
+    language: &'static str,
+    primary: Option<FluentBundle<FluentResource>>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+impl Catalog {
+    fn load(language: Option<&str>) -> Self {
+        let requested = language
+            .map(str::to_string)
+            .or_else(locale_from_env)
+            .unwrap_or_default();
+        let (language, source) = match_catalog(&requested).unwrap_or(CATALOGS[0]);
+        let primary = (language != CATALOGS[0].0).then(|| build_bundle(language, source));
+        Self {
+            language,
+            primary,
+            fallback: build_bundle(CATALOGS[0].0, CATALOGS[0].1),
+        }
+    }
+
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        self.primary
+            .iter()
+            .chain(std::iter::once(&self.fallback))
+            .find_map(|bundle| {
+                let pattern = bundle.get_message(id)?.value()?;
+                let mut errors = Vec::new();
+                let text = bundle.format_pattern(pattern, args, &mut errors);
+                if !errors.is_empty() {
+                    tracing::debug!(id, ?errors, "translation formatted with errors");
+                }
+                Some(text.into_owned())
+            })
+            .unwrap_or_else(|| {
+                tracing::debug!(id, "missing translation");
+                id.to_string()
+            })
+    }
+}
+
+fn build_bundle(language: &str, source: &str) -> FluentBundle<FluentResource> {
+    let langid: LanguageIdentifier = language.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Bidi isolation marks show up as stray glyphs in GTK labels and terminals.
+    bundle.set_use_isolating(false);
+    let resource =
+        FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+            tracing::warn!(language, ?errors, "translation catalog has syntax errors");
+            resource
+        });
+    if let Err(errors) = bundle.add_resource(resource) {
+        tracing::warn!(
+            language,
+            ?errors,
+            "translation catalog has duplicate messages"
+        );
+    }
+    bundle
+}
+
+fn locale_from_env() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+/// Map a POSIX locale (`de_DE.UTF-8@euro`) or language tag (`de-AT`) onto a shipped catalog.
+fn match_catalog(locale: &str) -> Option<(&'static str, &'static str)> {
+    let tag = locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .replace('_', "-")
+        .to_ascii_lowercase();
+    if tag.is_empty() || tag == "c" || tag == "posix" {
+        return None;
+    }
+    let primary = tag.split('-').next().unwrap_or_default();
+    CATALOGS
+        .iter()
+        .find(|(language, _)| *language == tag)
+        .or_else(|| CATALOGS.iter().find(|(language, _)| *language == primary))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_posix_locales_and_tags() {
+        assert_eq!(match_catalog("de_DE.UTF-8").map(|(l, _)| l), Some("de"));
+        assert_eq!(match_catalog("de-AT").map(|(l, _)| l), Some("de"));
+        assert_eq!(match_catalog("en_GB@euro").map(|(l, _)| l), Some("en"));
+        assert_eq!(match_catalog("C.UTF-8"), None);
+        assert_eq!(match_catalog("xx_YY"), None);
+    }
+
+    #[test]
+    fn formats_selected_catalog_or_id() {
+        let catalog = Catalog::load(Some("de_DE.UTF-8"));
+        assert_eq!(catalog.language, "de");
+        assert_eq!(catalog.format("panel-clear", None), "Leeren");
+        assert_eq!(catalog.format("no-such-message", None), "no-such-message");
+
+        let mut args = FluentArgs::new();
+        args.set("count", 1);
+        let english = Catalog::load(Some("en"));
+        assert_eq!(english.format("updates-count", Some(&args)), "1 update");
+    }
+
+    fn ids(source: &str) -> Vec<&str> {
+        assert!(FluentResource::try_new(source.to_string()).is_ok());
+        let mut ids: Vec<&str> = source
+            .lines()
+            .filter(|line| line.starts_with(|ch: char| ch.is_ascii_alphabetic()))
+            .filter_map(|line| line.split_once(" =").map(|(id, _)| id))
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn catalogs_define_the_same_messages() {
+        let english = ids(CATALOGS[0].1);
+        for (language, source) in &CATALOGS[1..] {
+            assert_eq!(ids(source), english, "catalog {language} is out of sync");
+        }
+    }
+}
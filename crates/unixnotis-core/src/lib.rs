@@ -2,8 +2,14 @@
 
 pub mod config;
 pub mod control;
+pub mod i18n;
+pub mod logging;
+pub mod markup;
 pub mod model;
+pub mod notifications;
+pub mod popup_positions;
 pub mod theme;
+pub mod ui_ipc;
 pub mod util;
 
 pub use config::*;
@@ -0,0 +1,96 @@
+//! Tracing setup shared by the daemon and the UI processes.
+//!
+//! Hot paths (notify handling, store inserts, panel rebuilds, icon decodes) carry debug-level
+//! spans. Builds with the `profile` feature write those spans as a Chrome trace when
+//! `UNIXNOTIS_PROFILE` names a directory, one file per process, which Perfetto or
+//! `chrome://tracing` open as a flame chart. The spans stay out of the regular log output.
+
+use std::path::PathBuf;
+
+use tracing_subscriber::EnvFilter;
+
+/// Directory that profiled builds write Chrome traces into.
+pub const PROFILE_ENV: &str = "UNIXNOTIS_PROFILE";
+
+/// Holds the trace file open; dropping it flushes the trace.
+#[must_use = "dropping the guard stops profiling"]
+pub struct TracingGuard {
+    #[cfg(feature = "profile")]
+    chrome: Option<tracing_chrome::FlushGuard>,
+}
+
+impl TracingGuard {
+    /// Whether spans are being written to a trace file.
+    pub fn is_profiling(&self) -> bool {
+        #[cfg(feature = "profile")]
+        {
+            self.chrome.is_some()
+        }
+        #[cfg(not(feature = "profile"))]
+        {
+            false
+        }
+    }
+}
+
+/// Install the global subscriber; `RUST_LOG` overrides `log_level`.
+///
+/// `process` names the trace file, e.g. `unixnotis-daemon-1234.json`.
+pub fn init_tracing(log_level: Option<&str>, process: &str) -> TracingGuard {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(log_level.unwrap_or("info")));
+    let profile_dir = std::env::var_os(PROFILE_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+
+    #[cfg(feature = "profile")]
+    {
+        init_profiled(filter, profile_dir, process)
+    }
+    #[cfg(not(feature = "profile"))]
+    {
+        let _ = process;
+        tracing_subscriber::fmt().with_env_filter(filter).init();
+        if profile_dir.is_some() {
+            tracing::warn!("{PROFILE_ENV} is set, but this build lacks the `profile` feature");
+        }
+        TracingGuard {}
+    }
+}
+
+#[cfg(feature = "profile")]
+fn init_profiled(filter: EnvFilter, dir: Option<PathBuf>, process: &str) -> TracingGuard {
+    use tracing::info;
+    use tracing_subscriber::filter::filter_fn;
+    use tracing_subscriber::prelude::*;
+
+    let path = dir.and_then(|dir| match std::fs::create_dir_all(&dir) {
+        Ok(()) => Some(dir.join(format!("{process}-{}.json", std::process::id()))),
+        Err(err) => {
+            eprintln!("{PROFILE_ENV}: cannot create {}: {err}", dir.display());
+            None
+        }
+    });
+    let (chrome, guard) = match &path {
+        Some(path) => {
+            let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+                .file(path)
+                .include_args(true)
+                .build();
+            // Only our own spans: dependency spans (zbus, tokio) would drown the hot paths.
+            let layer = layer.with_filter(filter_fn(|meta| {
+                meta.is_span() && meta.target().starts_with("unixnotis")
+            }));
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(filter))
+        .with(chrome)
+        .init();
+    if let Some(path) = path {
+        info!(path = %path.display(), "writing profile trace");
+    }
+    TracingGuard { chrome: guard }
+}
@@ -0,0 +1,314 @@
+//! Sanitizer for notification bodies, which the spec lets carry a small markup subset.
+//!
+//! Bodies come from any client and end up in `gtk::Label::set_markup`, which shows nothing
+//! when a single tag is malformed and understands far more than the spec promises (fonts,
+//! sizes, colors). Only `b`, `i`, `u`, and `a` survive; `img` becomes its alt text, `br` a line
+//! break, and other Pango or HTML tags are dropped with their text kept. Anything else in angle
+//! brackets, such as `<Alice Bob>` in a plain-text body, is shown as written. Stray `<`, `>`, and
+//! `&` are escaped and open tags are closed, so the result always parses.
+//!
+//! The daemon stores bodies as sent, so rules and scripts see the original text; the UIs run
+//! this just before `set_markup`.
+
+/// Link schemes a click may hand to the desktop; anything else keeps its text but not the link.
+const LINK_SCHEMES: &[&str] = &["http", "https", "mailto"];
+/// Tags dropped without their text; other names in angle brackets are not markup.
+const DROPPED_TAGS: &[&str] = &[
+    "big",
+    "blockquote",
+    "body",
+    "code",
+    "del",
+    "div",
+    "em",
+    "font",
+    "hr",
+    "html",
+    "li",
+    "markup",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "small",
+    "span",
+    "strike",
+    "strong",
+    "sub",
+    "sup",
+    "tt",
+    "ul",
+];
+
+/// Rewrite `body` into markup that GTK accepts and that holds only the spec's tags.
+pub fn sanitize_body(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    // Open tags, innermost last; `None` marks a link that was dropped but still needs its close.
+    let mut open: Vec<Option<&'static str>> = Vec::new();
+    let mut rest = body;
+    while let Some(pos) = rest.find(['<', '&', '>']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with('>') {
+            out.push_str("&gt;");
+            rest = &rest[1..];
+        } else if rest.starts_with('&') {
+            let len = match entity_len(rest) {
+                Some(len) => {
+                    out.push_str(&rest[..len]);
+                    len
+                }
+                None => {
+                    out.push_str("&amp;");
+                    1
+                }
+            };
+            rest = &rest[len..];
+        } else {
+            let tag = rest.find('>').and_then(|end| {
+                let tag = parse_tag(&rest[1..end])?;
+                is_markup(&tag.name).then_some((end, tag))
+            });
+            match tag {
+                Some((end, tag)) => {
+                    apply_tag(tag, &mut out, &mut open);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    out.push_str("&lt;");
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+    out.push_str(rest);
+    while let Some(tag) = open.pop() {
+        close_tag(&mut out, tag);
+    }
+    out
+}
+
+fn is_markup(name: &str) -> bool {
+    matches!(name, "b" | "i" | "u" | "a" | "br" | "img") || DROPPED_TAGS.contains(&name)
+}
+
+struct Tag<'a> {
+    name: String,
+    attrs: &'a str,
+    closing: bool,
+}
+
+// `inner` is the text between `<` and `>`; `None` means it is not a tag at all.
+fn parse_tag(inner: &str) -> Option<Tag<'_>> {
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(inner) => (true, inner),
+        None => (false, inner),
+    };
+    let inner = inner.strip_suffix('/').unwrap_or(inner);
+    let name_len = inner
+        .find(|ch: char| !ch.is_ascii_alphanumeric())
+        .unwrap_or(inner.len());
+    let (name, attrs) = inner.split_at(name_len);
+    if !name.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        return None;
+    }
+    if !attrs.is_empty() && !attrs.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(Tag {
+        name: name.to_ascii_lowercase(),
+        attrs,
+        closing,
+    })
+}
+
+fn apply_tag(tag: Tag<'_>, out: &mut String, open: &mut Vec<Option<&'static str>>) {
+    let name = match tag.name.as_str() {
+        "b" => "b",
+        "i" => "i",
+        "u" => "u",
+        "a" => "a",
+        "br" => {
+            out.push('\n');
+            return;
+        }
+        "img" => {
+            if let Some(alt) = attribute(tag.attrs, "alt") {
+                out.push_str(&escape_text(&alt));
+            }
+            return;
+        }
+        _ => return,
+    };
+    if tag.closing {
+        // Close everything opened inside it too; a close with nothing open is dropped.
+        let Some(depth) = open.iter().rposition(|entry| entry.unwrap_or("a") == name) else {
+            return;
+        };
+        for entry in open.drain(depth..).rev() {
+            close_tag(out, entry);
+        }
+        return;
+    }
+    if name != "a" {
+        out.push('<');
+        out.push_str(name);
+        out.push('>');
+        open.push(Some(name));
+        return;
+    }
+    // Links cannot nest, and only known schemes stay clickable.
+    let href = attribute(tag.attrs, "href").filter(|href| is_allowed_link(href));
+    match href {
+        Some(href) if !open.contains(&Some("a")) => {
+            out.push_str("<a href=\"");
+            out.push_str(&escape_text(&href));
+            out.push_str("\">");
+            open.push(Some("a"));
+        }
+        _ => open.push(None),
+    }
+}
+
+fn close_tag(out: &mut String, tag: Option<&'static str>) {
+    if let Some(name) = tag {
+        out.push_str("</");
+        out.push_str(name);
+        out.push('>');
+    }
+}
+
+/// Value of `key` in a tag's attribute text, with basic entities decoded.
+fn attribute(attrs: &str, key: &str) -> Option<String> {
+    let mut rest = attrs.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|ch: char| ch == '=' || ch.is_whitespace())
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let body = &after[1..];
+                        let end = body.find(quote)?;
+                        (&body[..end], &body[end + 1..])
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        after.split_at(end)
+                    }
+                };
+                rest = remaining.trim_start();
+                value
+            }
+            None => "",
+        };
+        if name.eq_ignore_ascii_case(key) {
+            return Some(unescape(value));
+        }
+        if name.is_empty() {
+            // Not attribute syntax; give up rather than loop.
+            return None;
+        }
+    }
+    None
+}
+
+/// Whether a click may hand `href` to the desktop: only http, https, and mailto links.
+pub fn is_allowed_link(href: &str) -> bool {
+    href.split_once(':').is_some_and(|(scheme, _)| {
+        LINK_SCHEMES
+            .iter()
+            .any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+    })
+}
+
+/// Length of the entity at the start of `text`, if it is one Pango understands.
+fn entity_len(text: &str) -> Option<usize> {
+    let end = text.find(';')?;
+    let name = &text[1..end];
+    let valid = match name.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => number.parse::<u32>().ok(),
+            };
+            code.and_then(char::from_u32).is_some_and(|ch| ch != '\0')
+        }
+        None => matches!(name, "amp" | "lt" | "gt" | "quot" | "apos"),
+    };
+    valid.then_some(end + 1)
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_allowed_link, sanitize_body};
+
+    #[test]
+    fn keeps_spec_tags_and_drops_the_rest() {
+        assert_eq!(
+            sanitize_body("<b>Bold</b> <span color='red'>red</span> <I>it</I>"),
+            "<b>Bold</b> red <i>it</i>"
+        );
+        assert_eq!(
+            sanitize_body("<img src=\"x.png\" alt=\"cat & dog\"/> line<br/>two"),
+            "cat &amp; dog line\ntwo"
+        );
+    }
+
+    #[test]
+    fn escapes_stray_characters_and_closes_open_tags() {
+        assert_eq!(
+            sanitize_body("1 < 2 && 3 > 2"),
+            "1 &lt; 2 &amp;&amp; 3 &gt; 2"
+        );
+        assert_eq!(
+            sanitize_body("&amp; &#169; &nbsp;"),
+            "&amp; &#169; &amp;nbsp;"
+        );
+        assert_eq!(sanitize_body("<b><i>open"), "<b><i>open</i></b>");
+        assert_eq!(
+            sanitize_body("From <Alice Bob>: hi"),
+            "From &lt;Alice Bob&gt;: hi"
+        );
+        assert_eq!(sanitize_body("<b><i>x</b> y</i>"), "<b><i>x</i></b> y");
+    }
+
+    #[test]
+    fn links_keep_only_safe_schemes() {
+        assert_eq!(
+            sanitize_body("<a href=\"https://example.org/?a=1&amp;b=2\">site</a>"),
+            "<a href=\"https://example.org/?a=1&amp;b=2\">site</a>"
+        );
+        assert_eq!(
+            sanitize_body("<a href='file:///etc/passwd'>x</a> after"),
+            "x after"
+        );
+        assert_eq!(
+            sanitize_body("<a href=\"https://a\"><a href=\"https://b\">x</a></a>"),
+            "<a href=\"https://a\">x</a>"
+        );
+        assert!(is_allowed_link("MAILTO:someone@example.org"));
+        assert!(!is_allowed_link("file:///etc/passwd"));
+        assert!(!is_allowed_link("no-scheme"));
+    }
+}
@@ -39,6 +39,26 @@ impl Urgency {
     }
 }
 
+/// Percentage from the `value` hint sent for volume, brightness, or download progress.
+///
+/// The spec types it as int32; unsigned and 64-bit senders are accepted too, and values
+/// outside 0 to 100 are clamped.
+pub fn progress_from_hint(value: Option<&OwnedValue>) -> Option<u8> {
+    let value = value?;
+    let percent = if let Ok(v) = i32::try_from(value) {
+        i64::from(v)
+    } else if let Ok(v) = u32::try_from(value) {
+        i64::from(v)
+    } else if let Ok(v) = u8::try_from(value) {
+        i64::from(v)
+    } else if let Ok(v) = i64::try_from(value) {
+        v
+    } else {
+        return None;
+    };
+    Some(percent.clamp(0, 100) as u8)
+}
+
 /// Action pair in the notification protocol.
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct Action {
@@ -59,6 +79,9 @@ pub struct ImageData {
 }
 
 /// Image information derived from standard hints and app_icon.
+///
+/// Listings (`list_active`, `list_history`) keep `has_image_data` and the image size but leave
+/// the pixels out to keep replies small; `get_image` returns them.
 #[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
 pub struct NotificationImage {
     pub has_image_data: bool,
@@ -67,8 +90,33 @@ pub struct NotificationImage {
     pub icon_name: String,
 }
 
-const MAX_IMAGE_BYTES: usize = 1024 * 1024;
-const MAX_IMAGE_DIMENSION: i32 = 512;
+/// Size caps for an inline `image-data` hint; larger images are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+    pub max_bytes: usize,
+    pub max_dimension: i32,
+}
+
+impl ImageLimits {
+    /// Enough for icons and avatars.
+    pub const ICON: Self = Self {
+        max_bytes: 1024 * 1024,
+        max_dimension: 512,
+    };
+    /// Screenshot thumbnails shown with `icons.image_preview`; the UIs downscale on decode.
+    pub const PREVIEW: Self = Self {
+        max_bytes: 4 * 1024 * 1024,
+        max_dimension: 1024,
+    };
+
+    pub fn for_previews(enabled: bool) -> Self {
+        if enabled {
+            Self::PREVIEW
+        } else {
+            Self::ICON
+        }
+    }
+}
 
 /// Full notification record stored by the daemon.
 #[derive(Debug)]
@@ -91,6 +139,16 @@ pub struct Notification {
     pub image: NotificationImage,
     pub expire_timeout: i32,
     pub received_at: DateTime<Utc>,
+    /// Minutes this notification may stay in history, assigned by rules.
+    pub history_ttl_minutes: Option<u32>,
+    /// Hold the timeout within the configured bounds; rules can turn this off.
+    pub clamp_timeout: bool,
+    /// Unique bus name of the client that sent it; unknown for restored history.
+    pub sender: Option<String>,
+    /// Pinned from the panel: listed first and never trimmed from history.
+    pub pinned: bool,
+    /// Percentage from the `value` hint, shown as a progress bar.
+    pub progress: Option<u8>,
 }
 
 impl Notification {
@@ -107,6 +165,9 @@ impl Notification {
             is_resident: self.is_resident,
             received_at_unix_ms: self.received_at.timestamp_millis(),
             image: self.image.clone(),
+            history_ttl_minutes: self.history_ttl_minutes.unwrap_or(0),
+            pinned: self.pinned,
+            progress: self.progress.map_or(-1, i32::from),
         }
     }
 
@@ -122,10 +183,76 @@ impl Notification {
             is_resident: self.is_resident,
             received_at_unix_ms: self.received_at.timestamp_millis(),
             image: self.image.for_listing(),
+            history_ttl_minutes: self.history_ttl_minutes.unwrap_or(0),
+            pinned: self.pinned,
+            progress: self.progress.map_or(-1, i32::from),
         }
     }
 
-    pub fn to_history(&self) -> Notification {
+    /// Rough heap footprint used for memory reporting; hints are counted per entry.
+    pub fn estimated_size(&self) -> usize {
+        const HINT_ESTIMATE: usize = 64;
+        let actions: usize = self
+            .actions
+            .iter()
+            .map(|action| action.key.len() + action.label.len())
+            .sum();
+        std::mem::size_of::<Notification>()
+            + self.app_name.len()
+            + self.app_icon.len()
+            + self.summary.len()
+            + self.body.len()
+            + self.category.as_ref().map_or(0, String::len)
+            + self.sender.as_ref().map_or(0, String::len)
+            + actions
+            + self.hints.len() * HINT_ESTIMATE
+            + self.image.image_data.data.len()
+            + self.image.image_path.len()
+            + self.image.icon_name.len()
+    }
+
+    /// Copy with the actions removed, for a sender that can no longer receive them.
+    pub fn without_actions(&self) -> Notification {
+        Notification {
+            id: self.id,
+            app_name: self.app_name.clone(),
+            app_icon: self.app_icon.clone(),
+            summary: self.summary.clone(),
+            body: self.body.clone(),
+            actions: Vec::new(),
+            hints: self
+                .hints
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.try_clone().ok()?)))
+                .collect(),
+            urgency: self.urgency,
+            category: self.category.clone(),
+            is_transient: self.is_transient,
+            is_resident: self.is_resident,
+            suppress_popup: self.suppress_popup,
+            suppress_sound: self.suppress_sound,
+            image: self.image.clone(),
+            expire_timeout: self.expire_timeout,
+            received_at: self.received_at,
+            history_ttl_minutes: self.history_ttl_minutes,
+            clamp_timeout: self.clamp_timeout,
+            sender: self.sender.clone(),
+            pinned: self.pinned,
+            progress: self.progress,
+        }
+    }
+
+    /// Copy with `pinned` set.
+    pub fn with_pinned(&self, pinned: bool) -> Notification {
+        Notification {
+            actions: self.actions.clone(),
+            pinned,
+            ..self.without_actions()
+        }
+    }
+
+    /// Copy for history, without hints; `keep_image_data` keeps inline pixels for previews.
+    pub fn to_history(&self, keep_image_data: bool) -> Notification {
         Notification {
             id: self.id,
             app_name: self.app_name.clone(),
@@ -140,9 +267,19 @@ impl Notification {
             is_resident: self.is_resident,
             suppress_popup: self.suppress_popup,
             suppress_sound: self.suppress_sound,
-            image: self.image.for_history(),
+            image: if keep_image_data {
+                self.image.clone()
+            } else {
+                self.image.for_history()
+            },
             expire_timeout: self.expire_timeout,
             received_at: self.received_at,
+            history_ttl_minutes: self.history_ttl_minutes,
+            clamp_timeout: self.clamp_timeout,
+            sender: self.sender.clone(),
+            pinned: self.pinned,
+            // A level or download left in history has stopped moving; the text says enough.
+            progress: None,
         }
     }
 }
@@ -160,17 +297,55 @@ pub struct NotificationView {
     pub is_resident: bool,
     pub received_at_unix_ms: i64,
     pub image: NotificationImage,
+    /// History lifetime in minutes set by a rule; 0 follows global retention.
+    pub history_ttl_minutes: u32,
+    pub pinned: bool,
+    /// Progress percentage from the `value` hint, or -1 when the notification has none.
+    pub progress: i32,
+}
+
+impl NotificationView {
+    /// Progress as a 0.0 to 1.0 fraction for a progress bar.
+    pub fn progress_fraction(&self) -> Option<f64> {
+        (0..=100)
+            .contains(&self.progress)
+            .then(|| f64::from(self.progress) / 100.0)
+    }
+
+    /// Rough heap footprint used for memory reporting in the UI processes.
+    pub fn estimated_size(&self) -> usize {
+        let actions: usize = self
+            .actions
+            .iter()
+            .map(|action| action.key.len() + action.label.len())
+            .sum();
+        std::mem::size_of::<NotificationView>()
+            + self.app_name.len()
+            + self.summary.len()
+            + self.body.len()
+            + actions
+            + self.image.image_data.data.len()
+            + self.image.image_path.len()
+            + self.image.icon_name.len()
+    }
 }
 
 impl NotificationImage {
-    pub fn from_hints(app_name: &str, app_icon: &str, hints: &HashMap<String, OwnedValue>) -> Self {
+    pub fn from_hints(
+        app_name: &str,
+        app_icon: &str,
+        hints: &HashMap<String, OwnedValue>,
+        limits: ImageLimits,
+    ) -> Self {
         // The spec prefers image-data over image-path and app_icon.
-        let image_data = hints
-            .get("image-data")
-            .and_then(Self::parse_image_data)
-            .or_else(|| hints.get("image_data").and_then(Self::parse_image_data))
-            .or_else(|| hints.get("icon_data").and_then(Self::parse_image_data));
-        let image_data = image_data.filter(Self::is_image_data_usable);
+        let image_data = ["image-data", "image_data", "icon_data"]
+            .iter()
+            .find_map(|key| {
+                hints
+                    .get(*key)
+                    .and_then(|value| Self::parse_image_data(value, limits))
+            })
+            .filter(|data| Self::is_image_data_usable(data, limits));
 
         let mut image_path = hints
             .get("image-path")
@@ -214,23 +389,28 @@ impl NotificationImage {
     }
 
     fn for_listing(&self) -> Self {
-        if self.image_data.data.is_empty() {
-            return self.clone();
-        }
         Self {
-            has_image_data: false,
-            image_data: ImageData::default(),
+            has_image_data: self.has_image_data,
+            image_data: ImageData {
+                width: self.image_data.width,
+                height: self.image_data.height,
+                rowstride: self.image_data.rowstride,
+                has_alpha: self.image_data.has_alpha,
+                bits_per_sample: self.image_data.bits_per_sample,
+                channels: self.image_data.channels,
+                data: Vec::new(),
+            },
             image_path: self.image_path.clone(),
             icon_name: self.icon_name.clone(),
         }
     }
 
-    fn is_image_data_usable(data: &ImageData) -> bool {
+    fn is_image_data_usable(data: &ImageData, limits: ImageLimits) -> bool {
         // Strict validation keeps downstream GTK texture creation safe.
         if data.width <= 0 || data.height <= 0 {
             return false;
         }
-        if data.width > MAX_IMAGE_DIMENSION || data.height > MAX_IMAGE_DIMENSION {
+        if data.width > limits.max_dimension || data.height > limits.max_dimension {
             return false;
         }
         if data.bits_per_sample != 8 || data.channels != 4 {
@@ -244,11 +424,12 @@ impl NotificationImage {
             data.bits_per_sample,
             data.channels,
             data.data.len(),
+            limits.max_bytes,
         )
         .is_some()
     }
 
-    fn parse_image_data(value: &OwnedValue) -> Option<ImageData> {
+    fn parse_image_data(value: &OwnedValue, limits: ImageLimits) -> Option<ImageData> {
         // The image-data hint is a struct of (iiibiiay) per the spec.
         let structure = <&Structure>::try_from(value).ok()?;
         let fields = structure.fields();
@@ -263,7 +444,7 @@ impl NotificationImage {
         let bits_per_sample = i32::try_from(&fields[4]).ok()?;
         let channels = i32::try_from(&fields[5]).ok()?;
         // Copy array contents into an owned buffer, enforcing size limits.
-        let data = Self::array_to_bytes(&fields[6])?;
+        let data = Self::array_to_bytes(&fields[6], limits.max_bytes)?;
         let image = ImageData {
             width,
             height,
@@ -273,7 +454,7 @@ impl NotificationImage {
             channels,
             data,
         };
-        Self::normalize_image_data(image)
+        Self::normalize_image_data(image, limits)
     }
 
     pub fn for_history(&self) -> NotificationImage {
@@ -286,7 +467,7 @@ impl NotificationImage {
         self.clone()
     }
 
-    fn normalize_image_data(image: ImageData) -> Option<ImageData> {
+    fn normalize_image_data(image: ImageData, limits: ImageLimits) -> Option<ImageData> {
         if image.bits_per_sample != 8 {
             return None;
         }
@@ -298,24 +479,22 @@ impl NotificationImage {
             image.bits_per_sample,
             image.channels,
             image.data.len(),
+            limits.max_bytes,
         )?;
         let rowstride = i32::try_from(rowstride).ok()?;
-        let image = ImageData {
-            rowstride,
-            ..image
-        };
+        let image = ImageData { rowstride, ..image };
         match image.channels {
             4 => Some(image),
-            3 => Self::expand_rgb_to_rgba(&image),
+            3 => Self::expand_rgb_to_rgba(&image, limits.max_bytes),
             _ => None,
         }
     }
 
-    fn array_to_bytes(value: &Value<'_>) -> Option<Vec<u8>> {
+    fn array_to_bytes(value: &Value<'_>, max_bytes: usize) -> Option<Vec<u8>> {
         let array = <&Array>::try_from(value).ok()?;
         let elements = array.inner();
         // Cap allocation to the maximum allowed payload to avoid oversized hint buffers.
-        if elements.is_empty() || elements.len() > MAX_IMAGE_BYTES {
+        if elements.is_empty() || elements.len() > max_bytes {
             return None;
         }
         let mut bytes = Vec::with_capacity(elements.len());
@@ -332,9 +511,10 @@ impl NotificationImage {
         bits_per_sample: i32,
         channels: i32,
         data_len: usize,
+        max_bytes: usize,
     ) -> Option<usize> {
         // Ensure rowstride covers at least one full pixel row and the backing buffer is large enough.
-        if data_len == 0 || data_len > MAX_IMAGE_BYTES {
+        if data_len == 0 || data_len > max_bytes {
             return None;
         }
         // Negative rowstride is invalid for memory buffers.
@@ -375,7 +555,7 @@ impl NotificationImage {
         usize::try_from(bits_per_pixel / 8).ok()
     }
 
-    fn expand_rgb_to_rgba(image: &ImageData) -> Option<ImageData> {
+    fn expand_rgb_to_rgba(image: &ImageData, max_bytes: usize) -> Option<ImageData> {
         // Expand RGB to RGBA while preserving row semantics and size limits.
         let width = image.width.max(1) as usize;
         let height = image.height.max(1) as usize;
@@ -387,7 +567,7 @@ impl NotificationImage {
         let pixel_count = width.checked_mul(height)?;
         let output_len = pixel_count.checked_mul(4)?;
         // Cap expanded output to the same limit as the raw image payload.
-        if output_len > MAX_IMAGE_BYTES {
+        if output_len > max_bytes {
             return None;
         }
         let mut rgba = vec![0u8; output_len];
@@ -500,9 +680,53 @@ fn strip_desktop_suffix(value: &str) -> String {
     }
 }
 
+#[cfg(test)]
+impl Notification {
+    /// A plain normal-urgency notification for tests to adjust.
+    pub(crate) fn for_test(id: u32, app_name: &str) -> Self {
+        Self {
+            id,
+            app_name: app_name.to_string(),
+            app_icon: String::new(),
+            summary: "summary".to_string(),
+            body: String::new(),
+            actions: Vec::new(),
+            hints: HashMap::new(),
+            urgency: Urgency::Normal,
+            category: None,
+            is_transient: false,
+            is_resident: false,
+            suppress_popup: false,
+            suppress_sound: false,
+            image: NotificationImage::default(),
+            expire_timeout: -1,
+            received_at: Utc::now(),
+            history_ttl_minutes: None,
+            clamp_timeout: true,
+            sender: None,
+            pinned: false,
+            progress: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{ImageData, NotificationImage};
+    use super::{progress_from_hint, ImageData, ImageLimits, NotificationImage};
+    use zbus::zvariant::{OwnedValue, Value};
+
+    #[test]
+    fn progress_hint_accepts_integer_types_and_clamps() {
+        let hint = |value: Value<'static>| OwnedValue::try_from(value).ok();
+        assert_eq!(progress_from_hint(hint(Value::I32(42)).as_ref()), Some(42));
+        assert_eq!(
+            progress_from_hint(hint(Value::U32(150)).as_ref()),
+            Some(100)
+        );
+        assert_eq!(progress_from_hint(hint(Value::I32(-5)).as_ref()), Some(0));
+        assert_eq!(progress_from_hint(hint(Value::from("50")).as_ref()), None);
+        assert_eq!(progress_from_hint(None), None);
+    }
 
     #[test]
     fn normalize_image_data_rejects_short_rowstride() {
@@ -516,7 +740,7 @@ mod tests {
             channels: 4,
             data: vec![0u8; 8],
         };
-        assert!(NotificationImage::normalize_image_data(image).is_none());
+        assert!(NotificationImage::normalize_image_data(image, ImageLimits::ICON).is_none());
     }
 
     #[test]
@@ -531,7 +755,7 @@ mod tests {
             channels: 4,
             data: vec![0u8; 8],
         };
-        assert!(NotificationImage::normalize_image_data(image).is_none());
+        assert!(NotificationImage::normalize_image_data(image, ImageLimits::ICON).is_none());
     }
 
     #[test]
@@ -546,10 +770,32 @@ mod tests {
             channels: 4,
             data: vec![0u8; 8],
         };
-        let normalized = NotificationImage::normalize_image_data(image).expect("valid image data");
+        let normalized = NotificationImage::normalize_image_data(image, ImageLimits::ICON)
+            .expect("valid image data");
         assert_eq!(normalized.rowstride, 8);
     }
 
+    #[test]
+    fn screenshot_sized_images_need_previews() {
+        let image = ImageData {
+            width: 800,
+            height: 600,
+            rowstride: 3200,
+            has_alpha: true,
+            bits_per_sample: 8,
+            channels: 4,
+            data: vec![0u8; 3200 * 600],
+        };
+        assert!(!NotificationImage::is_image_data_usable(
+            &image,
+            ImageLimits::ICON
+        ));
+        assert!(NotificationImage::is_image_data_usable(
+            &image,
+            ImageLimits::PREVIEW
+        ));
+    }
+
     #[test]
     fn normalize_image_data_expands_rgb() {
         // RGB input should expand to RGBA with the expected output size.
@@ -562,7 +808,8 @@ mod tests {
             channels: 3,
             data: vec![10, 20, 30, 40, 50, 60],
         };
-        let normalized = NotificationImage::normalize_image_data(image).expect("expanded image");
+        let normalized = NotificationImage::normalize_image_data(image, ImageLimits::ICON)
+            .expect("expanded image");
         assert_eq!(normalized.channels, 4);
         assert_eq!(normalized.data.len(), 8);
     }
@@ -0,0 +1,125 @@
+//! Per-app popup positions that outlast the session.
+//!
+//! `noticenterctl popup-position` moves an app's popups to another corner or edge. The daemon
+//! keeps each choice in `popup-positions.json` under the XDG state directory, so it holds even
+//! while the popups are not running, and hands the list to the popups when they connect. Apps
+//! are keyed by their lowercased name, as the popups show it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{Anchor, Config};
+
+const POSITIONS_FILE: &str = "popup-positions.json";
+
+/// Remembered anchor per app.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PopupPositions {
+    apps: BTreeMap<String, Anchor>,
+}
+
+impl PopupPositions {
+    /// `popup-positions.json` in the state directory, when one can be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        Config::default_state_dir()
+            .ok()
+            .map(|dir| dir.join(POSITIONS_FILE))
+    }
+
+    /// Positions saved at `path`; a missing or unreadable file yields none.
+    pub fn load(path: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            warn!(?err, path = %path.display(), "ignoring unreadable popup positions");
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write through a temp file so a crash never leaves a truncated file.
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, path)
+    }
+
+    pub fn anchor_for(&self, app: &str) -> Option<Anchor> {
+        self.apps.get(&app_key(app)).copied()
+    }
+
+    /// Remember `anchor` for `app`, or forget it with `None`; returns whether anything changed.
+    pub fn set(&mut self, app: &str, anchor: Option<Anchor>) -> bool {
+        let key = app_key(app);
+        if key.is_empty() {
+            return false;
+        }
+        match anchor {
+            Some(anchor) => self.apps.insert(key, anchor) != Some(anchor),
+            None => self.apps.remove(&key).is_some(),
+        }
+    }
+
+    /// Positions from `list_app_popup_anchors`; unknown anchor names are skipped.
+    pub fn from_names<'a>(entries: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut positions = Self::default();
+        for (app, anchor) in entries {
+            if let Some(anchor) = Anchor::from_name(anchor) {
+                positions.set(app, Some(anchor));
+            }
+        }
+        positions
+    }
+
+    /// Apps and their anchors, sorted by app.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Anchor)> {
+        self.apps
+            .iter()
+            .map(|(app, anchor)| (app.as_str(), *anchor))
+    }
+}
+
+fn app_key(app: &str) -> String {
+    app.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_are_keyed_by_app_and_survive_a_round_trip() {
+        let mut positions = PopupPositions::default();
+        assert!(positions.set(" Discord ", Some(Anchor::BottomLeft)));
+        assert!(!positions.set("discord", Some(Anchor::BottomLeft)));
+        assert!(positions.set("Spotify", Some(Anchor::Top)));
+        assert_eq!(positions.anchor_for("DISCORD"), Some(Anchor::BottomLeft));
+        assert!(!positions.set("", Some(Anchor::Top)));
+
+        let dir = std::env::temp_dir().join(format!("unixnotis-positions-{}", std::process::id()));
+        let path = dir.join(POSITIONS_FILE);
+        positions.save(&path).unwrap();
+        let loaded = PopupPositions::load(&path);
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(loaded, positions);
+
+        assert!(positions.set("spotify", None));
+        assert!(!positions.set("spotify", None));
+        assert_eq!(
+            positions.iter().collect::<Vec<_>>(),
+            vec![("discord", Anchor::BottomLeft)]
+        );
+        let listed = PopupPositions::from_names([("Discord", "bottom-left"), ("mpv", "sideways")]);
+        assert_eq!(listed, positions);
+    }
+}
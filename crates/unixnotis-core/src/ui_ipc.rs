@@ -0,0 +1,164 @@
+//! Messages between the UI processes' GTK threads and their D-Bus tasks.
+//!
+//! The panel and the popups each run a D-Bus task beside GTK and talk to it over channels.
+//! `ControlCommand` covers every command that is a plain control call, and `ControlEvent`
+//! covers the control signals both UIs act on. Each UI wraps these in its own `UiEvent` and
+//! `UiCommand` next to the variants only it has, so a shared signal or call is defined once
+//! and cannot drift between the two. The types are serde-serializable so the contract can be
+//! checked, logged, or replayed.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CloseReason, ControlProxy, ControlState, MemoryReport, NotificationView, PanelDebugLevel,
+    RuleConfig,
+};
+
+/// Control signals as delivered to a UI's GTK thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", content = "args", rename_all = "snake_case")]
+pub enum ControlEvent {
+    /// A notification changed; the flag says whether it still shows as a popup.
+    NotificationUpdated(NotificationView, bool),
+    NotificationClosed(u32, CloseReason),
+    StateChanged(ControlState),
+    /// Show the layout preview for this many seconds.
+    LayoutPreview(u32),
+    /// The daemon asked every UI process for a memory report.
+    MemoryReportRequested,
+    /// The daemon asked every UI process to drop caches.
+    TrimCaches,
+}
+
+/// Commands a UI sends that map one to one onto a control call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", content = "args", rename_all = "snake_case")]
+pub enum ControlCommand {
+    Dismiss(u32),
+    Pin {
+        id: u32,
+        pinned: bool,
+    },
+    InvokeAction {
+        id: u32,
+        action_key: String,
+    },
+    /// Answer a notification's `inline-reply` action with `text`.
+    Reply {
+        id: u32,
+        text: String,
+    },
+    /// Pause (`hold`) or resume the daemon's timeout while the pointer is over a popup.
+    HoldExpiration {
+        id: u32,
+        hold: bool,
+    },
+    SetDnd(bool),
+    /// Enable Do Not Disturb for this many seconds.
+    SetDndFor(u32),
+    OpenPanel,
+    ClosePanel,
+    ReportMemory(MemoryReport),
+    /// Popup `id` painted `render_us` after its signal arrived.
+    PopupRendered {
+        id: u32,
+        render_us: u64,
+    },
+    /// Add or replace a rule by name.
+    SetRule(RuleConfig),
+    /// Remove the rule with this name.
+    RemoveRule(String),
+    /// Relay a debug line to `noticenterctl open-panel --debug`.
+    DebugLog {
+        level: PanelDebugLevel,
+        message: String,
+    },
+}
+
+impl ControlCommand {
+    /// Make the control call; answers the call returns are not needed by the UIs.
+    pub async fn send(self, proxy: &ControlProxy<'_>) -> zbus::Result<()> {
+        match self {
+            Self::Dismiss(id) => proxy.dismiss(id).await,
+            Self::Pin { id, pinned } => proxy.pin(id, pinned).await.map(|_| ()),
+            Self::InvokeAction { id, action_key } => proxy.invoke_action(id, &action_key).await,
+            Self::Reply { id, text } => proxy.reply(id, &text).await,
+            Self::HoldExpiration { id, hold } => proxy.hold_expiration(id, hold).await,
+            Self::SetDnd(enabled) => proxy.set_dnd(enabled).await,
+            Self::SetDndFor(seconds) => proxy.set_dnd_for(seconds).await,
+            Self::OpenPanel => proxy.open_panel().await,
+            Self::ClosePanel => proxy.close_panel().await,
+            Self::ReportMemory(report) => proxy.report_memory(report).await,
+            Self::PopupRendered { id, render_us } => {
+                proxy.report_popup_rendered(id, render_us).await
+            }
+            // The daemon answers with rules_changed, which refreshes the editor.
+            Self::SetRule(rule) => proxy.set_rule(rule.to_dict()).await,
+            Self::RemoveRule(name) => proxy.remove_rule(&name).await.map(|_| ()),
+            Self::DebugLog { level, message } => proxy.publish_debug_log(level, &message).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Notification;
+
+    /// JSON for `value` after a trip through JSON, which must match the first encoding.
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> (String, String) {
+        let json = serde_json::to_string(value).unwrap();
+        let back: T = serde_json::from_str(&json).unwrap();
+        (json, serde_json::to_string(&back).unwrap())
+    }
+
+    #[test]
+    fn events_and_commands_survive_a_serde_round_trip() {
+        let mut notification = Notification::for_test(7, "chat");
+        notification.progress = Some(40);
+        let view = notification.to_view();
+        let state = ControlState {
+            dnd_enabled: true,
+            dnd_until: 0,
+            history_count: 3,
+            active_evictions: 0,
+            history_evictions: 0,
+            inhibited_by: "fullscreen".to_string(),
+            popups_enabled: true,
+            muted: false,
+        };
+        let events = [
+            ControlEvent::NotificationUpdated(view, true),
+            ControlEvent::NotificationClosed(7, CloseReason::DismissedByUser),
+            ControlEvent::StateChanged(state),
+            ControlEvent::LayoutPreview(5),
+            ControlEvent::TrimCaches,
+        ];
+        for event in &events {
+            let (json, again) = round_trip(event);
+            assert_eq!(json, again);
+        }
+        let (json, _) = round_trip(&events[1]);
+        assert_eq!(json, r#"{"event":"notification_closed","args":[7,2]}"#);
+
+        let commands = [
+            ControlCommand::Dismiss(7),
+            ControlCommand::InvokeAction {
+                id: 7,
+                action_key: "default".to_string(),
+            },
+            ControlCommand::SetRule(RuleConfig::for_app("chat")),
+            ControlCommand::DebugLog {
+                level: PanelDebugLevel::Info,
+                message: "hello".to_string(),
+            },
+            ControlCommand::OpenPanel,
+        ];
+        for command in &commands {
+            let (json, again) = round_trip(command);
+            assert_eq!(json, again);
+        }
+        let (json, _) = round_trip(&commands[0]);
+        assert_eq!(json, r#"{"command":"dismiss","args":7}"#);
+    }
+}
@@ -126,6 +126,69 @@ pub fn log_snippet(value: &str) -> String {
     sanitize_log_value(value, log_limit())
 }
 
+/// Escape plain text for notification bodies, which are Pango markup.
+pub fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Resident set size of the current process, read from procfs.
+pub fn process_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_rss(&status)
+}
+
+/// Size and free space of the filesystem mounted at a path, in bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct FsUsage {
+    /// Device the filesystem is on (`st_dev`); two paths on one filesystem share it.
+    pub device: u64,
+    pub total: u64,
+    /// Space unprivileged users can still write.
+    pub available: u64,
+    pub used: u64,
+}
+
+/// Usage of the filesystem holding `path`, or `None` when it cannot be read or reports no size.
+pub fn fs_usage(path: &Path) -> Option<FsUsage> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+
+    // `f_fsid` is zero on many filesystems, so the device number tells mounts apart.
+    let device = std::fs::metadata(path).ok()?.dev();
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is NUL-terminated and `stat` is only read after statvfs succeeds.
+    let stat = unsafe {
+        if libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+    let block = stat.f_frsize as u64;
+    let total = stat.f_blocks as u64 * block;
+    if total == 0 {
+        return None;
+    }
+    Some(FsUsage {
+        device,
+        total,
+        available: stat.f_bavail as u64 * block,
+        used: (stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64) * block,
+    })
+}
+
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib = line["VmRSS:".len()..]
+        .split_whitespace()
+        .next()?
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,6 +203,15 @@ mod tests {
         assert_eq!(no_truncate, "ok");
     }
 
+    #[test]
+    fn fs_usage_reads_a_mount_and_skips_missing_paths() {
+        let root = fs_usage(Path::new("/")).expect("root filesystem");
+        assert!(root.used <= root.total && root.available <= root.total);
+        let again = fs_usage(Path::new("/.")).expect("root filesystem");
+        assert_eq!(again.device, root.device);
+        assert!(fs_usage(Path::new("/nonexistent/unixnotis")).is_none());
+    }
+
     #[test]
     fn diagnostic_mode_parses_expected_values() {
         assert!(diagnostic_mode_from(Some("1")));
@@ -151,6 +223,13 @@ mod tests {
         assert!(!diagnostic_mode_from(None));
     }
 
+    #[test]
+    fn parse_vm_rss_reads_kib() {
+        let status = "Name:\tunixnotis\nVmPeak:\t  9000 kB\nVmRSS:\t    2048 kB\n";
+        assert_eq!(parse_vm_rss(status), Some(2048 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tunixnotis\n"), None);
+    }
+
     #[test]
     fn log_limit_respects_mode() {
         assert_eq!(log_limit_for(false), DEFAULT_LOG_LIMIT);
@@ -0,0 +1,338 @@
+//! Low-battery and charger notifications from UPower.
+//!
+//! Watches the UPower display device (the combined state of all laptop batteries) on the
+//! system bus and sends notifications through the daemon's own Notify method, so rules,
+//! popups, and history treat them like any other. The critical warning carries a
+//! "Suspend now" action that asks logind to suspend.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use tracing::{debug, info, warn};
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::{BatteryConfig, BusNames};
+use zbus::zvariant::Value;
+use zbus::{proxy, Connection};
+
+use crate::daemon::DaemonState;
+use crate::notify_proxy::{NotificationsProxy, Notifier};
+
+const SUSPEND_ACTION: &str = "suspend";
+
+#[proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower/devices/DisplayDevice"
+)]
+trait UPowerDevice {
+    #[zbus(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LoginManager {
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+}
+
+/// Start the UPower watcher; call once the notification bus name is owned.
+pub fn spawn_battery_watcher(state: Arc<DaemonState>, names: BusNames, config: BatteryConfig) {
+    if !config.enabled {
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(err) = watch_battery(state, names, config).await {
+            warn!(?err, "battery watcher stopped");
+        }
+    });
+}
+
+async fn watch_battery(
+    state: Arc<DaemonState>,
+    names: BusNames,
+    config: BatteryConfig,
+) -> zbus::Result<()> {
+    let system = Connection::system().await?;
+    let device = UPowerDeviceProxy::new(&system).await?;
+    // Desktops and systems without UPower have nothing to report.
+    match device.is_present().await {
+        Ok(true) => {}
+        Ok(false) => {
+            debug!("no battery present; battery notifications disabled");
+            return Ok(());
+        }
+        Err(err) => {
+            debug!(?err, "UPower unavailable; battery notifications disabled");
+            return Ok(());
+        }
+    }
+    info!(
+        low = config.low_percent,
+        critical = config.critical_percent,
+        "watching battery through UPower"
+    );
+
+    let mut notifier = Notifier::new(&state, names).await?;
+    let mut percentage = device.receive_percentage_changed().await;
+    let mut device_state = device.receive_state_changed().await;
+
+    let mut tracker = BatteryTracker::new(&config);
+    let mut shown: Option<u32> = None;
+    loop {
+        if let Some(reading) = read_device(&device).await {
+            if let Some(alert) = tracker.update(reading) {
+                shown = match notifier.proxy().await {
+                    Ok(notifications) => show_alert(notifications, shown, alert).await,
+                    Err(err) => {
+                        warn!(?err, "failed to send battery notification");
+                        shown
+                    }
+                };
+            }
+        }
+        tokio::select! {
+            Some(_) = percentage.next() => {}
+            Some(_) = device_state.next() => {}
+            Some(signal) = notifier.next_action() => {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+                if Some(args.id) == shown && args.action_key == SUSPEND_ACTION {
+                    suspend(&system).await;
+                }
+            }
+            else => break,
+        }
+    }
+    Ok(())
+}
+
+async fn read_device(device: &UPowerDeviceProxy<'_>) -> Option<Reading> {
+    let percentage = device.percentage().await.ok()?;
+    let discharging = discharging(device.state().await.ok()?)?;
+    Some(Reading {
+        percentage,
+        discharging,
+    })
+}
+
+/// Map UPower's `State` property; unknown states are skipped until the next update.
+fn discharging(state: u32) -> Option<bool> {
+    match state {
+        // Charging, fully charged, pending charge.
+        1 | 4 | 5 => Some(false),
+        // Discharging, empty, pending discharge.
+        2 | 3 | 6 => Some(true),
+        _ => None,
+    }
+}
+
+/// Send `alert`, replacing the previous battery notification, and return the id now shown.
+async fn show_alert(
+    notifications: &NotificationsProxy<'_>,
+    shown: Option<u32>,
+    alert: Alert,
+) -> Option<u32> {
+    let (summary, body, icon, urgency, expire_timeout) = match alert {
+        Alert::Cleared => {
+            if let Some(id) = shown {
+                if let Err(err) = notifications.close_notification(id).await {
+                    debug!(?err, "failed to withdraw battery warning");
+                }
+            }
+            return None;
+        }
+        Alert::Unplugged(percent) => (
+            tr("battery-unplugged-summary"),
+            tr_args("battery-unplugged-body", &[("percent", percent.into())]),
+            "ac-adapter-symbolic",
+            1u8,
+            -1,
+        ),
+        Alert::Low(percent) => (
+            tr("battery-low-summary"),
+            tr_args("battery-low-body", &[("percent", percent.into())]),
+            "battery-low-symbolic",
+            1u8,
+            -1,
+        ),
+        // Zero keeps the critical warning on screen until the charger is plugged in.
+        Alert::Critical(percent) => (
+            tr("battery-critical-summary"),
+            tr_args("battery-critical-body", &[("percent", percent.into())]),
+            "battery-caution-symbolic",
+            2u8,
+            0,
+        ),
+    };
+    let suspend_label = tr("battery-suspend");
+    let actions: Vec<&str> = if matches!(alert, Alert::Critical(_)) {
+        vec![SUSPEND_ACTION, suspend_label.as_str()]
+    } else {
+        Vec::new()
+    };
+    let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+    hints.insert("urgency", Value::U8(urgency));
+    hints.insert("category", Value::from("device"));
+    if matches!(alert, Alert::Unplugged(_)) {
+        hints.insert("transient", Value::Bool(true));
+    }
+    match notifications
+        .notify(
+            "UnixNotis",
+            shown.unwrap_or(0),
+            icon,
+            &summary,
+            &body,
+            &actions,
+            hints,
+            expire_timeout,
+        )
+        .await
+    {
+        Ok(id) => Some(id),
+        Err(err) => {
+            warn!(?err, "failed to send battery notification");
+            shown
+        }
+    }
+}
+
+async fn suspend(system: &Connection) {
+    info!("suspending from the battery warning");
+    let result = match LoginManagerProxy::new(system).await {
+        Ok(manager) => manager.suspend(false).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        warn!(?err, "logind refused to suspend");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Reading {
+    percentage: f64,
+    discharging: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alert {
+    /// The charger was plugged back in; withdraw the outstanding warning.
+    Cleared,
+    Unplugged(u32),
+    Low(u32),
+    Critical(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Normal,
+    Low,
+    Critical,
+}
+
+/// Turns UPower readings into alerts, warning once per level per discharge cycle.
+struct BatteryTracker {
+    low_percent: f64,
+    critical_percent: f64,
+    notify_unplugged: bool,
+    level: Level,
+    /// Last known charger state; `None` until the first reading.
+    discharging: Option<bool>,
+}
+
+impl BatteryTracker {
+    fn new(config: &BatteryConfig) -> Self {
+        Self {
+            low_percent: f64::from(config.low_percent),
+            critical_percent: f64::from(config.critical_percent),
+            notify_unplugged: config.notify_unplugged,
+            level: Level::Normal,
+            discharging: None,
+        }
+    }
+
+    fn update(&mut self, reading: Reading) -> Option<Alert> {
+        let was_discharging = self.discharging.replace(reading.discharging);
+        let percent = reading.percentage.round().clamp(0.0, 100.0) as u32;
+        if !reading.discharging {
+            let warned = self.level != Level::Normal;
+            self.level = Level::Normal;
+            return (was_discharging == Some(true) && (warned || self.notify_unplugged))
+                .then_some(Alert::Cleared);
+        }
+        let level = if reading.percentage <= self.critical_percent {
+            Level::Critical
+        } else if reading.percentage <= self.low_percent {
+            Level::Low
+        } else {
+            Level::Normal
+        };
+        // Only escalate, so a reading hovering around a threshold does not repeat the warning.
+        if level > self.level {
+            self.level = level;
+            return Some(match level {
+                Level::Critical => Alert::Critical(percent),
+                _ => Alert::Low(percent),
+            });
+        }
+        // The first reading after startup is not an unplug event.
+        (was_discharging == Some(false) && self.notify_unplugged)
+            .then_some(Alert::Unplugged(percent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(percentage: f64, discharging: bool) -> Reading {
+        Reading {
+            percentage,
+            discharging,
+        }
+    }
+
+    #[test]
+    fn tracker_warns_once_per_level_and_clears_on_charge() {
+        let mut tracker = BatteryTracker::new(&BatteryConfig::default());
+        assert_eq!(tracker.update(reading(80.0, false)), None);
+        assert_eq!(
+            tracker.update(reading(79.6, true)),
+            Some(Alert::Unplugged(80))
+        );
+        assert_eq!(tracker.update(reading(40.0, true)), None);
+        assert_eq!(tracker.update(reading(15.0, true)), Some(Alert::Low(15)));
+        assert_eq!(tracker.update(reading(15.4, true)), None);
+        assert_eq!(tracker.update(reading(14.0, true)), None);
+        assert_eq!(tracker.update(reading(5.0, true)), Some(Alert::Critical(5)));
+        assert_eq!(tracker.update(reading(4.0, true)), None);
+        assert_eq!(tracker.update(reading(4.0, false)), Some(Alert::Cleared));
+        assert_eq!(tracker.update(reading(4.0, true)), Some(Alert::Critical(4)));
+    }
+
+    #[test]
+    fn tracker_warns_at_startup_without_unplug_notice() {
+        let config = BatteryConfig {
+            notify_unplugged: false,
+            ..BatteryConfig::default()
+        };
+        let mut tracker = BatteryTracker::new(&config);
+        assert_eq!(tracker.update(reading(10.0, true)), Some(Alert::Low(10)));
+
+        let mut tracker = BatteryTracker::new(&config);
+        assert_eq!(tracker.update(reading(50.0, false)), None);
+        assert_eq!(tracker.update(reading(50.0, true)), None);
+        assert_eq!(tracker.update(reading(50.0, false)), None);
+    }
+}
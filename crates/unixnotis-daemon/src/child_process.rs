@@ -10,14 +10,15 @@ use std::time::{Duration, Instant};
 use anyhow::{anyhow, Result};
 use tokio::time::sleep;
 use tracing::warn;
+use unixnotis_core::{BusNames, BUS_SUFFIX_ENV};
 
 use super::Args;
 
 #[cfg(target_os = "linux")]
 use std::os::unix::process::CommandExt;
 
-pub(super) fn start_popups_process(args: &Args) -> Result<Option<Child>> {
-    let Some(mut command) = build_popups_command(args)? else {
+pub(super) fn start_popups_process(args: &Args, names: &BusNames) -> Result<Option<Child>> {
+    let Some(mut command) = build_popups_command(args, names)? else {
         return Ok(None);
     };
     // Spawn the popup UI as a child process so resource usage is attributed correctly.
@@ -34,8 +35,8 @@ pub(super) async fn stop_popups_process(child: &mut Child) {
     terminate_child(child, "unixnotis-popups").await;
 }
 
-pub(super) fn start_center_process(args: &Args) -> Result<Option<Child>> {
-    let Some(mut command) = build_center_command(args)? else {
+pub(super) fn start_center_process(args: &Args, names: &BusNames) -> Result<Option<Child>> {
+    let Some(mut command) = build_center_command(args, names)? else {
         return Ok(None);
     };
     // Spawn the panel UI as a child process so resource usage is attributed correctly.
@@ -55,6 +56,14 @@ pub(super) async fn stop_center_process(child: &mut Child) {
     terminate_child(child, "unixnotis-center").await;
 }
 
+/// Keep the daemon running when a UI fails to come back; it is only missing until restart.
+pub(super) fn restart_ui(started: Result<Option<Child>>, label: &str) -> Option<Child> {
+    started.unwrap_or_else(|err| {
+        warn!(?err, process = label, "failed to restart UI process");
+        None
+    })
+}
+
 async fn terminate_child(child: &mut Child, label: &str) {
     let pid = child.id();
     #[cfg(unix)]
@@ -82,7 +91,7 @@ async fn terminate_child(child: &mut Child, label: &str) {
     let _ = child.wait();
 }
 
-fn build_popups_command(args: &Args) -> Result<Option<Command>> {
+fn build_popups_command(args: &Args, names: &BusNames) -> Result<Option<Command>> {
     let mut command = if let Some(path) = resolve_popups_path() {
         Command::new(path)
     } else {
@@ -94,6 +103,7 @@ fn build_popups_command(args: &Args) -> Result<Option<Command>> {
     if let Some(config) = args.config.as_ref() {
         command.arg("--config").arg(config);
     }
+    apply_bus_suffix(&mut command, names);
 
     Ok(Some(command))
 }
@@ -112,7 +122,7 @@ fn resolve_popups_path() -> Option<PathBuf> {
     None
 }
 
-fn build_center_command(args: &Args) -> Result<Option<Command>> {
+fn build_center_command(args: &Args, names: &BusNames) -> Result<Option<Command>> {
     let mut command = if let Some(path) = resolve_center_path() {
         Command::new(path)
     } else {
@@ -124,10 +134,23 @@ fn build_center_command(args: &Args) -> Result<Option<Command>> {
     if let Some(config) = args.config.as_ref() {
         command.arg("--config").arg(config);
     }
+    apply_bus_suffix(&mut command, names);
 
     Ok(Some(command))
 }
 
+fn apply_bus_suffix(command: &mut Command, names: &BusNames) {
+    // The env var also reaches anything the UI launches, such as noticenterctl in card commands.
+    match names.suffix() {
+        Some(suffix) => {
+            command.env(BUS_SUFFIX_ENV, suffix);
+        }
+        None => {
+            command.env_remove(BUS_SUFFIX_ENV);
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn apply_parent_death_signal(command: &mut Command) {
     // Ensure UI subprocesses exit if the daemon dies unexpectedly.
@@ -0,0 +1,257 @@
+//! Authorization for control methods that change state (`[security.control]`).
+//!
+//! Callers are identified by the executable behind their bus connection, looked up from the
+//! PID the bus reports. The panel, popups, and noticenterctl installed next to the daemon are
+//! always allowed. Clearing and rule changes can additionally require a polkit authorization.
+//! With `audit` on, every call that reaches the policy is logged and kept for `ListAudit`.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tokio::sync::{Mutex, OnceCell};
+use tracing::{debug, info, warn};
+use unixnotis_core::{AuditEntry, ControlSecurityConfig};
+use zbus::fdo::{self, DBusProxy};
+use zbus::message::Header;
+use zbus::names::BusName;
+use zbus::zvariant::Value;
+use zbus::{proxy, Connection};
+
+/// Unique names are never reused, so cached lookups stay valid; the cap only bounds memory.
+const EXE_CACHE_LIMIT: usize = 256;
+/// Audit entries kept for `ListAudit`; older ones are only in the log.
+const AUDIT_LIMIT: usize = 200;
+/// Let polkit prompt the user instead of failing outright.
+const POLKIT_ALLOW_INTERACTION: u32 = 1;
+/// Companion binaries trusted when they sit in the daemon's own directory.
+const COMPANIONS: [&str; 3] = ["unixnotis-center", "unixnotis-popups", "noticenterctl"];
+
+#[proxy(
+    interface = "org.freedesktop.PolicyKit1.Authority",
+    default_service = "org.freedesktop.PolicyKit1",
+    default_path = "/org/freedesktop/PolicyKit1/Authority"
+)]
+trait Authority {
+    fn check_authorization(
+        &self,
+        subject: &(&str, HashMap<&str, Value<'_>>),
+        action_id: &str,
+        details: HashMap<&str, &str>,
+        flags: u32,
+        cancellation_id: &str,
+    ) -> zbus::Result<(bool, bool, HashMap<String, String>)>;
+}
+
+/// How much a control method can change.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Access {
+    /// Panel, DND, dismissing, and other reversible changes.
+    Change,
+    /// Clearing notifications and editing rules.
+    Destructive,
+}
+
+pub struct ControlPolicy {
+    allowed: Vec<PathBuf>,
+    polkit_action: Option<String>,
+    exe_cache: Mutex<HashMap<String, Option<PathBuf>>>,
+    system: OnceCell<Connection>,
+    audit: Option<Mutex<VecDeque<AuditEntry>>>,
+}
+
+impl ControlPolicy {
+    pub fn new(config: &ControlSecurityConfig) -> Self {
+        let mut allowed: Vec<PathBuf> = config
+            .allowed_executables
+            .iter()
+            .map(|path| canonical(Path::new(path.trim())))
+            .collect();
+        if !allowed.is_empty() {
+            if let Some(dir) = std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(canonical))
+            {
+                allowed.extend(COMPANIONS.iter().map(|name| dir.join(name)));
+            }
+        }
+        Self {
+            allowed,
+            polkit_action: config
+                .polkit
+                .then(|| config.polkit_action.trim().to_string())
+                .filter(|action| !action.is_empty()),
+            exe_cache: Mutex::new(HashMap::new()),
+            system: OnceCell::new(),
+            audit: config.audit.then(|| Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Audited calls, oldest first.
+    pub async fn audit_entries(&self) -> Vec<AuditEntry> {
+        match &self.audit {
+            Some(audit) => audit.lock().await.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Refuse the call unless the policy lets its sender perform `method`.
+    pub async fn authorize(
+        &self,
+        connection: &Connection,
+        header: &Header<'_>,
+        access: Access,
+        method: &str,
+    ) -> fdo::Result<()> {
+        let checks_polkit = access == Access::Destructive && self.polkit_action.is_some();
+        if self.allowed.is_empty() && !checks_polkit && self.audit.is_none() {
+            return Ok(());
+        }
+        let sender = header
+            .sender()
+            .ok_or_else(|| fdo::Error::AccessDenied("caller has no bus name".to_string()))?;
+        let pid = DBusProxy::new(connection)
+            .await?
+            .get_connection_unix_process_id(BusName::from(sender.clone()))
+            .await;
+        let (pid, result) = match pid {
+            Ok(pid) => (pid, self.check(sender.as_str(), pid, access, method).await),
+            // Only auditing needs the PID here, and a missing one should not refuse the call.
+            Err(_) if self.allowed.is_empty() && !checks_polkit => (0, Ok(())),
+            Err(err) => (0, Err(err)),
+        };
+        if let Some(audit) = &self.audit {
+            let entry = AuditEntry {
+                at_unix_ms: chrono::Utc::now().timestamp_millis(),
+                method: method.to_string(),
+                sender: sender.to_string(),
+                pid,
+                comm: process_comm(pid).unwrap_or_default(),
+                allowed: result.is_ok(),
+            };
+            info!(
+                method,
+                sender = %entry.sender,
+                pid,
+                comm = %entry.comm,
+                allowed = entry.allowed,
+                "control call"
+            );
+            let mut audit = audit.lock().await;
+            if audit.len() >= AUDIT_LIMIT {
+                audit.pop_front();
+            }
+            audit.push_back(entry);
+        }
+        result
+    }
+
+    async fn check(&self, sender: &str, pid: u32, access: Access, method: &str) -> fdo::Result<()> {
+        if !self.allowed.is_empty() {
+            let exe = self.executable(sender, pid).await;
+            let permitted = exe
+                .as_deref()
+                .is_some_and(|exe| self.allowed.iter().any(|allowed| allowed == exe));
+            if !permitted {
+                let exe =
+                    exe.map_or_else(|| "unknown".to_string(), |exe| exe.display().to_string());
+                warn!(method, %exe, "control call refused by allowed_executables");
+                return Err(fdo::Error::AccessDenied(format!(
+                    "{method} is not allowed for {exe}"
+                )));
+            }
+        }
+        if let (Access::Destructive, Some(action)) = (access, self.polkit_action.as_deref()) {
+            self.check_polkit(pid, action, method).await?;
+        }
+        Ok(())
+    }
+
+    async fn executable(&self, sender: &str, pid: u32) -> Option<PathBuf> {
+        let mut cache = self.exe_cache.lock().await;
+        if let Some(exe) = cache.get(sender) {
+            return exe.clone();
+        }
+        let exe = fs::read_link(format!("/proc/{pid}/exe")).ok();
+        if cache.len() >= EXE_CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert(sender.to_string(), exe.clone());
+        exe
+    }
+
+    async fn check_polkit(&self, pid: u32, action: &str, method: &str) -> fdo::Result<()> {
+        let start_time = process_start_time(pid).ok_or_else(|| {
+            fdo::Error::AccessDenied(format!("cannot identify the process calling {method}"))
+        })?;
+        let system = self
+            .system
+            .get_or_try_init(Connection::system)
+            .await
+            .map_err(|err| fdo::Error::AccessDenied(format!("polkit unavailable: {err}")))?;
+        let authority = AuthorityProxy::new(system).await?;
+        let subject = (
+            "unix-process",
+            HashMap::from([
+                ("pid", Value::U32(pid)),
+                ("start-time", Value::U64(start_time)),
+            ]),
+        );
+        let (authorized, _, _) = authority
+            .check_authorization(
+                &subject,
+                action,
+                HashMap::new(),
+                POLKIT_ALLOW_INTERACTION,
+                "",
+            )
+            .await
+            .map_err(|err| {
+                fdo::Error::AccessDenied(format!("polkit check for {action} failed: {err}"))
+            })?;
+        if authorized {
+            debug!(method, action, "polkit authorized control call");
+            Ok(())
+        } else {
+            warn!(method, action, "control call refused by polkit");
+            Err(fdo::Error::AccessDenied(format!(
+                "{method} needs polkit authorization for {action}"
+            )))
+        }
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn process_comm(pid: u32) -> Option<String> {
+    if pid == 0 {
+        return None;
+    }
+    let comm = fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    Some(comm.trim_end().to_string())
+}
+
+/// Process start time in clock ticks (field 22 of `/proc/PID/stat`), as polkit expects.
+fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    parse_start_time(&stat)
+}
+
+fn parse_start_time(stat: &str) -> Option<u64> {
+    // The command name may contain spaces and parentheses, so count fields after its end.
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_time_skips_command_names_with_spaces() {
+        let stat = "4242 (my (odd) app) S 1 4242 4242 0 -1 4194560 100 0 0 0 1 2 0 0 20 0 1 0 987654 1000 10";
+        assert_eq!(parse_start_time(stat), Some(987654));
+    }
+}
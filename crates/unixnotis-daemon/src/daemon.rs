@@ -1,57 +1,167 @@
 //! D-Bus server implementation and daemon state coordination.
 
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use futures_util::stream::{FuturesUnordered, StreamExt};
-use tokio::sync::Mutex;
-use tracing::{debug, info};
+use tokio::sync::{watch, Mutex, Notify};
+use tokio::task::AbortHandle;
+use tracing::{debug, debug_span, info, warn, Instrument};
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::popup_positions::PopupPositions;
 use unixnotis_core::{
-    Action, CloseReason, Config, Notification, NotificationImage, NotificationView,
-    PanelDebugLevel, PanelRequest, Urgency, CONTROL_BUS_NAME, CONTROL_OBJECT_PATH,
+    progress_from_hint, Action, Anchor, AuditEntry, BusNames, ClearScope, CloseReason, Config,
+    ImageLimits, MemoryReport, Notification, NotificationImage, NotificationView, OsdConfig,
+    OsdKind, PanelDebugLevel, PanelGeometry, PanelRequest, PanelTarget, PopupConfig, RuleConfig,
+    RuleDict, SenderExit, Urgency, CLEAR_UNDO_SECONDS, CONTROL_OBJECT_PATH, INLINE_REPLY_ACTION,
 };
-use zbus::fdo::{RequestNameFlags, RequestNameReply};
-use zbus::zvariant::OwnedValue;
+use zbus::fdo::{DBusProxy, RequestNameFlags, RequestNameReply};
+use zbus::message::Header;
+use zbus::names::{BusName, UniqueName};
+use zbus::zvariant::{OwnedValue, Value};
 use zbus::{interface, Connection, SignalContext};
 
-use crate::expire::ExpirationScheduler;
+use crate::control_policy::{Access, ControlPolicy};
+use crate::dbus_owner::wait_for_owner_gone;
+use crate::expire::{schedule_dnd_end, spawn_history_pruner, ExpirationScheduler};
+use crate::inhibit::{self, InhibitReason};
+use crate::latency::LatencyProbe;
+use crate::media_players::spawn_player_watcher;
 use crate::sound::SoundSettings;
 use crate::store::NotificationStore;
 
-const NOTIFICATIONS_OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+pub const NOTIFICATIONS_OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+/// UI processes expected to answer a memory report request (center and popups).
+const UI_MEMORY_PROCESSES: usize = 2;
+/// Upper bound on how long a memory report waits for UI processes to answer.
+const UI_REPORT_WAIT: Duration = Duration::from_millis(500);
+/// On-screen time of the notice shown when an action's app has exited.
+const SENDER_GONE_TOAST_MS: i32 = 4000;
+/// Notifications added within this window of the first one go out as one batch signal.
+const ADDED_BATCH_WINDOW: Duration = Duration::from_millis(50);
+/// Hints OSD scripts use to replace their previous notification; the value names the level.
+const OSD_TAG_HINTS: [&str; 3] = [
+    "x-canonical-private-synchronous",
+    "x-dunst-stack-tag",
+    "synchronous",
+];
 
 /// Shared daemon state guarded behind an async mutex.
 pub struct DaemonState {
     pub store: Mutex<NotificationStore>,
     /// Immutable sound settings resolved at startup.
     pub sound: SoundSettings,
-    connection: Connection,
+    /// Session bus connection, swapped for a fresh one when the bus restarts.
+    connection: watch::Sender<Connection>,
+    names: BusNames,
+    /// Config file that rules changed over D-Bus are saved to; `None` keeps them in memory.
+    config_path: Option<PathBuf>,
+    /// Serializes rule saves so an older rule list never overwrites a newer one.
+    rules_save: Mutex<()>,
+    /// Per-app popup anchors; held across saves so the file never goes back to older ones.
+    popup_positions: Mutex<PopupPositions>,
+    /// Where `popup_positions` is saved; `None` when rules are not saved either.
+    positions_path: Option<PathBuf>,
+    history_pruner_started: AtomicBool,
+    /// Latest UI self-reports keyed by process role, with their arrival time.
+    ui_reports: Mutex<HashMap<String, (Instant, MemoryReport)>>,
+    ui_report_notify: Notify,
+    added_batch: Mutex<AddedBatch>,
+    control_policy: ControlPolicy,
+    /// Popup latency samples, filled only while `general.measure_latency` is on.
+    latency: Mutex<LatencyProbe>,
+    /// Whether a client asked for panel debug lines and is still on the bus.
+    debug_followed: AtomicBool,
+    /// Waits for that client to leave the bus.
+    debug_follower: TaskSlot,
+    /// Watchers that config reloads and bus restarts start, replace, or stop.
+    pub hyprland_watch: TaskSlot,
+    pub screensaver: TaskSlot,
+    pub player_watcher: TaskSlot,
+}
+
+/// A background task that is replaced rather than duplicated when it is started again.
+#[derive(Default)]
+pub struct TaskSlot(std::sync::Mutex<Option<AbortHandle>>);
+
+impl TaskSlot {
+    /// Abort the running task, if any, and keep `task` in its place.
+    pub fn replace(&self, task: Option<AbortHandle>) {
+        let previous = std::mem::replace(
+            &mut *self.0.lock().unwrap_or_else(|err| err.into_inner()),
+            task,
+        );
+        if let Some(previous) = previous {
+            previous.abort();
+        }
+    }
+}
+
+/// Additions held back while a batch window is open.
+#[derive(Default)]
+struct AddedBatch {
+    pending: Vec<(NotificationView, bool)>,
+    window_open: bool,
 }
 
 impl DaemonState {
-    pub fn new(connection: Connection, config: Config, sound: SoundSettings) -> Arc<Self> {
+    pub fn new(
+        connection: Connection,
+        names: BusNames,
+        config: Config,
+        config_path: Option<PathBuf>,
+        sound: SoundSettings,
+    ) -> Arc<Self> {
+        let control_policy = ControlPolicy::new(&config.security.control);
         let store = NotificationStore::new(config);
+        let positions_path = config_path
+            .as_ref()
+            .and_then(|_| PopupPositions::default_path());
+        let popup_positions = positions_path
+            .as_deref()
+            .map(PopupPositions::load)
+            .unwrap_or_default();
         Arc::new(Self {
             store: Mutex::new(store),
             sound,
-            connection,
+            connection: watch::Sender::new(connection),
+            names,
+            config_path,
+            rules_save: Mutex::new(()),
+            popup_positions: Mutex::new(popup_positions),
+            positions_path,
+            history_pruner_started: AtomicBool::new(false),
+            ui_reports: Mutex::new(HashMap::new()),
+            ui_report_notify: Notify::new(),
+            added_batch: Mutex::new(AddedBatch::default()),
+            control_policy,
+            latency: Mutex::new(LatencyProbe::default()),
+            debug_followed: AtomicBool::new(false),
+            debug_follower: TaskSlot::default(),
+            hyprland_watch: TaskSlot::default(),
+            screensaver: TaskSlot::default(),
+            player_watcher: TaskSlot::default(),
         })
     }
 
     pub async fn close_notification(&self, id: u32, reason: CloseReason) -> zbus::Result<()> {
         let removed = {
             let mut store = self.store.lock().await;
-            store.close(id)
+            store.close(id, reason)
         };
         if removed.is_none() {
             return Ok(());
         }
 
-        let notif_ctx = SignalContext::new(&self.connection, NOTIFICATIONS_OBJECT_PATH)?;
-        NotificationServer::notification_closed(&notif_ctx, id, reason as u32).await?;
+        let notif_ctx = SignalContext::new(&self.connection(), NOTIFICATIONS_OBJECT_PATH)?;
+        NotificationServer::notification_closed(&notif_ctx, id, reason.spec_code()).await?;
 
-        let control_ctx = SignalContext::new(&self.connection, CONTROL_OBJECT_PATH)?;
+        self.flush_added().await?;
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
         ControlServer::notification_closed(&control_ctx, id, reason).await?;
         self.emit_state_changed().await?;
 
@@ -69,7 +179,7 @@ impl DaemonState {
         }
 
         if outcome.removed_active {
-            let notif_ctx = SignalContext::new(&self.connection, NOTIFICATIONS_OBJECT_PATH)?;
+            let notif_ctx = SignalContext::new(&self.connection(), NOTIFICATIONS_OBJECT_PATH)?;
             NotificationServer::notification_closed(
                 &notif_ctx,
                 id,
@@ -78,28 +188,445 @@ impl DaemonState {
             .await?;
         }
 
-        let control_ctx = SignalContext::new(&self.connection, CONTROL_OBJECT_PATH)?;
+        self.flush_added().await?;
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
         ControlServer::notification_closed(&control_ctx, id, CloseReason::DismissedByUser).await?;
         self.emit_state_changed().await?;
 
         Ok(())
     }
 
-    async fn emit_state_changed(&self) -> zbus::Result<()> {
+    /// Apply `general.sender_exit` to the active notifications from `sender`, which left the bus.
+    pub async fn sender_departed(&self, sender: &str) -> zbus::Result<()> {
+        // Read per departure so a reload that changes `sender_exit` applies right away.
+        let (mode, updated) = {
+            let mut store = self.store.lock().await;
+            let mode = store.config().general.sender_exit;
+            if mode == SenderExit::Keep {
+                return Ok(());
+            }
+            (mode, store.strip_sender_actions(sender))
+        };
+        if updated.is_empty() {
+            return Ok(());
+        }
+        debug!(
+            sender,
+            count = updated.len(),
+            ?mode,
+            "notification sender left the bus"
+        );
+        if mode == SenderExit::Close {
+            for notification in updated {
+                self.close_notification(notification.id, CloseReason::SenderGone)
+                    .await?;
+            }
+            return Ok(());
+        }
+        self.flush_added().await?;
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
+        for notification in updated {
+            // No popup: it is taken down rather than shown again without its buttons.
+            ControlServer::notification_updated(&control_ctx, notification.to_view(), false)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Tell clients and the UIs that the active limit pushed these notifications out.
+    async fn announce_evicted(&self, evicted: Vec<u32>) -> zbus::Result<()> {
+        if evicted.is_empty() {
+            return Ok(());
+        }
+        let notif_ctx = SignalContext::new(&self.connection(), NOTIFICATIONS_OBJECT_PATH)?;
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
+        debug!(count = evicted.len(), "active limit evicted notifications");
+        self.flush_added().await?;
+        for id in evicted {
+            NotificationServer::notification_closed(
+                &notif_ctx,
+                id,
+                CloseReason::Evicted.spec_code(),
+            )
+            .await?;
+            ControlServer::notification_closed(&control_ctx, id, CloseReason::Evicted).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply a reloaded config to the store and announce whatever its limits pushed out.
+    pub async fn reload_config(self: &Arc<Self>, config: Config) -> zbus::Result<()> {
+        if uses_history_ttl(&config) {
+            self.ensure_history_pruner();
+        }
+        let general = config.general.clone();
+        let suppress_players = config.media.suppress_player_notifications;
+        let (outcome, previous) = {
+            let mut store = self.store.lock().await;
+            let previous = store.config().clone();
+            (store.apply_config(config), previous)
+        };
+        inhibit::reload_suppression(self, &previous.general, &general).await;
+        if suppress_players != previous.media.suppress_player_notifications {
+            if suppress_players {
+                spawn_player_watcher(self.clone());
+            } else {
+                self.player_watcher.replace(None);
+                self.set_player_apps(Vec::new()).await;
+            }
+        }
+        info!(
+            evicted = outcome.evicted.len(),
+            dropped = outcome.dropped.len(),
+            "config reloaded"
+        );
+        self.announce_evicted(outcome.evicted).await?;
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
+        for id in outcome.dropped {
+            ControlServer::notification_closed(&control_ctx, id, CloseReason::Pruned).await?;
+        }
+        // The file may carry rule edits made outside the panel.
+        ControlServer::rules_changed(&control_ctx).await?;
+        self.emit_state_changed().await
+    }
+
+    /// Drop history entries past their rule TTL or the maximum age and tell the UIs.
+    pub async fn prune_history(&self) -> zbus::Result<()> {
+        let pruned = {
+            let mut store = self.store.lock().await;
+            store.prune_history(Instant::now(), Utc::now())
+        };
+        if pruned.is_empty() {
+            return Ok(());
+        }
+        debug!(
+            count = pruned.len(),
+            "pruned history entries past their ttl or max age"
+        );
+        self.flush_added().await?;
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
+        for id in pruned {
+            ControlServer::notification_closed(&control_ctx, id, CloseReason::Pruned).await?;
+        }
+        self.emit_state_changed().await
+    }
+
+    /// Remove what `scope` covers, keeping it for `undo_clear` until the undo window closes.
+    ///
+    /// Returns the IDs removed from both lists; empty when there was nothing to clear.
+    pub async fn clear(self: &Arc<Self>, scope: ClearScope) -> zbus::Result<Vec<u32>> {
+        // Drain active notifications in one lock to avoid quadratic scans.
+        let undo_window = Duration::from_secs(CLEAR_UNDO_SECONDS);
+        let outcome = {
+            let mut store = self.store.lock().await;
+            store.clear(scope, Instant::now() + undo_window)
+        };
+        if !outcome.removed_any() {
+            return Ok(Vec::new());
+        }
+        // Free the undo buffer once the window closes instead of waiting for the next clear.
+        let state = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(undo_window).await;
+            state.store.lock().await.discard_cleared(Instant::now());
+        });
+        let mut removed = outcome.active.clone();
+        removed.extend(&outcome.history);
+        if outcome.active.is_empty() {
+            self.emit_state_changed().await?;
+            return Ok(removed);
+        }
+        self.flush_added().await?;
+        let notif_ctx = SignalContext::new(&self.connection(), NOTIFICATIONS_OBJECT_PATH)?;
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
+        // Emit close signals concurrently to avoid blocking on large clears.
+        let mut tasks = FuturesUnordered::new();
+        for id in outcome.active {
+            let notif_ctx = notif_ctx.clone();
+            let control_ctx = control_ctx.clone();
+            tasks.push(async move {
+                NotificationServer::notification_closed(
+                    &notif_ctx,
+                    id,
+                    CloseReason::DismissedByUser as u32,
+                )
+                .await?;
+                ControlServer::notification_closed(&control_ctx, id, CloseReason::DismissedByUser)
+                    .await?;
+                Ok::<(), zbus::Error>(())
+            });
+        }
+        while let Some(result) = tasks.next().await {
+            result?;
+        }
+        self.emit_state_changed().await?;
+        Ok(removed)
+    }
+
+    /// End the timed Do Not Disturb period that was scheduled to stop at `until`.
+    pub async fn end_timed_dnd(&self, until: DateTime<Utc>) -> zbus::Result<()> {
+        let ended = {
+            let mut store = self.store.lock().await;
+            store.expire_dnd(until, Utc::now())
+        };
+        if !ended {
+            return Ok(());
+        }
+        debug!("timed do not disturb ended");
+        self.emit_state_changed().await
+    }
+
+    /// Announce a new notification to the UIs.
+    ///
+    /// The first addition goes out at once and opens a short window; anything added while it is
+    /// open is sent together when it closes, so a burst costs the UIs one rebuild.
+    async fn announce_added(
+        self: &Arc<Self>,
+        notification: NotificationView,
+        show_popup: bool,
+    ) -> zbus::Result<()> {
+        let mut batch = self.added_batch.lock().await;
+        if batch.window_open {
+            batch.pending.push((notification, show_popup));
+            return Ok(());
+        }
+        batch.window_open = true;
+        let state = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(ADDED_BATCH_WINDOW).await;
+            if let Err(err) = state.flush_added().await {
+                warn!(?err, "failed to emit batched notifications");
+            }
+        });
+        // Emitted under the batch lock so a concurrent flush cannot overtake it.
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
+        ControlServer::notification_added(&control_ctx, notification, show_popup).await
+    }
+
+    /// Send held-back additions now and close the batch window.
+    ///
+    /// Runs before any close or update signal so UIs never hear about a notification
+    /// they have not been told was added.
+    async fn flush_added(&self) -> zbus::Result<()> {
+        let mut batch = self.added_batch.lock().await;
+        batch.window_open = false;
+        let pending = std::mem::take(&mut batch.pending);
+        self.emit_added(pending).await
+    }
+
+    async fn emit_added(&self, mut added: Vec<(NotificationView, bool)>) -> zbus::Result<()> {
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
+        match added.len() {
+            0 => Ok(()),
+            1 => {
+                let (notification, show_popup) = added.remove(0);
+                ControlServer::notification_added(&control_ctx, notification, show_popup).await
+            }
+            count => {
+                debug!(count, "emitting batched notification_added");
+                ControlServer::notifications_added_batch(&control_ctx, added).await
+            }
+        }
+    }
+
+    pub async fn emit_state_changed(&self) -> zbus::Result<()> {
         let state = {
             let store = self.store.lock().await;
-            let history_count = store.history_len() as u32;
-            unixnotis_core::ControlState {
-                dnd_enabled: store.dnd_enabled(),
-                history_count,
-            }
+            store.control_state()
         };
-        let control_ctx = SignalContext::new(&self.connection, CONTROL_OBJECT_PATH)?;
+        let control_ctx = SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH)?;
         ControlServer::state_changed(&control_ctx, state).await
     }
 
-    fn connection(&self) -> &Connection {
-        &self.connection
+    /// Refuse control calls that `[security.control]` does not allow.
+    async fn authorize(
+        &self,
+        header: &Header<'_>,
+        access: Access,
+        method: &str,
+    ) -> zbus::fdo::Result<()> {
+        self.control_policy
+            .authorize(&self.connection(), header, access, method)
+            .await
+    }
+
+    /// Start or stop holding back notifications for `reason`, announcing any change.
+    pub async fn set_inhibited(&self, reason: InhibitReason, active: bool) -> zbus::Result<()> {
+        let changed = self.store.lock().await.set_inhibited(reason, active);
+        if !changed {
+            return Ok(());
+        }
+        info!(
+            reason = reason.as_str(),
+            active, "notification suppression changed"
+        );
+        self.emit_state_changed().await
+    }
+
+    /// Rebroadcast panel debug lines while `sender` stays on the bus; `Off` stops them.
+    fn follow_debug_log(self: &Arc<Self>, level: PanelDebugLevel, sender: Option<&UniqueName<'_>>) {
+        let following = level != PanelDebugLevel::Off;
+        let task = match sender {
+            Some(sender) if following => {
+                let state = Arc::downgrade(self);
+                let connection = self.connection();
+                let sender = BusName::from(sender.to_owned());
+                let task = tokio::spawn(async move {
+                    if let Err(err) = wait_for_owner_gone(&connection, sender).await {
+                        debug!(?err, "lost track of the panel debug follower");
+                    }
+                    if let Some(state) = state.upgrade() {
+                        state.debug_followed.store(false, Ordering::SeqCst);
+                    }
+                });
+                Some(task.abort_handle())
+            }
+            _ => None,
+        };
+        self.debug_follower.replace(task);
+        self.debug_followed.store(following, Ordering::SeqCst);
+    }
+
+    pub fn connection(&self) -> Connection {
+        self.connection.borrow().clone()
+    }
+
+    /// The current connection, updated each time the bus restarts.
+    pub fn watch_connection(&self) -> watch::Receiver<Connection> {
+        self.connection.subscribe()
+    }
+
+    /// Send signals and the daemon's own notices over `connection` from now on.
+    pub fn replace_connection(&self, connection: Connection) {
+        // The old connection is already dead; dropping it closes what is left of the socket.
+        drop(self.connection.send_replace(connection));
+    }
+
+    /// Start the history pruner unless it already runs.
+    pub fn ensure_history_pruner(self: &Arc<Self>) {
+        if !self.history_pruner_started.swap(true, Ordering::Relaxed) {
+            spawn_history_pruner(self.clone());
+        }
+    }
+
+    /// Announce a rule change, then write the current rules to the config file.
+    async fn rules_updated(self: &Arc<Self>) -> zbus::fdo::Result<()> {
+        let ctx =
+            SignalContext::new(&self.connection(), CONTROL_OBJECT_PATH).map_err(to_fdo_error)?;
+        ControlServer::rules_changed(&ctx)
+            .await
+            .map_err(to_fdo_error)?;
+        let Some(path) = self.config_path.clone() else {
+            return Ok(());
+        };
+        let _guard = self.rules_save.lock().await;
+        let rules = {
+            let store = self.store.lock().await;
+            store.rules().to_vec()
+        };
+        let result = tokio::task::spawn_blocking(move || Config::write_rules(&path, &rules)).await;
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(err)) => {
+                warn!(?err, "failed to save rules");
+                Err(zbus::fdo::Error::Failed(format!(
+                    "rule is active but was not saved: {err}"
+                )))
+            }
+            Err(err) => Err(zbus::fdo::Error::Failed(err.to_string())),
+        }
+    }
+
+    /// App name of notification `id` when the client that sent it is no longer on the bus.
+    async fn departed_sender_app(&self, id: u32) -> Option<String> {
+        let notification = {
+            let store = self.store.lock().await;
+            store.find(id)?
+        };
+        let sender = BusName::try_from(notification.sender.as_deref()?).ok()?;
+        let dbus = DBusProxy::new(&self.connection()).await.ok()?;
+        match dbus.name_has_owner(sender).await {
+            Ok(false) => Some(notification.app_name.clone()),
+            _ => None,
+        }
+    }
+
+    /// Show a short notice that an action went nowhere because its app exited.
+    fn spawn_sender_gone_toast(&self, app_name: String) {
+        let connection = self.connection();
+        let names = self.names.clone();
+        // Spawned so the action call returns without waiting on our own Notify.
+        tokio::spawn(async move {
+            let summary = tr_args("action-app-gone-summary", &[("app", app_name.into())]);
+            let body = tr("action-app-gone-body");
+            let actions: Vec<&str> = Vec::new();
+            let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+            hints.insert("urgency", Value::U8(Urgency::Low.as_u8()));
+            hints.insert("transient", Value::Bool(true));
+            let result = match names.notifications_proxy(&connection).await {
+                Ok(proxy) => {
+                    proxy
+                        .notify(
+                            "UnixNotis",
+                            0,
+                            "dialog-information-symbolic",
+                            &summary,
+                            &body,
+                            &actions,
+                            hints,
+                            SENDER_GONE_TOAST_MS,
+                        )
+                        .await
+                }
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                debug!(?err, "failed to show sender-gone notice");
+            }
+        });
+    }
+
+    /// Replace the generated suppression rules with ones for these player app names.
+    pub async fn set_player_apps(&self, apps: Vec<String>) {
+        let mut store = self.store.lock().await;
+        store.set_player_apps(apps);
+    }
+
+    async fn record_ui_report(&self, report: MemoryReport) {
+        let mut reports = self.ui_reports.lock().await;
+        reports.insert(report.process.clone(), (Instant::now(), report));
+        drop(reports);
+        self.ui_report_notify.notify_waiters();
+    }
+
+    /// Wait for UI reports newer than `since`, giving up after a short deadline so a
+    /// missing frontend does not stall the caller.
+    async fn collect_ui_reports(&self, since: Instant) -> Vec<MemoryReport> {
+        let deadline = tokio::time::sleep(UI_REPORT_WAIT);
+        tokio::pin!(deadline);
+        loop {
+            // Register interest before checking so a report landing in between is not missed.
+            let notified = self.ui_report_notify.notified();
+            let fresh = self.fresh_ui_reports(since).await;
+            if fresh.len() >= UI_MEMORY_PROCESSES {
+                return fresh;
+            }
+            tokio::select! {
+                _ = notified => {}
+                _ = &mut deadline => return self.fresh_ui_reports(since).await,
+            }
+        }
+    }
+
+    async fn fresh_ui_reports(&self, since: Instant) -> Vec<MemoryReport> {
+        let reports = self.ui_reports.lock().await;
+        let mut fresh: Vec<MemoryReport> = reports
+            .values()
+            .filter(|(received, _)| *received >= since)
+            .map(|(_, report)| report.clone())
+            .collect();
+        fresh.sort_by(|a, b| a.process.cmp(&b.process));
+        fresh
     }
 }
 
@@ -118,11 +645,13 @@ impl NotificationServer {
 /// D-Bus server for com.unixnotis.Control.
 pub struct ControlServer {
     state: Arc<DaemonState>,
+    /// Re-arms timeouts for notifications brought back by `undo_clear`.
+    scheduler: ExpirationScheduler,
 }
 
 impl ControlServer {
-    pub fn new(state: Arc<DaemonState>) -> Self {
-        Self { state }
+    pub fn new(state: Arc<DaemonState>, scheduler: ExpirationScheduler) -> Self {
+        Self { state, scheduler }
     }
 }
 
@@ -134,6 +663,7 @@ impl NotificationServer {
             "body".to_string(),
             "body-markup".to_string(),
             "icon-static".to_string(),
+            INLINE_REPLY_ACTION.to_string(),
         ];
         if self.state.sound.supports_sound() {
             caps.push("sound".to_string());
@@ -152,91 +682,127 @@ impl NotificationServer {
         actions: Vec<String>,
         hints: HashMap<String, OwnedValue>,
         expire_timeout: i32,
+        #[zbus(header)] header: Header<'_>,
     ) -> zbus::fdo::Result<u32> {
-        if tracing::enabled!(tracing::Level::DEBUG) {
-            let summary_snip = unixnotis_core::util::log_snippet(&summary);
-            debug!(
-                app = %app_name,
-                summary = %summary_snip,
-                summary_len = summary.len(),
-                body_len = body.len(),
-                replaces_id,
+        let received = Instant::now();
+        // Covers the store insert and every signal the notification fans out to.
+        let span = debug_span!("notify", app = %app_name, replaces_id);
+        async move {
+            if tracing::enabled!(tracing::Level::DEBUG) {
+                let summary_snip = unixnotis_core::util::log_snippet(&summary);
+                debug!(
+                    app = %app_name,
+                    summary = %summary_snip,
+                    summary_len = summary.len(),
+                    body_len = body.len(),
+                    replaces_id,
+                    expire_timeout,
+                    "received notification"
+                );
+                if unixnotis_core::util::diagnostic_mode() {
+                    let body_snip = unixnotis_core::util::log_snippet(&body);
+                    debug!(body = %body_snip, "notification body snippet");
+                }
+            }
+            let image_limits = ImageLimits::for_previews(
+                self.state.store.lock().await.config().icons.image_preview,
+            );
+            let mut notification = build_notification(
+                image_limits,
+                app_name,
+                app_icon,
+                summary,
+                body,
+                actions,
+                hints,
                 expire_timeout,
-                "received notification"
             );
-            if unixnotis_core::util::diagnostic_mode() {
-                let body_snip = unixnotis_core::util::log_snippet(&body);
-                debug!(body = %body_snip, "notification body snippet");
-            }
-        }
-        let notification = build_notification(
-            app_name,
-            app_icon,
-            summary,
-            body,
-            actions,
-            hints,
-            expire_timeout,
-        );
+            notification.sender = header.sender().map(ToString::to_string);
 
-        let (outcome, expiration) = {
-            let mut store = self.state.store.lock().await;
-            let outcome = store.insert(notification, replaces_id);
-            let expiration = resolve_expiration(store.config(), &outcome.notification);
-            store.set_expiration(outcome.notification.id, expiration);
-            (outcome, expiration)
-        };
-        self.scheduler.schedule(outcome.notification.id, expiration);
-        // Sound playback is driven by hints plus configured defaults.
-        self.state
-            .sound
-            .play_from_hints(&outcome.notification.hints, outcome.allow_sound);
-
-        let control_ctx = SignalContext::new(self.state.connection(), CONTROL_OBJECT_PATH)
-            .map_err(to_fdo_error)?;
-        if outcome.replaced {
-            ControlServer::notification_updated(
-                &control_ctx,
-                outcome.notification.to_view(),
-                outcome.show_popup,
-            )
-            .await
-            .map_err(to_fdo_error)?;
-        } else {
-            ControlServer::notification_added(
-                &control_ctx,
-                outcome.notification.to_view(),
-                outcome.show_popup,
-            )
-            .await
-            .map_err(to_fdo_error)?;
-        }
-        self.handle_evicted(outcome.evicted).await?;
-        self.state
-            .emit_state_changed()
-            .await
-            .map_err(to_fdo_error)?;
-
-        Ok(outcome.notification.id)
-    }
+            let (outcome, expiration, urgent, measure, osd) = {
+                let mut store = self.state.store.lock().await;
+                let osd = osd_request(
+                    &store.config().popups.osd,
+                    &notification.hints,
+                    notification.progress,
+                );
+                if osd.is_some() {
+                    // The overlay replaces the toast, and level changes are not worth keeping.
+                    notification.suppress_popup = true;
+                    notification.is_transient = true;
+                }
+                let outcome = store.insert(notification, replaces_id);
+                let osd = osd.filter(|_| store.popups_enabled());
+                let expiration = resolve_expiration(store.config(), &outcome.notification);
+                store.set_expiration(outcome.notification.id, expiration);
+                // Rules have run by now, so a rule that raises urgency also turns on urgent mode.
+                let urgent = store
+                    .config()
+                    .popups
+                    .is_urgent(outcome.notification.urgency);
+                let measure = store.config().general.measure_latency;
+                (outcome, expiration, urgent, measure, osd)
+            };
+            self.scheduler.schedule(outcome.notification.id, expiration);
+            // Sound playback is driven by hints plus configured defaults.
+            self.state.sound.play_from_hints(
+                &outcome.notification.hints,
+                outcome.allow_sound,
+                urgent,
+            );
 
-    async fn handle_evicted(&self, evicted: Vec<u32>) -> zbus::fdo::Result<()> {
-        if evicted.is_empty() {
-            return Ok(());
-        }
-        let notif_ctx = SignalContext::new(self.state.connection(), NOTIFICATIONS_OBJECT_PATH)
-            .map_err(to_fdo_error)?;
-        let control_ctx = SignalContext::new(self.state.connection(), CONTROL_OBJECT_PATH)
-            .map_err(to_fdo_error)?;
-        for id in evicted {
-            NotificationServer::notification_closed(&notif_ctx, id, CloseReason::Undefined as u32)
+            let control_ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+                .map_err(to_fdo_error)?;
+            if outcome.replaced {
+                self.state.flush_added().await.map_err(to_fdo_error)?;
+                ControlServer::notification_updated(
+                    &control_ctx,
+                    outcome.notification.to_view(),
+                    outcome.show_popup,
+                )
                 .await
                 .map_err(to_fdo_error)?;
-            ControlServer::notification_closed(&control_ctx, id, CloseReason::Undefined)
+            } else {
+                self.state
+                    .announce_added(outcome.notification.to_view(), outcome.show_popup)
+                    .await
+                    .map_err(to_fdo_error)?;
+            }
+            if let Some((kind, percent)) = osd {
+                ControlServer::osd_requested(&control_ctx, kind, percent)
+                    .await
+                    .map_err(to_fdo_error)?;
+            }
+            if let Some(slot) = outcome.popup_slot {
+                // Recorded first: the popup process may report the paint before the send returns.
+                if measure {
+                    self.state
+                        .latency
+                        .lock()
+                        .await
+                        .signalled(outcome.notification.id, received);
+                }
+                ControlServer::popup_shown(&control_ctx, outcome.notification.id, slot)
+                    .await
+                    .map_err(to_fdo_error)?;
+            }
+            self.handle_evicted(outcome.evicted).await?;
+            self.state
+                .emit_state_changed()
                 .await
                 .map_err(to_fdo_error)?;
+
+            Ok(outcome.notification.id)
         }
-        Ok(())
+        .instrument(span)
+        .await
+    }
+
+    async fn handle_evicted(&self, evicted: Vec<u32>) -> zbus::fdo::Result<()> {
+        self.state
+            .announce_evicted(evicted)
+            .await
+            .map_err(to_fdo_error)
     }
 
     async fn close_notification(&self, id: u32) -> zbus::fdo::Result<()> {
@@ -263,16 +829,17 @@ impl NotificationServer {
     #[zbus(signal)]
     async fn action_invoked(ctx: &SignalContext<'_>, id: u32, action_key: &str)
         -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn notification_replied(ctx: &SignalContext<'_>, id: u32, text: &str)
+        -> zbus::Result<()>;
 }
 
 #[interface(name = "com.unixnotis.Control")]
 impl ControlServer {
     async fn get_state(&self) -> unixnotis_core::ControlState {
         let store = self.state.store.lock().await;
-        unixnotis_core::ControlState {
-            dnd_enabled: store.dnd_enabled(),
-            history_count: store.history_len() as u32,
-        }
+        store.control_state()
     }
 
     async fn list_active(&self) -> Vec<NotificationView> {
@@ -285,97 +852,620 @@ impl ControlServer {
         store.list_history()
     }
 
-    async fn open_panel(&self) -> zbus::fdo::Result<()> {
-        let ctx = SignalContext::new(self.state.connection(), CONTROL_OBJECT_PATH)
+    async fn list_history_range(&self, offset: u32, limit: u32) -> Vec<NotificationView> {
+        let store = self.state.store.lock().await;
+        store.list_history_range(offset as usize, limit as usize)
+    }
+
+    async fn get_image(&self, id: u32) -> zbus::fdo::Result<NotificationImage> {
+        let store = self.state.store.lock().await;
+        store
+            .find(id)
+            .map(|notification| notification.image.clone())
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("unknown notification: {id}")))
+    }
+
+    async fn open_panel(&self, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "OpenPanel")
+            .await?;
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
             .map_err(to_fdo_error)?;
         ControlServer::panel_requested(&ctx, PanelRequest::open())
             .await
             .map_err(to_fdo_error)
     }
 
-    async fn open_panel_debug(&self, level: PanelDebugLevel) -> zbus::fdo::Result<()> {
-        let ctx = SignalContext::new(self.state.connection(), CONTROL_OBJECT_PATH)
+    async fn open_panel_debug(
+        &self,
+        level: PanelDebugLevel,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "OpenPanelDebug")
+            .await?;
+        self.state.follow_debug_log(level, header.sender());
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
             .map_err(to_fdo_error)?;
         ControlServer::panel_requested(&ctx, PanelRequest::open_debug(level))
             .await
             .map_err(to_fdo_error)
     }
 
-    async fn close_panel(&self) -> zbus::fdo::Result<()> {
-        let ctx = SignalContext::new(self.state.connection(), CONTROL_OBJECT_PATH)
+    async fn set_panel_debug_level(
+        &self,
+        level: PanelDebugLevel,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "SetPanelDebugLevel")
+            .await?;
+        self.state.follow_debug_log(level, header.sender());
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
             .map_err(to_fdo_error)?;
-        ControlServer::panel_requested(&ctx, PanelRequest::close())
+        ControlServer::panel_debug_level_requested(&ctx, level)
+            .await
+            .map_err(to_fdo_error)
+    }
+
+    async fn open_panel_at(
+        &self,
+        target: PanelTarget,
+        app: String,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "OpenPanelAt")
+            .await?;
+        let app = app.trim();
+        if target == PanelTarget::App && app.is_empty() {
+            return Err(zbus::fdo::Error::InvalidArgs(
+                "app target needs an app name".to_string(),
+            ));
+        }
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        ControlServer::panel_requested(&ctx, PanelRequest::open_target(target, app))
             .await
             .map_err(to_fdo_error)
     }
 
-    async fn toggle_panel(&self) -> zbus::fdo::Result<()> {
-        let ctx = SignalContext::new(self.state.connection(), CONTROL_OBJECT_PATH)
+    async fn set_panel_geometry(
+        &self,
+        geometry: PanelGeometry,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "SetPanelGeometry")
+            .await?;
+        geometry.validate().map_err(zbus::fdo::Error::InvalidArgs)?;
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
             .map_err(to_fdo_error)?;
-        ControlServer::panel_requested(&ctx, PanelRequest::toggle())
+        ControlServer::panel_geometry_requested(&ctx, geometry)
             .await
             .map_err(to_fdo_error)
     }
 
-    async fn set_dnd(&self, enabled: bool) -> zbus::fdo::Result<()> {
-        {
-            let mut store = self.state.store.lock().await;
-            store.set_dnd(enabled);
+    async fn preview_layout(
+        &self,
+        seconds: u32,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "PreviewLayout")
+            .await?;
+        if !(1..=60).contains(&seconds) {
+            return Err(zbus::fdo::Error::InvalidArgs(
+                "preview duration must be 1 to 60 seconds".to_string(),
+            ));
         }
-        self.state.emit_state_changed().await.map_err(to_fdo_error)
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        ControlServer::layout_preview_requested(&ctx, seconds)
+            .await
+            .map_err(to_fdo_error)
     }
 
-    async fn dismiss(&self, id: u32) -> zbus::fdo::Result<()> {
+    async fn close_panel(&self, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<()> {
         self.state
-            .dismiss_from_panel(id)
+            .authorize(&header, Access::Change, "ClosePanel")
+            .await?;
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        ControlServer::panel_requested(&ctx, PanelRequest::close())
             .await
             .map_err(to_fdo_error)
     }
 
-    async fn invoke_action(&self, id: u32, action_key: &str) -> zbus::fdo::Result<()> {
-        let ctx = SignalContext::new(self.state.connection(), NOTIFICATIONS_OBJECT_PATH)
+    async fn toggle_panel(&self, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "TogglePanel")
+            .await?;
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
             .map_err(to_fdo_error)?;
-        NotificationServer::action_invoked(&ctx, id, action_key)
+        ControlServer::panel_requested(&ctx, PanelRequest::toggle())
             .await
             .map_err(to_fdo_error)
     }
 
-    async fn clear_all(&self) -> zbus::fdo::Result<()> {
-        // Drain active notifications in one lock to avoid quadratic scans.
-        let ids = {
+    async fn set_dnd(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "SetDnd")
+            .await?;
+        {
             let mut store = self.state.store.lock().await;
-            let ids = store.drain_active_ids();
-            store.clear_history();
-            ids
+            store.set_dnd(enabled);
+        }
+        self.state.emit_state_changed().await.map_err(to_fdo_error)
+    }
+
+    async fn set_dnd_for(
+        &self,
+        seconds: u32,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "SetDndFor")
+            .await?;
+        if seconds == 0 {
+            return Err(zbus::fdo::Error::InvalidArgs(
+                "duration must be at least one second".to_string(),
+            ));
+        }
+        let until = Utc::now() + chrono::Duration::seconds(i64::from(seconds));
+        {
+            let mut store = self.state.store.lock().await;
+            store.set_dnd_until(until);
+        }
+        debug!(seconds, "timed do not disturb started");
+        schedule_dnd_end(self.state.clone(), until);
+        self.state.emit_state_changed().await.map_err(to_fdo_error)
+    }
+
+    async fn set_popups_enabled(
+        &self,
+        enabled: bool,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "SetPopupsEnabled")
+            .await?;
+        let changed = self.state.store.lock().await.set_popups_enabled(enabled);
+        if !changed {
+            return Ok(());
+        }
+        info!(enabled, "popups switched");
+        self.state.emit_state_changed().await.map_err(to_fdo_error)
+    }
+
+    async fn set_muted(
+        &self,
+        muted: bool,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "SetMuted")
+            .await?;
+        let changed = self.state.store.lock().await.set_muted(muted);
+        if !changed {
+            return Ok(());
+        }
+        info!(muted, "notification sounds muted");
+        self.state.emit_state_changed().await.map_err(to_fdo_error)
+    }
+
+    async fn show_osd(
+        &self,
+        kind: OsdKind,
+        percent: u32,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "ShowOsd")
+            .await?;
+        if !self.state.store.lock().await.popups_enabled() {
+            return Ok(());
+        }
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        ControlServer::osd_requested(&ctx, kind, percent.min(100))
+            .await
+            .map_err(to_fdo_error)
+    }
+
+    async fn set_app_popup_anchor(
+        &self,
+        app: &str,
+        anchor: &str,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "SetAppPopupAnchor")
+            .await?;
+        let app = app.trim();
+        if app.is_empty() {
+            return Err(zbus::fdo::Error::InvalidArgs(
+                "popup position needs an app name".to_string(),
+            ));
+        }
+        let anchor = anchor.trim();
+        let parsed = Anchor::from_name(anchor);
+        if !anchor.is_empty() && parsed.is_none() {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "unknown anchor: {anchor}"
+            )));
+        }
+        let mut positions = self.state.popup_positions.lock().await;
+        if !positions.set(app, parsed) {
+            return Ok(());
+        }
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        ControlServer::app_popup_anchor_changed(&ctx, &app.to_lowercase(), anchor)
+            .await
+            .map_err(to_fdo_error)?;
+        let Some(path) = self.state.positions_path.clone() else {
+            return Ok(());
         };
-        if ids.is_empty() {
-            return self.state.emit_state_changed().await.map_err(to_fdo_error);
+        let saved = positions.clone();
+        let result = tokio::task::spawn_blocking(move || saved.save(&path)).await;
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(err)) => {
+                warn!(?err, "failed to save popup positions");
+                Err(zbus::fdo::Error::Failed(format!(
+                    "popup position is active but was not saved: {err}"
+                )))
+            }
+            Err(err) => Err(zbus::fdo::Error::Failed(err.to_string())),
         }
-        let notif_ctx = SignalContext::new(self.state.connection(), NOTIFICATIONS_OBJECT_PATH)
+    }
+
+    async fn list_app_popup_anchors(&self) -> Vec<(String, String)> {
+        let positions = self.state.popup_positions.lock().await;
+        positions
+            .iter()
+            .map(|(app, anchor)| (app.to_string(), anchor.name().to_string()))
+            .collect()
+    }
+
+    async fn dismiss(&self, id: u32, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "Dismiss")
+            .await?;
+        self.state
+            .dismiss_from_panel(id)
+            .await
+            .map_err(to_fdo_error)
+    }
+
+    async fn pin(
+        &self,
+        id: u32,
+        pinned: bool,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<bool> {
+        self.state.authorize(&header, Access::Change, "Pin").await?;
+        let outcome = {
+            let mut store = self.state.store.lock().await;
+            store.set_pinned(id, pinned)
+        };
+        let Some((_, dropped)) = outcome else {
+            return Ok(false);
+        };
+        debug!(
+            id,
+            pinned,
+            dropped = dropped.len(),
+            "notification pin changed"
+        );
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
             .map_err(to_fdo_error)?;
-        let control_ctx = SignalContext::new(self.state.connection(), CONTROL_OBJECT_PATH)
+        ControlServer::notification_pinned(&ctx, id, pinned)
+            .await
             .map_err(to_fdo_error)?;
-        // Emit close signals concurrently to avoid blocking on large clears.
-        let mut tasks = FuturesUnordered::new();
-        for id in ids {
-            let notif_ctx = notif_ctx.clone();
-            let control_ctx = control_ctx.clone();
-            tasks.push(async move {
-                NotificationServer::notification_closed(
-                    &notif_ctx,
-                    id,
-                    CloseReason::DismissedByUser as u32,
-                )
-                .await?;
-                ControlServer::notification_closed(&control_ctx, id, CloseReason::DismissedByUser)
-                    .await?;
-                Ok::<(), zbus::Error>(())
+        if dropped.is_empty() {
+            return Ok(true);
+        }
+        // An unpin put history back over its limit; the UIs drop these rows as they would
+        // after a reload.
+        for dropped_id in dropped {
+            ControlServer::notification_closed(&ctx, dropped_id, CloseReason::Pruned)
+                .await
+                .map_err(to_fdo_error)?;
+        }
+        self.state
+            .emit_state_changed()
+            .await
+            .map_err(to_fdo_error)?;
+        Ok(true)
+    }
+
+    async fn hold_expiration(
+        &self,
+        id: u32,
+        hold: bool,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "HoldExpiration")
+            .await?;
+        let now = Instant::now();
+        let deadline = {
+            let mut store = self.state.store.lock().await;
+            if hold {
+                if !store.hold_expiration(id, now) {
+                    return Ok(());
+                }
+                None
+            } else {
+                let Some(deadline) = store.release_expiration(id, now) else {
+                    return Ok(());
+                };
+                Some(deadline)
+            }
+        };
+        self.scheduler.schedule(id, deadline);
+        Ok(())
+    }
+
+    async fn invoke_action(
+        &self,
+        id: u32,
+        action_key: &str,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "InvokeAction")
+            .await?;
+        // Nobody would receive the signal, so tell the user instead of silently doing nothing.
+        if let Some(app_name) = self.state.departed_sender_app(id).await {
+            debug!(id, app = %app_name, "action target has left the bus");
+            self.state.spawn_sender_gone_toast(app_name);
+            return Ok(());
+        }
+        let ctx = SignalContext::new(&self.state.connection(), NOTIFICATIONS_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        NotificationServer::action_invoked(&ctx, id, action_key)
+            .await
+            .map_err(to_fdo_error)
+    }
+
+    async fn reply(
+        &self,
+        id: u32,
+        text: &str,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "Reply")
+            .await?;
+        let takes_reply = self
+            .state
+            .store
+            .lock()
+            .await
+            .find(id)
+            .is_some_and(|notification| {
+                notification
+                    .actions
+                    .iter()
+                    .any(|action| action.key == INLINE_REPLY_ACTION)
             });
+        if !takes_reply {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "notification {id} does not take replies"
+            )));
         }
-        while let Some(result) = tasks.next().await {
-            result.map_err(to_fdo_error)?;
+        if let Some(app_name) = self.state.departed_sender_app(id).await {
+            debug!(id, app = %app_name, "reply target has left the bus");
+            self.state.spawn_sender_gone_toast(app_name);
+            return Ok(());
         }
-        self.state.emit_state_changed().await.map_err(to_fdo_error)
+        let ctx = SignalContext::new(&self.state.connection(), NOTIFICATIONS_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        NotificationServer::notification_replied(&ctx, id, text)
+            .await
+            .map_err(to_fdo_error)
+    }
+
+    async fn clear_all(&self, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Destructive, "ClearAll")
+            .await?;
+        let scope = self.state.store.lock().await.config().history.clear_scope;
+        self.state.clear(scope).await.map_err(to_fdo_error)?;
+        Ok(())
+    }
+
+    async fn clear(
+        &self,
+        scope: ClearScope,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<Vec<u32>> {
+        self.state
+            .authorize(&header, Access::Destructive, "Clear")
+            .await?;
+        self.state.clear(scope).await.map_err(to_fdo_error)
+    }
+
+    async fn undo_clear(&self, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<bool> {
+        self.state
+            .authorize(&header, Access::Change, "UndoClear")
+            .await?;
+        let outcome = self.state.store.lock().await.undo_clear(Instant::now());
+        let Some(outcome) = outcome else {
+            return Ok(false);
+        };
+        debug!(
+            active = outcome.restored.len(),
+            history = outcome.history.len(),
+            "restored cleared notifications"
+        );
+        self.state.flush_added().await.map_err(to_fdo_error)?;
+        let mut added = Vec::with_capacity(outcome.restored.len());
+        for (notification, deadline) in outcome.restored {
+            // Popups were already dismissed once; restored entries only return to the panel.
+            self.scheduler.schedule(notification.id, deadline);
+            added.push((notification.to_view(), false));
+        }
+        self.state.emit_added(added).await.map_err(to_fdo_error)?;
+        self.state
+            .announce_evicted(outcome.evicted)
+            .await
+            .map_err(to_fdo_error)?;
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        for id in outcome.dropped {
+            ControlServer::notification_closed(&ctx, id, CloseReason::Pruned)
+                .await
+                .map_err(to_fdo_error)?;
+        }
+        // No close signal describes entries coming back to history.
+        if !outcome.history.is_empty() {
+            ControlServer::history_changed(&ctx)
+                .await
+                .map_err(to_fdo_error)?;
+        }
+        self.state
+            .emit_state_changed()
+            .await
+            .map_err(to_fdo_error)?;
+        Ok(true)
+    }
+
+    async fn get_memory_report(&self) -> zbus::fdo::Result<Vec<MemoryReport>> {
+        let daemon_report = {
+            let store = self.state.store.lock().await;
+            MemoryReport::for_current_process("daemon", store.memory_estimates())
+        };
+        let requested_at = Instant::now();
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        ControlServer::memory_report_requested(&ctx)
+            .await
+            .map_err(to_fdo_error)?;
+
+        let mut reports = vec![daemon_report];
+        reports.extend(self.state.collect_ui_reports(requested_at).await);
+        Ok(reports)
+    }
+
+    async fn report_memory(&self, report: MemoryReport) {
+        self.state.record_ui_report(report).await;
+    }
+
+    async fn report_popup_rendered(
+        &self,
+        id: u32,
+        render_us: u64,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "ReportPopupRendered")
+            .await?;
+        self.state
+            .latency
+            .lock()
+            .await
+            .rendered(id, Duration::from_micros(render_us));
+        Ok(())
+    }
+
+    async fn publish_debug_log(
+        &self,
+        level: PanelDebugLevel,
+        message: String,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "PublishDebugLog")
+            .await?;
+        // The panel sends every line while debugging; only a follower needs them on the bus.
+        if !self.state.debug_followed.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        ControlServer::debug_log(&ctx, level, &message)
+            .await
+            .map_err(to_fdo_error)
+    }
+
+    async fn list_rules(&self) -> Vec<RuleDict> {
+        let store = self.state.store.lock().await;
+        store.rules().iter().map(RuleConfig::to_dict).collect()
+    }
+
+    async fn set_rule(
+        &self,
+        rule: RuleDict,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Destructive, "SetRule")
+            .await?;
+        let rule = RuleConfig::from_dict(&rule).map_err(zbus::fdo::Error::InvalidArgs)?;
+        if rule
+            .name
+            .as_deref()
+            .is_none_or(|name| name.trim().is_empty())
+        {
+            return Err(zbus::fdo::Error::InvalidArgs(
+                "rules set over D-Bus need a name".to_string(),
+            ));
+        }
+        if rule.history_ttl_minutes.is_some_and(|minutes| minutes > 0) {
+            self.state.ensure_history_pruner();
+        }
+        info!(name = ?rule.name, "rule set");
+        {
+            let mut store = self.state.store.lock().await;
+            store.set_rule(rule);
+        }
+        self.state.rules_updated().await
+    }
+
+    async fn remove_rule(
+        &self,
+        name: &str,
+        #[zbus(header)] header: Header<'_>,
+    ) -> zbus::fdo::Result<bool> {
+        self.state
+            .authorize(&header, Access::Destructive, "RemoveRule")
+            .await?;
+        let removed = {
+            let mut store = self.state.store.lock().await;
+            store.remove_rule(name)
+        };
+        if removed {
+            info!(name, "rule removed");
+            self.state.rules_updated().await?;
+        }
+        Ok(removed)
+    }
+
+    async fn list_audit(&self) -> Vec<AuditEntry> {
+        self.state.control_policy.audit_entries().await
+    }
+
+    async fn trim_caches(&self, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<()> {
+        self.state
+            .authorize(&header, Access::Change, "TrimCaches")
+            .await?;
+        {
+            let mut store = self.state.store.lock().await;
+            store.shrink_buffers();
+        }
+        let ctx = SignalContext::new(&self.state.connection(), CONTROL_OBJECT_PATH)
+            .map_err(to_fdo_error)?;
+        ControlServer::trim_requested(&ctx)
+            .await
+            .map_err(to_fdo_error)
     }
 
     #[zbus(signal)]
@@ -399,6 +1489,29 @@ impl ControlServer {
         reason: CloseReason,
     ) -> zbus::Result<()>;
 
+    #[zbus(signal)]
+    async fn notification_pinned(
+        ctx: &SignalContext<'_>,
+        id: u32,
+        pinned: bool,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn debug_log(
+        ctx: &SignalContext<'_>,
+        level: PanelDebugLevel,
+        message: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn notifications_added_batch(
+        ctx: &SignalContext<'_>,
+        notifications: Vec<(NotificationView, bool)>,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn popup_shown(ctx: &SignalContext<'_>, id: u32, slot: u32) -> zbus::Result<()>;
+
     #[zbus(signal)]
     async fn state_changed(
         ctx: &SignalContext<'_>,
@@ -407,9 +1520,52 @@ impl ControlServer {
 
     #[zbus(signal)]
     async fn panel_requested(ctx: &SignalContext<'_>, request: PanelRequest) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn panel_geometry_requested(
+        ctx: &SignalContext<'_>,
+        geometry: PanelGeometry,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn panel_debug_level_requested(
+        ctx: &SignalContext<'_>,
+        level: PanelDebugLevel,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn layout_preview_requested(ctx: &SignalContext<'_>, seconds: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn osd_requested(
+        ctx: &SignalContext<'_>,
+        kind: OsdKind,
+        percent: u32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn app_popup_anchor_changed(
+        ctx: &SignalContext<'_>,
+        app: &str,
+        anchor: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn memory_report_requested(ctx: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn trim_requested(ctx: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn rules_changed(ctx: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn history_changed(ctx: &SignalContext<'_>) -> zbus::Result<()>;
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_notification(
+    image_limits: ImageLimits,
     app_name: String,
     app_icon: String,
     summary: String,
@@ -429,7 +1585,8 @@ fn build_notification(
         .get("resident")
         .and_then(|value| bool::try_from(value).ok())
         .unwrap_or(false);
-    let image = NotificationImage::from_hints(&app_name, &app_icon, &hints);
+    let image = NotificationImage::from_hints(&app_name, &app_icon, &hints, image_limits);
+    let progress = progress_from_hint(hints.get("value"));
 
     Notification {
         id: 0,
@@ -440,6 +1597,7 @@ fn build_notification(
         },
         app_icon,
         summary,
+        // Kept as sent so rules, search, and scripts see the text; the UIs sanitize it to render.
         body,
         actions: parse_actions(actions),
         hints,
@@ -452,6 +1610,11 @@ fn build_notification(
         image,
         expire_timeout,
         received_at: chrono::Utc::now(),
+        history_ttl_minutes: None,
+        clamp_timeout: true,
+        sender: None,
+        pinned: false,
+        progress,
     }
 }
 
@@ -466,30 +1629,78 @@ fn parse_actions(raw: Vec<String>) -> Vec<Action> {
     actions
 }
 
-fn resolve_expiration(config: &Config, notification: &Notification) -> Option<Instant> {
-    // Explicit timeouts and resident notifications override defaults.
-    if notification.expire_timeout == 0 || notification.is_resident {
-        return None;
-    }
+/// Whether any rule TTL or the history age limit needs the pruner running.
+pub fn uses_history_ttl(config: &Config) -> bool {
+    config
+        .rules
+        .iter()
+        .any(|rule| rule.history_ttl_minutes.is_some_and(|minutes| minutes > 0))
+        || config.history.max_age_days > 0
+}
 
-    let timeout_ms = if notification.expire_timeout > 0 {
-        notification.expire_timeout as u64
+fn resolve_expiration(config: &Config, notification: &Notification) -> Option<Instant> {
+    let timeout_ms = expiration_timeout_ms(
+        &config.popups,
+        notification.urgency,
+        notification.expire_timeout,
+        notification.is_resident,
+    )?;
+    // Bounds catch apps that ask for a 1 ms flash or an hour-long toast; rules can exempt apps.
+    let timeout_ms = if notification.clamp_timeout {
+        config.popups.clamp_timeout_ms(timeout_ms)
     } else {
-        match notification.urgency {
-            Urgency::Critical => config.popups.critical_timeout_ms?,
-            _ => config.popups.default_timeout_ms,
-        }
+        timeout_ms
     };
+    Some(Instant::now() + Duration::from_millis(timeout_ms))
+}
 
-    if timeout_ms == 0 {
+/// Overlay kind and level for a volume or brightness notification, when the OSD takes those.
+///
+/// OSD scripts tag their notifications so each one replaces the last; the tag names the kind
+/// and the `value` hint carries the level.
+fn osd_request(
+    osd: &OsdConfig,
+    hints: &HashMap<String, OwnedValue>,
+    progress: Option<u8>,
+) -> Option<(OsdKind, u32)> {
+    if !osd.capture_notifications {
         return None;
     }
+    let percent = progress?;
+    let kind = OSD_TAG_HINTS
+        .iter()
+        .filter_map(|key| hints.get(*key).and_then(owned_to_string))
+        .find_map(|tag| OsdKind::from_tag(&tag))?;
+    Some((kind, u32::from(percent)))
+}
 
-    Some(Instant::now() + Duration::from_millis(timeout_ms))
+/// Timeout for a notification, in precedence order: never for resident notifications, an
+/// `expire_timeout` of 0, or urgent mode; then the app's own positive `expire_timeout`; then
+/// the configured default for its urgency.
+fn expiration_timeout_ms(
+    popups: &PopupConfig,
+    urgency: Urgency,
+    expire_timeout: i32,
+    is_resident: bool,
+) -> Option<u64> {
+    if expire_timeout == 0 || is_resident {
+        return None;
+    }
+    // Urgent mode keeps critical notifications, including ones made critical by a rule, up
+    // until dismissed whatever timeout the app asked for.
+    if popups.is_urgent(urgency) {
+        return None;
+    }
+    match u64::try_from(expire_timeout) {
+        Ok(timeout_ms) => Some(timeout_ms),
+        // -1 (or any negative value) leaves the timeout to the server.
+        Err(_) => popups.timeout_ms(urgency),
+    }
 }
 
 pub async fn request_well_known_name(
     connection: &Connection,
+    names: &BusNames,
     replace_existing: bool,
 ) -> zbus::Result<RequestNameReply> {
     let flags = if replace_existing {
@@ -499,14 +1710,17 @@ pub async fn request_well_known_name(
         zbus::fdo::RequestNameFlags::DoNotQueue.into()
     };
     connection
-        .request_name_with_flags("org.freedesktop.Notifications", flags)
+        .request_name_with_flags(names.notifications.as_str(), flags)
         .await
 }
 
-pub async fn request_control_name(connection: &Connection) -> zbus::Result<RequestNameReply> {
+pub async fn request_control_name(
+    connection: &Connection,
+    names: &BusNames,
+) -> zbus::Result<RequestNameReply> {
     let flags = RequestNameFlags::DoNotQueue;
     connection
-        .request_name_with_flags(CONTROL_BUS_NAME, flags.into())
+        .request_name_with_flags(names.control.as_str(), flags.into())
         .await
 }
 
@@ -517,19 +1731,20 @@ fn owned_to_string(value: &OwnedValue) -> Option<String> {
         .and_then(|owned| String::try_from(owned).ok())
 }
 
-pub fn log_name_reply(reply: &RequestNameReply) {
+pub fn log_name_reply(names: &BusNames, reply: &RequestNameReply) {
+    let name = names.notifications.as_str();
     match reply {
         RequestNameReply::PrimaryOwner => {
-            info!("acquired org.freedesktop.Notifications");
+            info!(name, "acquired notification bus name");
         }
         RequestNameReply::InQueue => {
-            info!("queued for org.freedesktop.Notifications");
+            info!(name, "queued for notification bus name");
         }
         RequestNameReply::AlreadyOwner => {
-            info!("already owns org.freedesktop.Notifications");
+            info!(name, "already owns notification bus name");
         }
         RequestNameReply::Exists => {
-            info!("org.freedesktop.Notifications is already owned");
+            info!(name, "notification bus name is already owned");
         }
     }
 }
@@ -537,3 +1752,250 @@ pub fn log_name_reply(reply: &RequestNameReply) {
 fn to_fdo_error(err: zbus::Error) -> zbus::fdo::Error {
     zbus::fdo::Error::Failed(err.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use unixnotis_core::ControlProxy;
+
+    use super::*;
+
+    #[test]
+    fn app_timeouts_override_urgency_defaults() {
+        let popups = PopupConfig {
+            low_timeout_ms: Some(2000),
+            default_timeout_ms: 5000,
+            critical_timeout_ms: Some(30_000),
+            ..PopupConfig::default()
+        };
+        // Server defaults, one per urgency.
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Low, -1, false),
+            Some(2000)
+        );
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Normal, -1, false),
+            Some(5000)
+        );
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Critical, -1, false),
+            Some(30_000)
+        );
+        // A positive hint wins over every default.
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Low, 700, false),
+            Some(700)
+        );
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Critical, 700, false),
+            Some(700)
+        );
+        // 0 and residency mean never, whatever the defaults say.
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Normal, 0, false),
+            None
+        );
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Normal, 700, true),
+            None
+        );
+    }
+
+    #[test]
+    fn timeouts_are_held_within_the_configured_bounds() {
+        let mut popups = PopupConfig::default();
+        assert_eq!(popups.clamp_timeout_ms(1), 1);
+        popups.min_timeout_ms = Some(2000);
+        popups.max_timeout_ms = Some(10_000);
+        assert_eq!(popups.clamp_timeout_ms(1), 2000);
+        assert_eq!(popups.clamp_timeout_ms(4000), 4000);
+        assert_eq!(popups.clamp_timeout_ms(3_600_000), 10_000);
+        // Crossed bounds resolve to the maximum.
+        popups.min_timeout_ms = Some(20_000);
+        assert_eq!(popups.clamp_timeout_ms(1), 10_000);
+    }
+
+    #[test]
+    fn tagged_level_notifications_become_osd_requests() {
+        let mut osd = OsdConfig::default();
+        let hints = HashMap::from([(
+            "x-canonical-private-synchronous".to_string(),
+            OwnedValue::from(zbus::zvariant::Str::from("volume")),
+        )]);
+        assert_eq!(osd_request(&osd, &hints, Some(40)), None);
+
+        osd.capture_notifications = true;
+        assert_eq!(
+            osd_request(&osd, &hints, Some(40)),
+            Some((OsdKind::Volume, 40))
+        );
+        // No level, or a tag that names neither kind, stays a regular toast.
+        assert_eq!(osd_request(&osd, &hints, None), None);
+        let other = HashMap::from([(
+            "x-dunst-stack-tag".to_string(),
+            OwnedValue::from(zbus::zvariant::Str::from("download")),
+        )]);
+        assert_eq!(osd_request(&osd, &other, Some(40)), None);
+    }
+
+    #[test]
+    fn urgency_defaults_fall_back_and_urgent_mode_wins() {
+        let mut popups = PopupConfig::default();
+        // Unset low follows normal; unset critical never expires.
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Low, -1, false),
+            Some(5000)
+        );
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Critical, -1, false),
+            None
+        );
+        popups.low_timeout_ms = Some(0);
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Low, -1, false),
+            None
+        );
+
+        popups.urgent.enabled = true;
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Critical, 700, false),
+            None
+        );
+        assert_eq!(
+            expiration_timeout_ms(&popups, Urgency::Normal, 700, false),
+            Some(700)
+        );
+    }
+
+    #[test]
+    fn bodies_are_stored_as_sent() {
+        let notification = build_notification(
+            ImageLimits::ICON,
+            "app".to_string(),
+            String::new(),
+            "summary".to_string(),
+            "R&D from <Alice Bob>".to_string(),
+            Vec::new(),
+            HashMap::new(),
+            -1,
+        );
+        assert_eq!(notification.body, "R&D from <Alice Bob>");
+    }
+
+    /// A daemon served over a peer-to-peer connection, seen from the client end.
+    struct PeerDaemon {
+        names: BusNames,
+        client: Connection,
+        control: ControlProxy<'static>,
+        /// Opened before any call, so no signal the calls cause is missed.
+        signals: zbus::MessageStream,
+        _state: Arc<DaemonState>,
+    }
+
+    async fn serve_peer_daemon() -> PeerDaemon {
+        let (daemon_side, client_side) = tokio::net::UnixStream::pair().unwrap();
+        let daemon_bus = zbus::connection::Builder::unix_stream(daemon_side)
+            .server(zbus::Guid::generate())
+            .unwrap()
+            .p2p()
+            .build();
+        let client_bus = zbus::connection::Builder::unix_stream(client_side)
+            .p2p()
+            .build();
+        let (daemon_bus, client) = tokio::try_join!(daemon_bus, client_bus).unwrap();
+        let names = BusNames::new(None).unwrap();
+        let config = Config::default();
+        let sound = SoundSettings::from_config(&config);
+        let state = DaemonState::new(daemon_bus.clone(), names.clone(), config, None, sound);
+        let scheduler = ExpirationScheduler::start(state.clone());
+        crate::session_bus::serve(&daemon_bus, &state, &scheduler)
+            .await
+            .unwrap();
+
+        let signals = zbus::MessageStream::from(&client);
+        let control = ControlProxy::builder(&client)
+            .destination(names.control.clone())
+            .unwrap()
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await
+            .unwrap();
+        PeerDaemon {
+            names,
+            client,
+            control,
+            signals,
+            _state: state,
+        }
+    }
+
+    /// Arguments of the next `member` signal, waiting at most five seconds.
+    async fn next_signal<B>(signals: &mut zbus::MessageStream, member: &str) -> B
+    where
+        B: serde::de::DeserializeOwned + zbus::zvariant::Type,
+    {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while let Some(Ok(message)) = signals.next().await {
+                if message.header().member().is_some_and(|name| name == member) {
+                    return message.body().deserialize::<B>().unwrap();
+                }
+            }
+            panic!("connection closed before {member}");
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn replies_reach_the_app_only_for_inline_reply_notifications() {
+        let mut daemon = serve_peer_daemon().await;
+        let notifications = daemon
+            .names
+            .notifications_proxy(&daemon.client)
+            .await
+            .unwrap();
+        let caps = notifications.get_capabilities().await.unwrap();
+        assert!(caps.iter().any(|cap| cap == INLINE_REPLY_ACTION));
+        let send = |actions: &'static [&'static str]| {
+            notifications.notify(
+                "chat",
+                0,
+                "",
+                "Alice",
+                "lunch?",
+                actions,
+                HashMap::new(),
+                -1,
+            )
+        };
+        let plain = send(&[]).await.unwrap();
+        let chat = send(&[INLINE_REPLY_ACTION, "Reply"]).await.unwrap();
+
+        assert!(daemon.control.reply(plain, "no").await.is_err());
+        daemon.control.reply(chat, "on my way").await.unwrap();
+
+        let replied: (u32, String) = next_signal(&mut daemon.signals, "NotificationReplied").await;
+        assert_eq!(replied, (chat, "on my way".to_string()));
+    }
+
+    #[tokio::test]
+    async fn debug_lines_reach_the_bus_only_while_followed() {
+        let mut daemon = serve_peer_daemon().await;
+        let control = &daemon.control;
+        control
+            .publish_debug_log(PanelDebugLevel::Info, "unheard")
+            .await
+            .unwrap();
+        control
+            .set_panel_debug_level(PanelDebugLevel::Info)
+            .await
+            .unwrap();
+        control
+            .publish_debug_log(PanelDebugLevel::Info, "heard")
+            .await
+            .unwrap();
+
+        let (_, line): (PanelDebugLevel, String) =
+            next_signal(&mut daemon.signals, "DebugLog").await;
+        assert_eq!(line, "heard");
+    }
+}
@@ -1,6 +1,7 @@
 //! D-Bus owner tracking helpers.
 //!
-//! Provides reusable helpers for name ownership checks during startup and trial mode.
+//! Provides reusable helpers for name ownership checks during startup and trial mode, and for
+//! noticing when a client that asked for signals leaves the bus.
 
 use std::time::Duration;
 
@@ -56,6 +57,28 @@ pub(super) async fn wait_for_owner_state(
     }
 }
 
+/// Resolve once `name` has no owner on the bus.
+pub(super) async fn wait_for_owner_gone(
+    connection: &Connection,
+    name: zbus::names::BusName<'_>,
+) -> Result<()> {
+    let dbus_proxy = DBusProxy::new(connection).await?;
+    let name_str = name.to_string();
+    // Subscribe before asking so a departure in between is not missed.
+    let mut stream = dbus_proxy
+        .receive_name_owner_changed_with_args(&[(0, name_str.as_str())])
+        .await?;
+    if !dbus_proxy.name_has_owner(name).await? {
+        return Ok(());
+    }
+    while let Some(signal) = stream.next().await {
+        if signal.args()?.new_owner().is_none() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
 pub(super) async fn log_current_owner(
     dbus_proxy: &DBusProxy<'_>,
     connection: &Connection,
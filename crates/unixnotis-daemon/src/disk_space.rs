@@ -0,0 +1,261 @@
+//! Low disk space warnings for configured mount points.
+//!
+//! Checks free space on the slow refresh interval and keeps at most one notification that lists
+//! every low mount. The notification is replaced when the set of low mounts changes and
+//! withdrawn once they all recover.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::{util, BusNames, DiskConfig};
+use zbus::zvariant::Value;
+
+use crate::daemon::DaemonState;
+use crate::notify_proxy::{NotificationsProxy, Notifier};
+
+/// Extra free space, in percentage points, a low mount needs before it counts as recovered.
+const RECOVERY_MARGIN: f64 = 2.0;
+
+/// Start the disk monitor; call once the notification bus name is owned.
+pub fn spawn_disk_monitor(
+    state: Arc<DaemonState>,
+    names: BusNames,
+    config: DiskConfig,
+    interval: Duration,
+) {
+    if !config.enabled || config.mounts.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(err) = watch_disks(state, names, config, interval).await {
+            warn!(?err, "disk space monitor stopped");
+        }
+    });
+}
+
+async fn watch_disks(
+    state: Arc<DaemonState>,
+    names: BusNames,
+    config: DiskConfig,
+    interval: Duration,
+) -> zbus::Result<()> {
+    let mut notifier = Notifier::new(&state, names).await?;
+    info!(
+        mounts = ?config.mounts,
+        min_free_percent = config.min_free_percent,
+        "watching disk space"
+    );
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut low = LowMounts::new(config.min_free_percent);
+    let mut shown: Option<u32> = None;
+    loop {
+        ticker.tick().await;
+        let mounts = config.mounts.clone();
+        // statvfs can block for a long time on a stale network mount.
+        let usage = match tokio::task::spawn_blocking(move || read_mounts(&mounts)).await {
+            Ok(usage) => usage,
+            Err(err) => {
+                warn!(?err, "disk space check panicked");
+                continue;
+            }
+        };
+        if !low.update(&usage) {
+            continue;
+        }
+        let low_usage: Vec<&DiskUsage> = usage
+            .iter()
+            .filter(|disk| low.contains(&disk.mount))
+            .collect();
+        shown = match notifier.proxy().await {
+            Ok(notifications) => show_warning(notifications, shown, &low_usage).await,
+            Err(err) => {
+                warn!(?err, "failed to send disk space warning");
+                shown
+            }
+        };
+    }
+}
+
+/// Send, replace, or withdraw the warning and return the id now shown.
+async fn show_warning(
+    notifications: &NotificationsProxy<'_>,
+    shown: Option<u32>,
+    low: &[&DiskUsage],
+) -> Option<u32> {
+    if low.is_empty() {
+        debug!("disk space recovered");
+        if let Some(id) = shown {
+            if let Err(err) = notifications.close_notification(id).await {
+                debug!(?err, "failed to withdraw disk space warning");
+            }
+        }
+        return None;
+    }
+    let body = low
+        .iter()
+        .map(|disk| {
+            tr_args(
+                "disk-low-line",
+                &[
+                    ("mount", disk.mount.as_str().into()),
+                    ("free", format_size(disk.free).into()),
+                    ("percent", (disk.free_percent().floor() as u32).into()),
+                ],
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let actions: Vec<&str> = Vec::new();
+    let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+    hints.insert("urgency", Value::U8(1));
+    hints.insert("category", Value::from("x-unixnotis.disk-low"));
+    // Zero keeps the warning up until space recovers or it is dismissed.
+    let expire_timeout = 0;
+    match notifications
+        .notify(
+            "UnixNotis",
+            shown.unwrap_or(0),
+            "drive-harddisk-symbolic",
+            &tr("disk-low-summary"),
+            &body,
+            &actions,
+            hints,
+            expire_timeout,
+        )
+        .await
+    {
+        Ok(id) => Some(id),
+        Err(err) => {
+            warn!(?err, "failed to send disk space warning");
+            shown
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DiskUsage {
+    mount: String,
+    /// Bytes available to unprivileged users.
+    free: u64,
+    total: u64,
+}
+
+impl DiskUsage {
+    fn free_percent(&self) -> f64 {
+        self.free as f64 * 100.0 / self.total as f64
+    }
+}
+
+/// Read every mount, skipping unreadable ones and later mounts on an already listed filesystem.
+fn read_mounts(mounts: &[String]) -> Vec<DiskUsage> {
+    let mut devices = Vec::new();
+    let mut usage = Vec::new();
+    for mount in mounts {
+        let Some(fs) = util::fs_usage(Path::new(mount)) else {
+            debug!(mount, "cannot read free space");
+            continue;
+        };
+        if devices.contains(&fs.device) {
+            continue;
+        }
+        devices.push(fs.device);
+        usage.push(DiskUsage {
+            mount: mount.to_string(),
+            free: fs.available,
+            total: fs.total,
+        });
+    }
+    usage
+}
+
+fn format_size(bytes: u64) -> String {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    const MIB: f64 = 1024.0 * 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= GIB {
+        format!("{:.1} GiB", bytes / GIB)
+    } else {
+        format!("{:.0} MiB", bytes / MIB)
+    }
+}
+
+/// Mounts currently below the threshold.
+struct LowMounts {
+    threshold: f64,
+    mounts: BTreeSet<String>,
+}
+
+impl LowMounts {
+    fn new(min_free_percent: u8) -> Self {
+        Self {
+            threshold: f64::from(min_free_percent),
+            mounts: BTreeSet::new(),
+        }
+    }
+
+    fn contains(&self, mount: &str) -> bool {
+        self.mounts.contains(mount)
+    }
+
+    /// Re-evaluate every mount; returns true when the set of low mounts changed.
+    fn update(&mut self, usage: &[DiskUsage]) -> bool {
+        let mut low = BTreeSet::new();
+        for disk in usage {
+            // Recovery needs some headroom so a mount hovering at the threshold does not flap.
+            let limit = if self.mounts.contains(&disk.mount) {
+                self.threshold + RECOVERY_MARGIN
+            } else {
+                self.threshold
+            };
+            if disk.free_percent() < limit {
+                low.insert(disk.mount.clone());
+            }
+        }
+        let changed = low != self.mounts;
+        self.mounts = low;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(mount: &str, free_percent: u64) -> DiskUsage {
+        DiskUsage {
+            mount: mount.to_string(),
+            free: free_percent,
+            total: 100,
+        }
+    }
+
+    #[test]
+    fn low_mounts_coalesce_and_recover_with_margin() {
+        let mut low = LowMounts::new(10);
+        assert!(!low.update(&[usage("/", 50), usage("/home", 30)]));
+        assert!(low.update(&[usage("/", 9), usage("/home", 30)]));
+        assert!(low.update(&[usage("/", 8), usage("/home", 5)]));
+        assert!(!low.update(&[usage("/", 7), usage("/home", 4)]));
+        // Back at the threshold is not enough to clear the warning.
+        assert!(!low.update(&[usage("/", 11), usage("/home", 4)]));
+        assert!(low.update(&[usage("/", 12), usage("/home", 4)]));
+        assert!(!low.contains("/"));
+        assert!(low.update(&[usage("/", 12), usage("/home", 40)]));
+        assert!(low.mounts.is_empty());
+    }
+
+    #[test]
+    fn format_size_picks_unit() {
+        assert_eq!(
+            format_size(3 * 1024 * 1024 * 1024 + 200 * 1024 * 1024),
+            "3.2 GiB"
+        );
+        assert_eq!(format_size(512 * 1024 * 1024), "512 MiB");
+    }
+}
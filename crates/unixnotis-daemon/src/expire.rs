@@ -3,20 +3,33 @@
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use tokio::sync::mpsc;
 
 use crate::daemon::DaemonState;
 use unixnotis_core::CloseReason;
 
+/// How often history is scanned for entries past their rule TTL.
+const HISTORY_PRUNE_INTERVAL: Duration = Duration::from_secs(30);
+/// Longest single sleep while waiting for timed DND to end. Its end is a wall-clock time that
+/// suspend does not move, unlike the Tokio timer, so the wall clock is re-checked this often.
+/// Notification timeouts are not affected; `suspend_watch` adjusts those on resume.
+const DND_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
 /// Commands sent to the expiration scheduler.
 pub enum ExpirationCommand {
     Schedule { id: u32, deadline: Instant },
     Cancel { id: u32 },
 }
 
-/// Asynchronous expiration manager backed by a priority queue.
+/// Asynchronous expiration manager: one task sleeping until the earliest deadline.
+///
+/// Deadlines are monotonic `Instant`s, so wall-clock jumps never expire anything early.
+/// Across a suspend, `suspend_watch` holds expirations until resume and then reschedules every
+/// deadline. With `popups.pause_during_suspend` the time asleep does not count; otherwise
+/// deadlines are pulled forward by it, and timeouts that ran out while asleep expire on resume.
 #[derive(Clone)]
 pub struct ExpirationScheduler {
     sender: mpsc::UnboundedSender<ExpirationCommand>,
@@ -26,45 +39,25 @@ impl ExpirationScheduler {
     pub fn start(state: Arc<DaemonState>) -> Self {
         let (sender, mut receiver) = mpsc::unbounded_channel();
         tokio::spawn(async move {
-            let mut heap: BinaryHeap<ExpirationItem> = BinaryHeap::new();
-            // Tracks the latest deadline per notification to discard stale heap entries.
-            let mut scheduled: HashMap<u32, Instant> = HashMap::new();
+            let mut queue = ExpirationQueue::default();
             loop {
-                let next_deadline = heap.peek().map(|item| item.deadline);
-                if next_deadline.is_none() {
+                let Some(deadline) = queue.next_deadline() else {
                     let Some(cmd) = receiver.recv().await else {
                         break;
                     };
-                    apply_command(cmd, &mut heap, &mut scheduled);
-                    continue;
-                }
-                let Some(deadline) = next_deadline else {
+                    queue.apply(cmd);
                     continue;
                 };
 
                 tokio::select! {
-                    Some(cmd) = receiver.recv() => {
-                        apply_command(cmd, &mut heap, &mut scheduled);
-                        maybe_compact(&mut heap, &scheduled);
+                    cmd = receiver.recv() => {
+                        let Some(cmd) = cmd else {
+                            break;
+                        };
+                        queue.apply(cmd);
                     }
                     _ = tokio::time::sleep_until(deadline.into()) => {
-                        let now = Instant::now();
-                        while let Some(item) = heap.peek() {
-                            if item.deadline > now {
-                                break;
-                            }
-                            let Some(item) = heap.pop() else {
-                                break;
-                            };
-                            let is_current = scheduled
-                                .get(&item.id)
-                                .map(|deadline| *deadline == item.deadline)
-                                .unwrap_or(false);
-                            if !is_current {
-                                continue;
-                            }
-                            // Remove the scheduled entry once the matching deadline is handled.
-                            scheduled.remove(&item.id);
+                        for item in queue.pop_due(Instant::now()) {
                             // Verify the deadline is still current before closing the notification.
                             let should_expire = {
                                 let store = state.store.lock().await;
@@ -77,9 +70,7 @@ impl ExpirationScheduler {
                                 let _ = state.close_notification(item.id, CloseReason::Expired).await;
                             }
                         }
-                        maybe_compact(&mut heap, &scheduled);
                     }
-                    else => break,
                 };
             }
         });
@@ -101,6 +92,36 @@ impl ExpirationScheduler {
     }
 }
 
+/// Periodically prune history entries archived with a rule `history_ttl_minutes`.
+pub fn spawn_history_pruner(state: Arc<DaemonState>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HISTORY_PRUNE_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(err) = state.prune_history().await {
+                tracing::warn!(?err, "failed to prune history");
+            }
+        }
+    });
+}
+
+/// Turn Do Not Disturb off once the wall clock reaches `until`.
+pub fn schedule_dnd_end(state: Arc<DaemonState>, until: DateTime<Utc>) {
+    tokio::spawn(async move {
+        loop {
+            let remaining = (until - Utc::now()).to_std().unwrap_or_default();
+            if remaining.is_zero() {
+                break;
+            }
+            tokio::time::sleep(remaining.min(DND_CHECK_INTERVAL)).await;
+        }
+        if let Err(err) = state.end_timed_dnd(until).await {
+            tracing::warn!(?err, "failed to end timed do not disturb");
+        }
+    });
+}
+
 #[derive(Debug, Copy, Clone)]
 struct ExpirationItem {
     id: u32,
@@ -128,40 +149,165 @@ impl Ord for ExpirationItem {
     }
 }
 
-fn apply_command(
-    cmd: ExpirationCommand,
-    heap: &mut BinaryHeap<ExpirationItem>,
-    scheduled: &mut HashMap<u32, Instant>,
-) {
-    match cmd {
-        ExpirationCommand::Schedule { id, deadline } => {
-            // Keep the newest deadline and push to the heap for ordering.
-            scheduled.insert(id, deadline);
-            heap.push(ExpirationItem { id, deadline });
+/// Deadline queue behind the scheduler task: a min-heap plus the live deadline per id.
+///
+/// Rescheduling and cancelling (including hover pauses) only touch the map; heap entries whose
+/// deadline is no longer live are skipped when they surface and dropped by compaction.
+#[derive(Default)]
+struct ExpirationQueue {
+    heap: BinaryHeap<ExpirationItem>,
+    scheduled: HashMap<u32, Instant>,
+}
+
+impl ExpirationQueue {
+    fn apply(&mut self, cmd: ExpirationCommand) {
+        match cmd {
+            ExpirationCommand::Schedule { id, deadline } => {
+                // Keep the newest deadline and push to the heap for ordering.
+                self.scheduled.insert(id, deadline);
+                self.heap.push(ExpirationItem { id, deadline });
+            }
+            ExpirationCommand::Cancel { id } => {
+                self.scheduled.remove(&id);
+            }
         }
-        ExpirationCommand::Cancel { id } => {
-            // Cancel only updates the tracking map; stale heap entries are ignored.
-            scheduled.remove(&id);
+        self.compact();
+    }
+
+    /// Earliest live deadline; stale entries on top are discarded so they cause no wake-up.
+    fn next_deadline(&mut self) -> Option<Instant> {
+        while let Some(item) = self.heap.peek() {
+            if self.is_live(item) {
+                return Some(item.deadline);
+            }
+            self.heap.pop();
         }
+        None
+    }
+
+    /// Remove and return every live entry due at `now`, earliest first.
+    fn pop_due(&mut self, now: Instant) -> Vec<ExpirationItem> {
+        let mut due = Vec::new();
+        while let Some(item) = self.heap.peek().copied() {
+            if item.deadline > now {
+                break;
+            }
+            self.heap.pop();
+            if self.is_live(&item) {
+                self.scheduled.remove(&item.id);
+                due.push(item);
+            }
+        }
+        self.compact();
+        due
+    }
+
+    fn is_live(&self, item: &ExpirationItem) -> bool {
+        self.scheduled.get(&item.id) == Some(&item.deadline)
+    }
+
+    fn compact(&mut self) {
+        let live = self.scheduled.len();
+        if live == 0 {
+            self.heap.clear();
+            return;
+        }
+        let threshold = live.saturating_mul(4).max(128);
+        if self.heap.len() <= threshold {
+            return;
+        }
+        self.heap = self
+            .scheduled
+            .iter()
+            .map(|(id, deadline)| ExpirationItem {
+                id: *id,
+                deadline: *deadline,
+            })
+            .collect();
     }
 }
 
-fn maybe_compact(heap: &mut BinaryHeap<ExpirationItem>, scheduled: &HashMap<u32, Instant>) {
-    let live = scheduled.len();
-    if live == 0 {
-        heap.clear();
-        return;
-    }
-    let threshold = live.saturating_mul(4).max(128);
-    if heap.len() <= threshold {
-        return;
-    }
-    let mut rebuilt = BinaryHeap::with_capacity(live);
-    for (id, deadline) in scheduled {
-        rebuilt.push(ExpirationItem {
-            id: *id,
-            deadline: *deadline,
-        });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(queue: &mut ExpirationQueue, id: u32, deadline: Instant) {
+        queue.apply(ExpirationCommand::Schedule { id, deadline });
+    }
+
+    fn ids(items: Vec<ExpirationItem>) -> Vec<u32> {
+        items.into_iter().map(|item| item.id).collect()
+    }
+
+    #[test]
+    fn rescheduling_keeps_only_the_latest_deadline() {
+        let start = Instant::now();
+        let mut queue = ExpirationQueue::default();
+        schedule(&mut queue, 1, start + Duration::from_secs(10));
+        schedule(&mut queue, 1, start + Duration::from_secs(5));
+        assert_eq!(queue.next_deadline(), Some(start + Duration::from_secs(5)));
+        assert_eq!(ids(queue.pop_due(start + Duration::from_secs(6))), vec![1]);
+        // The superseded deadline is gone and never fires.
+        assert_eq!(queue.next_deadline(), None);
+        assert!(queue.pop_due(start + Duration::from_secs(11)).is_empty());
+    }
+
+    #[test]
+    fn paused_entries_do_not_wake_the_scheduler() {
+        let start = Instant::now();
+        let mut queue = ExpirationQueue::default();
+        schedule(&mut queue, 1, start + Duration::from_secs(1));
+        schedule(&mut queue, 2, start + Duration::from_secs(5));
+        // A hover pause cancels; resuming schedules the remaining time again.
+        queue.apply(ExpirationCommand::Cancel { id: 1 });
+        assert_eq!(queue.next_deadline(), Some(start + Duration::from_secs(5)));
+        schedule(&mut queue, 1, start + Duration::from_secs(8));
+        assert_eq!(
+            ids(queue.pop_due(start + Duration::from_secs(8))),
+            vec![2, 1]
+        );
+    }
+
+    #[test]
+    fn overdue_entries_after_a_stall_expire_in_deadline_order() {
+        // Covers waking late, whether from suspend or a blocked runtime: everything overdue
+        // comes out in one pass, earliest first.
+        let start = Instant::now();
+        let mut queue = ExpirationQueue::default();
+        schedule(&mut queue, 1, start + Duration::from_secs(3));
+        schedule(&mut queue, 2, start + Duration::from_secs(1));
+        schedule(&mut queue, 3, start + Duration::from_secs(2));
+        schedule(&mut queue, 4, start + Duration::from_secs(600));
+        assert_eq!(
+            ids(queue.pop_due(start + Duration::from_secs(60))),
+            vec![2, 3, 1]
+        );
+        assert_eq!(
+            queue.next_deadline(),
+            Some(start + Duration::from_secs(600))
+        );
+    }
+
+    #[test]
+    fn deadlines_already_past_are_due_immediately() {
+        // A deadline computed before a delay in reaching the scheduler may already be behind.
+        let now = Instant::now();
+        let mut queue = ExpirationQueue::default();
+        schedule(&mut queue, 7, now);
+        assert_eq!(ids(queue.pop_due(now)), vec![7]);
+    }
+
+    #[test]
+    fn repeated_rescheduling_keeps_the_heap_bounded() {
+        let start = Instant::now();
+        let mut queue = ExpirationQueue::default();
+        for step in 0..1_000 {
+            schedule(&mut queue, 1, start + Duration::from_millis(step));
+        }
+        assert!(queue.heap.len() <= 128);
+        assert_eq!(
+            queue.next_deadline(),
+            Some(start + Duration::from_millis(999))
+        );
     }
-    *heap = rebuilt;
 }
@@ -0,0 +1,327 @@
+//! Saved notification history for `history.persist`.
+//!
+//! History is written as JSON under the state directory shortly after it changes and once more
+//! at shutdown, then restored before the daemon starts serving. Hints and raw image data are
+//! not saved; entries keep their icon name and image path.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use unixnotis_core::{Action, BusNames, Config, Notification, NotificationImage, Urgency};
+
+use crate::daemon::DaemonState;
+
+/// Delay between a history change and the write that saves it.
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+const FORMAT_VERSION: u32 = 1;
+
+pub struct HistoryFile {
+    path: PathBuf,
+    /// Store history revision last written, so unchanged history is not rewritten.
+    saved_revision: AtomicU64,
+}
+
+impl HistoryFile {
+    /// Locate the history file; instances with a bus name suffix keep their own.
+    pub fn for_names(names: &BusNames) -> Option<Self> {
+        let dir = match Config::default_state_dir() {
+            Ok(dir) => dir,
+            Err(err) => {
+                warn!(?err, "no state directory; history will not be saved");
+                return None;
+            }
+        };
+        let file = match names.suffix() {
+            Some(suffix) => format!("history-{suffix}.json"),
+            None => "history.json".to_string(),
+        };
+        Some(Self {
+            path: dir.join(file),
+            saved_revision: AtomicU64::new(u64::MAX),
+        })
+    }
+
+    fn read(&self) -> io::Result<Vec<StoredEntry>> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let document: HistoryDocument = serde_json::from_slice(&bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if document.version != FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported history format {}", document.version),
+            ));
+        }
+        Ok(document.entries)
+    }
+
+    /// Replace the file atomically so a crash mid-write keeps the previous copy.
+    fn write(&self, document: &HistoryDocument) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec(document)?;
+        let tmp = self.path.with_extension("json.tmp");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp)?;
+        file.write_all(&json)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// Load saved history into the store; call before the daemon starts serving.
+pub async fn restore_history(state: &DaemonState, file: &Arc<HistoryFile>) {
+    let reader = file.clone();
+    let entries = match tokio::task::spawn_blocking(move || reader.read()).await {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(err)) => {
+            warn!(?err, path = %file.path.display(), "failed to read saved history");
+            return;
+        }
+        Err(err) => {
+            warn!(?err, "reading saved history panicked");
+            return;
+        }
+    };
+    let count = entries.len();
+    let entries = entries
+        .into_iter()
+        .filter_map(StoredEntry::into_notification)
+        .collect();
+    let mut store = state.store.lock().await;
+    store.restore_history(entries);
+    file.saved_revision
+        .store(store.history_revision(), Ordering::Relaxed);
+    info!(
+        saved = count,
+        restored = store.history_len(),
+        "restored notification history"
+    );
+}
+
+/// Periodically save history while it keeps changing.
+pub fn spawn_history_writer(state: Arc<DaemonState>, file: Arc<HistoryFile>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SAVE_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            save_history(&state, &file).await;
+        }
+    });
+}
+
+/// Write history if it changed since the last save; also used for the final save at shutdown.
+pub async fn save_history(state: &DaemonState, file: &Arc<HistoryFile>) {
+    let (revision, document) = {
+        let store = state.store.lock().await;
+        let revision = store.history_revision();
+        if revision == file.saved_revision.load(Ordering::Relaxed) {
+            return;
+        }
+        let now = Instant::now();
+        let wall_now = Utc::now();
+        let entries = store
+            .history_snapshot()
+            .iter()
+            .map(|(notification, deadline)| {
+                StoredEntry::from_notification(notification, *deadline, now, wall_now)
+            })
+            .collect();
+        (
+            revision,
+            HistoryDocument {
+                version: FORMAT_VERSION,
+                entries,
+            },
+        )
+    };
+    let writer = file.clone();
+    match tokio::task::spawn_blocking(move || writer.write(&document)).await {
+        Ok(Ok(())) => {
+            file.saved_revision.store(revision, Ordering::Relaxed);
+            debug!(path = %file.path.display(), "saved notification history");
+        }
+        Ok(Err(err)) => warn!(?err, path = %file.path.display(), "failed to save history"),
+        Err(err) => warn!(?err, "saving history panicked"),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HistoryDocument {
+    version: u32,
+    /// Oldest entry first, matching the store's archive order.
+    entries: Vec<StoredEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StoredEntry {
+    id: u32,
+    app_name: String,
+    #[serde(default)]
+    app_icon: String,
+    summary: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    actions: Vec<(String, String)>,
+    urgency: u8,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    is_transient: bool,
+    #[serde(default)]
+    is_resident: bool,
+    #[serde(default)]
+    image_path: String,
+    #[serde(default)]
+    icon_name: String,
+    received_at_unix_ms: i64,
+    #[serde(default)]
+    history_ttl_minutes: Option<u32>,
+    /// Wall-clock time a rule TTL removes the entry, carried over restarts.
+    #[serde(default)]
+    prune_at_unix_ms: Option<i64>,
+    #[serde(default)]
+    pinned: bool,
+}
+
+impl StoredEntry {
+    fn from_notification(
+        notification: &Notification,
+        deadline: Option<Instant>,
+        now: Instant,
+        wall_now: DateTime<Utc>,
+    ) -> Self {
+        let prune_at = deadline.map(|deadline| {
+            let remaining = deadline.saturating_duration_since(now);
+            wall_now + chrono::Duration::from_std(remaining).unwrap_or_default()
+        });
+        Self {
+            id: notification.id,
+            app_name: notification.app_name.clone(),
+            app_icon: notification.app_icon.clone(),
+            summary: notification.summary.clone(),
+            body: notification.body.clone(),
+            actions: notification
+                .actions
+                .iter()
+                .map(|action| (action.key.clone(), action.label.clone()))
+                .collect(),
+            urgency: notification.urgency.as_u8(),
+            category: notification.category.clone(),
+            is_transient: notification.is_transient,
+            is_resident: notification.is_resident,
+            image_path: notification.image.image_path.clone(),
+            icon_name: notification.image.icon_name.clone(),
+            received_at_unix_ms: notification.received_at.timestamp_millis(),
+            history_ttl_minutes: notification.history_ttl_minutes,
+            prune_at_unix_ms: prune_at.map(|at| at.timestamp_millis()),
+            pinned: notification.pinned,
+        }
+    }
+
+    /// Rebuild the history record; entries with an unusable id or timestamp are skipped.
+    fn into_notification(self) -> Option<(Notification, Option<Instant>)> {
+        if self.id == 0 {
+            return None;
+        }
+        let received_at = DateTime::from_timestamp_millis(self.received_at_unix_ms)?;
+        let deadline = self
+            .prune_at_unix_ms
+            .and_then(DateTime::from_timestamp_millis)
+            .map(|at: DateTime<Utc>| {
+                Instant::now() + (at - Utc::now()).to_std().unwrap_or_default()
+            });
+        let notification = Notification {
+            id: self.id,
+            app_name: self.app_name,
+            app_icon: self.app_icon,
+            summary: self.summary,
+            body: self.body,
+            actions: self
+                .actions
+                .into_iter()
+                .map(|(key, label)| Action { key, label })
+                .collect(),
+            hints: Default::default(),
+            urgency: match self.urgency {
+                0 => Urgency::Low,
+                2 => Urgency::Critical,
+                _ => Urgency::Normal,
+            },
+            category: self.category,
+            is_transient: self.is_transient,
+            is_resident: self.is_resident,
+            suppress_popup: false,
+            suppress_sound: false,
+            image: NotificationImage {
+                image_path: self.image_path,
+                icon_name: self.icon_name,
+                ..NotificationImage::default()
+            },
+            expire_timeout: -1,
+            received_at,
+            history_ttl_minutes: self.history_ttl_minutes,
+            clamp_timeout: true,
+            sender: None,
+            pinned: self.pinned,
+            progress: None,
+        };
+        Some((notification, deadline))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_entry_round_trips_through_json() {
+        let json = r#"{
+            "id": 7,
+            "app_name": "mail",
+            "summary": "New message",
+            "actions": [["default", "Open"]],
+            "urgency": 2,
+            "icon_name": "mail-unread",
+            "received_at_unix_ms": 1700000000000,
+            "history_ttl_minutes": 30,
+            "prune_at_unix_ms": 1
+        }"#;
+        let entry: StoredEntry = serde_json::from_str(json).expect("entry parses");
+        let (notification, deadline) = entry.clone().into_notification().expect("valid entry");
+        assert_eq!(notification.urgency, Urgency::Critical);
+        assert_eq!(notification.actions[0].label, "Open");
+        assert_eq!(notification.image.icon_name, "mail-unread");
+        // A TTL that ran out while the daemon was stopped prunes on the next pass.
+        let now = Instant::now();
+        assert!(deadline.is_some_and(|deadline| deadline <= now));
+
+        let wall_now = Utc::now();
+        let saved = StoredEntry::from_notification(&notification, None, now, wall_now);
+        assert_eq!(
+            saved,
+            StoredEntry {
+                prune_at_unix_ms: None,
+                ..entry
+            }
+        );
+    }
+}
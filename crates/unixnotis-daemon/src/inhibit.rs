@@ -0,0 +1,445 @@
+//! Automatic suppression while something on screen should not be interrupted.
+//!
+//! Each `general.suppress_when_*` key adds one source, and the keys follow config reloads.
+//! Fullscreen windows and screencasts come from Hyprland's event socket. Screensaver inhibitors
+//! come from serving `org.freedesktop.ScreenSaver` ourselves, which only works while no other
+//! service owns it; each inhibitor is passed on to logind as an idle lock so idle managers that
+//! honor logind still stay awake, and a service that asks to replace us gets the name. Any
+//! active source holds back popups and sounds, and sends non-critical notifications straight
+//! to history.
+
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+use unixnotis_core::GeneralConfig;
+use zbus::fdo::{DBusProxy, RequestNameFlags, RequestNameReply};
+use zbus::message::Header;
+use zbus::zvariant::OwnedFd;
+use zbus::{interface, proxy, Connection};
+
+use crate::daemon::DaemonState;
+
+const SCREENSAVER_NAME: &str = "org.freedesktop.ScreenSaver";
+/// Apps call the screensaver at either path depending on their toolkit.
+const SCREENSAVER_PATHS: [&str; 2] = ["/org/freedesktop/ScreenSaver", "/ScreenSaver"];
+const HYPRLAND_RETRY: Duration = Duration::from_secs(5);
+
+/// Why notifications are held back without DND.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InhibitReason {
+    Fullscreen,
+    Screencast,
+    Inhibitor,
+}
+
+impl InhibitReason {
+    /// Name reported in `ControlState::inhibited_by`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InhibitReason::Fullscreen => "fullscreen",
+            InhibitReason::Screencast => "screencast",
+            InhibitReason::Inhibitor => "inhibitor",
+        }
+    }
+}
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LoginManager {
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+}
+
+/// Follow reloaded `suppress_when_*` keys; a source turned off releases what it held back.
+pub async fn reload_suppression(
+    state: &Arc<DaemonState>,
+    before: &GeneralConfig,
+    after: &GeneralConfig,
+) {
+    let hyprland = |general: &GeneralConfig| {
+        (
+            general.suppress_when_fullscreen,
+            general.suppress_when_screencast,
+        )
+    };
+    if hyprland(before) != hyprland(after) {
+        state.hyprland_watch.replace(None);
+        for reason in [InhibitReason::Fullscreen, InhibitReason::Screencast] {
+            if let Err(err) = state.set_inhibited(reason, false).await {
+                warn!(?err, "failed to announce suppression change");
+            }
+        }
+        spawn_hyprland_watch(state.clone(), after);
+    }
+    if before.suppress_when_inhibited != after.suppress_when_inhibited {
+        if after.suppress_when_inhibited {
+            spawn_screensaver(state.clone(), after);
+        } else {
+            stop_screensaver(state).await;
+        }
+    }
+}
+
+/// Start the Hyprland watcher for the fullscreen and screencast keys that are on.
+pub fn spawn_hyprland_watch(state: Arc<DaemonState>, general: &GeneralConfig) {
+    let fullscreen = general.suppress_when_fullscreen;
+    let screencast = general.suppress_when_screencast;
+    if !fullscreen && !screencast {
+        return;
+    }
+    let Some(dir) = hyprland_dir() else {
+        info!("not running under Hyprland; fullscreen and screencast suppression is off");
+        return;
+    };
+    let slot = state.clone();
+    let task = tokio::spawn(async move {
+        loop {
+            if let Err(err) = watch_hyprland(&state, &dir, fullscreen, screencast).await {
+                debug!(?err, "Hyprland event stream failed");
+            }
+            // Nothing reports the end of a fullscreen or screencast from a dead compositor.
+            for reason in [InhibitReason::Fullscreen, InhibitReason::Screencast] {
+                if let Err(err) = state.set_inhibited(reason, false).await {
+                    warn!(?err, "failed to announce suppression change");
+                }
+            }
+            tokio::time::sleep(HYPRLAND_RETRY).await;
+        }
+    });
+    slot.hyprland_watch.replace(Some(task.abort_handle()));
+}
+
+fn hyprland_dir() -> Option<PathBuf> {
+    let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").ok()?;
+    if signature.is_empty() || runtime_dir.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(runtime_dir).join("hypr").join(signature))
+}
+
+async fn watch_hyprland(
+    state: &DaemonState,
+    dir: &Path,
+    fullscreen: bool,
+    screencast: bool,
+) -> std::io::Result<()> {
+    let stream = UnixStream::connect(dir.join(".socket2.sock")).await?;
+    if fullscreen {
+        refresh_fullscreen(state, dir).await;
+    }
+    let mut lines = BufReader::new(stream).lines();
+    while let Some(line) = lines.next_line().await? {
+        let (event, data) = line.split_once(">>").unwrap_or((line.as_str(), ""));
+        match event {
+            // Focus and workspace changes can bring a fullscreen window forward or hide it.
+            "fullscreen" | "activewindowv2" | "workspacev2" | "focusedmon" | "closewindow"
+                if fullscreen =>
+            {
+                refresh_fullscreen(state, dir).await;
+            }
+            // `screencast>>STATE,OWNER`, where STATE is 1 while a capture runs.
+            "screencast" if screencast => {
+                let active = data.split(',').next() == Some("1");
+                if let Err(err) = state.set_inhibited(InhibitReason::Screencast, active).await {
+                    warn!(?err, "failed to announce suppression change");
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+async fn refresh_fullscreen(state: &DaemonState, dir: &Path) {
+    let active = match hyprland_request(dir, "j/activewindow").await {
+        Ok(reply) => serde_json::from_str::<Value>(&reply)
+            .map(|window| is_fullscreen(&window))
+            .unwrap_or(false),
+        Err(err) => {
+            debug!(?err, "failed to query the active Hyprland window");
+            return;
+        }
+    };
+    if let Err(err) = state.set_inhibited(InhibitReason::Fullscreen, active).await {
+        warn!(?err, "failed to announce suppression change");
+    }
+}
+
+async fn hyprland_request(dir: &Path, command: &str) -> std::io::Result<String> {
+    let mut stream = UnixStream::connect(dir.join(".socket.sock")).await?;
+    stream.write_all(command.as_bytes()).await?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).await?;
+    Ok(reply)
+}
+
+/// Whether Hyprland's `activewindow` reply describes a real fullscreen window.
+///
+/// Newer releases report `fullscreen` as a mode where 2 is fullscreen and 1 is maximized;
+/// older ones report a bool with `fullscreenMode` 0 for fullscreen.
+fn is_fullscreen(window: &Value) -> bool {
+    match window.get("fullscreen") {
+        Some(Value::Bool(fullscreen)) => {
+            *fullscreen && window.get("fullscreenMode").and_then(Value::as_u64) != Some(1)
+        }
+        Some(Value::Number(mode)) => mode.as_u64().is_some_and(|mode| mode >= 2),
+        _ => false,
+    }
+}
+
+/// Screensaver inhibitors by cookie.
+#[derive(Default)]
+struct Inhibitors {
+    next_cookie: u32,
+    held: HashMap<u32, Holder>,
+}
+
+struct Holder {
+    /// Bus name of the app that asked.
+    sender: String,
+    /// logind's idle inhibitor for this cookie; dropping the fd releases it.
+    _idle_lock: Option<OwnedFd>,
+}
+
+struct ScreenSaver {
+    state: Arc<DaemonState>,
+    inhibitors: Arc<Mutex<Inhibitors>>,
+    login: Option<LoginManagerProxy<'static>>,
+}
+
+#[interface(name = "org.freedesktop.ScreenSaver")]
+impl ScreenSaver {
+    async fn inhibit(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        application_name: String,
+        reason_for_inhibit: String,
+    ) -> u32 {
+        let sender = header
+            .sender()
+            .map(|sender| sender.to_string())
+            .unwrap_or_default();
+        // Holding the name keeps real idle managers from seeing the call, so logind gets it.
+        let idle_lock = match &self.login {
+            Some(login) => match login
+                .inhibit("idle", &application_name, &reason_for_inhibit, "block")
+                .await
+            {
+                Ok(fd) => Some(fd),
+                Err(err) => {
+                    debug!(?err, "logind refused the idle inhibitor");
+                    None
+                }
+            },
+            None => None,
+        };
+        let cookie = {
+            let mut inhibitors = self.inhibitors.lock().await;
+            inhibitors.next_cookie = inhibitors.next_cookie.wrapping_add(1).max(1);
+            let cookie = inhibitors.next_cookie;
+            inhibitors.held.insert(
+                cookie,
+                Holder {
+                    sender,
+                    _idle_lock: idle_lock,
+                },
+            );
+            cookie
+        };
+        debug!(
+            app = %application_name,
+            reason = %reason_for_inhibit,
+            cookie,
+            "screensaver inhibited"
+        );
+        if let Err(err) = self
+            .state
+            .set_inhibited(InhibitReason::Inhibitor, true)
+            .await
+        {
+            warn!(?err, "failed to announce suppression change");
+        }
+        cookie
+    }
+
+    async fn un_inhibit(&self, cookie: u32) {
+        let empty = {
+            let mut inhibitors = self.inhibitors.lock().await;
+            inhibitors.held.remove(&cookie);
+            inhibitors.held.is_empty()
+        };
+        debug!(cookie, "screensaver inhibitor released");
+        if empty {
+            if let Err(err) = self
+                .state
+                .set_inhibited(InhibitReason::Inhibitor, false)
+                .await
+            {
+                warn!(?err, "failed to announce suppression change");
+            }
+        }
+    }
+}
+
+/// Serve `org.freedesktop.ScreenSaver` when `general.suppress_when_inhibited` is on and no
+/// other service owns the name. Called again after the session bus restarts.
+pub fn spawn_screensaver(state: Arc<DaemonState>, general: &GeneralConfig) {
+    if !general.suppress_when_inhibited {
+        return;
+    }
+    let slot = state.clone();
+    let task = tokio::spawn(async move {
+        if let Err(err) = serve_screensaver(state).await {
+            warn!(?err, "screensaver inhibitor tracking stopped");
+        }
+    });
+    slot.screensaver.replace(Some(task.abort_handle()));
+}
+
+/// Stop serving the screensaver and hand the name back.
+async fn stop_screensaver(state: &DaemonState) {
+    state.screensaver.replace(None);
+    withdraw_screensaver(state).await;
+}
+
+async fn serve_screensaver(state: Arc<DaemonState>) -> zbus::Result<()> {
+    // Inhibitors from before a bus restart went with the old map.
+    state.set_inhibited(InhibitReason::Inhibitor, false).await?;
+    let connection = state.connection();
+    let reply = connection
+        .request_name_with_flags(
+            SCREENSAVER_NAME,
+            RequestNameFlags::DoNotQueue | RequestNameFlags::AllowReplacement,
+        )
+        .await?;
+    if !matches!(
+        reply,
+        RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner
+    ) {
+        info!("another service owns {SCREENSAVER_NAME}; screensaver inhibitors are not seen");
+        return Ok(());
+    }
+    let login = match Connection::system().await {
+        Ok(system) => LoginManagerProxy::new(&system)
+            .await
+            .map_err(|err| debug!(?err, "logind unavailable"))
+            .ok(),
+        Err(err) => {
+            debug!(?err, "no system bus; inhibitors will not block idle");
+            None
+        }
+    };
+    let inhibitors = Arc::new(Mutex::new(Inhibitors::default()));
+    for path in SCREENSAVER_PATHS {
+        connection
+            .object_server()
+            .at(
+                path,
+                ScreenSaver {
+                    state: state.clone(),
+                    inhibitors: inhibitors.clone(),
+                    login: login.clone(),
+                },
+            )
+            .await?;
+    }
+    let dbus = DBusProxy::new(&connection).await?;
+    let mut lost = dbus
+        .receive_name_lost_with_args(&[(0, SCREENSAVER_NAME)])
+        .await?;
+    tokio::select! {
+        result = release_departed_holders(&dbus, &state, &inhibitors) => result,
+        Some(_) = lost.next() => {
+            info!("another service took over {SCREENSAVER_NAME}");
+            inhibitors.lock().await.held.clear();
+            withdraw_screensaver(&state).await;
+            Ok(())
+        }
+    }
+}
+
+/// Remove the screensaver objects, release the name, and stop holding anything back.
+async fn withdraw_screensaver(state: &DaemonState) {
+    let connection = state.connection();
+    for path in SCREENSAVER_PATHS {
+        if let Err(err) = connection
+            .object_server()
+            .remove::<ScreenSaver, _>(path)
+            .await
+        {
+            debug!(?err, path, "failed to remove screensaver object");
+        }
+    }
+    if let Err(err) = connection.release_name(SCREENSAVER_NAME).await {
+        debug!(?err, "failed to release {SCREENSAVER_NAME}");
+    }
+    if let Err(err) = state.set_inhibited(InhibitReason::Inhibitor, false).await {
+        warn!(?err, "failed to announce suppression change");
+    }
+}
+
+/// Drop the inhibitors of apps that leave the bus without calling UnInhibit.
+async fn release_departed_holders(
+    dbus: &DBusProxy<'_>,
+    state: &DaemonState,
+    inhibitors: &Mutex<Inhibitors>,
+) -> zbus::Result<()> {
+    let mut changes = dbus
+        .receive_name_owner_changed_with_args(&[(2, "")])
+        .await?;
+    while let Some(signal) = changes.next().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        if args.new_owner().is_some() {
+            continue;
+        }
+        let name = args.name().as_str();
+        let empty = {
+            let mut inhibitors = inhibitors.lock().await;
+            let before = inhibitors.held.len();
+            inhibitors.held.retain(|_, holder| holder.sender != name);
+            if inhibitors.held.len() == before {
+                continue;
+            }
+            inhibitors.held.is_empty()
+        };
+        debug!(name, "released inhibitors of a departed app");
+        if empty {
+            state.set_inhibited(InhibitReason::Inhibitor, false).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_true_fullscreen_counts() {
+        let window = |json: &str| serde_json::from_str::<Value>(json).expect("valid json");
+        assert!(is_fullscreen(&window(r#"{"fullscreen": 2}"#)));
+        assert!(!is_fullscreen(&window(r#"{"fullscreen": 1}"#)));
+        assert!(!is_fullscreen(&window(r#"{"fullscreen": 0}"#)));
+        assert!(is_fullscreen(&window(
+            r#"{"fullscreen": true, "fullscreenMode": 0}"#
+        )));
+        assert!(!is_fullscreen(&window(
+            r#"{"fullscreen": true, "fullscreenMode": 1}"#
+        )));
+        assert!(!is_fullscreen(&window("{}")));
+    }
+}
@@ -0,0 +1,167 @@
+//! Opt-in popup latency measurement (`general.measure_latency`).
+//!
+//! The daemon stamps each Notify call on arrival and again once `popup_shown` is sent. The
+//! popup process reports how long it took from receiving that signal to painting the popup.
+//! What remains of the round trip is bus time, counting both the signal and the report, so a
+//! slow popup can be pinned on the daemon, the bus, or GTK.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, info};
+
+/// Popups that never report (hidden in a collapsed group, or popups not running) age out.
+const MAX_PENDING: usize = 64;
+/// Percentiles cover this many of the latest samples.
+const MAX_SAMPLES: usize = 200;
+/// A summary is logged after this many new samples.
+const LOG_EVERY: usize = 20;
+
+/// Timestamps for a popup that has been signalled but not yet reported as painted.
+struct Pending {
+    received: Instant,
+    signalled: Instant,
+}
+
+/// One popup's latency split by stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sample {
+    /// Notify arrival until `popup_shown` was sent.
+    daemon: Duration,
+    /// Signal delivery plus the report's way back.
+    bus: Duration,
+    /// Signal arrival in the popup process until the popup was painted.
+    render: Duration,
+    total: Duration,
+}
+
+#[derive(Default)]
+pub struct LatencyProbe {
+    pending: HashMap<u32, Pending>,
+    /// Ids in `pending`, oldest first.
+    order: VecDeque<u32>,
+    samples: VecDeque<Sample>,
+    since_log: usize,
+}
+
+impl LatencyProbe {
+    /// Note that `popup_shown` is going out for `id`, whose Notify call arrived at `received`.
+    pub fn signalled(&mut self, id: u32, received: Instant) {
+        self.order.retain(|pending| *pending != id);
+        self.order.push_back(id);
+        self.pending.insert(
+            id,
+            Pending {
+                received,
+                signalled: Instant::now(),
+            },
+        );
+        while self.order.len() > MAX_PENDING {
+            if let Some(oldest) = self.order.pop_front() {
+                self.pending.remove(&oldest);
+            }
+        }
+    }
+
+    /// Record the popup process's report that `id` painted `render` after its signal arrived.
+    pub fn rendered(&mut self, id: u32, render: Duration) {
+        let Some(pending) = self.pending.remove(&id) else {
+            return;
+        };
+        self.order.retain(|other| *other != id);
+        let sample = split(&pending, render, Instant::now());
+        debug!(
+            id,
+            total_ms = ms(sample.total),
+            daemon_ms = ms(sample.daemon),
+            bus_ms = ms(sample.bus),
+            render_ms = ms(sample.render),
+            "popup latency"
+        );
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        self.since_log += 1;
+        if self.since_log >= LOG_EVERY {
+            self.since_log = 0;
+            self.log_summary();
+        }
+    }
+
+    fn log_summary(&self) {
+        let stage = |pick: fn(&Sample) -> Duration| {
+            let mut values: Vec<Duration> = self.samples.iter().map(pick).collect();
+            values.sort_unstable();
+            format!(
+                "{:.1}/{:.1}ms",
+                ms(percentile(&values, 0.5)),
+                ms(percentile(&values, 0.95))
+            )
+        };
+        info!(
+            samples = self.samples.len(),
+            total = %stage(|sample| sample.total),
+            daemon = %stage(|sample| sample.daemon),
+            bus = %stage(|sample| sample.bus),
+            render = %stage(|sample| sample.render),
+            "popup latency p50/p95"
+        );
+    }
+}
+
+fn split(pending: &Pending, render: Duration, now: Instant) -> Sample {
+    let total = now.saturating_duration_since(pending.received);
+    let daemon = pending
+        .signalled
+        .saturating_duration_since(pending.received);
+    // The popup's own clock cannot be compared with ours, so the bus gets what is left.
+    let render = render.min(total.saturating_sub(daemon));
+    Sample {
+        daemon,
+        bus: total.saturating_sub(daemon).saturating_sub(render),
+        render,
+        total,
+    }
+}
+
+/// Nearest-rank percentile of `sorted`; zero when there are no samples.
+fn percentile(sorted: &[Duration], fraction: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((sorted.len() - 1) as f64 * fraction).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bus_time_is_what_the_daemon_and_popup_do_not_account_for() {
+        let received = Instant::now();
+        let pending = Pending {
+            received,
+            signalled: received + Duration::from_millis(2),
+        };
+        let sample = split(
+            &pending,
+            Duration::from_millis(10),
+            received + Duration::from_millis(15),
+        );
+        assert_eq!(sample.daemon, Duration::from_millis(2));
+        assert_eq!(sample.render, Duration::from_millis(10));
+        assert_eq!(sample.bus, Duration::from_millis(3));
+        assert_eq!(sample.total, Duration::from_millis(15));
+
+        let values: Vec<Duration> = (1..=20).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&values, 0.5), Duration::from_millis(11));
+        assert_eq!(percentile(&values, 0.95), Duration::from_millis(19));
+        assert_eq!(percentile(&[], 0.5), Duration::ZERO);
+    }
+}
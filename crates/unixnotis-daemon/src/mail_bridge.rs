@@ -0,0 +1,685 @@
+//! New-mail notifications over IMAP IDLE (`imap` feature).
+//!
+//! Each enabled `[[mail.accounts]]` entry keeps one read-only IMAP session, waits in IDLE, and
+//! fetches the sender and subject of messages whose UID is new since the last check. TLS comes
+//! from `openssl s_client`, or the account's `tunnel` command carries the session, so the
+//! daemon needs no TLS stack of its own.
+
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tracing::{debug, info, warn};
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::util::escape_markup;
+use unixnotis_core::{BusNames, MailAccountConfig, MailConfig};
+use zbus::zvariant::Value;
+
+use crate::daemon::DaemonState;
+use crate::notify_proxy::Notifier;
+use crate::password_cmd::read_password;
+
+/// Servers may drop an IDLE after 30 minutes (RFC 2177), so it is renewed before that.
+const IDLE_REFRESH: Duration = Duration::from_secs(25 * 60);
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+const RETRY_MIN: Duration = Duration::from_secs(30);
+const RETRY_MAX: Duration = Duration::from_secs(10 * 60);
+/// Larger batches, e.g. after an outage, become one summary notification.
+const MAX_SINGLE_NOTIFICATIONS: usize = 3;
+const MAX_LITERAL_BYTES: usize = 1024 * 1024;
+const OPEN_ACTION: &str = "default";
+/// Notifications whose click still runs `open_cmd`; older ones are forgotten.
+const MAX_CLICKABLE: usize = 64;
+
+type Clickable = Arc<Mutex<VecDeque<u32>>>;
+
+/// Start one watcher per enabled account; call once the notification bus name is owned.
+pub fn spawn_mail_bridge(state: Arc<DaemonState>, names: BusNames, config: MailConfig) {
+    for account in config
+        .accounts
+        .into_iter()
+        .filter(|account| account.enabled)
+    {
+        if account.host.trim().is_empty() && account.tunnel.is_none() {
+            warn!(account = %account.name, "mail account has neither host nor tunnel; skipping");
+            continue;
+        }
+        let state = state.clone();
+        let names = names.clone();
+        tokio::spawn(async move {
+            let notifier = match Notifier::new(&state, names.clone()).await {
+                Ok(notifier) => notifier,
+                Err(err) => {
+                    warn!(?err, "mail bridge cannot reach the notification server");
+                    return;
+                }
+            };
+            let clickable = Clickable::default();
+            if let Some(cmd) = account.open_cmd.clone() {
+                let clickable = clickable.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = handle_clicks(&state, names, cmd, clickable).await {
+                        warn!(?err, "mail click handler stopped");
+                    }
+                });
+            }
+            run_account(notifier, account, clickable).await;
+        });
+    }
+}
+
+async fn handle_clicks(
+    state: &DaemonState,
+    names: BusNames,
+    cmd: String,
+    clickable: Clickable,
+) -> zbus::Result<()> {
+    let mut notifier = Notifier::new(state, names).await?;
+    while let Some(signal) = notifier.next_action().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        if args.action_key != OPEN_ACTION {
+            continue;
+        }
+        let known = {
+            let mut clickable = clickable.lock().unwrap_or_else(|err| err.into_inner());
+            let position = clickable.iter().position(|id| *id == args.id);
+            position.and_then(|position| clickable.remove(position))
+        };
+        if known.is_some() {
+            run_open_cmd(&cmd);
+        }
+    }
+    Ok(())
+}
+
+async fn run_account(mut notifier: Notifier, account: MailAccountConfig, clickable: Clickable) {
+    let mut cursor = Cursor::default();
+    let mut retry = RETRY_MIN;
+    loop {
+        let started = Instant::now();
+        if let Err(err) = session(&mut notifier, &account, &mut cursor, &clickable).await {
+            warn!(account = %account.name, err = %format!("{err:#}"), "mail session ended");
+        }
+        // A session that stayed up for a while was not a startup failure; retry quickly.
+        if started.elapsed() > RETRY_MAX {
+            retry = RETRY_MIN;
+        }
+        tokio::time::sleep(retry).await;
+        retry = (retry * 2).min(RETRY_MAX);
+    }
+}
+
+/// Mailbox position that survives reconnects, so mail that arrived meanwhile is still reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Cursor {
+    uid_validity: Option<u32>,
+    uid_next: u32,
+}
+
+impl Cursor {
+    /// Adopt the server's view after selecting the mailbox; returns true when UIDs from
+    /// `self.uid_next` on have to be fetched.
+    fn sync(&mut self, uid_validity: u32, uid_next: u32) -> bool {
+        if self.uid_validity != Some(uid_validity) {
+            // First connection, or the mailbox was rebuilt: existing mail is not news.
+            *self = Cursor {
+                uid_validity: Some(uid_validity),
+                uid_next,
+            };
+            return false;
+        }
+        uid_next > self.uid_next
+    }
+}
+
+async fn session(
+    notifier: &mut Notifier,
+    account: &MailAccountConfig,
+    cursor: &mut Cursor,
+    clickable: &Clickable,
+) -> Result<()> {
+    let mut imap = Imap::connect(account)?;
+    let greeting = imap.read_response(RESPONSE_TIMEOUT).await?;
+    if !greeting.text.starts_with("* PREAUTH") {
+        if !greeting.text.starts_with("* OK") {
+            bail!("unexpected greeting: {}", greeting.text);
+        }
+        let password = read_password(&account.password_cmd).await?;
+        imap.command(&format!(
+            "LOGIN {} {}",
+            quote(&account.username),
+            quote(&password)
+        ))
+        .await
+        .context("login")?;
+    }
+    // EXAMINE keeps the mailbox read-only, so the \Recent and \Seen flags stay untouched.
+    let selected = imap
+        .command(&format!("EXAMINE {}", quote(&account.mailbox)))
+        .await
+        .context("open mailbox")?;
+    let uid_validity = response_code(&selected, "UIDVALIDITY").unwrap_or(0);
+    let uid_next = match response_code(&selected, "UIDNEXT") {
+        Some(uid_next) => uid_next,
+        None => imap.highest_uid().await? + 1,
+    };
+    info!(account = %account.name, mailbox = %account.mailbox, "watching mailbox");
+
+    let mut pending = cursor.sync(uid_validity, uid_next);
+    loop {
+        if pending {
+            let messages = imap.fetch_new(cursor.uid_next).await?;
+            debug!(account = %account.name, count = messages.len(), "fetched new mail");
+            if let Some(last) = messages.iter().map(|message| message.uid).max() {
+                cursor.uid_next = last + 1;
+            }
+            let ids = notify_messages(notifier, account, &messages).await;
+            if account.open_cmd.is_some() {
+                let mut clickable = clickable.lock().unwrap_or_else(|err| err.into_inner());
+                clickable.extend(ids);
+                while clickable.len() > MAX_CLICKABLE {
+                    clickable.pop_front();
+                }
+            }
+        }
+        pending = imap.idle(IDLE_REFRESH).await?;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Message {
+    uid: u32,
+    from: String,
+    subject: String,
+}
+
+/// Send one notification per message, or one summary for a batch; returns the ids sent.
+async fn notify_messages(
+    notifier: &mut Notifier,
+    account: &MailAccountConfig,
+    messages: &[Message],
+) -> Vec<u32> {
+    let open_label = tr("mail-open");
+    let actions: Vec<&str> = if account.open_cmd.is_some() {
+        vec![OPEN_ACTION, open_label.as_str()]
+    } else {
+        Vec::new()
+    };
+    let notes: Vec<(String, String)> = if messages.len() > MAX_SINGLE_NOTIFICATIONS {
+        let listed = messages
+            .iter()
+            .rev()
+            .take(MAX_SINGLE_NOTIFICATIONS)
+            .map(|message| escape_markup(&format!("{}: {}", message.from, subject(message))))
+            .collect::<Vec<_>>()
+            .join("\n");
+        vec![(
+            tr_args("mail-new-count", &[("count", messages.len().into())]),
+            listed,
+        )]
+    } else {
+        messages
+            .iter()
+            .map(|message| (message.from.clone(), escape_markup(&subject(message))))
+            .collect()
+    };
+    let mut ids = Vec::new();
+    for (summary, body) in notes {
+        let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+        hints.insert("category", Value::from("email.arrived"));
+        let notifications = match notifier.proxy().await {
+            Ok(notifications) => notifications,
+            Err(err) => {
+                warn!(?err, "failed to send new-mail notification");
+                continue;
+            }
+        };
+        match notifications
+            .notify(
+                &account.name,
+                0,
+                "mail-unread-symbolic",
+                &summary,
+                &body,
+                &actions,
+                hints,
+                -1,
+            )
+            .await
+        {
+            Ok(id) => ids.push(id),
+            Err(err) => warn!(?err, "failed to send new-mail notification"),
+        }
+    }
+    ids
+}
+
+fn subject(message: &Message) -> String {
+    if message.subject.trim().is_empty() {
+        tr("mail-no-subject")
+    } else {
+        message.subject.clone()
+    }
+}
+
+fn run_open_cmd(cmd: &str) {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(cmd).stdin(Stdio::null());
+    match command.spawn() {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                let _ = child.wait().await;
+            });
+        }
+        Err(err) => warn!(?err, "failed to run mail open_cmd"),
+    }
+}
+
+/// One server response with `{n}` literals taken out of the line.
+#[derive(Debug, Default)]
+struct Response {
+    text: String,
+    literals: Vec<Vec<u8>>,
+}
+
+struct Imap {
+    reader: BufReader<ChildStdout>,
+    writer: ChildStdin,
+    /// Kept so the transport is killed with the session.
+    _child: Child,
+    /// Bytes of a line whose read timed out, kept so IDLE timeouts lose no data.
+    partial: Vec<u8>,
+    tag: u32,
+}
+
+impl Imap {
+    fn connect(account: &MailAccountConfig) -> Result<Self> {
+        let mut command = match account.tunnel.as_deref() {
+            Some(tunnel) => {
+                let mut command = Command::new("sh");
+                command.arg("-c").arg(tunnel);
+                command
+            }
+            None => {
+                let host = account.host.trim();
+                let mut command = Command::new("openssl");
+                command
+                    .args(["s_client", "-quiet", "-nocommands", "-verify_return_error"])
+                    .args(["-verify_hostname", host, "-servername", host])
+                    .arg("-connect")
+                    .arg(format!("{host}:{}", account.port));
+                command
+            }
+        };
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        let mut child = command.spawn().context("start IMAP transport")?;
+        let stdout = child.stdout.take().context("transport stdout")?;
+        let stdin = child.stdin.take().context("transport stdin")?;
+        Ok(Self {
+            reader: BufReader::new(stdout),
+            writer: stdin,
+            _child: child,
+            partial: Vec::new(),
+            tag: 0,
+        })
+    }
+
+    async fn send(&mut self, line: &str) -> Result<()> {
+        self.writer.write_all(line.as_bytes()).await?;
+        self.writer.write_all(b"\r\n").await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    /// Read one response, or `None` when `wait` passes before a line starts arriving.
+    async fn try_read_response(&mut self, wait: Duration) -> Result<Option<Response>> {
+        let mut response = Response::default();
+        let mut first = true;
+        loop {
+            let limit = if first { wait } else { RESPONSE_TIMEOUT };
+            let read =
+                tokio::time::timeout(limit, self.reader.read_until(b'\n', &mut self.partial));
+            match read.await {
+                Ok(Ok(0)) => bail!("server closed the connection"),
+                Ok(Ok(_)) => {}
+                Ok(Err(err)) => return Err(err.into()),
+                // Bytes read so far stay in `partial` for the next call.
+                Err(_) if first => return Ok(None),
+                Err(_) => bail!("server stopped mid-response"),
+            }
+            first = false;
+            let line = String::from_utf8_lossy(&self.partial).into_owned();
+            self.partial.clear();
+            let line = line.trim_end_matches(['\r', '\n']);
+            response.text.push_str(line);
+            let Some(length) = literal_length(line) else {
+                return Ok(Some(response));
+            };
+            if length > MAX_LITERAL_BYTES {
+                bail!("server sent a {length} byte literal");
+            }
+            let mut literal = vec![0; length];
+            tokio::time::timeout(RESPONSE_TIMEOUT, self.reader.read_exact(&mut literal))
+                .await
+                .map_err(|_| anyhow!("server stopped mid-literal"))??;
+            response.literals.push(literal);
+        }
+    }
+
+    async fn read_response(&mut self, wait: Duration) -> Result<Response> {
+        self.try_read_response(wait)
+            .await?
+            .ok_or_else(|| anyhow!("server did not answer"))
+    }
+
+    fn next_tag(&mut self) -> String {
+        self.tag += 1;
+        format!("u{}", self.tag)
+    }
+
+    /// Run a command and return its untagged responses.
+    async fn command(&mut self, command: &str) -> Result<Vec<Response>> {
+        let tag = self.next_tag();
+        self.send(&format!("{tag} {command}")).await?;
+        self.finish(&tag).await
+    }
+
+    async fn finish(&mut self, tag: &str) -> Result<Vec<Response>> {
+        let prefix = format!("{tag} ");
+        let mut untagged = Vec::new();
+        loop {
+            let response = self.read_response(RESPONSE_TIMEOUT).await?;
+            let Some(status) = response.text.strip_prefix(&prefix) else {
+                untagged.push(response);
+                continue;
+            };
+            if status.starts_with("OK") {
+                return Ok(untagged);
+            }
+            bail!("server answered: {status}");
+        }
+    }
+
+    async fn highest_uid(&mut self) -> Result<u32> {
+        let responses = self.command("UID FETCH * (UID)").await?;
+        Ok(responses
+            .iter()
+            .filter_map(|response| number_after(&response.text, "UID "))
+            .max()
+            .unwrap_or(0))
+    }
+
+    async fn fetch_new(&mut self, from_uid: u32) -> Result<Vec<Message>> {
+        let responses = self
+            .command(&format!(
+                "UID FETCH {from_uid}:* (UID BODY.PEEK[HEADER.FIELDS (FROM SUBJECT)])"
+            ))
+            .await?;
+        let mut messages: Vec<Message> = responses
+            .iter()
+            .filter_map(parse_fetch)
+            // `n:*` always matches the last message, even when its UID is below n.
+            .filter(|message| message.uid >= from_uid)
+            .collect();
+        messages.sort_by_key(|message| message.uid);
+        Ok(messages)
+    }
+
+    /// Wait in IDLE until the server reports new messages or `refresh` passes; returns true
+    /// when mail arrived.
+    async fn idle(&mut self, refresh: Duration) -> Result<bool> {
+        let tag = self.next_tag();
+        self.send(&format!("{tag} IDLE")).await?;
+        loop {
+            let response = self.read_response(RESPONSE_TIMEOUT).await?;
+            if response.text.starts_with('+') {
+                break;
+            }
+            if response.text.starts_with(&tag) {
+                bail!("server refused IDLE: {}", response.text);
+            }
+        }
+        let deadline = Instant::now() + refresh;
+        let mut arrived = false;
+        while !arrived {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.try_read_response(left).await? {
+                Some(response) => arrived = is_exists(&response.text),
+                None => break,
+            }
+        }
+        self.send("DONE").await?;
+        let prefix = format!("{tag} ");
+        loop {
+            let response = self.read_response(RESPONSE_TIMEOUT).await?;
+            if let Some(status) = response.text.strip_prefix(&prefix) {
+                if !status.starts_with("OK") {
+                    bail!("IDLE ended with: {status}");
+                }
+                return Ok(arrived);
+            }
+            arrived |= is_exists(&response.text);
+        }
+    }
+}
+
+/// Length of a `{n}` literal announced at the end of a response line.
+fn literal_length(line: &str) -> Option<usize> {
+    let open = line.strip_suffix('}')?.rfind('{')?;
+    line[open + 1..line.len() - 1].parse().ok()
+}
+
+fn is_exists(text: &str) -> bool {
+    text.starts_with("* ") && text.ends_with(" EXISTS")
+}
+
+/// Value of a bracketed response code such as `[UIDNEXT 4392]`.
+fn response_code(responses: &[Response], code: &str) -> Option<u32> {
+    let marker = format!("[{code} ");
+    responses
+        .iter()
+        .find_map(|response| number_after(&response.text, &marker))
+}
+
+fn number_after(text: &str, marker: &str) -> Option<u32> {
+    let start = text.find(marker)? + marker.len();
+    let digits: String = text[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+fn parse_fetch(response: &Response) -> Option<Message> {
+    let (_, items) = response.text.split_once(" FETCH (")?;
+    let uid = number_after(items, "UID ")?;
+    let header = response
+        .literals
+        .first()
+        .map(|literal| String::from_utf8_lossy(literal).into_owned())
+        .unwrap_or_default();
+    let mut from = String::new();
+    let mut subject = String::new();
+    for (name, value) in unfold_headers(&header) {
+        if name.eq_ignore_ascii_case("from") {
+            from = sender_name(&decode_words(&value));
+        } else if name.eq_ignore_ascii_case("subject") {
+            subject = decode_words(&value);
+        }
+    }
+    Some(Message { uid, from, subject })
+}
+
+fn unfold_headers(header: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in header.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            fields.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    fields
+}
+
+/// Display name of a `From` value, falling back to the address.
+fn sender_name(from: &str) -> String {
+    let Some((name, address)) = from.split_once('<') else {
+        return from.trim().to_string();
+    };
+    let name = name.trim().trim_matches('"').trim();
+    if name.is_empty() {
+        address.trim_end_matches('>').trim().to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Decode RFC 2047 encoded words (`=?UTF-8?B?...?=`) in UTF-8, ASCII, or Latin-1.
+fn decode_words(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, candidate) = rest.split_at(start);
+        match decode_word(candidate) {
+            Some((word, consumed)) => {
+                // Whitespace between two encoded words is not part of the text.
+                if !(after_word && before.trim().is_empty()) {
+                    decoded.push_str(before);
+                }
+                decoded.push_str(&word);
+                rest = &candidate[consumed..];
+                after_word = true;
+            }
+            None => {
+                decoded.push_str(before);
+                decoded.push_str("=?");
+                rest = &candidate[2..];
+                after_word = false;
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_word(candidate: &str) -> Option<(String, usize)> {
+    let mut parts = candidate[2..].splitn(3, '?');
+    let charset = parts.next()?;
+    let encoding = parts.next()?;
+    let remainder = parts.next()?;
+    let end = remainder.find("?=")?;
+    let text = &remainder[..end];
+    let consumed = 2 + charset.len() + 1 + encoding.len() + 1 + end + 2;
+    let bytes = match encoding {
+        "B" | "b" => BASE64.decode(text).ok()?,
+        "Q" | "q" => decode_q(text)?,
+        _ => return None,
+    };
+    // RFC 2231 allows a language suffix such as `UTF-8*en`.
+    let charset = charset.split('*').next()?.to_ascii_lowercase();
+    let word = match charset.as_str() {
+        "utf-8" | "us-ascii" => String::from_utf8_lossy(&bytes).into_owned(),
+        "iso-8859-1" | "latin1" => bytes.iter().map(|&byte| char::from(byte)).collect(),
+        _ => return None,
+    };
+    Some((word, consumed))
+}
+
+fn decode_q(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.bytes();
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'_' => bytes.push(b' '),
+            b'=' => {
+                let hex = [chars.next()?, chars.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    Some(bytes)
+}
+
+/// IMAP quoted string.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fetch_with_encoded_headers() {
+        let response = Response {
+            text: "* 12 FETCH (UID 345 BODY[HEADER.FIELDS (FROM SUBJECT)] {0})".to_string(),
+            literals: vec![b"From: =?UTF-8?Q?J=C3=BCrgen_M?= <jm@example.com>\r\n\
+Subject: =?UTF-8?B?R3LDvMOfZQ==?=\r\n =?UTF-8?B?IGF1cyBCZXJsaW4=?=\r\n\r\n"
+                .to_vec()],
+        };
+        assert_eq!(
+            parse_fetch(&response),
+            Some(Message {
+                uid: 345,
+                from: "Jürgen M".to_string(),
+                subject: "Grüße aus Berlin".to_string(),
+            })
+        );
+        assert_eq!(literal_length("* 1 FETCH (UID 2 BODY[] {80}"), Some(80));
+        assert_eq!(sender_name("<noreply@example.com>"), "noreply@example.com");
+        assert_eq!(
+            decode_words("Re: =?x-unknown?B?Zm9v?= ok"),
+            "Re: =?x-unknown?B?Zm9v?= ok"
+        );
+    }
+
+    #[test]
+    fn cursor_skips_existing_mail_until_uidvalidity_is_known() {
+        let mut cursor = Cursor::default();
+        assert!(!cursor.sync(7, 100));
+        assert_eq!(cursor.uid_next, 100);
+        // Reconnecting after mail arrived reports it.
+        assert!(cursor.sync(7, 103));
+        assert_eq!(cursor.uid_next, 100);
+        // A rebuilt mailbox starts over.
+        assert!(!cursor.sync(8, 5));
+        assert_eq!(cursor.uid_next, 5);
+    }
+
+    #[test]
+    fn response_codes_and_quoting() {
+        let responses = vec![
+            Response {
+                text: "* OK [UIDVALIDITY 3857529045] UIDs valid".to_string(),
+                literals: Vec::new(),
+            },
+            Response {
+                text: "* OK [UIDNEXT 4392] Predicted next UID".to_string(),
+                literals: Vec::new(),
+            },
+        ];
+        assert_eq!(response_code(&responses, "UIDVALIDITY"), Some(3857529045));
+        assert_eq!(response_code(&responses, "UIDNEXT"), Some(4392));
+        assert_eq!(quote(r#"pa"ss\word"#), r#""pa\"ss\\word""#);
+        assert!(is_exists("* 23 EXISTS"));
+    }
+}
@@ -1,6 +1,7 @@
 //! Daemon entrypoint and service bootstrap.
 
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
@@ -9,37 +10,82 @@ use tracing::{error, info, warn};
 use zbus::fdo::DBusProxy;
 use zbus::Connection;
 
+#[path = "battery.rs"]
+mod battery;
 #[path = "child_process.rs"]
 mod child_process;
+mod control_policy;
 mod daemon;
 #[path = "dbus_owner.rs"]
 mod dbus_owner;
+#[path = "disk_space.rs"]
+mod disk_space;
 mod expire;
+#[path = "history_file.rs"]
+mod history_file;
+mod inhibit;
+mod latency;
+#[cfg(feature = "imap")]
+#[path = "mail_bridge.rs"]
+mod mail_bridge;
+#[path = "media_players.rs"]
+mod media_players;
+#[cfg(feature = "mqtt")]
+#[path = "mqtt_bridge.rs"]
+mod mqtt_bridge;
+#[path = "notify_proxy.rs"]
+mod notify_proxy;
+#[cfg(any(feature = "imap", feature = "mqtt"))]
+#[path = "password_cmd.rs"]
+mod password_cmd;
+#[path = "remote.rs"]
+mod remote;
 #[path = "runtime_config.rs"]
 mod runtime_config;
+#[path = "sender_watch.rs"]
+mod sender_watch;
+mod session_bus;
 #[path = "shutdown_signal.rs"]
 mod shutdown_signal;
 mod sound;
 mod store;
+mod suspend_watch;
 #[path = "trial_mode.rs"]
 mod trial_mode;
+#[path = "unit_failures.rs"]
+mod unit_failures;
+#[path = "welcome.rs"]
+mod welcome;
 
+use crate::battery::spawn_battery_watcher;
 use crate::child_process::{
-    start_center_process, start_popups_process, stop_center_process, stop_popups_process,
+    restart_ui, start_center_process, start_popups_process, stop_center_process,
+    stop_popups_process,
 };
 use crate::daemon::{
-    log_name_reply, request_control_name, request_well_known_name, ControlServer, DaemonState,
-    NotificationServer,
+    log_name_reply, request_control_name, request_well_known_name, uses_history_ttl, DaemonState,
 };
 use crate::dbus_owner::{log_current_owner, wait_for_owner_state};
+use crate::disk_space::spawn_disk_monitor;
 use crate::expire::ExpirationScheduler;
-use crate::runtime_config::{ensure_wayland_session, init_tracing, load_config};
+use crate::history_file::{restore_history, save_history, spawn_history_writer, HistoryFile};
+use crate::inhibit::{spawn_hyprland_watch, spawn_screensaver};
+#[cfg(feature = "imap")]
+use crate::mail_bridge::spawn_mail_bridge;
+use crate::media_players::spawn_player_watcher;
+#[cfg(feature = "mqtt")]
+use crate::mqtt_bridge::spawn_mqtt_bridge;
+use crate::remote::spawn_remote_listener;
+use crate::runtime_config::{
+    ensure_wayland_session, load_config, spawn_reload_on_hangup, write_first_run_config,
+};
+use crate::sender_watch::spawn_sender_watch;
 use crate::shutdown_signal::shutdown_signal;
 use crate::sound::SoundSettings;
+use crate::suspend_watch::spawn_suspend_watch;
 use crate::trial_mode::{prepare_trial, restore_previous, TrialState};
-use unixnotis_core::{Config, CONTROL_BUS_NAME, CONTROL_OBJECT_PATH};
-
-const NOTIFICATIONS_OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+use crate::unit_failures::spawn_unit_watcher;
+use unixnotis_core::{BusNames, Config};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
@@ -71,6 +117,19 @@ struct Args {
     /// Exit after running for the requested number of seconds (profiling helper)
     #[arg(long)]
     run_seconds: Option<u64>,
+
+    /// Do not start unixnotis-popups (it is launched some other way)
+    #[arg(long)]
+    no_popups: bool,
+
+    /// Do not start unixnotis-center (it is launched some other way)
+    #[arg(long)]
+    no_center: bool,
+
+    /// Suffix for alternate bus names, for a second instance in a nested session
+    /// (falls back to UNIXNOTIS_BUS_SUFFIX)
+    #[arg(long)]
+    bus_suffix: Option<String>,
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -85,8 +144,13 @@ enum RestoreStrategy {
 async fn main() -> Result<()> {
     let args = Args::parse();
     let config = load_config(&args).context("load config")?;
+    let names = BusNames::resolve(args.bus_suffix.as_deref())?;
 
-    init_tracing(&config);
+    let _tracing = unixnotis_core::logging::init_tracing(
+        config.general.log_level.as_deref(),
+        "unixnotis-daemon",
+    );
+    unixnotis_core::i18n::init(config.general.language.as_deref());
     let config_source = if args.config.is_some() {
         "custom"
     } else {
@@ -96,6 +160,9 @@ async fn main() -> Result<()> {
         }
     };
     info!(config_source, "configuration loaded");
+    if let Some(suffix) = names.suffix() {
+        info!(suffix, notifications = %names.notifications, control = %names.control, "using suffixed bus names");
+    }
     if unixnotis_core::util::diagnostic_mode() {
         info!(
             limit = unixnotis_core::util::log_limit(),
@@ -107,16 +174,17 @@ async fn main() -> Result<()> {
         info!("configuration loaded successfully");
         return Ok(());
     }
+    let first_run_config = write_first_run_config(&args);
 
     ensure_wayland_session(Duration::from_secs(20))
         .await
         .context("wait for Wayland session")?;
 
-    let connection = Connection::session()
+    let mut connection = Connection::session()
         .await
         .context("connect to session bus")?;
-    let dbus_proxy = DBusProxy::new(&connection).await?;
-    let notifications_name = zbus::names::BusName::try_from("org.freedesktop.Notifications")?;
+    let mut dbus_proxy = DBusProxy::new(&connection).await?;
+    let notifications_name = zbus::names::BusName::try_from(names.notifications.as_str())?;
 
     let mut trial_state = if args.trial {
         prepare_trial(&args, &dbus_proxy, notifications_name.clone()).await?
@@ -126,28 +194,63 @@ async fn main() -> Result<()> {
 
     // Resolve sound settings once to avoid repeated filesystem work.
     let sound_settings = SoundSettings::from_config(&config);
-    let state = DaemonState::new(connection.clone(), config, sound_settings);
+    let uses_history_ttl = uses_history_ttl(&config);
+    let history_file = if config.history.persist {
+        HistoryFile::for_names(&names).map(Arc::new)
+    } else {
+        None
+    };
+    let suppress_players = config.media.suppress_player_notifications;
+    let manage_ui = config.general.manage_ui;
+    let general = config.general.clone();
+    let battery = config.battery.clone();
+    let systemd = config.systemd.clone();
+    let disk = config.disk.clone();
+    let remote = config.remote.clone();
+    let mail = config.mail.clone();
+    let mqtt = config.mqtt.clone();
+    let slow_interval = Duration::from_millis(config.widgets.refresh_interval_slow_ms);
+    // Trial runs leave the user's config alone, so rule edits there only last for the run.
+    let config_path = if args.trial {
+        None
+    } else {
+        args.config
+            .clone()
+            .or_else(|| Config::default_config_path().ok())
+    };
+    let state = DaemonState::new(
+        connection.clone(),
+        names.clone(),
+        config,
+        config_path,
+        sound_settings,
+    );
+    if let Some(file) = history_file.as_ref() {
+        // Restore before serving so the first ListHistory call already sees saved entries.
+        restore_history(&state, file).await;
+        spawn_history_writer(state.clone(), file.clone());
+    }
     let scheduler = ExpirationScheduler::start(state.clone());
+    spawn_suspend_watch(state.clone(), scheduler.clone());
+    if uses_history_ttl {
+        state.ensure_history_pruner();
+    }
+    if suppress_players {
+        spawn_player_watcher(state.clone());
+    }
+    spawn_sender_watch(state.clone());
+    spawn_hyprland_watch(state.clone(), &general);
+    spawn_reload_on_hangup(state.clone(), args.config.clone());
 
-    connection
-        .object_server()
-        .at(
-            NOTIFICATIONS_OBJECT_PATH,
-            NotificationServer::new(state.clone(), scheduler),
-        )
-        .await?;
-    connection
-        .object_server()
-        .at(CONTROL_OBJECT_PATH, ControlServer::new(state.clone()))
-        .await?;
+    session_bus::serve(&connection, &state, &scheduler).await?;
 
-    let control_reply = request_control_name(&connection).await?;
+    let control_reply = request_control_name(&connection, &names).await?;
     match control_reply {
         zbus::fdo::RequestNameReply::PrimaryOwner => {
-            info!(CONTROL_BUS_NAME, "acquired control bus name");
+            info!(name = %names.control, "acquired control bus name");
         }
         zbus::fdo::RequestNameReply::AlreadyOwner => {
-            info!(CONTROL_BUS_NAME, "already owns control bus name");
+            info!(name = %names.control, "already owns control bus name");
         }
         _ => {
             return Err(anyhow!(
@@ -156,8 +259,8 @@ async fn main() -> Result<()> {
         }
     }
 
-    let reply = request_well_known_name(&connection, args.trial).await?;
-    log_name_reply(&reply);
+    let reply = request_well_known_name(&connection, &names, args.trial).await?;
+    log_name_reply(&names, &reply);
     let owner_is_self =
         match log_current_owner(&dbus_proxy, &connection, notifications_name.clone()).await {
             Ok(value) => value,
@@ -173,34 +276,114 @@ async fn main() -> Result<()> {
         )
     {
         return Err(anyhow!(
-            "org.freedesktop.Notifications is already owned; retry with --trial"
+            "{} is already owned; retry with --trial",
+            names.notifications
         ));
     }
     if args.trial && !owner_is_self {
         return Err(anyhow!(
-            "org.freedesktop.Notifications is still owned by another daemon; stop it or use --restore systemd if managed by systemd --user"
+            "{} is still owned by another daemon; stop it or use --restore systemd if managed by systemd --user",
+            names.notifications
         ));
     }
 
-    let mut popups_process = start_popups_process(&args)?;
-    let mut center_process = start_center_process(&args)?;
+    // Built-in sources notify through the notification name, so it has to be owned first.
+    // They send through the daemon's current connection, so they carry on after a bus restart.
+    spawn_battery_watcher(state.clone(), names.clone(), battery);
+    spawn_unit_watcher(state.clone(), names.clone(), systemd);
+    spawn_disk_monitor(state.clone(), names.clone(), disk, slow_interval);
+    spawn_remote_listener(state.clone(), names.clone(), remote);
+    spawn_screensaver(state.clone(), &general);
+    #[cfg(feature = "imap")]
+    spawn_mail_bridge(state.clone(), names.clone(), mail);
+    #[cfg(not(feature = "imap"))]
+    if !mail.accounts.is_empty() {
+        warn!("mail accounts are configured, but this daemon was built without the imap feature");
+    }
+    #[cfg(feature = "mqtt")]
+    spawn_mqtt_bridge(state.clone(), names.clone(), mqtt);
+    #[cfg(not(feature = "mqtt"))]
+    if mqtt.enabled {
+        warn!("mqtt is enabled, but this daemon was built without the mqtt feature");
+    }
+
+    if !manage_ui {
+        info!("general.manage_ui is off; not starting UI processes");
+    }
+    let mut popups_process = if manage_ui && !args.no_popups {
+        start_popups_process(&args, &names)?
+    } else {
+        None
+    };
+    let mut center_process = if manage_ui && !args.no_center {
+        start_center_process(&args, &names)?
+    } else {
+        None
+    };
+
+    if let Some(path) = first_run_config {
+        if let Err(err) = welcome::send_welcome(&connection, &names, &path).await {
+            warn!(?err, "failed to send welcome notification");
+        }
+    }
 
     info!("unixnotis-daemon running");
-    match args.run_seconds {
-        Some(seconds) => {
-            let timeout = tokio::time::sleep(Duration::from_secs(seconds));
-            tokio::select! {
-                _ = shutdown_signal() => {},
-                _ = timeout => {
-                    info!(seconds, "run-seconds elapsed, shutting down");
-                }
+    let run_limit = async {
+        match args.run_seconds {
+            Some(seconds) => {
+                tokio::time::sleep(Duration::from_secs(seconds)).await;
+                info!(seconds, "run-seconds elapsed, shutting down");
             }
+            None => std::future::pending().await,
+        }
+    };
+    let stop = async {
+        tokio::select! {
+            _ = shutdown_signal() => {},
+            _ = run_limit => {},
+        }
+    };
+    tokio::pin!(stop);
+    loop {
+        tokio::select! {
+            _ = &mut stop => break,
+            _ = session_bus::disconnected(&connection) => {},
+        }
+        warn!("lost the session bus; waiting for it to come back");
+        connection = tokio::select! {
+            _ = &mut stop => break,
+            result = session_bus::reconnect(&state, &scheduler, &names, args.trial) => result?,
+        };
+        dbus_proxy = DBusProxy::new(&connection).await?;
+        // Watchers subscribed on the old connection never hear from the new bus, and a reload
+        // may have switched them on or off since startup.
+        let (general, suppress_players) = {
+            let store = state.store.lock().await;
+            let config = store.config();
+            (
+                config.general.clone(),
+                config.media.suppress_player_notifications,
+            )
+        };
+        if suppress_players {
+            spawn_player_watcher(state.clone());
+        }
+        spawn_sender_watch(state.clone());
+        spawn_screensaver(state.clone(), &general);
+        // The UIs lost their bus too; fresh ones seed themselves from the control interface.
+        if let Some(mut child) = popups_process.take() {
+            stop_popups_process(&mut child).await;
+            popups_process = restart_ui(start_popups_process(&args, &names), "unixnotis-popups");
         }
-        None => {
-            shutdown_signal().await;
+        if let Some(mut child) = center_process.take() {
+            stop_center_process(&mut child).await;
+            center_process = restart_ui(start_center_process(&args, &names), "unixnotis-center");
         }
     }
 
+    if let Some(file) = history_file.as_ref() {
+        save_history(&state, file).await;
+    }
     if let Some(mut child) = popups_process.take() {
         stop_popups_process(&mut child).await;
     }
@@ -209,10 +392,7 @@ async fn main() -> Result<()> {
     }
 
     if args.trial {
-        if let Err(err) = connection
-            .release_name("org.freedesktop.Notifications")
-            .await
-        {
+        if let Err(err) = connection.release_name(names.notifications.as_str()).await {
             error!(?err, "failed to release notification name");
         }
 
@@ -222,7 +402,7 @@ async fn main() -> Result<()> {
             }
             let reacquired = match wait_for_owner_state(
                 &dbus_proxy,
-                zbus::names::BusName::try_from("org.freedesktop.Notifications")?,
+                zbus::names::BusName::try_from(names.notifications.as_str())?,
                 true,
                 Duration::from_millis(args.restore_wait_ms),
             )
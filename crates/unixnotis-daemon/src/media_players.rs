@@ -0,0 +1,136 @@
+//! MPRIS player discovery for now-playing notification suppression.
+//!
+//! Tracks running media players on the session bus and keeps the store's generated
+//! suppression rules in sync with their app names.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use tracing::{debug, warn};
+use zbus::fdo::DBusProxy;
+use zbus::{Connection, Proxy, ProxyBuilder};
+
+use crate::daemon::DaemonState;
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_APP: &str = "org.mpris.MediaPlayer2";
+/// Browsers also send unrelated web notifications, so they never get a suppression rule.
+const BROWSER_TOKENS: [&str; 6] = ["firefox", "brave", "chromium", "chrome", "vivaldi", "edge"];
+
+/// Start the watcher, replacing one already running.
+pub fn spawn_player_watcher(state: Arc<DaemonState>) {
+    let slot = state.clone();
+    let task = tokio::spawn(async move {
+        if let Err(err) = watch_players(state).await {
+            warn!(?err, "media player watcher stopped");
+        }
+    });
+    slot.player_watcher.replace(Some(task.abort_handle()));
+}
+
+async fn watch_players(state: Arc<DaemonState>) -> zbus::Result<()> {
+    let connection = state.connection();
+    let dbus = DBusProxy::new(&connection).await?;
+    // Subscribe before listing so players that appear in between are not missed.
+    let mut changes = dbus.receive_name_owner_changed().await?;
+
+    let mut players: HashMap<String, Vec<String>> = HashMap::new();
+    for name in dbus.list_names().await? {
+        let name = name.to_string();
+        if let Some(apps) = player_apps(&connection, &name).await {
+            players.insert(name, apps);
+        }
+    }
+    publish(&state, &players).await;
+
+    while let Some(signal) = changes.next().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        let name = args.name().to_string();
+        if !name.starts_with(MPRIS_PREFIX) {
+            continue;
+        }
+        let changed = if args.new_owner().is_some() {
+            match player_apps(&connection, &name).await {
+                Some(apps) => players.insert(name, apps.clone()) != Some(apps),
+                None => players.remove(&name).is_some(),
+            }
+        } else {
+            players.remove(&name).is_some()
+        };
+        if changed {
+            publish(&state, &players).await;
+        }
+    }
+    Ok(())
+}
+
+async fn publish(state: &DaemonState, players: &HashMap<String, Vec<String>>) {
+    let apps: Vec<String> = players.values().flatten().cloned().collect();
+    debug!(
+        players = players.len(),
+        "updated media player suppression rules"
+    );
+    state.set_player_apps(apps).await;
+}
+
+async fn player_apps(connection: &Connection, bus_name: &str) -> Option<Vec<String>> {
+    let suffix = bus_name.strip_prefix(MPRIS_PREFIX)?;
+    let proxy: Proxy<'static> = ProxyBuilder::new(connection)
+        .destination(bus_name.to_string())
+        .ok()?
+        .path(MPRIS_PATH)
+        .ok()?
+        .interface(MPRIS_APP)
+        .ok()?
+        .build()
+        .await
+        .ok()?;
+    let identity: Option<String> = proxy.get_property("Identity").await.ok();
+    let desktop_entry: Option<String> = proxy.get_property("DesktopEntry").await.ok();
+    app_names(suffix, identity.as_deref(), desktop_entry.as_deref())
+}
+
+fn app_names(
+    bus_suffix: &str,
+    identity: Option<&str>,
+    desktop_entry: Option<&str>,
+) -> Option<Vec<String>> {
+    // Multi-instance players append ".instance<pid>" to the bus name.
+    let bus_app = bus_suffix.split('.').next().unwrap_or(bus_suffix);
+    let mut apps: Vec<String> = [Some(bus_app), identity, desktop_entry]
+        .into_iter()
+        .flatten()
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if apps
+        .iter()
+        .any(|name| BROWSER_TOKENS.iter().any(|token| name.contains(token)))
+    {
+        return None;
+    }
+    apps.sort_unstable();
+    apps.dedup();
+    Some(apps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::app_names;
+
+    #[test]
+    fn app_names_skip_browsers_and_instances() {
+        assert_eq!(
+            app_names("mpv.instance4242", Some("mpv Media Player"), Some("mpv")),
+            Some(vec!["mpv".to_string(), "mpv media player".to_string()])
+        );
+        assert_eq!(
+            app_names("firefox.instance_1_42", Some("Mozilla Firefox"), None),
+            None
+        );
+    }
+}
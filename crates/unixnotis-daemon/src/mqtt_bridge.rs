@@ -0,0 +1,279 @@
+//! Notifications from MQTT topics (`mqtt` feature).
+//!
+//! Subscribes to every `[[mqtt.topics]]` filter on one broker connection and turns each
+//! published message into a notification, filling the topic's summary and body templates from
+//! the topic name and payload. Retained messages are skipped: the broker replays them on every
+//! subscribe, so they would repeat old alerts after each reconnect.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, Publish, QoS, SubscribeFilter, Transport};
+use serde_json::Value as Json;
+use tracing::{debug, info, warn};
+use unixnotis_core::util::escape_markup;
+use unixnotis_core::{BusNames, MqttConfig, MqttTopicConfig};
+use zbus::zvariant::Value;
+
+use crate::daemon::DaemonState;
+use crate::notify_proxy::{NotificationsProxy, Notifier};
+use crate::password_cmd::read_password;
+
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+/// Largest packet accepted from the broker; alert payloads are small.
+const MAX_PACKET_BYTES: usize = 64 * 1024;
+const REQUEST_CAPACITY: usize = 16;
+const RETRY_MIN: Duration = Duration::from_secs(5);
+const RETRY_MAX: Duration = Duration::from_secs(5 * 60);
+
+/// Start the MQTT subscriber; call once the notification bus name is owned.
+pub fn spawn_mqtt_bridge(state: Arc<DaemonState>, names: BusNames, config: MqttConfig) {
+    if !config.enabled {
+        return;
+    }
+    let (valid, invalid): (Vec<_>, Vec<_>) = config
+        .topics
+        .iter()
+        .cloned()
+        .partition(|topic| rumqttc::valid_filter(&topic.topic));
+    for topic in &invalid {
+        warn!(topic = %topic.topic, "ignoring invalid mqtt topic filter");
+    }
+    if valid.is_empty() {
+        warn!("mqtt is enabled without any topics; not connecting");
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(err) = run_bridge(state, names, config, valid).await {
+            warn!(?err, "mqtt bridge stopped");
+        }
+    });
+}
+
+async fn run_bridge(
+    state: Arc<DaemonState>,
+    names: BusNames,
+    config: MqttConfig,
+    topics: Vec<MqttTopicConfig>,
+) -> anyhow::Result<()> {
+    let mut notifier = Notifier::new(&state, names).await?;
+    let mut options = MqttOptions::new(client_id(&config), config.host.clone(), config.port);
+    options
+        .set_keep_alive(KEEP_ALIVE)
+        .set_max_packet_size(MAX_PACKET_BYTES, MAX_PACKET_BYTES)
+        .set_clean_session(true);
+    if !config.username.is_empty() {
+        let password = if config.password_cmd.trim().is_empty() {
+            String::new()
+        } else {
+            read_password(&config.password_cmd).await?
+        };
+        options.set_credentials(config.username.clone(), password);
+    }
+    if config.tls {
+        options.set_transport(Transport::tls_with_default_config());
+    }
+
+    let (client, mut events) = AsyncClient::new(options, REQUEST_CAPACITY);
+    let filters: Vec<SubscribeFilter> = topics
+        .iter()
+        .map(|topic| SubscribeFilter::new(topic.topic.clone(), QoS::AtLeastOnce))
+        .collect();
+    let mut retry = RETRY_MIN;
+    loop {
+        match events.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!(host = %config.host, port = config.port, "connected to mqtt broker");
+                retry = RETRY_MIN;
+                // A clean session forgets subscriptions, so they are renewed on every connect.
+                if let Err(err) = client.try_subscribe_many(filters.clone()) {
+                    warn!(?err, "failed to subscribe to mqtt topics");
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                if publish.retain {
+                    debug!(topic = %publish.topic, "skipping retained mqtt message");
+                    continue;
+                }
+                let Some(topic) = topics
+                    .iter()
+                    .find(|topic| rumqttc::matches(&publish.topic, &topic.topic))
+                else {
+                    continue;
+                };
+                let sent = match notifier.proxy().await {
+                    Ok(notifications) => notify_message(notifications, topic, &publish).await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = sent {
+                    warn!(?err, "failed to send mqtt notification");
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                // Polling again reconnects; wait first so a down broker is not hammered.
+                warn!(?err, retry_secs = retry.as_secs(), "mqtt connection failed");
+                tokio::time::sleep(retry).await;
+                retry = (retry * 2).min(RETRY_MAX);
+            }
+        }
+    }
+}
+
+fn client_id(config: &MqttConfig) -> String {
+    let configured = config.client_id.trim();
+    if !configured.is_empty() {
+        return configured.to_string();
+    }
+    match std::fs::read_to_string("/proc/sys/kernel/hostname") {
+        Ok(hostname) if !hostname.trim().is_empty() => format!("unixnotis-{}", hostname.trim()),
+        _ => format!("unixnotis-{}", std::process::id()),
+    }
+}
+
+async fn notify_message(
+    notifications: &NotificationsProxy<'_>,
+    topic: &MqttTopicConfig,
+    publish: &Publish,
+) -> zbus::Result<u32> {
+    let payload = String::from_utf8_lossy(&publish.payload);
+    let json = serde_json::from_str::<Json>(&payload)
+        .ok()
+        .filter(Json::is_object);
+    let message = Message {
+        topic: &publish.topic,
+        payload: payload.trim(),
+        json: json.as_ref(),
+    };
+    let mut summary = message.render(&topic.summary, false);
+    if summary.trim().is_empty() {
+        summary = publish.topic.clone();
+    }
+    // The body template may carry markup of its own; only the substituted values are escaped.
+    let body = message.render(&topic.body, true);
+    debug!(topic = %publish.topic, "mqtt message");
+
+    let actions: Vec<&str> = Vec::new();
+    let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+    hints.insert("urgency", Value::U8(topic.urgency));
+    if let Some(category) = topic.category.as_deref() {
+        hints.insert("category", Value::from(category));
+    }
+    notifications
+        .notify(
+            &topic.app,
+            0,
+            &topic.icon,
+            &summary,
+            &body,
+            &actions,
+            hints,
+            -1,
+        )
+        .await
+}
+
+/// One received message as seen by the summary and body templates.
+struct Message<'a> {
+    topic: &'a str,
+    payload: &'a str,
+    /// The payload parsed as a JSON object, when it is one.
+    json: Option<&'a Json>,
+}
+
+impl Message<'_> {
+    /// Fill `{topic}`, `{payload}`, and `{field.path}` placeholders; missing fields render empty.
+    fn render(&self, template: &str, escape: bool) -> String {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let key = after
+                .find('}')
+                .map(|end| &after[..end])
+                .filter(|key| is_placeholder(key));
+            let Some(key) = key else {
+                out.push('{');
+                rest = after;
+                continue;
+            };
+            let value = self.lookup(key);
+            if escape {
+                out.push_str(&escape_markup(&value));
+            } else {
+                out.push_str(&value);
+            }
+            rest = &after[key.len() + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn lookup(&self, key: &str) -> String {
+        match key {
+            "topic" => return self.topic.to_string(),
+            "payload" => return self.payload.to_string(),
+            _ => {}
+        }
+        let Some(mut value) = self.json else {
+            return String::new();
+        };
+        for part in key.split('.') {
+            match value.get(part) {
+                Some(inner) => value = inner,
+                None => return String::new(),
+            }
+        }
+        match value {
+            Json::Null => String::new(),
+            Json::String(text) => text.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+fn is_placeholder(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_fill_topic_payload_and_json_fields() {
+        let json: Json = serde_json::from_str(
+            r#"{"title": "Door", "message": "Front <door> open", "attributes": {"battery": 80}}"#,
+        )
+        .expect("json parses");
+        let message = Message {
+            topic: "home/door/front",
+            payload: "raw",
+            json: Some(&json),
+        };
+        assert_eq!(
+            message.render("{title} on {topic}", false),
+            "Door on home/door/front"
+        );
+        assert_eq!(
+            message.render("<b>{message}</b> ({attributes.battery}%)", true),
+            "<b>Front &lt;door&gt; open</b> (80%)"
+        );
+        assert_eq!(
+            message.render("{missing}{ not a key }", false),
+            "{ not a key }"
+        );
+
+        let plain = Message {
+            topic: "alerts",
+            payload: "disk full",
+            json: None,
+        };
+        assert_eq!(plain.render("{payload}: {title}", false), "disk full: ");
+    }
+}
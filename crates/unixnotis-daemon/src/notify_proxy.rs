@@ -0,0 +1,159 @@
+//! Client for the daemon's own notification server, used by built-in notification sources.
+//!
+//! Going through Notify instead of the store directly keeps rules, popups, sounds, and history
+//! behaving exactly as for any other client.
+
+use futures_util::StreamExt;
+use tokio::sync::watch;
+use tracing::debug;
+use unixnotis_core::BusNames;
+use zbus::Connection;
+
+use crate::daemon::DaemonState;
+
+pub use unixnotis_core::notifications::{ActionInvoked, ActionInvokedStream, NotificationsProxy};
+
+/// Notifications client for sources that outlive a session bus restart.
+///
+/// A plain proxy stays on the connection it was built with, which is dead once the bus
+/// restarts. This one moves its proxy and its `ActionInvoked` subscription to the daemon's
+/// current connection whenever that changes.
+pub struct Notifier {
+    names: BusNames,
+    connections: watch::Receiver<Connection>,
+    proxy: NotificationsProxy<'static>,
+    /// Subscribed on the first `next_action`; an unread stream would queue every signal.
+    actions: Option<ActionInvokedStream<'static>>,
+}
+
+impl Notifier {
+    /// Client on the daemon's current connection; the notification name must already be owned.
+    pub async fn new(state: &DaemonState, names: BusNames) -> zbus::Result<Self> {
+        let mut connections = state.watch_connection();
+        let connection = connections.borrow_and_update().clone();
+        let proxy = names.notifications_proxy(&connection).await?;
+        Ok(Self {
+            names,
+            connections,
+            proxy,
+            actions: None,
+        })
+    }
+
+    /// Proxy on the connection the daemon serves on now.
+    pub async fn proxy(&mut self) -> zbus::Result<&NotificationsProxy<'static>> {
+        if self.connections.has_changed().unwrap_or(false) {
+            self.follow().await?;
+        }
+        Ok(&self.proxy)
+    }
+
+    /// Next `ActionInvoked` from the daemon, across bus restarts; `None` once the daemon is gone.
+    pub async fn next_action(&mut self) -> Option<ActionInvoked> {
+        loop {
+            if self.actions.is_none() {
+                match self.proxy.receive_action_invoked().await {
+                    Ok(actions) => self.actions = Some(actions),
+                    Err(err) => debug!(?err, "failed to subscribe to ActionInvoked"),
+                }
+            }
+            let actions = self.actions.as_mut();
+            tokio::select! {
+                Some(signal) = async { actions?.next().await } => return Some(signal),
+                changed = self.connections.changed() => {
+                    changed.ok()?;
+                    if let Err(err) = self.follow().await {
+                        debug!(?err, "failed to follow the restarted session bus");
+                    }
+                }
+            }
+        }
+    }
+
+    async fn follow(&mut self) -> zbus::Result<()> {
+        let connection = self.connections.borrow_and_update().clone();
+        self.proxy = self.names.notifications_proxy(&connection).await?;
+        self.actions = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use unixnotis_core::Config;
+    use zbus::zvariant::OwnedValue;
+
+    use super::*;
+    use crate::daemon::NOTIFICATIONS_OBJECT_PATH;
+    use crate::sound::SoundSettings;
+
+    /// Stands in for the daemon's Notify and keeps the summaries it receives.
+    struct Recorder {
+        summaries: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl Recorder {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            _app_name: String,
+            _replaces_id: u32,
+            _app_icon: String,
+            summary: String,
+            _body: String,
+            _actions: Vec<String>,
+            _hints: HashMap<String, OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let mut summaries = self.summaries.lock().unwrap();
+            summaries.push(summary);
+            summaries.len() as u32
+        }
+    }
+
+    /// A client connection whose peer records notifications; the peer must be kept alive.
+    async fn recording_bus(summaries: Arc<Mutex<Vec<String>>>) -> (Connection, Connection) {
+        let (server, client) = tokio::net::UnixStream::pair().unwrap();
+        let server = zbus::connection::Builder::unix_stream(server)
+            .server(zbus::Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at(NOTIFICATIONS_OBJECT_PATH, Recorder { summaries })
+            .unwrap()
+            .build();
+        let client = zbus::connection::Builder::unix_stream(client).p2p().build();
+        tokio::try_join!(server, client).unwrap()
+    }
+
+    async fn send(notifier: &mut Notifier, summary: &str) -> zbus::Result<u32> {
+        notifier
+            .proxy()
+            .await?
+            .notify("test", 0, "", summary, "", &[], HashMap::new(), -1)
+            .await
+    }
+
+    #[tokio::test]
+    async fn notifications_follow_the_daemon_onto_a_restarted_bus() {
+        let first = Arc::new(Mutex::new(Vec::new()));
+        let second = Arc::new(Mutex::new(Vec::new()));
+        let (_first_peer, first_bus) = recording_bus(first.clone()).await;
+        let names = BusNames::new(None).unwrap();
+        let config = Config::default();
+        let sound = SoundSettings::from_config(&config);
+        let state = DaemonState::new(first_bus, names.clone(), config, None, sound);
+        let mut notifier = Notifier::new(&state, names).await.unwrap();
+        send(&mut notifier, "before").await.unwrap();
+
+        let (_second_peer, second_bus) = recording_bus(second.clone()).await;
+        state.replace_connection(second_bus);
+        send(&mut notifier, "after").await.unwrap();
+
+        assert_eq!(*first.lock().unwrap(), ["before"]);
+        assert_eq!(*second.lock().unwrap(), ["after"]);
+    }
+}
@@ -0,0 +1,27 @@
+//! Secrets read from a user command such as `pass show mail/work`.
+
+use std::process::Stdio;
+
+use anyhow::{bail, Context, Result};
+use tokio::process::Command;
+
+/// Run `cmd` and return the first line it prints.
+pub async fn read_password(cmd: &str) -> Result<String> {
+    if cmd.trim().is_empty() {
+        bail!("password_cmd is not set");
+    }
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .context("run password_cmd")?;
+    if !output.status.success() {
+        bail!("password_cmd exited with {}", output.status);
+    }
+    // Password managers such as pass print extra lines after the password.
+    let stdout = String::from_utf8(output.stdout).context("password is not UTF-8")?;
+    Ok(stdout.lines().next().unwrap_or_default().to_string())
+}
@@ -0,0 +1,487 @@
+//! Opt-in HTTP listener that turns JSON payloads into notifications.
+//!
+//! Serves `POST /notify` on a Unix socket and, when `remote.listen` is set, on a TCP address.
+//! Every request must carry the configured shared secret as a bearer token. Requests are
+//! small and one-shot, so the listener speaks just enough HTTP/1.1 for curl and webhook
+//! senders: one request per connection, bodies framed by `Content-Length`.
+
+use std::collections::HashMap;
+use std::fs::{self, DirBuilder, Permissions};
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::{Mutex, Semaphore};
+use tracing::{debug, info, warn};
+use unixnotis_core::{BusNames, RemoteConfig};
+use zbus::zvariant::Value;
+
+use crate::daemon::DaemonState;
+use crate::notify_proxy::Notifier;
+
+const NOTIFY_PATH: &str = "/notify";
+/// Cap on the request line plus headers.
+const MAX_HEAD_BYTES: usize = 8 * 1024;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+/// Whole-request deadline so a stalled client cannot hold a connection slot.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_CONNECTIONS: usize = 16;
+/// Pause after a failed accept, e.g. when the process is out of file descriptors.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Start the listener; call once the notification bus name is owned.
+pub fn spawn_remote_listener(state: Arc<DaemonState>, names: BusNames, config: RemoteConfig) {
+    if !config.enabled {
+        return;
+    }
+    if config.token.trim().is_empty() {
+        warn!("remote.token is empty; not starting the remote listener");
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(err) = listen(state, names, config).await {
+            warn!(?err, "remote listener stopped");
+        }
+    });
+}
+
+async fn listen(state: Arc<DaemonState>, names: BusNames, config: RemoteConfig) -> Result<()> {
+    let path = socket_path(&config, &names).context("XDG_RUNTIME_DIR is not set")?;
+    let notifier = Notifier::new(&state, names).await?;
+    let handler = Arc::new(Handler {
+        notifier: Mutex::new(notifier),
+        token: config.token.trim().to_string(),
+        slots: Arc::new(Semaphore::new(MAX_CONNECTIONS)),
+    });
+
+    let unix = bind_socket(&path).with_context(|| format!("bind {}", path.display()))?;
+    info!(socket = %path.display(), "remote notifications listening");
+    let tcp = match config.listen.as_deref().map(str::trim) {
+        Some(address) if !address.is_empty() => {
+            let listener = TcpListener::bind(address)
+                .await
+                .with_context(|| format!("bind {address}"))?;
+            info!(address, "remote notifications listening on tcp");
+            Some(listener)
+        }
+        _ => None,
+    };
+
+    if let Some(tcp) = tcp {
+        let handler = handler.clone();
+        tokio::spawn(async move {
+            loop {
+                match tcp.accept().await {
+                    Ok((stream, peer)) => {
+                        debug!(%peer, "remote connection");
+                        handler.spawn_connection(stream);
+                    }
+                    Err(err) => {
+                        warn!(?err, "remote tcp accept failed");
+                        tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    }
+                }
+            }
+        });
+    }
+    loop {
+        match unix.accept().await {
+            Ok((stream, _)) => handler.spawn_connection(stream),
+            Err(err) => {
+                warn!(?err, "remote socket accept failed");
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+            }
+        }
+    }
+}
+
+fn socket_path(config: &RemoteConfig, names: &BusNames) -> Option<PathBuf> {
+    if let Some(path) = config.socket.as_deref().map(str::trim) {
+        if !path.is_empty() {
+            return Some(PathBuf::from(path));
+        }
+    }
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")?;
+    let file = match names.suffix() {
+        Some(suffix) => format!("remote-{suffix}.sock"),
+        None => "remote.sock".to_string(),
+    };
+    Some(PathBuf::from(runtime_dir).join("unixnotis").join(file))
+}
+
+fn bind_socket(path: &Path) -> io::Result<UnixListener> {
+    if let Some(parent) = path.parent() {
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(parent)?;
+    }
+    // A socket left behind by an earlier run makes bind fail; anything else is not ours.
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "path exists and is not a socket",
+            ))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+struct Handler {
+    notifier: Mutex<Notifier>,
+    token: String,
+    slots: Arc<Semaphore>,
+}
+
+impl Handler {
+    fn spawn_connection<S>(self: &Arc<Self>, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let Ok(slot) = self.slots.clone().try_acquire_owned() else {
+            debug!("too many remote connections; dropping one");
+            return;
+        };
+        let handler = self.clone();
+        tokio::spawn(async move {
+            handler.serve(stream).await;
+            drop(slot);
+        });
+    }
+
+    async fn serve<S>(&self, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut stream = BufReader::new(stream);
+        let response = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await
+        {
+            Ok(Ok(request)) => self.handle(request).await,
+            Ok(Err(response)) => response,
+            Err(_) => Response::error(408, "request timed out"),
+        };
+        let stream = stream.get_mut();
+        if let Err(err) = stream.write_all(&response.to_bytes()).await {
+            debug!(?err, "failed to write remote response");
+        }
+        let _ = stream.shutdown().await;
+    }
+
+    async fn handle(&self, request: Request) -> Response {
+        if request.head.path != NOTIFY_PATH {
+            return Response::error(404, "only /notify is served");
+        }
+        if request.head.method != "POST" {
+            return Response::error(405, "use POST");
+        }
+        if !token_matches(request.head.authorization.as_deref(), &self.token) {
+            warn!("rejected remote notification with a missing or wrong token");
+            return Response::error(401, "missing or wrong bearer token");
+        }
+        let payload: Payload = match serde_json::from_slice(&request.body) {
+            Ok(payload) => payload,
+            Err(err) => return Response::error(400, &format!("invalid payload: {err}")),
+        };
+        if payload.summary.trim().is_empty() {
+            return Response::error(400, "summary is required");
+        }
+        match self.notify(&payload).await {
+            Ok(id) => Response {
+                status: 200,
+                body: serde_json::json!({ "id": id }).to_string(),
+            },
+            Err(err) => {
+                warn!(?err, "failed to post remote notification");
+                Response::error(500, "notification server unavailable")
+            }
+        }
+    }
+
+    async fn notify(&self, payload: &Payload) -> zbus::Result<u32> {
+        let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+        hints.insert(
+            "urgency",
+            Value::U8(payload.urgency.unwrap_or_default() as u8),
+        );
+        if let Some(category) = payload.category.as_deref() {
+            hints.insert("category", Value::from(category));
+        }
+        let actions: Vec<&str> = Vec::new();
+        let mut notifier = self.notifier.lock().await;
+        notifier
+            .proxy()
+            .await?
+            .notify(
+                payload.app.as_deref().unwrap_or("Remote"),
+                0,
+                payload.icon.as_deref().unwrap_or_default(),
+                &payload.summary,
+                &payload.body,
+                &actions,
+                hints,
+                payload.timeout_ms.unwrap_or(-1),
+            )
+            .await
+    }
+}
+
+/// JSON body of `POST /notify`; `title` and `message` are accepted for common webhook senders.
+#[derive(Debug, Deserialize)]
+struct Payload {
+    #[serde(alias = "title")]
+    summary: String,
+    #[serde(default, alias = "message")]
+    body: String,
+    #[serde(default)]
+    app: Option<String>,
+    #[serde(default)]
+    icon: Option<String>,
+    #[serde(default)]
+    urgency: Option<PayloadUrgency>,
+    /// Milliseconds on screen; 0 keeps it until dismissed, unset uses the popup default.
+    #[serde(default)]
+    timeout_ms: Option<i32>,
+    #[serde(default)]
+    category: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum PayloadUrgency {
+    Low = 0,
+    #[default]
+    Normal = 1,
+    Critical = 2,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Head {
+    method: String,
+    path: String,
+    content_length: Option<usize>,
+    authorization: Option<String>,
+    expect_continue: bool,
+}
+
+struct Request {
+    head: Head,
+    body: Vec<u8>,
+}
+
+async fn read_request<S>(stream: &mut BufReader<S>) -> Result<Request, Response>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut head = String::new();
+    loop {
+        let before = head.len();
+        // Read at most one byte past the cap so a line without a newline cannot grow `head`.
+        let budget = (MAX_HEAD_BYTES + 1 - head.len()) as u64;
+        let read = (&mut *stream)
+            .take(budget)
+            .read_line(&mut head)
+            .await
+            .map_err(|_| Response::error(400, "unreadable request"))?;
+        if head.len() > MAX_HEAD_BYTES {
+            return Err(Response::error(431, "headers too large"));
+        }
+        if read == 0 {
+            return Err(Response::error(400, "incomplete request"));
+        }
+        if head[before..].trim_end_matches(['\r', '\n']).is_empty() {
+            break;
+        }
+    }
+    let head = parse_head(&head)?;
+    let length = match (head.method.as_str(), head.content_length) {
+        (_, Some(length)) => length,
+        ("POST", None) => return Err(Response::error(411, "Content-Length is required")),
+        (_, None) => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(Response::error(413, "payload too large"));
+    }
+    if head.expect_continue {
+        // curl waits for this before sending larger bodies.
+        let _ = stream
+            .get_mut()
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+            .await;
+    }
+    let mut body = vec![0; length];
+    stream
+        .read_exact(&mut body)
+        .await
+        .map_err(|_| Response::error(400, "body shorter than Content-Length"))?;
+    Ok(Request { head, body })
+}
+
+fn parse_head(head: &str) -> Result<Head, Response> {
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target), Some(version)) = (
+        request_line.next(),
+        request_line.next(),
+        request_line.next(),
+    ) else {
+        return Err(Response::error(400, "malformed request line"));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(Response::error(400, "only HTTP/1.x is supported"));
+    }
+    let mut parsed = Head {
+        method: method.to_string(),
+        path: target.split('?').next().unwrap_or(target).to_string(),
+        content_length: None,
+        authorization: None,
+        expect_continue: false,
+    };
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => {
+                let length = value
+                    .parse()
+                    .map_err(|_| Response::error(400, "invalid Content-Length"))?;
+                parsed.content_length = Some(length);
+            }
+            "authorization" => parsed.authorization = Some(value.to_string()),
+            "expect" => parsed.expect_continue = value.eq_ignore_ascii_case("100-continue"),
+            "transfer-encoding" => {
+                return Err(Response::error(411, "chunked bodies are not supported"));
+            }
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+/// Compare the bearer token without short-circuiting on the first differing byte.
+fn token_matches(authorization: Option<&str>, token: &str) -> bool {
+    let Some(given) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    let (given, token) = (given.trim().as_bytes(), token.as_bytes());
+    given.len() == token.len()
+        && given
+            .iter()
+            .zip(token)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.status,
+            reason(self.status),
+            self.body.len(),
+            self.body
+        )
+        .into_bytes()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_head_reads_framing_and_token() {
+        let head = parse_head(
+            "POST /notify?x=1 HTTP/1.1\r\nHost: localhost\r\ncontent-length: 42\r\n\
+             Authorization: Bearer s3cret\r\nExpect: 100-continue\r\n\r\n",
+        )
+        .unwrap_or_else(|response| panic!("rejected with {}", response.status));
+        assert_eq!(
+            head,
+            Head {
+                method: "POST".to_string(),
+                path: "/notify".to_string(),
+                content_length: Some(42),
+                authorization: Some("Bearer s3cret".to_string()),
+                expect_continue: true,
+            }
+        );
+        assert!(parse_head("GARBAGE\r\n\r\n").is_err());
+        assert!(parse_head("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn oversized_request_line_is_refused_without_a_newline() {
+        let (mut client, server) = tokio::io::duplex(4 * MAX_HEAD_BYTES);
+        let line = format!("POST /{} HTTP/1.1", "a".repeat(2 * MAX_HEAD_BYTES));
+        client.write_all(line.as_bytes()).await.unwrap();
+        // The client stays connected, so only the cap can end the read.
+        let mut reader = BufReader::new(server);
+        match read_request(&mut reader).await {
+            Err(response) => assert_eq!(response.status, 431),
+            Ok(_) => panic!("oversized request accepted"),
+        }
+        drop(client);
+    }
+
+    #[test]
+    fn token_must_match_exactly() {
+        assert!(token_matches(Some("Bearer s3cret"), "s3cret"));
+        assert!(!token_matches(Some("Bearer s3cre"), "s3cret"));
+        assert!(!token_matches(Some("s3cret"), "s3cret"));
+        assert!(!token_matches(None, "s3cret"));
+    }
+
+    #[test]
+    fn payload_accepts_webhook_aliases() {
+        let payload: Payload = serde_json::from_str(
+            r#"{"title": "Backup failed", "message": "disk full", "urgency": "critical"}"#,
+        )
+        .expect("payload parses");
+        assert_eq!(payload.summary, "Backup failed");
+        assert_eq!(payload.body, "disk full");
+        assert_eq!(payload.urgency, Some(PayloadUrgency::Critical));
+        assert_eq!(payload.timeout_ms, None);
+    }
+}
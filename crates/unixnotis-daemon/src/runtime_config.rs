@@ -6,13 +6,15 @@ use std::env;
 use std::fs;
 use std::os::unix::fs::FileTypeExt;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use tracing_subscriber::EnvFilter;
+use tracing::{info, warn};
 use unixnotis_core::Config;
 
 use super::Args;
+use crate::daemon::DaemonState;
 
 pub(super) fn load_config(args: &Args) -> Result<Config> {
     match args.config.as_ref() {
@@ -21,17 +23,62 @@ pub(super) fn load_config(args: &Args) -> Result<Config> {
     }
 }
 
-pub(super) fn init_tracing(config: &Config) {
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        EnvFilter::new(
-            config
-                .general
-                .log_level
-                .clone()
-                .unwrap_or_else(|| "info".to_string()),
-        )
+/// Reload the config on SIGHUP, e.g. from `systemctl --user reload unixnotis-daemon`.
+///
+/// `path` is the `--config` override, if any. A file that fails to parse leaves the running
+/// config untouched.
+pub(super) fn spawn_reload_on_hangup(state: Arc<DaemonState>, path: Option<PathBuf>) {
+    tokio::spawn(async move {
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+        {
+            Ok(signal) => signal,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    "failed to listen for SIGHUP; config reload is unavailable"
+                );
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            let loaded = match path.as_deref() {
+                Some(path) => Config::load_from_path(path),
+                None => Config::load_default(),
+            };
+            let config = match loaded {
+                Ok(config) => config,
+                Err(err) => {
+                    warn!(%err, "config reload failed; keeping the running config");
+                    continue;
+                }
+            };
+            if let Err(err) = state.reload_config(config).await {
+                warn!(?err, "failed to announce config reload");
+            }
+        }
     });
-    tracing_subscriber::fmt().with_env_filter(filter).init();
+}
+
+/// Write the commented config template when no config exists yet.
+///
+/// Returns the path when this run created it. Trial runs and explicit `--config` paths never
+/// write anything.
+pub(super) fn write_first_run_config(args: &Args) -> Option<PathBuf> {
+    if args.trial || args.config.is_some() {
+        return None;
+    }
+    let path = Config::default_config_path().ok()?;
+    match Config::write_default_config(&path) {
+        Ok(true) => {
+            info!(path = %path.display(), "wrote default config");
+            Some(path)
+        }
+        Ok(false) => None,
+        Err(err) => {
+            warn!(?err, "failed to write default config");
+            None
+        }
+    }
 }
 
 pub(super) async fn ensure_wayland_session(timeout: Duration) -> Result<()> {
@@ -0,0 +1,50 @@
+//! Handling for notifications whose sender left the bus (`general.sender_exit`).
+//!
+//! Only notifications with actions are touched. Without actions nothing in them looks
+//! clickable, and one-shot senders such as `notify-send` disconnect right after sending.
+//! The watcher always runs and reads the mode on every departure, so a reload can switch it
+//! on or off.
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use tracing::warn;
+use zbus::fdo::DBusProxy;
+use zbus::names::BusName;
+
+use crate::daemon::DaemonState;
+
+pub fn spawn_sender_watch(state: Arc<DaemonState>) {
+    tokio::spawn(async move {
+        if let Err(err) = watch_senders(state).await {
+            warn!(?err, "sender watcher stopped");
+        }
+    });
+}
+
+async fn watch_senders(state: Arc<DaemonState>) -> zbus::Result<()> {
+    let dbus = DBusProxy::new(&state.connection()).await?;
+    // Match only names losing their owner; every client connect would wake us otherwise.
+    let mut changes = dbus
+        .receive_name_owner_changed_with_args(&[(2, "")])
+        .await?;
+    while let Some(signal) = changes.next().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        // Unique names are never reused, so an ownerless one is a client that disconnected.
+        let BusName::Unique(name) = args.name() else {
+            continue;
+        };
+        if args.new_owner().is_some() {
+            continue;
+        }
+        if let Err(err) = state.sender_departed(name.as_str()).await {
+            warn!(
+                ?err,
+                "failed to update notifications from a departed sender"
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+//! Serving the daemon on the session bus, and serving it again after the bus restarts.
+//!
+//! A restarted bus (it happens in nested sessions) takes every name and object with it.
+//! `disconnected` notices the old connection failing and `reconnect` registers the same
+//! state on a fresh one, so clients see the daemon come back instead of a dead name.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use tracing::{debug, info, warn};
+use unixnotis_core::{BusNames, CONTROL_OBJECT_PATH};
+use zbus::fdo::RequestNameReply;
+use zbus::{Connection, MatchRule, MessageStream};
+
+use crate::daemon::{
+    log_name_reply, request_control_name, request_well_known_name, ControlServer, DaemonState,
+    NotificationServer, NOTIFICATIONS_OBJECT_PATH,
+};
+use crate::expire::ExpirationScheduler;
+
+const RETRY_MIN: Duration = Duration::from_millis(250);
+const RETRY_MAX: Duration = Duration::from_secs(5);
+
+/// Register the notification and control objects on `connection`.
+pub async fn serve(
+    connection: &Connection,
+    state: &Arc<DaemonState>,
+    scheduler: &ExpirationScheduler,
+) -> zbus::Result<()> {
+    connection
+        .object_server()
+        .at(
+            NOTIFICATIONS_OBJECT_PATH,
+            NotificationServer::new(state.clone(), scheduler.clone()),
+        )
+        .await?;
+    connection
+        .object_server()
+        .at(
+            CONTROL_OBJECT_PATH,
+            ControlServer::new(state.clone(), scheduler.clone()),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Resolve once `connection` can no longer reach the bus.
+pub async fn disconnected(connection: &Connection) {
+    watch_until_failed(name_lost_stream(connection).await).await
+}
+
+async fn watch_until_failed(stream: zbus::Result<MessageStream>) {
+    let mut stream = match stream {
+        Ok(stream) => stream,
+        Err(err) => {
+            // The bus refused the match but is still there; reconnecting now would find our
+            // own names taken and exit, so give up on noticing a restart instead.
+            warn!(?err, "failed to watch the session bus connection");
+            return std::future::pending().await;
+        }
+    };
+    while let Some(message) = stream.next().await {
+        match message {
+            Ok(message) => {
+                let name: Option<String> = message.body().deserialize().ok();
+                info!(name = name.as_deref().unwrap_or("?"), "lost bus name");
+            }
+            Err(err) => {
+                debug!(?err, "session bus connection failed");
+                return;
+            }
+        }
+    }
+}
+
+/// NameLost is addressed to us alone, so this stream stays quiet until the socket fails and
+/// the error is handed to every stream on the connection.
+async fn name_lost_stream(connection: &Connection) -> zbus::Result<MessageStream> {
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.freedesktop.DBus")?
+        .interface("org.freedesktop.DBus")?
+        .member("NameLost")?
+        .build();
+    MessageStream::for_match_rule(rule, connection, None).await
+}
+
+/// Connect to the restarted bus and serve `state` there under the same names.
+///
+/// Keeps retrying until the bus is back. Fails only when another process took one of the
+/// names first, which a restart cannot fix.
+pub async fn reconnect(
+    state: &Arc<DaemonState>,
+    scheduler: &ExpirationScheduler,
+    names: &BusNames,
+    trial: bool,
+) -> Result<Connection> {
+    let mut delay = RETRY_MIN;
+    let connection = loop {
+        match Connection::session().await {
+            Ok(connection) => break connection,
+            Err(err) => {
+                debug!(
+                    ?err,
+                    retry_ms = delay.as_millis(),
+                    "session bus not back yet"
+                );
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(RETRY_MAX);
+            }
+        }
+    };
+    state.replace_connection(connection.clone());
+    serve(&connection, state, scheduler).await?;
+
+    let control_reply = request_control_name(&connection, names).await?;
+    if !matches!(
+        control_reply,
+        RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner
+    ) {
+        return Err(anyhow!(
+            "{} was taken while the session bus restarted",
+            names.control
+        ));
+    }
+    let reply = request_well_known_name(&connection, names, trial).await?;
+    log_name_reply(names, &reply);
+    if !trial
+        && !matches!(
+            reply,
+            RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner
+        )
+    {
+        return Err(anyhow!(
+            "{} was taken while the session bus restarted",
+            names.notifications
+        ));
+    }
+
+    // Restarted UIs seed themselves from the control interface; this covers listeners that
+    // only follow state_changed.
+    if let Err(err) = state.emit_state_changed().await {
+        warn!(?err, "failed to announce state after reconnecting");
+    }
+    info!("serving again on the restarted session bus");
+    Ok(connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_refused_match_is_not_a_disconnect() {
+        let refused = Err(zbus::Error::Failure("AddMatch refused".to_string()));
+        let watch = tokio::time::timeout(Duration::from_millis(50), watch_until_failed(refused));
+        assert!(watch.await.is_err());
+    }
+}
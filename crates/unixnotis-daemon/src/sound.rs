@@ -20,6 +20,8 @@ pub struct SoundSettings {
     backend: SoundBackend,
     default_name: Option<String>,
     default_file: Option<PathBuf>,
+    /// Sound from `[popups.urgent]` that replaces any other for urgent notifications.
+    urgent: Option<SoundSource>,
     last_played: Mutex<Option<Instant>>,
 }
 
@@ -52,6 +54,7 @@ impl SoundSettings {
             backend,
             default_name: config.sound.default_name.clone(),
             default_file,
+            urgent: resolve_urgent_source(config),
             last_played: Mutex::new(None),
         }
     }
@@ -62,7 +65,14 @@ impl SoundSettings {
     }
 
     /// Resolve a sound source from hints or defaults and play if allowed.
-    pub fn play_from_hints(&self, hints: &HashMap<String, OwnedValue>, allow_sound: bool) {
+    ///
+    /// `urgent` notifications play the `[popups.urgent]` sound instead, when one is set.
+    pub fn play_from_hints(
+        &self,
+        hints: &HashMap<String, OwnedValue>,
+        allow_sound: bool,
+        urgent: bool,
+    ) {
         if !self.enabled || !allow_sound {
             return;
         }
@@ -73,7 +83,12 @@ impl SoundSettings {
             return;
         }
 
-        let source = resolve_hint_sound(hints).or_else(|| self.default_source());
+        let source = self
+            .urgent
+            .clone()
+            .filter(|_| urgent)
+            .or_else(|| resolve_hint_sound(hints))
+            .or_else(|| self.default_source());
         if let Some(source) = source {
             self.play(source);
         }
@@ -190,6 +205,15 @@ fn resolve_default_file(config: &Config) -> Option<PathBuf> {
     None
 }
 
+fn resolve_urgent_source(config: &Config) -> Option<SoundSource> {
+    let urgent = &config.popups.urgent;
+    if let Some(path) = urgent.sound_file.as_ref() {
+        let path = resolve_config_path(path).unwrap_or_else(|| PathBuf::from(path));
+        return Some(SoundSource::File(path));
+    }
+    urgent.sound_name.clone().map(SoundSource::Name)
+}
+
 fn resolve_config_path(value: &str) -> Option<PathBuf> {
     let path = PathBuf::from(value);
     if path.is_absolute() {
@@ -1,11 +1,21 @@
 //! Notification store with ordering and history management.
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
-use unixnotis_core::{Config, Notification, NotificationView, RuleConfig, Urgency};
+use tracing::instrument;
+use unixnotis_core::{
+    ClearScope, CloseReason, Config, ControlState, Notification, NotificationView, RuleConfig,
+    Urgency,
+};
+
+use crate::inhibit::InhibitReason;
+
+/// Shortest timeout left after the pointer leaves a paused popup.
+const HOLD_RELEASE_MIN: Duration = Duration::from_secs(1);
 
 /// Mutable notification state owned by the daemon.
 pub struct NotificationStore {
@@ -14,15 +24,56 @@ pub struct NotificationStore {
     active: IndexMap<u32, Arc<Notification>>,
     history: HistoryStore,
     expirations: HashMap<u32, Instant>,
+    // Time left on timeouts paused while the pointer is over their popup.
+    held: HashMap<u32, Duration>,
+    // When logind announced a suspend still in progress; nothing expires until resume.
+    // Also read off CLOCK_BOOTTIME, which unlike `Instant` keeps counting while suspended.
+    asleep_since: Option<(Instant, Duration)>,
+    // Popup stacking slot per active notification; replacements keep theirs.
+    popup_slots: HashMap<u32, u32>,
+    next_popup_slot: u32,
     dnd_enabled: bool,
+    // End of a timed Do Not Disturb period; None while DND is off or untimed.
+    dnd_until: Option<DateTime<Utc>>,
+    // Conditions holding back popups and sounds like DND, such as a fullscreen window.
+    inhibitors: BTreeSet<InhibitReason>,
+    // Runtime switch for every popup, critical ones too; sounds and the panel are unaffected.
+    popups_enabled: bool,
+    // Runtime mute for every sound, critical ones too; popups are unaffected.
+    muted: bool,
+    // Lifetime eviction counters surfaced in ControlState to flag undersized limits.
+    active_evictions: u32,
+    history_evictions: u32,
+    // Rules generated for running MPRIS players, applied before user rules.
+    player_rules: Vec<RuleConfig>,
+    // What the last clear removed, kept until its undo window closes.
+    cleared: Option<ClearedBatch>,
+}
+
+struct ClearedBatch {
+    // Active notifications oldest first, with their expiration deadlines.
+    active: Vec<(Arc<Notification>, Option<Instant>)>,
+    history: Vec<(Arc<Notification>, Option<Instant>)>,
+    undo_until: Instant,
 }
 
 pub struct InsertOutcome {
     pub notification: Arc<Notification>,
     pub replaced: bool,
     pub show_popup: bool,
+    /// Stacking slot for the popup, set when `show_popup` is.
+    pub popup_slot: Option<u32>,
     pub allow_sound: bool,
+    /// Ids moved to history by `max_active`, or this one when suppression sent it there.
+    pub evicted: Vec<u32>,
+}
+
+/// Notifications a reloaded config pushed out of the store.
+pub struct ReloadOutcome {
+    /// Active notifications moved to history by a lower `max_active`, oldest first.
     pub evicted: Vec<u32>,
+    /// History entries dropped by a lower `max_entries`, oldest first.
+    pub dropped: Vec<u32>,
 }
 
 pub struct DismissOutcome {
@@ -36,9 +87,39 @@ impl DismissOutcome {
     }
 }
 
+/// Notifications a clear removed.
+pub struct ClearOutcome {
+    /// Active notifications, newest first.
+    pub active: Vec<u32>,
+    /// History entries, in history order.
+    pub history: Vec<u32>,
+}
+
+impl ClearOutcome {
+    pub fn removed_any(&self) -> bool {
+        !self.active.is_empty() || !self.history.is_empty()
+    }
+}
+
+/// What an undone clear put back, and what the limits pushed out to make room.
+pub struct UndoOutcome {
+    /// Restored active notifications with their expiration deadlines.
+    pub restored: Vec<(Arc<Notification>, Option<Instant>)>,
+    /// Entries put back into history, including restored ones the active limit moved there.
+    pub history: Vec<u32>,
+    /// Active notifications from before the undo that the active limit moved to history.
+    pub evicted: Vec<u32>,
+    /// Earlier history entries, and evicted ones, that `max_entries` dropped.
+    pub dropped: Vec<u32>,
+}
+
 struct HistoryStore {
     entries: HashMap<u32, Arc<Notification>>,
     order: VecDeque<u32>,
+    // Prune deadlines for entries archived with a rule TTL.
+    deadlines: HashMap<u32, Instant>,
+    // Bumped on every change so the history file is only rewritten when needed.
+    revision: u64,
 }
 
 impl HistoryStore {
@@ -46,6 +127,8 @@ impl HistoryStore {
         Self {
             entries: HashMap::new(),
             order: VecDeque::new(),
+            deadlines: HashMap::new(),
+            revision: 0,
         }
     }
 
@@ -58,8 +141,12 @@ impl HistoryStore {
     }
 
     fn clear(&mut self) {
+        if !self.entries.is_empty() {
+            self.revision += 1;
+        }
         self.entries.clear();
         self.order.clear();
+        self.deadlines.clear();
     }
 
     fn list_views(&self) -> Vec<NotificationView> {
@@ -72,9 +159,22 @@ impl HistoryStore {
         views
     }
 
+    fn list_views_range(&self, offset: usize, limit: usize) -> Vec<NotificationView> {
+        self.order
+            .iter()
+            .rev()
+            .filter_map(|id| self.entries.get(id))
+            .skip(offset)
+            .take(limit)
+            .map(|notification| notification.to_list_view())
+            .collect()
+    }
+
     fn remove(&mut self, id: &u32) -> Option<Arc<Notification>> {
         let removed = self.entries.remove(id);
+        self.deadlines.remove(id);
         if removed.is_some() {
+            self.revision += 1;
             // Removal is infrequent compared to insertion; pay the cost here to keep order clean.
             self.order.retain(|entry| entry != id);
         }
@@ -82,25 +182,124 @@ impl HistoryStore {
     }
 
     fn insert(&mut self, notification: Arc<Notification>) {
+        let deadline = notification.history_ttl_minutes.map(|minutes| {
+            let ttl = Duration::from_secs(u64::from(minutes) * 60);
+            Instant::now() + ttl
+        });
+        self.insert_with_deadline(notification, deadline);
+    }
+
+    fn insert_with_deadline(&mut self, notification: Arc<Notification>, deadline: Option<Instant>) {
         let id = notification.id;
         if self.entries.contains_key(&id) {
             // Avoid duplicate IDs in order when a notification is replaced.
             self.order.retain(|entry| *entry != id);
         }
+        match deadline {
+            Some(deadline) => {
+                self.deadlines.insert(id, deadline);
+            }
+            None => {
+                self.deadlines.remove(&id);
+            }
+        }
         self.entries.insert(id, notification);
         self.order.push_back(id);
+        self.revision += 1;
     }
 
-    fn evict_to_limit(&mut self, max_entries: usize) {
-        while self.entries.len() > max_entries {
-            if let Some(id) = self.order.pop_front() {
-                if self.entries.remove(&id).is_some() {
-                    continue;
-                }
-            } else {
-                break;
-            }
+    /// Entries oldest first, with their TTL prune deadline if any.
+    fn snapshot(&self) -> Vec<(Arc<Notification>, Option<Instant>)> {
+        self.order
+            .iter()
+            .filter_map(|id| {
+                let notification = self.entries.get(id)?;
+                Some((notification.clone(), self.deadlines.get(id).copied()))
+            })
+            .collect()
+    }
+
+    fn is_pinned(&self, id: &u32) -> bool {
+        self.entries
+            .get(id)
+            .is_some_and(|notification| notification.pinned)
+    }
+
+    fn prune_expired(&mut self, now: Instant) -> Vec<u32> {
+        if self.deadlines.is_empty() {
+            return Vec::new();
+        }
+        // Pinned entries keep their deadline so unpinning lets the rule TTL apply again.
+        let expired: Vec<u32> = self
+            .deadlines
+            .iter()
+            .filter(|(id, deadline)| **deadline <= now && !self.is_pinned(id))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &expired {
+            self.remove(id);
+        }
+        expired
+    }
+
+    fn prune_received_before(&mut self, cutoff: DateTime<Utc>) -> Vec<u32> {
+        let stale: Vec<u32> = self
+            .order
+            .iter()
+            .filter(|id| {
+                self.entries.get(id).is_some_and(|notification| {
+                    notification.received_at < cutoff && !notification.pinned
+                })
+            })
+            .copied()
+            .collect();
+        for id in &stale {
+            self.remove(id);
         }
+        stale
+    }
+
+    fn estimated_bytes(&self) -> usize {
+        self.entries
+            .values()
+            .map(|notification| notification.estimated_size())
+            .sum::<usize>()
+            + self.order.capacity() * std::mem::size_of::<u32>()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.entries.shrink_to_fit();
+        self.order.shrink_to_fit();
+        self.deadlines.shrink_to_fit();
+    }
+
+    /// Drop the oldest unpinned entries past `max_entries` and return their ids; pinned
+    /// entries can push history over the limit.
+    fn evict_to_limit(&mut self, max_entries: usize) -> Vec<u32> {
+        let excess = self.entries.len().saturating_sub(max_entries);
+        if excess == 0 {
+            return Vec::new();
+        }
+        let evicted: Vec<u32> = self
+            .order
+            .iter()
+            .filter(|id| !self.is_pinned(id))
+            .take(excess)
+            .copied()
+            .collect();
+        for id in &evicted {
+            self.remove(id);
+        }
+        evicted
+    }
+
+    fn set_pinned(&mut self, id: u32, pinned: bool) -> Option<Arc<Notification>> {
+        let entry = self.entries.get_mut(&id)?;
+        if entry.pinned != pinned {
+            *entry = Arc::new(entry.with_pinned(pinned));
+            self.revision += 1;
+        }
+        Some(entry.clone())
     }
 }
 
@@ -109,10 +308,22 @@ impl NotificationStore {
         Self {
             next_id: 1,
             dnd_enabled: config.general.dnd_default,
+            dnd_until: None,
+            inhibitors: BTreeSet::new(),
+            popups_enabled: true,
+            muted: false,
             config,
             active: IndexMap::new(),
             history: HistoryStore::new(),
             expirations: HashMap::new(),
+            held: HashMap::new(),
+            asleep_since: None,
+            popup_slots: HashMap::new(),
+            next_popup_slot: 1,
+            active_evictions: 0,
+            history_evictions: 0,
+            player_rules: Vec::new(),
+            cleared: None,
         }
     }
 
@@ -120,12 +331,105 @@ impl NotificationStore {
         &self.config
     }
 
+    /// Swap in a reloaded config and bring the store within its limits.
+    ///
+    /// The new config is in place before anything moves, so every eviction follows the new
+    /// limits and history policy. A lower `max_active` sends the oldest active notifications
+    /// to history as an overflowing Notify would; a lower `max_entries` then drops the oldest
+    /// unpinned history. Do Not Disturb, inhibitors, the popup switch, mute, and player rules
+    /// are runtime state and stay as they are.
+    pub fn apply_config(&mut self, config: Config) -> ReloadOutcome {
+        let history_before: Vec<u32> = self.history.order.iter().copied().collect();
+        self.config = config;
+        let evicted = self.enforce_active_limit();
+        self.trim_history();
+        // Entries pushed out while the evicted ones were archived count as dropped too.
+        let dropped = history_before
+            .into_iter()
+            .filter(|id| !self.history.contains(id))
+            .collect();
+        ReloadOutcome { evicted, dropped }
+    }
+
+    pub fn rules(&self) -> &[RuleConfig] {
+        &self.config.rules
+    }
+
+    /// Replace the rule with the same name in place, or append it so it applies last.
+    pub fn set_rule(&mut self, rule: RuleConfig) {
+        let existing = self
+            .config
+            .rules
+            .iter_mut()
+            .find(|existing| existing.name.is_some() && existing.name == rule.name);
+        match existing {
+            Some(existing) => *existing = rule,
+            None => self.config.rules.push(rule),
+        }
+    }
+
+    /// Remove every rule named `name`; returns false when none matched.
+    pub fn remove_rule(&mut self, name: &str) -> bool {
+        let before = self.config.rules.len();
+        self.config
+            .rules
+            .retain(|rule| rule.name.as_deref() != Some(name));
+        self.config.rules.len() != before
+    }
+
     pub fn dnd_enabled(&self) -> bool {
         self.dnd_enabled
     }
 
     pub fn set_dnd(&mut self, enabled: bool) {
         self.dnd_enabled = enabled;
+        self.dnd_until = None;
+    }
+
+    /// Start or stop holding back notifications for `reason`; returns whether anything changed.
+    pub fn set_inhibited(&mut self, reason: InhibitReason, active: bool) -> bool {
+        if active {
+            self.inhibitors.insert(reason)
+        } else {
+            self.inhibitors.remove(&reason)
+        }
+    }
+
+    /// Switch popups off or on; returns whether anything changed.
+    pub fn set_popups_enabled(&mut self, enabled: bool) -> bool {
+        std::mem::replace(&mut self.popups_enabled, enabled) != enabled
+    }
+
+    pub fn popups_enabled(&self) -> bool {
+        self.popups_enabled
+    }
+
+    /// Mute or unmute sounds; returns whether anything changed.
+    pub fn set_muted(&mut self, muted: bool) -> bool {
+        std::mem::replace(&mut self.muted, muted) != muted
+    }
+
+    /// Whether popups and sounds are held back, by DND or by an inhibitor.
+    fn quiet(&self) -> bool {
+        self.dnd_enabled || !self.inhibitors.is_empty()
+    }
+
+    /// Enable Do Not Disturb until `until`, replacing any earlier end time.
+    pub fn set_dnd_until(&mut self, until: DateTime<Utc>) {
+        self.dnd_enabled = true;
+        self.dnd_until = Some(until);
+    }
+
+    /// Turn off timed Do Not Disturb once its end time has passed.
+    ///
+    /// Only the period ending at `until` is cleared, so a stale timer cannot end a newer one.
+    pub fn expire_dnd(&mut self, until: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        if self.dnd_until != Some(until) || now < until {
+            return false;
+        }
+        self.dnd_enabled = false;
+        self.dnd_until = None;
+        true
     }
 
     pub fn list_active(&self) -> Vec<NotificationView> {
@@ -136,14 +440,117 @@ impl NotificationStore {
             .collect()
     }
 
+    /// Look up a notification that is still active or in history.
+    pub fn find(&self, id: u32) -> Option<Arc<Notification>> {
+        self.active
+            .get(&id)
+            .or_else(|| self.history.entries.get(&id))
+            .cloned()
+    }
+
+    /// Remove the actions of active notifications from `sender` and return the updated ones.
+    pub fn strip_sender_actions(&mut self, sender: &str) -> Vec<Arc<Notification>> {
+        let mut updated = Vec::new();
+        for notification in self.active.values_mut() {
+            if notification.actions.is_empty() || notification.sender.as_deref() != Some(sender) {
+                continue;
+            }
+            *notification = Arc::new(notification.without_actions());
+            updated.push(notification.clone());
+        }
+        updated
+    }
+
+    /// Pin or unpin a notification; when it exists, returns it and the history entries the
+    /// change dropped.
+    pub fn set_pinned(&mut self, id: u32, pinned: bool) -> Option<(Arc<Notification>, Vec<u32>)> {
+        if let Some(entry) = self.active.get_mut(&id) {
+            if entry.pinned != pinned {
+                *entry = Arc::new(entry.with_pinned(pinned));
+            }
+            return Some((entry.clone(), Vec::new()));
+        }
+        let notification = self.history.set_pinned(id, pinned)?;
+        if pinned {
+            return Some((notification, Vec::new()));
+        }
+        // Unpinning may leave history over the limit it was exempt from.
+        let dropped = self.trim_history();
+        Some((notification, dropped))
+    }
+
     pub fn list_history(&self) -> Vec<NotificationView> {
         self.history.list_views()
     }
 
+    /// One page of history, newest first.
+    pub fn list_history_range(&self, offset: usize, limit: usize) -> Vec<NotificationView> {
+        self.history.list_views_range(offset, limit)
+    }
+
     pub fn history_len(&self) -> usize {
         self.history.len()
     }
 
+    pub fn control_state(&self) -> ControlState {
+        ControlState {
+            dnd_enabled: self.dnd_enabled(),
+            dnd_until: self.dnd_until.map_or(0, |until| until.timestamp()),
+            history_count: self.history_len() as u32,
+            active_evictions: self.active_evictions,
+            history_evictions: self.history_evictions,
+            inhibited_by: self
+                .inhibitors
+                .iter()
+                .map(|reason| reason.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            popups_enabled: self.popups_enabled,
+            muted: self.muted,
+        }
+    }
+
+    /// Estimated memory held by active and history notifications, in bytes.
+    pub fn memory_estimates(&self) -> Vec<(String, u64)> {
+        let active: usize = self
+            .active
+            .values()
+            .map(|notification| notification.estimated_size())
+            .sum();
+        vec![
+            ("active_notifications".to_string(), active as u64),
+            (
+                "history_notifications".to_string(),
+                self.history.estimated_bytes() as u64,
+            ),
+        ]
+    }
+
+    /// Release spare capacity left behind by bursts or clears.
+    pub fn shrink_buffers(&mut self) {
+        self.active.shrink_to_fit();
+        self.history.shrink_to_fit();
+        self.expirations.shrink_to_fit();
+    }
+
+    /// Rebuild the now-playing suppression rules for the given player app names.
+    pub fn set_player_apps(&mut self, mut apps: Vec<String>) {
+        apps.sort_unstable();
+        apps.dedup();
+        self.player_rules = apps
+            .into_iter()
+            .map(|app| RuleConfig {
+                name: Some(format!("media player: {app}")),
+                app: Some(app),
+                no_popup: Some(true),
+                silent: Some(true),
+                transient: Some(true),
+                ..RuleConfig::default()
+            })
+            .collect();
+    }
+
+    #[instrument(level = "debug", skip_all, fields(replaces_id = replaces_id))]
     pub fn insert(&mut self, mut notification: Notification, replaces_id: u32) -> InsertOutcome {
         self.apply_rules(&mut notification);
         // Preserve protocol semantics: replaces_id only applies when it matches an existing item.
@@ -158,6 +565,9 @@ impl NotificationStore {
         };
         notification.id = assigned_id;
 
+        // A replacement stays pinned like the notification it updates.
+        notification.pinned = replaced && self.find(assigned_id).is_some_and(|old| old.pinned);
+
         // Remove any stale entries for this ID before inserting the replacement.
         self.active.shift_remove(&assigned_id);
         self.history.remove(&assigned_id);
@@ -165,10 +575,24 @@ impl NotificationStore {
 
         let notification = Arc::new(notification);
         self.active.insert(assigned_id, notification.clone());
-        let evicted = self.enforce_active_limit();
+        let mut evicted = self.enforce_active_limit();
+        let show_popup = self.should_show_popup(&notification);
+        // Automatic suppression sends what it holds back straight to history; DND keeps it active.
+        if !self.inhibitors.is_empty() && notification.urgency != Urgency::Critical {
+            self.active.shift_remove(&assigned_id);
+            self.push_history(notification.clone());
+            evicted.push(assigned_id);
+        }
+        let popup_slot = if show_popup {
+            Some(self.popup_slot(assigned_id))
+        } else {
+            self.popup_slots.remove(&assigned_id);
+            None
+        };
 
         InsertOutcome {
-            show_popup: self.should_show_popup(&notification),
+            show_popup,
+            popup_slot,
             allow_sound: self.should_play_sound(&notification),
             notification,
             replaced,
@@ -176,24 +600,67 @@ impl NotificationStore {
         }
     }
 
-    pub fn close(&mut self, id: u32) -> Option<Arc<Notification>> {
+    pub fn close(&mut self, id: u32, reason: CloseReason) -> Option<Arc<Notification>> {
         let removed = self.active.shift_remove(&id);
         self.expirations.remove(&id);
+        self.held.remove(&id);
+        self.popup_slots.remove(&id);
         if let Some(notification) = removed.clone() {
+            let keep = !matches!(reason, CloseReason::Expired)
+                || self
+                    .config
+                    .history
+                    .keep_expired
+                    .keeps(notification.urgency.as_u8());
             // History entries are appended only when the notification is explicitly closed.
-            self.push_history(notification.clone());
+            if keep {
+                self.push_history(notification.clone());
+            }
         }
         removed
     }
 
-    pub fn clear_history(&mut self) {
-        self.history.clear();
+    /// Remove history entries whose rule TTL elapsed before `now` or that are older than
+    /// `history.max_age_days` at `wall_now`.
+    pub fn prune_history(&mut self, now: Instant, wall_now: DateTime<Utc>) -> Vec<u32> {
+        let mut pruned = self.history.prune_expired(now);
+        let max_age_days = self.config.history.max_age_days;
+        if max_age_days > 0 {
+            let cutoff = wall_now - chrono::Duration::days(i64::from(max_age_days));
+            pruned.extend(self.history.prune_received_before(cutoff));
+        }
+        pruned
+    }
+
+    /// Counter that changes whenever history does.
+    pub fn history_revision(&self) -> u64 {
+        self.history.revision
+    }
+
+    /// History entries oldest first, with their TTL prune deadline if any.
+    pub fn history_snapshot(&self) -> Vec<(Arc<Notification>, Option<Instant>)> {
+        self.history.snapshot()
+    }
+
+    /// Load saved history at startup, oldest entry first; extra entries past the limits are dropped.
+    pub fn restore_history(&mut self, entries: Vec<(Notification, Option<Instant>)>) {
+        for (notification, deadline) in entries {
+            if self.history.contains(&notification.id) {
+                continue;
+            }
+            self.history
+                .insert_with_deadline(Arc::new(notification), deadline);
+        }
+        self.history.evict_to_limit(self.config.history.max_entries);
+        self.prune_history(Instant::now(), Utc::now());
     }
 
     pub fn dismiss_from_panel(&mut self, id: u32) -> DismissOutcome {
         let removed_active = self.active.shift_remove(&id).is_some();
         if removed_active {
             self.expirations.remove(&id);
+            self.held.remove(&id);
+            self.popup_slots.remove(&id);
         }
 
         let removed_history = self.history.remove(&id).is_some();
@@ -204,15 +671,134 @@ impl NotificationStore {
         }
     }
 
-    pub fn drain_active_ids(&mut self) -> Vec<u32> {
-        // Drain active notifications in one pass to avoid repeated scans.
-        let ids = self.active.keys().rev().copied().collect();
-        self.active.clear();
-        self.expirations.clear();
-        ids
+    /// Remove the notifications `scope` covers and return their IDs.
+    ///
+    /// The removed notifications are kept until `undo_until` so `undo_clear` can restore them;
+    /// a later clear that removes something replaces the batch.
+    pub fn clear(&mut self, scope: ClearScope, undo_until: Instant) -> ClearOutcome {
+        let mut ids = Vec::new();
+        let mut active = Vec::new();
+        if scope != ClearScope::History {
+            ids = self.active.keys().rev().copied().collect();
+            active = self
+                .active
+                .drain(..)
+                .map(|(id, notification)| {
+                    let deadline = self.expirations.get(&id).copied().or_else(|| {
+                        self.held
+                            .get(&id)
+                            .map(|remaining| Instant::now() + *remaining)
+                    });
+                    (notification, deadline)
+                })
+                .collect();
+            self.expirations.clear();
+            self.held.clear();
+            self.popup_slots.clear();
+        }
+        let mut history = Vec::new();
+        if scope != ClearScope::Active {
+            history = self.history.snapshot();
+            self.history.clear();
+        }
+        let outcome = ClearOutcome {
+            active: ids,
+            history: history
+                .iter()
+                .map(|(notification, _)| notification.id)
+                .collect(),
+        };
+        // An empty clear leaves the previous batch undoable.
+        if outcome.removed_any() {
+            self.cleared = Some(ClearedBatch {
+                active,
+                history,
+                undo_until,
+            });
+        }
+        outcome
+    }
+
+    /// Put back what the last clear removed if its undo window is still open.
+    ///
+    /// Returns `None` when there is nothing to undo. Entries whose ID came back in the meantime
+    /// are skipped, and the usual limits still apply.
+    pub fn undo_clear(&mut self, now: Instant) -> Option<UndoOutcome> {
+        let batch = self.cleared.take()?;
+        if now >= batch.undo_until {
+            return None;
+        }
+        let earlier: Vec<u32> = self.history.order.iter().copied().collect();
+        let mut history = Vec::new();
+        for (notification, deadline) in batch.history {
+            if self.history.contains(&notification.id) || self.active.contains_key(&notification.id)
+            {
+                continue;
+            }
+            history.push(notification.id);
+            self.history.insert_with_deadline(notification, deadline);
+        }
+        self.trim_history();
+
+        let mut restored = Vec::new();
+        for (notification, deadline) in batch.active {
+            let id = notification.id;
+            if self.active.contains_key(&id) || self.history.contains(&id) {
+                continue;
+            }
+            // Restored entries sit below anything that arrived since the clear.
+            self.active
+                .shift_insert(restored.len(), id, notification.clone());
+            if let Some(deadline) = deadline {
+                self.expirations.insert(id, deadline);
+            }
+            restored.push((notification, deadline));
+        }
+        let (archived, evicted): (Vec<u32>, Vec<u32>) =
+            self.enforce_active_limit().into_iter().partition(|id| {
+                restored
+                    .iter()
+                    .any(|(notification, _)| notification.id == *id)
+            });
+        restored.retain(|(notification, _)| !archived.contains(&notification.id));
+        history.extend(archived);
+        // Restored entries the history limit dropped at once were never shown again.
+        history.retain(|id| self.history.contains(id));
+        let dropped = earlier
+            .into_iter()
+            .chain(evicted.iter().copied())
+            .filter(|id| !self.history.contains(id))
+            .collect();
+        Some(UndoOutcome {
+            restored,
+            history,
+            evicted,
+            dropped,
+        })
+    }
+
+    /// Forget the cleared notifications once their undo window has closed.
+    pub fn discard_cleared(&mut self, now: Instant) {
+        if self
+            .cleared
+            .as_ref()
+            .is_some_and(|batch| now >= batch.undo_until)
+        {
+            self.cleared = None;
+        }
     }
 
     pub fn set_expiration(&mut self, id: u32, deadline: Option<Instant>) {
+        if let Some(remaining) = self.held.get_mut(&id) {
+            // A replacement under the pointer stays paused with its new timeout.
+            match deadline {
+                Some(deadline) => *remaining = deadline.saturating_duration_since(Instant::now()),
+                None => {
+                    self.held.remove(&id);
+                }
+            }
+            return;
+        }
         match deadline {
             Some(deadline) => {
                 self.expirations.insert(id, deadline);
@@ -223,10 +809,82 @@ impl NotificationStore {
         }
     }
 
+    /// Pause an active notification's timeout, keeping the time it had left.
+    pub fn hold_expiration(&mut self, id: u32, now: Instant) -> bool {
+        let Some(deadline) = self.expirations.remove(&id) else {
+            return false;
+        };
+        self.held
+            .insert(id, deadline.saturating_duration_since(now));
+        true
+    }
+
+    /// Restart a paused timeout with the time it had left, returning the new deadline.
+    ///
+    /// At least `HOLD_RELEASE_MIN` is given back so a popup does not vanish the moment the
+    /// pointer leaves it.
+    pub fn release_expiration(&mut self, id: u32, now: Instant) -> Option<Instant> {
+        let remaining = self.held.remove(&id)?;
+        if !self.active.contains_key(&id) {
+            return None;
+        }
+        let deadline = now + remaining.max(HOLD_RELEASE_MIN);
+        self.expirations.insert(id, deadline);
+        Some(deadline)
+    }
+
     pub fn expiration_for(&self, id: u32) -> Option<Instant> {
+        if self.asleep_since.is_some() {
+            // Timers that fire around suspend are dropped; resume re-arms every deadline.
+            return None;
+        }
         self.expirations.get(&id).copied()
     }
 
+    /// Stop timeouts from firing until `resume_from_sleep`; `boot` is CLOCK_BOOTTIME at `now`.
+    pub fn prepare_for_sleep(&mut self, now: Instant, boot: Duration) {
+        self.asleep_since.get_or_insert((now, boot));
+    }
+
+    /// Move every pending deadline for the time spent asleep and return them for re-arming.
+    ///
+    /// Deadlines are `Instant`s, which stand still while the system is suspended. With
+    /// `popups.pause_during_suspend` they are pushed back by the time that did pass on that
+    /// clock around the suspend, so the whole sleep is skipped. Without it they are pulled
+    /// forward by the suspended time CLOCK_BOOTTIME saw, and the ones it covered expire now.
+    pub fn resume_from_sleep(&mut self, now: Instant, boot: Duration) -> Vec<(u32, Instant)> {
+        let Some((since, boot_since)) = self.asleep_since.take() else {
+            return Vec::new();
+        };
+        let awake = now.saturating_duration_since(since);
+        let suspended = boot.saturating_sub(boot_since).saturating_sub(awake);
+        let pause = self.config.popups.pause_during_suspend;
+        self.expirations
+            .iter_mut()
+            .map(|(id, deadline)| {
+                *deadline = if pause {
+                    *deadline + awake
+                } else {
+                    deadline
+                        .checked_sub(suspended)
+                        .map_or(now, |deadline| deadline.max(now))
+                };
+                (*id, *deadline)
+            })
+            .collect()
+    }
+
+    /// Slot the popup for `id` keeps while it is active; new popups stack above older ones.
+    fn popup_slot(&mut self, id: u32) -> u32 {
+        if let Some(slot) = self.popup_slots.get(&id) {
+            return *slot;
+        }
+        let slot = self.next_popup_slot;
+        self.next_popup_slot = self.next_popup_slot.wrapping_add(1).max(1);
+        self.popup_slots.insert(id, slot);
+        slot
+    }
+
     fn next_id(&mut self) -> u32 {
         let start = self.next_id.max(1);
         let mut candidate = start;
@@ -257,8 +915,11 @@ impl NotificationStore {
         while self.active.len() > max_active {
             if let Some((id, notification)) = self.active.shift_remove_index(0) {
                 self.expirations.remove(&id);
+                self.held.remove(&id);
+                self.popup_slots.remove(&id);
                 self.push_history(notification);
                 evicted.push(id);
+                self.active_evictions = self.active_evictions.saturating_add(1);
             } else {
                 break;
             }
@@ -270,33 +931,41 @@ impl NotificationStore {
         if notification.is_transient && !self.config.history.transient_to_history {
             return;
         }
-        let stored = Arc::new(notification.to_history());
+        let stored = Arc::new(notification.to_history(self.config.icons.image_preview));
         self.history.insert(stored);
-        self.history.evict_to_limit(self.config.history.max_entries);
+        self.trim_history();
+    }
+
+    /// Drop history past `max_entries`, counting what went, and return the dropped ids.
+    fn trim_history(&mut self) -> Vec<u32> {
+        let dropped = self.history.evict_to_limit(self.config.history.max_entries);
+        self.history_evictions = self.history_evictions.saturating_add(dropped.len() as u32);
+        dropped
     }
 
     fn should_show_popup(&self, notification: &Notification) -> bool {
-        if notification.suppress_popup {
+        if notification.suppress_popup || !self.popups_enabled {
             return false;
         }
-        if self.dnd_enabled {
+        if self.quiet() {
             return notification.urgency == Urgency::Critical;
         }
         true
     }
 
     fn should_play_sound(&self, notification: &Notification) -> bool {
-        if notification.suppress_sound {
+        if notification.suppress_sound || self.muted {
             return false;
         }
-        if self.dnd_enabled {
+        if self.quiet() {
             return notification.urgency == Urgency::Critical;
         }
         true
     }
 
     fn apply_rules(&self, notification: &mut Notification) {
-        for rule in &self.config.rules {
+        // User rules run last so they can override the generated player rules.
+        for rule in self.player_rules.iter().chain(&self.config.rules) {
             if !rule_matches(rule, notification) {
                 continue;
             }
@@ -359,6 +1028,12 @@ fn apply_rule(rule: &RuleConfig, notification: &mut Notification) {
     if let Some(transient) = rule.transient {
         notification.is_transient = transient;
     }
+    if let Some(minutes) = rule.history_ttl_minutes {
+        notification.history_ttl_minutes = (minutes > 0).then_some(minutes);
+    }
+    if let Some(clamp_timeout) = rule.clamp_timeout {
+        notification.clamp_timeout = clamp_timeout;
+    }
 }
 
 fn contains_ci(haystack: &str, needle: &str) -> bool {
@@ -378,7 +1053,644 @@ fn contains_ci(haystack: &str, needle: &str) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::contains_ci;
+    use super::*;
+    use unixnotis_core::NotificationImage;
+
+    fn notification(urgency: Urgency) -> Notification {
+        Notification {
+            id: 0,
+            app_name: "app".to_string(),
+            app_icon: String::new(),
+            summary: "summary".to_string(),
+            body: String::new(),
+            actions: Vec::new(),
+            hints: HashMap::new(),
+            urgency,
+            category: None,
+            is_transient: false,
+            is_resident: false,
+            suppress_popup: false,
+            suppress_sound: false,
+            image: NotificationImage::default(),
+            expire_timeout: -1,
+            received_at: chrono::Utc::now(),
+            history_ttl_minutes: None,
+            clamp_timeout: true,
+            sender: None,
+            pinned: false,
+            progress: None,
+        }
+    }
+
+    #[test]
+    fn expired_notifications_follow_keep_policy() {
+        let mut config = Config::default();
+        config.history.keep_expired.low = false;
+        let mut store = NotificationStore::new(config);
+
+        let low = store.insert(notification(Urgency::Low), 0).notification.id;
+        let critical = store
+            .insert(notification(Urgency::Critical), 0)
+            .notification
+            .id;
+        store.close(low, CloseReason::Expired);
+        store.close(critical, CloseReason::Expired);
+        assert_eq!(store.history_len(), 1);
+
+        let dismissed = store.insert(notification(Urgency::Low), 0).notification.id;
+        store.close(dismissed, CloseReason::ClosedByCall);
+        assert_eq!(store.history_len(), 2);
+    }
+
+    #[test]
+    fn previews_keep_image_data_in_history_but_not_in_listings() {
+        let screenshot = || {
+            let mut sent = notification(Urgency::Normal);
+            sent.image = NotificationImage {
+                has_image_data: true,
+                image_data: unixnotis_core::ImageData {
+                    width: 2,
+                    height: 1,
+                    rowstride: 8,
+                    has_alpha: true,
+                    bits_per_sample: 8,
+                    channels: 4,
+                    data: vec![7; 8],
+                },
+                image_path: String::new(),
+                icon_name: "app".to_string(),
+            };
+            sent
+        };
+        let mut config = Config::default();
+        config.icons.image_preview = true;
+        let mut store = NotificationStore::new(config);
+        let id = store.insert(screenshot(), 0).notification.id;
+        store.close(id, CloseReason::ClosedByCall);
+
+        assert_eq!(store.find(id).unwrap().image.image_data.data, vec![7; 8]);
+        let listed = &store.list_history()[0].image;
+        assert!(listed.has_image_data && listed.image_data.data.is_empty());
+        assert_eq!(listed.image_data.width, 2);
+
+        let mut store = NotificationStore::new(Config::default());
+        let id = store.insert(screenshot(), 0).notification.id;
+        store.close(id, CloseReason::ClosedByCall);
+        assert!(store.find(id).unwrap().image.image_data.data.is_empty());
+    }
+
+    #[test]
+    fn history_pages_run_newest_first() {
+        let mut store = NotificationStore::new(Config::default());
+        let ids: Vec<u32> = (0..5)
+            .map(|_| {
+                let id = store
+                    .insert(notification(Urgency::Normal), 0)
+                    .notification
+                    .id;
+                store.close(id, CloseReason::ClosedByCall);
+                id
+            })
+            .collect();
+        let page = |offset, limit| -> Vec<u32> {
+            store
+                .list_history_range(offset, limit)
+                .iter()
+                .map(|view| view.id)
+                .collect()
+        };
+        assert_eq!(page(0, 2), vec![ids[4], ids[3]]);
+        assert_eq!(page(2, 2), vec![ids[2], ids[1]]);
+        assert_eq!(page(4, 2), vec![ids[0]]);
+        assert!(page(5, 2).is_empty());
+    }
+
+    #[test]
+    fn history_ttl_prunes_only_matching_entries() {
+        let mut store = NotificationStore::new(Config::default());
+        let mut short = notification(Urgency::Normal);
+        short.history_ttl_minutes = Some(1);
+        let short = store.insert(short, 0).notification.id;
+        let kept = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        store.close(short, CloseReason::ClosedByCall);
+        store.close(kept, CloseReason::ClosedByCall);
+
+        assert!(store.prune_history(Instant::now(), Utc::now()).is_empty());
+        let later = Instant::now() + Duration::from_secs(61);
+        assert_eq!(store.prune_history(later, Utc::now()), vec![short]);
+        assert_eq!(store.history_len(), 1);
+    }
+
+    #[test]
+    fn unpinning_past_the_limit_reports_what_it_dropped() {
+        let mut config = Config::default();
+        config.history.max_entries = 2;
+        let mut store = NotificationStore::new(config);
+        // Pinned while active, so all three reach history with the limit at two.
+        let ids: Vec<u32> = (0..3)
+            .map(|_| {
+                let id = store
+                    .insert(notification(Urgency::Normal), 0)
+                    .notification
+                    .id;
+                store.set_pinned(id, true);
+                id
+            })
+            .collect();
+        for id in &ids {
+            store.close(*id, CloseReason::ClosedByCall);
+        }
+        assert_eq!(store.history_len(), 3);
+
+        let (_, dropped) = store.set_pinned(ids[1], false).unwrap();
+        assert_eq!(dropped, vec![ids[1]]);
+        assert_eq!(store.history_len(), 2);
+        assert!(store.find(ids[1]).is_none());
+    }
+
+    #[test]
+    fn pinned_history_is_never_trimmed() {
+        let mut config = Config::default();
+        config.history.max_entries = 2;
+        let mut store = NotificationStore::new(config);
+        let archive = |store: &mut NotificationStore| {
+            let id = store
+                .insert(notification(Urgency::Normal), 0)
+                .notification
+                .id;
+            store.close(id, CloseReason::ClosedByCall);
+            id
+        };
+        let pinned = archive(&mut store);
+        assert!(store.set_pinned(pinned, true).is_some());
+        let ids: Vec<u32> = (0..3).map(|_| archive(&mut store)).collect();
+        let history = |store: &NotificationStore| -> Vec<u32> {
+            store
+                .history_snapshot()
+                .iter()
+                .map(|(entry, _)| entry.id)
+                .collect()
+        };
+        assert_eq!(history(&store), vec![pinned, ids[2]]);
+        assert!(store.find(pinned).is_some_and(|entry| entry.pinned));
+
+        let (_, dropped) = store.set_pinned(pinned, false).unwrap();
+        assert!(dropped.is_empty());
+        archive(&mut store);
+        assert!(!history(&store).contains(&pinned));
+        assert!(store.set_pinned(pinned, true).is_none());
+    }
+
+    #[test]
+    fn reloaded_limits_shrink_the_store_oldest_first() {
+        let mut store = NotificationStore::new(Config::default());
+        let ids: Vec<u32> = (0..4)
+            .map(|_| {
+                store
+                    .insert(notification(Urgency::Normal), 0)
+                    .notification
+                    .id
+            })
+            .collect();
+        for id in &ids[..2] {
+            store.close(*id, CloseReason::ClosedByCall);
+        }
+        assert!(store.set_pinned(ids[0], true).is_some());
+
+        let mut config = Config::default();
+        config.history.max_active = 1;
+        config.history.max_entries = 2;
+        let outcome = store.apply_config(config);
+        // ids[2] is archived; pinned ids[0] is exempt, so ids[1] makes room for it.
+        assert_eq!(outcome.evicted, vec![ids[2]]);
+        assert_eq!(outcome.dropped, vec![ids[1]]);
+        assert_eq!(store.list_active().len(), 1);
+        let history: Vec<u32> = store
+            .history_snapshot()
+            .iter()
+            .map(|(entry, _)| entry.id)
+            .collect();
+        assert_eq!(history, vec![ids[0], ids[2]]);
+        assert_eq!(store.config().history.max_active, 1);
+
+        let unchanged = store.apply_config(store.config().clone());
+        assert!(unchanged.evicted.is_empty() && unchanged.dropped.is_empty());
+    }
+
+    #[test]
+    fn restored_history_respects_limits_and_max_age() {
+        let mut config = Config::default();
+        config.history.max_entries = 2;
+        config.history.max_age_days = 7;
+        let mut store = NotificationStore::new(config);
+
+        let now = Utc::now();
+        let entries = [(1, 30), (2, 3), (3, 2), (4, 1)]
+            .into_iter()
+            .map(|(id, days_ago)| {
+                let mut entry = notification(Urgency::Normal);
+                entry.id = id;
+                entry.received_at = now - chrono::Duration::days(days_ago);
+                (entry, None)
+            })
+            .collect();
+        let revision = store.history_revision();
+        store.restore_history(entries);
+        assert_ne!(store.history_revision(), revision);
+        let ids: Vec<u32> = store
+            .history_snapshot()
+            .iter()
+            .map(|(entry, _)| entry.id)
+            .collect();
+        assert_eq!(ids, vec![3, 4]);
+
+        // New notifications must not reuse restored ids.
+        let fresh = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        assert!(fresh != 3 && fresh != 4);
+
+        let later = now + chrono::Duration::days(5) + chrono::Duration::hours(12);
+        assert_eq!(store.prune_history(Instant::now(), later), vec![3]);
+    }
+
+    #[test]
+    fn player_rules_suppress_matching_apps() {
+        let mut store = NotificationStore::new(Config::default());
+        store.set_player_apps(vec!["spotify".to_string(), "spotify".to_string()]);
+
+        let mut track = notification(Urgency::Normal);
+        track.app_name = "Spotify".to_string();
+        let outcome = store.insert(track, 0);
+        assert!(!outcome.show_popup);
+        assert!(!outcome.allow_sound);
+        assert!(outcome.notification.is_transient);
+
+        let other = store.insert(notification(Urgency::Normal), 0);
+        assert!(other.show_popup);
+    }
+
+    #[test]
+    fn runtime_rules_replace_by_name_and_apply_immediately() {
+        let mut store = NotificationStore::new(Config::default());
+        let mut mute = RuleConfig::for_app("app");
+        mute.no_popup = Some(true);
+        store.set_rule(mute.clone());
+        assert!(!store.insert(notification(Urgency::Normal), 0).show_popup);
+
+        mute.no_popup = None;
+        mute.force_urgency = Some(2);
+        store.set_rule(mute);
+        assert_eq!(store.rules().len(), 1);
+        let outcome = store.insert(notification(Urgency::Low), 0);
+        assert!(outcome.show_popup);
+        assert_eq!(outcome.notification.urgency, Urgency::Critical);
+
+        assert!(store.remove_rule("app"));
+        assert!(!store.remove_rule("app"));
+        assert!(store.rules().is_empty());
+    }
+
+    #[test]
+    fn undo_restores_the_last_clear_within_its_window() {
+        let mut store = NotificationStore::new(Config::default());
+        let first = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        let second = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        let now = Instant::now();
+        assert_eq!(
+            store
+                .clear(ClearScope::All, now + Duration::from_secs(10))
+                .active,
+            vec![second, first]
+        );
+        assert!(store.list_active().is_empty());
+
+        let newer = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        let undone = store.undo_clear(now).expect("window still open");
+        assert_eq!(undone.restored.len(), 2);
+        let order: Vec<u32> = store.list_active().iter().map(|view| view.id).collect();
+        assert_eq!(order, vec![newer, second, first]);
+        assert!(store.undo_clear(now).is_none());
+
+        store.clear(ClearScope::All, now + Duration::from_secs(10));
+        assert!(store.undo_clear(now + Duration::from_secs(10)).is_none());
+        assert!(store.list_active().is_empty());
+    }
+
+    #[test]
+    fn undo_reports_restored_history_and_what_the_limits_pushed_out() {
+        let mut config = Config::default();
+        config.history.max_active = 1;
+        config.history.max_entries = 2;
+        let mut store = NotificationStore::new(config);
+        let insert = |store: &mut NotificationStore| {
+            store
+                .insert(notification(Urgency::Normal), 0)
+                .notification
+                .id
+        };
+        let cleared_history = insert(&mut store);
+        store.close(cleared_history, CloseReason::DismissedByUser);
+        let cleared_active = insert(&mut store);
+        let now = Instant::now();
+        store.clear(ClearScope::All, now + Duration::from_secs(10));
+
+        let older = insert(&mut store);
+        store.close(older, CloseReason::DismissedByUser);
+        let old = insert(&mut store);
+        store.close(old, CloseReason::DismissedByUser);
+        let newer = insert(&mut store);
+
+        let undone = store.undo_clear(now).expect("window still open");
+        // The restored active notification lands below `newer`, so the limit archives it.
+        assert!(undone.restored.is_empty());
+        assert_eq!(undone.history, vec![cleared_history, cleared_active]);
+        assert!(undone.evicted.is_empty());
+        assert_eq!(undone.dropped, vec![older, old]);
+        let active: Vec<u32> = store.list_active().iter().map(|view| view.id).collect();
+        assert_eq!(active, vec![newer]);
+        assert_eq!(store.history_len(), 2);
+    }
+
+    #[test]
+    fn clear_scope_leaves_the_other_list_alone() {
+        let mut store = NotificationStore::new(Config::default());
+        let archived = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        store.close(archived, CloseReason::DismissedByUser);
+        let active = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        let undo_until = Instant::now() + Duration::from_secs(10);
+
+        let cleared = store.clear(ClearScope::History, undo_until);
+        assert!(cleared.active.is_empty());
+        assert_eq!(cleared.history, vec![archived]);
+        assert_eq!(store.history_len(), 0);
+        assert_eq!(store.list_active().len(), 1);
+
+        assert!(store.undo_clear(Instant::now()).is_some());
+        let cleared = store.clear(ClearScope::Active, undo_until);
+        assert_eq!(cleared.active, vec![active]);
+        assert!(cleared.history.is_empty());
+        assert_eq!(store.history_len(), 1);
+        assert!(store.list_active().is_empty());
+
+        // Clearing an empty list keeps the earlier batch for undo.
+        assert!(!store.clear(ClearScope::Active, undo_until).removed_any());
+        assert!(store.undo_clear(Instant::now()).is_some());
+        assert_eq!(store.list_active().len(), 1);
+    }
+
+    #[test]
+    fn held_timeouts_resume_with_the_time_they_had_left() {
+        let mut store = NotificationStore::new(Config::default());
+        let id = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        let now = Instant::now();
+        store.set_expiration(id, Some(now + Duration::from_secs(4)));
+
+        assert!(store.hold_expiration(id, now + Duration::from_secs(1)));
+        assert!(store.expiration_for(id).is_none());
+        let later = now + Duration::from_secs(60);
+        assert_eq!(
+            store.release_expiration(id, later),
+            Some(later + Duration::from_secs(3))
+        );
+        assert!(store.release_expiration(id, later).is_none());
+
+        assert!(store.hold_expiration(id, later + Duration::from_secs(5)));
+        assert_eq!(
+            store.release_expiration(id, later + Duration::from_secs(5)),
+            Some(later + Duration::from_secs(5) + HOLD_RELEASE_MIN)
+        );
+        store.close(id, CloseReason::DismissedByUser);
+        assert!(!store.hold_expiration(id, later));
+    }
+
+    #[test]
+    fn suspend_pushes_deadlines_back_by_the_time_asleep() {
+        let mut store = NotificationStore::new(Config::default());
+        let id = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        let now = Instant::now();
+        let deadline = now + Duration::from_secs(4);
+        store.set_expiration(id, Some(deadline));
+
+        // Two seconds pass on the monotonic clock around an hour of suspend.
+        let boot = Duration::from_secs(100);
+        store.prepare_for_sleep(now + Duration::from_secs(1), boot);
+        assert!(store.expiration_for(id).is_none());
+        let awake = Duration::from_secs(2);
+        let resumed = now + Duration::from_secs(1) + awake;
+        let boot_resumed = boot + Duration::from_secs(3600) + awake;
+        assert_eq!(
+            store.resume_from_sleep(resumed, boot_resumed),
+            vec![(id, deadline + awake)]
+        );
+        assert_eq!(store.expiration_for(id), Some(deadline + awake));
+        assert!(store.resume_from_sleep(resumed, boot_resumed).is_empty());
+    }
+
+    #[test]
+    fn without_pause_the_suspend_counts_against_deadlines() {
+        let mut config = Config::default();
+        config.popups.pause_during_suspend = false;
+        let mut store = NotificationStore::new(config);
+        let short = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        let long = store
+            .insert(notification(Urgency::Normal), 0)
+            .notification
+            .id;
+        let now = Instant::now();
+        store.set_expiration(short, Some(now + Duration::from_secs(4)));
+        store.set_expiration(long, Some(now + Duration::from_secs(7200)));
+
+        let boot = Duration::from_secs(100);
+        store.prepare_for_sleep(now, boot);
+        let resumed = now + Duration::from_secs(1);
+        let mut deadlines = store.resume_from_sleep(resumed, boot + Duration::from_secs(3600 + 1));
+        deadlines.sort();
+        assert_eq!(
+            deadlines,
+            vec![
+                (short, resumed),
+                (long, now + Duration::from_secs(7200 - 3600)),
+            ]
+        );
+    }
+
+    #[test]
+    fn replacements_keep_their_popup_slot() {
+        let mut store = NotificationStore::new(Config::default());
+        let first = store.insert(notification(Urgency::Normal), 0);
+        let second = store.insert(notification(Urgency::Normal), 0);
+        let first_slot = first.popup_slot.expect("popup shown");
+        let second_slot = second.popup_slot.expect("popup shown");
+        assert!(second_slot > first_slot);
+
+        let update = store.insert(notification(Urgency::Normal), first.notification.id);
+        assert!(update.replaced);
+        assert_eq!(update.popup_slot, Some(first_slot));
+
+        store.close(first.notification.id, CloseReason::DismissedByUser);
+        let later = store.insert(notification(Urgency::Normal), 0);
+        assert!(later.popup_slot.expect("popup shown") > second_slot);
+    }
+
+    #[test]
+    fn find_reaches_active_and_history_with_the_sender() {
+        let mut store = NotificationStore::new(Config::default());
+        let sent = |sender: &str| Notification {
+            sender: Some(sender.to_string()),
+            ..notification(Urgency::Normal)
+        };
+        let active = store.insert(sent(":1.7"), 0).notification.id;
+        let archived = store.insert(sent(":1.8"), 0).notification.id;
+        store.close(archived, CloseReason::DismissedByUser);
+
+        let sender_of = |id| store.find(id).and_then(|entry| entry.sender.clone());
+        assert_eq!(sender_of(active).as_deref(), Some(":1.7"));
+        assert_eq!(sender_of(archived).as_deref(), Some(":1.8"));
+        assert!(store.find(archived + 1).is_none());
+    }
+
+    #[test]
+    fn departed_sender_loses_actions_on_active_notifications_only() {
+        let mut store = NotificationStore::new(Config::default());
+        let with_actions = |sender: &str| Notification {
+            actions: vec![unixnotis_core::Action {
+                key: "default".to_string(),
+                label: "Open".to_string(),
+            }],
+            sender: Some(sender.to_string()),
+            ..notification(Urgency::Normal)
+        };
+        let gone = store.insert(with_actions(":1.7"), 0).notification.id;
+        let other = store.insert(with_actions(":1.8"), 0).notification.id;
+        let plain = Notification {
+            sender: Some(":1.7".to_string()),
+            ..notification(Urgency::Normal)
+        };
+        store.insert(plain, 0);
+
+        let updated = store.strip_sender_actions(":1.7");
+        assert_eq!(updated.len(), 1);
+        assert_eq!(updated[0].id, gone);
+        assert!(store
+            .find(gone)
+            .is_some_and(|entry| entry.actions.is_empty()));
+        assert!(store
+            .find(other)
+            .is_some_and(|entry| !entry.actions.is_empty()));
+        assert!(store.strip_sender_actions(":1.7").is_empty());
+    }
+
+    #[test]
+    fn inhibitors_hold_back_popups_like_dnd() {
+        let mut store = NotificationStore::new(Config::default());
+        assert!(store.set_inhibited(InhibitReason::Screencast, true));
+        assert!(store.set_inhibited(InhibitReason::Fullscreen, true));
+        assert!(!store.set_inhibited(InhibitReason::Fullscreen, true));
+        assert_eq!(store.control_state().inhibited_by, "fullscreen,screencast");
+        assert!(!store.control_state().dnd_enabled);
+
+        let quiet = store.insert(notification(Urgency::Normal), 0);
+        assert!(!quiet.show_popup && !quiet.allow_sound);
+        // Unlike DND, held-back notifications skip the active list.
+        let quiet_id = quiet.notification.id;
+        assert_eq!(quiet.evicted, [quiet_id]);
+        assert!(store.list_active().is_empty());
+        assert_eq!(store.list_history()[0].id, quiet_id);
+        let critical = store.insert(notification(Urgency::Critical), 0);
+        assert!(critical.show_popup && critical.evicted.is_empty());
+        assert_eq!(store.list_active().len(), 1);
+
+        store.set_inhibited(InhibitReason::Fullscreen, false);
+        store.set_inhibited(InhibitReason::Screencast, false);
+        assert!(store.control_state().inhibited_by.is_empty());
+        assert!(store.insert(notification(Urgency::Normal), 0).show_popup);
+    }
+
+    #[test]
+    fn popups_off_hides_every_popup_but_keeps_sounds() {
+        let mut store = NotificationStore::new(Config::default());
+        assert!(store.set_popups_enabled(false));
+        assert!(!store.set_popups_enabled(false));
+        assert!(!store.control_state().popups_enabled);
+
+        let normal = store.insert(notification(Urgency::Normal), 0);
+        assert!(!normal.show_popup && normal.allow_sound);
+        assert!(normal.popup_slot.is_none());
+        assert!(!store.insert(notification(Urgency::Critical), 0).show_popup);
+        assert_eq!(store.list_active().len(), 2);
+
+        assert!(store.set_popups_enabled(true));
+        assert!(store.insert(notification(Urgency::Normal), 0).show_popup);
+    }
+
+    #[test]
+    fn mute_silences_every_sound_but_keeps_popups() {
+        let mut store = NotificationStore::new(Config::default());
+        assert!(store.set_muted(true));
+        assert!(!store.set_muted(true));
+        assert!(store.control_state().muted);
+
+        let normal = store.insert(notification(Urgency::Normal), 0);
+        assert!(normal.show_popup && !normal.allow_sound);
+        let critical = store.insert(notification(Urgency::Critical), 0);
+        assert!(critical.show_popup && !critical.allow_sound);
+
+        assert!(store.set_muted(false));
+        assert!(store.insert(notification(Urgency::Normal), 0).allow_sound);
+    }
+
+    #[test]
+    fn timed_dnd_expires_only_its_own_period() {
+        let mut store = NotificationStore::new(Config::default());
+        let now = Utc::now();
+        let first = now + chrono::Duration::minutes(30);
+        store.set_dnd_until(first);
+        assert!(store.dnd_enabled());
+        assert!(!store.expire_dnd(first, now));
+
+        // A newer period replaces the first one; the old timer must not end it.
+        let second = now + chrono::Duration::hours(1);
+        store.set_dnd_until(second);
+        assert!(!store.expire_dnd(first, second));
+        assert!(store.expire_dnd(second, second));
+        assert!(!store.dnd_enabled());
+        assert_eq!(store.control_state().dnd_until, 0);
+
+        // Turning DND on manually drops the end time.
+        store.set_dnd_until(first);
+        store.set_dnd(true);
+        assert!(!store.expire_dnd(first, second));
+        assert!(store.dnd_enabled());
+    }
 
     #[test]
     fn contains_ci_matches_ascii() {
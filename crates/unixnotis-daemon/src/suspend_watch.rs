@@ -0,0 +1,79 @@
+//! Notification timeouts across system suspend (`popups.pause_during_suspend`).
+//!
+//! logind sends `PrepareForSleep(true)` before suspending and `PrepareForSleep(false)` after
+//! resuming. In between nothing expires; on resume every pending deadline is moved for the time
+//! asleep, as the store's current config says, and re-armed. The setting is read at each
+//! resume, so a reload applies to the next one.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+use tracing::{debug, warn};
+use zbus::{proxy, Connection};
+
+use crate::daemon::DaemonState;
+use crate::expire::ExpirationScheduler;
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LoginManager {
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+pub fn spawn_suspend_watch(state: Arc<DaemonState>, scheduler: ExpirationScheduler) {
+    tokio::spawn(async move {
+        if let Err(err) = watch_suspend(state, scheduler).await {
+            warn!(
+                ?err,
+                "suspend watcher stopped; pause_during_suspend no longer applies"
+            );
+        }
+    });
+}
+
+async fn watch_suspend(
+    state: Arc<DaemonState>,
+    scheduler: ExpirationScheduler,
+) -> zbus::Result<()> {
+    let system = Connection::system().await?;
+    let manager = LoginManagerProxy::new(&system).await?;
+    let mut signals = manager.receive_prepare_for_sleep().await?;
+    while let Some(signal) = signals.next().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        let (now, boot) = (Instant::now(), boottime());
+        if args.start {
+            debug!("system suspending; holding notification timeouts");
+            state.store.lock().await.prepare_for_sleep(now, boot);
+            continue;
+        }
+        let deadlines = state.store.lock().await.resume_from_sleep(now, boot);
+        debug!(
+            count = deadlines.len(),
+            "system resumed; re-arming notification timeouts"
+        );
+        for (id, deadline) in deadlines {
+            scheduler.schedule(id, Some(deadline));
+        }
+    }
+    Ok(())
+}
+
+/// Time since boot including suspend, which `Instant` leaves out on Linux.
+fn boottime() -> Duration {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `now` is a valid timespec for clock_gettime to fill.
+    if unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut now) } != 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(now.tv_sec as u64, now.tv_nsec as u32)
+}
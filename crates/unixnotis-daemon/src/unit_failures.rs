@@ -0,0 +1,405 @@
+//! Notifications for failed systemd units.
+//!
+//! Subscribes to the user manager (and optionally the system manager) and watches unit
+//! `ActiveState` changes. Each unit that turns `failed` gets a notification with a
+//! "Show journal" action running the configured command.
+
+use std::collections::{HashMap, HashSet};
+use std::process::Stdio;
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, info, warn};
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::{BusNames, SystemdConfig};
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{proxy, Connection, MatchRule, MessageStream};
+
+use crate::daemon::DaemonState;
+use crate::notify_proxy::{NotificationsProxy, Notifier};
+
+const SYSTEMD_NAME: &str = "org.freedesktop.systemd1";
+const UNIT_PATH_PREFIX: &str = "/org/freedesktop/systemd1/unit";
+const UNIT_INTERFACE: &str = "org.freedesktop.systemd1.Unit";
+const JOURNAL_ACTION: &str = "journal";
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait SystemdManager {
+    fn subscribe(&self) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Unit",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait Unit {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn description(&self) -> zbus::Result<String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Scope {
+    User,
+    System,
+}
+
+impl Scope {
+    fn journal_flag(self) -> &'static str {
+        match self {
+            Scope::User => "--user",
+            Scope::System => "--system",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Failure {
+    scope: Scope,
+    unit: String,
+    description: String,
+}
+
+/// Start the unit watcher; call once the notification bus name is owned.
+pub fn spawn_unit_watcher(state: Arc<DaemonState>, names: BusNames, config: SystemdConfig) {
+    if !config.enabled || !(config.user || config.system) {
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(err) = watch_units(state, names, config).await {
+            warn!(?err, "systemd unit watcher stopped");
+        }
+    });
+}
+
+async fn watch_units(
+    state: Arc<DaemonState>,
+    names: BusNames,
+    config: SystemdConfig,
+) -> zbus::Result<()> {
+    let mut notifier = Notifier::new(&state, names).await?;
+
+    let (sender, mut failures) = mpsc::unbounded_channel();
+    if config.user {
+        spawn_user_scope_watcher(state.watch_connection(), sender.clone());
+    }
+    if config.system {
+        match Connection::system().await {
+            Ok(system) => spawn_scope_watcher(system, Scope::System, sender.clone()),
+            Err(err) => warn!(?err, "cannot watch system units without the system bus"),
+        }
+    }
+    drop(sender);
+    info!(
+        user = config.user,
+        system = config.system,
+        "watching for failed systemd units"
+    );
+
+    // Notification id -> the failure it reports, for actions and replacing repeat failures.
+    let mut shown: HashMap<u32, Failure> = HashMap::new();
+    loop {
+        tokio::select! {
+            Some(failure) = failures.recv() => {
+                if is_ignored(&failure.unit, &config.ignore) {
+                    debug!(unit = %failure.unit, "ignoring failed unit");
+                    continue;
+                }
+                let previous = shown
+                    .iter()
+                    .find(|(_, shown)| shown.scope == failure.scope && shown.unit == failure.unit)
+                    .map(|(id, _)| *id);
+                if let Some(id) = previous {
+                    shown.remove(&id);
+                }
+                let sent = match notifier.proxy().await {
+                    Ok(notifications) => {
+                        notify_failure(notifications, previous.unwrap_or(0), &failure).await
+                    }
+                    Err(err) => Err(err),
+                };
+                match sent {
+                    Ok(id) => {
+                        shown.insert(id, failure);
+                    }
+                    Err(err) => warn!(?err, "failed to send unit failure notification"),
+                }
+            }
+            Some(signal) = notifier.next_action() => {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+                if args.action_key != JOURNAL_ACTION {
+                    continue;
+                }
+                if let Some(failure) = shown.get(&args.id) {
+                    show_journal(&config.journal_cmd, failure);
+                }
+            }
+            else => break,
+        }
+    }
+    Ok(())
+}
+
+fn spawn_scope_watcher(
+    connection: Connection,
+    scope: Scope,
+    sender: mpsc::UnboundedSender<Failure>,
+) {
+    tokio::spawn(async move {
+        if let Err(err) = watch_scope(connection, scope, sender).await {
+            warn!(
+                ?err,
+                ?scope,
+                "systemd manager unavailable; not watching its units"
+            );
+        }
+    });
+}
+
+/// The user manager sits on the user bus, which is the session bus the daemon uses, so the
+/// watch starts over on each restarted bus.
+fn spawn_user_scope_watcher(
+    mut connections: watch::Receiver<Connection>,
+    sender: mpsc::UnboundedSender<Failure>,
+) {
+    tokio::spawn(async move {
+        loop {
+            let connection = connections.borrow_and_update().clone();
+            tokio::select! {
+                result = watch_scope(connection, Scope::User, sender.clone()) => {
+                    if let Err(err) = result {
+                        warn!(?err, "user manager unavailable; not watching its units");
+                    }
+                    if sender.is_closed() || connections.changed().await.is_err() {
+                        break;
+                    }
+                }
+                changed = connections.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+async fn watch_scope(
+    connection: Connection,
+    scope: Scope,
+    sender: mpsc::UnboundedSender<Failure>,
+) -> zbus::Result<()> {
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(SYSTEMD_NAME)?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path_namespace(UNIT_PATH_PREFIX)?
+        .build();
+    let mut stream = MessageStream::for_match_rule(rule, &connection, None).await?;
+    // systemd only emits unit property changes while at least one client is subscribed.
+    SystemdManagerProxy::new(&connection)
+        .await?
+        .subscribe()
+        .await?;
+
+    let mut failed = FailedUnits::default();
+    while let Some(message) = stream.next().await {
+        let Ok(message) = message else {
+            continue;
+        };
+        let Some(path): Option<OwnedObjectPath> =
+            message.header().path().map(|path| path.to_owned().into())
+        else {
+            continue;
+        };
+        let Ok((interface, changed, _)) =
+            message
+                .body()
+                .deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+        else {
+            continue;
+        };
+        if interface != UNIT_INTERFACE {
+            continue;
+        }
+        let Some(state) = changed.get("ActiveState").and_then(owned_str) else {
+            continue;
+        };
+        if !failed.update(path.as_str(), state == "failed") {
+            continue;
+        }
+        // Transient units can vanish before they are described; that must not end the watch.
+        let failure = match describe_unit(&connection, scope, path).await {
+            Ok(failure) => failure,
+            Err(err) => {
+                debug!(?err, "failed to describe failed unit");
+                continue;
+            }
+        };
+        if sender.send(failure).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+async fn describe_unit(
+    connection: &Connection,
+    scope: Scope,
+    path: OwnedObjectPath,
+) -> zbus::Result<Failure> {
+    let unit = UnitProxy::builder(connection)
+        .path(path.clone())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    let id = match unit.id().await {
+        Ok(id) => id,
+        Err(err) => {
+            debug!(?err, path = %path.as_str(), "failed unit has no readable id");
+            path.as_str().to_string()
+        }
+    };
+    let description = unit.description().await.unwrap_or_default();
+    Ok(Failure {
+        scope,
+        unit: id,
+        description,
+    })
+}
+
+fn owned_str(value: &OwnedValue) -> Option<String> {
+    value
+        .try_clone()
+        .ok()
+        .and_then(|owned| String::try_from(owned).ok())
+}
+
+async fn notify_failure(
+    notifications: &NotificationsProxy<'_>,
+    replaces_id: u32,
+    failure: &Failure,
+) -> zbus::Result<u32> {
+    let summary = tr_args(
+        "unit-failed-summary",
+        &[("unit", failure.unit.as_str().into())],
+    );
+    let description = if failure.description.is_empty() {
+        failure.unit.as_str()
+    } else {
+        failure.description.as_str()
+    };
+    let body_id = match failure.scope {
+        Scope::User => "unit-failed-user-body",
+        Scope::System => "unit-failed-system-body",
+    };
+    let body = tr_args(body_id, &[("description", description.into())]);
+    let journal_label = tr("unit-show-journal");
+    let actions = [JOURNAL_ACTION, journal_label.as_str()];
+    let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+    hints.insert("urgency", Value::U8(2));
+    hints.insert("category", Value::from("x-unixnotis.unit-failed"));
+    notifications
+        .notify(
+            "systemd",
+            replaces_id,
+            "dialog-error-symbolic",
+            &summary,
+            &body,
+            &actions,
+            hints,
+            -1,
+        )
+        .await
+}
+
+fn show_journal(template: &str, failure: &Failure) {
+    let cmd = journal_command(template, failure.scope, &failure.unit);
+    debug!(%cmd, "opening journal for failed unit");
+    let mut command = tokio::process::Command::new("sh");
+    command.arg("-c").arg(&cmd).stdin(Stdio::null());
+    match command.spawn() {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                let _ = child.wait().await;
+            });
+        }
+        Err(err) => warn!(?err, %cmd, "failed to run journal command"),
+    }
+}
+
+fn journal_command(template: &str, scope: Scope, unit: &str) -> String {
+    template
+        .replace("{scope}", scope.journal_flag())
+        .replace("{unit}", &shell_quote(unit))
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn is_ignored(unit: &str, ignore: &[String]) -> bool {
+    let unit = unit.to_lowercase();
+    ignore.iter().any(|pattern| {
+        let pattern = pattern.trim().to_lowercase();
+        !pattern.is_empty() && unit.contains(&pattern)
+    })
+}
+
+/// Units currently in the failed state, so repeated property updates notify only once.
+#[derive(Default)]
+struct FailedUnits {
+    paths: HashSet<String>,
+}
+
+impl FailedUnits {
+    /// Record the unit's state; returns true when it has just become failed.
+    fn update(&mut self, path: &str, failed: bool) -> bool {
+        if failed {
+            self.paths.insert(path.to_string())
+        } else {
+            self.paths.remove(path);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_units_notify_once_per_failure() {
+        let mut failed = FailedUnits::default();
+        let path = "/org/freedesktop/systemd1/unit/backup_2eservice";
+        assert!(failed.update(path, true));
+        assert!(!failed.update(path, true));
+        assert!(!failed.update(path, false));
+        assert!(failed.update(path, true));
+    }
+
+    #[test]
+    fn journal_command_fills_scope_and_quotes_unit() {
+        assert_eq!(
+            journal_command(
+                "foot journalctl {scope} -e -u {unit}",
+                Scope::User,
+                "backup@it's.service"
+            ),
+            r"foot journalctl --user -e -u 'backup@it'\''s.service'"
+        );
+        assert!(is_ignored("Backup.service", &["backup".to_string()]));
+        assert!(!is_ignored("backup.service", &[" ".to_string()]));
+    }
+}
@@ -0,0 +1,42 @@
+//! Welcome notification for the first start with a freshly written config.
+//!
+//! Sent through the daemon's own Notify method so rules, popups, and history treat it like any
+//! other notification.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::BusNames;
+use zbus::zvariant::Value;
+use zbus::Connection;
+
+pub(super) async fn send_welcome(
+    connection: &Connection,
+    names: &BusNames,
+    config_path: &Path,
+) -> zbus::Result<u32> {
+    let summary = tr("welcome-summary");
+    let body = tr_args(
+        "welcome-body",
+        &[("config", config_path.display().to_string().into())],
+    );
+    let actions: Vec<&str> = Vec::new();
+    let hints: HashMap<&str, Value<'_>> = HashMap::new();
+    // Zero keeps it on screen until dismissed.
+    let expire_timeout = 0i32;
+    names
+        .notifications_proxy(connection)
+        .await?
+        .notify(
+            "UnixNotis",
+            0,
+            "preferences-system-notifications-symbolic",
+            &summary,
+            &body,
+            &actions,
+            hints,
+            expire_timeout,
+        )
+        .await
+}
@@ -4,6 +4,8 @@
 mod actions_config;
 #[path = "actions_daemon.rs"]
 mod actions_daemon;
+#[path = "actions_extras.rs"]
+mod actions_extras;
 #[path = "actions_format.rs"]
 mod actions_format;
 #[path = "actions_install.rs"]
@@ -23,6 +25,7 @@ pub use actions_state::{check_install_state, ActionContext, InstallState};
 
 pub(super) use actions_config::{ensure_config, reset_config};
 pub(super) use actions_daemon::stop_active_daemon;
+pub(super) use actions_extras::{install_shell_extras, remove_shell_extras};
 pub(super) use actions_install::{
     enable_service, install_binaries, install_service, remove_binaries, uninstall_service,
 };
@@ -74,8 +74,8 @@ pub fn reset_config(ctx: &mut ActionContext) -> Result<()> {
 
     fs::create_dir_all(&config_dir).with_context(|| "failed to create config directory")?;
 
-    let config_toml = toml::to_string_pretty(&config).map_err(|err| anyhow!(err.to_string()))?;
-    fs::write(&config_path, config_toml).with_context(|| "failed to write config.toml")?;
+    fs::write(&config_path, unixnotis_core::DEFAULT_CONFIG_TOML)
+        .with_context(|| "failed to write config.toml")?;
 
     log_line(
         ctx,
@@ -0,0 +1,74 @@
+//! Shell completions and the noticenterctl man page.
+//!
+//! Both are generated by the installed noticenterctl, so they always match the binary.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::paths::format_with_home;
+
+use super::{log_line, ActionContext};
+
+pub fn install_shell_extras(ctx: &mut ActionContext) -> Result<()> {
+    let ctl = ctx.paths.bin_dir.join("noticenterctl");
+    for (args, path) in extras(ctx) {
+        let output = Command::new(&ctl)
+            .args(args)
+            .output()
+            .with_context(|| format!("failed to run noticenterctl {}", args.join(" ")))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "noticenterctl {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", format_with_home(parent)))?;
+        }
+        fs::write(path, output.stdout)
+            .with_context(|| format!("failed to write {}", format_with_home(path)))?;
+        log_line(ctx, format!("Installed {}", format_with_home(path)));
+    }
+    // zsh only reads the directory when it is on fpath; bash, fish, and man find theirs.
+    log_line(
+        ctx,
+        format!(
+            "For zsh, add {} to fpath before compinit",
+            format_with_home(
+                ctx.paths
+                    .zsh_completion_path
+                    .parent()
+                    .unwrap_or(Path::new(""))
+            )
+        ),
+    );
+    Ok(())
+}
+
+pub fn remove_shell_extras(ctx: &mut ActionContext) -> Result<()> {
+    for (_, path) in extras(ctx) {
+        if !path.exists() {
+            continue;
+        }
+        fs::remove_file(path)
+            .with_context(|| format!("failed to remove {}", format_with_home(path)))?;
+        log_line(ctx, format!("Removed {}", format_with_home(path)));
+    }
+    Ok(())
+}
+
+// Generator arguments paired with where their output belongs.
+fn extras<'a>(ctx: &ActionContext<'a>) -> [(&'static [&'static str], &'a Path); 4] {
+    let paths = ctx.paths;
+    [
+        (&["completions", "bash"], &paths.bash_completion_path),
+        (&["completions", "zsh"], &paths.zsh_completion_path),
+        (&["completions", "fish"], &paths.fish_completion_path),
+        (&["man"], &paths.man_page_path),
+    ]
+}
@@ -6,6 +6,7 @@ use std::process::Command;
 
 use anyhow::{anyhow, Context, Result};
 
+use crate::model::ServiceLayout;
 use crate::paths::{format_with_home, InstallPaths};
 
 use super::{log_line, run_command, ActionContext};
@@ -29,11 +30,22 @@ pub fn install_binaries(ctx: &mut ActionContext) -> Result<()> {
     Ok(())
 }
 
+// Frontend units as (binary, description); the unit name matches the binary.
+const UI_UNITS: [(&str, &str); 2] = [
+    ("unixnotis-popups", "UnixNotis Popups"),
+    ("unixnotis-center", "UnixNotis Panel"),
+];
+
 pub fn install_service(ctx: &mut ActionContext) -> Result<()> {
     fs::create_dir_all(&ctx.paths.unit_dir)
         .with_context(|| "failed to create systemd user directory")?;
 
-    let exec_start = format_exec_start(ctx.paths);
+    let split = ctx.service_layout == ServiceLayout::Split;
+    let mut exec_start = format_exec_start(ctx.paths, "unixnotis-daemon");
+    if split {
+        // The UI units own the frontends; the daemon must not start a second copy.
+        exec_start.push_str(" --no-popups --no-center");
+    }
     let unit_contents = [
         "[Unit]".to_string(),
         "Description=UnixNotis Notification Daemon".to_string(),
@@ -43,6 +55,8 @@ pub fn install_service(ctx: &mut ActionContext) -> Result<()> {
         "[Service]".to_string(),
         "Type=simple".to_string(),
         format!("ExecStart={}", exec_start),
+        // `systemctl --user reload` applies rule and history limit changes without a restart.
+        "ExecReload=kill -HUP $MAINPID".to_string(),
         "Restart=on-failure".to_string(),
         "RestartSec=1".to_string(),
         "".to_string(),
@@ -63,9 +77,85 @@ pub fn install_service(ctx: &mut ActionContext) -> Result<()> {
         ),
     );
 
+    for (binary, description) in UI_UNITS {
+        let path = ui_unit_path(ctx.paths, binary);
+        if !split {
+            // Switching back to a single unit: drop UI units left by a split install.
+            remove_ui_unit(ctx, binary, path);
+            continue;
+        }
+        fs::write(path, ui_unit_contents(ctx.paths, binary, description))
+            .with_context(|| format!("failed to write {binary}.service"))?;
+        log_line(
+            ctx,
+            format!("Installed systemd unit to {}", format_with_home(path)),
+        );
+    }
+
     Ok(())
 }
 
+fn ui_unit_contents(paths: &InstallPaths, binary: &str, description: &str) -> String {
+    [
+        "[Unit]".to_string(),
+        format!("Description={description}"),
+        // PartOf stops and restarts the frontend with the daemon; the frontend can still be
+        // restarted alone.
+        "After=unixnotis-daemon.service graphical-session.target".to_string(),
+        "PartOf=unixnotis-daemon.service".to_string(),
+        "".to_string(),
+        "[Service]".to_string(),
+        "Type=simple".to_string(),
+        format!("ExecStart={}", format_exec_start(paths, binary)),
+        "Restart=on-failure".to_string(),
+        "RestartSec=1".to_string(),
+        "".to_string(),
+        "[Install]".to_string(),
+        "WantedBy=unixnotis-daemon.service".to_string(),
+        "".to_string(),
+    ]
+    .join("\n")
+}
+
+fn ui_unit_path<'a>(paths: &'a InstallPaths, binary: &str) -> &'a Path {
+    if binary == "unixnotis-popups" {
+        &paths.popups_unit_path
+    } else {
+        &paths.center_unit_path
+    }
+}
+
+fn remove_ui_unit(ctx: &mut ActionContext, binary: &str, path: &Path) {
+    if !path.exists() {
+        return;
+    }
+    let unit = format!("{binary}.service");
+    let mut disable = Command::new("systemctl");
+    disable.args(["--user", "disable", "--now", unit.as_str()]);
+    if let Err(err) = run_command(
+        ctx,
+        &format!("systemctl --user disable --now {unit}"),
+        disable,
+        None,
+    ) {
+        log_line(ctx, format!("Warning: {}", err));
+    }
+    match fs::remove_file(path) {
+        Ok(()) => log_line(
+            ctx,
+            format!("Removed systemd unit at {}", format_with_home(path)),
+        ),
+        Err(err) => log_line(
+            ctx,
+            format!(
+                "Warning: failed to remove {}: {}",
+                format_with_home(path),
+                err
+            ),
+        ),
+    }
+}
+
 pub fn enable_service(ctx: &mut ActionContext) -> Result<()> {
     let mut daemon_reload = Command::new("systemctl");
     daemon_reload.args(["--user", "daemon-reload"]);
@@ -78,10 +168,27 @@ pub fn enable_service(ctx: &mut ActionContext) -> Result<()> {
         enable,
         None,
     )?;
+    if ctx.service_layout == ServiceLayout::Split {
+        for (binary, _) in UI_UNITS {
+            let unit = format!("{binary}.service");
+            let mut enable = Command::new("systemctl");
+            enable.args(["--user", "enable", "--now", unit.as_str()]);
+            run_command(
+                ctx,
+                &format!("systemctl --user enable --now {unit}"),
+                enable,
+                None,
+            )?;
+        }
+    }
     Ok(())
 }
 
 pub fn uninstall_service(ctx: &mut ActionContext) -> Result<()> {
+    for (binary, _) in UI_UNITS {
+        remove_ui_unit(ctx, binary, ui_unit_path(ctx.paths, binary));
+    }
+
     let unit = &ctx.paths.unit_path;
     let unit_display = format_with_home(unit);
 
@@ -161,8 +268,8 @@ fn copy_binary(ctx: &mut ActionContext, source: &Path, destination: &Path) -> Re
     Ok(())
 }
 
-fn format_exec_start(paths: &InstallPaths) -> String {
-    let path = paths.bin_dir.join("unixnotis-daemon");
+fn format_exec_start(paths: &InstallPaths, binary: &str) -> String {
+    let path = paths.bin_dir.join(binary);
     let rendered = format_with_home(&path);
     if let Some(tail) = rendered.strip_prefix("$HOME") {
         format!("%h{}", tail)
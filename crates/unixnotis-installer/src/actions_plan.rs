@@ -9,8 +9,8 @@ use crate::model::{ActionMode, ActionStep, StepStatus};
 
 use super::{
     check_install_state_step, enable_service, ensure_config, install_binaries, install_service,
-    remove_binaries, reset_config, run_build, run_verify, stop_active_daemon, uninstall_service,
-    ActionContext,
+    install_shell_extras, remove_binaries, remove_shell_extras, reset_config, run_build,
+    run_verify, stop_active_daemon, uninstall_service, ActionContext,
 };
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -22,13 +22,15 @@ pub enum StepKind {
     EnsureConfig,
     ResetConfig,
     InstallBinaries,
+    InstallShellExtras,
     InstallService,
     EnableService,
     UninstallService,
     RemoveBinaries,
+    RemoveShellExtras,
 }
 
-pub fn build_plan(mode: ActionMode, verify: bool) -> Vec<StepKind> {
+pub fn build_plan(mode: ActionMode, verify: bool, shell_extras: bool) -> Vec<StepKind> {
     match mode {
         ActionMode::Test => Vec::new(),
         ActionMode::Install => {
@@ -41,12 +43,18 @@ pub fn build_plan(mode: ActionMode, verify: bool) -> Vec<StepKind> {
                 StepKind::EnsureConfig,
                 StepKind::StopDaemon,
                 StepKind::InstallBinaries,
-                StepKind::InstallService,
-                StepKind::EnableService,
             ]);
+            if shell_extras {
+                steps.push(StepKind::InstallShellExtras);
+            }
+            steps.extend([StepKind::InstallService, StepKind::EnableService]);
             steps
         }
-        ActionMode::Uninstall => vec![StepKind::UninstallService, StepKind::RemoveBinaries],
+        ActionMode::Uninstall => vec![
+            StepKind::UninstallService,
+            StepKind::RemoveBinaries,
+            StepKind::RemoveShellExtras,
+        ],
         ActionMode::Reset => vec![StepKind::ResetConfig],
     }
 }
@@ -69,10 +77,12 @@ pub fn run_step(step: StepKind, ctx: &mut ActionContext) -> Result<()> {
         StepKind::EnsureConfig => ensure_config(ctx),
         StepKind::ResetConfig => reset_config(ctx),
         StepKind::InstallBinaries => install_binaries(ctx),
+        StepKind::InstallShellExtras => install_shell_extras(ctx),
         StepKind::InstallService => install_service(ctx),
         StepKind::EnableService => enable_service(ctx),
         StepKind::UninstallService => uninstall_service(ctx),
         StepKind::RemoveBinaries => remove_binaries(ctx),
+        StepKind::RemoveShellExtras => remove_shell_extras(ctx),
     }
 }
 
@@ -85,9 +95,11 @@ pub fn step_label(kind: StepKind) -> &'static str {
         StepKind::EnsureConfig => "Ensure config files",
         StepKind::ResetConfig => "Reset config files",
         StepKind::InstallBinaries => "Install binaries",
+        StepKind::InstallShellExtras => "Install completions and man page",
         StepKind::InstallService => "Install systemd unit",
         StepKind::EnableService => "Enable user service",
         StepKind::UninstallService => "Remove systemd unit",
         StepKind::RemoveBinaries => "Remove binaries",
+        StepKind::RemoveShellExtras => "Remove completions and man page",
     }
 }
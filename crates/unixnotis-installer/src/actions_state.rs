@@ -10,7 +10,7 @@ use anyhow::Result;
 
 use crate::detect::Detection;
 use crate::events::UiMessage;
-use crate::model::ActionMode;
+use crate::model::{ActionMode, ServiceLayout};
 use crate::paths::{format_with_home, InstallPaths};
 
 use super::log_line;
@@ -21,6 +21,7 @@ pub struct ActionContext<'a> {
     pub install_state: Option<InstallState>,
     pub log_tx: Sender<UiMessage>,
     pub action_mode: ActionMode,
+    pub service_layout: ServiceLayout,
 }
 
 #[derive(Clone)]
@@ -116,6 +117,10 @@ pub fn check_install_state_step(ctx: &mut ActionContext) -> Result<()> {
             format_with_home(&ctx.paths.unit_path)
         ),
     );
+    log_line(
+        ctx,
+        format!("- unit layout: {}", ctx.paths.installed_layout().label()),
+    );
     if let Some(err) = state.unit_active_error.as_ref() {
         log_line(ctx, format!("- systemd status check failed: {}", err));
     }
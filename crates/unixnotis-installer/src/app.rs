@@ -3,9 +3,10 @@
 use crate::actions::{check_install_state, InstallState};
 use crate::checks::Checks;
 use crate::detect::Detection;
-use crate::model::{ActionMode, ActionStep};
+use crate::model::{ActionMode, ActionStep, ServiceLayout};
 use crate::paths::InstallPaths;
 use std::time::Instant;
+use unixnotis_core::i18n::tr;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ProgressState {
@@ -53,6 +54,12 @@ pub struct App {
     // Whether to run extra verification steps.
     pub verify: bool,
 
+    // Systemd unit layout used by install.
+    pub service_layout: ServiceLayout,
+
+    // Whether install also writes shell completions and the man page.
+    pub shell_extras: bool,
+
     // Log lines for UI display.
     pub logs: Vec<String>,
 
@@ -77,9 +84,16 @@ impl App {
         // Initialize with current system state.
         let checks = Checks::run();
         let detection = crate::detect::detect();
-        let install_state = InstallPaths::discover()
-            .ok()
-            .map(|paths| check_install_state(&paths));
+        let paths = InstallPaths::discover().ok();
+        let install_state = paths.as_ref().map(check_install_state);
+        // Reinstalls keep the layout already on disk unless the user switches it.
+        let service_layout = paths
+            .as_ref()
+            .map(InstallPaths::installed_layout)
+            .unwrap_or(ServiceLayout::Single);
+        let shell_extras = paths
+            .as_ref()
+            .is_some_and(InstallPaths::shell_extras_installed);
 
         Self {
             checks,
@@ -87,6 +101,8 @@ impl App {
             menu_index: 0,
             screen: Screen::Welcome,
             verify: false,
+            service_layout,
+            shell_extras,
             logs: Vec::new(),
             steps: Vec::new(),
             progress_state: ProgressState::Idle,
@@ -119,24 +135,23 @@ impl App {
             .map(|paths| check_install_state(&paths));
     }
 
-    pub fn action_label(&self, mode: ActionMode) -> &'static str {
+    pub fn action_label(&self, mode: ActionMode) -> String {
         match mode {
             ActionMode::Install => self.install_label(),
-            ActionMode::Reset => "Reset config",
             _ => mode.label(),
         }
     }
 
-    fn install_label(&self) -> &'static str {
+    fn install_label(&self) -> String {
         if self
             .install_state
             .as_ref()
             .map(|state| state.is_fully_installed())
             .unwrap_or(false)
         {
-            "Reinstall"
+            tr("action-reinstall")
         } else {
-            "Install"
+            ActionMode::Install.label()
         }
     }
 }
@@ -22,11 +22,15 @@ use crate::actions::{
 };
 use crate::app::{App, MenuItem, ProgressState, Screen};
 use crate::events::{UiMessage, WorkerEvent};
-use crate::model::{ActionMode, StepStatus};
+use crate::model::{ActionMode, ServiceLayout, StepStatus};
 use crate::paths::InstallPaths;
 use crate::terminal::TerminalGuard;
 
 fn main() -> Result<()> {
+    let language = unixnotis_core::Config::load_default()
+        .ok()
+        .and_then(|config| config.general.language);
+    unixnotis_core::i18n::init(language.as_deref());
     let mut app = App::new();
     let mut terminal_guard = TerminalGuard::new()?;
     let exit_action = run_app(&mut terminal_guard, &mut app);
@@ -111,6 +115,14 @@ fn handle_welcome_key(app: &mut App, key: KeyEvent) -> Result<Option<ExitAction>
             app.verify = !app.verify;
             Ok(None)
         }
+        KeyCode::Char('l') | KeyCode::Char('L') => {
+            app.service_layout = app.service_layout.toggle();
+            Ok(None)
+        }
+        KeyCode::Char('c') | KeyCode::Char('C') => {
+            app.shell_extras = !app.shell_extras;
+            Ok(None)
+        }
         KeyCode::Enter => match app.selected_menu() {
             MenuItem::Quit => Ok(Some(ExitAction::None)),
             MenuItem::Action(mode) => {
@@ -198,7 +210,7 @@ fn start_action(
         None
     };
 
-    let plan = build_plan(mode, app.verify);
+    let plan = build_plan(mode, app.verify, app.shell_extras);
 
     app.steps = steps_from_plan(&plan);
     app.logs.clear();
@@ -212,9 +224,18 @@ fn start_action(
         .draw(|frame| ui::draw(frame, app))?;
 
     let detection = app.detection.clone();
+    let service_layout = app.service_layout;
     let ui_tx = ui_tx.clone();
     thread::spawn(move || {
-        run_action_worker(plan, mode, detection, paths, install_state, ui_tx);
+        run_action_worker(
+            plan,
+            mode,
+            detection,
+            paths,
+            install_state,
+            service_layout,
+            ui_tx,
+        );
     });
 
     Ok(())
@@ -226,6 +247,7 @@ fn run_action_worker(
     detection: crate::detect::Detection,
     paths: InstallPaths,
     install_state: Option<crate::actions::InstallState>,
+    service_layout: ServiceLayout,
     ui_tx: mpsc::Sender<UiMessage>,
 ) {
     // Run plan steps on the worker thread and stream progress events to the UI.
@@ -241,6 +263,7 @@ fn run_action_worker(
                 install_state: install_state.clone(),
                 log_tx: ui_tx.clone(),
                 action_mode: mode,
+                service_layout,
             };
             run_step(*step, &mut ctx)
         };
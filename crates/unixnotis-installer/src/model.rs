@@ -1,5 +1,7 @@
 //! Shared installer types for action selection and progress reporting.
 
+use unixnotis_core::i18n::tr;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ActionMode {
     Test,
@@ -9,12 +11,37 @@ pub enum ActionMode {
 }
 
 impl ActionMode {
-    pub fn label(self) -> &'static str {
+    pub fn label(self) -> String {
+        tr(match self {
+            ActionMode::Test => "action-test",
+            ActionMode::Install => "action-install",
+            ActionMode::Uninstall => "action-uninstall",
+            ActionMode::Reset => "action-reset",
+        })
+    }
+}
+
+/// How the installer lays out systemd user units.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ServiceLayout {
+    // One daemon unit; the daemon starts the popups and panel itself.
+    Single,
+    // Separate popups and center units tied to the daemon unit, restartable on their own.
+    Split,
+}
+
+impl ServiceLayout {
+    pub fn label(self) -> String {
+        tr(match self {
+            ServiceLayout::Single => "layout-single",
+            ServiceLayout::Split => "layout-split",
+        })
+    }
+
+    pub fn toggle(self) -> Self {
         match self {
-            ActionMode::Test => "Trial run",
-            ActionMode::Install => "Install",
-            ActionMode::Uninstall => "Uninstall",
-            ActionMode::Reset => "Reset config",
+            ServiceLayout::Single => ServiceLayout::Split,
+            ServiceLayout::Split => ServiceLayout::Single,
         }
     }
 }
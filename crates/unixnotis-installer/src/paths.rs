@@ -6,12 +6,20 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 
+use crate::model::ServiceLayout;
+
 pub struct InstallPaths {
     pub repo_root: PathBuf,
     pub release_dir: PathBuf,
     pub bin_dir: PathBuf,
     pub unit_dir: PathBuf,
     pub unit_path: PathBuf,
+    pub popups_unit_path: PathBuf,
+    pub center_unit_path: PathBuf,
+    pub bash_completion_path: PathBuf,
+    pub zsh_completion_path: PathBuf,
+    pub fish_completion_path: PathBuf,
+    pub man_page_path: PathBuf,
 }
 
 impl InstallPaths {
@@ -21,6 +29,23 @@ impl InstallPaths {
         let bin_dir = home_dir()?.join(".local").join("bin");
         let unit_dir = home_dir()?.join(".config").join("systemd").join("user");
         let unit_path = unit_dir.join("unixnotis-daemon.service");
+        let popups_unit_path = unit_dir.join("unixnotis-popups.service");
+        let center_unit_path = unit_dir.join("unixnotis-center.service");
+        let data_dir = data_dir()?;
+        let bash_completion_path = data_dir
+            .join("bash-completion")
+            .join("completions")
+            .join("noticenterctl");
+        let zsh_completion_path = data_dir
+            .join("zsh")
+            .join("site-functions")
+            .join("_noticenterctl");
+        let fish_completion_path = data_dir
+            .join("fish")
+            .join("vendor_completions.d")
+            .join("noticenterctl.fish");
+        // man-db maps ~/.local/bin on PATH to ~/.local/share/man.
+        let man_page_path = data_dir.join("man").join("man1").join("noticenterctl.1");
 
         Ok(Self {
             repo_root,
@@ -28,8 +53,28 @@ impl InstallPaths {
             bin_dir,
             unit_dir,
             unit_path,
+            popups_unit_path,
+            center_unit_path,
+            bash_completion_path,
+            zsh_completion_path,
+            fish_completion_path,
+            man_page_path,
         })
     }
+
+    /// Layout of the units currently on disk, judged by the presence of the UI units.
+    pub fn installed_layout(&self) -> ServiceLayout {
+        if self.popups_unit_path.exists() || self.center_unit_path.exists() {
+            ServiceLayout::Split
+        } else {
+            ServiceLayout::Single
+        }
+    }
+
+    /// Whether an earlier install left completions and the man page in place.
+    pub fn shell_extras_installed(&self) -> bool {
+        self.man_page_path.exists()
+    }
 }
 
 pub fn home_dir() -> Result<PathBuf> {
@@ -37,6 +82,14 @@ pub fn home_dir() -> Result<PathBuf> {
     Ok(PathBuf::from(home))
 }
 
+/// XDG data directory, where completions and man pages are looked up per user.
+fn data_dir() -> Result<PathBuf> {
+    match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => Ok(home_dir()?.join(".local").join("share")),
+    }
+}
+
 pub fn format_with_home(path: &Path) -> String {
     if let Ok(home) = home_dir() {
         if let Ok(stripped) = path.strip_prefix(&home) {
@@ -5,6 +5,7 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap};
 use ratatui::Frame;
+use unixnotis_core::i18n::{tr, tr_args};
 
 use crate::actions::{format_daemon_status, summarize_owner};
 use crate::app::{App, MenuItem, ProgressState, Screen};
@@ -39,7 +40,7 @@ fn draw_welcome(frame: &mut Frame<'_>, app: &App) {
 
     let status = render_status(app);
     let status_block = Block::default()
-        .title("System status")
+        .title(tr("installer-system-status"))
         .borders(Borders::ALL);
     frame.render_widget(
         Paragraph::new(status)
@@ -49,20 +50,26 @@ fn draw_welcome(frame: &mut Frame<'_>, app: &App) {
     );
 
     let menu = render_menu(app, body[1].width);
-    let menu_block = Block::default().title("Actions").borders(Borders::ALL);
+    let menu_block = Block::default()
+        .title(tr("installer-actions"))
+        .borders(Borders::ALL);
     frame.render_widget(menu.block(menu_block), body[1]);
 
     let footer = Paragraph::new(Text::from(Line::from(vec![
         Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" = select  "),
+        Span::raw(format!(" = {}  ", tr("installer-key-select"))),
         Span::styled("Up/Down", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" = move  "),
+        Span::raw(format!(" = {}  ", tr("installer-key-move"))),
         Span::styled("R", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" = refresh  "),
+        Span::raw(format!(" = {}  ", tr("installer-key-refresh"))),
         Span::styled("V", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" = toggle verify  "),
+        Span::raw(format!(" = {}  ", tr("installer-key-verify"))),
+        Span::styled("L", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(format!(" = {}  ", tr("installer-key-layout"))),
+        Span::styled("C", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(format!(" = {}  ", tr("installer-key-extras"))),
         Span::styled("Q", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" = quit"),
+        Span::raw(format!(" = {}", tr("installer-key-quit"))),
     ])))
     .alignment(Alignment::Center)
     .block(Block::default().borders(Borders::TOP));
@@ -83,7 +90,10 @@ fn draw_confirm(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
 
     let mut lines = Vec::new();
     lines.push(Line::from(vec![Span::styled(
-        format!("Confirm {}", app.action_label(mode)),
+        tr_args(
+            "installer-confirm",
+            &[("action", app.action_label(mode).into())],
+        ),
         Style::default()
             .fg(Color::Cyan)
             .add_modifier(Modifier::BOLD),
@@ -92,7 +102,7 @@ fn draw_confirm(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
 
     lines.push(Line::from(vec![
         Span::styled(
-            "Current owner: ",
+            format!("{}: ", tr("installer-current-owner")),
             Style::default().add_modifier(Modifier::BOLD),
         ),
         Span::raw(summarize_owner(&app.detection.owner)),
@@ -100,17 +110,34 @@ fn draw_confirm(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
 
     lines.push(Line::from(vec![
         Span::styled(
-            "Verification: ",
+            format!("{}: ", tr("installer-verification")),
             Style::default().add_modifier(Modifier::BOLD),
         ),
-        Span::raw(if app.verify { "enabled" } else { "disabled" }),
+        Span::raw(verify_label(app.verify)),
     ]));
 
+    if matches!(mode, ActionMode::Install) {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{}: ", tr("installer-service-layout")),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(app.service_layout.label()),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{}: ", tr("installer-shell-extras")),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(verify_label(app.shell_extras)),
+        ]));
+    }
+
     if let Err(reason) = app.checks.ready_for(mode) {
         lines.push(Line::from(""));
         lines.push(Line::from(vec![
             Span::styled(
-                "Blocked: ",
+                format!("{}: ", tr("installer-blocked")),
                 Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             ),
             Span::raw(reason),
@@ -125,19 +152,21 @@ fn draw_confirm(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
     {
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
-            "Reinstall will overwrite binaries and the systemd unit.",
+            tr("installer-reinstall-warning"),
             Style::default().fg(Color::Yellow),
         )));
     }
     if matches!(mode, ActionMode::Reset) {
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
-            "Reset will overwrite config.toml and theme files with defaults.",
+            tr("installer-reset-warning"),
             Style::default().fg(Color::Yellow),
         )));
     }
 
-    let block = Block::default().title("Confirmation").borders(Borders::ALL);
+    let block = Block::default()
+        .title(tr("installer-confirmation"))
+        .borders(Borders::ALL);
     frame.render_widget(
         Paragraph::new(Text::from(lines))
             .block(block)
@@ -147,9 +176,9 @@ fn draw_confirm(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
 
     let footer = Paragraph::new(Text::from(Line::from(vec![
         Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" = proceed  "),
+        Span::raw(format!(" = {}  ", tr("installer-key-proceed"))),
         Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" = cancel"),
+        Span::raw(format!(" = {}", tr("installer-key-cancel"))),
     ])))
     .alignment(Alignment::Center)
     .block(Block::default().borders(Borders::TOP));
@@ -158,10 +187,10 @@ fn draw_confirm(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
 
 fn draw_progress(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
     let (status_label, status_color) = match app.progress_state {
-        ProgressState::Running => ("In progress", Color::Yellow),
-        ProgressState::Completed => ("Completed", Color::Green),
-        ProgressState::Failed => ("Failed", Color::Red),
-        ProgressState::Idle => ("Pending", Color::Gray),
+        ProgressState::Running => (tr("installer-in-progress"), Color::Yellow),
+        ProgressState::Completed => (tr("installer-completed"), Color::Green),
+        ProgressState::Failed => (tr("installer-failed"), Color::Red),
+        ProgressState::Idle => (tr("installer-pending"), Color::Gray),
     };
 
     let layout = Layout::default()
@@ -186,16 +215,23 @@ fn draw_progress(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
         if matches!(app.progress_state, ProgressState::Failed) {
             let summary = summarize_error(err);
             status_lines.push(Line::from(vec![
-                Span::styled("Error: ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("{}: ", tr("installer-error")),
+                    Style::default().fg(Color::Red),
+                ),
                 Span::raw(summary),
             ]));
-            status_lines.push(Line::from("See logs for full output."));
+            status_lines.push(Line::from(tr("installer-see-logs")));
         }
     }
 
     let status = Paragraph::new(Text::from(status_lines))
         .alignment(Alignment::Center)
-        .block(Block::default().title("Progress").borders(Borders::ALL))
+        .block(
+            Block::default()
+                .title(tr("installer-progress"))
+                .borders(Borders::ALL),
+        )
         .wrap(Wrap { trim: true });
     frame.render_widget(status, layout[1]);
 
@@ -205,17 +241,21 @@ fn draw_progress(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
         .split(layout[2]);
 
     let steps = render_steps(&app.steps, body[0].width);
-    let steps_block = Block::default().title("Steps").borders(Borders::ALL);
+    let steps_block = Block::default()
+        .title(tr("installer-steps"))
+        .borders(Borders::ALL);
     frame.render_widget(steps.block(steps_block), body[0]);
 
     let logs = render_logs(&app.logs, body[1].width);
-    let logs_block = Block::default().title("Logs").borders(Borders::ALL);
+    let logs_block = Block::default()
+        .title(tr("installer-logs"))
+        .borders(Borders::ALL);
     frame.render_widget(Paragraph::new(logs).block(logs_block), body[1]);
 
     let footer_text = match app.progress_state {
-        ProgressState::Running => "Running...",
-        ProgressState::Completed | ProgressState::Failed => "Enter = back to menu  Q = quit",
-        ProgressState::Idle => "",
+        ProgressState::Running => tr("installer-running"),
+        ProgressState::Completed | ProgressState::Failed => tr("installer-done-footer"),
+        ProgressState::Idle => String::new(),
     };
     let footer = Paragraph::new(footer_text)
         .alignment(Alignment::Center)
@@ -226,12 +266,12 @@ fn draw_progress(frame: &mut Frame<'_>, app: &App, mode: ActionMode) {
 fn draw_header(frame: &mut Frame<'_>, area: Rect) {
     let title = Paragraph::new(Line::from(vec![
         Span::styled(
-            "UnixNotis Installer",
+            tr("installer-title"),
             Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::raw("  —  Arch Wayland Notification Center"),
+        Span::raw(format!("  —  {}", tr("installer-subtitle"))),
     ]))
     .alignment(Alignment::Center)
     .block(Block::default().borders(Borders::BOTTOM));
@@ -245,7 +285,7 @@ fn render_status(app: &App) -> Text<'static> {
 
     // Section header: Compatibility / environment checks.
     lines.push(Line::from(Span::styled(
-        "Compatibility",
+        tr("installer-compatibility"),
         Style::default().add_modifier(Modifier::BOLD),
     )));
 
@@ -262,13 +302,16 @@ fn render_status(app: &App) -> Text<'static> {
 
     // Section header: notification daemon detection + current bus owner.
     lines.push(Line::from(Span::styled(
-        "Notification daemons",
+        tr("installer-daemons"),
         Style::default().add_modifier(Modifier::BOLD),
     )));
 
     // Show who currently owns the org.freedesktop.Notifications bus name.
     lines.push(Line::from(vec![
-        Span::styled("Owner: ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled(
+            format!("{}: ", tr("installer-owner")),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
         Span::raw(summarize_owner(&app.detection.owner)),
     ]));
 
@@ -290,16 +333,38 @@ fn render_status(app: &App) -> Text<'static> {
     // Whether extra verification is enabled (affects action plan and/or checks).
     lines.push(Line::from(vec![
         Span::styled(
-            "Verification: ",
+            format!("{}: ", tr("installer-verification")),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(verify_label(app.verify)),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("{}: ", tr("installer-service-layout")),
             Style::default().add_modifier(Modifier::BOLD),
         ),
-        Span::raw(if app.verify { "enabled" } else { "disabled" }),
+        Span::raw(app.service_layout.label()),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("{}: ", tr("installer-shell-extras")),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(verify_label(app.shell_extras)),
     ]));
 
     // Convert the collected lines into a ratatui Text.
     Text::from(lines)
 }
 
+fn verify_label(verify: bool) -> String {
+    tr(if verify {
+        "installer-enabled"
+    } else {
+        "installer-disabled"
+    })
+}
+
 fn render_check(item: &CheckItem) -> Vec<Line<'static>> {
     // Map check state -> a short label plus color.
     // Bolding the tag makes it stand out even in crowded terminal themes.
@@ -330,9 +395,9 @@ fn render_menu(app: &App, width: u16) -> List<'static> {
         .map(|(index, item)| {
             let label = match item {
                 MenuItem::Action(mode) => app.action_label(*mode),
-                MenuItem::Quit => "Quit",
+                MenuItem::Quit => tr("installer-quit"),
             };
-            let label = truncate_to_width(label, inner_width);
+            let label = truncate_to_width(&label, inner_width);
             let style = if index == app.menu_index {
                 Style::default()
                     .fg(Color::Black)
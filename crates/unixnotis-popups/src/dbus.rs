@@ -1,13 +1,19 @@
 //! D-Bus runtime for popup UI events and control updates.
 
+use std::collections::HashMap;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures_util::StreamExt;
 use tokio::sync::mpsc::{self, UnboundedSender};
 use tracing::{info, warn};
-use unixnotis_core::{CloseReason, ControlProxy, ControlState, NotificationView};
-use zbus::{Connection, Result as ZbusResult};
+use unixnotis_core::popup_positions::PopupPositions;
+use unixnotis_core::ui_ipc::{ControlCommand, ControlEvent};
+use unixnotis_core::{
+    Anchor, BusNames, ControlProxy, ControlState, NotificationView, OsdKind, PanelAction,
+    PanelDebugLevel,
+};
+use zbus::Connection;
 
 /// Events delivered to the GTK main loop.
 #[derive(Debug, Clone)]
@@ -15,23 +21,37 @@ pub enum UiEvent {
     Seed {
         state: ControlState,
         active: Vec<NotificationView>,
+        positions: PopupPositions,
     },
-    NotificationAdded(NotificationView, bool),
-    NotificationUpdated(NotificationView, bool),
-    NotificationClosed(u32, CloseReason),
-    StateChanged(ControlState),
+    /// Show a popup in the given stacking slot, updating it in place if the slot is unchanged.
+    /// Carries when the signal arrived, for `general.measure_latency`.
+    PopupShown(NotificationView, u32, Instant),
+    /// A control signal shared with the panel.
+    Control(ControlEvent),
+    /// The counter on an app group's leading popup was clicked.
+    GroupToggled(String),
+    /// Flash the volume or brightness overlay at this percentage.
+    Osd(OsdKind, u32),
+    /// Show an app's popups at this anchor from now on, or in the regular stack for `None`.
+    AppAnchor(String, Option<Anchor>),
+    /// Connector and model of the output the popup surface entered.
+    OutputEntered(Vec<String>),
+    /// A monitor was connected or disconnected.
+    MonitorsChanged,
+    /// The popup window's scale factor changed.
+    ScaleChanged(i32),
+    /// The popup window was closed by the compositor or, without layer-shell, the user.
+    SurfaceClosed,
+    /// Debug level carried by the latest panel request; popups follow the panel's session.
+    DebugLevel(PanelDebugLevel),
     CssReload,
     ConfigReload,
 }
 
-/// Commands sent from GTK handlers to the D-Bus runtime.
-#[derive(Debug, Clone)]
-pub enum UiCommand {
-    Dismiss(u32),
-    InvokeAction { id: u32, action_key: String },
-}
-
-pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSender<UiCommand> {
+pub fn start_dbus_runtime(
+    names: BusNames,
+    sender: async_channel::Sender<UiEvent>,
+) -> UnboundedSender<ControlCommand> {
     let (command_tx, mut command_rx) = mpsc::unbounded_channel();
 
     thread::spawn(move || {
@@ -53,7 +73,7 @@ pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSe
             };
 
             loop {
-                let proxy = match ControlProxy::new(&connection).await {
+                let proxy = match names.control_proxy(&connection).await {
                     Ok(proxy) => proxy,
                     Err(err) => {
                         warn!(?err, "control interface unavailable, retrying");
@@ -65,6 +85,17 @@ pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSe
                 info!("connected to unixnotis control interface");
                 seed_state(&proxy, &sender).await;
 
+                // popup_shown carries only the id and stacking slot; the notification itself comes
+                // from the added and updated signals and is paired with it here.
+                let mut pending = PendingPopups::default();
+                let mut popup_stream = match proxy.receive_popup_shown().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!(?err, "failed to subscribe to popup_shown");
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        continue;
+                    }
+                };
                 let mut added_stream = match proxy.receive_notification_added().await {
                     Ok(stream) => stream,
                     Err(err) => {
@@ -73,6 +104,14 @@ pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSe
                         continue;
                     }
                 };
+                let mut batch_stream = match proxy.receive_notifications_added_batch().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!(?err, "failed to subscribe to notifications_added_batch");
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        continue;
+                    }
+                };
                 let mut updated_stream = match proxy.receive_notification_updated().await {
                     Ok(stream) => stream,
                     Err(err) => {
@@ -97,6 +136,54 @@ pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSe
                         continue;
                     }
                 };
+                let mut panel_stream = match proxy.receive_panel_requested().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!(?err, "failed to subscribe to panel_requested");
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        continue;
+                    }
+                };
+                let mut preview_stream = match proxy.receive_layout_preview_requested().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!(?err, "failed to subscribe to layout_preview_requested");
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        continue;
+                    }
+                };
+                let mut memory_stream = match proxy.receive_memory_report_requested().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!(?err, "failed to subscribe to memory_report_requested");
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        continue;
+                    }
+                };
+                let mut trim_stream = match proxy.receive_trim_requested().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!(?err, "failed to subscribe to trim_requested");
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        continue;
+                    }
+                };
+                let mut osd_stream = match proxy.receive_osd_requested().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!(?err, "failed to subscribe to osd_requested");
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        continue;
+                    }
+                };
+                let mut anchor_stream = match proxy.receive_app_popup_anchor_changed().await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!(?err, "failed to subscribe to app_popup_anchor_changed");
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        continue;
+                    }
+                };
 
                 loop {
                     tokio::select! {
@@ -104,22 +191,40 @@ pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSe
                             let Some(command) = command else {
                                 break;
                             };
-                            if let Err(err) = handle_command(&proxy, command).await {
+                            if let Err(err) = command.send(&proxy).await {
                                 warn!(?err, "control command failed");
                             }
                         }
+                        signal = popup_stream.next() => {
+                            let Some(signal) = signal else {
+                                warn!("popup_shown stream ended");
+                                break;
+                            };
+                            if let Ok(args) = signal.args() {
+                                let shown = pending.slot(*args.id(), *args.slot(), Instant::now());
+                                send_popup(&sender, shown).await;
+                            }
+                        }
                         signal = added_stream.next() => {
                             let Some(signal) = signal else {
                                 warn!("notification_added stream ended");
                                 break;
                             };
                             if let Ok(args) = signal.args() {
-                                let _ = sender
-                                    .send(UiEvent::NotificationAdded(
-                                        args.notification().clone(),
-                                        *args.show_popup(),
-                                    ))
-                                    .await;
+                                let shown = pending.view(args.notification(), *args.show_popup());
+                                send_popup(&sender, shown).await;
+                            }
+                        }
+                        signal = batch_stream.next() => {
+                            let Some(signal) = signal else {
+                                warn!("notifications_added_batch stream ended");
+                                break;
+                            };
+                            if let Ok(args) = signal.args() {
+                                for (notification, show_popup) in args.notifications() {
+                                    let shown = pending.view(notification, *show_popup);
+                                    send_popup(&sender, shown).await;
+                                }
                             }
                         }
                         signal = updated_stream.next() => {
@@ -128,11 +233,13 @@ pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSe
                                 break;
                             };
                             if let Ok(args) = signal.args() {
+                                let shown = pending.view(args.notification(), *args.show_popup());
+                                send_popup(&sender, shown).await;
                                 let _ = sender
-                                    .send(UiEvent::NotificationUpdated(
+                                    .send(UiEvent::Control(ControlEvent::NotificationUpdated(
                                         args.notification().clone(),
                                         *args.show_popup(),
-                                    ))
+                                    )))
                                     .await;
                             }
                         }
@@ -142,11 +249,12 @@ pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSe
                                 break;
                             };
                             if let Ok(args) = signal.args() {
+                                pending.forget(*args.id());
                                 let _ = sender
-                                    .send(UiEvent::NotificationClosed(
+                                    .send(UiEvent::Control(ControlEvent::NotificationClosed(
                                         *args.id(),
                                         *args.reason(),
-                                    ))
+                                    )))
                                     .await;
                             }
                         }
@@ -156,9 +264,73 @@ pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSe
                                 break;
                             };
                             if let Ok(args) = signal.args() {
-                                let _ = sender.send(UiEvent::StateChanged(args.state().clone())).await;
+                                let event = ControlEvent::StateChanged(args.state().clone());
+                                let _ = sender.send(UiEvent::Control(event)).await;
+                            }
+                        }
+                        signal = panel_stream.next() => {
+                            let Some(signal) = signal else {
+                                warn!("panel_requested stream ended");
+                                break;
+                            };
+                            if let Ok(args) = signal.args() {
+                                let request = args.request();
+                                let level = match request.action {
+                                    PanelAction::Close => PanelDebugLevel::Off,
+                                    PanelAction::Open | PanelAction::Toggle => request.debug,
+                                };
+                                let _ = sender.send(UiEvent::DebugLevel(level)).await;
+                            }
+                        }
+                        signal = preview_stream.next() => {
+                            let Some(signal) = signal else {
+                                warn!("layout_preview_requested stream ended");
+                                break;
+                            };
+                            if let Ok(args) = signal.args() {
+                                let event = ControlEvent::LayoutPreview(*args.seconds());
+                                let _ = sender.send(UiEvent::Control(event)).await;
                             }
                         }
+                        signal = osd_stream.next() => {
+                            let Some(signal) = signal else {
+                                warn!("osd_requested stream ended");
+                                break;
+                            };
+                            if let Ok(args) = signal.args() {
+                                let _ = sender
+                                    .send(UiEvent::Osd(*args.kind(), *args.percent()))
+                                    .await;
+                            }
+                        }
+                        signal = anchor_stream.next() => {
+                            let Some(signal) = signal else {
+                                warn!("app_popup_anchor_changed stream ended");
+                                break;
+                            };
+                            if let Ok(args) = signal.args() {
+                                let anchor = Anchor::from_name(args.anchor());
+                                let _ = sender
+                                    .send(UiEvent::AppAnchor(args.app().to_string(), anchor))
+                                    .await;
+                            }
+                        }
+                        signal = memory_stream.next() => {
+                            if signal.is_none() {
+                                warn!("memory_report_requested stream ended");
+                                break;
+                            }
+                            let _ = sender
+                                .send(UiEvent::Control(ControlEvent::MemoryReportRequested))
+                                .await;
+                        }
+                        signal = trim_stream.next() => {
+                            if signal.is_none() {
+                                warn!("trim_requested stream ended");
+                                break;
+                            }
+                            let _ = sender.send(UiEvent::Control(ControlEvent::TrimCaches)).await;
+                        }
                     }
                 }
                 tokio::time::sleep(Duration::from_millis(300)).await;
@@ -169,23 +341,94 @@ pub fn start_dbus_runtime(sender: async_channel::Sender<UiEvent>) -> UnboundedSe
     command_tx
 }
 
+/// Notifications and popup slots waiting for their other half.
+///
+/// An update arrives before its `popup_shown`, while a batched addition can arrive after it.
+#[derive(Default)]
+struct PendingPopups {
+    views: HashMap<u32, NotificationView>,
+    slots: HashMap<u32, (u32, Instant)>,
+}
+
+impl PendingPopups {
+    fn view(
+        &mut self,
+        notification: &NotificationView,
+        show_popup: bool,
+    ) -> Option<(NotificationView, u32, Instant)> {
+        let id = notification.id;
+        if !show_popup {
+            self.forget(id);
+            return None;
+        }
+        match self.slots.remove(&id) {
+            Some((slot, received)) => Some((notification.clone(), slot, received)),
+            None => {
+                self.views.insert(id, notification.clone());
+                None
+            }
+        }
+    }
+
+    fn slot(
+        &mut self,
+        id: u32,
+        slot: u32,
+        received: Instant,
+    ) -> Option<(NotificationView, u32, Instant)> {
+        match self.views.remove(&id) {
+            Some(notification) => Some((notification, slot, received)),
+            None => {
+                self.slots.insert(id, (slot, received));
+                None
+            }
+        }
+    }
+
+    fn forget(&mut self, id: u32) {
+        self.views.remove(&id);
+        self.slots.remove(&id);
+    }
+}
+
+async fn send_popup(
+    sender: &async_channel::Sender<UiEvent>,
+    shown: Option<(NotificationView, u32, Instant)>,
+) {
+    if let Some((notification, slot, received)) = shown {
+        let _ = sender
+            .send(UiEvent::PopupShown(notification, slot, received))
+            .await;
+    }
+}
+
 async fn seed_state(proxy: &ControlProxy<'_>, sender: &async_channel::Sender<UiEvent>) {
     let state = proxy.get_state().await;
     let active = proxy.list_active().await;
+    let positions = match proxy.list_app_popup_anchors().await {
+        Ok(anchors) => PopupPositions::from_names(
+            anchors
+                .iter()
+                .map(|(app, anchor)| (app.as_str(), anchor.as_str())),
+        ),
+        Err(err) => {
+            warn!(?err, "failed to list app popup positions");
+            PopupPositions::default()
+        }
+    };
 
     if let (Ok(state), Ok(active)) = (state, active) {
-        let _ = sender.send(UiEvent::Seed { state, active }).await;
-    }
-}
-
-async fn handle_command(proxy: &ControlProxy<'_>, command: UiCommand) -> ZbusResult<()> {
-    match command {
-        UiCommand::Dismiss(id) => proxy.dismiss(id).await,
-        UiCommand::InvokeAction { id, action_key } => proxy.invoke_action(id, &action_key).await,
+        let _ = sender
+            .send(UiEvent::Seed {
+                state,
+                active,
+                positions,
+            })
+            .await;
     }
 }
 
-fn drain_offline_commands(command_rx: &mut mpsc::UnboundedReceiver<UiCommand>) {
+fn drain_offline_commands(command_rx: &mut mpsc::UnboundedReceiver<ControlCommand>) {
     while command_rx.try_recv().is_ok() {
         warn!("dropping control command while interface is unavailable");
     }
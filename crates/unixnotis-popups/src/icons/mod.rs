@@ -2,17 +2,19 @@
 //!
 //! Separates icon lookup and image decoding from UI state management.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use gio::prelude::{AppInfoExt, FileExt};
+use gio::prelude::FileExt;
 use gtk::gdk;
 use gtk::gdk::prelude::*;
 use gtk::{gdk::Texture, IconLookupFlags, IconPaintable, TextDirection};
 use image::imageops::FilterType;
 use image::GenericImageView;
+use tracing::instrument;
 use unixnotis_core::{NotificationImage, NotificationView};
+use unixnotis_ui::preview::{pack_rows, PreviewSource, MIN_PREVIEW_SOURCE};
 
 pub(super) fn file_path_from_hint(path: &str) -> Option<PathBuf> {
     // Accept raw absolute paths and file:// URIs, decoding percent escapes when present.
@@ -62,8 +64,10 @@ pub(super) fn resolve_icon_image(name: &str, size: i32) -> Option<gtk::Image> {
             return Some(gtk::Image::from_file(&file_path));
         }
     }
-    let paintable = resolve_icon_paintable(name, size)?;
-    let widget = gtk::Image::from_paintable(Some(&paintable));
+    // The lookup only filters out missing icons. Loading by name lets GTK pick the variant for
+    // the window's scale and reload it when the scale changes, instead of a fixed 1x paintable.
+    resolve_icon_paintable(name, size)?;
+    let widget = gtk::Image::from_icon_name(name);
     widget.set_pixel_size(size);
     Some(widget)
 }
@@ -91,99 +95,6 @@ pub(super) fn collect_icon_candidates(notification: &NotificationView) -> Vec<St
         .collect()
 }
 
-#[derive(Default)]
-pub(super) struct DesktopIconIndex {
-    by_name: HashMap<String, Vec<String>>,
-    by_wm_class: HashMap<String, Vec<String>>,
-    by_id: HashMap<String, Vec<String>>,
-}
-
-impl DesktopIconIndex {
-    pub(super) fn new() -> Self {
-        let mut index = Self::default();
-        for app_info in gio::AppInfo::all() {
-            let Ok(desktop) = app_info.downcast::<gio::DesktopAppInfo>() else {
-                continue;
-            };
-            let icon_name = desktop
-                .string("Icon")
-                .map(|value| value.to_string())
-                .unwrap_or_default();
-            if icon_name.is_empty() {
-                continue;
-            }
-            index.add_name(desktop.name().as_str(), &icon_name);
-            index.add_name(desktop.display_name().as_str(), &icon_name);
-            if let Some(generic) = desktop.generic_name() {
-                index.add_name(generic.as_str(), &icon_name);
-            }
-            if let Some(startup_wm_class) = desktop.startup_wm_class() {
-                index.add_wm_class(startup_wm_class.as_str(), &icon_name);
-            }
-            if let Some(id) = desktop.id() {
-                index.add_id(id.as_str(), &icon_name);
-            }
-        }
-        index
-    }
-
-    pub(super) fn icons_for(&self, key: &str) -> Option<Vec<String>> {
-        let normalized = normalize_key(key);
-        if normalized.is_empty() {
-            return None;
-        }
-        let mut out = Vec::new();
-        if let Some(values) = self.by_id.get(&normalized) {
-            out.extend(values.iter().cloned());
-        }
-        if let Some(values) = self.by_wm_class.get(&normalized) {
-            out.extend(values.iter().cloned());
-        }
-        if let Some(values) = self.by_name.get(&normalized) {
-            out.extend(values.iter().cloned());
-        }
-        if out.is_empty() {
-            return None;
-        }
-        let mut seen = HashSet::new();
-        let filtered = out
-            .into_iter()
-            .filter(|value| seen.insert(value.clone()))
-            .collect::<Vec<_>>();
-        Some(filtered)
-    }
-
-    fn add_name(&mut self, key: &str, icon: &str) {
-        add_icon_to_map(&mut self.by_name, key, icon);
-    }
-
-    fn add_wm_class(&mut self, key: &str, icon: &str) {
-        add_icon_to_map(&mut self.by_wm_class, key, icon);
-    }
-
-    fn add_id(&mut self, key: &str, icon: &str) {
-        add_icon_to_map(&mut self.by_id, key, icon);
-        if let Some(stripped) = key.strip_suffix(".desktop") {
-            add_icon_to_map(&mut self.by_id, stripped, icon);
-        }
-    }
-}
-
-fn add_icon_to_map(map: &mut HashMap<String, Vec<String>>, key: &str, icon: &str) {
-    let key = normalize_key(key);
-    if key.is_empty() || icon.is_empty() {
-        return;
-    }
-    let entry = map.entry(key).or_default();
-    if !entry.iter().any(|value| value == icon) {
-        entry.push(icon.to_string());
-    }
-}
-
-fn normalize_key(value: &str) -> String {
-    value.trim().to_lowercase()
-}
-
 fn is_missing_icon(path: &Path) -> bool {
     // Filter the theme placeholder to avoid rendering a missing-icon glyph.
     let Some(stem) = path.file_stem().and_then(|value| value.to_str()) else {
@@ -257,6 +168,61 @@ pub(super) struct RasterIcon {
 const MAX_ICON_BYTES: u64 = 16 * 1024 * 1024;
 const MAX_ICON_DIMENSION: u32 = 2048;
 
+/// Preview source for a notification, image-data first; `None` when it has no large image.
+pub(super) fn preview_source(image: &NotificationImage) -> Option<PreviewSource> {
+    if image.has_image_data && !image.image_data.data.is_empty() {
+        return PreviewSource::from_image_data(&image.image_data);
+    }
+    let path = file_path_from_hint(&image.image_path)?;
+    // Vector images are icons, not pictures worth a preview.
+    let svg = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"));
+    (!svg).then_some(PreviewSource::Path(path))
+}
+
+/// Decode a preview scaled down to fit `max_width` x `max_height` device pixels.
+#[instrument(level = "debug", skip_all, fields(max_height = max_height))]
+pub(super) fn decode_preview(
+    source: PreviewSource,
+    max_width: u32,
+    max_height: u32,
+) -> Result<RasterIcon, String> {
+    let image = match source {
+        PreviewSource::Path(path) => {
+            let icon = decode_icon_file(&path)?;
+            image::RgbaImage::from_raw(icon.width as u32, icon.height as u32, icon.bytes)
+        }
+        PreviewSource::Pixels {
+            bytes,
+            width,
+            height,
+            rowstride,
+        } => image::RgbaImage::from_raw(width, height, pack_rows(bytes, width, height, rowstride)?),
+    }
+    .ok_or_else(|| "image buffer does not match its size".to_string())?;
+    let (width, height) = image.dimensions();
+    if width.max(height) < MIN_PREVIEW_SOURCE as u32 {
+        return Err("image too small for a preview".to_string());
+    }
+    let mut image = image::DynamicImage::ImageRgba8(image);
+    if width > max_width || height > max_height {
+        // resize keeps the aspect ratio inside the box; smaller images are left as they are.
+        image = image.resize(max_width, max_height, FilterType::CatmullRom);
+    }
+    let rgba = image.to_rgba8();
+    let width = rgba.width() as i32;
+    let height = rgba.height() as i32;
+    Ok(RasterIcon {
+        bytes: rgba.into_raw(),
+        width,
+        height,
+        stride: width.saturating_mul(4),
+    })
+}
+
+#[instrument(level = "debug", skip_all)]
 pub(super) fn decode_icon_file(path: &Path) -> Result<RasterIcon, String> {
     // Decode on a worker thread; keep I/O and CPU-bound work off the GTK main loop.
     let metadata = fs::metadata(path).map_err(|err| err.to_string())?;
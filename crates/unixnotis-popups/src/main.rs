@@ -10,8 +10,7 @@ use clap::Parser;
 use glib::MainContext;
 use gtk::prelude::*;
 use tracing::info;
-use tracing_subscriber::EnvFilter;
-use unixnotis_core::Config;
+use unixnotis_core::{BusNames, Config};
 use unixnotis_ui::css::{self, CssKind};
 
 mod dbus;
@@ -23,12 +22,21 @@ struct Args {
     /// Path to config.toml
     #[arg(long)]
     config: Option<PathBuf>,
+
+    /// Suffix for alternate bus names (falls back to UNIXNOTIS_BUS_SUFFIX)
+    #[arg(long)]
+    bus_suffix: Option<String>,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
     let (config, config_path) = load_config(&args).context("load config")?;
-    init_tracing(&config);
+    let names = BusNames::resolve(args.bus_suffix.as_deref())?;
+    let trace_guard = unixnotis_core::logging::init_tracing(
+        config.general.log_level.as_deref(),
+        "unixnotis-popups",
+    );
+    unixnotis_core::i18n::init(config.general.language.as_deref());
     let config_source = if args.config.is_some() {
         "custom"
     } else if config_path.exists() {
@@ -47,6 +55,8 @@ fn main() -> Result<()> {
     if !is_wayland_session() {
         return Err(anyhow!("Wayland session not detected; UI requires Wayland"));
     }
+    gtk::init().context("initialize GTK")?;
+    unixnotis_ui::surface::init(config.general.layer_shell).context("layer-shell required")?;
 
     let theme_base = config_path
         .parent()
@@ -59,11 +69,17 @@ fn main() -> Result<()> {
         .ensure_theme_files(&theme_paths)
         .context("ensure theme files")?;
 
-    let app = gtk::Application::new(Some("com.unixnotis.Popups"), Default::default());
+    let app = gtk::Application::new(
+        Some(names.app_id("com.unixnotis.Popups").as_str()),
+        Default::default(),
+    );
+    if trace_guard.is_profiling() {
+        unixnotis_ui::profiling::quit_on_terminate(&app);
+    }
 
     app.connect_activate(move |app| {
         let (event_tx, event_rx) = async_channel::unbounded();
-        let command_tx = dbus::start_dbus_runtime(event_tx.clone());
+        let command_tx = dbus::start_dbus_runtime(names.clone(), event_tx.clone());
 
         let css_manager = css::CssManager::new_popup(theme_paths.clone(), config.theme.clone());
         css_manager.apply_to_display();
@@ -74,8 +90,10 @@ fn main() -> Result<()> {
             config.clone(),
             config_path.clone(),
             command_tx,
+            event_tx.clone(),
             css_manager,
         )));
+        ui.borrow().watch_outputs(event_tx.clone());
 
         let ui_clone = ui.clone();
         MainContext::default().spawn_local(async move {
@@ -90,9 +108,14 @@ fn main() -> Result<()> {
                 let _ = event_tx.try_send(dbus::UiEvent::CssReload);
             }
         });
-        css::start_config_watcher(config_path.clone(), move || {
-            let _ = event_tx.try_send(dbus::UiEvent::ConfigReload);
-        });
+        // The panel reports parse errors; the watcher has already logged them here.
+        css::start_config_watcher(
+            config_path.clone(),
+            move || {
+                let _ = event_tx.try_send(dbus::UiEvent::ConfigReload);
+            },
+            |_| {},
+        );
         info!("unixnotis-popups running");
     });
 
@@ -112,19 +135,6 @@ fn load_config(args: &Args) -> Result<(Config, PathBuf)> {
     Ok((config, path))
 }
 
-fn init_tracing(config: &Config) {
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        EnvFilter::new(
-            config
-                .general
-                .log_level
-                .clone()
-                .unwrap_or_else(|| "info".to_string()),
-        )
-    });
-    tracing_subscriber::fmt().with_env_filter(filter).init();
-}
-
 fn is_wayland_session() -> bool {
     if let Ok(session_type) = env::var("XDG_SESSION_TYPE") {
         if session_type.eq_ignore_ascii_case("wayland") {
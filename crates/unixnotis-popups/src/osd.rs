@@ -0,0 +1,100 @@
+//! Volume and brightness overlay (`[popups.osd]`, `noticenterctl osd`).
+//!
+//! One small surface is reused for every request: a new level updates the bar in place and
+//! restarts the hide timer, so holding a volume key shows a steady overlay rather than a
+//! stream of toasts.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::Align;
+use unixnotis_core::{OsdConfig, OsdKind};
+
+use super::ui_window::{apply_osd_config, build_osd_window};
+
+pub(super) struct OsdWindow {
+    window: gtk::ApplicationWindow,
+    icon: gtk::Image,
+    level: gtk::LevelBar,
+    value: gtk::Label,
+    hide_timer: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl OsdWindow {
+    pub(super) fn new(app: &gtk::Application) -> Self {
+        let window = build_osd_window(app);
+
+        let root = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+        root.add_css_class("unixnotis-osd");
+
+        let icon = gtk::Image::from_icon_name("audio-volume-medium-symbolic");
+        icon.add_css_class("unixnotis-osd-icon");
+        icon.set_pixel_size(24);
+
+        let level = gtk::LevelBar::for_interval(0.0, 100.0);
+        level.add_css_class("unixnotis-osd-level");
+        level.set_hexpand(true);
+        level.set_valign(Align::Center);
+        // The bar is one color at any level; GTK's low/high offsets would recolor it.
+        level.remove_offset_value(Some(gtk::LEVEL_BAR_OFFSET_LOW));
+        level.remove_offset_value(Some(gtk::LEVEL_BAR_OFFSET_HIGH));
+        level.remove_offset_value(Some(gtk::LEVEL_BAR_OFFSET_FULL));
+
+        let value = gtk::Label::new(None);
+        value.add_css_class("unixnotis-osd-value");
+        value.set_width_chars(4);
+        value.set_xalign(1.0);
+
+        root.append(&icon);
+        root.append(&level);
+        root.append(&value);
+        window.set_child(Some(&root));
+
+        Self {
+            window,
+            icon,
+            level,
+            value,
+            hide_timer: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    pub(super) fn apply_config(&self, osd: &OsdConfig, output: Option<&str>) {
+        apply_osd_config(&self.window, osd, output);
+    }
+
+    /// Show `percent` for `kind`, hiding the overlay `timeout_ms` after the latest call.
+    pub(super) fn show(&self, kind: OsdKind, percent: u32, timeout_ms: u64) {
+        let percent = percent.min(100);
+        self.icon.set_icon_name(Some(icon_name(kind, percent)));
+        self.level.set_value(f64::from(percent));
+        self.value.set_text(&format!("{percent}%"));
+        self.window.set_visible(true);
+
+        if let Some(timer) = self.hide_timer.borrow_mut().take() {
+            timer.remove();
+        }
+        let window = self.window.clone();
+        let slot = self.hide_timer.clone();
+        let timer = glib::timeout_add_local_once(Duration::from_millis(timeout_ms), move || {
+            slot.replace(None);
+            window.set_visible(false);
+        });
+        self.hide_timer.replace(Some(timer));
+    }
+}
+
+fn icon_name(kind: OsdKind, percent: u32) -> &'static str {
+    match kind {
+        OsdKind::Brightness => "display-brightness-symbolic",
+        OsdKind::Volume => match percent {
+            0 => "audio-volume-muted-symbolic",
+            1..=33 => "audio-volume-low-symbolic",
+            34..=66 => "audio-volume-medium-symbolic",
+            _ => "audio-volume-high-symbolic",
+        },
+    }
+}
@@ -2,115 +2,302 @@
 
 #[path = "icons/mod.rs"]
 mod icons;
+#[path = "osd.rs"]
+mod osd;
 #[path = "ui_window.rs"]
 mod ui_window;
 
-use std::collections::{HashMap, VecDeque};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
-use std::thread;
+use std::rc::Rc;
+use std::time::Instant;
 
 use gtk::prelude::*;
 use gtk::Align;
 use gtk::{gdk, glib};
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::debug;
-use unixnotis_core::{Config, NotificationView, Urgency};
+use unixnotis_core::i18n::{tr, tr_args};
+use unixnotis_core::markup;
+use unixnotis_core::popup_positions::PopupPositions;
+use unixnotis_core::ui_ipc::{ControlCommand, ControlEvent};
+use unixnotis_core::{
+    Anchor, Config, MemoryReport, NotificationView, PanelDebugLevel, PopupConfig, Urgency,
+};
 
-use crate::dbus::{UiCommand, UiEvent};
+use crate::dbus::UiEvent;
 use unixnotis_ui::css::{self, CssManager};
+use unixnotis_ui::debug_hud::DebugHud;
+use unixnotis_ui::decode_pool::{DecodePool, DecodePriority, DecodeToken};
+use unixnotis_ui::desktop_icons::DesktopIconIndex;
+use unixnotis_ui::hyprland;
+use unixnotis_ui::layout_preview::LayoutPreview;
+use unixnotis_ui::links;
+use unixnotis_ui::scaled_texture::ScaledTexture;
+use unixnotis_ui::surface;
 
 use icons::{
-    collect_icon_candidates, decode_icon_file, file_path_from_hint, image_data_texture,
-    resolve_icon_image, DesktopIconIndex, RasterIcon,
+    collect_icon_candidates, decode_icon_file, decode_preview, file_path_from_hint,
+    image_data_texture, preview_source, resolve_icon_image, RasterIcon,
+};
+use osd::OsdWindow;
+use ui_window::{
+    app_placement, apply_popup_config, build_app_window, build_popup_window, build_preview_window,
+    build_urgent_window, connect_output_entered, hud_placement, resolve_popups, urgent_placement,
 };
-use ui_window::{apply_popup_config, build_popup_window};
 
 /// Popup-only GTK state for notification toasts.
 pub struct UiState {
     config: Config,
     config_path: std::path::PathBuf,
     css: CssManager,
-    command_tx: UnboundedSender<UiCommand>,
+    command_tx: UnboundedSender<ControlCommand>,
+    event_tx: async_channel::Sender<UiEvent>,
     popup_window: gtk::ApplicationWindow,
     popup_stack: gtk::Box,
+    /// Count of popups not shown in full; opens the panel when clicked.
+    overflow_badge: gtk::Button,
+    // Separate surface for urgent popups with their own anchor (`[popups.urgent]`).
+    urgent_window: gtk::ApplicationWindow,
+    urgent_stack: gtk::Box,
+    // Surfaces for apps given their own anchor, built when the first such popup arrives.
+    app_surfaces: HashMap<Anchor, AppSurface>,
+    /// App anchors the daemon keeps for `noticenterctl popup-position`.
+    positions: PopupPositions,
+    debug_hud: DebugHud,
+    // Connector and model of the output the popups were last shown on.
+    entered_output: Vec<String>,
+    // Monitor focused when the current batch of popups opened, for `output = "focused"`.
+    focused_output: Option<String>,
     popups: HashMap<u32, PopupEntry>,
     popup_order: VecDeque<u32>,
+    // Apps whose popup group was expanded with its counter (`popups.group_by_app`).
+    expanded_groups: HashSet<String>,
     desktop_icons: DesktopIconIndex,
     icon_cache: HashMap<String, Option<String>>,
+    /// Desktop index generation `icon_cache` was filled against.
+    icon_cache_generation: u64,
+    decode_pool: DecodePool,
+    // Placeholder windows from `noticenterctl preview-layout`.
+    layout_preview: LayoutPreview,
+    osd: OsdWindow,
 }
 
 struct PopupEntry {
     revealer: gtk::Revealer,
     root: gtk::Box,
+    /// What the card shows, so it can be built again for a new scale factor.
+    notification: NotificationView,
+    /// Counter shown while this popup leads a collapsed app group.
+    group_badge: gtk::Button,
+    placement: Placement,
+    /// Daemon-assigned stacking slot; higher slots sit closer to the top.
+    slot: u32,
+    /// Set when the card shows a `value` hint, so level changes can skip the rebuild.
+    progress: Option<PopupProgress>,
+}
+
+/// Widgets a progress-only replacement rewrites in place.
+struct PopupProgress {
+    bar: gtk::ProgressBar,
+    summary: gtk::Label,
+    body: gtk::Label,
+    /// Everything else the card was built from; see `card_shape`.
+    shape: u64,
+}
+
+/// Surface of the apps moved to one anchor; it stacks and groups like the regular one.
+struct AppSurface {
+    window: gtk::ApplicationWindow,
+    stack: gtk::Box,
+    /// Popup ids on this surface in stacking order, as `popup_order` is for the regular stack.
+    order: VecDeque<u32>,
+}
+
+/// Surface a popup is shown on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    Stack,
+    /// The urgent surface, when `popups.urgent.anchor` sets urgent popups apart.
+    Urgent,
+    /// The surface of apps remembered at this anchor (`noticenterctl popup-position`).
+    App(Anchor),
 }
 
+/// Popups piled behind the ones shown in full on each surface.
+const STACK_DEPTH: usize = 3;
+
+/// Slot for popups restored from `list_active`, which carries none; they stack below new ones.
+const SEED_SLOT: u32 = 0;
+
 impl UiState {
     pub fn new(
         app: &gtk::Application,
         config: Config,
         config_path: std::path::PathBuf,
-        command_tx: UnboundedSender<UiCommand>,
+        command_tx: UnboundedSender<ControlCommand>,
+        event_tx: async_channel::Sender<UiEvent>,
         css: CssManager,
     ) -> Self {
-        let (popup_window, popup_stack) = build_popup_window(app, &config);
+        let (popup_window, popup_stack, overflow_badge, debug_hud) =
+            build_popup_window(app, &config);
+        overflow_badge.connect_clicked({
+            let command_tx = command_tx.clone();
+            move |_| {
+                let _ = command_tx.send(ControlCommand::OpenPanel);
+            }
+        });
+        let (urgent_window, urgent_stack) = build_urgent_window(app);
+        let decode_pool = DecodePool::new(config.icons.decode_workers);
+        let osd = OsdWindow::new(app);
+        osd.apply_config(&config.popups.osd, config.popups.output.as_deref());
 
         Self {
             config,
             config_path,
             css,
             command_tx,
+            event_tx,
             popup_window,
             popup_stack,
+            overflow_badge,
+            urgent_window,
+            urgent_stack,
+            app_surfaces: HashMap::new(),
+            positions: PopupPositions::default(),
+            debug_hud,
+            entered_output: Vec::new(),
+            focused_output: None,
             popups: HashMap::new(),
             popup_order: VecDeque::new(),
+            expanded_groups: HashSet::new(),
             desktop_icons: DesktopIconIndex::new(),
             icon_cache: HashMap::new(),
+            icon_cache_generation: 0,
+            decode_pool,
+            layout_preview: LayoutPreview::default(),
+            osd,
         }
     }
 
+    /// Forward output changes and hot-plug events so the popup surface can follow them.
+    pub fn watch_outputs(&self, sender: async_channel::Sender<UiEvent>) {
+        let entered_tx = sender.clone();
+        connect_output_entered(&self.popup_window, move |names| {
+            let _ = entered_tx.try_send(UiEvent::OutputEntered(names));
+        });
+        let closed_tx = sender.clone();
+        surface::keep_alive_on_close(&self.popup_window, move || {
+            let _ = closed_tx.try_send(UiEvent::SurfaceClosed);
+        });
+        let monitors_tx = sender.clone();
+        surface::connect_monitors_changed(move || {
+            let _ = monitors_tx.try_send(UiEvent::MonitorsChanged);
+        });
+        self.popup_window
+            .connect_scale_factor_notify(move |window| {
+                let _ = sender.try_send(UiEvent::ScaleChanged(window.scale_factor()));
+            });
+    }
+
     pub fn handle_event(&mut self, event: UiEvent) {
         match event {
-            UiEvent::Seed { state, active } => {
-                if state.dnd_enabled {
+            UiEvent::Seed {
+                state,
+                active,
+                positions,
+            } => {
+                self.positions = positions;
+                if !state.popups_enabled {
+                    debug!("popups are switched off; skipping seed");
+                } else if state.dnd_enabled {
                     for notification in active {
                         if notification.urgency == Urgency::Critical as u8 {
-                            self.add_popup(notification);
+                            self.add_popup(notification, SEED_SLOT);
                         }
                     }
                 } else {
                     for notification in active {
-                        self.add_popup(notification);
+                        self.add_popup(notification, SEED_SLOT);
                     }
                 }
             }
-            UiEvent::NotificationAdded(notification, show_popup) => {
-                if show_popup {
-                    debug!(
-                        id = notification.id,
-                        app = %notification.app_name,
-                        "popup added"
-                    );
-                    self.add_popup(notification);
-                }
-            }
-            UiEvent::NotificationUpdated(notification, show_popup) => {
+            UiEvent::PopupShown(notification, slot, received) => {
                 debug!(
                     id = notification.id,
                     app = %notification.app_name,
-                    "popup updated"
+                    slot,
+                    "popup shown"
                 );
-                self.replace_popup(notification, show_popup);
+                let id = notification.id;
+                self.show_popup(notification, slot);
+                if self.config.general.measure_latency {
+                    self.report_when_painted(id, received);
+                }
             }
-            UiEvent::NotificationClosed(id, _reason) => {
-                debug!(id, "popup closed");
-                self.remove_popup(id);
+            UiEvent::Control(event) => self.handle_control_event(event),
+            UiEvent::GroupToggled(app) => {
+                if !self.expanded_groups.remove(&app) {
+                    debug!(app = %app, "popup group expanded");
+                    self.expanded_groups.insert(app);
+                }
+                self.update_popup_visibility();
             }
-            UiEvent::StateChanged(state) => {
-                if state.dnd_enabled {
-                    debug!("clearing popups due to dnd");
-                    self.clear_popups();
+            UiEvent::Osd(kind, percent) => {
+                debug!(?kind, percent, "osd requested");
+                self.osd
+                    .show(kind, percent, self.config.popups.osd.timeout_ms);
+            }
+            UiEvent::AppAnchor(app, anchor) => {
+                // Popups already on screen stay put; the app's next one moves.
+                debug!(app = %app, ?anchor, "app popup position set");
+                self.positions.set(&app, anchor);
+            }
+            UiEvent::OutputEntered(names) => {
+                if names != self.entered_output {
+                    debug!(?names, "popups moved to another output");
+                    self.entered_output = names;
+                    self.apply_placement();
+                }
+            }
+            UiEvent::MonitorsChanged => {
+                debug!("monitors changed; re-anchoring popups");
+                self.apply_placement();
+                self.update_popup_visibility();
+            }
+            UiEvent::ScaleChanged(scale) => {
+                // Themed icons follow the scale on their own, but previews were decoded for the
+                // old one; build the cards again so they are not stretched.
+                debug!(scale, "popup scale factor changed");
+                self.apply_placement();
+                let shown: Vec<NotificationView> = self
+                    .popups
+                    .values()
+                    .map(|entry| entry.notification.clone())
+                    .collect();
+                for notification in shown {
+                    self.replace_card(notification);
+                }
+                self.update_popup_visibility();
+            }
+            UiEvent::SurfaceClosed => {
+                if surface::layer_shell_enabled() {
+                    // Usually the popups' output went away; map them again on a valid one.
+                    debug!("popup surface closed by compositor");
+                    self.apply_placement();
+                    self.update_popup_visibility();
+                } else {
+                    debug!("popup window closed");
                 }
             }
+            UiEvent::DebugLevel(level) => {
+                debug!(?level, "popup debug level updated");
+                self.debug_hud
+                    .set_enabled(level.allows(PanelDebugLevel::Info));
+            }
             UiEvent::CssReload => {
                 debug!("popup css reload requested");
                 self.css.reload(css::DEFAULT_CSS);
@@ -122,6 +309,51 @@ impl UiState {
         }
     }
 
+    fn handle_control_event(&mut self, event: ControlEvent) {
+        match event {
+            ControlEvent::NotificationUpdated(notification, show_popup) => {
+                // Updates that keep a popup arrive again as popup_shown with its slot.
+                if !show_popup {
+                    debug!(id = notification.id, "popup hidden by update");
+                    self.remove_popup(notification.id);
+                }
+            }
+            ControlEvent::NotificationClosed(id, _reason) => {
+                debug!(id, "popup closed");
+                self.remove_popup(id);
+            }
+            ControlEvent::StateChanged(state) => {
+                if state.dnd_enabled {
+                    debug!("clearing popups due to dnd");
+                    self.clear_popups();
+                } else if !state.popups_enabled {
+                    debug!("clearing popups; popups switched off");
+                    self.clear_popups();
+                }
+            }
+            ControlEvent::LayoutPreview(seconds) => {
+                debug!(seconds, "popup layout preview requested");
+                self.show_layout_preview(seconds);
+            }
+            ControlEvent::MemoryReportRequested => {
+                let icon_paths: usize = self
+                    .icon_cache
+                    .iter()
+                    .map(|(key, path)| key.len() + path.as_ref().map_or(0, String::len))
+                    .sum();
+                let caches = vec![("icon_path_cache".to_string(), icon_paths as u64)];
+                let report = MemoryReport::for_current_process("popups", caches);
+                let _ = self.command_tx.send(ControlCommand::ReportMemory(report));
+            }
+            ControlEvent::TrimCaches => {
+                debug!("popup cache trim requested");
+                self.icon_cache = HashMap::new();
+                self.popup_order.shrink_to_fit();
+                self.popups.shrink_to_fit();
+            }
+        }
+    }
+
     fn reload_config(&mut self) {
         let config = match Config::load_from_path(&self.config_path) {
             Ok(config) => config,
@@ -146,38 +378,335 @@ impl UiState {
         };
 
         self.config = config.clone();
+        // Groups are rebuilt from the new config, so they start out collapsed again.
+        self.expanded_groups.clear();
         debug!("popup config reloaded");
         self.css.update_theme(theme_paths, config.theme.clone());
         self.css.reload(css::DEFAULT_CSS);
-        apply_popup_config(&self.popup_window, &self.popup_stack, &config);
+        self.apply_placement();
+        self.update_popup_visibility();
     }
 
-    fn add_popup(&mut self, notification: NotificationView) {
-        let id = notification.id;
-        if self.popups.contains_key(&id) {
+    fn apply_placement(&self) {
+        let popups = self.resolve_placement();
+        apply_popup_config(&self.popup_window, &self.popup_stack, &popups);
+        if let Some(urgent) = urgent_placement(&popups) {
+            apply_popup_config(&self.urgent_window, &self.urgent_stack, &urgent);
+        }
+        for (anchor, surface) in &self.app_surfaces {
+            apply_popup_config(
+                &surface.window,
+                &surface.stack,
+                &app_placement(&popups, *anchor),
+            );
+        }
+        self.debug_hud.set_placement(hud_placement(&popups));
+        self.osd.apply_config(&popups.osd, popups.output.as_deref());
+    }
+
+    fn resolve_placement(&self) -> PopupConfig {
+        resolve_popups(
+            &self.config,
+            &self.entered_output,
+            self.focused_output.as_deref(),
+        )
+    }
+
+    /// Move a hidden popup surface to the focused monitor before the next popup opens.
+    ///
+    /// Popups already on screen stay where they are, so the stack never jumps between
+    /// monitors while it is being read.
+    fn follow_focus(&mut self) {
+        if !self.config.popups.follows_focus()
+            || self.popup_window.is_visible()
+            || !hyprland::is_available()
+        {
             return;
         }
+        let focused = match hyprland::focused_monitor() {
+            Ok(focused) => focused,
+            Err(err) => {
+                tracing::warn!(
+                    ?err,
+                    "failed to query focused monitor; leaving output to the compositor"
+                );
+                None
+            }
+        };
+        if focused != self.focused_output {
+            debug!(?focused, "popups following focused monitor");
+            self.focused_output = focused;
+            self.apply_placement();
+        }
+    }
 
-        let entry = self.build_popup_entry(&notification);
-        self.popup_stack.prepend(&entry.revealer);
-        self.popups.insert(id, entry);
-        self.popup_order.push_front(id);
-        self.update_popup_visibility();
-        debug!(id, total = self.popup_order.len(), "popup inserted");
+    fn show_layout_preview(&self, seconds: u32) {
+        let Some(app) = self.popup_window.application() else {
+            return;
+        };
+        let popups = self.resolve_placement();
+        let mut windows = vec![build_preview_window(&app, &popups, |slot| {
+            tr_args("preview-popup", &[("slot", slot.into())])
+        })];
+        if let Some(urgent) = urgent_placement(&popups) {
+            windows.push(build_preview_window(&app, &urgent, |slot| {
+                tr_args("preview-urgent", &[("slot", slot.into())])
+            }));
+        }
+        // One outline per anchor apps were moved to, named after the apps that use it.
+        let mut app_anchors: Vec<(Anchor, Vec<&str>)> = Vec::new();
+        for (app_name, anchor) in self.positions.iter() {
+            if anchor == popups.anchor {
+                continue;
+            }
+            match app_anchors.iter_mut().find(|(known, _)| *known == anchor) {
+                Some((_, names)) => names.push(app_name),
+                None => app_anchors.push((anchor, vec![app_name])),
+            }
+        }
+        for (anchor, names) in app_anchors {
+            let apps = names.join(", ");
+            windows.push(build_preview_window(
+                &app,
+                &app_placement(&popups, anchor),
+                |slot| {
+                    tr_args(
+                        "preview-app",
+                        &[("apps", apps.as_str().into()), ("slot", slot.into())],
+                    )
+                },
+            ));
+        }
+        self.layout_preview.show(windows, seconds);
     }
 
-    fn replace_popup(&mut self, notification: NotificationView, show_popup: bool) {
+    /// Surface for `notification`: urgent popups set apart win over an app's own anchor.
+    fn placement_for(&self, notification: &NotificationView) -> Placement {
+        let popups = &self.config.popups;
+        if notification.urgency == Urgency::Critical as u8
+            && popups.urgent.enabled
+            && popups.urgent.anchor.is_some()
+        {
+            return Placement::Urgent;
+        }
+        match self.positions.anchor_for(&notification.app_name) {
+            Some(anchor) if anchor != popups.anchor => Placement::App(anchor),
+            _ => Placement::Stack,
+        }
+    }
+
+    /// Build the surface for apps at `anchor` on first use; false when it cannot be built.
+    fn ensure_app_surface(&mut self, anchor: Anchor) -> bool {
+        if self.app_surfaces.contains_key(&anchor) {
+            return true;
+        }
+        let Some(app) = self.popup_window.application() else {
+            return false;
+        };
+        let (window, stack) = build_app_window(&app, anchor);
+        apply_popup_config(
+            &window,
+            &stack,
+            &app_placement(&self.resolve_placement(), anchor),
+        );
+        self.app_surfaces.insert(
+            anchor,
+            AppSurface {
+                window,
+                stack,
+                order: VecDeque::new(),
+            },
+        );
+        true
+    }
+
+    /// Stack widget of the surface for `placement`.
+    fn stack_for(&self, placement: Placement) -> &gtk::Box {
+        match placement {
+            Placement::Stack => &self.popup_stack,
+            Placement::Urgent => &self.urgent_stack,
+            Placement::App(anchor) => self
+                .app_surfaces
+                .get(&anchor)
+                .map_or(&self.popup_stack, |surface| &surface.stack),
+        }
+    }
+
+    /// Stacking order of the surface for `placement`; urgent popups keep none.
+    fn order_for(&self, placement: Placement) -> Option<&VecDeque<u32>> {
+        match placement {
+            Placement::Stack => Some(&self.popup_order),
+            Placement::Urgent => None,
+            Placement::App(anchor) => self.app_surfaces.get(&anchor).map(|surface| &surface.order),
+        }
+    }
+
+    fn order_for_mut(&mut self, placement: Placement) -> Option<&mut VecDeque<u32>> {
+        match placement {
+            Placement::Stack => Some(&mut self.popup_order),
+            Placement::Urgent => None,
+            Placement::App(anchor) => self
+                .app_surfaces
+                .get_mut(&anchor)
+                .map(|surface| &mut surface.order),
+        }
+    }
+
+    fn show_popup(&mut self, notification: NotificationView, slot: u32) {
         let id = notification.id;
-        self.remove_popup(id);
-        if show_popup {
-            self.add_popup(notification);
+        let placement = self.placement_for(&notification);
+        match self
+            .popups
+            .get(&id)
+            .map(|entry| (entry.slot, entry.placement))
+        {
+            // Same slot: swap the card inside the existing revealer, with no slide out and in.
+            Some(current) if current == (slot, placement) => {
+                if self.update_progress(&notification) {
+                    debug!(
+                        id,
+                        progress = notification.progress,
+                        "popup progress updated"
+                    );
+                    if let Some(entry) = self.popups.get_mut(&id) {
+                        entry.notification = notification;
+                    }
+                    return;
+                }
+                self.replace_card(notification);
+                self.update_popup_visibility();
+                debug!(id, slot, "popup updated in place");
+            }
+            Some(_) => {
+                self.remove_popup(id);
+                self.add_popup(notification, slot);
+            }
+            None => self.add_popup(notification, slot),
         }
     }
 
+    /// Swap the card of a popup on screen for one built from `notification`.
+    fn replace_card(&mut self, notification: NotificationView) {
+        let (card, group_badge, progress) = self.build_popup_card(&notification);
+        if let Some(entry) = self.popups.get_mut(&notification.id) {
+            entry.revealer.set_child(Some(&card));
+            entry.root = card;
+            entry.group_badge = group_badge;
+            entry.progress = progress;
+            entry.notification = notification;
+        }
+    }
+
+    /// Tell the daemon once the popup's next frame has been painted.
+    ///
+    /// A tick callback only runs while the card is mapped, so popups hidden in a collapsed
+    /// group never report and the daemon ages them out.
+    fn report_when_painted(&self, id: u32, received: Instant) {
+        let Some(entry) = self.popups.get(&id) else {
+            return;
+        };
+        let tx = self.command_tx.clone();
+        entry.root.add_tick_callback(move |_, frame_clock| {
+            let handler = Rc::new(RefCell::new(None));
+            let handler_slot = handler.clone();
+            let tx = tx.clone();
+            let handler_id = frame_clock.connect_after_paint(move |frame_clock| {
+                let render_us = u64::try_from(received.elapsed().as_micros()).unwrap_or(u64::MAX);
+                let _ = tx.send(ControlCommand::PopupRendered { id, render_us });
+                if let Some(handler) = handler_slot.borrow_mut().take() {
+                    frame_clock.disconnect(handler);
+                }
+            });
+            handler.replace(Some(handler_id));
+            glib::ControlFlow::Break
+        });
+    }
+
+    /// Rewrite the bar and text of a card whose replacement only moved its level.
+    ///
+    /// Volume and brightness keys resend many times a second; rebuilding the card each
+    /// time would restart the urgent pulse and reload icons and previews.
+    fn update_progress(&self, notification: &NotificationView) -> bool {
+        let Some(progress) = self
+            .popups
+            .get(&notification.id)
+            .and_then(|entry| entry.progress.as_ref())
+        else {
+            return false;
+        };
+        let Some(fraction) = notification.progress_fraction() else {
+            return false;
+        };
+        if progress.shape != card_shape(notification) {
+            return false;
+        }
+        progress.summary.set_text(&notification.summary);
+        set_label_markup(&progress.body, &notification.body);
+        progress.bar.set_fraction(fraction);
+        true
+    }
+
+    fn add_popup(&mut self, notification: NotificationView, slot: u32) {
+        let id = notification.id;
+        if self.popups.contains_key(&id) {
+            return;
+        }
+
+        self.follow_focus();
+        let mut entry = self.build_popup_entry(&notification, slot);
+        if let Placement::App(anchor) = entry.placement {
+            if !self.ensure_app_surface(anchor) {
+                entry.placement = Placement::Stack;
+            }
+        }
+        let placement = entry.placement;
+        let Some(order) = self.order_for(placement) else {
+            // Urgent popups skip stacking and grouping; every one of them stays in view.
+            self.urgent_stack.prepend(&entry.revealer);
+            self.popups.insert(id, entry);
+            self.update_popup_visibility();
+            debug!(id, "urgent popup inserted");
+            return;
+        };
+        // Newest first: go below every popup with an equal or higher slot.
+        let index = order
+            .iter()
+            .position(|other| {
+                self.popups
+                    .get(other)
+                    .is_some_and(|other| other.slot < slot)
+            })
+            .unwrap_or(order.len());
+        let above = index
+            .checked_sub(1)
+            .and_then(|above| order.get(above))
+            .and_then(|above| self.popups.get(above))
+            .map(|above| above.revealer.clone());
+        let total = order.len() + 1;
+        let stack = self.stack_for(placement);
+        match above {
+            Some(above) => stack.insert_child_after(&entry.revealer, Some(&above)),
+            None => stack.prepend(&entry.revealer),
+        }
+        self.popups.insert(id, entry);
+        if let Some(order) = self.order_for_mut(placement) {
+            order.insert(index, id);
+        }
+        self.update_popup_visibility();
+        debug!(id, slot, total, "popup inserted");
+    }
+
     fn remove_popup(&mut self, id: u32) {
         if let Some(entry) = self.popups.remove(&id) {
+            if !self
+                .popups
+                .values()
+                .any(|other| other.notification.app_name == entry.notification.app_name)
+            {
+                self.expanded_groups.remove(&entry.notification.app_name);
+            }
             entry.revealer.set_reveal_child(false);
-            let stack = self.popup_stack.clone();
+            let stack = self.stack_for(entry.placement).clone();
             entry
                 .revealer
                 .connect_notify_local(Some("child-revealed"), move |revealer, _| {
@@ -185,14 +714,26 @@ impl UiState {
                         stack.remove(revealer);
                     }
                 });
+            if let Some(order) = self.order_for_mut(entry.placement) {
+                order.retain(|item| *item != id);
+            }
         }
-        self.popup_order.retain(|item| *item != id);
         self.update_popup_visibility();
-        debug!(id, total = self.popup_order.len(), "popup removed");
+        debug!(id, total = self.popups.len(), "popup removed");
     }
 
     fn clear_popups(&mut self) {
-        let ids: Vec<u32> = self.popup_order.iter().copied().collect();
+        // Urgent popups on their own surface stay up, like critical ones under Do Not Disturb.
+        let ids: Vec<u32> = self
+            .popup_order
+            .iter()
+            .chain(
+                self.app_surfaces
+                    .values()
+                    .flat_map(|surface| &surface.order),
+            )
+            .copied()
+            .collect();
         for id in ids {
             self.remove_popup(id);
         }
@@ -200,7 +741,6 @@ impl UiState {
 
     fn update_popup_visibility(&self) {
         let max_visible = self.config.popups.max_visible;
-        let stack_depth = 3; // Increased depth for better visual pile
 
         if max_visible == 0 {
             for entry in self.popups.values() {
@@ -208,22 +748,49 @@ impl UiState {
                 entry.revealer.set_reveal_child(false);
             }
             self.popup_window.set_visible(false);
+            self.urgent_window.set_visible(false);
+            for surface in self.app_surfaces.values() {
+                surface.window.set_visible(false);
+            }
             debug!("popups disabled by max_visible = 0");
             return;
         }
 
-        if self.popup_order.is_empty() {
-            self.popup_window.set_visible(false);
-        } else {
-            self.popup_window.set_visible(true);
+        self.urgent_window.set_visible(
+            self.popups
+                .values()
+                .any(|entry| entry.placement == Placement::Urgent),
+        );
+        self.popup_window.set_visible(!self.popup_order.is_empty());
+
+        let groups = self.popup_groups(&self.popup_order);
+        self.arrange_groups(&self.popup_stack, &groups);
+        let order = display_order(&groups, &self.expanded_groups);
+        self.lay_out(&order);
+        self.update_overflow_badge(order.len().min(max_visible));
+        // App surfaces pile and group the same way; the overflow counter stays on the stack.
+        for surface in self.app_surfaces.values() {
+            surface.window.set_visible(!surface.order.is_empty());
+            let groups = self.popup_groups(&surface.order);
+            self.arrange_groups(&surface.stack, &groups);
+            self.lay_out(&display_order(&groups, &self.expanded_groups));
         }
+        debug!(
+            visible = self.popup_order.len().min(max_visible + STACK_DEPTH),
+            total = self.popup_order.len(),
+            "popup visibility updated"
+        );
+    }
 
-        for (index, id) in self.popup_order.iter().enumerate() {
+    /// Show the first `max_visible` of `order` in full and pile a few more behind them.
+    fn lay_out(&self, order: &[u32]) {
+        let max_visible = self.config.popups.max_visible;
+        for (index, id) in order.iter().enumerate() {
             if let Some(entry) = self.popups.get(id) {
                 // Clean up previous state classes
                 entry.root.remove_css_class("unixnotis-popup-visible");
                 entry.root.remove_css_class("unixnotis-popup-stacked");
-                for i in 0..stack_depth {
+                for i in 0..STACK_DEPTH {
                     entry
                         .root
                         .remove_css_class(&format!("unixnotis-popup-stacked-{}", i));
@@ -234,7 +801,7 @@ impl UiState {
                     entry.root.set_visible(true);
                     entry.revealer.set_reveal_child(true);
                     entry.root.add_css_class("unixnotis-popup-visible");
-                } else if index < max_visible + stack_depth {
+                } else if index < max_visible + STACK_DEPTH {
                     // Stacked (pile) notification
                     let stack_idx = index - max_visible;
                     entry.root.set_visible(true);
@@ -250,23 +817,105 @@ impl UiState {
                 }
             }
         }
-        debug!(
-            visible = self.popup_order.len().min(max_visible + stack_depth),
-            total = self.popup_order.len(),
-            "popup visibility updated"
-        );
     }
 
-    fn build_popup_entry(&mut self, notification: &NotificationView) -> PopupEntry {
+    /// Show how many popups are piled up, collapsed, or hidden past the `shown` full cards.
+    fn update_overflow_badge(&self, shown: usize) {
+        let hidden = self.popup_order.len().saturating_sub(shown);
+        self.overflow_badge.set_visible(hidden > 0);
+        if hidden > 0 {
+            self.overflow_badge
+                .set_label(&tr_args("popup-overflow", &[("count", hidden.into())]));
+        }
+    }
+
+    /// Popups of one surface grouped by app, each group led by its newest popup.
+    ///
+    /// Without `group_by_app` every popup is a group of its own.
+    fn popup_groups<'a>(&'a self, popup_order: &VecDeque<u32>) -> Vec<(&'a str, Vec<u32>)> {
+        let mut groups: Vec<(&str, Vec<u32>)> = Vec::new();
+        for id in popup_order {
+            let Some(entry) = self.popups.get(id) else {
+                continue;
+            };
+            let app = entry.notification.app_name.as_str();
+            match groups.iter_mut().find(|(other, _)| *other == app) {
+                Some((_, members)) if self.config.popups.group_by_app => members.push(*id),
+                _ => groups.push((app, vec![*id])),
+            }
+        }
+        groups
+    }
+
+    /// Put each group's members right below its leader, label the leaders' counters, and hide
+    /// the members of collapsed groups.
+    fn arrange_groups(&self, stack: &gtk::Box, groups: &[(&str, Vec<u32>)]) {
+        let mut previous: Option<gtk::Revealer> = None;
+        for (app, members) in groups {
+            let expanded = self.expanded_groups.contains(*app);
+            for (position, id) in members.iter().enumerate() {
+                let Some(entry) = self.popups.get(id) else {
+                    continue;
+                };
+                // Members sit right below their leader so an expanded group reads as one stack.
+                stack.reorder_child_after(&entry.revealer, previous.as_ref());
+                previous = Some(entry.revealer.clone());
+
+                if position == 0 && members.len() > 1 {
+                    let label = if expanded {
+                        tr("popup-group-collapse")
+                    } else {
+                        tr_args("popup-group-more", &[("count", (members.len() - 1).into())])
+                    };
+                    entry.group_badge.set_label(&label);
+                    entry.group_badge.set_visible(true);
+                } else {
+                    entry.group_badge.set_visible(false);
+                }
+
+                if position > 0 && !expanded {
+                    entry.root.set_visible(false);
+                    entry.revealer.set_reveal_child(false);
+                }
+            }
+        }
+    }
+
+    fn build_popup_entry(&mut self, notification: &NotificationView, slot: u32) -> PopupEntry {
         let revealer = gtk::Revealer::new();
         revealer.add_css_class("unixnotis-popup-revealer");
         revealer.set_transition_type(gtk::RevealerTransitionType::SlideDown);
         revealer.set_transition_duration(200);
 
+        let (root, group_badge, progress) = self.build_popup_card(notification);
+        revealer.set_child(Some(&root));
+        revealer.set_reveal_child(true);
+
+        PopupEntry {
+            revealer,
+            root,
+            notification: notification.clone(),
+            group_badge,
+            placement: self.placement_for(notification),
+            slot,
+            progress,
+        }
+    }
+
+    fn build_popup_card(
+        &mut self,
+        notification: &NotificationView,
+    ) -> (gtk::Box, gtk::Button, Option<PopupProgress>) {
         let root = gtk::Box::new(gtk::Orientation::Vertical, 6);
         root.add_css_class("unixnotis-popup-card");
         if notification.urgency == Urgency::Critical as u8 {
             root.add_css_class("critical");
+            if self.config.popups.urgent.enabled {
+                root.add_css_class("unixnotis-popup-urgent");
+                if self.config.popups.urgent.pulse {
+                    root.add_css_class("unixnotis-popup-pulse");
+                }
+            }
         }
 
         let header = gtk::Box::new(gtk::Orientation::Horizontal, 6);
@@ -285,8 +934,20 @@ impl UiState {
         close.add_css_class("unixnotis-popup-close");
         close.set_halign(Align::End);
 
+        // Hidden until this popup leads a group; see arrange_groups.
+        let group_badge = gtk::Button::new();
+        group_badge.add_css_class("unixnotis-popup-group-badge");
+        group_badge.set_valign(Align::Center);
+        group_badge.set_visible(false);
+        let event_tx = self.event_tx.clone();
+        let app_name = notification.app_name.clone();
+        group_badge.connect_clicked(move |_| {
+            let _ = event_tx.try_send(UiEvent::GroupToggled(app_name.clone()));
+        });
+
         header.append(&app);
         header.append(&gtk::Box::new(gtk::Orientation::Horizontal, 1));
+        header.append(&group_badge);
         header.append(&close);
 
         let summary = gtk::Label::new(Some(&notification.summary));
@@ -298,11 +959,29 @@ impl UiState {
         body.set_xalign(0.0);
         body.set_wrap(true);
         body.add_css_class("unixnotis-popup-body");
+        links::open_with_default_app(&body);
         set_label_markup(&body, &notification.body);
 
         root.append(&header);
         root.append(&summary);
         root.append(&body);
+        let progress = notification.progress_fraction().map(|fraction| {
+            let bar = gtk::ProgressBar::new();
+            bar.add_css_class("unixnotis-popup-progress");
+            bar.set_fraction(fraction);
+            root.append(&bar);
+            PopupProgress {
+                bar,
+                summary: summary.clone(),
+                body: body.clone(),
+                shape: card_shape(notification),
+            }
+        });
+        if self.config.icons.image_preview {
+            if let Some(preview) = self.spawn_preview(notification) {
+                root.append(&preview);
+            }
+        }
 
         if !notification.actions.is_empty() {
             let actions = gtk::Box::new(gtk::Orientation::Horizontal, 6);
@@ -314,7 +993,7 @@ impl UiState {
                 let tx = self.command_tx.clone();
                 let id = notification.id;
                 button.connect_clicked(move |_| {
-                    let _ = tx.send(UiCommand::InvokeAction {
+                    let _ = tx.send(ControlCommand::InvokeAction {
                         id,
                         action_key: action_key.clone(),
                     });
@@ -325,9 +1004,23 @@ impl UiState {
         }
 
         let id = notification.id;
+        // Timeouts live in the daemon; popups only ask it to pause while hovered.
+        if self.config.popups.pause_on_hover {
+            let motion = gtk::EventControllerMotion::new();
+            let tx = self.command_tx.clone();
+            motion.connect_enter(move |_, _, _| {
+                let _ = tx.send(ControlCommand::HoldExpiration { id, hold: true });
+            });
+            let tx = self.command_tx.clone();
+            motion.connect_leave(move |_| {
+                let _ = tx.send(ControlCommand::HoldExpiration { id, hold: false });
+            });
+            root.add_controller(motion);
+        }
+
         let command_tx_close = self.command_tx.clone();
         close.connect_clicked(move |_| {
-            let _ = command_tx_close.send(UiCommand::Dismiss(id));
+            let _ = command_tx_close.send(ControlCommand::Dismiss(id));
         });
 
         let default_action = notification
@@ -339,7 +1032,7 @@ impl UiState {
             let gesture = gtk::GestureClick::new();
             let tx = self.command_tx.clone();
             gesture.connect_released(move |_, _, _, _| {
-                let _ = tx.send(UiCommand::InvokeAction {
+                let _ = tx.send(ControlCommand::InvokeAction {
                     id,
                     action_key: action_key.clone(),
                 });
@@ -347,10 +1040,7 @@ impl UiState {
             root.add_controller(gesture);
         }
 
-        revealer.set_child(Some(&root));
-        revealer.set_reveal_child(true);
-
-        PopupEntry { revealer, root }
+        (root, group_badge, progress)
     }
 
     fn build_image_widget(&mut self, notification: &NotificationView) -> Option<gtk::Image> {
@@ -372,6 +1062,12 @@ impl UiState {
             return resolve_icon_image(path, 20);
         }
 
+        // A newly installed app may resolve names that were cached as misses.
+        let generation = self.desktop_icons.generation();
+        if generation != self.icon_cache_generation {
+            self.icon_cache.clear();
+            self.icon_cache_generation = generation;
+        }
         let cache_key = format!("{}|{}", notification.app_name, notification.image.icon_name);
         if let Some(cached) = self.icon_cache.get(&cache_key) {
             return cached
@@ -435,14 +1131,107 @@ impl UiState {
             }
         });
 
-        thread::spawn(move || {
-            // Decode on a background thread to keep popup animations smooth.
+        self.decode_pool.submit(DecodePriority::Popup, move || {
+            // Decode on a pool thread to keep popup animations smooth.
             let result = decode_icon_file(&path);
             let _ = tx.send_blocking(result);
         });
 
         widget
     }
+
+    /// Picture for a large notification image, filled in once the pool has scaled it down.
+    fn spawn_preview(&self, notification: &NotificationView) -> Option<gtk::Picture> {
+        let source = preview_source(&notification.image)?;
+        let picture = gtk::Picture::new();
+        picture.set_can_shrink(true);
+        picture.set_halign(Align::Start);
+        picture.set_overflow(gtk::Overflow::Hidden);
+        picture.add_css_class("unixnotis-popup-preview");
+        picture.set_visible(false);
+
+        // Decode at the surface scale so previews stay sharp on HiDPI outputs.
+        let scale = self.popup_window.scale_factor().max(1);
+        let max_height = self.config.icons.preview_max_height.max(1) as u32 * scale as u32;
+        // A card replaced or dismissed before its turn on the pool skips the decode.
+        let token = DecodeToken::default();
+        let cancel = token.clone();
+        picture.connect_destroy(move |_| cancel.cancel());
+        let (tx, rx) = async_channel::bounded::<Result<RasterIcon, String>>(1);
+        let picture_weak = picture.downgrade();
+        glib::MainContext::default().spawn_local(async move {
+            let result = rx.recv().await;
+            let Some(picture) = picture_weak.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(Ok(preview)) => {
+                    let bytes = glib::Bytes::from(&preview.bytes);
+                    let texture = gdk::MemoryTexture::new(
+                        preview.width,
+                        preview.height,
+                        gdk::MemoryFormat::R8g8b8a8,
+                        &bytes,
+                        preview.stride as usize,
+                    );
+                    let paintable = ScaledTexture::new(texture.upcast(), scale);
+                    picture.set_paintable(Some(&paintable));
+                    picture.set_visible(true);
+                }
+                Ok(Err(err)) => debug!(?err, "popup preview decode failed"),
+                Err(_) => {}
+            }
+        });
+
+        self.decode_pool.submit(DecodePriority::Popup, move || {
+            if !token.claim() {
+                return;
+            }
+            let _ = tx.send_blocking(decode_preview(source, max_height * 2, max_height));
+        });
+
+        Some(picture)
+    }
+}
+
+/// Popups in the order they are laid out: every group's leader, followed by the rest of the
+/// group while it is expanded.
+fn display_order(groups: &[(&str, Vec<u32>)], expanded: &HashSet<String>) -> Vec<u32> {
+    groups
+        .iter()
+        .flat_map(|(app, members)| {
+            let shown = if expanded.contains(*app) {
+                members.len()
+            } else {
+                1
+            };
+            members.iter().take(shown).copied()
+        })
+        .collect()
+}
+
+/// Hash of the card inputs a progress update leaves alone: app, urgency, actions, and image.
+fn card_shape(notification: &NotificationView) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    notification.app_name.hash(&mut hasher);
+    notification.urgency.hash(&mut hasher);
+    for action in &notification.actions {
+        action.key.hash(&mut hasher);
+        action.label.hash(&mut hasher);
+    }
+    let image = &notification.image;
+    image.image_path.hash(&mut hasher);
+    image.icon_name.hash(&mut hasher);
+    // Pixel data can run to megabytes; its size and both ends stand in for it, so a new
+    // image of the same size (a track change, say) still rebuilds the card.
+    let data = &image.image_data.data;
+    let sample = 64.min(data.len());
+    data.len().hash(&mut hasher);
+    image.image_data.width.hash(&mut hasher);
+    image.image_data.height.hash(&mut hasher);
+    data[..sample].hash(&mut hasher);
+    data[data.len() - sample..].hash(&mut hasher);
+    hasher.finish()
 }
 
 fn set_label_markup(label: &gtk::Label, body: &str) {
@@ -450,5 +1239,5 @@ fn set_label_markup(label: &gtk::Label, body: &str) {
         label.set_text("");
         return;
     }
-    label.set_markup(body);
+    label.set_markup(&markup::sanitize_body(body));
 }
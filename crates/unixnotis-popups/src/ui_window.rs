@@ -5,59 +5,272 @@
 use gtk::glib::translate::ToGlibPtr;
 use gtk::prelude::*;
 use gtk::{cairo, gdk};
-use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
-use unixnotis_core::{Anchor, Config, Margins};
+use gtk4_layer_shell::{Edge, KeyboardMode, LayerShell};
+use unixnotis_core::i18n::tr;
+use unixnotis_core::{Anchor, Config, ExclusiveZone, Margins, OsdConfig, PopupConfig};
+use unixnotis_ui::debug_hud::{DebugHud, HudPlacement};
+use unixnotis_ui::surface;
 
 pub(super) fn build_popup_window(
     app: &gtk::Application,
     config: &Config,
-) -> (gtk::ApplicationWindow, gtk::Box) {
+) -> (gtk::ApplicationWindow, gtk::Box, gtk::Button, DebugHud) {
     let window = gtk::ApplicationWindow::new(app);
     window.set_decorated(false);
     window.set_resizable(false);
     window.set_title(Some("UnixNotis Popups"));
     window.add_css_class("unixnotis-popup-window");
 
-    window.init_layer_shell();
-    window.set_namespace(Some("unixnotis-popups"));
-    window.set_layer(Layer::Overlay);
+    surface::init_layer_surface(&window, "unixnotis-popups");
 
-    let stack = gtk::Box::new(gtk::Orientation::Vertical, config.popups.spacing);
+    let popups = resolve_popups(config, &[], None);
+    let stack = gtk::Box::new(gtk::Orientation::Vertical, popups.spacing);
     stack.add_css_class("unixnotis-popup-stack");
-    window.set_child(Some(&stack));
+    // "+N more" under the stack for popups beyond the ones shown in full.
+    let overflow = gtk::Button::new();
+    overflow.add_css_class("unixnotis-popup-overflow");
+    overflow.set_halign(gtk::Align::Center);
+    overflow.set_tooltip_text(Some(&tr("popup-overflow-tooltip")));
+    overflow.set_visible(false);
+    let column = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    column.append(&stack);
+    column.append(&overflow);
+    // Overlay children are not measured, so the HUD never changes the surface it reports on.
+    let debug_hud = DebugHud::new();
+    debug_hud.attach(&window);
+    let overlay = gtk::Overlay::new();
+    overlay.set_child(Some(&column));
+    overlay.add_overlay(debug_hud.widget());
+    window.set_child(Some(&overlay));
     window.set_visible(false);
-    apply_popup_config(&window, &stack, config);
+    apply_popup_config(&window, &stack, &popups);
+    debug_hud.set_placement(hud_placement(&popups));
     window.connect_realize({
-        let allow_click_through = config.popups.allow_click_through;
+        let allow_click_through = popups.allow_click_through;
         move |window| {
             apply_input_region(window, allow_click_through);
         }
     });
 
-    (window, stack)
+    (window, stack, overflow, debug_hud)
 }
 
 pub(super) fn apply_popup_config(
     window: &gtk::ApplicationWindow,
     stack: &gtk::Box,
+    popups: &PopupConfig,
+) {
+    window.set_default_size(popups.width, 1);
+    window.set_size_request(popups.width, -1);
+    stack.set_spacing(popups.spacing);
+
+    if surface::layer_shell_enabled() {
+        apply_anchor(window, popups.anchor, popups.margin);
+        surface::apply_exclusive_zone(window, popups.exclusive_zone);
+        window.set_keyboard_mode(KeyboardMode::None);
+
+        // A configured output that is not connected falls back to the compositor's choice
+        // instead of pinning the surface to a monitor that may have been unplugged.
+        let monitor = popups.output.as_deref().and_then(find_monitor);
+        window.set_monitor(monitor.as_ref());
+    }
+    apply_input_region(window, popups.allow_click_through);
+}
+
+/// Surface for urgent popups that `popups.urgent.anchor` places away from the regular stack.
+pub(super) fn build_urgent_window(app: &gtk::Application) -> (gtk::ApplicationWindow, gtk::Box) {
+    let (window, stack) =
+        build_set_apart_window(app, "UnixNotis Urgent Popups", "unixnotis-urgent");
+    window.add_css_class("unixnotis-urgent-window");
+    (window, stack)
+}
+
+/// Surface for the popups of apps moved to `anchor` with `noticenterctl popup-position`.
+pub(super) fn build_app_window(
+    app: &gtk::Application,
+    anchor: Anchor,
+) -> (gtk::ApplicationWindow, gtk::Box) {
+    let title = format!("UnixNotis Popups ({})", anchor.name());
+    let (window, stack) = build_set_apart_window(app, &title, "unixnotis-app-popups");
+    window.add_css_class("unixnotis-app-popup-window");
+    (window, stack)
+}
+
+fn build_set_apart_window(
+    app: &gtk::Application,
+    title: &str,
+    namespace: &str,
+) -> (gtk::ApplicationWindow, gtk::Box) {
+    let window = gtk::ApplicationWindow::new(app);
+    window.set_decorated(false);
+    window.set_resizable(false);
+    window.set_title(Some(title));
+    window.add_css_class("unixnotis-popup-window");
+
+    surface::init_layer_surface(&window, namespace);
+
+    let stack = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    stack.add_css_class("unixnotis-popup-stack");
+    window.set_child(Some(&stack));
+    window.set_visible(false);
+    (window, stack)
+}
+
+/// Popup settings for the urgent surface, or `None` when urgent popups share the stack.
+pub(super) fn urgent_placement(popups: &PopupConfig) -> Option<PopupConfig> {
+    if !popups.urgent.enabled {
+        return None;
+    }
+    let anchor = popups.urgent.anchor?;
+    Some(PopupConfig {
+        anchor,
+        // A surface that comes and goes with alerts should not shove windows around.
+        exclusive_zone: ExclusiveZone::None,
+        ..popups.clone()
+    })
+}
+
+/// Popup settings for an app's own surface at `anchor`.
+pub(super) fn app_placement(popups: &PopupConfig, anchor: Anchor) -> PopupConfig {
+    PopupConfig {
+        anchor,
+        exclusive_zone: ExclusiveZone::None,
+        ..popups.clone()
+    }
+}
+
+/// Popup settings with the `[outputs]` block for the popups' output applied.
+///
+/// A configured `output` decides the block; otherwise `entered` names the output the
+/// compositor last showed the popups on, which is empty until they are first mapped.
+/// With `output = "focused"`, `focused` stands in for the configured output.
+pub(super) fn resolve_popups(
     config: &Config,
+    entered: &[String],
+    focused: Option<&str>,
+) -> PopupConfig {
+    let output = if config.popups.follows_focus() {
+        focused
+    } else {
+        config.popups.output.as_deref()
+    };
+    let mut popups = match output.and_then(find_monitor) {
+        Some(monitor) => config.popups_for_output(&output_names(&monitor)),
+        None => config.popups_for_output(entered),
+    };
+    popups.output = output.map(str::to_string);
+    popups
+}
+
+/// Report the output names each time the popup surface enters a monitor.
+pub(super) fn connect_output_entered(
+    window: &gtk::ApplicationWindow,
+    on_enter: impl Fn(Vec<String>) + Clone + 'static,
 ) {
-    window.set_default_size(config.popups.width, 1);
-    window.set_size_request(config.popups.width, -1);
-    stack.set_spacing(config.popups.spacing);
+    window.connect_realize(move |window| {
+        let Some(surface) = window.surface() else {
+            return;
+        };
+        let on_enter = on_enter.clone();
+        surface.connect_enter_monitor(move |_, monitor| on_enter(output_names(monitor)));
+    });
+}
 
-    apply_anchor(window, config.popups.anchor, config.popups.margin);
-    window.set_exclusive_zone(0);
-    window.set_keyboard_mode(KeyboardMode::None);
+/// Placement shown by the debug HUD; popups leave reserved edges to the compositor.
+pub(super) fn hud_placement(popups: &PopupConfig) -> HudPlacement {
+    HudPlacement {
+        anchor: popups.anchor,
+        margin: popups.margin,
+        work_area: None,
+        requested: (popups.width, -1),
+    }
+}
+
+/// Connector first, so `[outputs."DP-1"]` wins over a block keyed by the model name.
+fn output_names(monitor: &gdk::Monitor) -> Vec<String> {
+    [monitor.connector(), monitor.model()]
+        .into_iter()
+        .flatten()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Click-through window with one placeholder card per visible popup slot; `label` names the
+/// card for each slot, counted from 1.
+pub(super) fn build_preview_window(
+    app: &gtk::Application,
+    popups: &PopupConfig,
+    label: impl Fn(usize) -> String,
+) -> gtk::ApplicationWindow {
+    let window = gtk::ApplicationWindow::new(app);
+    window.set_decorated(false);
+    window.set_resizable(false);
+    window.set_title(Some("UnixNotis Layout Preview"));
+    window.add_css_class("unixnotis-popup-window");
+
+    surface::init_layer_surface(&window, "unixnotis-layout-preview");
 
-    if let Some(output) = config.popups.output.as_ref() {
-        if let Some(monitor) = find_monitor(output) {
-            window.set_monitor(Some(&monitor));
+    let stack = gtk::Box::new(gtk::Orientation::Vertical, popups.spacing);
+    stack.add_css_class("unixnotis-popup-stack");
+    for slot in 1..=popups.max_visible.max(1) {
+        let card = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        card.add_css_class("unixnotis-popup-card");
+        card.add_css_class("unixnotis-layout-preview");
+        let label = gtk::Label::new(Some(&label(slot)));
+        label.add_css_class("unixnotis-layout-preview-label");
+        card.append(&label);
+        stack.append(&card);
+    }
+    window.set_child(Some(&stack));
+    apply_popup_config(&window, &stack, popups);
+    // Placeholders never take clicks, whatever allow_click_through says.
+    window.connect_realize(|window| apply_input_region(window, true));
+    window
+}
+
+/// Surface for the volume and brightness overlay; it never takes clicks.
+pub(super) fn build_osd_window(app: &gtk::Application) -> gtk::ApplicationWindow {
+    let window = gtk::ApplicationWindow::new(app);
+    window.set_decorated(false);
+    window.set_resizable(false);
+    window.set_title(Some("UnixNotis OSD"));
+    window.add_css_class("unixnotis-popup-window");
+    window.add_css_class("unixnotis-osd-window");
+
+    surface::init_layer_surface(&window, "unixnotis-osd");
+    window.set_visible(false);
+    window.connect_realize(|window| apply_input_region(window, true));
+    window
+}
+
+/// Place the overlay; `output` is the popups' output, so both appear on the same monitor.
+pub(super) fn apply_osd_config(
+    window: &gtk::ApplicationWindow,
+    osd: &OsdConfig,
+    output: Option<&str>,
+) {
+    window.set_default_size(osd.width, 1);
+    window.set_size_request(osd.width, -1);
+    if !surface::layer_shell_enabled() {
+        return;
+    }
+    apply_anchor(window, osd.anchor, osd.margin);
+    // Edge anchors center the overlay on that edge instead of stretching it along the edge.
+    match osd.anchor {
+        Anchor::Top | Anchor::Bottom => {
+            window.set_anchor(Edge::Left, false);
+            window.set_anchor(Edge::Right, false);
         }
-    } else {
-        window.set_monitor(None);
+        Anchor::Left | Anchor::Right => {
+            window.set_anchor(Edge::Top, false);
+            window.set_anchor(Edge::Bottom, false);
+        }
+        _ => {}
     }
-    apply_input_region(window, config.popups.allow_click_through);
+    window.set_exclusive_zone(0);
+    window.set_keyboard_mode(KeyboardMode::None);
+    let monitor = output.and_then(find_monitor);
+    window.set_monitor(monitor.as_ref());
 }
 
 fn apply_input_region(window: &gtk::ApplicationWindow, allow_click_through: bool) {
@@ -118,6 +331,8 @@ fn apply_anchor(window: &impl IsA<gtk::Window>, anchor: Anchor, margin: Margins)
             window.set_anchor(Edge::Top, true);
             window.set_anchor(Edge::Bottom, true);
         }
+        // No anchored edges; the compositor centers the surface.
+        Anchor::Center => {}
     }
 
     window.set_margin(Edge::Top, margin.top);
@@ -132,10 +347,8 @@ fn find_monitor(name: &str) -> Option<gtk::gdk::Monitor> {
     for index in 0..monitors.n_items() {
         let item = monitors.item(index)?;
         let monitor = item.downcast::<gtk::gdk::Monitor>().ok()?;
-        if let Some(model) = monitor.model() {
-            if model == name {
-                return Some(monitor);
-            }
+        if output_names(&monitor).iter().any(|output| output == name) {
+            return Some(monitor);
         }
     }
     None
@@ -1,6 +1,7 @@
 //! CSS loading, validation, and hot-reload support shared by UnixNotis UIs.
 
 use std::collections::HashSet;
+use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
@@ -10,10 +11,10 @@ use std::time::Duration;
 use gtk::gdk;
 use gtk::CssProvider;
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
-use tracing::warn;
+use tracing::{debug, warn};
 use unixnotis_core::{
-    ThemeConfig, ThemePaths, DEFAULT_BASE_CSS, DEFAULT_PANEL_CSS, DEFAULT_POPUP_CSS,
-    DEFAULT_WIDGETS_CSS,
+    Config, PanelDensity, ThemeConfig, ThemePaths, DEFAULT_BASE_CSS, DEFAULT_PANEL_CSS,
+    DEFAULT_POPUP_CSS, DEFAULT_WIDGETS_CSS,
 };
 
 pub const DEFAULT_CSS: &str = DEFAULT_BASE_CSS;
@@ -30,6 +31,7 @@ pub enum CssKind {
 pub struct CssManager {
     theme_paths: ThemePaths,
     theme_config: ThemeConfig,
+    density: PanelDensity,
     base: CssProvider,
     panel: Option<CssProvider>,
     widgets: Option<CssProvider>,
@@ -41,6 +43,7 @@ impl CssManager {
         Self {
             theme_paths,
             theme_config,
+            density: PanelDensity::default(),
             base: CssProvider::new(),
             panel: Some(CssProvider::new()),
             widgets: Some(CssProvider::new()),
@@ -52,6 +55,7 @@ impl CssManager {
         Self {
             theme_paths,
             theme_config,
+            density: PanelDensity::default(),
             base: CssProvider::new(),
             panel: None,
             widgets: None,
@@ -102,7 +106,7 @@ impl CssManager {
         );
 
         if let Some(panel) = self.panel.as_ref() {
-            let panel_overrides = build_panel_overrides(&self.theme_config);
+            let panel_overrides = build_panel_overrides(&self.theme_config, self.density);
             load_provider_with_overrides(
                 panel,
                 &self.theme_paths.panel_css,
@@ -136,6 +140,11 @@ impl CssManager {
         self.theme_paths = theme_paths;
         self.theme_config = theme_config;
     }
+
+    /// Set the panel density preset used for injected row sizing rules.
+    pub fn set_density(&mut self, density: PanelDensity) {
+        self.density = density;
+    }
 }
 
 /// Start a file watcher for CSS paths and emit reload callbacks.
@@ -200,11 +209,21 @@ pub fn start_css_watcher(paths: &ThemePaths, kind: CssKind, on_reload: impl Fn()
 }
 
 /// Start a file watcher for the config path and emit reload callbacks.
-pub fn start_config_watcher(config_path: PathBuf, on_reload: impl Fn() + Send + 'static) {
-    let Some(parent) = config_path.parent().map(PathBuf::from) else {
+///
+/// The parent directory is watched rather than the file, so saves that replace the file
+/// (vim, VSCode's atomic save) keep being seen; a symlinked config also watches its target,
+/// resolved again after each change so a repointed link is followed.
+/// Events are debounced, an unchanged file is ignored, and `on_reload` only runs once the
+/// file parses. A file that does not parse is handed to `on_invalid` once per distinct edit.
+pub fn start_config_watcher(
+    config_path: PathBuf,
+    on_reload: impl Fn() + Send + 'static,
+    on_invalid: impl Fn(String) + Send + 'static,
+) {
+    let (mut dirs, mut names) = watch_set(&config_path);
+    if dirs.is_empty() {
         return;
-    };
-    let config_name = config_path.file_name().map(|name| name.to_os_string());
+    }
     thread::spawn(move || {
         let (event_tx, event_rx) = mpsc::channel::<notify::Result<Event>>();
         let mut watcher = match RecommendedWatcher::new(
@@ -220,10 +239,16 @@ pub fn start_config_watcher(config_path: PathBuf, on_reload: impl Fn() + Send +
             }
         };
 
-        if let Err(err) = watcher.watch(&parent, RecursiveMode::NonRecursive) {
-            warn!(?err, "failed to watch config directory");
+        for dir in &dirs {
+            if let Err(err) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                warn!(?err, "failed to watch config directory");
+            }
         }
 
+        // Contents last applied and last reported as invalid, so repeated events for one
+        // save neither reload twice nor notify twice.
+        let mut applied = fs::read(&config_path).ok();
+        let mut reported: Option<Vec<u8>> = None;
         let debounce = Duration::from_millis(150);
         let mut pending = false;
         loop {
@@ -233,21 +258,58 @@ pub fn start_config_watcher(config_path: PathBuf, on_reload: impl Fn() + Send +
                         warn!(?event, "config watcher reported an error");
                         continue;
                     };
-                    if let Some(name) = config_name.as_ref() {
-                        let matches = event
-                            .paths
-                            .iter()
-                            .any(|path| path.file_name() == Some(name));
-                        if !matches {
-                            continue;
-                        }
+                    let matches = event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name().is_some_and(|name| names.contains(name)));
+                    if matches {
+                        pending = true;
                     }
-                    pending = true;
                 }
                 Err(mpsc::RecvTimeoutError::Timeout) => {
-                    if pending {
-                        on_reload();
-                        pending = false;
+                    if !pending {
+                        continue;
+                    }
+                    pending = false;
+                    // Dotfile managers repoint the link; follow it to the new target.
+                    let (next_dirs, next_names) = watch_set(&config_path);
+                    if next_dirs != dirs {
+                        for dir in dirs.difference(&next_dirs) {
+                            let _ = watcher.unwatch(dir);
+                        }
+                        for dir in next_dirs.difference(&dirs) {
+                            if let Err(err) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                                warn!(?err, "failed to watch config directory");
+                            }
+                        }
+                        debug!("config symlink target moved");
+                        dirs = next_dirs;
+                    }
+                    names = next_names;
+                    let Ok(contents) = fs::read(&config_path) else {
+                        // Removed, or mid-rename; the file's return sends another event.
+                        debug!("config file missing after change");
+                        continue;
+                    };
+                    if applied.as_ref() == Some(&contents) {
+                        continue;
+                    }
+                    let parsed = std::str::from_utf8(&contents)
+                        .map_err(|err| err.to_string())
+                        .and_then(|text| Config::parse(text).map_err(|err| err.to_string()));
+                    match parsed {
+                        Ok(_) => {
+                            applied = Some(contents);
+                            reported = None;
+                            on_reload();
+                        }
+                        Err(err) => {
+                            if reported.as_ref() != Some(&contents) {
+                                warn!(%err, "config change not applied");
+                                reported = Some(contents);
+                                on_invalid(err);
+                            }
+                        }
                     }
                 }
                 Err(mpsc::RecvTimeoutError::Disconnected) => break,
@@ -256,6 +318,26 @@ pub fn start_config_watcher(config_path: PathBuf, on_reload: impl Fn() + Send +
     });
 }
 
+/// Directories to watch for `config_path`, and the file names that matter in them: the path
+/// itself and, when it is a symlink, its current target.
+fn watch_set(config_path: &Path) -> (HashSet<PathBuf>, HashSet<OsString>) {
+    let mut watched = vec![config_path.to_path_buf()];
+    if let Ok(target) = fs::canonicalize(config_path) {
+        if target != config_path {
+            watched.push(target);
+        }
+    }
+    let dirs = watched
+        .iter()
+        .filter_map(|path| path.parent().map(PathBuf::from))
+        .collect();
+    let names = watched
+        .iter()
+        .filter_map(|path| path.file_name().map(|name| name.to_os_string()))
+        .collect();
+    (dirs, names)
+}
+
 fn load_provider_with_overrides(
     provider: &CssProvider,
     path: &Path,
@@ -311,11 +393,11 @@ fn build_base_overrides(theme: &ThemeConfig) -> String {
     )
 }
 
-fn build_panel_overrides(theme: &ThemeConfig) -> String {
+fn build_panel_overrides(theme: &ThemeConfig, density: PanelDensity) -> String {
     let border_width = theme.border_width as f32;
     let card_radius = theme.card_radius as f32;
     let card_alpha = theme.card_alpha.clamp(0.0, 1.0);
-    format!(
+    let mut overrides = format!(
         r#"
 .unixnotis-panel-card {{
   border-width: {border_width}px;
@@ -323,6 +405,35 @@ fn build_panel_overrides(theme: &ThemeConfig) -> String {
   border-radius: {card_radius}px;
   background: alpha(@unixnotis-card, {card_alpha});
 }}
+"#
+    );
+    overrides.push_str(&build_density_overrides(density));
+    overrides
+}
+
+fn build_density_overrides(density: PanelDensity) -> String {
+    // Comfortable keeps the stock theme values; denser presets shrink paddings and type.
+    let (pad_y, pad_x, gap, app_size, text_size) = match density {
+        PanelDensity::Comfortable => return String::new(),
+        PanelDensity::Compact => (6, 10, 6, 12, 11),
+        PanelDensity::Minimal => (4, 8, 4, 11, 11),
+    };
+    format!(
+        r#"
+.unixnotis-panel-card {{
+  padding: {pad_y}px {pad_x}px;
+  margin-bottom: {gap}px;
+}}
+.unixnotis-panel-app {{
+  font-size: {app_size}px;
+}}
+.unixnotis-panel-summary,
+.unixnotis-panel-body {{
+  font-size: {text_size}px;
+}}
+.unixnotis-group {{
+  margin-bottom: {gap}px;
+}}
 "#
     )
 }
@@ -356,3 +467,37 @@ fn build_popup_overrides(theme: &ThemeConfig) -> String {
 "#
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::watch_set;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn watch_set_follows_a_repointed_symlink() {
+        let root = std::env::temp_dir().join(format!("unixnotis-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["config", "old", "new"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("old/first.toml"), "").unwrap();
+        fs::write(root.join("new/second.toml"), "").unwrap();
+        let link = root.join("config/config.toml");
+        symlink(root.join("old/first.toml"), &link).unwrap();
+
+        let (dirs, names) = watch_set(&link);
+        let old = fs::canonicalize(root.join("old")).unwrap();
+        assert!(dirs.contains(&root.join("config")) && dirs.contains(&old));
+        assert!(names.contains(std::ffi::OsStr::new("first.toml")));
+
+        fs::remove_file(&link).unwrap();
+        symlink(root.join("new/second.toml"), &link).unwrap();
+        let (dirs, names) = watch_set(&link);
+        assert!(!dirs.contains(&old));
+        assert!(dirs.contains(&fs::canonicalize(root.join("new")).unwrap()));
+        assert!(names.contains(std::ffi::OsStr::new("second.toml")));
+        assert!(!names.contains(std::ffi::OsStr::new("first.toml")));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
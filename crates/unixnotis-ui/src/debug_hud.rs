@@ -0,0 +1,151 @@
+//! On-surface readout of the resolved layer-shell placement for debugging margin issues.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use gtk::prelude::*;
+use unixnotis_core::{Anchor, Margins};
+
+/// Placement values a surface was configured with, as opposed to what the compositor granted.
+#[derive(Debug, Clone, Copy)]
+pub struct HudPlacement {
+    pub anchor: Anchor,
+    pub margin: Margins,
+    /// Reserved edges subtracted by UnixNotis itself; `None` when the compositor handles them.
+    pub work_area: Option<Margins>,
+    /// Requested surface size; `-1` means natural size on that axis.
+    pub requested: (i32, i32),
+}
+
+/// Timing of the latest list rebuild on surfaces that batch them per frame.
+#[derive(Debug, Clone, Copy)]
+pub struct HudRebuild {
+    /// Time from the first queued change to the frame that applied it.
+    pub wait: Duration,
+    /// Time spent applying the changes.
+    pub took: Duration,
+    /// Changes folded into this rebuild.
+    pub requests: u32,
+}
+
+/// Click-through label that shows placement next to the surface size the compositor assigned.
+#[derive(Clone)]
+pub struct DebugHud {
+    label: gtk::Label,
+    state: Rc<RefCell<HudState>>,
+}
+
+#[derive(Default)]
+struct HudState {
+    placement: Option<HudPlacement>,
+    surface: Option<(i32, i32)>,
+    rebuild: Option<HudRebuild>,
+}
+
+impl DebugHud {
+    pub fn new() -> Self {
+        let label = gtk::Label::new(None);
+        label.add_css_class("unixnotis-debug-hud");
+        label.set_halign(gtk::Align::Start);
+        label.set_valign(gtk::Align::End);
+        label.set_xalign(0.0);
+        label.set_can_target(false);
+        label.set_visible(false);
+        Self {
+            label,
+            state: Rc::new(RefCell::new(HudState::default())),
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::Label {
+        &self.label
+    }
+
+    /// Track the size of `window`'s surface each time it is realized.
+    pub fn attach(&self, window: &impl IsA<gtk::Window>) {
+        let hud = self.clone();
+        window.connect_realize(move |window| {
+            let Some(surface) = window.surface() else {
+                return;
+            };
+            hud.set_surface_size(surface.width(), surface.height());
+            let hud = hud.clone();
+            surface.connect_layout(move |_, width, height| {
+                hud.set_surface_size(width, height);
+            });
+        });
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.label.set_visible(enabled);
+        if enabled {
+            self.render();
+        }
+    }
+
+    pub fn set_placement(&self, placement: HudPlacement) {
+        self.state.borrow_mut().placement = Some(placement);
+        self.render();
+    }
+
+    pub fn set_rebuild(&self, rebuild: HudRebuild) {
+        self.state.borrow_mut().rebuild = Some(rebuild);
+        self.render();
+    }
+
+    fn set_surface_size(&self, width: i32, height: i32) {
+        self.state.borrow_mut().surface = Some((width, height));
+        self.render();
+    }
+
+    fn render(&self) {
+        if !self.label.is_visible() {
+            return;
+        }
+        let state = self.state.borrow();
+        let Some(placement) = state.placement else {
+            return;
+        };
+        let work_area = placement
+            .work_area
+            .map(format_edges)
+            .unwrap_or_else(|| "compositor".to_string());
+        let surface = state
+            .surface
+            .map(|(width, height)| format!("{width}x{height}"))
+            .unwrap_or_else(|| "unmapped".to_string());
+        let (width, height) = placement.requested;
+        let mut text = format!(
+            "anchor    {:?}\nmargin    {}\nwork area {}\nrequested {}x{}\nsurface   {}",
+            placement.anchor,
+            format_edges(placement.margin),
+            work_area,
+            width,
+            height,
+            surface
+        );
+        if let Some(rebuild) = state.rebuild {
+            text.push_str(&format!(
+                "\nrebuild   {:.1} ms, {} changes after {:.1} ms",
+                rebuild.took.as_secs_f64() * 1000.0,
+                rebuild.requests,
+                rebuild.wait.as_secs_f64() * 1000.0
+            ));
+        }
+        self.label.set_text(&text);
+    }
+}
+
+impl Default for DebugHud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn format_edges(edges: Margins) -> String {
+    format!(
+        "t{} r{} b{} l{}",
+        edges.top, edges.right, edges.bottom, edges.left
+    )
+}
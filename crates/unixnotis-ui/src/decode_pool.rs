@@ -0,0 +1,168 @@
+//! Small prioritized worker pool for image decoding.
+//!
+//! Decoding and resizing are CPU-heavy, so both UI binaries share one bounded pool design
+//! instead of spawning a thread per image.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use tracing::warn;
+
+// Upper bound for the automatic worker count; decode should never starve the GTK thread.
+const AUTO_MAX_WORKERS: usize = 2;
+
+/// Scheduling lane for a decode job, highest priority first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodePriority {
+    /// Icons for popups that are about to be shown.
+    Popup,
+    /// Icons for panel rows currently bound on screen.
+    Visible,
+    /// Speculative decodes for rows that are not visible yet.
+    Prefetch,
+}
+
+impl DecodePriority {
+    fn lane(self) -> usize {
+        match self {
+            DecodePriority::Popup => 0,
+            DecodePriority::Visible => 1,
+            DecodePriority::Prefetch => 2,
+        }
+    }
+}
+
+const TOKEN_PENDING: u8 = 0;
+const TOKEN_CLAIMED: u8 = 1;
+const TOKEN_CANCELLED: u8 = 2;
+
+/// Shared claim/cancel state for a queued decode.
+///
+/// A decode may be queued in more than one lane after a promotion; the first worker to claim
+/// the token runs it and later copies are skipped. Cancel it once nothing waits on the result.
+#[derive(Clone, Default)]
+pub struct DecodeToken(Arc<AtomicU8>);
+
+impl DecodeToken {
+    pub fn cancel(&self) {
+        let _ = self.0.compare_exchange(
+            TOKEN_PENDING,
+            TOKEN_CANCELLED,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// True for the one caller that should run the decode.
+    pub fn claim(&self) -> bool {
+        self.0
+            .compare_exchange(
+                TOKEN_PENDING,
+                TOKEN_CLAIMED,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+#[derive(Default)]
+struct PoolState {
+    lanes: [VecDeque<Job>; 3],
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<PoolState>,
+    ready: Condvar,
+}
+
+impl Shared {
+    fn next_job(&self) -> Option<Job> {
+        let mut state = self.state.lock().ok()?;
+        loop {
+            if state.closed {
+                return None;
+            }
+            if let Some(job) = state.lanes.iter_mut().find_map(VecDeque::pop_front) {
+                return Some(job);
+            }
+            state = self.ready.wait(state).ok()?;
+        }
+    }
+}
+
+/// Fixed-size pool that always runs the highest-priority queued job next.
+pub struct DecodePool {
+    shared: Arc<Shared>,
+}
+
+impl DecodePool {
+    /// Start `workers` decode threads; 0 picks a small count from available parallelism.
+    pub fn new(workers: usize) -> Self {
+        let shared = Arc::new(Shared::default());
+        for index in 0..resolve_worker_count(workers) {
+            let shared = shared.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("unixnotis-decode-{index}"))
+                .spawn(move || {
+                    while let Some(job) = shared.next_job() {
+                        job();
+                    }
+                });
+            if let Err(err) = spawned {
+                warn!(?err, "failed to spawn decode worker");
+            }
+        }
+        Self { shared }
+    }
+
+    pub fn submit<F>(&self, priority: DecodePriority, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let Ok(mut state) = self.shared.state.lock() else {
+            return;
+        };
+        let lane = &mut state.lanes[priority.lane()];
+        if priority == DecodePriority::Popup {
+            // Popups appear in arrival order, so their icons decode first-in first-out.
+            lane.push_back(Box::new(job));
+        } else {
+            // Flinging binds rows in scroll order; newest-first serves the final viewport
+            // before rows that already scrolled away.
+            lane.push_front(Box::new(job));
+        }
+        drop(state);
+        self.shared.ready.notify_one();
+    }
+}
+
+impl Drop for DecodePool {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.closed = true;
+            for lane in &mut state.lanes {
+                lane.clear();
+            }
+        }
+        // Wake idle workers so they exit instead of blocking forever.
+        self.shared.ready.notify_all();
+    }
+}
+
+/// Resolve a configured worker count, treating 0 as "auto".
+pub fn resolve_worker_count(configured: usize) -> usize {
+    if configured > 0 {
+        return configured;
+    }
+    // available_parallelism() may fail in constrained environments, so default to 1.
+    thread::available_parallelism()
+        .map(|count| count.get().min(AUTO_MAX_WORKERS))
+        .unwrap_or(1)
+}
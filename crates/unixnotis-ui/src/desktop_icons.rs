@@ -0,0 +1,426 @@
+//! Icon names from installed desktop files, keyed by app name, WM class, and desktop id.
+//!
+//! Listing every desktop file takes a noticeable moment on systems with many apps, so the index
+//! is built on a GLib worker thread and lookups find nothing until it is ready; most
+//! notifications resolve through the icon theme without it anyway. The `applications`
+//! directories under the XDG data dirs and their subdirectories are watched afterwards, and a
+//! desktop file that is added, edited, or removed updates just its own entry, so newly installed
+//! apps get their icons without a restart. A subdirectory coming or going rebuilds the index.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+
+use gtk::gio;
+use gtk::gio::prelude::*;
+use gtk::glib;
+use tracing::{debug, warn};
+
+/// Desktop-file icon lookup, built in the background and kept current with installed apps.
+pub struct DesktopIconIndex {
+    state: Rc<IndexState>,
+}
+
+struct IndexState {
+    index: RefCell<Option<Index>>,
+    generation: Cell<u64>,
+    building: Cell<bool>,
+    /// A desktop file changed while the index was being built; build it again.
+    stale: Cell<bool>,
+    /// Application directories, highest precedence first.
+    dirs: Vec<PathBuf>,
+    /// Watched directories, subdirectories included; dropping a monitor stops it.
+    monitors: RefCell<HashMap<PathBuf, gio::FileMonitor>>,
+}
+
+impl DesktopIconIndex {
+    pub fn new() -> Self {
+        let dirs = std::iter::once(glib::user_data_dir())
+            .chain(glib::system_data_dirs())
+            .map(|dir| dir.join("applications"))
+            .collect::<Vec<_>>();
+        let state = Rc::new(IndexState {
+            index: RefCell::new(None),
+            generation: Cell::new(0),
+            building: Cell::new(false),
+            stale: Cell::new(false),
+            dirs,
+            monitors: RefCell::new(HashMap::new()),
+        });
+        for dir in &state.dirs {
+            watch_tree(&state, dir);
+        }
+        start_build(&state);
+        Self { state }
+    }
+
+    /// Icon names of desktop entries matching `key`; `None` as well until the index is built.
+    pub fn icons_for(&self, key: &str) -> Option<Vec<String>> {
+        let normalized = normalize_key(key);
+        if normalized.is_empty() {
+            return None;
+        }
+        self.state.index.borrow().as_ref()?.icons_for(&normalized)
+    }
+
+    /// Changes whenever a desktop file changes or the index is built; callers caching lookups
+    /// compare it.
+    pub fn generation(&self) -> u64 {
+        self.state.generation.get()
+    }
+}
+
+impl Default for DesktopIconIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scan the installed desktop files on a worker, then swap the result in on the main context.
+fn start_build(state: &Rc<IndexState>) {
+    if state.building.get() {
+        state.stale.set(true);
+        return;
+    }
+    state.building.set(true);
+    state.stale.set(false);
+    let state = Rc::downgrade(state);
+    glib::MainContext::default().spawn_local(async move {
+        let built = gio::spawn_blocking(Index::build).await;
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        state.building.set(false);
+        match built {
+            Ok(index) => {
+                state.index.replace(Some(index));
+                state.bump_generation();
+            }
+            Err(_) => warn!("desktop icon index build panicked"),
+        }
+        if state.stale.get() {
+            start_build(&state);
+        }
+    });
+}
+
+/// Watch `dir` and every directory below it that is not watched yet.
+fn watch_tree(state: &Rc<IndexState>, dir: &Path) {
+    if state.monitors.borrow().contains_key(dir) {
+        return;
+    }
+    let Some(monitor) = watch_dir(dir, Rc::downgrade(state)) else {
+        return;
+    };
+    state
+        .monitors
+        .borrow_mut()
+        .insert(dir.to_path_buf(), monitor);
+    // Vendors and Flatpak group desktop files in subdirectories; their ids gain a prefix.
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+            watch_tree(state, &entry.path());
+        }
+    }
+}
+
+fn watch_dir(dir: &Path, state: Weak<IndexState>) -> Option<gio::FileMonitor> {
+    let monitor = match gio::File::for_path(dir)
+        .monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+    {
+        Ok(monitor) => monitor,
+        Err(err) => {
+            warn!(?err, dir = %dir.display(), "failed to watch application directory");
+            return None;
+        }
+    };
+    monitor.connect_changed(move |_, file, other, event| {
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let path = file.path();
+        let added_dir = match event {
+            gio::FileMonitorEvent::Created | gio::FileMonitorEvent::MovedIn => path.clone(),
+            gio::FileMonitorEvent::Renamed => other.and_then(|other| other.path()),
+            _ => None,
+        }
+        .filter(|path| path.is_dir());
+        let removed_dir = matches!(
+            event,
+            gio::FileMonitorEvent::Deleted
+                | gio::FileMonitorEvent::MovedOut
+                | gio::FileMonitorEvent::Renamed
+        ) && path
+            .as_deref()
+            .is_some_and(|path| state.forget_dirs_under(path));
+        if let Some(dir) = &added_dir {
+            watch_tree(&state, dir);
+        }
+        // Which ids a whole directory held is not tracked, so it is cheaper to scan again.
+        if added_dir.is_some() || removed_dir {
+            start_build(&state);
+            return;
+        }
+        match event {
+            // Created and Changed are followed by ChangesDoneHint once the write finishes.
+            gio::FileMonitorEvent::ChangesDoneHint
+            | gio::FileMonitorEvent::Deleted
+            | gio::FileMonitorEvent::MovedIn
+            | gio::FileMonitorEvent::MovedOut => state.refresh(file),
+            // Installers often write a temporary file and rename it into place.
+            gio::FileMonitorEvent::Renamed => {
+                state.refresh(file);
+                if let Some(other) = other {
+                    state.refresh(other);
+                }
+            }
+            _ => {}
+        }
+    });
+    Some(monitor)
+}
+
+impl IndexState {
+    fn bump_generation(&self) {
+        self.generation.set(self.generation.get().wrapping_add(1));
+    }
+
+    /// Stop watching `path` and the directories below it; false when none were watched.
+    /// The application directories themselves stay watched so they are seen if recreated.
+    fn forget_dirs_under(&self, path: &Path) -> bool {
+        let mut monitors = self.monitors.borrow_mut();
+        let before = monitors.len();
+        monitors.retain(|dir, _| !dir.starts_with(path) || self.dirs.contains(dir));
+        monitors.len() != before
+    }
+
+    /// Re-read the entry for the desktop file `file` names, wherever it now lives.
+    fn refresh(&self, file: &gio::File) {
+        let Some(path) = file.path() else {
+            return;
+        };
+        let Some(relative) = self.dirs.iter().find_map(|dir| path.strip_prefix(dir).ok()) else {
+            return;
+        };
+        let Some(id) = desktop_id(relative) else {
+            return;
+        };
+        let id = id.as_str();
+        self.bump_generation();
+        let mut index = self.index.borrow_mut();
+        let Some(index) = index.as_mut() else {
+            // The scan in flight may already have read the old file.
+            self.stale.set(self.building.get());
+            return;
+        };
+        index.remove(id);
+        // Only the first directory holding the id counts, as in GIO's own lookup. A file that
+        // fails to load, e.g. one with `Hidden=true`, masks the ones below it.
+        let current = self
+            .dirs
+            .iter()
+            .flat_map(|dir| [dir.join(relative), dir.join(id)])
+            .find(|path| path.is_file());
+        if let Some(desktop) = current.and_then(gio::DesktopAppInfo::from_filename) {
+            index.insert(id, &desktop);
+        }
+        debug!(id, "desktop icon index entry refreshed");
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Field {
+    Name,
+    WmClass,
+    Id,
+}
+
+// Keys point at desktop ids, so one file's entry can be replaced without a rebuild.
+#[derive(Default)]
+struct Index {
+    by_name: HashMap<String, Vec<String>>,
+    by_wm_class: HashMap<String, Vec<String>>,
+    by_id: HashMap<String, Vec<String>>,
+    entries: HashMap<String, Entry>,
+}
+
+struct Entry {
+    icon: String,
+    keys: Vec<(Field, String)>,
+}
+
+impl Index {
+    fn build() -> Self {
+        let started = std::time::Instant::now();
+        let mut index = Self::default();
+        for app_info in gio::AppInfo::all() {
+            let Ok(desktop) = app_info.downcast::<gio::DesktopAppInfo>() else {
+                continue;
+            };
+            if let Some(id) = desktop.id() {
+                index.insert(id.as_str(), &desktop);
+            }
+        }
+        debug!(
+            entries = index.entries.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "desktop icon index built"
+        );
+        index
+    }
+
+    fn insert(&mut self, id: &str, desktop: &gio::DesktopAppInfo) {
+        let icon = desktop
+            .string("Icon")
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        let mut keys = vec![
+            (Field::Name, desktop.name().to_string()),
+            (Field::Name, desktop.display_name().to_string()),
+            (Field::Id, id.to_string()),
+        ];
+        if let Some(generic) = desktop.generic_name() {
+            keys.push((Field::Name, generic.to_string()));
+        }
+        if let Some(startup_wm_class) = desktop.startup_wm_class() {
+            keys.push((Field::WmClass, startup_wm_class.to_string()));
+        }
+        if let Some(stripped) = id.strip_suffix(".desktop") {
+            keys.push((Field::Id, stripped.to_string()));
+        }
+        self.add(id, icon, keys);
+    }
+
+    fn add(&mut self, id: &str, icon: String, keys: Vec<(Field, String)>) {
+        self.remove(id);
+        if icon.is_empty() {
+            return;
+        }
+        let mut seen = HashSet::new();
+        let keys = keys
+            .into_iter()
+            .map(|(field, key)| (field, normalize_key(&key)))
+            .filter(|(field, key)| !key.is_empty() && seen.insert((*field, key.clone())))
+            .collect::<Vec<_>>();
+        for (field, key) in &keys {
+            self.map_mut(*field)
+                .entry(key.clone())
+                .or_default()
+                .push(id.to_string());
+        }
+        self.entries.insert(id.to_string(), Entry { icon, keys });
+    }
+
+    fn remove(&mut self, id: &str) {
+        let Some(entry) = self.entries.remove(id) else {
+            return;
+        };
+        for (field, key) in entry.keys {
+            let map = self.map_mut(field);
+            if let Some(ids) = map.get_mut(&key) {
+                ids.retain(|other| other != id);
+                if ids.is_empty() {
+                    map.remove(&key);
+                }
+            }
+        }
+    }
+
+    fn icons_for(&self, normalized: &str) -> Option<Vec<String>> {
+        let mut seen = HashSet::new();
+        let icons = [&self.by_id, &self.by_wm_class, &self.by_name]
+            .into_iter()
+            .filter_map(|map| map.get(normalized))
+            .flatten()
+            .filter_map(|id| self.entries.get(id))
+            .map(|entry| entry.icon.clone())
+            .filter(|icon| seen.insert(icon.clone()))
+            .collect::<Vec<_>>();
+        (!icons.is_empty()).then_some(icons)
+    }
+
+    fn map_mut(&mut self, field: Field) -> &mut HashMap<String, Vec<String>> {
+        match field {
+            Field::Name => &mut self.by_name,
+            Field::WmClass => &mut self.by_wm_class,
+            Field::Id => &mut self.by_id,
+        }
+    }
+}
+
+/// Desktop id of the file at `relative` in an applications directory: `kde4/foo.desktop` is
+/// `kde4-foo.desktop`.
+fn desktop_id(relative: &Path) -> Option<String> {
+    let parts = relative
+        .iter()
+        .map(|part| part.to_str())
+        .collect::<Option<Vec<_>>>()?;
+    let id = parts.join("-");
+    id.ends_with(".desktop").then_some(id)
+}
+
+fn normalize_key(value: &str) -> String {
+    // Theme and desktop names vary in case and stray whitespace.
+    value.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{desktop_id, Field, Index};
+
+    fn keys(id: &str, name: &str) -> Vec<(Field, String)> {
+        vec![(Field::Id, id.to_string()), (Field::Name, name.to_string())]
+    }
+
+    #[test]
+    fn entries_are_replaced_and_removed_individually() {
+        let mut index = Index::default();
+        index.add("a.desktop", "editor".into(), keys("a.desktop", "Editor"));
+        index.add(
+            "b.desktop",
+            "editor-alt".into(),
+            keys("b.desktop", "editor"),
+        );
+        assert_eq!(
+            index.icons_for("editor"),
+            Some(vec!["editor".to_string(), "editor-alt".to_string()])
+        );
+
+        index.add(
+            "a.desktop",
+            "editor-new".into(),
+            keys("a.desktop", "Editor"),
+        );
+        assert_eq!(
+            index.icons_for("a.desktop"),
+            Some(vec!["editor-new".to_string()])
+        );
+
+        index.remove("b.desktop");
+        assert_eq!(
+            index.icons_for("editor"),
+            Some(vec!["editor-new".to_string()])
+        );
+        assert_eq!(index.icons_for("b.desktop"), None);
+    }
+
+    #[test]
+    fn subdirectories_prefix_the_desktop_id() {
+        assert_eq!(
+            desktop_id(Path::new("firefox.desktop")).as_deref(),
+            Some("firefox.desktop")
+        );
+        assert_eq!(
+            desktop_id(Path::new("kde4/dolphin.desktop")).as_deref(),
+            Some("kde4-dolphin.desktop")
+        );
+        assert_eq!(desktop_id(Path::new("kde4")), None);
+        assert_eq!(desktop_id(Path::new("mimeinfo.cache")), None);
+    }
+}
@@ -0,0 +1,288 @@
+//! Notifications and daemon state for panels and bars outside UnixNotis.
+//!
+//! `NotificationFeed` wraps the control interface the panel and popups use. It gives snapshots
+//! of what the daemon holds and a stream of typed events, so a bar written in Rust can show
+//! UnixNotis data without its own zbus glue. zbus runs on Tokio here, so the feed must be used
+//! inside a Tokio runtime.
+//!
+//! ```no_run
+//! use futures_util::StreamExt;
+//! use unixnotis_ui::feed::{FeedEvent, NotificationFeed};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let feed = NotificationFeed::builder().build().await?;
+//! let mut unread = feed.active().await?.len();
+//! let mut events = feed.events().await?;
+//! while let Some(event) = events.next().await {
+//!     match event {
+//!         FeedEvent::Added { .. } => unread += 1,
+//!         FeedEvent::Closed { .. } => unread = unread.saturating_sub(1),
+//!         _ => {}
+//!     }
+//!     println!("{unread} unread");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use futures_util::stream::{self, BoxStream, StreamExt};
+use unixnotis_core::{
+    BusNames, CloseReason, ControlProxy, ControlState, InvalidBusSuffix, NotificationView,
+};
+use zbus::Connection;
+
+/// A change on the daemon.
+#[derive(Debug, Clone)]
+pub enum FeedEvent {
+    /// A new notification; `popup` says whether it is also shown as a popup.
+    Added {
+        notification: NotificationView,
+        popup: bool,
+    },
+    /// A notification was replaced or its progress changed.
+    Updated {
+        notification: NotificationView,
+        popup: bool,
+    },
+    /// A notification left the active list; it may still be in history.
+    Closed {
+        id: u32,
+        reason: CloseReason,
+    },
+    Pinned {
+        id: u32,
+        pinned: bool,
+    },
+    /// Do Not Disturb, mute, popups, or the history count changed.
+    State(ControlState),
+    /// History changed beyond what `Closed` reports, e.g. an undone clear; list it again.
+    HistoryChanged,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FeedError {
+    #[error(transparent)]
+    BusSuffix(#[from] InvalidBusSuffix),
+    #[error(transparent)]
+    Bus(#[from] zbus::Error),
+}
+
+/// Builds a `NotificationFeed`; by default it uses the session bus and the instance named by
+/// `UNIXNOTIS_BUS_SUFFIX`, like the UnixNotis binaries.
+#[derive(Debug, Default)]
+pub struct NotificationFeedBuilder {
+    connection: Option<Connection>,
+    bus_suffix: Option<String>,
+}
+
+impl NotificationFeedBuilder {
+    /// Share a bus connection the caller already holds.
+    pub fn connection(mut self, connection: Connection) -> Self {
+        self.connection = Some(connection);
+        self
+    }
+
+    /// Follow the instance started with this `--bus-suffix`.
+    pub fn bus_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.bus_suffix = Some(suffix.into());
+        self
+    }
+
+    pub async fn build(self) -> Result<NotificationFeed, FeedError> {
+        let names = BusNames::resolve(self.bus_suffix.as_deref())?;
+        let connection = match self.connection {
+            Some(connection) => connection,
+            None => Connection::session().await?,
+        };
+        let proxy = names.control_proxy(&connection).await?;
+        Ok(NotificationFeed { proxy })
+    }
+}
+
+/// Read access to a running UnixNotis daemon, plus the few calls a bar needs.
+#[derive(Debug, Clone)]
+pub struct NotificationFeed {
+    proxy: ControlProxy<'static>,
+}
+
+impl NotificationFeed {
+    pub fn builder() -> NotificationFeedBuilder {
+        NotificationFeedBuilder::default()
+    }
+
+    /// The underlying control proxy, for calls the feed does not wrap.
+    pub fn proxy(&self) -> &ControlProxy<'static> {
+        &self.proxy
+    }
+
+    pub async fn state(&self) -> zbus::Result<ControlState> {
+        self.proxy.get_state().await
+    }
+
+    /// Notifications the daemon still holds as active.
+    pub async fn active(&self) -> zbus::Result<Vec<NotificationView>> {
+        self.proxy.list_active().await
+    }
+
+    /// Closed notifications kept in history.
+    pub async fn history(&self) -> zbus::Result<Vec<NotificationView>> {
+        self.proxy.list_history().await
+    }
+
+    /// Typed events from now on. Take a snapshot after subscribing to miss nothing in between.
+    ///
+    /// The stream ends when the connection closes. Signals are matched by bus name, so it keeps
+    /// running across a daemon restart; watch `proxy().receive_owner_changed()` and take a fresh
+    /// snapshot when the owner changes, since the new daemon starts over.
+    pub async fn events(&self) -> zbus::Result<BoxStream<'static, FeedEvent>> {
+        let added = self
+            .proxy
+            .receive_notification_added()
+            .await?
+            .filter_map(|signal| {
+                let event = signal.args().ok().map(|args| FeedEvent::Added {
+                    notification: args.notification().clone(),
+                    popup: *args.show_popup(),
+                });
+                async move { event }
+            });
+        let batches = self
+            .proxy
+            .receive_notifications_added_batch()
+            .await?
+            .flat_map(|signal| {
+                let events = signal
+                    .args()
+                    .map(|args| batch_events(args.notifications()))
+                    .unwrap_or_default();
+                stream::iter(events)
+            });
+        let updated = self
+            .proxy
+            .receive_notification_updated()
+            .await?
+            .filter_map(|signal| {
+                let event = signal.args().ok().map(|args| FeedEvent::Updated {
+                    notification: args.notification().clone(),
+                    popup: *args.show_popup(),
+                });
+                async move { event }
+            });
+        let closed = self
+            .proxy
+            .receive_notification_closed()
+            .await?
+            .filter_map(|signal| {
+                let event = signal.args().ok().map(|args| FeedEvent::Closed {
+                    id: *args.id(),
+                    reason: *args.reason(),
+                });
+                async move { event }
+            });
+        let pinned = self
+            .proxy
+            .receive_notification_pinned()
+            .await?
+            .filter_map(|signal| {
+                let event = signal.args().ok().map(|args| FeedEvent::Pinned {
+                    id: *args.id(),
+                    pinned: *args.pinned(),
+                });
+                async move { event }
+            });
+        let state = self
+            .proxy
+            .receive_state_changed()
+            .await?
+            .filter_map(|signal| {
+                let event = signal
+                    .args()
+                    .ok()
+                    .map(|args| FeedEvent::State(args.state().clone()));
+                async move { event }
+            });
+        let history = self
+            .proxy
+            .receive_history_changed()
+            .await?
+            .map(|_| FeedEvent::HistoryChanged);
+        Ok(stream::select_all([
+            added.boxed(),
+            batches.boxed(),
+            updated.boxed(),
+            closed.boxed(),
+            pinned.boxed(),
+            state.boxed(),
+            history.boxed(),
+        ])
+        .boxed())
+    }
+
+    pub async fn dismiss(&self, id: u32) -> zbus::Result<()> {
+        self.proxy.dismiss(id).await
+    }
+
+    pub async fn invoke_action(&self, id: u32, action_key: &str) -> zbus::Result<()> {
+        self.proxy.invoke_action(id, action_key).await
+    }
+
+    pub async fn set_dnd(&self, enabled: bool) -> zbus::Result<()> {
+        self.proxy.set_dnd(enabled).await
+    }
+
+    pub async fn toggle_panel(&self) -> zbus::Result<()> {
+        self.proxy.toggle_panel().await
+    }
+}
+
+/// Bursts arrive folded into one signal; unfold them so callers see each notification.
+fn batch_events(notifications: &[(NotificationView, bool)]) -> Vec<FeedEvent> {
+    notifications
+        .iter()
+        .map(|(notification, popup)| FeedEvent::Added {
+            notification: notification.clone(),
+            popup: *popup,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unixnotis_core::NotificationImage;
+
+    fn view(id: u32) -> NotificationView {
+        NotificationView {
+            id,
+            app_name: "chat".to_string(),
+            summary: "summary".to_string(),
+            body: String::new(),
+            actions: Vec::new(),
+            urgency: 1,
+            is_transient: false,
+            is_resident: false,
+            received_at_unix_ms: 0,
+            image: NotificationImage::default(),
+            history_ttl_minutes: 0,
+            pinned: false,
+            progress: -1,
+        }
+    }
+
+    #[test]
+    fn batches_unfold_into_added_events_in_order() {
+        let events = batch_events(&[(view(1), true), (view(2), false)]);
+        let added: Vec<(u32, bool)> = events
+            .iter()
+            .map(|event| match event {
+                FeedEvent::Added {
+                    notification,
+                    popup,
+                } => (notification.id, *popup),
+                other => panic!("unexpected event {other:?}"),
+            })
+            .collect();
+        assert_eq!(added, vec![(1, true), (2, false)]);
+        assert!(batch_events(&[]).is_empty());
+    }
+}
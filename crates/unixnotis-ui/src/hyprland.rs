@@ -0,0 +1,79 @@
+//! Hyprland IPC requests shared by the panel and the popups.
+
+use std::env;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// Most callers run on the GTK thread, so a stalled compositor must not hold it for long.
+const IPC_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Whether this session has a Hyprland instance to talk to.
+pub fn is_available() -> bool {
+    env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some_and(|value| !value.is_empty())
+}
+
+/// Connector name of the focused monitor, such as "DP-1".
+pub fn focused_monitor() -> std::io::Result<Option<String>> {
+    let response = send_command("j/monitors")?;
+    let value: Value = serde_json::from_str(&response)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    Ok(parse_focused_monitor(&value))
+}
+
+fn parse_focused_monitor(monitors: &Value) -> Option<String> {
+    monitors
+        .as_array()?
+        .iter()
+        .find(|monitor| monitor.get("focused").and_then(Value::as_bool) == Some(true))?
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Send one request on Hyprland's command socket and return the raw reply.
+pub fn send_command(command: &str) -> std::io::Result<String> {
+    // Hyprland exposes its IPC socket via XDG_RUNTIME_DIR + HYPRLAND_INSTANCE_SIGNATURE.
+    // If these env vars aren't present, we're not in a Hyprland session (or IPC isn't available).
+    let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE").unwrap_or_default();
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").unwrap_or_default();
+    if signature.is_empty() || runtime_dir.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Hyprland environment not available",
+        ));
+    }
+
+    // ".socket.sock" is Hyprland's request/response command socket (not the event stream).
+    let socket_path = format!("{runtime_dir}/hypr/{signature}/.socket.sock");
+    let mut stream = UnixStream::connect(&socket_path)?;
+    stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+    stream.set_write_timeout(Some(IPC_TIMEOUT))?;
+
+    // Hyprland expects newline-terminated commands on this socket.
+    let request = format!("{command}\n");
+    stream.write_all(request.as_bytes())?;
+    stream.flush()?; // Make sure the command is sent immediately.
+
+    // Hyprland replies with a plain-text response; read it fully until EOF.
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_focused_monitor;
+
+    #[test]
+    fn parse_focused_monitor_picks_focused_entry() {
+        let value = serde_json::json!([
+            {"name": "eDP-1", "focused": false},
+            {"name": "DP-2", "focused": true}
+        ]);
+        assert_eq!(parse_focused_monitor(&value), Some("DP-2".to_string()));
+        assert_eq!(parse_focused_monitor(&serde_json::json!([])), None);
+    }
+}
@@ -0,0 +1,46 @@
+//! Placeholder windows shown by `noticenterctl preview-layout`.
+//!
+//! The panel and the popups each build their own outlines; this keeps the windows of the
+//! latest preview and takes them down once its timer runs out.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::glib;
+use gtk::prelude::*;
+
+/// Windows of the preview on screen, if any.
+#[derive(Clone, Default)]
+pub struct LayoutPreview {
+    windows: Rc<RefCell<Vec<gtk::ApplicationWindow>>>,
+}
+
+impl LayoutPreview {
+    /// Replace the current preview with `windows` and remove them after `seconds`.
+    pub fn show(&self, windows: Vec<gtk::ApplicationWindow>, seconds: u32) {
+        self.clear();
+        let Some(first) = windows.first().cloned() else {
+            return;
+        };
+        for window in &windows {
+            window.set_visible(true);
+        }
+        self.windows.replace(windows);
+
+        let current = self.windows.clone();
+        glib::timeout_add_seconds_local_once(seconds, move || {
+            // A newer preview may have replaced this one; leave that to its own timer.
+            if current.borrow().first() == Some(&first) {
+                for window in current.take() {
+                    window.destroy();
+                }
+            }
+        });
+    }
+
+    pub fn clear(&self) {
+        for window in self.windows.take() {
+            window.destroy();
+        }
+    }
+}
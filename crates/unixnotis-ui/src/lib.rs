@@ -1,3 +1,15 @@
-//! GTK-oriented helpers shared by UnixNotis UI binaries.
+//! GTK-oriented helpers shared by UnixNotis UI binaries, and `feed` for panels and bars
+//! built outside UnixNotis.
 
 pub mod css;
+pub mod debug_hud;
+pub mod decode_pool;
+pub mod desktop_icons;
+pub mod feed;
+pub mod hyprland;
+pub mod layout_preview;
+pub mod links;
+pub mod preview;
+pub mod profiling;
+pub mod scaled_texture;
+pub mod surface;
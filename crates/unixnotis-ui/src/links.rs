@@ -0,0 +1,27 @@
+//! Clickable links in notification bodies.
+//!
+//! The UIs run bodies through `markup::sanitize_body` just before `set_markup`, which keeps
+//! only http, https, and mailto links. Clicks are checked against the same schemes, so a label
+//! built without the sanitizer still cannot open anything else.
+
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use tracing::{debug, warn};
+use unixnotis_core::markup::is_allowed_link;
+
+/// Open links clicked in `label` with the desktop's default handler.
+pub fn open_with_default_app(label: &gtk::Label) {
+    label.connect_activate_link(|label, uri| {
+        if !is_allowed_link(uri) {
+            warn!(uri, "refusing to open body link with an unsupported scheme");
+            return glib::Propagation::Stop;
+        }
+        debug!(uri, "body link activated");
+        let context = label.display().app_launch_context();
+        if let Err(err) = gio::AppInfo::launch_default_for_uri(uri, Some(&context)) {
+            warn!(?err, "failed to open body link");
+        }
+        glib::Propagation::Stop
+    });
+}
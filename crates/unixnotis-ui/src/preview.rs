@@ -0,0 +1,76 @@
+//! Image sources for the large previews the panel and the popups show.
+//!
+//! Both UIs decode previews on their own pool with their own resizer; what counts as a preview
+//! and how inline pixels reach the worker is shared here.
+
+use std::path::PathBuf;
+
+use unixnotis_core::ImageData;
+
+/// Smaller images are icons or avatars; the row or header icon already shows them.
+pub const MIN_PREVIEW_SOURCE: i32 = 128;
+
+/// Image handed to a decode worker for a preview.
+#[derive(Clone)]
+pub enum PreviewSource {
+    Path(PathBuf),
+    /// RGBA8 rows copied from an image-data hint.
+    Pixels {
+        bytes: Vec<u8>,
+        width: u32,
+        height: u32,
+        rowstride: usize,
+    },
+}
+
+impl PreviewSource {
+    /// Copy inline pixels for a worker, or `None` when the image is too small for a preview.
+    pub fn from_image_data(data: &ImageData) -> Option<Self> {
+        if data.data.is_empty() || data.width.max(data.height) < MIN_PREVIEW_SOURCE {
+            return None;
+        }
+        // The pixels are copied once so the worker owns them; the view keeps its own.
+        Some(Self::Pixels {
+            bytes: data.data.clone(),
+            width: u32::try_from(data.width).ok()?,
+            height: u32::try_from(data.height).ok()?,
+            rowstride: usize::try_from(data.rowstride).ok()?,
+        })
+    }
+}
+
+/// Drop rowstride padding so resizers see tightly packed RGBA rows.
+pub fn pack_rows(
+    bytes: Vec<u8>,
+    width: u32,
+    height: u32,
+    rowstride: usize,
+) -> Result<Vec<u8>, String> {
+    let row = width as usize * 4;
+    if rowstride < row || bytes.len() < rowstride * height as usize {
+        return Err("image data shorter than its rows".to_string());
+    }
+    if rowstride == row {
+        return Ok(bytes);
+    }
+    Ok(bytes
+        .chunks(rowstride)
+        .take(height as usize)
+        .flat_map(|line| &line[..row])
+        .copied()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pack_rows;
+
+    #[test]
+    fn padded_rows_are_packed_and_short_buffers_rejected() {
+        let padded = vec![1, 2, 3, 4, 0, 0, 5, 6, 7, 8, 0, 0];
+        assert_eq!(pack_rows(padded, 1, 2, 6), Ok(vec![1, 2, 3, 4, 5, 6, 7, 8]));
+        assert_eq!(pack_rows(vec![9; 8], 1, 2, 4), Ok(vec![9; 8]));
+        assert!(pack_rows(vec![0; 6], 1, 2, 6).is_err());
+        assert!(pack_rows(vec![0; 8], 2, 1, 4).is_err());
+    }
+}
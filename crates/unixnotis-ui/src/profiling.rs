@@ -0,0 +1,18 @@
+//! Profiling support for the GTK processes.
+//!
+//! A Chrome trace is only complete once its guard drops at the end of `main`, but GTK
+//! processes usually end by signal, which skips that.
+
+use gtk::glib;
+use gtk::prelude::*;
+
+/// Quit `app` on SIGTERM or SIGINT so `run` returns and the trace is flushed.
+pub fn quit_on_terminate(app: &gtk::Application) {
+    for signal in [libc::SIGTERM, libc::SIGINT] {
+        let app = app.clone();
+        glib::unix_signal_add_local(signal, move || {
+            app.quit();
+            glib::ControlFlow::Break
+        });
+    }
+}
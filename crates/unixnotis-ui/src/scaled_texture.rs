@@ -0,0 +1,71 @@
+//! Texture paintable sized in logical pixels.
+//!
+//! A texture decoded for a 2x output reports twice its logical size to GTK, so a
+//! `gtk::Picture` showing it would grow to double height. Wrapping it keeps the intended
+//! logical size while every decoded pixel is still drawn.
+
+use std::cell::{Cell, OnceCell};
+
+use gtk::gdk;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct ScaledTexture {
+        pub texture: OnceCell<gdk::Texture>,
+        pub scale: Cell<i32>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ScaledTexture {
+        const NAME: &'static str = "UnixNotisScaledTexture";
+        type Type = super::ScaledTexture;
+        type Interfaces = (gdk::Paintable,);
+    }
+
+    impl ObjectImpl for ScaledTexture {}
+
+    impl PaintableImpl for ScaledTexture {
+        fn flags(&self) -> gdk::PaintableFlags {
+            // The texture never changes after construction.
+            gdk::PaintableFlags::SIZE | gdk::PaintableFlags::CONTENTS
+        }
+
+        fn intrinsic_width(&self) -> i32 {
+            self.texture
+                .get()
+                .map_or(0, |texture| texture.width() / self.scale.get().max(1))
+        }
+
+        fn intrinsic_height(&self) -> i32 {
+            self.texture
+                .get()
+                .map_or(0, |texture| texture.height() / self.scale.get().max(1))
+        }
+
+        fn snapshot(&self, snapshot: &gdk::Snapshot, width: f64, height: f64) {
+            if let Some(texture) = self.texture.get() {
+                texture.snapshot(snapshot, width, height);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct ScaledTexture(ObjectSubclass<imp::ScaledTexture>) @implements gdk::Paintable;
+}
+
+impl ScaledTexture {
+    /// Wrap a texture decoded at `scale` device pixels per logical pixel.
+    pub fn new(texture: gdk::Texture, scale: i32) -> Self {
+        let paintable: Self = glib::Object::new::<Self>();
+        let imp = paintable.imp();
+        let _ = imp.texture.set(texture);
+        imp.scale.set(scale.max(1));
+        paintable
+    }
+}
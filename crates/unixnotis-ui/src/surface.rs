@@ -0,0 +1,137 @@
+//! Layer-shell capability detection with a regular-window fallback.
+//!
+//! The panel and popups normally become wlr-layer-shell overlay surfaces. When the compositor
+//! does not offer the protocol, or `general.layer_shell = "off"`, they open as ordinary
+//! xdg-shell windows instead: anchors, margins, and output selection are then left to the
+//! compositor. [`init`] decides once per process, after GTK is initialized.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use gtk::prelude::*;
+use gtk4_layer_shell::{Layer, LayerShell};
+use tracing::{info, warn};
+use unixnotis_core::{ExclusiveZone, LayerShellMode};
+
+static LAYER_SHELL: AtomicBool = AtomicBool::new(false);
+
+/// Why layer-shell cannot be used on the current display.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LayerShellUnavailable {
+    /// GTK opened a non-Wayland display, e.g. through `GDK_BACKEND=x11`.
+    NotWayland,
+    /// The Wayland compositor does not advertise `zwlr_layer_shell_v1`.
+    NotAdvertised,
+}
+
+impl fmt::Display for LayerShellUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotWayland => write!(
+                f,
+                "GTK is not connected to a Wayland display (check GDK_BACKEND); \
+                 wlr-layer-shell needs Wayland"
+            ),
+            Self::NotAdvertised => {
+                write!(f, "the compositor does not advertise zwlr_layer_shell_v1")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayerShellUnavailable {}
+
+/// Probe the display and choose between layer-shell and regular windows.
+///
+/// Returns an error only for [`LayerShellMode::Require`] when the protocol is missing.
+pub fn init(mode: LayerShellMode) -> Result<(), LayerShellUnavailable> {
+    let enabled = match mode {
+        LayerShellMode::Off => {
+            info!("layer-shell disabled by config; using regular windows");
+            false
+        }
+        LayerShellMode::Auto | LayerShellMode::Require => match probe() {
+            Ok(()) => true,
+            Err(err) if mode == LayerShellMode::Require => return Err(err),
+            Err(err) => {
+                warn!(
+                    reason = %err,
+                    "layer-shell unavailable; falling back to regular windows, \
+                     placement and margins are up to the compositor"
+                );
+                false
+            }
+        },
+    };
+    LAYER_SHELL.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Whether windows in this process are layer-shell surfaces.
+pub fn layer_shell_enabled() -> bool {
+    LAYER_SHELL.load(Ordering::Relaxed)
+}
+
+/// Turn `window` into an overlay layer surface; returns false in fallback mode.
+///
+/// Callers skip anchor, margin, and monitor setup when this returns false, since those
+/// calls only apply to layer surfaces.
+pub fn init_layer_surface(window: &impl IsA<gtk::Window>, namespace: &str) -> bool {
+    if !layer_shell_enabled() {
+        return false;
+    }
+    window.init_layer_shell();
+    window.set_namespace(Some(namespace));
+    window.set_layer(Layer::Overlay);
+    true
+}
+
+/// Ask the compositor to keep other windows out of the space `zone` describes.
+///
+/// Compositors only honour this for surfaces anchored along a whole edge; corner and
+/// centered surfaces reserve nothing whatever `zone` says.
+pub fn apply_exclusive_zone(window: &impl IsA<gtk::Window>, zone: ExclusiveZone) {
+    match zone {
+        ExclusiveZone::None => window.set_exclusive_zone(0),
+        ExclusiveZone::Auto => window.auto_exclusive_zone_enable(),
+        ExclusiveZone::Fixed(pixels) => window.set_exclusive_zone(pixels),
+    }
+}
+
+/// Call `on_change` whenever a monitor is connected or disconnected.
+pub fn connect_monitors_changed(on_change: impl Fn() + 'static) {
+    let Some(display) = gtk::gdk::Display::default() else {
+        return;
+    };
+    display
+        .monitors()
+        .connect_items_changed(move |_, _, removed, added| {
+            if removed > 0 || added > 0 {
+                on_change();
+            }
+        });
+}
+
+/// Keep `window` alive when the compositor closes its surface, e.g. because its output was
+/// unplugged. `on_close` runs first so the caller can re-anchor or re-show the window.
+pub fn keep_alive_on_close(window: &impl IsA<gtk::Window>, on_close: impl Fn() + 'static) {
+    // Without this the window is destroyed, and with its last window gone the app exits.
+    window.set_hide_on_close(true);
+    window.connect_close_request(move |_| {
+        on_close();
+        gtk::glib::Propagation::Proceed
+    });
+}
+
+fn probe() -> Result<(), LayerShellUnavailable> {
+    if gtk4_layer_shell::is_supported() {
+        return Ok(());
+    }
+    let wayland = gtk::gdk::Display::default()
+        .is_some_and(|display| display.type_().name() == "GdkWaylandDisplay");
+    if wayland {
+        Err(LayerShellUnavailable::NotAdvertised)
+    } else {
+        Err(LayerShellUnavailable::NotWayland)
+    }
+}